@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::Operation;
+use crate::symbols::SymbolTable;
+
+pub fn commented(op: &Operation, symbols: &SymbolTable, comment: Option<&str>) -> String {
+    // Listing line with a note on the address after the target's name
+    //      0010   cd 00 20    CALL adr    ; Inner - back to Main
+    //  Shares the semicolon with the name so everything after it is still one comment to an assembler
+
+    let line: String = op.annotated(symbols);
+    match comment {
+        Some(comment) if line.contains(';') => format!("{} - {}", line, comment),
+        Some(comment) => format!("{}    ; {}", line, comment),
+        None => line,
+    }
+}
+
+pub fn listing(ops: &[Operation], symbols: &SymbolTable, comments: &BTreeMap<u16, String>) -> String {
+    // The disassembler's output as text, a label line before each named address
+    //  Comments on addresses that no operation starts at are added to the end on their own lines
+
+    let mut lines: Vec<String> = vec![];
+    for op in ops {
+        if let Some(name) = symbols.name(op.address()) {
+            lines.push(format!("{}:", name));
+        }
+        lines.push(commented(op, symbols, comments.get(&op.address()).map(String::as_str)));
+    }
+
+    for (address, comment) in comments {
+        if !ops.iter().any(|op| op.address() == *address) {
+            lines.push(format!("{:04x}    ; {}", address, comment));
+        }
+    }
+
+    lines.join("\n")
+}
+
+pub fn parse(text: &str) -> BTreeMap<u16, String> {
+    // Reads the comments back out of a listing, label lines and anything else without an address are skipped
+    //  Lines can start with the debugger's pc marker
+    //  The first word after the semicolon of an instruction with a target is the target's name, not part of the comment
+
+    let mut comments: BTreeMap<u16, String> = BTreeMap::new();
+    for line in text.lines() {
+        let line: &str = line.trim_start_matches(['>', ' ']);
+        let Some(address) = line.get(..4).and_then(|address| u16::from_str_radix(address, 16).ok()) else {
+            continue;
+        };
+        if !line[4..].starts_with(' ') {
+            continue;
+        }
+        // Labels can look like hex, DrawAlien: won't but Beef: would
+        let Some((code, comment)) = line[4..].split_once(';') else {
+            continue;
+        };
+
+        let instruction: &str = code.trim();
+        let has_target: bool = instruction.ends_with("adr") || instruction.contains("RST");
+        let comment: &str = match comment.trim().split_once(" - ") {
+            Some((name, rest)) if has_target && !name.contains(char::is_whitespace) => rest,
+            None if has_target && !comment.trim().contains(char::is_whitespace) => "",
+            _ => comment,
+        };
+        if !comment.trim().is_empty() {
+            comments.insert(address, comment.trim().to_string());
+        }
+    }
+
+    comments
+}
+
+pub fn load(path: &Path) -> Result<BTreeMap<u16, String>, String> {
+    let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(parse(&text))
+}
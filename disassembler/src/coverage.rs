@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use super::get_instruction_set;
+
+pub const COVERAGE_BYTES: usize = 0x10000 / 8;
+// Coverage maps exported by the emulator have one bit per address,
+//  address n is bit n % 8 of byte n / 8
+
+fn is_executed(coverage: &[u8], address: usize) -> bool {
+    coverage.get(address / 8).is_some_and(|bits| bits & (1 << (address % 8)) != 0)
+}
+
+pub fn data_mask(data: &[u8], coverage: &[u8]) -> Vec<bool> {
+    // Marks everything that was never executed as data
+    //  The emulator only records op code addresses so the operand bytes of executed
+    //  instructions are kept as code too
+
+    let instructions: HashMap<u8, (String, u8)> = get_instruction_set();
+    let mut data_mask: Vec<bool> = vec![true; data.len()];
+
+    for (address, op_code) in data.iter().enumerate() {
+        if !is_executed(coverage, address) {
+            continue;
+        }
+
+        let op_bytes: usize = instructions.get(op_code).map_or(1, |(_, op_bytes)| *op_bytes as usize);
+        let end: usize = (address + op_bytes).min(data.len());
+        data_mask[address..end].fill(false);
+    }
+
+    data_mask
+}
+
+pub fn print_coverage(data: &[u8], coverage: &[u8]) {
+    // Prints how much of the rom a session exercised
+
+    let executed: usize = (0..data.len()).filter(|address| is_executed(coverage, *address)).count();
+    let code_bytes: usize = data_mask(data, coverage).iter().filter(|is_data| !**is_data).count();
+    let percent = |bytes: usize| if data.is_empty() { 0.0 } else { bytes as f64 * 100.0 / data.len() as f64 };
+
+    println!("Instructions executed: {}", executed);
+    println!("Bytes covered: {} of {} ({:.1}%)", code_bytes, data.len(), percent(code_bytes));
+}
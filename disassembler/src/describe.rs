@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use super::get_instruction_set;
+
+const CLOCK_CYCLES: [u8; 0x100] = [
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5,
+    5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 13, 5, 10, 10, 10, 4,
+    4, 10, 13, 5, 5, 5, 7, 4, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5,
+    7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 7, 7, 7, 7,
+    7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 7, 5, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4,
+    4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4,
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10,
+    10, 10, 17, 7, 11, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10, 10, 10, 17, 7, 11, 11, 10,
+    10, 18, 17, 11, 7, 11, 11, 5, 10, 5, 17, 17, 7, 11, 11, 10, 10, 4, 17, 11, 7, 11, 11, 5,
+    10, 4, 17, 17, 7, 11,
+];
+// Same counts the emulator uses, conditional calls and returns are handled separately in cycles
+
+const ALL_FLAGS: &str = "S Z AC P CY";
+
+pub struct Description {
+    pub op_code: u8,
+    pub instruction: String,
+    pub op_bytes: u8,
+    pub cycles: String,
+    pub flags: &'static str,
+    // Flags the instruction can change, - when it leaves them alone
+    pub summary: &'static str,
+}
+
+fn family(op_code: u8, mnemonic: &str) -> (&'static str, &'static str) {
+    // Flag effects and a short description shared by every op code with the same mnemonic
+
+    match mnemonic {
+        "NOP" if op_code != 0x00 => ("-", "Undocumented op code, runs as NOP"),
+        "NOP" => ("-", "No operation"),
+        "LXI" => ("-", "Load a 16 bit immediate into a register pair"),
+        "STAX" => ("-", "Store A at the address held in a register pair"),
+        "LDAX" => ("-", "Load A from the address held in a register pair"),
+        "INX" => ("-", "Increment a register pair"),
+        "DCX" => ("-", "Decrement a register pair"),
+        "INR" => ("S Z AC P", "Increment a register"),
+        "DCR" => ("S Z AC P", "Decrement a register"),
+        "MVI" => ("-", "Move an 8 bit immediate into a register"),
+        "DAD" => ("CY", "Add a register pair to HL"),
+        "RLC" => ("CY", "Rotate A left, bit 7 goes to carry and bit 0"),
+        "RRC" => ("CY", "Rotate A right, bit 0 goes to carry and bit 7"),
+        "RAL" => ("CY", "Rotate A left through carry"),
+        "RAR" => ("CY", "Rotate A right through carry"),
+        "SHLD" => ("-", "Store L and H at an address"),
+        "LHLD" => ("-", "Load L and H from an address"),
+        "STA" => ("-", "Store A at an address"),
+        "LDA" => ("-", "Load A from an address"),
+        "DAA" => (ALL_FLAGS, "Adjust A to binary coded decimal after an addition"),
+        "CMA" => ("-", "Complement A"),
+        "STC" => ("CY", "Set carry"),
+        "CMC" => ("CY", "Complement carry"),
+        "MOV" => ("-", "Copy a register to another register"),
+        "HLT" => ("-", "Halt until an interrupt"),
+        "ADD" | "ADI" => (ALL_FLAGS, "Add to A"),
+        "ADC" | "ACI" => (ALL_FLAGS, "Add to A with carry"),
+        "SUB" | "SUI" => (ALL_FLAGS, "Subtract from A"),
+        "SBB" | "SBI" => (ALL_FLAGS, "Subtract from A with borrow"),
+        "ANA" | "ANI" => (ALL_FLAGS, "AND with A, carry is cleared"),
+        "XRA" | "XRI" => (ALL_FLAGS, "Exclusive OR with A, carry and auxiliary carry are cleared"),
+        "ORA" | "ORI" => (ALL_FLAGS, "OR with A, carry and auxiliary carry are cleared"),
+        "CMP" | "CPI" => (ALL_FLAGS, "Compare with A by subtracting without storing the result"),
+        "RET" => ("-", "Return"),
+        "RNZ" | "RZ" | "RNC" | "RC" | "RPO" | "RPE" | "RP" | "RM" => ("-", "Return if the condition is met"),
+        "JMP" => ("-", "Jump to an address"),
+        "JNZ" | "JZ" | "JNC" | "JC" | "JPO" | "JPE" | "JP" | "JM" => ("-", "Jump to an address if the condition is met"),
+        "CALL" => ("-", "Push the return address and jump to an address"),
+        "CNZ" | "CZ" | "CNC" | "CC" | "CPO" | "CPE" | "CP" | "CM" => ("-", "Call an address if the condition is met"),
+        "POP" if op_code == 0xf1 => (ALL_FLAGS, "Pop A and the flags off the stack"),
+        "POP" => ("-", "Pop a register pair off the stack"),
+        "PUSH" => ("-", "Push a register pair onto the stack"),
+        "RST" => ("-", "Call one of the eight restart addresses"),
+        "OUT" => ("-", "Write A to an output port"),
+        "IN" => ("-", "Read an input port into A"),
+        "XTHL" => ("-", "Swap HL with the top of the stack"),
+        "PCHL" => ("-", "Jump to the address in HL"),
+        "XCHG" => ("-", "Swap HL and DE"),
+        "SPHL" => ("-", "Copy HL into the stack pointer"),
+        "DI" => ("-", "Disable interrupts"),
+        "EI" => ("-", "Enable interrupts after the next instruction"),
+        _ => ("?", "Unknown instruction"),
+    }
+}
+
+fn cycles(op_code: u8, mnemonic: &str) -> String {
+    // Conditional calls and returns take fewer cycles when the branch isn't taken
+
+    match mnemonic {
+        "RNZ" | "RZ" | "RNC" | "RC" | "RPO" | "RPE" | "RP" | "RM" => String::from("11/5"),
+        "CNZ" | "CZ" | "CNC" | "CC" | "CPO" | "CPE" | "CP" | "CM" => String::from("17/11"),
+        _ => CLOCK_CYCLES[op_code as usize].to_string(),
+    }
+}
+
+fn describe_op_code(op_code: u8, instructions: &HashMap<u8, (String, u8)>) -> Option<Description> {
+    let (instruction, op_bytes) = instructions.get(&op_code)?;
+    let mnemonic: &str = instruction.split_whitespace().next().unwrap_or("");
+    let (flags, summary) = family(op_code, mnemonic);
+
+    Some(Description {
+        op_code,
+        instruction: instruction.clone(),
+        op_bytes: *op_bytes,
+        cycles: cycles(op_code, mnemonic),
+        flags,
+        summary,
+    })
+}
+
+pub fn lookup(query: &str) -> Vec<Description> {
+    // Finds every op code matching a query
+    //  0x3e looks up a single op code, MVI finds every op code with that mnemonic and
+    //  "MVI A" or "MVI A,D8" narrows it down to the op codes starting with those operands
+    //  Op codes need the 0x prefix since some mnemonics like CC are also valid hex
+
+    let instructions: HashMap<u8, (String, u8)> = get_instruction_set();
+
+    if let Some(hex) = query.strip_prefix("0x").or_else(|| query.strip_prefix("0X")) {
+        return u8::from_str_radix(hex, 16).ok()
+            .and_then(|op_code| describe_op_code(op_code, &instructions))
+            .into_iter()
+            .collect();
+    }
+
+    let normalise = |text: &str| text.chars().filter(|c| !c.is_whitespace() && *c != ',').collect::<String>().to_uppercase();
+    let query_mnemonic: String = query.split_whitespace().next().unwrap_or("").to_uppercase();
+    let query_operands: String = normalise(query.trim_start().trim_start_matches(|c: char| !c.is_whitespace()));
+    // Spaces and commas are written inconsistently in the table so they are ignored
+
+    (0..=0xff)
+        .filter(|op_code| {
+            instructions.get(op_code).is_some_and(|(instruction, _)| {
+                let mnemonic: &str = instruction.split_whitespace().next().unwrap_or("");
+                let operands: String = normalise(instruction.trim_start_matches(|c: char| !c.is_whitespace()));
+                mnemonic == query_mnemonic && operands.starts_with(&query_operands)
+            })
+        })
+        .filter_map(|op_code| describe_op_code(op_code, &instructions))
+        .collect()
+}
+
+pub fn print_description(query: &str) -> Vec<Description> {
+    // Prints the encoding, size, timing and flag effects of the op codes matching a query
+
+    let descriptions: Vec<Description> = lookup(query);
+    if descriptions.is_empty() {
+        println!("No instruction matches {}", query);
+    }
+
+    for description in &descriptions {
+        println!("0x{:02x}  {}", description.op_code, description.instruction);
+        println!("  Encoding: {:08b}", description.op_code);
+        println!("  Bytes:    {}", description.op_bytes);
+        println!("  Cycles:   {}", description.cycles);
+        println!("  Flags:    {}", description.flags);
+        println!("  {}", description.summary);
+    }
+
+    descriptions
+}
@@ -0,0 +1,128 @@
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use super::{decode, Operation};
+use crate::symbols::SymbolTable;
+use crate::syntax::Syntax;
+
+pub const CONTEXT: usize = 3;
+// Unchanged instructions shown either side of a change, like diff -u
+
+pub enum Line {
+    Same(Operation),
+    Removed(Operation),
+    // Only in the old rom, or different there
+    Added(Operation),
+}
+impl Line {
+    pub fn operation(&self) -> &Operation {
+        match self {
+            Line::Same(op) | Line::Removed(op) | Line::Added(op) => op,
+        }
+    }
+
+    fn operation_mut(&mut self) -> &mut Operation {
+        match self {
+            Line::Same(op) | Line::Removed(op) | Line::Added(op) => op,
+        }
+    }
+
+    fn prefix(&self) -> char {
+        match self {
+            Line::Same(_) => ' ',
+            Line::Removed(_) => '-',
+            Line::Added(_) => '+',
+        }
+    }
+}
+
+fn in_step(old: &mut Peekable<IntoIter<Operation>>, new: &mut Peekable<IntoIter<Operation>>) -> bool {
+    // Both roms have the same instruction at the same address next
+    matches!((old.peek(), new.peek()), (Some(a), Some(b)) if a.address == b.address && a.bytes() == b.bytes())
+}
+
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<Line> {
+    // Lines the two disassemblies up by address rather than by matching instructions like a text diff would
+    //  Roms patched in place keep everything else where it was, so a change is any run of instructions
+    //  that differ and ends where both roms next decode the same instruction at the same address
+    //  A changed operand can shift the instruction boundaries for a few bytes, those are shown as part of the change
+
+    let mut old: Peekable<IntoIter<Operation>> = decode(old, &[]).into_iter().peekable();
+    let mut new: Peekable<IntoIter<Operation>> = decode(new, &[]).into_iter().peekable();
+    let mut lines: Vec<Line> = vec![];
+    let mut removed: Vec<Line> = vec![];
+    let mut added: Vec<Line> = vec![];
+
+    loop {
+        if in_step(&mut old, &mut new) || (old.peek().is_none() && new.peek().is_none()) {
+            lines.append(&mut removed);
+            lines.append(&mut added);
+            // Old lines of a change first, then the new ones
+            match (old.next(), new.next()) {
+                (Some(op), Some(_)) => lines.push(Line::Same(op)),
+                _ => return lines,
+            }
+            continue;
+        }
+
+        let old_address: Option<u16> = old.peek().map(Operation::address);
+        let new_address: Option<u16> = new.peek().map(Operation::address);
+        match (old_address, new_address) {
+            (Some(a), Some(b)) if a < b => removed.extend(old.next().map(Line::Removed)),
+            (Some(a), Some(b)) if b < a => added.extend(new.next().map(Line::Added)),
+            (Some(_), Some(_)) => {
+                removed.extend(old.next().map(Line::Removed));
+                added.extend(new.next().map(Line::Added));
+            },
+            (Some(_), None) => removed.extend(old.next().map(Line::Removed)),
+            (None, _) => added.extend(new.next().map(Line::Added)),
+        }
+    }
+}
+
+pub fn listing(old_name: &str, new_name: &str, mut lines: Vec<Line>, symbols: &SymbolTable, syntax: Syntax) -> String {
+    // Unified diff style listing, only the changes and CONTEXT instructions either side of each
+    //  Each hunk starts with the addresses it covers, e.g. "@@ 0x1a32..0x1a41 @@"
+
+    lines.iter_mut().for_each(|line| line.operation_mut().set_syntax(syntax));
+    let changed: Vec<usize> = lines.iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for index in &changed {
+        let (start, end): (usize, usize) = (index.saturating_sub(CONTEXT), (index + CONTEXT + 1).min(lines.len()));
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    // Changes close enough for their context to touch share a hunk
+
+    let mut output: Vec<String> = vec![format!("--- {}", old_name), format!("+++ {}", new_name)];
+    for (start, end) in &hunks {
+        let addresses = lines[*start..*end].iter().map(|line| line.operation().address);
+        let (first, last): (u16, u16) = (addresses.clone().min().unwrap_or(0), addresses.max().unwrap_or(0));
+        output.push(format!("@@ 0x{:04x}..0x{:04x} @@", first, last));
+        for line in &lines[*start..*end] {
+            if let Some(name) = symbols.name(line.operation().address) {
+                output.push(format!("{}{}:", line.prefix(), name));
+            }
+            output.push(format!("{}{}", line.prefix(), line.operation().annotated(symbols)));
+        }
+    }
+
+    let removed: usize = lines.iter().filter(|line| matches!(line, Line::Removed(_))).count();
+    let added: usize = lines.iter().filter(|line| matches!(line, Line::Added(_))).count();
+    output.push(match hunks.len() {
+        0 => String::from("No differences"),
+        count => format!("{} changes, {} instructions removed and {} added", count, removed, added),
+    });
+    output.join("\n")
+}
+
+pub fn print_diff(old_name: &str, old: &[u8], new_name: &str, new: &[u8], symbols: &SymbolTable, syntax: Syntax) {
+    println!("{}", listing(old_name, new_name, diff(old, new), symbols, syntax));
+}
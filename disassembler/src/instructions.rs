@@ -1,3 +1,4 @@
+// Every 8080 op code as 0x(op code) (instruction) (bytes used), one per line
 pub const INSTRUCTIONS: &str = "0x00 NOP 1
 0x01 LXI B,D16 3
 0x02 STAX B 1
@@ -254,3 +255,80 @@ pub const INSTRUCTIONS: &str = "0x00 NOP 1
 0xfd NOP 1
 0xfe CPI D8 2
 0xff RST 7 1";
+
+// Cycles each op code takes, conditional calls and returns are listed with the cycles they take when the condition is met
+pub const CLOCK_CYCLES: [u8; 0x100] = [
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5,
+    5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 13, 5, 10, 10, 10, 4,
+    4, 10, 13, 5, 5, 5, 7, 4, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5,
+    7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 7, 7, 7, 7,
+    7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 7, 5, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4,
+    4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4,
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10,
+    10, 17, 17, 7, 11, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10, 10, 17, 17, 7, 11, 11, 10,
+    10, 18, 17, 11, 7, 11, 11, 5, 10, 5, 17, 17, 7, 11, 11, 10, 10, 4, 17, 11, 7, 11, 11, 5,
+    10, 4, 17, 17, 7, 11,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    // One row of the op code table for tools outside the emulator
+    pub op_code: u8,
+    pub text: String,
+    // As the disassembler prints it, e.g. "MVI B, D8"
+    pub mnemonic: String,
+    pub operands: Vec<String>,
+    // D8 and D16 are immediate data, adr is a 16 bit address
+    pub bytes: u8,
+    pub cycles: u8,
+}
+
+pub fn table() -> Vec<Instruction> {
+    // Every op code in order, parsed from INSTRUCTIONS with the cycles from CLOCK_CYCLES
+
+    INSTRUCTIONS.lines()
+        .map(|line| {
+            let (op_code, rest): (&str, &str) = line.split_once(' ').expect("splitting op code from instruction");
+            let op_code: u8 = u8::from_str_radix(&op_code[2..=3], 16).expect("converting hex string slice to byte");
+            let (text, bytes): (&str, &str) = rest.trim().rsplit_once(' ').expect("splitting byte count from instruction");
+            let (mnemonic, operands): (&str, &str) = text.split_once(' ').unwrap_or((text, ""));
+
+            Instruction {
+                op_code,
+                text: text.trim().to_string(),
+                mnemonic: mnemonic.to_string(),
+                operands: operands.split(',').map(str::trim).filter(|operand| !operand.is_empty()).map(String::from).collect(),
+                bytes: bytes.parse().expect("converting digit into u8"),
+                cycles: CLOCK_CYCLES[op_code as usize],
+            }
+        })
+        .collect()
+}
+
+pub fn lengths() -> [u8; 0x100] {
+    // Bytes taken by each op code including the op code itself
+
+    let mut lengths: [u8; 0x100] = [1; 0x100];
+    for instruction in table() {
+        lengths[instruction.op_code as usize] = instruction.bytes;
+    }
+    lengths
+}
+
+pub fn to_json(table: &[Instruction]) -> String {
+    // Written by hand to keep the disassembler free of dependencies, one instruction per line so diffs stay readable
+
+    let rows: Vec<String> = table.iter()
+        .map(|instruction| {
+            let operands: Vec<String> = instruction.operands.iter().map(|operand| quote(operand)).collect();
+            format!("  {{ \"op_code\": {}, \"text\": {}, \"mnemonic\": {}, \"operands\": [{}], \"bytes\": {}, \"cycles\": {} }}",
+                instruction.op_code, quote(&instruction.text), quote(&instruction.mnemonic), operands.join(", "), instruction.bytes, instruction.cycles)
+        })
+        .collect();
+
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,59 +1,70 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+pub mod comments;
+pub mod coverage;
+pub mod describe;
+pub mod diff;
+pub mod instructions;
+pub mod search;
+pub mod stats;
+pub mod symbols;
+pub mod syntax;
+use symbols::SymbolTable;
+use syntax::Syntax;
+
+pub fn disassemble(data: &[u8], data_mask: &[bool], symbols: &SymbolTable, comments: &BTreeMap<u16, String>, syntax: Syntax) -> Vec<Operation> {
+    let mut ops: Vec<Operation> = decode(data, data_mask);
+    ops.iter_mut().for_each(|op| op.set_syntax(syntax));
+
+    println!("{}", comments::listing(&ops, symbols, comments));
 
-mod instructions;
-use instructions::INSTRUCTIONS;
+    ops
+}
+
+pub fn decode(data: &[u8], data_mask: &[bool]) -> Vec<Operation> {
+    // Decodes a rom into a list of operations
+    // Any byte marked true in data_mask is treated as a data byte instead of an instruction
+    //  The mask can be shorter than data, missing entries are treated as code
 
-pub fn disassemble(data: &[u8]) -> Vec<Operation> {
     let mut ops: Vec<Operation> = vec![];
     let instructions: HashMap<u8, (String, u8)> = get_instruction_set();
 
     let mut index: usize = 0;
     while index < data.len() {
-        let op = get_operation(data, index, &instructions);
+        let op = match data_mask.get(index) {
+            Some(true) => Operation::data_byte(data, index),
+            _ => get_operation(data, index, &instructions),
+        };
         index += op.op_bytes as usize;
 
         ops.push(op);
     };
 
-    let mut address: u16 = 0;
-    for op in &ops {
-        match op.op_bytes {
-            1 => println!("{:04x}   {:02x}          {}", address, op.op_code, op.instruction),
-            2 => println!("{:04x}   {:02x} {:02x}       {}", address, op.op_code, op.data.0, op.instruction),
-            3 => println!("{:04x}   {:02x} {:02x} {:02x}    {}", address, op.op_code, op.data.0, op.data.1, op.instruction),
-            _ => panic!("Invalid number of bytes used for instruction"),
-        }
-        address += op.op_bytes as u16;
-    }
-
     ops
 }
 
-fn get_instruction_set() -> HashMap<u8, (String, u8)> {
-    let mut instruction_set: HashMap<u8, (String, u8)> = HashMap::new();
-
-    for instruction_info in INSTRUCTIONS.lines() {
-        // Line should look like this
-        // 0x(hex op code) (operation name) (number of bytes used for operation)
-
-        let (op_code_str, op): (&str, &str) = instruction_info.split_once(' ').expect("splitting op code from instruction");
-        let op_code: u8 = u8::from_str_radix(&op_code_str[2..=3], 16).expect("converting hex string slice to byte");
-        // Only using second half because the opcodes are written as 0x[8 bit code]
-
-        let op_bytes: u8 = op.chars().last().expect("getting last char of op string which should be the number of bytes used in op")
-            .to_digit(10).expect("converting digit into u8") as u8;
-        // Getting number of bytes used by the operation
-
-        let instruction = op.trim_end_matches(char::is_numeric).trim();
-        // Trimming op_byte digit and whitespace off end
+pub fn decode_from(data: &[u8], origin: u16) -> Vec<Operation> {
+    // Decodes a block of memory that starts at origin instead of at the start of the rom
+    //  For disassembling around the pc of a running machine
 
-        instruction_set.insert(op_code, (String::from(instruction), op_bytes));
+    let mut ops: Vec<Operation> = decode(data, &[]);
+    for op in ops.iter_mut() {
+        op.address = op.address.wrapping_add(origin);
     }
 
-    instruction_set
+    ops
+}
+
+fn get_instruction_set() -> HashMap<u8, (String, u8)> {
+    instructions::table().into_iter()
+        .map(|instruction| (instruction.op_code, (instruction.text, instruction.bytes)))
+        .collect()
 }
 
 pub struct Operation {
+    address: u16,
+    // Address of the op code in the rom
     instruction: String,
     op_code: u8,
     // Hex code associated with instruction
@@ -62,14 +73,87 @@ pub struct Operation {
     data: (u8, u8),
     // Data used in instruction
     // TODO: Some way of handling instructions that use less than 3 bytes
+    syntax: Syntax,
+    // Only changes how the instruction is displayed, instruction() is always Intel's
 }
 impl Operation {
-    fn new(instruction: &str, op_code: u8, op_bytes: u8, data: (u8, u8)) -> Self {
+    fn new(address: usize, instruction: &str, op_code: u8, op_bytes: u8, data: (u8, u8)) -> Self {
         Self {
+            address: address as u16,
             instruction: String::from(instruction),
             op_code,
             op_bytes,
             data,
+            syntax: Syntax::Intel,
+        }
+    }
+
+    fn data_byte(data: &[u8], index: usize) -> Self {
+        // A single byte that is not interpreted as an instruction
+        Self::new(index, "DB", data[index], 1, (0, 0))
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    pub fn op_code(&self) -> u8 {
+        self.op_code
+    }
+
+    pub fn op_bytes(&self) -> u8 {
+        self.op_bytes
+    }
+
+    pub fn instruction(&self) -> &str {
+        &self.instruction
+    }
+
+    pub fn set_syntax(&mut self, syntax: Syntax) {
+        self.syntax = syntax;
+    }
+
+    pub fn text(&self) -> String {
+        // The instruction as it is displayed
+        self.syntax.format(&self.instruction)
+    }
+
+    pub fn target(&self) -> Option<u16> {
+        // Address the instruction jumps to, calls or reads and writes, None for everything else
+        //  Immediates loaded with LXI could be addresses or plain numbers so they are left out
+
+        match self.instruction.split_whitespace().next() {
+            Some("RST") => Some((self.op_code & 0b0011_1000) as u16),
+            _ if self.op_bytes == 3 && self.instruction.ends_with("adr") => Some((self.data.0 as u16) << 8 | self.data.1 as u16),
+            _ => None,
+        }
+    }
+
+    pub fn annotated(&self, symbols: &SymbolTable) -> String {
+        // Listing line with the name of the target address after it when it has one
+
+        match self.target().and_then(|target| symbols.name(target)) {
+            Some(name) => format!("{}    ; {}", self, name),
+            None => self.to_string(),
+        }
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        // Returns the bytes of the operation in the order they appear in the rom
+        match self.op_bytes {
+            1 => vec![self.op_code],
+            2 => vec![self.op_code, self.data.0],
+            _ => vec![self.op_code, self.data.1, self.data.0],
+        }
+    }
+}
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op_bytes {
+            1 => write!(f, "{:04x}   {:02x}          {}", self.address, self.op_code, self.text()),
+            2 => write!(f, "{:04x}   {:02x} {:02x}       {}", self.address, self.op_code, self.data.0, self.text()),
+            3 => write!(f, "{:04x}   {:02x} {:02x} {:02x}    {}", self.address, self.op_code, self.data.0, self.data.1, self.text()),
+            _ => panic!("Invalid number of bytes used for instruction"),
         }
     }
 }
@@ -77,11 +161,13 @@ impl Operation {
 fn get_operation(data: &[u8], index: usize, instructions: &HashMap<u8, (String, u8)>) -> Operation {
     let op = match instructions.get(&data[index]) {
         // Searching dictionary by op code
+        Some((_, op_bytes)) if index + *op_bytes as usize > data.len() => Operation::data_byte(data, index),
+        // An instruction cut off by the end of the rom can only be data
         Some((instruction, op_bytes)) => match op_bytes {
             // Taking the correct number of bytes for the given instruction
-            1 => Operation::new(instruction, data[index], *op_bytes, (0, 0)),
-            2 => Operation::new(instruction, data[index], *op_bytes, (data[index+1], 0)),
-            3 => Operation::new(instruction, data[index], *op_bytes, (data[index+2], data[index+1])),
+            1 => Operation::new(index, instruction, data[index], *op_bytes, (0, 0)),
+            2 => Operation::new(index, instruction, data[index], *op_bytes, (data[index+1], 0)),
+            3 => Operation::new(index, instruction, data[index], *op_bytes, (data[index+2], data[index+1])),
             _ => panic!("There should never be an instruction with more than 3 bytes"),
         }
         None => {
@@ -1,17 +1,128 @@
+use std::collections::BTreeMap;
 use std::{env, fs};
+use std::path::Path;
+
+use disassembler::symbols::SymbolTable;
+use disassembler::syntax::Syntax;
+
+mod tui;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
         println!("Please provide a file to disassemble");
+        println!("Usage: disassembler [--tui | --stats] [--coverage <map>] [--symbols <file>] [--comments <listing>] [--syntax <intel|z80>] <file>");
+        println!("       disassembler --find <pattern> [--symbols <file>] <file>");
+        println!("       disassembler --diff <old file> [--symbols <file>] [--syntax <intel|z80>] <new file>");
+        println!("       disassembler --describe <mnemonic | 0xopcode>");
+        println!("       disassembler --instruction-table");
+        return;
+    }
+
+    if args[1] == "--instruction-table" {
+        print!("{}", disassembler::instructions::to_json(&disassembler::instructions::table()));
+        // The same table is checked in as disassembler/instructions.json
+        return;
+    }
+
+    if args[1] == "--describe" {
+        let query: String = args[2..].join(" ");
+        // Lets "MOV A,B" be typed without quotes
+        disassembler::describe::print_description(&query);
+        return;
     }
 
-    let file_path: &str = &args[1];
+    let file_path: &str = &args[args.len() - 1];
+    let flags: &[String] = &args[1..args.len() - 1];
+    // Flags come before the file path
+
+    let mode: &str = flags.iter()
+        .find(|flag| matches!(flag.as_str(), "--tui" | "--stats"))
+        .map_or("", |flag| flag.as_str());
+    let coverage_path: Option<&String> = flags.windows(2)
+        .find(|pair| pair[0] == "--coverage")
+        .map(|pair| &pair[1]);
+    let symbols_path: Option<&String> = flags.windows(2)
+        .find(|pair| pair[0] == "--symbols")
+        .map(|pair| &pair[1]);
+    let comments_path: Option<&String> = flags.windows(2)
+        .find(|pair| pair[0] == "--comments")
+        .map(|pair| &pair[1]);
+    // A listing saved by the disassembler or the emulator's debugger, its comments are carried over
+    let syntax: Syntax = match flags.windows(2).find(|pair| pair[0] == "--syntax") {
+        Some(pair) => Syntax::parse(&pair[1]).unwrap_or_else(|e| panic!("{}", e)),
+        None => Syntax::Intel,
+    };
+    // Z80 style mnemonics for anyone more used to reading those
+    let find: Option<&String> = flags.windows(2)
+        .find(|pair| pair[0] == "--find")
+        .map(|pair| &pair[1]);
+    // Hex bytes with ?? for any byte, e.g. "CD ?? 01" for calls into 0x01xx, quoted so it's one argument
+    let diff_path: Option<&String> = flags.windows(2)
+        .find(|pair| pair[0] == "--diff")
+        .map(|pair| &pair[1]);
+    // Another revision of the rom to compare against, it is shown as the old one
+
     let data: Vec<u8> = match fs::read(file_path) {
         Ok(result) => result,
         Err(e) => panic!("{}", e),
     };
 
-    disassembler::disassemble(&data);
+    let coverage: Option<Vec<u8>> = coverage_path.map(|path| match fs::read(path) {
+        Ok(result) => result,
+        Err(e) => panic!("{}", e),
+    });
+    let data_mask: Vec<bool> = match &coverage {
+        Some(coverage) => disassembler::coverage::data_mask(&data, coverage),
+        None => vec![],
+    };
+    // Anything the emulator never executed is shown as data
+
+    let symbols: SymbolTable = match symbols_path {
+        Some(path) => match SymbolTable::load(Path::new(path)) {
+            Ok(symbols) => symbols,
+            Err(e) => panic!("{}", e),
+        },
+        None => SymbolTable::new(),
+    };
+
+    let comments: BTreeMap<u16, String> = match comments_path {
+        Some(path) => match disassembler::comments::load(Path::new(path)) {
+            Ok(comments) => comments,
+            Err(e) => panic!("{}", e),
+        },
+        None => BTreeMap::new(),
+    };
+
+    if let Some(path) = diff_path {
+        match fs::read(path) {
+            Ok(old) => disassembler::diff::print_diff(path, &old, file_path, &data, &symbols, syntax),
+            Err(e) => println!("{}: {}", path, e),
+        }
+        return;
+    }
+
+    if let Some(text) = find {
+        match disassembler::search::Pattern::parse(text) {
+            Ok(pattern) => disassembler::search::print_matches(&data, &pattern, &symbols),
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+
+    match mode {
+        "--tui" => {
+            if let Err(e) = tui::run(data, data_mask, symbols, syntax) {
+                println!("{}", e);
+            }
+        },
+        "--stats" => {
+            disassembler::stats::print_stats(&data);
+            if let Some(coverage) = &coverage {
+                disassembler::coverage::print_coverage(&data, coverage);
+            }
+        },
+        _ => { disassembler::disassemble(&data, &data_mask, &symbols, &comments, syntax); },
+    }
 }
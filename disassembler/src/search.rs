@@ -0,0 +1,90 @@
+use std::fmt;
+
+use super::{decode_from, Operation};
+use crate::symbols::SymbolTable;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    // Bytes to look for with parts of them allowed to be anything, e.g. "CD ?? 01" finds calls into 0x01xx
+    //  A ? stands for one hex digit so "C?" matches 0xc0 to 0xcf
+    bytes: Vec<(u8, u8)>,
+    // Value and mask, a byte matches when byte & mask == value
+}
+impl Pattern {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // Bytes are two hex digits each, spaces between them are optional
+        let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.is_empty() {
+            return Err(String::from("Empty search pattern"));
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err(format!("\"{}\" doesn't split into whole bytes", text));
+        }
+
+        let nibble = |c: char| -> Result<(u8, u8), String> {
+            match c {
+                '?' => Ok((0, 0)),
+                _ => c.to_digit(16).map(|digit| (digit as u8, 0xf)).ok_or_else(|| format!("\"{}\" isn't a hex digit or ?", c)),
+            }
+        };
+        let bytes: Vec<(u8, u8)> = digits.chunks(2)
+            .map(|pair| {
+                let (high, high_mask) = nibble(pair[0])?;
+                let (low, low_mask) = nibble(pair[1])?;
+                Ok((high << 4 | low, high_mask << 4 | low_mask))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { bytes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.bytes.len() && self.bytes.iter().zip(data).all(|((value, mask), byte)| byte & mask == *value)
+    }
+
+    pub fn find(&self, data: &[u8], origin: u16) -> Vec<u16> {
+        // Addresses of every match, data starts at origin
+        //  Matches can overlap, "00 00" finds every byte of a run of zeroes but the last
+        (0..data.len())
+            .filter(|index| self.matches(&data[*index..]))
+            .map(|index| origin.wrapping_add(index as u16))
+            .collect()
+    }
+}
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digit = |value: u8, mask: u8| if mask == 0 { '?' } else { char::from_digit(value as u32, 16).unwrap_or('?').to_ascii_uppercase() };
+        let bytes: Vec<String> = self.bytes.iter()
+            .map(|(value, mask)| format!("{}{}", digit(value >> 4, mask >> 4), digit(value & 0xf, mask & 0xf)))
+            .collect();
+        write!(f, "{}", bytes.join(" "))
+    }
+}
+
+pub fn instruction_at(data: &[u8], index: usize, origin: u16) -> Operation {
+    // Decodes the instruction a match starts with, whether or not the disassembly lines up with it there
+    let end: usize = (index + 3).min(data.len());
+    decode_from(&data[index..end], origin.wrapping_add(index as u16)).remove(0)
+}
+
+pub fn print_matches(data: &[u8], pattern: &Pattern, symbols: &SymbolTable) {
+    // Every match in a rom with the instruction it starts with, for finding a routine again in another revision
+
+    let found: Vec<u16> = pattern.find(data, 0);
+    for address in &found {
+        let op: Operation = instruction_at(data, *address as usize, 0);
+        if let Some(name) = symbols.name(*address) {
+            println!("{}:", name);
+        }
+        println!("{}", op.annotated(symbols));
+    }
+    println!("Matches of {}: {}", pattern, found.len());
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use super::{decode, get_instruction_set, Operation};
+
+mod tests;
+
+const BLOCK_SIZE: usize = 256;
+// Entropy is measured over blocks of this many bytes
+const ENTROPY_BAR_WIDTH: f64 = 32.0;
+// Number of characters used to draw an entropy of 8 bits per byte
+
+pub struct RomStats {
+    pub size: usize,
+    pub opcode_counts: [usize; 0x100],
+    // Number of times each op code was decoded as the start of an instruction
+    pub code_bytes: usize,
+    pub data_bytes: usize,
+    // Bytes decoded as NOPs (including 0x00 padding and undocumented op codes) are counted as data
+    //  since real code rarely contains more than a handful of them
+    pub block_entropy: Vec<f64>,
+    // Shannon entropy in bits per byte of each 256 byte block
+}
+
+pub fn analyse(data: &[u8]) -> RomStats {
+    // Gathers opcode frequency, code/data estimates, and block entropy for a rom
+
+    let ops: Vec<Operation> = decode(data, &[]);
+
+    let mut opcode_counts: [usize; 0x100] = [0; 0x100];
+    let mut code_bytes: usize = 0;
+    for op in &ops {
+        opcode_counts[op.op_code as usize] += 1;
+
+        if !matches!(op.instruction.as_str(), "NOP" | "DB") {
+            code_bytes += op.op_bytes as usize;
+        }
+    }
+
+    let block_entropy: Vec<f64> = data.chunks(BLOCK_SIZE).map(entropy).collect();
+
+    RomStats {
+        size: data.len(),
+        opcode_counts,
+        code_bytes,
+        data_bytes: data.len() - code_bytes,
+        block_entropy,
+    }
+}
+
+pub fn entropy(block: &[u8]) -> f64 {
+    // Shannon entropy of a block of bytes in bits per byte
+    //  0 means every byte is the same, 8 means every byte value is equally likely
+
+    let mut counts: [usize; 0x100] = [0; 0x100];
+    for byte in block {
+        counts[*byte as usize] += 1;
+    }
+
+    let total: f64 = block.len() as f64;
+    counts.iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let probability: f64 = *count as f64 / total;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+pub fn print_stats(data: &[u8]) -> RomStats {
+    // Prints a report of the rom's statistics
+
+    let stats: RomStats = analyse(data);
+    let instructions: HashMap<u8, (String, u8)> = get_instruction_set();
+    let percent = |bytes: usize| if stats.size == 0 { 0.0 } else { bytes as f64 * 100.0 / stats.size as f64 };
+
+    println!("Size: {} bytes", stats.size);
+    println!("Estimated code: {} bytes ({:.1}%)", stats.code_bytes, percent(stats.code_bytes));
+    println!("Estimated data: {} bytes ({:.1}%)", stats.data_bytes, percent(stats.data_bytes));
+
+    println!();
+    println!("Opcode frequency:");
+    let mut frequency: Vec<(usize, usize)> = stats.opcode_counts.iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    frequency.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    // Most common first, ties in op code order
+
+    for (op_code, count) in frequency {
+        let instruction: &str = instructions.get(&(op_code as u8)).map_or("", |(instruction, _)| instruction);
+        println!("  0x{:02x}  {:<12} {}", op_code, instruction, count);
+    }
+
+    println!();
+    println!("Block entropy (bits per byte):");
+    for (block, entropy) in stats.block_entropy.iter().enumerate() {
+        let start: usize = block * BLOCK_SIZE;
+        let end: usize = (start + BLOCK_SIZE).min(stats.size) - 1;
+        let bar: String = "#".repeat((entropy / 8.0 * ENTROPY_BAR_WIDTH).round() as usize);
+        println!("  {:04x}-{:04x}  {:.2}  {}", start, end, entropy, bar);
+    }
+
+    stats
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_entropy() {
+    assert_eq!(entropy(&[0; 256]), 0.0);
+    assert_eq!(entropy(&[]), 0.0);
+    let uniform: Vec<u8> = (0..=255).collect();
+    assert!((entropy(&uniform) - 8.0).abs() < 1e-9);
+    assert!((entropy(&[0x00, 0xff, 0x00, 0xff]) - 1.0).abs() < 1e-9);
+    // Two values equally likely is one bit a byte
+}
+
+#[test]
+fn test_analyse() {
+    let data: [u8; 8] = [
+        0x31, 0x00, 0x24, // LXI SP,0x2400
+        0x00, 0x00,       // NOP NOP, counted as data
+        0xc9,             // RET
+        0x3e, 0x01,       // MVI A,0x01
+    ];
+    let stats: RomStats = analyse(&data);
+
+    assert_eq!(stats.size, 8);
+    assert_eq!((stats.code_bytes, stats.data_bytes), (6, 2));
+    assert_eq!((stats.opcode_counts[0x31], stats.opcode_counts[0x00], stats.opcode_counts[0xc9], stats.opcode_counts[0x3e]), (1, 2, 1, 1));
+    assert_eq!(stats.opcode_counts.iter().sum::<usize>(), 5);
+    // Operands aren't counted as op codes
+    assert_eq!(stats.block_entropy.len(), 1);
+
+    let mut blocks: Vec<u8> = vec![0; BLOCK_SIZE];
+    blocks.extend(0..=255_u8);
+    blocks.extend([0x00, 0xff]);
+    let entropies: Vec<f64> = analyse(&blocks).block_entropy;
+    assert_eq!(entropies.len(), 3);
+    assert_eq!(entropies[0], 0.0);
+    assert!((entropies[1] - 8.0).abs() < 1e-9);
+    assert!((entropies[2] - 1.0).abs() < 1e-9);
+    // A short last block is measured on its own
+}
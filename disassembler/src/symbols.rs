@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    // Names for addresses in a rom, shared by the disassembler and the emulator's debugger
+    //  Symbol files have one symbol per line written as the address then the name
+    //      0x18d4 DrawAlien    ; anything after a semicolon is a comment
+    by_address: BTreeMap<u16, String>,
+    by_name: HashMap<String, u16>,
+}
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self {
+            by_address: BTreeMap::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut symbols: SymbolTable = Self::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line: &str = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (address, name): (&str, &str) = line.split_once(char::is_whitespace)
+                .ok_or_else(|| format!("Line {}: expected an address and a name", number + 1))?;
+            let address: u16 = parse_address(address)
+                .ok_or_else(|| format!("Line {}: {} is not an address", number + 1, address))?;
+            symbols.insert(address, name.trim())
+                .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+        }
+
+        Ok(symbols)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn insert(&mut self, address: u16, name: &str) -> Result<(), String> {
+        // Names have to be unique so they can be looked up, an address can only have one name
+
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '+') || parse_address(name).is_some() {
+            return Err(format!("{} can't be used as a symbol name", name));
+        }
+        if let Some(existing) = self.by_name.get(name).filter(|existing| **existing != address) {
+            return Err(format!("{} is already the name of 0x{:04x}", name, existing));
+        }
+
+        if let Some(old_name) = self.by_address.insert(address, name.to_string()) {
+            self.by_name.remove(&old_name);
+        }
+        self.by_name.insert(name.to_string(), address);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_address.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_address.is_empty()
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    pub fn locate(&self, address: u16) -> Option<(&str, u16)> {
+        // Closest symbol at or before an address and how far past it the address is
+        self.by_address.range(..=address)
+            .next_back()
+            .map(|(start, name)| (name.as_str(), address - start))
+    }
+
+    pub fn resolve(&self, text: &str) -> Option<u16> {
+        // Turns 0x18d4, DrawAlien or DrawAlien+3 into an address
+
+        let text: &str = text.trim();
+        if let Some(address) = parse_address(text) {
+            return Some(address);
+        }
+
+        let (name, offset): (&str, u16) = match text.split_once('+') {
+            Some((name, offset)) => (name.trim(), parse_offset(offset.trim())?),
+            None => (text, 0),
+        };
+        self.address(name)?.checked_add(offset)
+    }
+
+    pub fn format(&self, address: u16) -> String {
+        // Address written as the closest symbol when there is one, e.g. DrawAlien+3
+        match self.locate(address) {
+            Some((name, 0)) => name.to_string(),
+            Some((name, offset)) => format!("{}+{}", name, offset),
+            None => format!("0x{:04x}", address),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        // Every symbol in address order
+        self.by_address.iter().map(|(address, name)| (*address, name.as_str()))
+    }
+}
+
+fn parse_address(text: &str) -> Option<u16> {
+    // Addresses are hex, either with 0x or made only of digits so names like Beef aren't taken as addresses
+
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None if text.chars().all(|c| c.is_ascii_digit()) => u16::from_str_radix(text, 16).ok(),
+        None => None,
+    }
+}
+
+fn parse_offset(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
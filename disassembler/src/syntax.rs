@@ -0,0 +1,126 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    // How instructions are written out, the table and the assembler only know Intel's
+    #[default]
+    Intel,
+    // MOV A,M  LDAX D  JNZ adr
+    Z80,
+    // LD A,(HL)  LD A,(DE)  JP NZ,adr, the same op codes as a Z80 assembler writes them
+}
+impl Syntax {
+    pub const ALL: [Syntax; 2] = [Syntax::Intel, Syntax::Z80];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Syntax::Intel => "intel",
+            Syntax::Z80 => "z80",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::ALL.into_iter()
+            .find(|syntax| syntax.name() == text.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("{} is not a syntax, try one of {}", text, Self::ALL.map(|syntax| syntax.name()).join(", ")))
+    }
+
+    pub fn format(&self, text: &str) -> String {
+        // Rewrites an instruction from the disassembler's table, e.g. "MVI B, D8"
+        //  D8, D16 and adr are kept so they can still be filled in afterwards
+        match self {
+            Syntax::Intel => text.to_string(),
+            Syntax::Z80 => z80(text),
+        }
+    }
+}
+
+fn register(operand: &str) -> String {
+    // M is the byte HL points at, immediates like D8 are left alone
+    match operand {
+        "M" => String::from("(HL)"),
+        _ => operand.to_string(),
+    }
+}
+
+fn pair(operand: &str) -> &str {
+    match operand {
+        "B" => "BC",
+        "D" => "DE",
+        "H" => "HL",
+        "PSW" => "AF",
+        _ => operand,
+    }
+}
+
+const CONDITIONS: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+// Jumps, calls and returns end in one of these
+
+fn condition(mnemonic: &str, prefix: char) -> Option<&'static str> {
+    let rest: &str = mnemonic.strip_prefix(prefix)?;
+    CONDITIONS.into_iter().find(|condition| *condition == rest)
+}
+
+fn z80(text: &str) -> String {
+    let (mnemonic, operands): (&str, &str) = text.split_once(' ').unwrap_or((text, ""));
+    let operands: Vec<&str> = operands.split(',').map(str::trim).filter(|operand| !operand.is_empty()).collect();
+    let first: &str = operands.first().copied().unwrap_or("");
+    let second: &str = operands.get(1).copied().unwrap_or("");
+
+    match mnemonic {
+        "MOV" => format!("LD {},{}", register(first), register(second)),
+        "MVI" => format!("LD {},{}", register(first), second),
+        "LXI" => format!("LD {},{}", pair(first), second),
+        "LDA" => format!("LD A,({})", first),
+        "STA" => format!("LD ({}),A", first),
+        "LHLD" => format!("LD HL,({})", first),
+        "SHLD" => format!("LD ({}),HL", first),
+        "LDAX" => format!("LD A,({})", pair(first)),
+        "STAX" => format!("LD ({}),A", pair(first)),
+        "XCHG" => String::from("EX DE,HL"),
+        "XTHL" => String::from("EX (SP),HL"),
+        "SPHL" => String::from("LD SP,HL"),
+        "PCHL" => String::from("JP (HL)"),
+        "INR" => format!("INC {}", register(first)),
+        "DCR" => format!("DEC {}", register(first)),
+        "INX" => format!("INC {}", pair(first)),
+        "DCX" => format!("DEC {}", pair(first)),
+        "DAD" => format!("ADD HL,{}", pair(first)),
+        "ADD" | "ADI" => format!("ADD A,{}", register(first)),
+        "ADC" | "ACI" => format!("ADC A,{}", register(first)),
+        "SUB" | "SUI" => format!("SUB {}", register(first)),
+        "SBB" | "SBI" => format!("SBC A,{}", register(first)),
+        "ANA" | "ANI" => format!("AND {}", register(first)),
+        "XRA" | "XRI" => format!("XOR {}", register(first)),
+        "ORA" | "ORI" => format!("OR {}", register(first)),
+        "CMP" | "CPI" => format!("CP {}", register(first)),
+        "RLC" => String::from("RLCA"),
+        "RRC" => String::from("RRCA"),
+        "RAL" => String::from("RLA"),
+        "RAR" => String::from("RRA"),
+        "CMA" => String::from("CPL"),
+        "STC" => String::from("SCF"),
+        "CMC" => String::from("CCF"),
+        "HLT" => String::from("HALT"),
+        "PUSH" => format!("PUSH {}", pair(first)),
+        "POP" => format!("POP {}", pair(first)),
+        "JMP" => format!("JP {}", first),
+        "IN" => format!("IN A,({})", first),
+        "OUT" => format!("OUT ({}),A", first),
+        "RST" => match first.parse::<u8>() {
+            Ok(rst) => format!("RST 0x{:02x}", rst * 8),
+            Err(_) => text.to_string(),
+        },
+        _ => {
+            if let Some(condition) = condition(mnemonic, 'J') {
+                return format!("JP {},{}", condition, first);
+            }
+            if let Some(condition) = condition(mnemonic, 'C').filter(|_| !first.is_empty()) {
+                return format!("CALL {},{}", condition, first);
+            }
+            if let Some(condition) = condition(mnemonic, 'R').filter(|_| first.is_empty()) {
+                return format!("RET {}", condition);
+            }
+            text.to_string()
+        },
+        // NOP, EI, DI, DAA, CALL, RET and DB are written the same in both
+    }
+}
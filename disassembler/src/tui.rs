@@ -0,0 +1,273 @@
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use disassembler::Operation;
+use disassembler::symbols::SymbolTable;
+use disassembler::syntax::Syntax;
+
+mod tests;
+
+const HELP: &str = "j/k: Scroll  PgUp/PgDn: Page  g: Goto  /: Search  n: Next  v: Mark  d: Toggle data  q: Quit";
+
+enum Mode {
+    Browse,
+    Goto(String),
+    Search(String),
+}
+
+struct Browser {
+    data: Vec<u8>,
+    data_mask: Vec<bool>,
+    // Bytes marked true are shown as data instead of being decoded as instructions
+    ops: Vec<Operation>,
+    symbols: SymbolTable,
+    syntax: Syntax,
+    cursor: usize,
+    // Index into ops of the selected line
+    scroll: usize,
+    // Index into ops of the first visible line
+    mark: Option<u16>,
+    // Address where a region selection starts
+    mode: Mode,
+    last_search: Option<String>,
+    status: String,
+}
+impl Browser {
+    fn new(data: Vec<u8>, mut data_mask: Vec<bool>, symbols: SymbolTable, syntax: Syntax) -> Self {
+        data_mask.resize(data.len(), false);
+        let ops: Vec<Operation> = decode(&data, &data_mask, syntax);
+
+        Self {
+            data,
+            data_mask,
+            ops,
+            symbols,
+            syntax,
+            cursor: 0,
+            scroll: 0,
+            mark: None,
+            mode: Mode::Browse,
+            last_search: None,
+            status: String::from(HELP),
+        }
+    }
+
+    fn move_cursor(&mut self, lines: isize) {
+        let last: usize = self.ops.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(lines).min(last);
+    }
+
+    fn index_of_address(&self, address: u16) -> usize {
+        // Finds the operation containing the given address
+        match self.ops.binary_search_by_key(&address, |op| op.address()) {
+            Ok(index) => index,
+            Err(index) => index.saturating_sub(1),
+            // Address is in the middle of an instruction
+        }
+    }
+
+    fn goto(&mut self, input: &str) {
+        let address: Option<u16> = self.symbols.resolve(input)
+            .or_else(|| u16::from_str_radix(input.trim().trim_start_matches("0x"), 16).ok());
+        // Symbol names and offsets from them work as well as addresses
+        match address {
+            Some(address) if (address as usize) < self.data.len() => {
+                self.cursor = self.index_of_address(address);
+                self.status = format!("Jumped to {:04x}", address);
+            },
+            _ => self.status = format!("Invalid address: {}", input),
+        }
+    }
+
+    fn search(&mut self, query: &str) {
+        // Searches forward from the line after the cursor, wrapping around at the end
+        //  A query made entirely of hex byte pairs (e.g. "cd 05 00") also searches the raw bytes,
+        //  the mnemonics are always searched so "db" or "cc" still find those instructions
+
+        let pattern: Option<Vec<u8>> = query.split_whitespace()
+            .map(|byte| match byte.len() {
+                2 => u8::from_str_radix(byte, 16).ok(),
+                _ => None,
+            })
+            .collect();
+        let query_upper: String = query.to_uppercase();
+
+        let start: usize = self.cursor + 1;
+        for offset in 0..self.ops.len() {
+            let index: usize = (start + offset) % self.ops.len();
+            let op: &Operation = &self.ops[index];
+
+            let found: bool = op.text().to_uppercase().contains(&query_upper)
+                || pattern.as_ref().is_some_and(|bytes| !bytes.is_empty() && self.data[op.address() as usize..].starts_with(bytes));
+
+            if found {
+                self.cursor = index;
+                self.status = format!("Found \"{}\" at {:04x}", query, op.address());
+                return;
+            }
+        }
+
+        self.status = format!("\"{}\" not found", query);
+    }
+
+    fn toggle_data(&mut self) {
+        // Toggles whether the region between the mark and the cursor is shown as data
+        //  If every byte in the region is already data it goes back to being decoded as code
+
+        let cursor_op: &Operation = &self.ops[self.cursor];
+        let cursor_start: u16 = cursor_op.address();
+        let cursor_end: usize = cursor_start as usize + cursor_op.op_bytes() as usize;
+        // Worked out as a usize so the last instruction of a 64K image doesn't overflow
+
+        let (start, end): (usize, usize) = match self.mark.take() {
+            Some(mark) if mark < cursor_start => (mark as usize, cursor_end),
+            Some(mark) => (cursor_start as usize, (mark as usize + 1).max(cursor_end)),
+            None => (cursor_start as usize, cursor_end),
+        };
+        let end: usize = end.min(self.data.len());
+
+        let is_data: bool = !self.data_mask[start..end].iter().all(|byte| *byte);
+        self.data_mask[start..end].fill(is_data);
+
+        self.ops = decode(&self.data, &self.data_mask, self.syntax);
+        self.cursor = self.index_of_address(cursor_start);
+        self.status = format!("{:04x}-{:04x} shown as {}", start, end - 1, if is_data { "data" } else { "code" });
+    }
+
+    fn handle_key(&mut self, key: KeyCode, page: isize) -> bool {
+        // Returns false when the browser should close
+
+        match &mut self.mode {
+            Mode::Goto(input) | Mode::Search(input) => {
+                match key {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => { input.pop(); },
+                    KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Enter => {
+                        let mode: Mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                        match mode {
+                            Mode::Goto(input) => self.goto(&input),
+                            Mode::Search(input) => {
+                                self.search(&input);
+                                self.last_search = Some(input);
+                            },
+                            Mode::Browse => {},
+                        }
+                    },
+                    _ => {},
+                }
+                return true;
+            },
+            Mode::Browse => {},
+        }
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('j') | KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_cursor(-1),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.move_cursor(isize::MAX),
+            KeyCode::Char('g') => self.mode = Mode::Goto(String::new()),
+            KeyCode::Char('/') => self.mode = Mode::Search(String::new()),
+            KeyCode::Char('n') => match self.last_search.clone() {
+                Some(query) => self.search(&query),
+                None => self.status = String::from("No previous search"),
+            },
+            KeyCode::Char('v') => {
+                let address: u16 = self.ops[self.cursor].address();
+                self.mark = Some(address);
+                self.status = format!("Region marked from {:04x}", address);
+            },
+            KeyCode::Char('d') => self.toggle_data(),
+            _ => {},
+        }
+
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [listing_area, status_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let height: usize = listing_area.height.saturating_sub(2) as usize;
+        // Leave room for the border
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + height {
+            self.scroll = self.cursor + 1 - height;
+        }
+        // Keep the cursor on screen
+
+        let lines: Vec<Line> = self.ops.iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(height)
+            .map(|(index, op)| {
+                let marked: bool = self.mark == Some(op.address());
+                let marker: char = if marked { '>' } else { ' ' };
+                let text: String = match self.symbols.is_empty() {
+                    true => format!("{} {}", marker, op),
+                    false => format!("{} {:<16} {}", marker, self.symbols.name(op.address()).unwrap_or(""), op.annotated(&self.symbols)),
+                };
+                // Symbols get a column of their own so the listing stays one line per operation
+                match index == self.cursor {
+                    true => Line::styled(text, Style::new().reversed()),
+                    false => Line::from(text),
+                }
+            })
+            .collect();
+
+        let title: String = format!(" {} bytes, {} operations ", self.data.len(), self.ops.len());
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), listing_area);
+
+        let status: String = match &self.mode {
+            Mode::Browse => self.status.clone(),
+            Mode::Goto(input) => format!("Goto address: {}", input),
+            Mode::Search(input) => format!("Search: {}", input),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
+
+pub fn run(data: Vec<u8>, data_mask: Vec<bool>, symbols: SymbolTable, syntax: Syntax) -> io::Result<()> {
+    // Opens an interactive browser for the rom until the user quits
+
+    if data.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Nothing to disassemble"));
+    }
+
+    let mut terminal: DefaultTerminal = ratatui::init();
+    let result: io::Result<()> = browse(&mut terminal, Browser::new(data, data_mask, symbols, syntax));
+    ratatui::restore();
+    // Always give the terminal back even if drawing failed
+
+    result
+}
+
+fn decode(data: &[u8], data_mask: &[bool], syntax: Syntax) -> Vec<Operation> {
+    let mut ops: Vec<Operation> = disassembler::decode(data, data_mask);
+    ops.iter_mut().for_each(|op| op.set_syntax(syntax));
+    ops
+}
+
+fn browse(terminal: &mut DefaultTerminal, mut browser: Browser) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| browser.draw(frame))?;
+
+        let page: isize = terminal.size()?.height.saturating_sub(3) as isize;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !browser.handle_key(key.code, page) {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+fn browser() -> Browser {
+    let data: Vec<u8> = vec![
+        0x31, 0x00, 0x24, // 0x0000 LXI SP,0x2400
+        0xcd, 0x08, 0x00, // 0x0003 CALL 0x0008
+        0x76,             // 0x0006 HLT
+        0x42,             // 0x0007 DB 0x42
+        0xc9,             // 0x0008 RET
+    ];
+    let mut data_mask: Vec<bool> = vec![false; data.len()];
+    data_mask[7] = true;
+    let mut symbols: SymbolTable = SymbolTable::new();
+    symbols.insert(0x0008, "Return").unwrap();
+    Browser::new(data, data_mask, symbols, Syntax::Intel)
+}
+
+#[cfg(test)]
+fn cursor_address(browser: &Browser) -> u16 {
+    browser.ops[browser.cursor].address()
+}
+
+#[test]
+fn test_search() {
+    let mut browser: Browser = browser();
+
+    browser.search("ret");
+    assert_eq!(cursor_address(&browser), 0x0008);
+    browser.search("cd 08");
+    assert_eq!(cursor_address(&browser), 0x0003);
+    // Wraps around from the end
+
+    browser.search("db");
+    assert_eq!(cursor_address(&browser), 0x0007);
+    // Reads as hex but there's no 0xdb byte, the data byte's mnemonic is found instead
+    browser.search("76");
+    assert_eq!(cursor_address(&browser), 0x0006);
+
+    browser.search("xthl");
+    assert_eq!(cursor_address(&browser), 0x0006);
+    assert_eq!(browser.status, "\"xthl\" not found");
+}
+
+#[test]
+fn test_goto() {
+    let mut browser: Browser = browser();
+
+    browser.goto("0x0006");
+    assert_eq!(cursor_address(&browser), 0x0006);
+    browser.goto("4");
+    assert_eq!(cursor_address(&browser), 0x0003);
+    // The middle of an instruction goes to its start
+    browser.goto("Return");
+    assert_eq!(cursor_address(&browser), 0x0008);
+
+    browser.goto("0x0100");
+    assert_eq!(cursor_address(&browser), 0x0008);
+    assert_eq!(browser.status, "Invalid address: 0x0100");
+}
+
+#[test]
+fn test_toggle_data() {
+    let mut browser: Browser = browser();
+
+    browser.goto("0x0003");
+    browser.toggle_data();
+    assert_eq!(browser.data_mask[3..6], [true, true, true]);
+    assert_eq!(browser.ops.len(), 7);
+    assert_eq!(browser.status, "0003-0005 shown as data");
+    browser.mark = Some(0x0005);
+    browser.toggle_data();
+    // Every byte is already data so the region goes back to code
+    assert_eq!(browser.data_mask[3..6], [false, false, false]);
+    assert_eq!(browser.ops.len(), 5);
+
+    browser.goto("0x0006");
+    browser.mark = Some(0x0006);
+    browser.goto("0x0008");
+    browser.toggle_data();
+    assert_eq!(browser.data_mask[6..9], [true, true, true]);
+    assert!(browser.mark.is_none());
+    // A marked region runs from the mark to the end of the cursor's instruction
+
+    let mut full: Browser = Browser::new(vec![0; 0x10000], vec![], SymbolTable::new(), Syntax::Intel);
+    full.move_cursor(isize::MAX);
+    full.toggle_data();
+    assert_eq!(full.status, "ffff-ffff shown as data");
+    // The last instruction of a 64K image ends past 0xffff
+}
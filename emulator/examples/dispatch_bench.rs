@@ -0,0 +1,86 @@
+// Times the match dispatcher against the table of handler functions on the same instructions
+//  cargo run --release --example dispatch_bench -- [rom] [instructions]
+// Without a rom memory is filled with pseudo random bytes so every op code gets a share of the work
+
+use std::env;
+use std::fs;
+use std::time::Instant;
+
+use emulator::cpu::dispatcher;
+use emulator::prelude::*;
+
+const DEFAULT_INSTRUCTIONS: u64 = 50_000_000;
+const ROUNDS: usize = 3;
+// Each dispatcher is timed this many times and the fastest run is kept
+
+type Dispatch = fn(u8, &mut Cpu) -> Result<u16, &'static str>;
+
+fn random_memory(cpu: &mut Cpu) {
+    // Xorshift so the bytes are the same every run
+    let mut state: u32 = 0x2468_ace1;
+    for address in 0..=0xffff_u16 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        cpu.memory.poke(address, state as u8);
+    }
+}
+
+fn run(start: &Cpu, dispatch: Dispatch, instructions: u64) -> (f64, u16) {
+    // Returns the seconds taken and where the pc finished so the two runs can be checked against each other
+    //  IN and OUT are skipped over and a halt or error starts again from 0x0000
+
+    let mut cpu: Cpu = start.clone();
+    let started: Instant = Instant::now();
+    for _ in 0..instructions {
+        let op_code: u8 = cpu.memory.read_at(cpu.pc.address);
+        cpu.pc.address = cpu.pc.address.wrapping_add(1);
+        let additional_bytes: u16 = match op_code {
+            0xdb | 0xd3 => 1,
+            _ => match dispatch(op_code, &mut cpu) {
+                Ok(255) | Err(_) => {
+                    cpu.pc.address = 0;
+                    0
+                },
+                Ok(additional_bytes) => additional_bytes,
+            },
+        };
+        cpu.pc.address = cpu.pc.address.wrapping_add(additional_bytes);
+    }
+    (started.elapsed().as_secs_f64().max(f64::EPSILON), cpu.pc.address)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let instructions: u64 = args.get(2).and_then(|instructions| instructions.parse().ok()).unwrap_or(DEFAULT_INSTRUCTIONS);
+
+    let mut cpu: Cpu = Cpu::init();
+    match args.get(1) {
+        Some(rom_path) => {
+            let rom: Vec<u8> = fs::read(rom_path).unwrap_or_else(|e| panic!("Can't read {}: {}", rom_path, e));
+            for (address, byte) in rom.iter().take(0x10000).enumerate() {
+                cpu.memory.poke(address as u16, *byte);
+            }
+        },
+        None => random_memory(&mut cpu),
+    }
+
+    let dispatchers: [(&str, Dispatch); 2] = [
+        ("match", dispatcher::handle_op_code_match),
+        ("table", dispatcher::handle_op_code),
+    ];
+    let mut finished: Vec<u16> = vec![];
+    for (name, dispatch) in dispatchers {
+        let mut fastest: f64 = f64::MAX;
+        for _ in 0..ROUNDS {
+            let (seconds, pc): (f64, u16) = run(&cpu, dispatch, instructions);
+            fastest = fastest.min(seconds);
+            finished.push(pc);
+        }
+        println!("{:<6} {:.3}s, {:.1} million instructions per second", name, fastest, instructions as f64 / fastest / 1_000_000.0);
+    }
+
+    if finished.windows(2).any(|pair| pair[0] != pair[1]) {
+        println!("The dispatchers finished at different addresses {:04x?}", finished);
+    }
+}
@@ -0,0 +1,66 @@
+// Runs a rom as fast as possible and reports how much faster than the real machine it went
+//  cargo run --release --example headless_bench -- <rom> [frames]
+
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use emulator::prelude::*;
+
+const DEFAULT_FRAMES: u64 = 3600;
+// A minute of machine time
+
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+struct Bench {
+    // Times each frame without drawing anything
+    remaining: u64,
+    slowest: Duration,
+    frame_start: Instant,
+}
+impl Frontend for Bench {
+    fn poll_input(&mut self, _machine: &mut Machine) {
+        self.frame_start = Instant::now();
+    }
+
+    fn present(&mut self, _machine: &Machine) {
+        self.slowest = self.slowest.max(self.frame_start.elapsed());
+        self.remaining -= 1;
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.remaining == 0
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let Some(rom_path) = args.get(1) else {
+        println!("Usage: headless_bench <rom> [frames]");
+        return;
+    };
+    let frames: u64 = args.get(2).and_then(|frames| frames.parse().ok()).unwrap_or(DEFAULT_FRAMES);
+    let rom: Vec<u8> = fs::read(rom_path).unwrap_or_else(|e| panic!("Can't read {}: {}", rom_path, e));
+
+    for idle_skip in [false, true] {
+        let mut machine: Machine = Machine::with_profile(Profile::space_invaders()).unwrap_or_else(|e| panic!("{}", e));
+        machine.load_rom(&rom).unwrap_or_else(|e| panic!("{}", e));
+        machine.set_idle_skip(idle_skip);
+
+        let mut bench: Bench = Bench { remaining: frames, slowest: Duration::ZERO, frame_start: Instant::now() };
+        let started: Instant = Instant::now();
+        let ran: u64 = match emulator::frontend::run(&mut machine, &mut bench) {
+            Ok(ran) => ran,
+            Err(e) => {
+                println!("Machine stopped: {}", e);
+                frames - bench.remaining
+            },
+        };
+        let seconds: f64 = started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+        println!("Idle skip {}", if idle_skip { "on" } else { "off" });
+        println!("    {} frames in {:.3}s, {:.1}x real time", ran, seconds, ran as f64 / FRAMES_PER_SECOND / seconds);
+        println!("    {:.2} MHz emulated, {} cycles skipped", machine.cycles() as f64 / seconds / 1_000_000.0, machine.idle_skipped());
+        println!("    Slowest frame {:.3}ms", bench.slowest.as_secs_f64() * 1000.0);
+    }
+}
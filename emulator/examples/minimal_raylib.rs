@@ -0,0 +1,62 @@
+// The smallest windowed frontend, keyboard input and the screen with no sound or extras
+//  cargo run --example minimal_raylib -- <rom>
+
+use std::env;
+use std::fs;
+
+use emulator::hardware::input::{self, Binding};
+use emulator::palette::Palette;
+use emulator::postprocess::{Chain, Effect};
+use emulator::prelude::*;
+use emulator::Hud;
+use raylib::prelude::*;
+
+struct Window {
+    handle: RaylibHandle,
+    thread: RaylibThread,
+    palette: Palette,
+    post: Chain,
+    bindings: Vec<Binding>,
+}
+impl Frontend for Window {
+    fn poll_input(&mut self, machine: &mut Machine) {
+        input::read_bindings(&self.handle, &mut machine.hardware, &self.bindings);
+    }
+
+    fn present(&mut self, machine: &Machine) {
+        let hud: Vec<String> = emulator::controls(machine.profile().language);
+        emulator::render(&mut self.handle, &self.thread, machine, &self.palette, &mut self.post, &Hud { lines: &hud, buttons: None, scrubber: None });
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.handle.window_should_close()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let Some(rom_path) = args.get(1) else {
+        println!("Usage: minimal_raylib <rom>");
+        return;
+    };
+    let rom: Vec<u8> = fs::read(rom_path).unwrap_or_else(|e| panic!("Can't read {}: {}", rom_path, e));
+
+    let profile: Profile = Profile::space_invaders();
+    let bindings: Vec<Binding> = profile.bindings().unwrap_or_else(|e| panic!("{}", e));
+    let palette: Palette = Palette::new(&profile.overlay).unwrap_or_else(|e| panic!("{}", e));
+    let mut machine: Machine = Machine::with_profile(profile).unwrap_or_else(|e| panic!("{}", e));
+    machine.load_rom(&rom).unwrap_or_else(|e| panic!("{}", e));
+
+    let (mut handle, thread) = raylib::init()
+        .size(emulator::WIDTH, emulator::HEIGHT)
+        .title(&machine.profile().name)
+        .build();
+    handle.set_target_fps(60);
+    // raylib's own pacing is fine for a minimal frontend
+
+    let post: Chain = Chain::build(&[Effect::Overlay], &palette);
+    let mut frontend: Box<dyn Frontend> = Box::new(Window { handle, thread, palette, post, bindings });
+    if let Err(e) = emulator::frontend::run(&mut machine, frontend.as_mut()) {
+        println!("Machine stopped: {}", e);
+    }
+}
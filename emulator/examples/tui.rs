@@ -0,0 +1,92 @@
+// Draws the screen in a terminal with block characters, no window or graphics needed
+//  cargo run --example tui -- <rom> [frames]
+//  Input needs raw terminal mode which std doesn't offer, so a coin and start are pressed
+//  automatically to get a game going
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+
+use emulator::prelude::*;
+use emulator::{INVADERS_HEIGHT, INVADERS_WIDTH};
+
+const DEFAULT_FRAMES: u64 = 1800;
+
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 8;
+// Pixels covered by each character, about the shape of a terminal cell
+
+const SCRIPT: [(u64, u8); 2] = [(120, 0x01), (180, 0x04)];
+// Frame and input port 1 bits held for a few frames, coin then 1 player start
+const HOLD_FRAMES: u64 = 5;
+
+struct Terminal {
+    frame: u64,
+    frames: u64,
+    pacer: FramePacer,
+    out: io::Stdout,
+}
+impl Terminal {
+    fn draw(&self, machine: &Machine) -> String {
+        // Vram is stored a column at a time from the bottom of the rotated screen
+
+        let width: usize = INVADERS_WIDTH as usize;
+        let height: usize = INVADERS_HEIGHT as usize;
+        let vram: &[u8] = machine.cpu.memory.read_vram();
+        let lit = |column: usize, row: usize| vram.get(column * height / 8 + row / 8).is_some_and(|byte| byte >> (row % 8) & 1 == 1);
+
+        let mut text: String = String::from("\x1b[H");
+        // Back to the top left so each frame draws over the last
+        for y in (0..height).step_by(CELL_HEIGHT) {
+            for x in (0..width).step_by(CELL_WIDTH) {
+                let any: bool = (y..y + CELL_HEIGHT)
+                    .any(|screen_y| (x..x + CELL_WIDTH).any(|column| lit(column, height - 1 - screen_y)));
+                text.push(if any { '█' } else { ' ' });
+            }
+            text.push('\n');
+        }
+        text + &format!("Frame {} of {}\n", self.frame, self.frames)
+    }
+}
+impl Frontend for Terminal {
+    fn poll_input(&mut self, machine: &mut Machine) {
+        let held: u8 = SCRIPT.iter()
+            .filter(|(start, _)| (*start..*start + HOLD_FRAMES).contains(&self.frame))
+            .fold(0, |held, (_, bits)| held | bits);
+        machine.hardware.set_inputs([held, 0x00]);
+    }
+
+    fn present(&mut self, machine: &Machine) {
+        self.frame += 1;
+        if self.frame.is_multiple_of(2) {
+            let text: String = self.draw(machine);
+            let _ = self.out.write_all(text.as_bytes());
+            let _ = self.out.flush();
+        }
+        // Every other frame is plenty for a terminal
+        self.pacer.wait();
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.frame >= self.frames
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let Some(rom_path) = args.get(1) else {
+        println!("Usage: tui <rom> [frames]");
+        return;
+    };
+    let frames: u64 = args.get(2).and_then(|frames| frames.parse().ok()).unwrap_or(DEFAULT_FRAMES);
+    let rom: Vec<u8> = fs::read(rom_path).unwrap_or_else(|e| panic!("Can't read {}: {}", rom_path, e));
+
+    let mut machine: Machine = Machine::with_profile(Profile::space_invaders()).unwrap_or_else(|e| panic!("{}", e));
+    machine.load_rom(&rom).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut frontend: Box<dyn Frontend> = Box::new(Terminal { frame: 0, frames, pacer: FramePacer::new(60.0), out: io::stdout() });
+    print!("\x1b[2J");
+    if let Err(e) = emulator::frontend::run(&mut machine, frontend.as_mut()) {
+        println!("Machine stopped: {}", e);
+    }
+}
@@ -0,0 +1,77 @@
+; Demo rom for the text console profile, prints a title and counts frames
+;   Assembled to text_console.bin with emulator --assemble text_console.asm text_console.bin
+; Numbers are hex
+
+Screen          EQU     0x1000
+Frames          EQU     0x0800          ; Frames counted so far, 2 bytes
+Counter         EQU     0x1088          ; Row 3 column 16 where the count goes
+
+                DI
+                LXI     SP, 0x1000
+                JMP     Start
+
+                ORG     0x08            ; RST 1, halfway through the frame
+                EI
+                RET
+
+                ORG     0x10            ; RST 2, end of the frame
+                JMP     Frame
+
+Start:          LXI     H, Title
+                LXI     D, 0x1004       ; Row 0 column 4
+                CALL    Print
+                LXI     H, Label
+                LXI     D, 0x1080       ; Row 3 column 8
+                CALL    Print
+                EI
+Wait:           HLT
+                JMP     Wait
+
+Frame:          PUSH    PSW
+                PUSH    D
+                PUSH    H
+                OUT     0x06            ; Feed the watchdog
+                IN      0x01
+                ANI     0x01
+                JNZ     Shown           ; Space holds the count
+                LHLD    Frames
+                INX     H
+                SHLD    Frames
+                XCHG
+                LXI     H, Counter
+                MOV     A, D
+                CALL    Hex
+                MOV     A, E
+                CALL    Hex
+Shown:          POP     H
+                POP     D
+                POP     PSW
+                EI
+                RET
+
+Print:          MOV     A, M            ; Copies the string at HL to DE until a 0
+                ORA     A
+                RZ
+                STAX    D
+                INX     H
+                INX     D
+                JMP     Print
+
+Hex:            PUSH    PSW             ; Writes A to HL as two hex digits, HL ends up after them
+                RRC
+                RRC
+                RRC
+                RRC
+                CALL    Digit
+                POP     PSW
+Digit:          ANI     0x0f
+                ADI     0x30            ; '0'
+                CPI     0x3a
+                JC      Store
+                ADI     0x07            ; On to 'A'
+Store:          MOV     M, A
+                INX     H
+                RET
+
+Title:          DB      "HELLO FROM THE 8080 TEXT CONSOLE", 0
+Label:          DB      "FRAMES:", 0
@@ -0,0 +1,198 @@
+use crate::cpu::Memory;
+use crate::display::{Display, Framebuffer, Geometry};
+use crate::error::{EmulatorError, LoadError};
+use crate::machine::Machine;
+
+mod tests;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamValue {
+    // Number the game keeps in ram, read out after every step
+    pub name: String,
+    pub address: u16,
+    pub bytes: u8,
+    // Little endian, lowest byte at address
+    pub bcd: bool,
+    // Two decimal digits a byte, how most games of the time keep their score
+}
+impl RamValue {
+    pub fn new(name: &str, address: u16, bytes: u8, bcd: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            address,
+            bytes,
+            bcd,
+        }
+    }
+
+    pub fn read(&self, memory: &Memory) -> i64 {
+        (0..self.bytes as u16).rev().fold(0, |value, offset| {
+            let byte: u8 = memory.read_at(self.address.wrapping_add(offset));
+            match self.bcd {
+                true => value * 100 + (byte >> 4) as i64 * 10 + (byte & 0x0f) as i64,
+                false => value << 8 | byte as i64,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputState {
+    // Input ports held for a step, the same bits the keybinds set
+    pub inputs: [u8; 2],
+}
+impl InputState {
+    pub fn new() -> Self {
+        Self { inputs: [0, 0] }
+    }
+
+    pub fn press(mut self, input: u8, bit: u8) -> Self {
+        // e.g. InputState::new().press(1, 4).press(1, 5) fires while moving left on Space Invaders
+        if let Some(port) = self.inputs.get_mut((input as usize).wrapping_sub(1)) {
+            *port |= 1 << bit;
+        }
+        self
+    }
+}
+impl Default for InputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameObservation {
+    // Everything an agent sees after a step
+    pub frame: u64,
+    // Frames run since the last reset
+    pub framebuffer: Vec<u8>,
+    // Display memory exactly as the game left it, a bit a pixel laid out by geometry for a bitmap display
+    pub geometry: Geometry,
+    pub values: Vec<(String, i64)>,
+    // Every RamValue, in the order they were given
+    pub reward: i64,
+    // How much the reward value went up during the step
+    pub done: bool,
+    // The done value has run out, e.g. no lives left
+}
+impl FrameObservation {
+    pub fn value(&self, name: &str) -> Option<i64> {
+        self.values.iter().find(|(value_name, _)| value_name == name).map(|(_, value)| *value)
+    }
+
+    pub fn lit(&self, x: usize, y: usize) -> bool {
+        // Whether the bitmap pixel x across from the left and y down from the top is on
+        match (u16::try_from(x), u16::try_from(y)) {
+            (Ok(x), Ok(y)) => Framebuffer::new(self.geometry, &self.framebuffer).lit(x, y),
+            _ => false,
+        }
+    }
+}
+
+pub struct Environment {
+    // Runs a game headlessly a step at a time for reinforcement learning, the way gym environments do
+    //  Each step holds the inputs down for frame_skip frames and reports what changed
+    machine: Machine,
+    rom: Vec<u8>,
+    values: Vec<RamValue>,
+    reward: Option<String>,
+    // Name of the value whose increase is the reward
+    done: Option<String>,
+    // Name of the value that ends the episode when it drops to 0
+    frame_skip: u32,
+    frame: u64,
+    last: Vec<i64>,
+    // Values after the previous step
+}
+impl Environment {
+    pub fn new(machine: Machine, rom: &[u8], values: Vec<RamValue>) -> Result<Self, LoadError> {
+        let mut environment: Environment = Self {
+            machine,
+            rom: rom.to_vec(),
+            values,
+            reward: None,
+            done: None,
+            frame_skip: 1,
+            frame: 0,
+            last: vec![],
+        };
+        environment.machine.power_cycle(rom)?;
+        environment.last = environment.read_values();
+        Ok(environment)
+    }
+
+    pub fn space_invaders(rom: &[u8]) -> Result<Self, LoadError> {
+        // Rewarded for player 1's score, done when their last ship is lost
+        let values: Vec<RamValue> = vec![
+            RamValue::new("score", 0x20f8, 2, true),
+            RamValue::new("lives", 0x21ff, 1, false),
+        ];
+        let mut environment: Environment = Self::new(Machine::init(), rom, values)?;
+        environment.set_reward("score");
+        environment.set_done("lives");
+        Ok(environment)
+    }
+
+    pub fn set_reward(&mut self, name: &str) {
+        self.reward = Some(name.to_string());
+    }
+
+    pub fn set_done(&mut self, name: &str) {
+        self.done = Some(name.to_string());
+    }
+
+    pub fn set_frame_skip(&mut self, frames: u32) {
+        self.frame_skip = frames.max(1);
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    fn read_values(&self) -> Vec<i64> {
+        self.values.iter().map(|value| value.read(&self.machine.cpu.memory)).collect()
+    }
+
+    fn value_index(&self, name: &Option<String>) -> Option<usize> {
+        name.as_ref().and_then(|name| self.values.iter().position(|value| value.name == *name))
+    }
+
+    pub fn reset(&mut self) -> FrameObservation {
+        // Switches the machine off and on again for a new episode
+        self.machine.power_cycle(&self.rom).expect("the rom was loaded when the environment was made");
+        self.frame = 0;
+        self.last = self.read_values();
+        self.observe(&self.last.clone())
+    }
+
+    pub fn act(&mut self, input: InputState) -> Result<FrameObservation, EmulatorError> {
+        // Holds the input down for a step and reports the frame it ended on
+
+        let before: Vec<i64> = std::mem::take(&mut self.last);
+        self.machine.hardware.set_inputs(input.inputs);
+        for _ in 0..self.frame_skip {
+            self.machine.run_frame()?;
+            self.frame += 1;
+        }
+        self.last = self.read_values();
+        Ok(self.observe(&before))
+    }
+
+    fn observe(&self, before: &[i64]) -> FrameObservation {
+        let reward: i64 = self.value_index(&self.reward)
+            .map_or(0, |index| (self.last[index] - before[index]).max(0));
+        // A score going back to 0 for a new game isn't a punishment
+        let done: bool = self.value_index(&self.done)
+            .is_some_and(|index| before[index] > 0 && self.last[index] == 0);
+        let display: Display = self.machine.profile().display;
+
+        FrameObservation {
+            frame: self.frame,
+            framebuffer: self.machine.cpu.memory.view(display.memory()).to_vec(),
+            geometry: display.geometry(),
+            values: self.values.iter().map(|value| value.name.clone()).zip(self.last.iter().copied()).collect(),
+            reward,
+            done,
+        }
+    }
+}
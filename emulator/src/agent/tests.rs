@@ -0,0 +1,79 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+type Step = (u64, i64, Option<i64>, Option<i64>, bool);
+// Frame, reward, score, lives and done from one call to act
+
+#[cfg(test)]
+fn counting_rom() -> Vec<u8> {
+    // Loses a life halfway down every frame and copies input 1 into the score at the end of it
+    let mut rom: Vec<u8> = vec![0; 0x24];
+    rom[0x00..0x03].copy_from_slice(&[0xc3, 0x18, 0x00]); // 0x0000 JMP 0x0018
+    rom[0x08..0x0e].copy_from_slice(&[
+        0x21, 0xff, 0x21, // 0x0008 LXI H,0x21ff
+        0x35,             // 0x000b DCR M
+        0xfb, 0xc9,       // 0x000c EI, RET
+    ]);
+    rom[0x10..0x17].copy_from_slice(&[
+        0xdb, 0x01,       // 0x0010 IN 1
+        0x32, 0xf8, 0x20, // 0x0012 STA 0x20f8
+        0xfb, 0xc9,       // 0x0015 EI, RET
+    ]);
+    rom[0x18..0x24].copy_from_slice(&[
+        0x31, 0x00, 0x24, // 0x0018 LXI SP,0x2400
+        0x3e, 0x03,       // 0x001b MVI A,3
+        0x32, 0xff, 0x21, // 0x001d STA 0x21ff
+        0xfb,             // 0x0020 EI
+        0xc3, 0x21, 0x00, // 0x0021 JMP 0x0021
+    ]);
+    rom
+}
+
+#[test]
+fn test_ram_value() {
+    let mut machine: Machine = Machine::init();
+    machine.cpu.memory.write_at(0x20f8, 0x50);
+    machine.cpu.memory.write_at(0x20f9, 0x12);
+    assert_eq!(RamValue::new("score", 0x20f8, 2, true).read(&machine.cpu.memory), 1250);
+    assert_eq!(RamValue::new("raw", 0x20f8, 2, false).read(&machine.cpu.memory), 0x1250);
+    assert_eq!(InputState::new().press(1, 4).press(2, 0).press(3, 0).inputs, [0x10, 0x01]);
+}
+
+#[test]
+fn test_environment() {
+    let values: Vec<RamValue> = vec![RamValue::new("score", 0x20f8, 1, true), RamValue::new("lives", 0x21ff, 1, false)];
+    let mut environment: Environment = Environment::new(Machine::init(), &counting_rom(), values).unwrap();
+    environment.set_reward("score");
+    environment.set_done("lives");
+
+    let start: FrameObservation = environment.reset();
+    assert_eq!((start.frame, start.reward, start.done), (0, 0, false));
+    assert_eq!(start.framebuffer.len(), 0x1c00);
+
+    let mut steps: Vec<Step> = vec![];
+    for input in [InputState::new(), InputState::new().press(1, 4), InputState::new()] {
+        let step: FrameObservation = environment.act(input).unwrap();
+        steps.push((step.frame, step.reward, step.value("score"), step.value("lives"), step.done));
+    }
+    assert_eq!(steps, vec![
+        (1, 0, Some(0), Some(2), false),
+        (2, 10, Some(10), Some(1), false),
+        (3, 0, Some(0), Some(0), true),
+    ]);
+    // The end of frame interrupt runs at the start of the next one, so it sees that step's input
+
+    environment.set_frame_skip(2);
+    environment.reset();
+    assert_eq!(environment.act(InputState::new()).unwrap().frame, 2);
+}
+
+#[test]
+fn test_observation_pixels() {
+    let mut framebuffer: Vec<u8> = vec![0; 0x1c00];
+    framebuffer[0] = 0x80;
+    // Column 0, eighth row up from the bottom
+    let observation: FrameObservation = FrameObservation { frame: 0, framebuffer, geometry: Geometry::space_invaders(), values: vec![], reward: 0, done: false };
+    assert!(observation.lit(0, 248));
+    assert!(!observation.lit(0, 255) && !observation.lit(1, 248) && !observation.lit(500, 0));
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use disassembler::instructions::{self, Instruction};
+use disassembler::symbols::SymbolTable;
+
+mod tests;
+
+pub fn assemble(line: &str, symbols: &SymbolTable) -> Result<Vec<u8>, String> {
+    // Turns a single instruction like "MVI A, 20" or "JMP DrawAlien+3" into its bytes
+    //  Numbers are hex the same as everywhere else in the monitor and symbols can stand in for them
+    //  Instructions are matched against the disassembler's op code table, the lowest op code wins
+    //  so the undocumented copies are never picked
+
+    let line: &str = line.split(';').next().unwrap_or_default().trim();
+    // Anything after a semicolon is a comment
+    let (mnemonic, rest): (&str, &str) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let operands: Vec<&str> = rest.split(',').map(str::trim).filter(|operand| !operand.is_empty()).collect();
+    if mnemonic.is_empty() {
+        return Err(String::from("Nothing to assemble"));
+    }
+
+    let table: Vec<Instruction> = instructions::table();
+    let candidates: Vec<&Instruction> = table.iter()
+        .filter(|instruction| instruction.mnemonic.eq_ignore_ascii_case(mnemonic))
+        .collect();
+    if candidates.is_empty() {
+        return Err(format!("{} is not an 8080 instruction", mnemonic));
+    }
+
+    for instruction in candidates {
+        if instruction.operands.len() != operands.len() {
+            continue;
+        }
+        let registers_match: bool = instruction.operands.iter()
+            .zip(&operands)
+            .all(|(expected, given)| is_value(expected) || expected.eq_ignore_ascii_case(given));
+        if registers_match {
+            return encode(instruction, &operands, symbols);
+        }
+    }
+
+    Err(format!("{} doesn't take the operands {}", mnemonic.to_ascii_uppercase(), operands.join(", ")))
+}
+
+fn is_value(operand: &str) -> bool {
+    // Placeholders in the table for bytes that follow the op code
+    matches!(operand, "D8" | "D16" | "adr")
+}
+
+fn encode(instruction: &Instruction, operands: &[&str], symbols: &SymbolTable) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = vec![instruction.op_code];
+    for (expected, given) in instruction.operands.iter().zip(operands) {
+        if !is_value(expected) {
+            continue;
+        }
+        let value: u16 = symbols.resolve(given).ok_or_else(|| format!("{} is not a number or a known symbol", given))?;
+        match expected.as_str() {
+            "D8" if value > 0xff => return Err(format!("{} doesn't fit in a byte", given)),
+            "D8" => bytes.push(value as u8),
+            _ => bytes.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn assemble_program(source: &str) -> Result<Vec<u8>, String> {
+    // Assembles a whole program from address 0, one instruction or directive per line
+    //  Labels end in a colon and can share a line with an instruction, e.g. Loop: DCR B
+    //      Name EQU 2000       Names a value
+    //      ORG 10              Carries on from a later address, the gap is filled with zeros
+    //      DB "TEXT", 0        Strings and bytes placed as they are
+    //  Names can be used before they are defined, the program is assembled twice and the first time
+    //  only finds where everything lands
+
+    let mut names: HashMap<String, u16> = HashMap::new();
+    let mut bytes: Vec<u8> = vec![];
+    for last_pass in [false, true] {
+        bytes.clear();
+        for (number, line) in source.lines().enumerate() {
+            program_line(line, &mut names, &mut bytes, last_pass).map_err(|e| format!("Line {}: {}", number + 1, e))?;
+        }
+    }
+    Ok(bytes)
+}
+
+fn program_line(line: &str, names: &mut HashMap<String, u16>, bytes: &mut Vec<u8>, last_pass: bool) -> Result<(), String> {
+    let mut line: &str = strip_comment(line).trim();
+    if let Some((label, rest)) = line.split_once(':').filter(|(label, _)| is_name(label.trim())) {
+        define(names, label.trim(), bytes.len() as u16, last_pass)?;
+        line = rest.trim();
+    }
+    if line.is_empty() {
+        return Ok(());
+    }
+
+    let (first, rest): (&str, &str) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest: &str = rest.trim();
+    if let Some(("EQU" | "equ", value)) = rest.split_once(char::is_whitespace) {
+        let value: u16 = lookup(value.trim(), names, last_pass)?;
+        return define(names, first, value, last_pass);
+    }
+
+    match first.to_ascii_uppercase().as_str() {
+        "ORG" => {
+            let address: usize = lookup(rest, names, true)? as usize;
+            if address < bytes.len() {
+                return Err(format!("ORG {} is behind 0x{:04x}", rest, bytes.len()));
+            }
+            bytes.resize(address, 0);
+        },
+        "DB" => {
+            for item in split_items(rest) {
+                match item.strip_prefix('"').and_then(|item| item.strip_suffix('"')) {
+                    Some(text) => bytes.extend_from_slice(text.as_bytes()),
+                    None => match lookup(item, names, last_pass)? {
+                        byte @ 0..=0xff => bytes.push(byte as u8),
+                        _ => return Err(format!("{} doesn't fit in a byte", item)),
+                    },
+                }
+            }
+        },
+        _ => {
+            let operands: Vec<String> = rest.split(',')
+                .map(str::trim)
+                .filter(|operand| !operand.is_empty())
+                .map(|operand| match is_register(operand) || first.eq_ignore_ascii_case("RST") {
+                    true => Ok(operand.to_string()),
+                    false => lookup(operand, names, last_pass).map(|value| format!("0x{:04x}", value)),
+                })
+                .collect::<Result<Vec<String>, String>>()?;
+            bytes.extend(assemble(&format!("{} {}", first, operands.join(", ")), &SymbolTable::default())?);
+        },
+    }
+    if bytes.len() > 0x10000 {
+        return Err(String::from("Program runs past the end of memory"));
+    }
+    Ok(())
+}
+
+fn define(names: &mut HashMap<String, u16>, name: &str, value: u16, last_pass: bool) -> Result<(), String> {
+    if !is_name(name) {
+        return Err(format!("{} can't be used as a name", name));
+    }
+    if names.insert(name.to_string(), value).is_some() && !last_pass {
+        return Err(format!("{} is defined twice", name));
+    }
+    Ok(())
+}
+
+fn lookup(text: &str, names: &HashMap<String, u16>, last_pass: bool) -> Result<u16, String> {
+    // A number, a name or a name plus an offset like Message+3
+    //  Names not defined yet stand in as 0 on the first pass, the instruction is the same size whatever they are
+
+    let (name, offset): (&str, &str) = text.split_once('+').unwrap_or((text, "0"));
+    let value: Option<u16> = match names.get(name.trim()) {
+        Some(value) => Some(*value),
+        None => SymbolTable::default().resolve(name),
+    };
+    let offset: Option<u16> = SymbolTable::default().resolve(offset);
+    match (value, offset) {
+        (Some(value), Some(offset)) => Ok(value.wrapping_add(offset)),
+        (None, Some(_)) if !last_pass && is_name(name.trim()) => Ok(0),
+        _ => Err(format!("{} is not a number or a known name", text)),
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !is_register(text)
+}
+
+fn is_register(text: &str) -> bool {
+    ["A", "B", "C", "D", "E", "H", "L", "M", "SP", "PSW"].iter().any(|register| register.eq_ignore_ascii_case(text))
+}
+
+fn strip_comment(line: &str) -> &str {
+    // Everything before the first semicolon that isn't in a string
+    let mut quoted: bool = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..index],
+            _ => {},
+        }
+    }
+    line
+}
+
+fn split_items(text: &str) -> Vec<&str> {
+    // Splits DB's operands on commas that aren't in a string
+    let mut items: Vec<&str> = vec![];
+    let mut quoted: bool = false;
+    let mut start: usize = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(text[start..index].trim());
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+    items.push(text[start..].trim());
+    items.into_iter().filter(|item| !item.is_empty()).collect()
+}
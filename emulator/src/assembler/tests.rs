@@ -0,0 +1,70 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_assemble_every_instruction() {
+    // Filling in every documented instruction from the table has to give back its op code
+
+    let symbols: SymbolTable = SymbolTable::default();
+    let table: Vec<Instruction> = instructions::table();
+    for instruction in table.iter().filter(|instruction| instruction.mnemonic != "NOP" || instruction.op_code == 0x00) {
+        let operands: Vec<&str> = instruction.operands.iter()
+            .map(|operand| match operand.as_str() {
+                "D8" => "12",
+                "D16" | "adr" => "3456",
+                register => register,
+            })
+            .collect();
+        let line: String = format!("{} {}", instruction.mnemonic, operands.join(", "));
+        let bytes: Vec<u8> = assemble(&line, &symbols).unwrap();
+        assert_eq!((bytes[0], bytes.len()), (instruction.op_code, instruction.bytes as usize), "{}", line);
+    }
+}
+
+#[test]
+fn test_assemble() {
+    let symbols: SymbolTable = SymbolTable::parse("0x18d4 DrawAlien").unwrap();
+
+    assert_eq!(assemble("mvi a,0x20 ; lowercase works", &symbols), Ok(vec![0x3e, 0x20]));
+    assert_eq!(assemble("JMP DrawAlien+3", &symbols), Ok(vec![0xc3, 0xd7, 0x18]));
+    assert_eq!(assemble("LXI SP, 2400", &symbols), Ok(vec![0x31, 0x00, 0x24]));
+    assert_eq!(assemble("RST 7", &symbols), Ok(vec![0xff]));
+
+    assert!(assemble("", &symbols).is_err());
+    assert!(assemble("FROB A", &symbols).is_err_and(|e| e.contains("not an 8080 instruction")));
+    assert!(assemble("MOV A, Q", &symbols).is_err_and(|e| e.contains("operands")));
+    assert!(assemble("MVI A, 100", &symbols).is_err_and(|e| e.contains("byte")));
+    assert!(assemble("CALL Nowhere", &symbols).is_err_and(|e| e.contains("Nowhere")));
+}
+
+#[test]
+fn test_assemble_program() {
+    let program: Result<Vec<u8>, String> = assemble_program(r#"
+        Screen  EQU     0x1000
+                JMP     Start           ; jumps forward to a label
+                ORG     8
+                RST     1
+        Start:  LXI     H, Message+1
+                LXI     D, Screen
+        Loop:   MOV     A, M
+                STAX    D
+                JMP     Loop
+        Message: DB     "A;B", 0, 42
+    "#);
+    assert_eq!(program, Ok(vec![
+        0xc3, 0x09, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00,
+        0xcf,
+        0x21, 0x15, 0x00,
+        0x11, 0x00, 0x10,
+        0x7e,
+        0x12,
+        0xc3, 0x0f, 0x00,
+        b'A', b';', b'B', 0x00, 0x42,
+    ]));
+
+    assert_eq!(assemble_program("JMP Nowhere"), Err(String::from("Line 1: Nowhere is not a number or a known name")));
+    assert_eq!(assemble_program("Loop: NOP\nLoop: NOP"), Err(String::from("Line 2: Loop is defined twice")));
+    assert_eq!(assemble_program("NOP\nNOP\nORG 1"), Err(String::from("Line 3: ORG 1 is behind 0x0002")));
+    assert_eq!(assemble_program("DB 100"), Err(String::from("Line 1: 100 doesn't fit in a byte")));
+}
@@ -0,0 +1,204 @@
+use crate::hardware::sound::{AudioSink, Sound};
+use crate::profile::AudioConfig;
+
+pub mod device;
+pub mod samples;
+pub mod synth;
+pub mod underrun;
+pub mod wav;
+
+mod tests;
+
+use samples::SamplePack;
+
+pub const SAMPLE_RATE: u32 = 22050;
+// Sample packs are synthesized and loaded at this rate, plenty for the board's sounds and keeps startup quick
+//  Backends mix at whatever rate the stream was opened with
+
+const FADE_SECONDS: f32 = 0.02;
+// Ducking fades over 20ms instead of jumping which would click
+
+pub trait AudioBackend: AudioSink {
+    // Turns the sounds the game has started into samples for the audio device
+    fn mix(&mut self, buffer: &mut [i16]);
+    fn set_config(&mut self, config: AudioConfig);
+    fn set_sample_rate(&mut self, sample_rate: u32);
+    // Called whenever the stream is opened, sounds already playing carry on from the same point at the new rate
+    fn sample_rate(&self) -> u32;
+}
+
+fn gain_step(sample_rate: u32) -> f32 {
+    1.0 / (FADE_SECONDS * sample_rate.max(1) as f32)
+}
+
+pub struct Channels {
+    // Applies a profile's channel limit, priorities and ducking to the playing sounds
+    config: AudioConfig,
+    targets: [f32; Sound::ALL.len()],
+    gains: [f32; Sound::ALL.len()],
+    // Current volume of each sound, moves towards its target a little every sample
+    step: f32,
+}
+impl Channels {
+    pub fn new(config: AudioConfig) -> Self {
+        Self {
+            config,
+            targets: [1.0; Sound::ALL.len()],
+            gains: [1.0; Sound::ALL.len()],
+            step: gain_step(SAMPLE_RATE),
+        }
+    }
+
+    pub fn set_config(&mut self, config: AudioConfig) {
+        self.config = config;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.step = gain_step(sample_rate);
+        // Fades take as long whatever the rate
+    }
+
+    fn rank(&self, sound: Sound) -> usize {
+        // Lower plays first
+        self.config.priority.iter()
+            .position(|prioritised| *prioritised == sound)
+            .unwrap_or(self.config.priority.len() + sound as usize)
+    }
+
+    pub fn update(&mut self, is_playing: impl Fn(Sound) -> bool) {
+        // Works out the volume each sound should be heard at, should be called whenever sounds start or stop
+
+        let mut playing: Vec<Sound> = Sound::ALL.into_iter().filter(|sound| is_playing(*sound)).collect();
+        playing.sort_by_key(|sound| self.rank(*sound));
+
+        self.targets = [0.0; Sound::ALL.len()];
+        for sound in playing.iter().take(self.config.channels) {
+            self.targets[*sound as usize] = 1.0;
+        }
+
+        for duck in &self.config.ducking {
+            let ducked: bool = duck.under.iter().any(|under| self.targets[*under as usize] > 0.0);
+            if ducked {
+                self.targets[duck.sound as usize] *= duck.volume;
+            }
+        }
+    }
+
+    pub fn target(&self, sound: Sound) -> f32 {
+        self.targets[sound as usize]
+    }
+
+    pub fn gain(&mut self, sound: Sound) -> f32 {
+        // Volume for the next sample of a sound
+
+        let gain: &mut f32 = &mut self.gains[sound as usize];
+        let target: f32 = self.targets[sound as usize];
+        *gain = if *gain < target { (*gain + self.step).min(target) } else { (*gain - self.step).max(target) };
+
+        *gain
+    }
+}
+impl Default for Channels {
+    fn default() -> Self {
+        Self::new(AudioConfig::default())
+    }
+}
+
+fn to_pcm(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+pub struct Mixer {
+    // Plays the sample pack's clips and mixes them into a single stream
+    pack: SamplePack,
+    clips: Vec<Vec<f32>>,
+    // The pack's clips resampled to the stream's rate, indexed by `sound as usize`
+    sample_rate: u32,
+    positions: [Option<usize>; Sound::ALL.len()],
+    // Where each playing sound is up to, None when silent
+    channels: Channels,
+    pub volume: f32,
+}
+impl Mixer {
+    pub fn new(pack: SamplePack) -> Self {
+        Self {
+            clips: Sound::ALL.iter().map(|sound| pack.clip(*sound).to_vec()).collect(),
+            sample_rate: SAMPLE_RATE,
+            pack,
+            positions: [None; Sound::ALL.len()],
+            channels: Channels::default(),
+            volume: 1.0,
+        }
+    }
+
+    pub fn is_playing(&self, sound: Sound) -> bool {
+        self.positions[sound as usize].is_some()
+    }
+}
+impl AudioBackend for Mixer {
+    fn mix(&mut self, buffer: &mut [i16]) {
+        // Fills the buffer with the next samples of every playing sound
+
+        self.channels.update(|sound| self.positions[sound as usize].is_some());
+        for out in buffer.iter_mut() {
+            let mut sample: f32 = 0.0;
+
+            for sound in Sound::ALL {
+                let Some(position) = self.positions[sound as usize] else {
+                    continue;
+                };
+                let clip: &[f32] = &self.clips[sound as usize];
+
+                sample += clip.get(position).copied().unwrap_or(0.0) * self.channels.gain(sound);
+                self.positions[sound as usize] = match position + 1 {
+                    next if next < clip.len() => Some(next),
+                    _ if sound.is_looping() && !clip.is_empty() => Some(0),
+                    _ => None,
+                };
+            }
+
+            *out = to_pcm(sample * self.volume);
+        }
+    }
+
+    fn set_config(&mut self, config: AudioConfig) {
+        self.channels.set_config(config);
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Resamples from the pack each time so going back and forth doesn't blur the clips
+
+        if sample_rate == self.sample_rate || sample_rate == 0 {
+            return;
+        }
+        for sound in Sound::ALL {
+            self.clips[sound as usize] = wav::resample(self.pack.clip(sound), SAMPLE_RATE, sample_rate);
+            self.positions[sound as usize] = self.positions[sound as usize]
+                .map(|position| (position as u64 * sample_rate as u64 / self.sample_rate as u64) as usize);
+        }
+        self.channels.set_sample_rate(sample_rate);
+        self.sample_rate = sample_rate;
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+impl AudioSink for Mixer {
+    fn play(&mut self, sound: Sound) {
+        self.positions[sound as usize] = Some(0);
+        // Retriggering a sound starts it again from the beginning
+    }
+
+    fn stop(&mut self, sound: Sound) {
+        if sound.is_looping() {
+            self.positions[sound as usize] = None;
+        }
+        // One shots play to the end like the board's timer circuits
+    }
+}
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new(SamplePack::default())
+    }
+}
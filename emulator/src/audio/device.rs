@@ -0,0 +1,71 @@
+use crate::profile::DeviceConfig;
+
+use super::underrun::Underruns;
+
+#[derive(Debug, Clone)]
+pub struct Device {
+    // Works out when the stream to the audio device has to be opened again and with what
+    //  The frontend owns the stream, this only decides, so the backends never see a hardcoded rate or buffer
+    //  Happens when the device goes away and comes back (headphones unplugged on some platforms),
+    //  when the profile's [audio.device] settings change and when underruns grow the buffer
+    config: DeviceConfig,
+    underruns: Underruns,
+    open: Option<(u32, usize)>,
+    // Sample rate and buffer size the stream is open with, None while there isn't one
+}
+impl Device {
+    pub fn new(config: DeviceConfig) -> Self {
+        Self {
+            config,
+            underruns: Underruns::new(config.buffer_samples, config.max_buffer_samples()),
+            open: None,
+        }
+    }
+
+    pub fn config(&self) -> DeviceConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: DeviceConfig) {
+        // The buffer goes back to the configured size, growing again from there if it has to
+
+        if config != self.config {
+            self.config = config;
+            self.underruns.resize(config.buffer_samples, config.max_buffer_samples());
+        }
+    }
+
+    pub fn wanted(&self) -> (u32, usize) {
+        (self.config.sample_rate, self.underruns.buffer_samples())
+    }
+
+    pub fn poll(&mut self, ready: bool) -> Option<(u32, usize)> {
+        // Called every frame with whether the device is there
+        //  Returns the sample rate and buffer size to open the stream with when it needs opening, again or for the first time
+
+        if !ready {
+            self.open = None;
+            return None;
+        }
+        let wanted: (u32, usize) = self.wanted();
+        if self.open == Some(wanted) {
+            return None;
+        }
+        self.open = Some(wanted);
+        Some(wanted)
+    }
+
+    pub fn refilled(&mut self, buffers: usize, frame: u64, late: bool) -> Option<usize> {
+        // Passed on to the underrun counter, a bigger buffer is picked up by the next poll
+        self.underruns.refilled(buffers, frame, late)
+    }
+
+    pub fn underruns(&self) -> &Underruns {
+        &self.underruns
+    }
+}
+impl Default for Device {
+    fn default() -> Self {
+        Self::new(DeviceConfig::default())
+    }
+}
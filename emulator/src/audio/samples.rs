@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::Path;
+
+use crate::hardware::sound::Sound;
+
+use super::{synth, wav, SAMPLE_RATE};
+
+pub struct SamplePack {
+    // Audio for every sound resampled to SAMPLE_RATE, indexed by `sound as usize`
+    clips: Vec<Vec<f32>>,
+    loaded: Vec<Sound>,
+    // Sounds that came from files instead of being synthesized
+}
+impl SamplePack {
+    pub fn synthesized() -> Self {
+        Self {
+            clips: Sound::ALL.iter().map(|sound| synth::render(*sound)).collect(),
+            loaded: vec![],
+        }
+    }
+
+    pub fn load(directory: &Path) -> Self {
+        // Loads <name>.wav for each sound, or the numbered files used by the common
+        //  Invaders sample packs (0.wav is the UFO through to 8.wav for the UFO being hit)
+        //  Anything missing or unreadable is synthesized instead
+
+        let mut pack: SamplePack = Self::synthesized();
+
+        for sound in Sound::ALL.iter() {
+            let named: String = format!("{}.wav", sound.name());
+            let numbered: String = format!("{}.wav", numbered_file(*sound));
+            let path = [named, numbered].into_iter()
+                .map(|file| directory.join(file))
+                .find(|path| path.is_file());
+            let Some(path) = path else {
+                continue;
+            };
+
+            match fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| wav::decode(&bytes)) {
+                Ok((sample_rate, samples)) => {
+                    pack.clips[*sound as usize] = wav::resample(&samples, sample_rate, SAMPLE_RATE);
+                    pack.loaded.push(*sound);
+                },
+                Err(e) => println!("{}: {}, using a synthesized sound", path.display(), e),
+            }
+        }
+
+        pack
+    }
+
+    pub fn clip(&self, sound: Sound) -> &[f32] {
+        &self.clips[sound as usize]
+    }
+
+    pub fn loaded(&self) -> &[Sound] {
+        &self.loaded
+    }
+}
+impl Default for SamplePack {
+    fn default() -> Self {
+        Self::synthesized()
+    }
+}
+
+fn numbered_file(sound: Sound) -> usize {
+    // Sample packs number the sounds in the order the board's sound circuits are drawn
+    //  which doesn't quite match the port bits
+    match sound {
+        Sound::Ufo => 0,
+        Sound::Shot => 1,
+        Sound::PlayerDie => 2,
+        Sound::InvaderDie => 3,
+        Sound::FleetMove1 => 4,
+        Sound::FleetMove2 => 5,
+        Sound::FleetMove3 => 6,
+        Sound::FleetMove4 => 7,
+        Sound::UfoHit => 8,
+        Sound::ExtendedPlay => 9,
+    }
+}
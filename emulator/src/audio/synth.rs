@@ -0,0 +1,276 @@
+use std::f32::consts::TAU;
+
+use crate::hardware::sound::{AudioSink, Sound};
+
+use crate::profile::AudioConfig;
+
+use super::{to_pcm, AudioBackend, Channels, SAMPLE_RATE};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Noise,
+    // Sample and hold white noise, picks a new level at the generator's frequency
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Envelope {
+    // Seconds to reach full volume, then seconds to fall to the sustain level
+    //  A sustain of 0 makes a one shot, anything else holds until released
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+impl Envelope {
+    fn length(&self) -> Option<f32> {
+        // How long a one shot lasts, None for sustained sounds
+        (self.sustain == 0.0).then_some(self.attack + self.decay)
+    }
+
+    fn level(&self, time: f32, released: Option<f32>) -> Option<f32> {
+        // Volume at a point in time, None once the sound has finished
+
+        let held: f32 = if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else if self.sustain > 0.0 {
+            self.sustain
+        } else {
+            return None;
+        };
+
+        match released {
+            Some(released) if time - released >= self.release => None,
+            Some(released) => Some(held * (1.0 - (time - released) / self.release)),
+            None => Some(held),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Patch {
+    // Settings for one of the board's sound circuits
+    pub waveform: Waveform,
+    pub frequency: (f32, f32),
+    // Slides from the first to the second frequency over the length of a one shot
+    pub wobble: Option<(f32, f32)>,
+    // Rate and depth in Hz of a sine wave added to the frequency, like the SN76477's low frequency oscillator
+    pub envelope: Envelope,
+    pub volume: f32,
+}
+
+pub fn patch(sound: Sound) -> Patch {
+    // Rough approximations of the SN76477 and discrete circuits on the board
+
+    let one_shot = |attack: f32, decay: f32| Envelope { attack, decay, sustain: 0.0, release: 0.0 };
+    let fleet = |start: f32, end: f32| Patch {
+        waveform: Waveform::Square,
+        frequency: (start, end),
+        wobble: None,
+        envelope: one_shot(0.005, 0.1),
+        volume: 0.35,
+    };
+
+    match sound {
+        Sound::Ufo => Patch {
+            waveform: Waveform::Square,
+            frequency: (700.0, 700.0),
+            wobble: Some((8.0, 200.0)),
+            envelope: Envelope { attack: 0.01, decay: 0.0, sustain: 1.0, release: 0.05 },
+            volume: 0.25,
+        },
+        Sound::Shot => Patch {
+            waveform: Waveform::Square,
+            frequency: (1200.0, 300.0),
+            wobble: None,
+            envelope: one_shot(0.005, 0.15),
+            volume: 0.2,
+        },
+        Sound::PlayerDie => Patch {
+            waveform: Waveform::Noise,
+            frequency: (4000.0, 1500.0),
+            wobble: None,
+            envelope: one_shot(0.005, 1.2),
+            volume: 0.5,
+        },
+        Sound::InvaderDie => Patch {
+            waveform: Waveform::Noise,
+            frequency: (8000.0, 4000.0),
+            wobble: None,
+            envelope: one_shot(0.002, 0.3),
+            volume: 0.4,
+        },
+        Sound::ExtendedPlay => Patch {
+            waveform: Waveform::Square,
+            frequency: (1100.0, 1100.0),
+            wobble: Some((6.0, 220.0)),
+            envelope: one_shot(0.01, 0.5),
+            volume: 0.2,
+        },
+        Sound::FleetMove1 => fleet(110.0, 90.0),
+        Sound::FleetMove2 => fleet(98.0, 80.0),
+        Sound::FleetMove3 => fleet(87.0, 71.0),
+        Sound::FleetMove4 => fleet(82.0, 67.0),
+        Sound::UfoHit => Patch {
+            waveform: Waveform::Square,
+            frequency: (1600.0, 150.0),
+            wobble: None,
+            envelope: one_shot(0.005, 0.8),
+            volume: 0.25,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Voice {
+    patch: Patch,
+    time: f32,
+    phase: f32,
+    noise_state: u32,
+    noise_level: f32,
+    released: Option<f32>,
+}
+impl Voice {
+    fn new(patch: Patch) -> Self {
+        Self {
+            patch,
+            time: 0.0,
+            phase: 0.0,
+            noise_state: 0x1234_5678,
+            // Xorshift seed so the same noise is made every time
+            noise_level: 0.0,
+            released: None,
+        }
+    }
+
+    fn release(&mut self) {
+        self.released.get_or_insert(self.time);
+    }
+
+    fn next(&mut self, seconds_per_sample: f32) -> Option<f32> {
+        // Generates the next sample, None once the envelope has finished
+
+        let level: f32 = self.patch.envelope.level(self.time, self.released)?;
+
+        let (start, end): (f32, f32) = self.patch.frequency;
+        let progress: f32 = self.patch.envelope.length().map_or(0.0, |length| (self.time / length).min(1.0));
+        let wobble: f32 = self.patch.wobble.map_or(0.0, |(rate, depth)| (self.time * rate * TAU).sin() * depth);
+        let frequency: f32 = start + (end - start) * progress + wobble;
+
+        self.phase += frequency * seconds_per_sample;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.noise_state ^= self.noise_state << 13;
+            self.noise_state ^= self.noise_state >> 17;
+            self.noise_state ^= self.noise_state << 5;
+            self.noise_level = self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        }
+        self.time += seconds_per_sample;
+
+        let wave: f32 = match self.patch.waveform {
+            Waveform::Square if self.phase < 0.5 => 1.0,
+            Waveform::Square => -1.0,
+            Waveform::Noise => self.noise_level,
+        };
+
+        Some(wave * level * self.patch.volume)
+    }
+}
+
+pub fn render(sound: Sound) -> Vec<f32> {
+    // Runs a sound's patch ahead of time so it can be played like a sample
+    //  Sustained sounds are rendered for one cycle of their wobble so they loop smoothly
+
+    let patch: Patch = patch(sound);
+    let seconds: f32 = match (patch.envelope.length(), patch.wobble) {
+        (Some(length), _) => length,
+        (None, Some((rate, _))) => 1.0 / rate,
+        (None, None) => 1.0,
+    };
+
+    let mut voice: Voice = Voice::new(patch);
+    if patch.envelope.length().is_none() {
+        voice.patch.envelope.attack = 0.0;
+    }
+    // No attack on loops so there isn't a dip every time they go round
+
+    (0..(seconds * SAMPLE_RATE as f32) as usize)
+        .map_while(|_| voice.next(1.0 / SAMPLE_RATE as f32))
+        .collect()
+}
+
+pub struct Synth {
+    // Generates every sound as it plays instead of using samples
+    voices: [Option<Voice>; Sound::ALL.len()],
+    channels: Channels,
+    sample_rate: u32,
+    pub volume: f32,
+}
+impl Synth {
+    pub fn new() -> Self {
+        Self {
+            voices: [None; Sound::ALL.len()],
+            channels: Channels::default(),
+            sample_rate: SAMPLE_RATE,
+            volume: 1.0,
+        }
+    }
+
+    pub fn is_playing(&self, sound: Sound) -> bool {
+        self.voices[sound as usize].is_some()
+    }
+}
+impl AudioSink for Synth {
+    fn play(&mut self, sound: Sound) {
+        self.voices[sound as usize] = Some(Voice::new(patch(sound)));
+    }
+
+    fn stop(&mut self, sound: Sound) {
+        if sound.is_looping() {
+            if let Some(voice) = self.voices[sound as usize].as_mut() {
+                voice.release();
+            }
+        }
+    }
+}
+impl AudioBackend for Synth {
+    fn mix(&mut self, buffer: &mut [i16]) {
+        self.channels.update(|sound| self.voices[sound as usize].is_some());
+        let seconds_per_sample: f32 = 1.0 / self.sample_rate as f32;
+        for out in buffer.iter_mut() {
+            let mut sample: f32 = 0.0;
+            for (sound, voice) in Sound::ALL.into_iter().zip(self.voices.iter_mut()) {
+                match voice.as_mut().map(|voice| voice.next(seconds_per_sample)) {
+                    Some(Some(next)) => sample += next * self.channels.gain(sound),
+                    Some(None) => *voice = None,
+                    None => {},
+                }
+            }
+
+            *out = to_pcm(sample * self.volume);
+        }
+    }
+
+    fn set_config(&mut self, config: AudioConfig) {
+        self.channels.set_config(config);
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        if sample_rate > 0 {
+            self.sample_rate = sample_rate;
+            self.channels.set_sample_rate(sample_rate);
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+impl Default for Synth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use synth::Synth;
+#[cfg(test)]
+use underrun::Underruns;
+#[cfg(test)]
+use device::Device;
+#[cfg(test)]
+use crate::profile::DeviceConfig;
+#[cfg(test)]
+use crate::i18n::Language;
+#[cfg(test)]
+use crate::profile::Profile;
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[test]
+fn test_synthesized_pack() {
+    let pack: SamplePack = SamplePack::synthesized();
+
+    for sound in Sound::ALL {
+        let clip: &[f32] = pack.clip(sound);
+        assert!(!clip.is_empty(), "{:?} has no audio", sound);
+        assert!(clip.iter().all(|sample| (-1.0..=1.0).contains(sample)));
+        assert!(clip.iter().any(|sample| *sample != 0.0));
+    }
+    assert!(pack.loaded().is_empty());
+}
+
+#[test]
+fn test_load_pack() {
+    let directory: PathBuf = std::env::temp_dir().join(format!("emulator_samples_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("shot.wav"), wav::encode(SAMPLE_RATE / 2, &[0.5; 100])).unwrap();
+    fs::write(directory.join("8.wav"), wav::encode(SAMPLE_RATE, &[0.25; 10])).unwrap();
+    fs::write(directory.join("ufo.wav"), b"not a wav file").unwrap();
+
+    let pack: SamplePack = SamplePack::load(&directory);
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(pack.loaded(), &[Sound::Shot, Sound::UfoHit]);
+    assert_eq!(pack.clip(Sound::Shot).len(), 200);
+    // Resampled to the mixer's rate
+    assert_eq!(pack.clip(Sound::UfoHit).len(), 10);
+    assert_eq!(pack.clip(Sound::Ufo), SamplePack::synthesized().clip(Sound::Ufo));
+    // Broken files fall back to synthesis
+}
+
+#[test]
+fn test_mixer() {
+    let mut mixer: Mixer = Mixer::default();
+    let mut buffer: [i16; 256] = [1; 256];
+
+    mixer.mix(&mut buffer);
+    assert!(buffer.iter().all(|sample| *sample == 0));
+
+    mixer.play(Sound::Shot);
+    mixer.play(Sound::Ufo);
+    mixer.mix(&mut buffer);
+    assert!(buffer.iter().any(|sample| *sample != 0));
+
+    mixer.stop(Sound::Shot);
+    mixer.stop(Sound::Ufo);
+    assert!(mixer.is_playing(Sound::Shot));
+    assert!(!mixer.is_playing(Sound::Ufo));
+    // One shots finish on their own
+
+    let mut long: Vec<i16> = vec![0; SAMPLE_RATE as usize];
+    mixer.mix(&mut long);
+    assert!(!mixer.is_playing(Sound::Shot));
+
+    mixer.play(Sound::Ufo);
+    mixer.mix(&mut long);
+    assert!(mixer.is_playing(Sound::Ufo));
+    // Loops until stopped
+}
+
+#[test]
+fn test_synth() {
+    let mut synth: Synth = Synth::default();
+    let mut buffer: Vec<i16> = vec![0; SAMPLE_RATE as usize / 10];
+
+    synth.play(Sound::Ufo);
+    synth.play(Sound::InvaderDie);
+    synth.mix(&mut buffer);
+    assert!(buffer.iter().any(|sample| *sample != 0));
+
+    synth.stop(Sound::Ufo);
+    synth.stop(Sound::InvaderDie);
+    assert!(synth.is_playing(Sound::Ufo));
+    // Fades out over the release instead of cutting off
+
+    let mut long: Vec<i16> = vec![0; SAMPLE_RATE as usize];
+    synth.mix(&mut long);
+    assert!(!synth.is_playing(Sound::Ufo));
+    assert!(!synth.is_playing(Sound::InvaderDie));
+    assert!(long[long.len() / 2..].iter().all(|sample| *sample == 0));
+}
+
+#[test]
+fn test_render() {
+    let shot: Vec<f32> = synth::render(Sound::Shot);
+    let length: usize = ((0.005 + 0.15) * SAMPLE_RATE as f32) as usize;
+    assert!(shot.len().abs_diff(length) <= 1);
+    // Ends when the envelope does, give or take rounding
+
+    let ufo: Vec<f32> = synth::render(Sound::Ufo);
+    assert_eq!(ufo.len(), SAMPLE_RATE as usize / 8);
+    assert!(ufo[0] != 0.0);
+    // Loops start at full volume
+}
+
+#[test]
+fn test_channels() {
+    let mut channels: Channels = Channels::new(Profile::space_invaders().audio);
+
+    channels.update(|sound| matches!(sound, Sound::Ufo | Sound::FleetMove1));
+    assert_eq!(channels.target(Sound::Ufo), 1.0);
+    assert_eq!(channels.target(Sound::FleetMove1), 1.0);
+
+    channels.update(|sound| matches!(sound, Sound::Ufo | Sound::FleetMove1 | Sound::InvaderDie));
+    assert_eq!(channels.target(Sound::Ufo), 0.35);
+    // UFO ducks under the explosion
+    assert_eq!(channels.target(Sound::FleetMove1), 1.0);
+
+    channels.update(|sound| matches!(sound, Sound::Ufo | Sound::FleetMove1 | Sound::InvaderDie | Sound::Shot | Sound::PlayerDie));
+    assert_eq!(channels.target(Sound::FleetMove1), 0.0);
+    // Only 4 channels so the lowest priority sound drops out
+    assert_eq!(channels.target(Sound::Shot), 1.0);
+
+    let gains: Vec<f32> = (0..1000).map(|_| channels.gain(Sound::Ufo)).collect();
+    assert!(gains[0] < 1.0 && gains[0] > 0.9);
+    assert_eq!(gains[999], 0.35);
+    // Fades down to the ducked volume
+}
+
+#[test]
+fn test_underruns() {
+    let mut underruns: Underruns = Underruns::new(1024, 4096);
+    assert_eq!(underruns.refilled(1, 0, false), None);
+    assert_eq!(underruns.hud(0, Language::English), None);
+
+    assert_eq!(underruns.refilled(2, 10, false), None);
+    // One on its own doesn't grow the buffer
+    assert_eq!(underruns.hud(10, Language::English).unwrap(), "Audio underruns 1, buffer 1024 samples");
+    assert_eq!(underruns.refilled(2, 100, true), None);
+    // Too long after the first
+    assert_eq!(underruns.refilled(3, 120, true), Some(2048));
+    assert_eq!(underruns.hud(121, Language::English).unwrap(), "Audio underruns 3, buffer 2048 samples, frames are running late");
+    assert_eq!(underruns.hud(120 + underrun::SHOW_FRAMES, Language::English), None);
+
+    assert_eq!(underruns.refilled(2, 400, false), None);
+    assert_eq!(underruns.refilled(2, 401, false), Some(4096));
+    assert_eq!(underruns.refilled(2, 402, false), None);
+    assert_eq!(underruns.refilled(2, 403, false), None);
+    // Never past the limit
+    assert_eq!(underruns.buffer_samples(), 4096);
+    assert_eq!(underruns.count(), 7);
+}
+
+#[test]
+fn test_mixer_sample_rate() {
+    let mut mixer: Mixer = Mixer::default();
+    let mut at_base: Vec<i16> = vec![0; SAMPLE_RATE as usize];
+    mixer.play(Sound::Shot);
+    mixer.mix(&mut at_base);
+    let base_length: usize = at_base.iter().rposition(|sample| *sample != 0).unwrap();
+
+    mixer.set_sample_rate(SAMPLE_RATE * 2);
+    assert_eq!(mixer.sample_rate(), SAMPLE_RATE * 2);
+    let mut doubled: Vec<i16> = vec![0; SAMPLE_RATE as usize * 2];
+    mixer.play(Sound::Shot);
+    mixer.mix(&mut doubled);
+    let doubled_length: usize = doubled.iter().rposition(|sample| *sample != 0).unwrap();
+    assert!(doubled_length.abs_diff(base_length * 2) < 4, "{} samples at twice the rate of {}", doubled_length, base_length);
+    // Lasts just as long at the new rate
+
+    mixer.play(Sound::Ufo);
+    mixer.mix(&mut vec![0; 1000]);
+    mixer.set_sample_rate(SAMPLE_RATE);
+    assert!(mixer.is_playing(Sound::Ufo));
+    // Playing sounds carry on through a change
+}
+
+#[test]
+fn test_device() {
+    let config: DeviceConfig = DeviceConfig { sample_rate: 44100, buffer_samples: 1024, latency_ms: 100 };
+    assert_eq!(config.max_buffer_samples(), 4410);
+    assert!(config.problems().is_empty());
+    let mut device: Device = Device::new(config);
+
+    assert_eq!(device.poll(false), None);
+    assert_eq!(device.poll(true), Some((44100, 1024)));
+    assert_eq!(device.poll(true), None);
+    // Opened once the device is there and left alone after
+
+    device.refilled(2, 10, false);
+    assert_eq!(device.refilled(2, 11, false), Some(2048));
+    assert_eq!(device.poll(true), Some((44100, 2048)));
+    // Grown buffers are opened on the next poll
+
+    assert_eq!(device.poll(false), None);
+    assert_eq!(device.poll(true), Some((44100, 2048)));
+    // Unplugged and plugged back in
+
+    device.set_config(DeviceConfig { sample_rate: 48000, ..config });
+    assert_eq!(device.poll(true), Some((48000, 1024)));
+    assert_eq!(device.underruns().count(), 2);
+    // A new rate starts back at the configured buffer and keeps counting
+}
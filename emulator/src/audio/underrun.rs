@@ -0,0 +1,78 @@
+use crate::i18n::{Language, Text};
+
+pub const SHOW_FRAMES: u64 = 180;
+// The hud line stays up for 3 seconds after the last underrun
+pub const GROW_AFTER: usize = 2;
+// Underruns within WINDOW_FRAMES before the buffer is made bigger, one on its own is usually the window being dragged
+pub const WINDOW_FRAMES: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct Underruns {
+    // Notices the audio device running dry and grows the stream's buffer up to a limit
+    //  The stream has two halves, needing to fill both at once means the device played everything it had
+    buffer_samples: usize,
+    max_samples: usize,
+    count: u64,
+    recent: Vec<u64>,
+    // Frames of the underruns since the buffer last grew
+    last: Option<(u64, bool)>,
+    // Frame of the last underrun and whether frames were running late then
+}
+impl Underruns {
+    pub fn new(buffer_samples: usize, max_samples: usize) -> Self {
+        Self {
+            buffer_samples: buffer_samples.max(1),
+            max_samples: max_samples.max(buffer_samples),
+            count: 0,
+            recent: vec![],
+            last: None,
+        }
+    }
+
+    pub fn resize(&mut self, buffer_samples: usize, max_samples: usize) {
+        // Starts growing again from a new buffer size, the count carries on so the hud and soak reports keep adding up
+        self.buffer_samples = buffer_samples.max(1);
+        self.max_samples = max_samples.max(buffer_samples);
+        self.recent.clear();
+    }
+
+    pub fn buffer_samples(&self) -> usize {
+        self.buffer_samples
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn refilled(&mut self, buffers: usize, frame: u64, late: bool) -> Option<usize> {
+        // Called every frame with how many buffers the stream took and whether the frame pacer woke up late
+        //  Returns the new buffer size when the stream should be made again with a bigger buffer
+
+        if buffers < 2 {
+            return None;
+        }
+        self.count += 1;
+        self.last = Some((frame, late));
+        self.recent.retain(|underrun| frame - underrun < WINDOW_FRAMES);
+        self.recent.push(frame);
+
+        if self.recent.len() < GROW_AFTER || self.buffer_samples >= self.max_samples {
+            return None;
+        }
+        self.recent.clear();
+        self.buffer_samples = (self.buffer_samples * 2).min(self.max_samples);
+        Some(self.buffer_samples)
+    }
+
+    pub fn hud(&self, frame: u64, language: Language) -> Option<String> {
+        // e.g. "Audio underruns 3, buffer 2048 samples, frames are running late"
+
+        let (_, late) = self.last.filter(|(last, _)| frame - last < SHOW_FRAMES)?;
+        let mut line: String = format!("{} {}, {} {} {}", language.text(Text::AudioUnderruns), self.count,
+            language.text(Text::AudioBuffer), self.buffer_samples, language.text(Text::Samples));
+        if late {
+            line.push_str(&format!(", {}", language.text(Text::FramesLate)));
+        }
+        Some(line)
+    }
+}
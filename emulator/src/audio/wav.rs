@@ -0,0 +1,99 @@
+mod tests;
+
+pub fn decode(bytes: &[u8]) -> Result<(u32, Vec<f32>), String> {
+    // Reads an uncompressed 8 or 16 bit PCM .wav file
+    //  Returns the sample rate and the samples mixed down to mono between -1 and 1
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(String::from("Not a .wav file"));
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    // Audio format, channels, sample rate, bits per sample
+    let mut offset: usize = 12;
+    while offset + 8 <= bytes.len() {
+        let id: &[u8] = &bytes[offset..offset + 4];
+        let size: usize = u32::from_le_bytes([bytes[offset + 4], bytes[offset + 5], bytes[offset + 6], bytes[offset + 7]]) as usize;
+        let body: &[u8] = bytes.get(offset + 8..offset + 8 + size).unwrap_or(&bytes[offset + 8..]);
+        // Some files have a data chunk that claims to be longer than the file
+
+        match id {
+            b"fmt " if body.len() >= 16 => format = Some((
+                u16::from_le_bytes([body[0], body[1]]),
+                u16::from_le_bytes([body[2], body[3]]),
+                u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                u16::from_le_bytes([body[14], body[15]]),
+            )),
+            b"data" => {
+                let (audio_format, channels, sample_rate, bits) = format.ok_or("Data chunk comes before the format chunk")?;
+                if audio_format != 1 {
+                    return Err(format!("Only uncompressed PCM is supported, this file uses format {}", audio_format));
+                }
+                if channels == 0 {
+                    return Err(String::from("File has no channels"));
+                }
+
+                let samples: Vec<f32> = match bits {
+                    8 => body.iter().map(|sample| (*sample as f32 - 128.0) / 128.0).collect(),
+                    16 => body.chunks_exact(2).map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0).collect(),
+                    _ => return Err(format!("{} bit samples aren't supported", bits)),
+                };
+                let mono: Vec<f32> = samples.chunks_exact(channels as usize)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                    .collect();
+
+                return Ok((sample_rate, mono));
+            },
+            _ => {},
+        }
+
+        offset += 8 + size + size % 2;
+        // Chunks are padded to an even length
+    }
+
+    Err(String::from("No data chunk"))
+}
+
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    // Linear interpolation is plenty for the low sample rates of arcade sound effects
+
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let length: usize = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    let step: f64 = from as f64 / to as f64;
+    (0..length)
+        .map(|i| {
+            let position: f64 = i as f64 * step;
+            let index: usize = position as usize;
+            let fraction: f32 = (position - index as f64) as f32;
+            let next: f32 = samples.get(index + 1).copied().unwrap_or(samples[index]);
+            samples[index] + (next - samples[index]) * fraction
+        })
+        .collect()
+}
+
+pub fn encode(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    // Writes mono 16 bit PCM, used to save synthesized sounds so they can be edited
+
+    let data_size: u32 = samples.len() as u32 * 2;
+    let mut bytes: Vec<u8> = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend(b"RIFF");
+    bytes.extend((36 + data_size).to_le_bytes());
+    bytes.extend(b"WAVEfmt ");
+    bytes.extend(16_u32.to_le_bytes());
+    bytes.extend(1_u16.to_le_bytes());
+    bytes.extend(1_u16.to_le_bytes());
+    bytes.extend(sample_rate.to_le_bytes());
+    bytes.extend((sample_rate * 2).to_le_bytes());
+    bytes.extend(2_u16.to_le_bytes());
+    bytes.extend(16_u16.to_le_bytes());
+    bytes.extend(b"data");
+    bytes.extend(data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend(((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+
+    bytes
+}
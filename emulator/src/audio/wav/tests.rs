@@ -0,0 +1,45 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_round_trip() {
+    let samples: Vec<f32> = vec![0.0, 0.5, -0.5, 1.0, -1.0];
+    let (sample_rate, decoded): (u32, Vec<f32>) = decode(&encode(11025, &samples)).unwrap();
+
+    assert_eq!(sample_rate, 11025);
+    assert_eq!(decoded.len(), samples.len());
+    for (decoded, original) in decoded.iter().zip(samples.iter()) {
+        assert!((decoded - original).abs() < 0.001);
+    }
+}
+
+#[test]
+fn test_stereo_8_bit() {
+    let mut bytes: Vec<u8> = encode(8000, &[]);
+    bytes[22] = 2;
+    // Channels
+    bytes[34] = 8;
+    // Bits per sample
+    bytes[40] = 4;
+    // Data size
+    bytes.extend([0xff, 0x01, 0x80, 0x80]);
+
+    let (_, decoded): (u32, Vec<f32>) = decode(&bytes).unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert!(decoded[0].abs() < 0.01);
+    // Left and right cancel out
+    assert_eq!(decoded[1], 0.0);
+
+    assert!(decode(b"RIFF0000AVI ").is_err());
+}
+
+#[test]
+fn test_resample() {
+    let samples: Vec<f32> = vec![0.0, 1.0, 0.0, -1.0];
+    let doubled: Vec<f32> = resample(&samples, 11025, 22050);
+
+    assert_eq!(doubled.len(), 8);
+    assert_eq!(doubled[1], 0.5);
+    assert_eq!(doubled[2], 1.0);
+    assert_eq!(resample(&samples, 22050, 11025).len(), 2);
+}
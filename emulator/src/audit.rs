@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use disassembler::describe;
+use disassembler::instructions;
+use disassembler::Operation;
+
+use crate::cpu::{dispatcher, Cpu, Registers};
+
+mod tests;
+
+const BRANCHES: [&str; 29] = [
+    "JMP", "JNZ", "JZ", "JNC", "JC", "JPO", "JPE", "JP", "JM",
+    "CALL", "CNZ", "CZ", "CNC", "CC", "CPO", "CPE", "CP", "CM",
+    "RET", "RNZ", "RZ", "RNC", "RC", "RPO", "RPE", "RP", "RM",
+    "RST", "PCHL",
+];
+// Mnemonics that can leave the pc somewhere other than the next instruction, their length can't be checked from the pc
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Check {
+    Length,
+    // The pc moved a different number of bytes than the op code table says
+    Decode,
+    // The disassembler decoded the same bytes into a different op code or length
+    Cycles,
+    // The instruction took a different number of cycles than CLOCK_CYCLES gives
+    Described,
+    // The cycles aren't any of those the disassembler's describe lists
+}
+impl Check {
+    pub const ALL: [Check; 4] = [Check::Length, Check::Decode, Check::Cycles, Check::Described];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::Length => "length",
+            Check::Decode => "decode",
+            Check::Cycles => "cycles",
+            Check::Described => "described",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    // The first time an instruction at an address disagreed with one of the tables, and how often since
+    pub check: Check,
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub instruction: String,
+    // As the disassembler decoded it
+    pub detail: String,
+    pub registers: Registers,
+    // Before the instruction ran
+    pub cycle: u64,
+    pub count: u64,
+}
+impl Mismatch {
+    pub fn line(&self) -> String {
+        // e.g. "cycles at 0x1a32 (c4 10 20 CNZ $2010): CLOCK_CYCLES gives 11, took 17 - A=00 ... at cycle 2891, seen 3 times"
+        let bytes: Vec<String> = self.bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{} at 0x{:04x} ({} {}): {} - {} at cycle {}, seen {} {}",
+            self.check.name(), self.address, bytes.join(" "), self.instruction, self.detail, self.registers, self.cycle,
+            self.count, if self.count == 1 { "time" } else { "times" })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Audit {
+    // Cross checks every instruction the machine runs against the op code table, CLOCK_CYCLES
+    //  and the disassembler, so the modules drifting apart shows up while a rom runs
+    //  Each check is reported once an address, later disagreements only count
+    described: Vec<Option<(u8, String)>>,
+    // Bytes and cycles describe gives each op code, looked up once since lookup parses the table every call
+    branches: [bool; 0x100],
+    mismatches: BTreeMap<(u16, Check), Mismatch>,
+    reported: Vec<(u16, Check)>,
+    // Found since take_new was last called, in the order they were found
+    instructions: u64,
+}
+impl Audit {
+    pub fn new() -> Self {
+        let mut branches: [bool; 0x100] = [false; 0x100];
+        for instruction in instructions::table() {
+            branches[instruction.op_code as usize] = BRANCHES.contains(&instruction.mnemonic.as_str());
+        }
+
+        Self {
+            described: (0..=0xff)
+                .map(|op_code: u8| describe::lookup(&format!("0x{:02x}", op_code)).pop())
+                .map(|description| description.map(|description| (description.op_bytes, description.cycles)))
+                .collect(),
+            branches,
+            mismatches: BTreeMap::new(),
+            reported: vec![],
+            instructions: 0,
+        }
+    }
+
+    pub fn executed(&mut self, before: &Registers, bytes: [u8; 3], after: &Cpu, cycles: u64, cycle: u64) {
+        // Called after every instruction that ran with the registers and the 3 bytes at the pc from before it
+        //  cycle is the machine's count when it started
+
+        self.instructions += 1;
+        let op_code: u8 = bytes[0];
+        let length: u8 = dispatcher::length(op_code);
+        let decoded: Option<Operation> = disassembler::decode_from(&bytes, before.pc).into_iter().next();
+        let instruction: String = decoded.as_ref().map_or_else(|| String::from("?"), |op| op.instruction().to_string());
+        let mut found: Vec<(Check, String)> = vec![];
+
+        let moved: u16 = after.pc.address.wrapping_sub(before.pc);
+        if moved != length as u16 && !self.branches[op_code as usize] {
+            found.push((Check::Length, format!("the table says {} bytes, the pc moved {}", length, moved)));
+        }
+
+        match &decoded {
+            Some(op) if op.op_code() == op_code && op.op_bytes() == length => {},
+            Some(op) => found.push((Check::Decode, format!("the table says 0x{:02x} is {} bytes, decoded as 0x{:02x} of {} bytes", op_code, length, op.op_code(), op.op_bytes()))),
+            None => found.push((Check::Decode, String::from("the disassembler decoded nothing"))),
+        }
+
+        let taken: bool = !dispatcher::is_conditional_call_or_return(op_code) || after.registers().sp != before.sp;
+        let expected: u64 = dispatcher::CLOCK_CYCLES[op_code as usize] as u64 - if taken { 0 } else { dispatcher::NOT_TAKEN as u64 };
+        if cycles != expected {
+            found.push((Check::Cycles, format!("CLOCK_CYCLES gives {}, took {}", expected, cycles)));
+        }
+
+        match &self.described[op_code as usize] {
+            Some((op_bytes, _)) if *op_bytes != length => {
+                found.push((Check::Described, format!("describe says {} bytes, the table says {}", op_bytes, length)));
+            },
+            Some((_, listed)) if !listed.split('/').any(|listed| listed.trim().parse() == Ok(cycles)) => {
+                found.push((Check::Described, format!("describe lists {} cycles, took {}", listed, cycles)));
+            },
+            Some(_) => {},
+            None => found.push((Check::Described, String::from("describe has no entry"))),
+        }
+
+        for (check, detail) in found {
+            self.mismatches.entry((before.pc, check))
+                .and_modify(|mismatch| mismatch.count += 1)
+                .or_insert_with(|| {
+                    self.reported.push((before.pc, check));
+                    Mismatch {
+                        check,
+                        address: before.pc,
+                        bytes: bytes[..(length as usize).clamp(1, 3)].to_vec(),
+                        instruction: instruction.clone(),
+                        detail,
+                        registers: *before,
+                        cycle,
+                        count: 1,
+                    }
+                });
+        }
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn mismatches(&self) -> impl Iterator<Item = &Mismatch> {
+        // Ordered by address
+        self.mismatches.values()
+    }
+
+    pub fn take_new(&mut self) -> Vec<String> {
+        // A line for each mismatch found since the last call, for logging as the machine runs
+        std::mem::take(&mut self.reported).iter()
+            .filter_map(|key| self.mismatches.get(key))
+            .map(Mismatch::line)
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        let counts: Vec<String> = Check::ALL.iter()
+            .map(|check| (check, self.mismatches.keys().filter(|(_, found)| found == check).count()))
+            .filter(|(_, count)| *count > 0)
+            .map(|(check, count)| format!("{} {}", count, check.name()))
+            .collect();
+        match counts.is_empty() {
+            true => format!("Audited {} instructions, everything agreed", self.instructions),
+            false => format!("Audited {} instructions, {} mismatches: {}", self.instructions, self.mismatches.len(), counts.join(", ")),
+        }
+    }
+}
+impl Default for Audit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[test]
+fn test_audit_agrees() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x31, 0x00, 0x24, // 0x0001 LXI SP,0x2400
+        0xaf,             // 0x0004 XRA A
+        0xc4, 0x10, 0x00, // 0x0005 CNZ 0x0010
+        0xcc, 0x10, 0x00, // 0x0008 CZ 0x0010
+        0xc3, 0x04, 0x00, // 0x000b JMP 0x0004
+        0x00, 0x00,       // 0x000e NOP NOP
+        0xc0,             // 0x0010 RNZ
+        0xc8,             // 0x0011 RZ
+    ]).unwrap();
+    machine.set_audit(true);
+    machine.run_frame().unwrap();
+    // Conditional calls and returns both taken and not
+
+    let audit: &mut Audit = machine.audit_mut().unwrap();
+    assert!(audit.instructions() > 1000);
+    assert_eq!(audit.mismatches().count(), 0);
+    assert!(audit.take_new().is_empty());
+    assert!(audit.summary().ends_with("everything agreed"));
+}
+
+#[test]
+fn test_audit_mismatch() {
+    let mut before: Registers = Cpu::init().registers();
+    before.pc = 0x0100;
+    let mut after: Cpu = Cpu::init();
+    after.pc.address = 0x0103;
+    let mut audit: Audit = Audit::new();
+
+    audit.executed(&before, [0x3e, 0x12, 0x00], &after, 9, 500);
+    // MVI A,0x12 is 2 bytes and 7 cycles
+    let lines: Vec<String> = audit.take_new();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("length at 0x0100 (3e 12 MVI A,D8): the table says 2 bytes, the pc moved 3 - A=00"));
+    assert!(lines[0].ends_with("at cycle 500, seen 1 time"));
+    assert!(lines[1].starts_with("cycles at 0x0100 (3e 12 MVI A,D8): CLOCK_CYCLES gives 7, took 9"));
+    assert!(lines[2].starts_with("described at 0x0100"));
+
+    audit.executed(&before, [0x3e, 0x12, 0x00], &after, 9, 600);
+    assert!(audit.take_new().is_empty());
+    // Already reported, only counted
+    let mismatch: &Mismatch = audit.mismatches().next().unwrap();
+    assert_eq!((mismatch.check, mismatch.count, mismatch.cycle), (Check::Length, 2, 500));
+    assert_eq!(audit.summary(), "Audited 2 instructions, 3 mismatches: 1 length, 1 cycles, 1 described");
+
+    after.pc.address = 0x1234;
+    audit.executed(&before, [0xc3, 0x34, 0x12], &after, 10, 700);
+    assert!(audit.take_new().is_empty());
+    // A jump can leave the pc anywhere
+}
@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+
+use crate::cpu::{dispatcher, Cpu, Mirror, Registers};
+use crate::i18n::{Language, Text};
+use crate::profile::{Profile, Region, RegionKind};
+
+mod tests;
+
+pub const HISTORY: usize = 120;
+// Half frames kept for the plot, a second at 60 frames a second
+const LEVELS: &[u8] = b" .:-=+*#%@";
+// Plot characters from nothing to the busiest half frame, the default font has no block characters
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub fetches: u32,
+    // Instruction bytes, op codes and operands
+    pub reads: u32,
+    pub writes: u32,
+}
+impl Counts {
+    pub fn new() -> Self {
+        Self {
+            fetches: 0,
+            reads: 0,
+            writes: 0,
+        }
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.fetches += other.fetches;
+        self.reads += other.reads;
+        self.writes += other.writes;
+    }
+}
+impl Default for Counts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bandwidth {
+    // Memory accesses in each of the profile's regions, counted a half frame at a time so the plot shows
+    //  which half of the screen the game draws while the beam is in the other
+    //  Idle loops the machine skips over aren't counted
+    regions: Vec<Region>,
+    mirror: Option<Mirror>,
+    current: Vec<Counts>,
+    // One for each region and a last one for addresses outside all of them
+    history: VecDeque<Vec<Counts>>,
+    // Finished half frames, oldest first
+}
+impl Bandwidth {
+    pub fn new(profile: &Profile) -> Self {
+        Self {
+            regions: profile.memory.clone(),
+            mirror: profile.mirror,
+            current: vec![Counts::new(); profile.memory.len() + 1],
+            history: VecDeque::with_capacity(HISTORY),
+        }
+    }
+
+    fn index(&self, address: u16) -> usize {
+        let address: u16 = self.mirror.map_or(address, |mirror| mirror.resolve(address));
+        self.regions.iter()
+            .position(|region| region.contains(address))
+            .unwrap_or(self.regions.len())
+    }
+
+    pub fn fetched(&mut self, address: u16, length: u8) {
+        for offset in 0..length as u16 {
+            let index: usize = self.index(address.wrapping_add(offset));
+            self.current[index].fetches += 1;
+        }
+    }
+
+    pub fn read(&mut self, address: u16) {
+        let index: usize = self.index(address);
+        self.current[index].reads += 1;
+    }
+
+    pub fn written(&mut self, addresses: &[u16]) {
+        for address in addresses {
+            let index: usize = self.index(*address);
+            self.current[index].writes += 1;
+        }
+    }
+
+    pub fn executed(&mut self, before: &Registers, op_code: u8, cpu: &Cpu) {
+        // Counts the fetch and data reads of the instruction that just ran from the registers it started with
+        //  Writes come from the memory's write log instead
+
+        self.fetched(before.pc, dispatcher::length(op_code));
+        let hl: u16 = (before.h as u16) << 8 | before.l as u16;
+        let address: u16 = (cpu.memory.read_at(before.pc.wrapping_add(2)) as u16) << 8 | cpu.memory.read_at(before.pc.wrapping_add(1)) as u16;
+        let popped: bool = cpu.registers().sp == before.sp.wrapping_add(2);
+        // Conditional returns only read the stack when they're taken
+        match op_code {
+            0x46 | 0x4e | 0x56 | 0x5e | 0x66 | 0x6e | 0x7e | 0x86 | 0x8e | 0x96 | 0x9e | 0xa6 | 0xae | 0xb6 | 0xbe | 0x34 | 0x35 => self.read(hl),
+            // MOV r,M, arithmetic with M, INR M and DCR M
+            0x0a => self.read((before.b as u16) << 8 | before.c as u16),
+            0x1a => self.read((before.d as u16) << 8 | before.e as u16),
+            0x3a => self.read(address),
+            0x2a => {
+                self.read(address);
+                self.read(address.wrapping_add(1));
+            },
+            // LHLD
+            0xe3 => {
+                self.read(before.sp);
+                self.read(before.sp.wrapping_add(1));
+            },
+            // XTHL
+            0xc0 | 0xc1 | 0xc8 | 0xc9 | 0xd0 | 0xd1 | 0xd8 | 0xd9 | 0xe0 | 0xe1 | 0xe8 | 0xf0 | 0xf1 | 0xf8 if popped => {
+                self.read(before.sp);
+                self.read(before.sp.wrapping_add(1));
+            },
+            // POP and RET
+            _ => {},
+        }
+    }
+
+    pub fn end_half(&mut self) {
+        // Called at each of the frame's interrupts
+
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        let finished: Vec<Counts> = std::mem::replace(&mut self.current, vec![Counts::new(); self.regions.len() + 1]);
+        self.history.push_back(finished);
+    }
+
+    pub fn clear(&mut self) {
+        self.current.fill(Counts::new());
+        self.history.clear();
+    }
+
+    pub fn last_frame(&self) -> Vec<Counts> {
+        // Each region's counts over the last two half frames
+        let mut totals: Vec<Counts> = vec![Counts::new(); self.regions.len() + 1];
+        for half in self.history.iter().rev().take(2) {
+            for (total, counts) in totals.iter_mut().zip(half) {
+                total.add(counts);
+            }
+        }
+        totals
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &[Counts]> + '_ {
+        self.history.iter().map(Vec::as_slice)
+    }
+
+    fn plotted(kind: Option<RegionKind>, counts: &Counts) -> u32 {
+        // What each kind of region is interesting for, fetches from rom and writes to the screen
+        match kind {
+            Some(RegionKind::Rom) => counts.fetches,
+            Some(RegionKind::Vram) => counts.writes,
+            _ => counts.reads + counts.writes,
+        }
+    }
+
+    pub fn lines(&self, language: Language) -> Vec<String> {
+        // A line of counts for the last frame and a plot of the last HISTORY half frames for each region
+        //  e.g. "VRAM 0x2400-0x3fff fetched 0 read 12 written 1570"
+        //       "|::-=@.:-=@ ...|"
+
+        let mut lines: Vec<String> = vec![language.text(Text::MemoryBandwidth).to_string()];
+        let totals: Vec<Counts> = self.last_frame();
+        let names = self.regions.iter().map(|region| (Some(region.kind), format!("0x{:04x}-0x{:04x}", region.start, region.end.saturating_sub(1))))
+            .chain([(None, String::from("-"))]);
+        for (index, ((kind, range), total)) in names.zip(&totals).enumerate() {
+            if kind.is_none() && *total == Counts::new() {
+                continue;
+            }
+            // Nothing outside the profile's regions is the usual case
+            let name: &str = match kind {
+                Some(RegionKind::Rom) => "ROM",
+                Some(RegionKind::Ram) => "RAM",
+                Some(RegionKind::Vram) => "VRAM",
+                None => language.text(Text::Unmapped),
+            };
+            lines.push(format!("{} {} {} {} {} {} {} {}", name, range,
+                language.text(Text::Fetched), total.fetches, language.text(Text::Read), total.reads, language.text(Text::Written), total.writes));
+
+            let values: Vec<u32> = self.history.iter().map(|half| Self::plotted(kind, &half[index])).collect();
+            let max: u32 = values.iter().copied().max().unwrap_or(0).max(1);
+            let plot: String = values.iter()
+                .map(|value| LEVELS[(*value as usize * (LEVELS.len() - 1)).div_ceil(max as usize)] as char)
+                .collect();
+            lines.push(format!("|{:<width$}|", plot, width = HISTORY));
+        }
+        lines
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[test]
+fn test_bandwidth() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x31, 0x00, 0x24, // 0x0001 LXI SP,0x2400
+        0x21, 0x00, 0x24, // 0x0004 LXI H,0x2400
+        0x36, 0xff,       // 0x0007 MVI M,0xff
+        0x7e,             // 0x0009 MOV A,M
+        0xc5,             // 0x000a PUSH B
+        0xc1,             // 0x000b POP B
+        0x32, 0x00, 0x40, // 0x000c STA 0x4000
+        0x76,             // 0x000f HLT
+    ]).unwrap();
+    machine.set_bandwidth(true);
+    machine.run_half_frame().unwrap();
+    machine.run_half_frame().unwrap();
+    // Nothing more is counted once it halts
+
+    let bandwidth: &Bandwidth = machine.bandwidth().unwrap();
+    assert_eq!(bandwidth.history().count(), 2);
+    assert_eq!(bandwidth.last_frame(), vec![
+        Counts { fetches: 16, reads: 0, writes: 0 },
+        Counts { fetches: 0, reads: 2, writes: 3 },
+        // The stack and the write through the mirror
+        Counts { fetches: 0, reads: 1, writes: 1 },
+        Counts::new(),
+    ]);
+
+    let lines: Vec<String> = bandwidth.lines(Language::English);
+    assert_eq!(lines.len(), 7);
+    // Nothing landed outside the profile's regions so there's no line for it
+    assert_eq!(lines[1], "ROM 0x0000-0x1fff fetched 16 read 0 written 0");
+    assert_eq!(lines[2], format!("|@ {}|", " ".repeat(HISTORY - 2)));
+    assert_eq!(lines[5], "VRAM 0x2400-0x3fff fetched 0 read 1 written 1");
+
+    machine.power_cycle(&[0x00]).unwrap();
+    assert_eq!(machine.bandwidth().unwrap().history().count(), 0);
+}
+
+#[test]
+fn test_conditional_return() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x31, 0x00, 0x24, // 0x0001 LXI SP,0x2400
+        0xcd, 0x0a, 0x00, // 0x0004 CALL 0x000a
+        0xc3, 0x07, 0x00, // 0x0007 JMP 0x0007
+        0xc8,             // 0x000a RZ
+        0xc0,             // 0x000b RNZ
+    ]).unwrap();
+    machine.set_bandwidth(true);
+    for _ in 0..5 {
+        machine.step_frame().unwrap();
+    }
+    machine.run_half_frame().unwrap();
+    let ram: Counts = machine.bandwidth().unwrap().last_frame()[1];
+    assert_eq!((ram.reads, ram.writes), (2, 2));
+    // Only the return that was taken read the stack
+}
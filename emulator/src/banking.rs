@@ -0,0 +1,130 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use crate::cpu::Memory;
+use crate::hardware::devices::IoDevice;
+
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct BankConfig {
+    // A window of memory that shows one of several banks, picked by writing the bank number to a port
+    pub start: u16,
+    pub size: u16,
+    pub count: u8,
+    pub port: u8,
+    // Bank numbers past count wrap around like unused select lines would
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rom_offset: Option<usize>,
+    // Where bank 0 starts in the rom file for rom banks, ram banks start out cleared
+}
+impl BankConfig {
+    pub fn end(&self) -> usize {
+        self.start as usize + self.size as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Banks {
+    config: BankConfig,
+    storage: Vec<u8>,
+    // Every bank one after the other, the selected bank's copy is out of date while it is in the window
+    selected: u8,
+}
+impl Banks {
+    pub fn new(config: BankConfig) -> Self {
+        Self {
+            config,
+            storage: vec![0; config.size as usize * config.count as usize],
+            selected: 0,
+        }
+    }
+
+    pub fn config(&self) -> &BankConfig {
+        &self.config
+    }
+
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    fn bank(&self, bank: u8) -> std::ops::Range<usize> {
+        let size: usize = self.config.size as usize;
+        bank as usize * size..(bank as usize + 1) * size
+    }
+
+    pub fn load(&mut self, rom: &[u8], memory: &mut Memory) {
+        // Fills rom banks from the rom file and shows bank 0
+        //  Called whenever the rom is loaded or the machine is reset
+
+        self.storage.fill(0);
+        if let Some(offset) = self.config.rom_offset {
+            let banked: &[u8] = rom.get(offset..).unwrap_or(&[]);
+            let length: usize = banked.len().min(self.storage.len());
+            self.storage[..length].copy_from_slice(&banked[..length]);
+        }
+
+        self.selected = 0;
+        self.copy_in(memory);
+    }
+
+    pub fn select(&mut self, value: u8, memory: &mut Memory) {
+        // Swaps the window over to another bank, what was written to the old bank is kept
+
+        let bank: u8 = value % self.config.count.max(1);
+        if bank == self.selected {
+            return;
+        }
+
+        let old: std::ops::Range<usize> = self.bank(self.selected);
+        for (offset, byte) in self.storage[old].iter_mut().enumerate() {
+            *byte = memory.read_at(self.config.start.wrapping_add(offset as u16));
+        }
+        self.selected = bank;
+        self.copy_in(memory);
+    }
+
+    fn copy_in(&self, memory: &mut Memory) {
+        for (offset, byte) in self.storage[self.bank(self.selected)].iter().enumerate() {
+            memory.poke(self.config.start.wrapping_add(offset as u16), *byte);
+        }
+        // Poked so rom banks can be swapped into read only windows
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BankSelect {
+    // Claims a bank's select port, the machine does the switch when it sees the write
+    name: String,
+    value: u8,
+}
+impl BankSelect {
+    pub fn new(port: u8) -> Self {
+        Self {
+            name: format!("bank_select_{}", port),
+            value: 0,
+        }
+    }
+}
+impl IoDevice for BankSelect {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn read(&mut self, _number: u8) -> u8 {
+        self.value
+    }
+
+    fn write(&mut self, _number: u8, value: u8) {
+        self.value = value;
+    }
+
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+
+    fn clone_device(&self) -> Box<dyn IoDevice> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+const RAM_BANKS: BankConfig = BankConfig { start: 0x2100, size: 0x10, count: 3, port: 7, rom_offset: None };
+
+#[test]
+fn test_ram_banks() {
+    let mut memory: Memory = Memory::init();
+    let mut banks: Banks = Banks::new(RAM_BANKS);
+    banks.load(&[], &mut memory);
+
+    memory.write_at(0x2100, 0x11);
+    banks.select(1, &mut memory);
+    assert_eq!(memory.read_at(0x2100), 0x00);
+    memory.write_at(0x210f, 0x22);
+
+    banks.select(0, &mut memory);
+    assert_eq!(memory.read_at(0x2100), 0x11);
+    assert_eq!(memory.read_at(0x210f), 0x00);
+    banks.select(4, &mut memory);
+    assert_eq!(banks.selected(), 1);
+    assert_eq!(memory.read_at(0x210f), 0x22);
+    // Each bank keeps its own writes and bank numbers wrap around the count
+
+    banks.load(&[], &mut memory);
+    assert_eq!(banks.selected(), 0);
+    assert_eq!(memory.read_at(0x2100), 0x00);
+}
+
+#[test]
+fn test_rom_banks() {
+    let config: BankConfig = BankConfig { start: 0x1000, size: 0x04, count: 2, port: 8, rom_offset: Some(2) };
+    let mut memory: Memory = Memory::init();
+    let mut banks: Banks = Banks::new(config);
+    banks.load(&[0xff, 0xff, 1, 2, 3, 4, 5, 6], &mut memory);
+
+    assert_eq!(memory.view(0x1000..0x1004), &[1, 2, 3, 4]);
+    banks.select(1, &mut memory);
+    assert_eq!(memory.view(0x1000..0x1004), &[5, 6, 0, 0]);
+    // The rom file ran out part way through the last bank
+}
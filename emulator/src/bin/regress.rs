@@ -0,0 +1,44 @@
+// Runs every scenario in a manifest headless and prints a table of which still match their expected hashes
+//  regress [--threads <n>] [--record-ports] <manifest.toml>
+// Meant to be run before and after changes to the cpu, anything that changes how a game plays shows up here
+//  --record-ports writes each scenario's ports file from this build, run it before changing the hardware
+
+use std::env;
+use std::path::Path;
+use std::thread;
+
+use emulator::regress::{self, Manifest, Outcome};
+
+fn main() -> Result<(), u8> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!("Usage: regress [--threads <n>] [--record-ports] <manifest>");
+        return Err(1);
+    }
+
+    let threads: usize = args.windows(2)
+        .find(|pair| pair[0] == "--threads")
+        .and_then(|pair| pair[1].parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+    // One per core unless told otherwise
+
+    let record: bool = args.iter().any(|arg| arg == "--record-ports");
+
+    let manifest_path: &Path = Path::new(&args[args.len() - 1]);
+    let manifest: Manifest = match Manifest::load(manifest_path) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            println!("{}", e);
+            return Err(1);
+        },
+    };
+    let base: &Path = manifest_path.parent().unwrap_or(Path::new("."));
+
+    let outcomes: Vec<Outcome> = regress::run_all(&manifest.scenarios, base, threads, record);
+    println!("{}", regress::table(&outcomes));
+
+    match outcomes.iter().all(Outcome::passed) {
+        true => Ok(()),
+        false => Err(1),
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+mod tests;
+
+pub const COVERAGE_BYTES: usize = 0x10000 / 8;
+// One bit for every address, address n is bit n % 8 of byte n / 8
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coverage {
+    // Every address an instruction was fetched from
+    //  Operand bytes aren't marked, only the op code
+    bits: [u8; COVERAGE_BYTES],
+}
+impl Coverage {
+    pub fn init() -> Self {
+        Self {
+            bits: [0; COVERAGE_BYTES],
+        }
+    }
+
+    pub fn mark(&mut self, address: u16) {
+        self.bits[address as usize / 8] |= 1 << (address % 8);
+    }
+
+    pub fn is_executed(&self, address: u16) -> bool {
+        self.bits[address as usize / 8] & (1 << (address % 8)) != 0
+    }
+
+    pub fn count(&self, range: std::ops::Range<u16>) -> usize {
+        // Number of addresses in the range that were executed
+        range.filter(|address| self.is_executed(*address)).count()
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        // Combines coverage from another session into this one
+        for (bits, other_bits) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *bits |= other_bits;
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() != COVERAGE_BYTES {
+            return Err("Coverage map must be exactly 8192 bytes");
+        }
+
+        let mut coverage: Coverage = Coverage::init();
+        coverage.bits.copy_from_slice(bytes);
+        Ok(coverage)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        // Merges into the map already at path if there is one so multiple sessions add up
+
+        let mut merged: Coverage = *self;
+        if path.exists() {
+            let existing: Coverage = Coverage::from_bytes(&fs::read(path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            merged.merge(&existing);
+        }
+
+        fs::write(path, merged.as_bytes())
+    }
+}
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::init()
+    }
+}
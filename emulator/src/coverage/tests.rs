@@ -0,0 +1,25 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_coverage() {
+    let mut coverage: Coverage = Coverage::init();
+    coverage.mark(0x0000);
+    coverage.mark(0x0009);
+    coverage.mark(0xffff);
+
+    assert!(coverage.is_executed(0x0009));
+    assert!(!coverage.is_executed(0x0008));
+    assert_eq!(coverage.count(0x0000..0x2000), 2);
+    assert_eq!(coverage.as_bytes()[1], 0b0000_0010);
+    assert_eq!(coverage.as_bytes()[COVERAGE_BYTES - 1], 0b1000_0000);
+
+    let mut other: Coverage = Coverage::init();
+    other.mark(0x0001);
+    other.mark(0x0009);
+    coverage.merge(&other);
+    assert_eq!(coverage.count(0x0000..0x2000), 3);
+
+    assert_eq!(Coverage::from_bytes(coverage.as_bytes()), Ok(coverage));
+    assert!(Coverage::from_bytes(&[0; 16]).is_err());
+}
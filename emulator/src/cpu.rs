@@ -1,10 +1,25 @@
+use std::fmt;
+use std::ops::{BitOr, Range};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::LoadError;
+
 use self::dispatcher::handle_op_code;
 
+pub use self::delta::StateDelta;
+
 mod tests;
+pub mod analysis;
+pub mod delta;
 pub mod dispatcher;
+pub mod exerciser;
+#[cfg(feature = "serde")]
+pub mod vectors;
 
-const STACK_MIN: u16 = 0x2001;
-// This should be where the minimum stack address is
+const ROM_END: u32 = 0x2000;
+// Roms loaded without a profile have to fit below this
 
 const S_FLAG_BIT: u8 = 7;
 const Z_FLAG_BIT: u8 = 6;
@@ -45,43 +60,214 @@ impl AddressPointer {
     }
 }
 
-#[derive(Clone, Copy)]
+pub const PAGE_SIZE: usize = 0x100;
+// Permissions and dirty tracking are kept in pages of this many bytes
+const PAGES: usize = 0x10000 / PAGE_SIZE;
+const WRITE_LOG: usize = 4;
+// No instruction writes more than 2 bytes, an interrupt straight after it pushes 2 more
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permissions {
+    ReadWrite,
+    ReadOnly,
+    // Writes are ignored like writing to a rom chip
+    DirtyTracked,
+    // Writes go through and mark their page as dirty
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Mirror {
+    // Addresses from start to the top of memory repeat a smaller block lower down
+    //  Boards that don't decode every address line see the same chips at several addresses
+    pub start: u16,
+    pub target: u16,
+    pub size: u16,
+    // Block that is repeated, target..target + size
+}
+impl Mirror {
+    pub fn resolve(&self, addr: u16) -> u16 {
+        // Address that is really accessed when addr is used
+        if addr < self.start || self.size == 0 {
+            return addr;
+        }
+        self.target.wrapping_add((addr - self.start) % self.size)
+    }
+}
+
+#[derive(Clone)]
 pub struct Memory {
-    held_memory: [u8; 0xffff],
+    held_memory: [u8; 0x10000],
     // 8080 should have 65536 addresses
     // 0x0000 -> 0x2000 should contain rom
     // 0x2001 -> 0x2400 is ram
     // 0x2401 -> 0x4000 is vram
-    // 0x4000 -> 0xffff is a mirror
+    // 0x4000 -> 0xffff is a mirror when one is set, otherwise it is more ram
+    permissions: [Permissions; PAGES],
+    // Everything is read write until a region is given other permissions
+    //  Not Copy, copies of all 64K have to be asked for
+    dirty_pages: [bool; PAGES],
+    mirror: Option<Mirror>,
+    writes: [u16; WRITE_LOG],
+    write_count: usize,
+    // Addresses written by the cpu since clear_writes, read only ones included
 }
 impl Memory {
     pub fn init() -> Self {
         Self {
-            held_memory: [0x00; 0xffff],
+            held_memory: [0x00; 0x10000],
+            permissions: [Permissions::ReadWrite; PAGES],
+            dirty_pages: [false; PAGES],
+            mirror: None,
+            writes: [0; WRITE_LOG],
+            write_count: 0,
         }
     }
 
+    pub fn set_mirror(&mut self, mirror: Option<Mirror>) {
+        self.mirror = mirror;
+    }
+
+    pub fn mirror(&self) -> Option<Mirror> {
+        self.mirror
+    }
+
+    fn resolve(&self, addr: u16) -> usize {
+        self.mirror.map_or(addr, |mirror| mirror.resolve(addr)) as usize
+    }
+
+    pub fn set_region_permissions(&mut self, region: Range<u32>, permissions: Permissions) {
+        // Exclusive end up to 0x10000 so the last address can be included, anything past memory is left off
+        //  Every page the region touches gets the permissions, profiles keep their regions on page boundaries
+        let end: usize = (region.end as usize).min(0x10000).div_ceil(PAGE_SIZE);
+        self.permissions[(region.start as usize / PAGE_SIZE).min(end)..end].fill(permissions);
+    }
+
+    pub fn permissions_at(&self, addr: u16) -> Permissions {
+        self.permissions[self.resolve(addr) / PAGE_SIZE]
+    }
+
+    pub fn is_dirty(&self, addr: u16) -> bool {
+        // Whether the page containing addr has been written since the last clear
+        self.dirty_pages[self.resolve(addr) / PAGE_SIZE]
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty_pages = [false; PAGES];
+    }
+
+    pub fn writes(&self) -> &[u16] {
+        // The first few addresses written since clear_writes, after mirroring, so the debugger can see
+        //  what an instruction touched
+        &self.writes[..self.write_count]
+    }
+
+    pub fn clear_writes(&mut self) {
+        self.write_count = 0;
+    }
+
+    pub fn poke(&mut self, addr: u16, byte: u8) {
+        // Writes a byte regardless of permissions
+        //  For loading roms and patching memory from outside the emulated machine
+        let addr: usize = self.resolve(addr);
+        self.held_memory[addr] = byte;
+    }
+
+    pub fn restore(&mut self, bytes: &[u8]) {
+        // Copies a whole memory image back from address 0 regardless of permissions or mirrors
+        //  Every page is marked dirty since anything on screen may have changed
+        let length: usize = bytes.len().min(self.held_memory.len());
+        self.held_memory[..length].copy_from_slice(&bytes[..length]);
+        self.dirty_pages = [true; PAGES];
+    }
+
     pub fn read_vram(&self) -> &[u8] {
-        &self.held_memory[0x2400..=0x3fff]
+        self.view(0x2400..0x4000)
+    }
+
+    fn clamp(&self, range: Range<u16>) -> Range<usize> {
+        // Keeps a range inside memory so views never panic
+        let end: usize = (range.end as usize).min(self.held_memory.len());
+        (range.start as usize).min(end)..end
+    }
+
+    pub fn view(&self, range: Range<u16>) -> &[u8] {
+        // Borrows a block of memory, anything past the end of memory is left off
+        //  Views are of the memory itself so mirrored addresses aren't redirected
+        &self.held_memory[self.clamp(range)]
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        // All of memory, a u16 range can't reach the last address
+        &self.held_memory
+    }
+
+    pub fn view_mut(&mut self, range: Range<u16>) -> Result<&mut [u8], &'static str> {
+        // Mutably borrows a block of memory as long as every address in it can be written to
+        //  Dirty tracked pages are marked dirty up front since the writes can't be seen
+
+        let range: Range<usize> = self.clamp(range);
+        let pages: Range<usize> = range.start / PAGE_SIZE..range.end.div_ceil(PAGE_SIZE);
+        if self.permissions[pages.clone()].contains(&Permissions::ReadOnly) {
+            return Err("Range contains read only memory");
+        }
+
+        for page in pages {
+            if self.permissions[page] == Permissions::DirtyTracked {
+                self.dirty_pages[page] = true;
+            }
+        }
+
+        Ok(&mut self.held_memory[range])
+    }
+
+    pub fn iter(&self, range: Range<u16>) -> impl Iterator<Item = (u16, u8)> + '_ {
+        // Every address in the range along with the byte stored there
+        let start: u16 = range.start;
+        self.view(range).iter().enumerate().map(move |(offset, byte)| (start + offset as u16, *byte))
+    }
+
+    pub fn iter_mut(&mut self, range: Range<u16>) -> Result<impl Iterator<Item = (u16, &mut u8)>, &'static str> {
+        // Mutable version of iter with the same permission checks as view_mut
+        let start: u16 = range.start;
+        Ok(self.view_mut(range)?.iter_mut().enumerate().map(move |(offset, byte)| (start + offset as u16, byte)))
     }
 
     pub fn read_at(&self, addr: u16) -> u8 {
-        self.held_memory[addr as usize]
+        self.held_memory[self.resolve(addr)]
     }
 
     pub fn write_at(&mut self, addr: u16, byte: u8) {
-        self.held_memory[addr as usize] = byte;
+        let addr: usize = self.resolve(addr);
+        if self.write_count < WRITE_LOG {
+            self.writes[self.write_count] = addr as u16;
+            self.write_count += 1;
+        }
+        match self.permissions[addr / PAGE_SIZE] {
+            Permissions::ReadWrite => self.held_memory[addr] = byte,
+            Permissions::ReadOnly => {},
+            Permissions::DirtyTracked => {
+                self.held_memory[addr] = byte;
+                self.dirty_pages[addr / PAGE_SIZE] = true;
+            },
+        }
     }
 
-    pub fn load_rom(&mut self, rom: &[u8], offset: u16) {
-        // Loads a rom into memory
+    pub fn load_rom(&mut self, rom: &[u8], offset: u16) -> Result<(), LoadError> {
+        // Loads a rom into the space Space Invaders keeps for it
+        self.load_rom_within(rom, offset, 0..ROM_END)
+    }
 
-        for (address, byte) in rom.iter().enumerate() {
-            assert!(address < 0x2000);
-            // Rom should fit in the space of memory reserved for roms
+    pub fn load_rom_within(&mut self, rom: &[u8], offset: u16, region: Range<u32>) -> Result<(), LoadError> {
+        // Loads a rom that has to fit inside region, nothing is written when it doesn't
 
-            self.write_at(address as u16 + offset, *byte);
+        if (offset as u32) < region.start || offset as usize + rom.len() > region.end as usize {
+            return Err(LoadError { size: rom.len(), offset, region: (region.start as u16, region.end) });
         }
+        for (address, byte) in rom.iter().enumerate() {
+            self.poke(offset + address as u16, *byte);
+        }
+        Ok(())
     }
 }
 
@@ -97,6 +283,7 @@ pub struct Flags {
     // P: Set if the number of 1 bits in the result is even -- 6th bit
     // CY: Set if addition resulted in a carry or subtraction in a borrow -- 8th bit
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Flag {
     S,
     Z,
@@ -115,6 +302,44 @@ impl Flag {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlagSet(u8);
+// Any number of flags at once, combined with |
+//  e.g. flags.check(FlagSet::Z | FlagSet::CY)
+impl FlagSet {
+    pub const NONE: FlagSet = FlagSet(0);
+    pub const S: FlagSet = FlagSet(1 << S_FLAG_BIT);
+    pub const Z: FlagSet = FlagSet(1 << Z_FLAG_BIT);
+    pub const AC: FlagSet = FlagSet(1 << AC_FLAG_BIT);
+    pub const P: FlagSet = FlagSet(1 << P_FLAG_BIT);
+    pub const CY: FlagSet = FlagSet(1 << CY_FLAG_BIT);
+    pub const ALL: FlagSet = FlagSet(1 << S_FLAG_BIT | 1 << Z_FLAG_BIT | 1 << AC_FLAG_BIT | 1 << P_FLAG_BIT | 1 << CY_FLAG_BIT);
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, other: FlagSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+impl BitOr for FlagSet {
+    type Output = FlagSet;
+
+    fn bitor(self, other: FlagSet) -> FlagSet {
+        FlagSet(self.0 | other.0)
+    }
+}
+impl From<Flag> for FlagSet {
+    fn from(flag: Flag) -> Self {
+        FlagSet(1 << flag.position())
+    }
+}
 impl Flags {
     pub fn new() -> Self {
         Self {
@@ -141,6 +366,53 @@ impl Flags {
     pub fn clear_flags(&mut self) {
         self.flags = 0x00;
     }
+
+    pub fn check(&self, set: FlagSet) -> bool {
+        // True when every flag in the set is set
+        self.flags & set.bits() == set.bits()
+    }
+
+    pub fn any(&self, set: FlagSet) -> bool {
+        self.flags & set.bits() != 0
+    }
+
+    pub fn set(&mut self, set: FlagSet) {
+        self.flags |= set.bits();
+    }
+
+    pub fn clear(&mut self, set: FlagSet) {
+        self.flags &= !set.bits();
+    }
+
+    pub fn assign(&mut self, set: FlagSet, value: bool) {
+        // Sets or clears every flag in the set, for putting back flags an operation shouldn't change
+        if value { self.set(set) }
+        else { self.clear(set) }
+    }
+
+    pub fn as_str(&self) -> String {
+        // The flags byte from the top bit down with unused bits as - and clear flags as .
+        //  e.g. "S Z - A - P - C" with every flag set
+        [(S_FLAG_BIT, 'S'), (Z_FLAG_BIT, 'Z'), (5, '-'), (AC_FLAG_BIT, 'A'), (3, '-'), (P_FLAG_BIT, 'P'), (1, '-'), (CY_FLAG_BIT, 'C')].iter()
+            .map(|(bit, name)| match name {
+                '-' => '-',
+                _ if self.flags & 1 << bit != 0 => *name,
+                _ => '.',
+            }.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    pub fn bits(&self) -> u8 {
+        // The flags as they are pushed to the stack by PUSH PSW
+        self.flags
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            flags: bits,
+        }
+    }
 }
 impl Default for Flags {
     fn default() -> Self {
@@ -148,7 +420,32 @@ impl Default for Flags {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Registers {
+    // Copy of the cpu's registers for saving or comparing outside the emulator
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub flags: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub interrupt_enabled: bool,
+}
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "A={:02x} B={:02x} C={:02x} D={:02x} E={:02x} H={:02x} L={:02x} SP={:04x} PC={:04x} F={} {}",
+            self.a, self.b, self.c, self.d, self.e, self.h, self.l, self.sp, self.pc,
+            Flags::from_bits(self.flags).as_str(),
+            if self.interrupt_enabled { "EI" } else { "DI" })
+    }
+}
+
+#[derive(Clone)]
 pub struct Cpu {
     pub a: Register,
     // A is public so it can be accessed from main
@@ -163,6 +460,8 @@ pub struct Cpu {
     pub memory: Memory,
     flags: Flags,
     interrupt_enabled: bool,
+    halted: bool,
+    // Set by HLT, nothing runs until an interrupt is serviced
 }
 impl Cpu {
     pub fn init() -> Self {
@@ -180,6 +479,7 @@ impl Cpu {
             memory: Memory::init(),
             flags: Flags::default(),
             interrupt_enabled: true,
+            halted: false,
         }
     }
 
@@ -188,14 +488,38 @@ impl Cpu {
         *self = Cpu::init();
     }
 
-    pub fn check_stack_overflow(&self) -> bool {
-        // Checks if the stack has overflowed
-        // The stack grows growns downwards on the 8080
-        if self.sp.address < STACK_MIN {
-            println!("STACK OVERFLOW");
-            return true;
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn set_halted(&mut self, halted: bool) {
+        self.halted = halted;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self.a.value,
+            b: self.b.value,
+            c: self.c.value,
+            d: self.d.value,
+            e: self.e.value,
+            h: self.h.value,
+            l: self.l.value,
+            flags: self.flags.bits(),
+            sp: self.sp.address,
+            pc: self.pc.address,
+            interrupt_enabled: self.interrupt_enabled,
         }
-        false
+    }
+
+    pub fn set_registers(&mut self, registers: Registers) {
+        // Puts back registers taken with registers(), memory is left alone
+        (self.a.value, self.b.value, self.c.value, self.d.value) = (registers.a, registers.b, registers.c, registers.d);
+        (self.e.value, self.h.value, self.l.value) = (registers.e, registers.h, registers.l);
+        self.flags = Flags::from_bits(registers.flags);
+        self.sp.address = registers.sp;
+        self.pc.address = registers.pc;
+        self.interrupt_enabled = registers.interrupt_enabled;
     }
 
     // Being used for CPU DIAG tests
@@ -242,17 +566,13 @@ fn inr(reg: u8, flags: &mut Flags) -> u8 {
     // Increments an 8 bit register
     // INR does not effect the carry flag
 
-    let carry: u8 = flags.check_flag(Flag::CY);
+    let carry: bool = flags.check(FlagSet::CY);
     // Hold the status of the carry flag
 
     let result: u8 = add(reg, 1, flags);
     // Increment
 
-    match carry {
-        1 => flags.set_flag(Flag::CY),
-        0 => flags.clear_flag(Flag::CY),
-        _ => panic!("check_flag cannot return anything other then 0 or 1"),
-    }
+    flags.assign(FlagSet::CY, carry);
     // Resets the carry flag to what it was before since this operation should not effect it
 
     result
@@ -262,17 +582,13 @@ fn dcr(reg: u8, flags: &mut Flags) -> u8 {
     // Decrements an 8 bit register
     // DCR does not effect the carry flag
 
-    let carry: u8 = flags.check_flag(Flag::CY);
+    let carry: bool = flags.check(FlagSet::CY);
     // Hold status of carry flag
 
     let result: u8 = sub(reg, 1, flags);
     // Decrement
 
-    match carry {
-        1 => flags.set_flag(Flag::CY),
-        0 => flags.clear_flag(Flag::CY),
-        _ => panic!("check_flag cannot return anything other than 0 or 1"),
-    }
+    flags.assign(FlagSet::CY, carry);
     // Resets carry flag
 
     result
@@ -298,6 +614,8 @@ fn add(reg_1: u8, reg_2: u8, flags: &mut Flags) -> u8 {
     let result: u16 = reg_1 as u16 + reg_2 as u16;
     // Do math with i16 to capture carry and negatives without over or underflow
     *flags = set_flags_from_operation(result as i16, *flags);
+    flags.assign(FlagSet::AC, (reg_1 & 0x0f) + (reg_2 & 0x0f) > 0x0f);
+    // Carry out of the low digit, DAA needs it after adding decimal numbers
 
     result as u8
 }
@@ -306,8 +624,9 @@ fn adc(reg_1: u8, reg_2: u8, flags: &mut Flags) -> u8 {
     // ADD but also adds value from carry flag
 
     let carry: u8 = flags.check_flag(Flag::CY);
-    let result: u16 = add(reg_1, reg_2, flags) as u16 + carry as u16;
+    let result: u16 = reg_1 as u16 + reg_2 as u16 + carry as u16;
     *flags = set_flags_from_operation(result as i16, *flags);
+    flags.assign(FlagSet::AC, (reg_1 & 0x0f) + (reg_2 & 0x0f) + carry > 0x0f);
 
     result as u8
 }
@@ -338,37 +657,29 @@ fn sbb(reg_1: u8, reg_2: u8, flags: &mut Flags) -> u8 {
 fn daa(a: u8, flags: &mut Flags) -> u8 {
     // Changes a hex number into its decimal equivalent
     //  i.e. if A is 0x0a it becomes 0x10
-    let mut result: u16 = a as u16;
+    //  Always adjusts as if the last operation was an addition, the 8080 has no flag to say it wasn't
 
-    let lo: u16 = result & 0b0000_1111;
+    let lo: u8 = a & 0b0000_1111;
     // Ones digit is the 4 lo bits
-    if lo > 9 || flags.check_flag(Flag::AC) == 1 {
-        // If the first digit is above 9 or the auxilliary carry flag is set
-        result += 6;
-        // A hex digit plus 6 is it's equivalent in decimal
-        //  i.e. 0x0a + 6 = 0x10
-
-        if (lo + 6) > 0x0f {
-            flags.set_flag(Flag::AC);
-        } else { flags.clear_flag(Flag::AC); }
-        // Sets auxilliary carry is necessary
+    let mut correction: u8 = 0;
+    if lo > 9 || flags.check(FlagSet::AC) {
+        correction |= 0x06;
     }
-
-    let mut hi: u16 = (result & 0b1111_0000) >> 4;
-    // Tens digit is 4 hi bits
-    if hi > 9 || flags.check_flag(Flag::CY) == 1 {
-        hi += 6;
+    // A hex digit plus 6 is it's equivalent in decimal
+    //  i.e. 0x0a + 6 = 0x10
+    let carry: bool = flags.check(FlagSet::CY) || a > 0x99;
+    if carry {
+        correction |= 0x60;
     }
-    if (hi + 6) > 0x0f {
-        flags.set_flag(Flag::AC);
-    } else { flags.clear_flag(Flag::AC); }
-
-    result = (hi << 4) | (result & 0b0000_1111);
-    // Combines the two digits
+    // Same again for the tens digit, a > 0x99 catches the ones digit carrying into a tens digit of 9
 
+    let result: u8 = a.wrapping_add(correction);
     *flags = set_flags_from_operation(result as i16, *flags);
+    flags.assign(FlagSet::AC, lo + (correction & 0x0f) > 0x0f);
+    flags.assign(FlagSet::CY, carry);
+    // CY stays set once it is, it carries into the next pair of digits
 
-    result as u8
+    result
 }
 
 fn jmp(address_bytes: (u8, u8), condition: Option<bool>) -> Option<u16> {
@@ -432,24 +743,24 @@ fn ret(condition: Option<bool>, stack_pointer: &mut AddressPointer, memory: &mut
 fn push(data_bytes: (u8, u8), stack_pointer: &mut AddressPointer, memory: &mut Memory) {
     // Puts some data onto the stack
 
-    memory.write_at(stack_pointer.address - 1, data_bytes.0);
-    memory.write_at(stack_pointer.address - 2, data_bytes.1);
+    memory.write_at(stack_pointer.address.wrapping_sub(1), data_bytes.0);
+    memory.write_at(stack_pointer.address.wrapping_sub(2), data_bytes.1);
     // d4 c3 will go in as:
     // d4
     // c3
 
-    stack_pointer.address -= 2;
+    stack_pointer.address = stack_pointer.address.wrapping_sub(2);
     // stack grows downwards
 }
 
 fn pop(stack_pointer: &mut AddressPointer, memory: &mut Memory) -> (u8, u8) {
     // Returns the data at the top of the stack
 
-    let byte_1 = memory.read_at(stack_pointer.address + 1);
+    let byte_1 = memory.read_at(stack_pointer.address.wrapping_add(1));
     let byte_2 = memory.read_at(stack_pointer.address);
     // Find two bytes before stack pointer
 
-    stack_pointer.address += 2;
+    stack_pointer.address = stack_pointer.address.wrapping_add(2);
     // stack shrinks upwards
 
     (byte_1, byte_2)
@@ -579,8 +890,15 @@ fn swap_registers(reg_1: u8, reg_2: u8) -> (u8, u8) {
     (reg_2, reg_1)
 }
 
-pub fn generate_interrupt(op_code: u8, cpu: &mut Cpu) {
+pub fn generate_interrupt(op_code: u8, cpu: &mut Cpu) -> bool {
+    // Returns whether the interrupt was serviced
     if cpu.interrupt_enabled {
         let _ = handle_op_code(op_code, cpu);
+        cpu.interrupt_enabled = false;
+        // Taking an interrupt turns them off until the handler runs EI
+        cpu.halted = false;
+        // The pc pushed is the instruction after HLT so the cpu carries on from there once the handler returns
+        return true;
     }
+    false
 }
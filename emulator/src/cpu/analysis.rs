@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use super::*;
+
+mod tests;
+
+const MAX_SYMBOLIC_BITS: u32 = 18;
+// Every combination of the symbolic inputs is run so this keeps an analysis to a few seconds
+const STEP_LIMIT: usize = 10_000;
+// Instructions a single run can take before it is assumed to be stuck in a loop
+const RETURN_ADDRESS: u16 = 0xfffe;
+// Pushed before the snippet runs so a RET at the end of a routine leaves the snippet
+const STACK_TOP: u16 = 0x2400;
+
+const FLAGS: [Flag; 5] = [Flag::S, Flag::Z, Flag::AC, Flag::P, Flag::CY];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    // An input to the snippet that can take any value
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Flag(Flag),
+}
+impl Symbol {
+    fn bits(&self) -> u32 {
+        match self {
+            Self::Flag(_) => 1,
+            _ => 8,
+        }
+    }
+
+    fn apply(&self, cpu: &mut Cpu, value: u8) {
+        match self {
+            Self::A => cpu.a.value = value,
+            Self::B => cpu.b.value = value,
+            Self::C => cpu.c.value = value,
+            Self::D => cpu.d.value = value,
+            Self::E => cpu.e.value = value,
+            Self::H => cpu.h.value = value,
+            Self::L => cpu.l.value = value,
+            Self::Flag(flag) if value == 0 => cpu.flags.clear_flag(*flag),
+            Self::Flag(flag) => cpu.flags.set_flag(*flag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    Returned,
+    // RET back to the caller
+    Left(u16),
+    // Jumped or ran past the end of the snippet
+    Halted,
+    StepLimit,
+    Error(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlagOutcome {
+    pub set: usize,
+    pub clear: usize,
+    // Number of inputs that left the flag set or clear
+}
+impl FlagOutcome {
+    pub fn describe(&self) -> &'static str {
+        match (self.set, self.clear) {
+            (0, 0) => "never reached",
+            (_, 0) => "always set",
+            (0, _) => "always clear",
+            _ => "set or clear",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub addresses: Vec<u16>,
+    // Address of every instruction executed, two inputs took the same path if these match
+    pub end: End,
+    pub inputs: usize,
+    pub example: Vec<(Symbol, u8)>,
+    // First input found that takes this path
+    pub flags: [FlagOutcome; 5],
+    // Indexed in S, Z, AC, P, CY order
+}
+impl Path {
+    pub fn flag(&self, flag: Flag) -> FlagOutcome {
+        self.flags[FLAGS.iter().position(|f| *f == flag).unwrap_or(0)]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub symbols: Vec<Symbol>,
+    pub paths: Vec<Path>,
+    // In the order they were first found
+}
+impl Analysis {
+    pub fn print(&self) {
+        println!("{} inputs over {} paths", self.paths.iter().map(|path| path.inputs).sum::<usize>(), self.paths.len());
+
+        for (i, path) in self.paths.iter().enumerate() {
+            let example: Vec<String> = path.example.iter().map(|(symbol, value)| format!("{:?}=0x{:02x}", symbol, value)).collect();
+            println!();
+            println!("Path {}: {} inputs, {} instructions, ends {:?}", i, path.inputs, path.addresses.len(), path.end);
+            println!("  Example: {}", example.join(" "));
+            for (flag, outcome) in FLAGS.iter().zip(path.flags.iter()) {
+                println!("  {:<3} {}", format!("{:?}", flag), outcome.describe());
+            }
+        }
+    }
+}
+
+fn restore(cpu: &mut Cpu, base: &Cpu) {
+    // Puts the cpu back to its starting state, only memory pages written by the last run are copied
+
+    cpu.a = base.a;
+    cpu.b = base.b;
+    cpu.c = base.c;
+    cpu.d = base.d;
+    cpu.e = base.e;
+    cpu.h = base.h;
+    cpu.l = base.l;
+    cpu.sp = base.sp;
+    cpu.pc = base.pc;
+    cpu.flags = base.flags;
+    cpu.interrupt_enabled = base.interrupt_enabled;
+
+    for page in 0..0x100 {
+        let start: u16 = (page * PAGE_SIZE) as u16;
+        if !cpu.memory.is_dirty(start) {
+            continue;
+        }
+        let page: Range<u16> = start..start.saturating_add(PAGE_SIZE as u16);
+        if let Ok(bytes) = cpu.memory.view_mut(page.clone()) {
+            bytes.copy_from_slice(base.memory.view(page));
+        }
+        // Every page is dirty tracked so the view can't be refused
+    }
+    cpu.memory.clear_dirty();
+}
+
+fn run(cpu: &mut Cpu, snippet: &Range<u16>) -> (Vec<u16>, End) {
+    let mut addresses: Vec<u16> = vec![];
+
+    while addresses.len() < STEP_LIMIT {
+        let address: u16 = cpu.pc.address;
+        if address == RETURN_ADDRESS {
+            return (addresses, End::Returned);
+        }
+        if !snippet.contains(&address) {
+            return (addresses, End::Left(address));
+        }
+        addresses.push(address);
+
+        let op_code: u8 = cpu.memory.read_at(address);
+        cpu.pc.address = address.wrapping_add(1);
+        match handle_op_code(op_code, cpu) {
+            Ok(255) => return (addresses, End::Halted),
+            Ok(additional_bytes) => cpu.pc.address = cpu.pc.address.wrapping_add(additional_bytes),
+            Err(e) => return (addresses, End::Error(e)),
+        }
+    }
+
+    (addresses, End::StepLimit)
+}
+
+pub fn analyse(code: &[u8], origin: u16, symbols: &[Symbol]) -> Result<Analysis, String> {
+    // Runs a snippet for every possible value of the symbolic inputs and groups the results by path
+    //  Registers and flags that aren't symbolic start at 0, memory outside the snippet is all 0
+    //  The snippet is called so it can end with RET, jumping outside of it also ends the run
+
+    let bits: u32 = symbols.iter().map(Symbol::bits).sum();
+    if bits > MAX_SYMBOLIC_BITS {
+        return Err(format!("{} symbolic bits is too many, at most {} can be analysed", bits, MAX_SYMBOLIC_BITS));
+    }
+    if origin as usize + code.len() > STACK_TOP as usize - 2 || code.is_empty() {
+        return Err(format!("Snippet must fit between 0x{:04x} and the stack at 0x{:04x}", origin, STACK_TOP));
+    }
+
+    let mut base: Cpu = Cpu::init();
+    for (offset, byte) in code.iter().enumerate() {
+        base.memory.poke(origin + offset as u16, *byte);
+    }
+    base.pc.address = origin;
+    base.sp.address = STACK_TOP - 2;
+    base.memory.poke(STACK_TOP - 2, RETURN_ADDRESS as u8);
+    base.memory.poke(STACK_TOP - 1, (RETURN_ADDRESS >> 8) as u8);
+    base.memory.set_region_permissions(0x0000..0x10000, Permissions::DirtyTracked);
+    // Lets restore only copy back the pages a run changed
+
+    let snippet: Range<u16> = origin..origin + code.len() as u16;
+    let mut cpu: Cpu = base.clone();
+    let mut paths: Vec<Path> = vec![];
+    let mut path_index: HashMap<Vec<u16>, usize> = HashMap::new();
+
+    for combination in 0..1_u32 << bits {
+        restore(&mut cpu, &base);
+
+        let mut remaining: u32 = combination;
+        let mut input: Vec<(Symbol, u8)> = vec![];
+        for symbol in symbols {
+            let value: u8 = (remaining & ((1 << symbol.bits()) - 1)) as u8;
+            remaining >>= symbol.bits();
+            symbol.apply(&mut cpu, value);
+            input.push((*symbol, value));
+        }
+
+        let (addresses, end): (Vec<u16>, End) = run(&mut cpu, &snippet);
+        let index: usize = match path_index.get(&addresses) {
+            Some(index) => *index,
+            None => {
+                paths.push(Path { addresses: addresses.clone(), end, inputs: 0, example: input, flags: [FlagOutcome::default(); 5] });
+                path_index.insert(addresses, paths.len() - 1);
+                paths.len() - 1
+            },
+        };
+
+        let path: &mut Path = &mut paths[index];
+        path.inputs += 1;
+        for (flag, outcome) in FLAGS.iter().zip(path.flags.iter_mut()) {
+            if cpu.flags.check(FlagSet::from(*flag)) { outcome.set += 1 }
+            else { outcome.clear += 1 }
+        }
+    }
+
+    Ok(Analysis { symbols: symbols.to_vec(), paths })
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_straight_line() {
+    let code: [u8; 2] = [
+        0x3c, // INR A
+        0xc9, // RET
+    ];
+    let analysis: Analysis = analyse(&code, 0x0000, &[Symbol::A, Symbol::Flag(Flag::CY)]).unwrap();
+
+    assert_eq!(analysis.paths.len(), 1);
+    let path: &Path = &analysis.paths[0];
+    assert_eq!(path.inputs, 0x200);
+    assert_eq!(path.end, End::Returned);
+    assert_eq!(path.addresses, vec![0x0000, 0x0001]);
+
+    assert_eq!(path.flag(Flag::CY), FlagOutcome { set: 0x100, clear: 0x100 });
+    // INR leaves carry alone
+    assert_eq!(path.flag(Flag::Z), FlagOutcome { set: 2, clear: 0x1fe });
+    // Only 0xff wraps around to zero
+}
+
+#[test]
+fn test_branches() {
+    let code: [u8; 8] = [
+        0xfe, 0x10, // CPI 0x10
+        0xda, 0x07, 0x00, // JC 0x0007
+        0x3e, 0x00, // MVI A, 0x00
+        0xc9, // RET
+    ];
+    let analysis: Analysis = analyse(&code, 0x0000, &[Symbol::A]).unwrap();
+
+    assert_eq!(analysis.paths.len(), 2);
+    let (taken, falls_through): (&Path, &Path) = (&analysis.paths[0], &analysis.paths[1]);
+    assert_eq!(taken.example, vec![(Symbol::A, 0x00)]);
+    // A = 0 is below 0x10 so borrows
+    assert_eq!(taken.addresses, vec![0x0000, 0x0002, 0x0007]);
+    assert_eq!(taken.inputs, 0x10);
+    assert_eq!(taken.flag(Flag::CY).describe(), "always set");
+
+    assert_eq!(falls_through.addresses, vec![0x0000, 0x0002, 0x0005, 0x0007]);
+    assert_eq!(falls_through.inputs, 0xf0);
+    assert_eq!(falls_through.flag(Flag::CY).describe(), "always clear");
+    assert_eq!(falls_through.flag(Flag::Z).describe(), "set or clear");
+}
+
+#[test]
+fn test_logical_flags() {
+    let code: [u8; 1] = [
+        0xaf, // XRA A
+    ];
+    let analysis: Analysis = analyse(&code, 0x1000, &[Symbol::A, Symbol::Flag(Flag::CY)]).unwrap();
+
+    let path: &Path = &analysis.paths[0];
+    assert_eq!(path.end, End::Left(0x1001));
+    assert_eq!(path.flag(Flag::Z).describe(), "always set");
+    assert_eq!(path.flag(Flag::P).describe(), "always set");
+    assert_eq!(path.flag(Flag::CY).describe(), "always clear");
+    assert_eq!(path.flag(Flag::S).describe(), "always clear");
+}
+
+#[test]
+fn test_limits() {
+    assert!(analyse(&[0x00], 0x0000, &[Symbol::A, Symbol::B, Symbol::C]).is_err());
+    assert!(analyse(&[], 0x0000, &[Symbol::A]).is_err());
+
+    let code: [u8; 3] = [
+        0xc3, 0x00, 0x00, // JMP 0x0000
+    ];
+    let analysis: Analysis = analyse(&code, 0x0000, &[]).unwrap();
+    assert_eq!(analysis.paths[0].end, End::StepLimit);
+
+    let analysis: Analysis = analyse(&[0x76], 0x0000, &[]).unwrap();
+    assert_eq!(analysis.paths[0].end, End::Halted);
+}
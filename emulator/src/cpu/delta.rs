@@ -0,0 +1,73 @@
+use std::fmt;
+
+use super::{Cpu, Flag, Flags, Registers};
+
+const FLAGS: [Flag; 5] = [Flag::S, Flag::Z, Flag::AC, Flag::P, Flag::CY];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateDelta {
+    // One thing that differs between two cpu states, written out like "A: 0x12 -> 0x13" or "CY: 0 -> 1"
+    Register(&'static str, u8, u8),
+    Pointer(&'static str, u16, u16),
+    // SP or PC
+    Flag(Flag, bool, bool),
+    InterruptEnable(bool, bool),
+    Halted(bool, bool),
+    Memory(u16, u8, u8),
+}
+impl fmt::Display for StateDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateDelta::Register(name, old, new) => write!(f, "{}: 0x{:02x} -> 0x{:02x}", name, old, new),
+            StateDelta::Pointer(name, old, new) => write!(f, "{}: 0x{:04x} -> 0x{:04x}", name, old, new),
+            StateDelta::Flag(flag, old, new) => write!(f, "{:?}: {} -> {}", flag, old as u8, new as u8),
+            StateDelta::InterruptEnable(old, new) => write!(f, "INTE: {} -> {}", old as u8, new as u8),
+            StateDelta::Halted(old, new) => write!(f, "HALT: {} -> {}", old as u8, new as u8),
+            StateDelta::Memory(address, old, new) => write!(f, "0x{:04x}: 0x{:02x} -> 0x{:02x}", address, old, new),
+        }
+    }
+}
+
+pub fn registers(before: &Registers, after: &Registers) -> Vec<StateDelta> {
+    // Differences in the registers, flags and interrupt enable, in the order Registers prints them
+    //  For save states which keep the registers without the rest of the cpu
+
+    let mut deltas: Vec<StateDelta> = vec![];
+    let pairs: [(&'static str, u8, u8); 7] = [
+        ("A", before.a, after.a), ("B", before.b, after.b), ("C", before.c, after.c), ("D", before.d, after.d),
+        ("E", before.e, after.e), ("H", before.h, after.h), ("L", before.l, after.l),
+    ];
+    deltas.extend(pairs.into_iter().filter(|(_, old, new)| old != new).map(|(name, old, new)| StateDelta::Register(name, old, new)));
+    for (name, old, new) in [("SP", before.sp, after.sp), ("PC", before.pc, after.pc)] {
+        if old != new {
+            deltas.push(StateDelta::Pointer(name, old, new));
+        }
+    }
+
+    let (old_flags, new_flags): (Flags, Flags) = (Flags::from_bits(before.flags), Flags::from_bits(after.flags));
+    for flag in FLAGS {
+        let (old, new): (bool, bool) = (old_flags.check_flag(flag) == 1, new_flags.check_flag(flag) == 1);
+        if old != new {
+            deltas.push(StateDelta::Flag(flag, old, new));
+        }
+    }
+    if before.interrupt_enabled != after.interrupt_enabled {
+        deltas.push(StateDelta::InterruptEnable(before.interrupt_enabled, after.interrupt_enabled));
+    }
+    deltas
+}
+
+impl Cpu {
+    pub fn diff(&self, other: &Cpu) -> Vec<StateDelta> {
+        // Everything that changes going from self to other, memory last in address order
+        //  e.g. assert_eq!(before.diff(&after), vec![StateDelta::Register("A", 0x12, 0x13)]) or printed a line each
+
+        let mut deltas: Vec<StateDelta> = registers(&self.registers(), &other.registers());
+        if self.halted != other.halted {
+            deltas.push(StateDelta::Halted(self.halted, other.halted));
+        }
+        let memory = self.memory.bytes().iter().zip(other.memory.bytes()).enumerate();
+        deltas.extend(memory.filter(|(_, (old, new))| old != new).map(|(address, (old, new))| StateDelta::Memory(address as u16, *old, *new)));
+        deltas
+    }
+}
@@ -1,27 +1,89 @@
+use std::sync::OnceLock;
+
 use super::*;
 
-pub const CLOCK_CYCLES: [u8; 0x100] = [
-    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5,
-    5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 13, 5, 10, 10, 10, 4,
-    4, 10, 13, 5, 5, 5, 7, 4, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5,
-    7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, 7, 7, 7, 7,
-    7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 7, 5, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4,
-    4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4,
-    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10,
-    10, 10, 17, 7, 11, 11, 10, 10, 10, 17, 11, 7, 11, 11, 10, 10, 10, 10, 17, 7, 11, 11, 10,
-    10, 18, 17, 11, 7, 11, 11, 5, 10, 5, 17, 17, 7, 11, 11, 10, 10, 4, 17, 11, 7, 11, 11, 5,
-    10, 4, 17, 17, 7, 11,
+pub use disassembler::instructions::CLOCK_CYCLES;
+// Shared with the disassembler so tools reading its table see the same timings
+pub const NOT_TAKEN: u8 = 6;
+// Conditional calls and returns that aren't taken leave the stack alone and finish this many cycles sooner
+
+pub fn is_conditional_call_or_return(op_code: u8) -> bool {
+    // CNZ, CZ, ... are 11ccc100 and RNZ, RZ, ... are 11ccc000
+    op_code & 0xc7 == 0xc4 || op_code & 0xc7 == 0xc0
+}
+
+pub fn is_branch(op_code: u8) -> bool {
+    // Every op code that can set the pc itself, jumps, calls, returns, RST and PCHL
+    //  Jumps are 11ccc010 and RST is 11nnn111, the rest are listed with the undocumented copies of JMP, CALL and RET
+    is_conditional_call_or_return(op_code) || op_code & 0xc7 == 0xc2 || op_code & 0xc7 == 0xc7 || [0xc3, 0xcb, 0xcd, 0xdd, 0xed, 0xfd, 0xc9, 0xd9, 0xe9].contains(&op_code)
+}
+
+static LENGTHS: OnceLock<[u8; 0x100]> = OnceLock::new();
+// Parsed from the disassembler's table the first time an instruction is checked
+
+pub fn length(op_code: u8) -> u8 {
+    // Bytes in the instruction, op code included
+    LENGTHS.get_or_init(disassembler::instructions::lengths)[op_code as usize]
+}
+
+pub fn check_length(op_code: u8, additional_bytes: u16) -> Result<(), String> {
+    // Compares the bytes an instruction said it read with the op code table
+    //  Only meaningful for instructions that aren't a branch, a branch sets the pc itself and reports 0
+
+    let expected: u16 = length(op_code) as u16 - 1;
+    match additional_bytes == expected {
+        true => Ok(()),
+        false => Err(format!("Op code 0x{:02x} read {} additional bytes but the table says {}", op_code, additional_bytes, expected)),
+    }
+}
+
+pub type Handler = fn(&mut Cpu) -> Result<u16, &'static str>;
+
+pub static HANDLERS: [Handler; 0x100] = [
+    handler::<0x00>, handler::<0x01>, handler::<0x02>, handler::<0x03>, handler::<0x04>, handler::<0x05>, handler::<0x06>, handler::<0x07>, handler::<0x08>, handler::<0x09>, handler::<0x0a>, handler::<0x0b>, handler::<0x0c>, handler::<0x0d>, handler::<0x0e>, handler::<0x0f>,
+    handler::<0x10>, handler::<0x11>, handler::<0x12>, handler::<0x13>, handler::<0x14>, handler::<0x15>, handler::<0x16>, handler::<0x17>, handler::<0x18>, handler::<0x19>, handler::<0x1a>, handler::<0x1b>, handler::<0x1c>, handler::<0x1d>, handler::<0x1e>, handler::<0x1f>,
+    handler::<0x20>, handler::<0x21>, handler::<0x22>, handler::<0x23>, handler::<0x24>, handler::<0x25>, handler::<0x26>, handler::<0x27>, handler::<0x28>, handler::<0x29>, handler::<0x2a>, handler::<0x2b>, handler::<0x2c>, handler::<0x2d>, handler::<0x2e>, handler::<0x2f>,
+    handler::<0x30>, handler::<0x31>, handler::<0x32>, handler::<0x33>, handler::<0x34>, handler::<0x35>, handler::<0x36>, handler::<0x37>, handler::<0x38>, handler::<0x39>, handler::<0x3a>, handler::<0x3b>, handler::<0x3c>, handler::<0x3d>, handler::<0x3e>, handler::<0x3f>,
+    handler::<0x40>, handler::<0x41>, handler::<0x42>, handler::<0x43>, handler::<0x44>, handler::<0x45>, handler::<0x46>, handler::<0x47>, handler::<0x48>, handler::<0x49>, handler::<0x4a>, handler::<0x4b>, handler::<0x4c>, handler::<0x4d>, handler::<0x4e>, handler::<0x4f>,
+    handler::<0x50>, handler::<0x51>, handler::<0x52>, handler::<0x53>, handler::<0x54>, handler::<0x55>, handler::<0x56>, handler::<0x57>, handler::<0x58>, handler::<0x59>, handler::<0x5a>, handler::<0x5b>, handler::<0x5c>, handler::<0x5d>, handler::<0x5e>, handler::<0x5f>,
+    handler::<0x60>, handler::<0x61>, handler::<0x62>, handler::<0x63>, handler::<0x64>, handler::<0x65>, handler::<0x66>, handler::<0x67>, handler::<0x68>, handler::<0x69>, handler::<0x6a>, handler::<0x6b>, handler::<0x6c>, handler::<0x6d>, handler::<0x6e>, handler::<0x6f>,
+    handler::<0x70>, handler::<0x71>, handler::<0x72>, handler::<0x73>, handler::<0x74>, handler::<0x75>, handler::<0x76>, handler::<0x77>, handler::<0x78>, handler::<0x79>, handler::<0x7a>, handler::<0x7b>, handler::<0x7c>, handler::<0x7d>, handler::<0x7e>, handler::<0x7f>,
+    handler::<0x80>, handler::<0x81>, handler::<0x82>, handler::<0x83>, handler::<0x84>, handler::<0x85>, handler::<0x86>, handler::<0x87>, handler::<0x88>, handler::<0x89>, handler::<0x8a>, handler::<0x8b>, handler::<0x8c>, handler::<0x8d>, handler::<0x8e>, handler::<0x8f>,
+    handler::<0x90>, handler::<0x91>, handler::<0x92>, handler::<0x93>, handler::<0x94>, handler::<0x95>, handler::<0x96>, handler::<0x97>, handler::<0x98>, handler::<0x99>, handler::<0x9a>, handler::<0x9b>, handler::<0x9c>, handler::<0x9d>, handler::<0x9e>, handler::<0x9f>,
+    handler::<0xa0>, handler::<0xa1>, handler::<0xa2>, handler::<0xa3>, handler::<0xa4>, handler::<0xa5>, handler::<0xa6>, handler::<0xa7>, handler::<0xa8>, handler::<0xa9>, handler::<0xaa>, handler::<0xab>, handler::<0xac>, handler::<0xad>, handler::<0xae>, handler::<0xaf>,
+    handler::<0xb0>, handler::<0xb1>, handler::<0xb2>, handler::<0xb3>, handler::<0xb4>, handler::<0xb5>, handler::<0xb6>, handler::<0xb7>, handler::<0xb8>, handler::<0xb9>, handler::<0xba>, handler::<0xbb>, handler::<0xbc>, handler::<0xbd>, handler::<0xbe>, handler::<0xbf>,
+    handler::<0xc0>, handler::<0xc1>, handler::<0xc2>, handler::<0xc3>, handler::<0xc4>, handler::<0xc5>, handler::<0xc6>, handler::<0xc7>, handler::<0xc8>, handler::<0xc9>, handler::<0xca>, handler::<0xcb>, handler::<0xcc>, handler::<0xcd>, handler::<0xce>, handler::<0xcf>,
+    handler::<0xd0>, handler::<0xd1>, handler::<0xd2>, handler::<0xd3>, handler::<0xd4>, handler::<0xd5>, handler::<0xd6>, handler::<0xd7>, handler::<0xd8>, handler::<0xd9>, handler::<0xda>, handler::<0xdb>, handler::<0xdc>, handler::<0xdd>, handler::<0xde>, handler::<0xdf>,
+    handler::<0xe0>, handler::<0xe1>, handler::<0xe2>, handler::<0xe3>, handler::<0xe4>, handler::<0xe5>, handler::<0xe6>, handler::<0xe7>, handler::<0xe8>, handler::<0xe9>, handler::<0xea>, handler::<0xeb>, handler::<0xec>, handler::<0xed>, handler::<0xee>, handler::<0xef>,
+    handler::<0xf0>, handler::<0xf1>, handler::<0xf2>, handler::<0xf3>, handler::<0xf4>, handler::<0xf5>, handler::<0xf6>, handler::<0xf7>, handler::<0xf8>, handler::<0xf9>, handler::<0xfa>, handler::<0xfb>, handler::<0xfc>, handler::<0xfd>, handler::<0xfe>, handler::<0xff>,
 ];
+// One standalone function per op code so an instruction can be called or tested on its own
+//  and the dispatch is a single indirect call instead of a match
 
-pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
+pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &'static str> {
     // Reads an op_code and performs the cooresponding operation
     // Returns the number of additional bytes read for the operation
+    HANDLERS[op_code as usize](cpu)
+    // The table beat matching on the op code by a fifth or more in examples/dispatch_bench.rs
+}
+
+pub fn handle_op_code_match(op_code: u8, cpu: &mut Cpu) -> Result<u16, &'static str> {
+    // Same as handle_op_code but matches on the op code, kept to benchmark against
+    execute(op_code, cpu)
+}
+
+fn handler<const OP_CODE: u8>(cpu: &mut Cpu) -> Result<u16, &'static str> {
+    execute(OP_CODE, cpu)
+    // The op code is a constant here so the match below folds down to the one arm
+}
 
+#[inline(always)]
+fn execute(op_code: u8, cpu: &mut Cpu) -> Result<u16, &'static str> {
     match op_code {
         0x00 => {},
         // NOP
         0x01 => { // LXI B
-            (cpu.b.value, cpu.c.value) = (cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address));
+            (cpu.b.value, cpu.c.value) = (cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address));
             return Ok(2);
         },
         0x02 => cpu.memory.write_at(pair_registers(cpu.b.value, cpu.c.value), cpu.a.value),
@@ -50,7 +112,7 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0x0f => cpu.a.value = rotate_right(cpu.a.value, false, &mut cpu.flags),
         0x10 => {},
         0x11 => { // LXI D
-            (cpu.d.value, cpu.e.value) = (cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address));
+            (cpu.d.value, cpu.e.value) = (cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address));
             return Ok(2);
         },
         0x12 => cpu.memory.write_at(pair_registers(cpu.d.value, cpu.e.value), cpu.a.value),
@@ -79,15 +141,15 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0x1f => cpu.a.value = rotate_right(cpu.a.value, true, &mut cpu.flags),
         0x20 => {},
         0x21 => { // LXI H
-            (cpu.h.value, cpu.l.value) = (cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address));
+            (cpu.h.value, cpu.l.value) = (cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address));
             return Ok(2);
         },
         0x22 => { // SHLD
             let addr: u16 = pair_registers(
-                cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address)
+                cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address)
                 );
             cpu.memory.write_at(addr, cpu.l.value);
-            cpu.memory.write_at(addr + 1, cpu.h.value);
+            cpu.memory.write_at(addr.wrapping_add(1), cpu.h.value);
             return Ok(2);
         },
         0x23 => (cpu.h.value, cpu.l.value) = inx( pair_registers(cpu.h.value, cpu.l.value) ),
@@ -106,10 +168,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
             ),
         0x2a => { // LHLD
             let addr: u16 = pair_registers(
-                cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address)
+                cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address)
                 );
             cpu.l.value = cpu.memory.read_at(addr);
-            cpu.h.value = cpu.memory.read_at(addr + 1);
+            cpu.h.value = cpu.memory.read_at(addr.wrapping_add(1));
             return Ok(2);
         },
         0x2b => (cpu.h.value, cpu.l.value) = dcx( pair_registers(cpu.h.value, cpu.l.value) ),
@@ -122,13 +184,13 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0x2f => cpu.a.value = !cpu.a.value,
         0x30 => {},
         0x31 => { // LXI SP
-            cpu.sp.address = pair_registers(cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address));
+            cpu.sp.address = pair_registers(cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address));
             return Ok(2);
         },
         0x32 => { // STA
             cpu.memory.write_at(
                 pair_registers(
-                    cpu.memory.read_at(cpu.pc.address + 1),
+                    cpu.memory.read_at(cpu.pc.address.wrapping_add(1)),
                     cpu.memory.read_at(cpu.pc.address)),
                 cpu.a.value
                 );
@@ -169,7 +231,7 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
             ),
         0x3a => { // LDA
             cpu.a.value = cpu.memory.read_at(
-                pair_registers(cpu.memory.read_at(cpu.pc.address + 1), cpu.memory.read_at(cpu.pc.address))
+                pair_registers(cpu.memory.read_at(cpu.pc.address.wrapping_add(1)), cpu.memory.read_at(cpu.pc.address))
                 );
             return Ok(2);
         },
@@ -333,7 +395,7 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
 
         0xc0 => { // RNZ
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::Z) == 0),
+                Some(!cpu.flags.check(FlagSet::Z)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -344,8 +406,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xc1 => (cpu.b.value, cpu.c.value) = pop(&mut cpu.sp, &mut cpu.memory),
         0xc2 => { // JNZ
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::Z) == 0)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::Z))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -354,17 +416,17 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xc3 => { // JMP
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
                 None
                 );
-            cpu.pc.address = jmp_address.expect("jmp with no condition should always return Some(address)");
+            cpu.pc.address = jmp_address.ok_or("jmp with no condition should always return Some(address)")?;
         },
         0xc4 => { // CNZ
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::Z) == 0),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::Z)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -383,11 +445,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xc8 => { // RZ
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::Z) == 1),
+                Some(cpu.flags.check(FlagSet::Z)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -400,12 +462,12 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 None,
                 &mut cpu.sp, &mut cpu.memory
                 );
-            cpu.pc.address = ret_address.expect("ret with no conditions always returns an address");
+            cpu.pc.address = ret_address.ok_or("ret with no conditions always returns an address")?;
         },
         0xca => { // JZ
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::Z) == 1)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::Z))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -415,10 +477,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xcb => {},
         0xcc => { // CZ
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::Z) == 1),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::Z)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -427,12 +489,12 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xcd => { // CALL
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
                 None,
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xce => { // ACI
             cpu.a.value = adc(cpu.a.value, cpu.memory.read_at(cpu.pc.address), &mut cpu.flags);
@@ -445,11 +507,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xd0 => { // RNC
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::CY) == 0),
+                Some(!cpu.flags.check(FlagSet::CY)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -460,8 +522,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xd1 => (cpu.d.value, cpu.e.value) = pop(&mut cpu.sp, &mut cpu.memory),
         0xd2 => { // JNC
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::CY) == 0)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::CY))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -470,14 +532,14 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xd3 => { // OUT
             // This opcode and the opcode for IN will not be handled here
-            panic!("OUT should have been handled by the hardware module");
+            return Err("OUT should have been handled by the hardware module");
         },
         0xd4 => { // CNC
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::CY) == 0),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::CY)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -496,11 +558,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xd8 => { // RC
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::CY) == 1),
+                Some(cpu.flags.check(FlagSet::CY)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -511,8 +573,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xd9 => {},
         0xda => { // JC
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::CY) == 1)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::CY))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -521,14 +583,14 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xdb => { // IN
             // This opcode and the opcode for OUT will not be handled here
-            panic!("IN should have been handled by the hardware module");
+            return Err("IN should have been handled by the hardware module");
         },
         0xdc => { // CC
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::CY) == 1),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::CY)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -547,11 +609,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xe0 => { // RPO
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::P) == 0),
+                Some(!cpu.flags.check(FlagSet::P)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -562,8 +624,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xe1 => (cpu.h.value, cpu.l.value) = pop(&mut cpu.sp, &mut cpu.memory),
         0xe2 => { // JPO
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::P) == 0)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::P))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -577,10 +639,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xe4 => { // CPO
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::P) == 0),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::P)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -599,11 +661,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xe8 => { // RPE
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::P) == 1),
+                Some(cpu.flags.check(FlagSet::P)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -618,8 +680,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xea => { // JPE
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::P) == 1)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::P))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -632,10 +694,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         },
         0xec => { // CPE
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::P) == 1),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::P)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -654,11 +716,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xf0 => { // RP
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::S) == 0),
+                Some(!cpu.flags.check(FlagSet::S)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -669,8 +731,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xf1 => (cpu.a.value, cpu.flags.flags) = pop(&mut cpu.sp, &mut cpu.memory),
         0xf2 => { // JP
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::S) == 0)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::S))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -680,10 +742,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xf3 => cpu.interrupt_enabled = false,
         0xf4 => { // CP
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::S) == 0),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(!cpu.flags.check(FlagSet::S)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -702,11 +764,11 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
         0xf8 => { // RM
             let ret_address: Option<u16> = ret(
-                Some(cpu.flags.check_flag(Flag::S) == 1),
+                Some(cpu.flags.check(FlagSet::S)),
                 &mut cpu.sp, &mut cpu.memory
                 );
             match ret_address {
@@ -717,8 +779,8 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xf9 => cpu.sp.address = pair_registers(cpu.h.value, cpu.l.value),
         0xfa => { // JM
             let jmp_address: Option<u16> = jmp(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::S) == 1)
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::S))
                 );
             match jmp_address {
                 Some(address) => cpu.pc.address = address,
@@ -728,10 +790,10 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
         0xfb => cpu.interrupt_enabled = true,
         0xfc => { // CM
             let call_address: Option<u16> = call(
-                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1)),
-                Some(cpu.flags.check_flag(Flag::S) == 1),
+                (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address.wrapping_add(1))),
+                Some(cpu.flags.check(FlagSet::S)),
                 &mut cpu.sp, &mut cpu.memory,
-                cpu.pc.address + 2
+                cpu.pc.address.wrapping_add(2)
                 );
             match call_address {
                 Some(address) => cpu.pc.address = address,
@@ -750,7 +812,7 @@ pub fn handle_op_code(op_code: u8, cpu: &mut Cpu) -> Result<u16, &str> {
                 &mut cpu.sp, &mut cpu.memory,
                 cpu.pc.address
                 );
-            cpu.pc.address = call_address.expect("call with no condition always returns an address");
+            cpu.pc.address = call_address.ok_or("call with no condition always returns an address")?;
         },
     }
 
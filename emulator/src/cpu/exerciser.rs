@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use super::*;
+use crate::testing::{self, LOAD_ADDRESS};
+
+mod tests;
+
+const TEST_LOOP: [u8; 4] = [0x7e, 0x23, 0xb6, 0xca];
+// MOV A,M  INX H  ORA M  JZ, the exerciser walks its table of tests with this loop
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    // Result of one test group of an exerciser rom like 8080EXM or 8080PRE
+    pub index: usize,
+    pub name: String,
+    pub passed: bool,
+    pub crc: Option<String>,
+    // CRC the emulator produced
+    pub output: String,
+    pub error: Option<String>,
+    // Set when the test stopped without reporting a result
+}
+
+pub struct TestTable {
+    operand: usize,
+    // Offset in the rom of the LXI H operand that points at the table
+    address: u16,
+    pub count: usize,
+}
+
+pub fn find_test_table(rom: &[u8]) -> Option<TestTable> {
+    // The exerciser loads the table address into HL right before the loop that walks it
+    //  so the table can be found without hard coding its location for each build of the rom
+
+    let loop_offset: usize = rom.windows(TEST_LOOP.len()).position(|window| window == TEST_LOOP)?;
+    if loop_offset < 3 || rom[loop_offset - 3] != 0x21 {
+        return None;
+    }
+    // LXI H, tests
+
+    let operand: usize = loop_offset - 2;
+    let address: u16 = (rom[operand + 1] as u16) << 8 | rom[operand] as u16;
+    let mut count: usize = 0;
+    loop {
+        let entry: usize = (address.checked_sub(LOAD_ADDRESS)? as usize) + count * 2;
+        let pointer: &[u8] = rom.get(entry..entry + 2)?;
+        if pointer == [0, 0] {
+            break;
+        }
+        count += 1;
+    }
+
+    Some(TestTable { operand, address, count })
+}
+
+fn parse_result(index: usize, output: String, error: Option<String>) -> TestResult {
+    // Result lines look like
+    //  dad <b,d,h,sp>................  PASS! crc is:14474ba6
+    //  dad <b,d,h,sp>................  ERROR **** crc expected:14474ba6 found:2b1d5e4f
+
+    let line: &str = output.lines()
+        .find(|line| line.contains("PASS!") || line.contains("ERROR"))
+        .unwrap_or("");
+    let name: String = line.split("..").next().unwrap_or("").trim().to_string();
+    let crc: Option<String> = line.split_once("crc is:")
+        .or_else(|| line.split_once("found:"))
+        .map(|(_, crc)| crc.trim().to_string());
+
+    TestResult {
+        index,
+        name,
+        passed: error.is_none() && line.contains("PASS!"),
+        crc,
+        output,
+        error,
+    }
+}
+
+pub fn run_test(rom: &[u8], table: &TestTable, index: usize) -> TestResult {
+    // Runs a single test group on its own cpu
+    //  The table pointer is moved to the chosen test and the entry after it is cleared
+    //  so the exerciser finishes as soon as that test is done
+
+    let mut cpu: Cpu = Cpu::init();
+    testing::load(&mut cpu, rom);
+
+    let entry: u16 = table.address + index as u16 * 2;
+    let operand: u16 = LOAD_ADDRESS + table.operand as u16;
+    cpu.memory.poke(operand, entry as u8);
+    cpu.memory.poke(operand + 1, (entry >> 8) as u8);
+    cpu.memory.poke(entry + 2, 0x00);
+    cpu.memory.poke(entry + 3, 0x00);
+
+    let mut output: String = String::new();
+    let error: Option<String> = testing::run(&mut cpu, &mut output, None).err();
+
+    parse_result(index, output, error)
+}
+
+pub fn run_parallel(rom: &[u8], threads: usize) -> Result<Vec<TestResult>, &'static str> {
+    // Splits the test groups of an exerciser rom across threads, each with its own cpu
+    //  Threads take the next unstarted test when they finish one so a slow group doesn't hold up the rest
+    //  Results are returned in the order the rom lists the tests
+
+    let table: TestTable = find_test_table(rom).ok_or("Could not find the exerciser's table of tests")?;
+    let next: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<TestResult>> = Mutex::new(Vec::with_capacity(table.count));
+
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, table.count.max(1)) {
+            scope.spawn(|| loop {
+                let index: usize = next.fetch_add(1, Ordering::Relaxed);
+                if index >= table.count {
+                    break;
+                }
+
+                let result: TestResult = run_test(rom, &table, index);
+                results.lock().expect("no thread should panic while holding the results").push(result);
+            });
+        }
+    });
+
+    let mut results: Vec<TestResult> = results.into_inner().expect("threads have finished");
+    results.sort_by_key(|result| result.index);
+
+    Ok(results)
+}
+
+pub fn print_results(results: &[TestResult]) {
+    for result in results {
+        let status: &str = if result.passed { "PASS" } else { "FAIL" };
+        println!("{:>2} {:<32} {} {}", result.index, result.name, status, result.crc.as_deref().unwrap_or(""));
+        if let Some(e) = &result.error {
+            println!("   {}", e);
+        }
+    }
+
+    let passed: usize = results.iter().filter(|result| result.passed).count();
+    println!("{} of {} tests passed", passed, results.len());
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+fn test_rom(messages: &[&str]) -> Vec<u8> {
+    // A cut down exerciser that walks its table the same way and prints one message per test
+
+    let mut rom: Vec<u8> = vec![
+        0x21, 0x20, 0x01, // LXI H, tests
+        0x7e, // loop: MOV A,M
+        0x23, // INX H
+        0xb6, // ORA M
+        0xca, 0x14, 0x01, // JZ done
+        0x2b, // DCX H
+        0xe5, // PUSH H
+        0xcd, 0x17, 0x01, // CALL stt
+        0xe1, // POP H
+        0x23, // INX H
+        0x23, // INX H
+        0xc3, 0x03, 0x01, // JMP loop
+        0xc3, 0x00, 0x00, // done: JMP 0
+        0x5e, // stt: MOV E,M
+        0x23, // INX H
+        0x56, // MOV D,M
+        0x0e, 0x09, // MVI C, 9
+        0xcd, 0x05, 0x00, // CALL 5
+        0xc9, // RET
+    ];
+
+    let mut address: u16 = LOAD_ADDRESS + 0x20 + (messages.len() as u16 + 1) * 2;
+    let mut strings: Vec<u8> = vec![];
+    for message in messages {
+        rom.extend([address as u8, (address >> 8) as u8]);
+        strings.extend(message.bytes());
+        strings.push(b'$');
+        address += message.len() as u16 + 1;
+    }
+    rom.extend([0x00, 0x00]);
+    rom.extend(strings);
+
+    rom
+}
+
+#[test]
+fn test_find_table() {
+    let rom: Vec<u8> = test_rom(&["a", "b", "c"]);
+    let table: TestTable = find_test_table(&rom).unwrap();
+    assert_eq!(table.address, 0x0120);
+    assert_eq!(table.count, 3);
+
+    assert!(find_test_table(&[0x00; 16]).is_none());
+}
+
+#[test]
+fn test_run_parallel() {
+    let rom: Vec<u8> = test_rom(&[
+        "dad <b,d,h,sp>................  PASS! crc is:14474ba6\r\n",
+        "aluop nn......................  ERROR **** crc expected:9e922f9e found:12345678\r\n",
+        "inx <b,d,h,sp>................  PASS! crc is:5f2f3dd7\r\n",
+    ]);
+
+    let results: Vec<TestResult> = run_parallel(&rom, 2).unwrap();
+    assert_eq!(results.len(), 3);
+
+    assert_eq!(results[0].name, "dad <b,d,h,sp>");
+    assert!(results[0].passed);
+    assert_eq!(results[0].crc.as_deref(), Some("14474ba6"));
+    // Only the chosen test runs on each cpu
+    assert!(!results[0].output.contains("aluop"));
+
+    assert_eq!(results[1].name, "aluop nn");
+    assert!(!results[1].passed);
+    assert_eq!(results[1].crc.as_deref(), Some("12345678"));
+    assert!(results[1].error.is_none());
+
+    assert_eq!(results[2].index, 2);
+    assert!(results[2].passed);
+}
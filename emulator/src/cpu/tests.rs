@@ -1,6 +1,10 @@
 #[cfg(test)]
 use super::*;
 use super::dispatcher::handle_op_code;
+#[cfg(test)]
+use crate::fixtures;
+#[cfg(test)]
+use crate::machine::Machine;
 
 #[test]
 fn test_memory_rw() {
@@ -73,6 +77,30 @@ fn test_register_pairing() {
     assert_eq!(split_register_pair(hl), (h, l));
 }
 
+#[test]
+fn test_flag_sets() {
+    let mut flags: Flags = Flags::default();
+
+    flags.set(FlagSet::Z | FlagSet::CY);
+    assert_eq!(flags.bits(), 0b01000001);
+    assert!(flags.check(FlagSet::Z | FlagSet::CY));
+    assert!(!flags.check(FlagSet::Z | FlagSet::S));
+    assert!(flags.any(FlagSet::Z | FlagSet::S));
+    assert!(flags.check(FlagSet::NONE));
+    assert_eq!(FlagSet::from(Flag::AC), FlagSet::AC);
+    assert!(FlagSet::ALL.contains(FlagSet::P | FlagSet::S));
+
+    flags.clear(FlagSet::CY);
+    assert_eq!(flags.check_flag(Flag::CY), 0);
+    flags.assign(FlagSet::S | FlagSet::P, true);
+    assert_eq!(flags.as_str(), "S Z - . - P - .");
+
+    flags.assign(FlagSet::ALL, false);
+    assert_eq!(flags.as_str(), ". . - . - . - .");
+    flags.set(FlagSet::ALL);
+    assert_eq!(flags.as_str(), "S Z - A - P - C");
+}
+
 #[test]
 fn test_operation_flag_setting() {
     let mut flags: Flags = Flags::default();
@@ -157,6 +185,63 @@ fn test_arithmetic_operations() {
     // This should never affect any flag other than the carry flag
 }
 
+#[test]
+fn test_auxiliary_carry() {
+    let mut flags: Flags = Flags::default();
+
+    assert_eq!(add(0x08, 0x08, &mut flags), 0x10);
+    assert!(flags.check(FlagSet::AC));
+    assert_eq!(add(0x07, 0x08, &mut flags), 0x0f);
+    assert!(!flags.check(FlagSet::AC));
+    assert_eq!(add(0xf0, 0x10, &mut flags), 0x00);
+    assert!(flags.check(FlagSet::CY) && !flags.check(FlagSet::AC));
+    // A carry out of the high digit isn't one out of the low digit
+
+    flags.set_flag(Flag::CY);
+    assert_eq!(adc(0x07, 0x08, &mut flags), 0x10);
+    assert!(flags.check(FlagSet::AC));
+    // The carry in counts towards the low digit
+    flags.set_flag(Flag::CY);
+    assert_eq!(adc(0x0f, 0xf0, &mut flags), 0x00);
+    assert!(flags.check(FlagSet::AC) && flags.check(FlagSet::CY));
+    assert_eq!(adc(0x01, 0x01, &mut flags), 0x03);
+    // Carry is only added once
+}
+
+#[test]
+fn test_daa() {
+    let mut flags: Flags = Flags::default();
+
+    assert_eq!(daa(0x0a, &mut flags), 0x10);
+    assert!(flags.check(FlagSet::AC) && !flags.check(FlagSet::CY));
+    assert_eq!(daa(0x9b, &mut flags), 0x01);
+    assert!(flags.check(FlagSet::AC) && flags.check(FlagSet::CY));
+    // The example from the 8080 programmer's manual
+
+    let mut decimal_add = |a: u8, b: u8| -> (u8, bool) {
+        flags.clear_flags();
+        let sum: u8 = add(a, b, &mut flags);
+        let adjusted: u8 = daa(sum, &mut flags);
+        (adjusted, flags.check(FlagSet::CY))
+    };
+    assert_eq!(decimal_add(0x29, 0x18), (0x47, false));
+    // 0x41 with AC set, the low digit carried without going over 9
+    assert_eq!(decimal_add(0x38, 0x45), (0x83, false));
+    assert_eq!(decimal_add(0x99, 0x01), (0x00, true));
+    assert_eq!(decimal_add(0x85, 0x25), (0x10, true));
+    assert_eq!(decimal_add(0x90, 0x90), (0x80, true));
+    // CY from the add itself
+
+    flags.clear_flags();
+    flags.set_flag(Flag::CY);
+    assert_eq!(daa(0x12, &mut flags), 0x72);
+    assert!(flags.check(FlagSet::CY));
+    // CY stays set once it is
+    flags.clear_flags();
+    assert_eq!(daa(0x00, &mut flags), 0x00);
+    assert!(flags.check(FlagSet::Z) && !flags.check(FlagSet::AC));
+}
+
 #[test]
 fn test_branching_operations() {
     let mut cpu: Cpu = Cpu::init();
@@ -628,3 +713,279 @@ fn test_operation_handling() {
     assert_eq!(cpu.h.value, 0xff);
     assert_eq!(cpu.l.value, 0xee);
 }
+
+#[test]
+fn test_memory_permissions() {
+    let mut memory: Memory = Memory::init();
+
+    // Read only
+    memory.write_at(0x0010, 0xff);
+    memory.set_region_permissions(0x0000..0x2000, Permissions::ReadOnly);
+    memory.write_at(0x0010, 0x00);
+    memory.write_at(0x1fff, 0xee);
+    assert_eq!(memory.read_at(0x0010), 0xff);
+    assert_eq!(memory.read_at(0x1fff), 0x00);
+    // Writes to rom are ignored
+
+    memory.write_at(0x2000, 0xee);
+    assert_eq!(memory.read_at(0x2000), 0xee);
+    // Outside the region is unaffected
+
+    memory.poke(0x0010, 0x00);
+    assert_eq!(memory.read_at(0x0010), 0x00);
+    memory.load_rom(&[0xc3, 0xd4], 0).unwrap();
+    assert_eq!(memory.read_at(0x0001), 0xd4);
+    // Poking and loading roms ignore permissions
+
+    assert_eq!(memory.load_rom(&[0xff; 0x10], 0x1ff8), Err(LoadError { size: 0x10, offset: 0x1ff8, region: (0x0000, 0x2000) }));
+    assert_eq!(memory.read_at(0x1ff8), 0x00);
+    // Nothing is written when the rom runs past the end
+    assert!(memory.load_rom_within(&[0xff; 0x10], 0x1ff8, 0x0000..0x4000).is_ok());
+    assert!(memory.load_rom_within(&[0xff], 0x0100, 0x0800..0x1000).unwrap_err().to_string().contains("outside"));
+
+    memory.set_region_permissions(0x0000..0x2000, Permissions::ReadWrite);
+    memory.write_at(0x0010, 0x01);
+    assert_eq!(memory.read_at(0x0010), 0x01);
+    // Unlocking
+
+    // Dirty tracking
+    memory.set_region_permissions(0x2400..0x4000, Permissions::DirtyTracked);
+    assert!(!memory.is_dirty(0x2400));
+    memory.write_at(0x2410, 0xff);
+    assert_eq!(memory.read_at(0x2410), 0xff);
+    assert!(memory.is_dirty(0x2400));
+    assert!(memory.is_dirty(0x24ff));
+    assert!(!memory.is_dirty(0x2500));
+    memory.write_at(0x2000, 0xff);
+    assert!(!memory.is_dirty(0x2000));
+    // Read write regions aren't tracked
+
+    memory.clear_dirty();
+    assert!(!memory.is_dirty(0x2400));
+    assert_eq!(memory.permissions_at(0x2400), Permissions::DirtyTracked);
+
+    memory.set_region_permissions(0x4010..0x4020, Permissions::ReadOnly);
+    assert_eq!((memory.permissions_at(0x4000), memory.permissions_at(0x40ff)), (Permissions::ReadOnly, Permissions::ReadOnly));
+    assert_eq!(memory.permissions_at(0x4100), Permissions::ReadWrite);
+    // Regions off a page boundary take the whole pages they touch
+}
+
+#[test]
+fn test_memory_views() {
+    let mut memory: Memory = Memory::init();
+    memory.load_rom(&[0xc3, 0xd4, 0x18], 0x0100).unwrap();
+
+    assert_eq!(memory.view(0x0100..0x0103), &[0xc3, 0xd4, 0x18]);
+    assert_eq!(memory.view(0xfff0..0xffff).len(), 0x0f);
+    let (start, end): (u16, u16) = (0x0200, 0x0100);
+    assert!(memory.view(start..end).is_empty());
+    // Backwards ranges are empty instead of panicking
+
+    let found: Vec<(u16, u8)> = memory.iter(0x0000..0x2000).filter(|(_, byte)| *byte == 0xd4).collect();
+    assert_eq!(found, vec![(0x0101, 0xd4)]);
+
+    // Mutable views
+    memory.view_mut(0x0100..0x0102).unwrap().copy_from_slice(&[0x00, 0x01]);
+    assert_eq!(memory.read_at(0x0101), 0x01);
+
+    memory.set_region_permissions(0x0000..0x2000, Permissions::ReadOnly);
+    assert!(memory.view_mut(0x1ff0..0x2010).is_err());
+    assert!(memory.iter_mut(0x0000..0x0001).is_err());
+    assert!(memory.view_mut(0x2000..0x2010).is_ok());
+    // Any read only byte refuses the whole view
+
+    memory.set_region_permissions(0x2400..0x4000, Permissions::DirtyTracked);
+    for (address, byte) in memory.iter_mut(0x23f0..0x2410).unwrap() {
+        *byte = address as u8;
+    }
+    assert_eq!(memory.read_at(0x240f), 0x0f);
+    assert!(memory.is_dirty(0x2400));
+    assert!(!memory.is_dirty(0x2300));
+    assert!(!memory.is_dirty(0x2500));
+}
+
+#[test]
+fn test_memory_mirror() {
+    let mirror: Mirror = Mirror { start: 0x4000, target: 0x2000, size: 0x2000 };
+    assert_eq!(mirror.resolve(0x1fff), 0x1fff);
+    assert_eq!(mirror.resolve(0x3fff), 0x3fff);
+    assert_eq!(mirror.resolve(0x4000), 0x2000);
+    assert_eq!(mirror.resolve(0x5fff), 0x3fff);
+    assert_eq!(mirror.resolve(0x6000), 0x2000);
+    assert_eq!(mirror.resolve(0xffff), 0x3fff);
+
+    let mut memory: Memory = Memory::init();
+    memory.set_mirror(Some(mirror));
+    memory.set_region_permissions(0x2400..0x4000, Permissions::DirtyTracked);
+
+    memory.write_at(0x4010, 0xaa);
+    assert_eq!(memory.read_at(0x2010), 0xaa);
+    assert_eq!(memory.read_at(0xe010), 0xaa);
+    memory.write_at(0x2011, 0xbb);
+    assert_eq!(memory.read_at(0x8011), 0xbb);
+    // Reads and writes through the mirror reach the same bytes
+
+    memory.write_at(0xffff, 0xcc);
+    assert_eq!(memory.read_at(0x3fff), 0xcc);
+    assert!(memory.is_dirty(0x3f00));
+    assert!(memory.is_dirty(0xff00));
+    assert_eq!(memory.permissions_at(0x6400), Permissions::DirtyTracked);
+    // Permissions and dirty pages are those of the mirrored address
+
+    memory.poke(0xc000, 0xdd);
+    assert_eq!(memory.read_at(0x2000), 0xdd);
+    assert_eq!(memory.view(0xc000..0xc001), &[0x00]);
+    // Views aren't redirected
+
+    memory.set_mirror(None);
+    memory.write_at(0x4010, 0xee);
+    assert_eq!(memory.read_at(0x2010), 0xaa);
+}
+
+#[test]
+fn test_instruction_table() {
+    let table: Vec<disassembler::instructions::Instruction> = disassembler::instructions::table();
+    assert_eq!(table.len(), 0x100);
+    assert!(table.iter().enumerate().all(|(index, instruction)| instruction.op_code as usize == index));
+    assert_eq!(table[0x06].operands, vec!["B", "D8"]);
+    assert_eq!((table[0xcd].mnemonic.as_str(), table[0xcd].bytes, table[0xcd].cycles), ("CALL", 3, 17));
+
+    let artifact: &str = include_str!("../../../disassembler/instructions.json");
+    assert!(disassembler::instructions::to_json(&table) == artifact, "instructions.json is out of date, regenerate it with disassembler --instruction-table");
+}
+
+#[test]
+fn test_handler_table() {
+    // Every handler in the table does exactly what the match does for its op code
+
+    let mut start: Cpu = Cpu::init();
+    for address in 0..=0xffff_u16 {
+        start.memory.poke(address, (address as u8).wrapping_mul(0x3b) ^ (address >> 8) as u8);
+    }
+    let mut registers: Registers = start.registers();
+    (registers.a, registers.b, registers.c, registers.d, registers.e, registers.h, registers.l) = (0x9c, 0x12, 0x34, 0x56, 0x78, 0x21, 0x43);
+    (registers.sp, registers.pc) = (0x3000, 0x1801);
+    start.set_registers(registers);
+
+    for op_code in 0..=0xff_u8 {
+        let (mut matched, mut table): (Cpu, Cpu) = (start.clone(), start.clone());
+        assert_eq!(dispatcher::handle_op_code_match(op_code, &mut matched), dispatcher::HANDLERS[op_code as usize](&mut table), "0x{:02x}", op_code);
+        assert_eq!(matched.registers(), table.registers(), "0x{:02x}", op_code);
+        assert!(matched.memory.bytes() == table.memory.bytes(), "0x{:02x}", op_code);
+    }
+}
+
+#[test]
+fn test_cpu_diff() {
+    let mut before: Cpu = Cpu::init();
+    before.a.value = 0x12;
+    before.memory.write_at(0x2000, 0x00);
+    let mut after: Cpu = before.clone();
+    assert!(before.diff(&after).is_empty());
+
+    after.a.value = 0x13;
+    after.sp.address = 0x23fe;
+    after.flags.set_flag(Flag::CY);
+    after.interrupt_enabled = false;
+    after.halted = true;
+    after.memory.write_at(0x2000, 0x01);
+    after.memory.write_at(0x0005, 0xff);
+    let deltas: Vec<StateDelta> = before.diff(&after);
+    assert_eq!(deltas, vec![
+        StateDelta::Register("A", 0x12, 0x13),
+        StateDelta::Pointer("SP", 0x2400, 0x23fe),
+        StateDelta::Flag(Flag::CY, false, true),
+        StateDelta::InterruptEnable(true, false),
+        StateDelta::Halted(false, true),
+        StateDelta::Memory(0x0005, 0x00, 0xff),
+        StateDelta::Memory(0x2000, 0x00, 0x01),
+    ]);
+    let lines: Vec<String> = deltas.iter().map(StateDelta::to_string).collect();
+    assert_eq!(lines[..3], ["A: 0x12 -> 0x13", "SP: 0x2400 -> 0x23fe", "CY: 0 -> 1"]);
+    assert_eq!(lines[6], "0x2000: 0x00 -> 0x01");
+}
+
+#[test]
+fn test_generate_interrupt() {
+    let mut cpu: Cpu = Cpu::init();
+    cpu.pc.address = 0x1235;
+    cpu.halted = true;
+    // HLT at 0x1234 left the pc on the next instruction
+
+    assert!(generate_interrupt(0xcf, &mut cpu));
+    assert_eq!((cpu.pc.address, cpu.sp.address), (0x0008, 0x23fe));
+    assert_eq!((cpu.memory.read_at(0x23fe), cpu.memory.read_at(0x23ff)), (0x35, 0x12));
+    assert!(!cpu.interrupt_enabled);
+    // Taking an interrupt turns them off until the handler runs EI
+    assert!(!cpu.halted);
+
+    assert!(!generate_interrupt(0xd7, &mut cpu));
+    assert_eq!((cpu.pc.address, cpu.sp.address), (0x0008, 0x23fe));
+    // Not serviced while they're off, nothing is pushed
+}
+
+#[test]
+fn test_interrupt_after_ei() {
+    let mut machine: Machine = fixtures::machine("
+        LXI SP,0x2400
+    Loop: EI
+        DI
+        JMP Loop
+        ORG 0x08
+        INR D
+        RET
+        ORG 0x10
+        INR D
+        RET
+    ");
+    // The first mid screen interrupt comes due during an EI, 16500 cycles is 2 into the 18 cycle loop
+
+    for _ in 0..10 {
+        machine.run_frame().unwrap();
+    }
+    assert_eq!(machine.cpu.registers().d, 0);
+    // Held until the instruction after EI has run, which turns them off again
+}
+
+#[test]
+fn test_conditional_call_cycles() {
+    let mut machine: Machine = fixtures::machine("
+        XRA A
+        CNZ Sub
+        CZ Sub
+        CC Sub
+        CNC Sub
+        HLT
+        ORG 0x20
+    Sub: RNZ
+        RZ
+    ");
+    // XRA leaves Z set and CY clear
+
+    let mut steps: Vec<(u16, u64)> = vec![];
+    while !machine.cpu.halted() {
+        steps.push((machine.cpu.pc.address, machine.step().unwrap()));
+    }
+    assert_eq!(steps, vec![
+        (0x0000, 4),
+        (0x0001, 11),
+        // CNZ not taken
+        (0x0004, 17),
+        (0x0020, 5),
+        // RNZ not taken
+        (0x0021, 11),
+        (0x0007, 11),
+        // CC not taken
+        (0x000a, 17),
+        (0x0020, 5),
+        (0x0021, 11),
+        (0x000d, 7),
+    ]);
+
+    for op_code in [0xc4, 0xcc, 0xd4, 0xdc, 0xe4, 0xec, 0xf4, 0xfc] {
+        assert_eq!(dispatcher::CLOCK_CYCLES[op_code], 17);
+        assert!(dispatcher::is_conditional_call_or_return(op_code as u8));
+    }
+    // Every conditional call is 17 taken and 11 not
+    assert!(!dispatcher::is_conditional_call_or_return(0xcd) && !dispatcher::is_conditional_call_or_return(0xc9));
+}
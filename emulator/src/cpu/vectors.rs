@@ -0,0 +1,265 @@
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::*;
+
+const FLAG_MASK: u8 = 0b1101_0101;
+// Only S, Z, AC, P and CY are stored, the unused bits of the flag byte aren't compared
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestVector {
+    // A single instruction test in the format used by the community 8080 test suite
+    pub name: String,
+    pub initial: VectorState,
+    #[serde(rename = "final")]
+    pub expected: VectorState,
+    pub cycles: Vec<serde_json::Value>,
+    // One entry of bus activity per clock cycle, only the count is compared
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VectorState {
+    pub pc: u16,
+    pub sp: u16,
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub f: u8,
+    pub h: u8,
+    pub l: u8,
+    pub ram: Vec<(u16, u8)>,
+    // Address and value of every byte the test cares about
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    WrongState,
+    WrongCycles,
+    // Final state matched but the instruction took a different number of cycles
+    Crashed,
+    Skipped,
+    // IN and OUT need the hardware module so can't be tested on the cpu alone
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpcodeResults {
+    pub passed: usize,
+    pub wrong_state: usize,
+    pub wrong_cycles: usize,
+    pub crashed: usize,
+    pub skipped: usize,
+}
+impl OpcodeResults {
+    fn record(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Passed => self.passed += 1,
+            Outcome::WrongState => self.wrong_state += 1,
+            Outcome::WrongCycles => self.wrong_cycles += 1,
+            Outcome::Crashed => self.crashed += 1,
+            Outcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.passed + self.wrong_state + self.wrong_cycles + self.crashed + self.skipped
+    }
+
+    fn symbol(&self) -> char {
+        // Worst outcome for the op code
+        if self.total() == 0 { ' ' }
+        else if self.crashed > 0 { '!' }
+        else if self.wrong_state > 0 { 'X' }
+        else if self.wrong_cycles > 0 { 'C' }
+        else if self.skipped > 0 { '-' }
+        else { '.' }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub opcodes: [OpcodeResults; 0x100],
+    pub failures: Vec<String>,
+    // Names of the failing vectors
+}
+impl Report {
+    pub fn new() -> Self {
+        Self {
+            opcodes: [OpcodeResults::default(); 0x100],
+            failures: vec![],
+        }
+    }
+
+    pub fn record(&mut self, vector: &TestVector, outcome: Outcome) {
+        let op_code: u8 = vector.initial.ram.iter()
+            .find(|(address, _)| *address == vector.initial.pc)
+            .map_or(0, |(_, value)| *value);
+        self.opcodes[op_code as usize].record(outcome);
+
+        if !matches!(outcome, Outcome::Passed | Outcome::Skipped) {
+            self.failures.push(format!("{}: {:?}", vector.name, outcome));
+        }
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn print_matrix(&self) {
+        // Prints a 16x16 grid of op codes
+        //  . passed  C wrong cycles  X wrong state  ! crashed  - skipped
+
+        println!("   {}", (0..16).map(|low| format!("{:x} ", low)).collect::<String>());
+        for high in 0..16 {
+            let row: String = (0..16)
+                .map(|low| format!("{} ", self.opcodes[high << 4 | low].symbol()))
+                .collect();
+            println!("{:x}  {}", high, row);
+        }
+
+        let totals: OpcodeResults = self.opcodes.iter().fold(OpcodeResults::default(), |mut totals, results| {
+            totals.passed += results.passed;
+            totals.wrong_state += results.wrong_state;
+            totals.wrong_cycles += results.wrong_cycles;
+            totals.crashed += results.crashed;
+            totals.skipped += results.skipped;
+            totals
+        });
+        println!(
+            "Passed: {}  Wrong state: {}  Wrong cycles: {}  Crashed: {}  Skipped: {}",
+            totals.passed, totals.wrong_state, totals.wrong_cycles, totals.crashed, totals.skipped
+        );
+    }
+}
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn load_vectors(path: &Path) -> io::Result<Vec<TestVector>> {
+    // Each file holds every test for a single op code
+    serde_json::from_slice(&fs::read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn set_state(cpu: &mut Cpu, state: &VectorState) {
+    cpu.pc.address = state.pc;
+    cpu.sp.address = state.sp;
+    cpu.a.value = state.a;
+    cpu.b.value = state.b;
+    cpu.c.value = state.c;
+    cpu.d.value = state.d;
+    cpu.e.value = state.e;
+    cpu.flags = Flags::from_bits(state.f & FLAG_MASK);
+    cpu.h.value = state.h;
+    cpu.l.value = state.l;
+
+    for (address, value) in &state.ram {
+        cpu.memory.poke(*address, *value);
+    }
+}
+
+fn matches_state(cpu: &Cpu, state: &VectorState) -> bool {
+    let registers: [u8; 8] = [cpu.a.value, cpu.b.value, cpu.c.value, cpu.d.value, cpu.e.value, cpu.h.value, cpu.l.value, cpu.flags.bits() & FLAG_MASK];
+    let expected: [u8; 8] = [state.a, state.b, state.c, state.d, state.e, state.h, state.l, state.f & FLAG_MASK];
+
+    registers == expected
+        && cpu.pc.address == state.pc
+        && cpu.sp.address == state.sp
+        && state.ram.iter().all(|(address, value)| cpu.memory.read_at(*address) == *value)
+}
+
+fn execute(cpu: &mut Cpu) {
+    // Runs one instruction the same way machine::step does but without any hardware
+
+    let op_code: u8 = cpu.memory.read_at(cpu.pc.address);
+    cpu.pc.address = cpu.pc.address.wrapping_add(1);
+
+    match handle_op_code(op_code, cpu) {
+        Ok(255) => {},
+        // HLT leaves pc after the instruction
+        Ok(additional_bytes) => cpu.pc.address = cpu.pc.address.wrapping_add(additional_bytes),
+        Err(_) => {},
+    }
+}
+
+pub fn run_vector(cpu: &mut Cpu, vector: &TestVector) -> Outcome {
+    // Runs a single test vector on the given cpu
+    //  Only the addresses the vector uses are cleared afterwards so the cpu can be reused
+    //  without clearing all of memory for every test
+
+    let op_code: u8 = vector.initial.ram.iter()
+        .find(|(address, _)| *address == vector.initial.pc)
+        .map_or(0, |(_, value)| *value);
+    if matches!(op_code, 0xd3 | 0xdb) {
+        return Outcome::Skipped;
+    }
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        set_state(cpu, &vector.initial);
+        execute(cpu);
+        let state_matches: bool = matches_state(cpu, &vector.expected);
+
+        for (address, _) in vector.initial.ram.iter().chain(vector.expected.ram.iter()) {
+            cpu.memory.poke(*address, 0);
+        }
+
+        state_matches
+    }));
+    // Memory out of range and arithmetic overflow panic instead of wrapping
+
+    let outcome: Outcome = match result {
+        Err(_) => {
+            cpu.reset();
+            // Memory is left in an unknown state so start over with a clean cpu
+            Outcome::Crashed
+        },
+        Ok(false) => Outcome::WrongState,
+        Ok(true) if dispatcher::CLOCK_CYCLES[op_code as usize] as usize != vector.cycles.len() => Outcome::WrongCycles,
+        Ok(true) => Outcome::Passed,
+    };
+
+    outcome
+}
+
+pub fn run_directory(directory: &Path) -> io::Result<Report> {
+    // Runs every .json test vector file in a directory
+
+    let mut report: Report = Report::new();
+    let mut cpu: Cpu = Cpu::init();
+
+    let mut paths: Vec<_> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    // Crashes are reported in the matrix so the panic messages would just be noise
+
+    for path in paths {
+        let vectors: Vec<TestVector> = match load_vectors(&path) {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                panic::set_hook(default_hook);
+                return Err(e);
+            },
+        };
+
+        for vector in &vectors {
+            let outcome: Outcome = run_vector(&mut cpu, vector);
+            report.record(vector, outcome);
+        }
+    }
+
+    panic::set_hook(default_hook);
+
+    Ok(report)
+}
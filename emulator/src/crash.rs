@@ -0,0 +1,82 @@
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use disassembler::Operation;
+
+use crate::error::EmulatorError;
+#[cfg(feature = "serde")]
+use crate::headless::{self, StateDump};
+use crate::machine::Machine;
+#[cfg(feature = "serde")]
+use crate::palette::Palette;
+
+mod tests;
+
+pub const DIRECTORY: &str = "crashes";
+// Where crash dumps go when no other folder is given, relative to the working directory
+
+#[cfg(feature = "serde")]
+pub fn write(machine: &Machine, palette: &Palette, fault: &EmulatorError, frames: u32, directory: &Path) -> Result<PathBuf, String> {
+    // Saves everything needed to look into a fault to a new folder and returns its path
+    //      state.json      registers, cycles and ram hash in the same format as --headless --dump
+    //      trace.txt       the last instructions run, ending with the one that faulted
+    //      screen.png      the screen when the machine stopped
+
+    let path: PathBuf = directory.join(format!("crash-{}", timestamp(SystemTime::now())));
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    StateDump::capture(machine, frames, Some(fault.to_string())).save(&path.join("state.json"))?;
+    fs::write(path.join("trace.txt"), trace(machine, fault))
+        .map_err(|e| format!("Failed to write {}: {}", path.join("trace.txt").display(), e))?;
+    headless::save_png(&machine.cpu, machine.profile().display.geometry(), palette, &path.join("screen.png"))?;
+
+    Ok(path)
+}
+
+pub fn trace(machine: &Machine, fault: &EmulatorError) -> String {
+    // Recent instructions disassembled from memory as it is now, so self modified code shows its latest bytes
+
+    let mut lines: Vec<String> = vec![format!("{}", fault), machine.cpu.registers().to_string()];
+    lines.extend(recent_instructions(machine));
+    if let Some(last) = lines.last_mut().filter(|_| machine.trace().last() == Some(fault.address)) {
+        last.push_str("    <- fault");
+    }
+
+    lines.join("\n") + "\n"
+}
+
+pub fn recent_instructions(machine: &Machine) -> Vec<String> {
+    // The machine's trace ring disassembled, oldest first
+
+    machine.trace()
+        .map(|address| {
+            let view: &[u8] = machine.cpu.memory.view(address..address.saturating_add(3));
+            let ops: Vec<Operation> = disassembler::decode_from(view, address);
+            ops.first().map_or(format!("{:04x}", address), |op| op.to_string())
+        })
+        .collect()
+}
+
+pub fn timestamp(time: SystemTime) -> String {
+    // UTC time as YYYYMMDD-HHMMSS so dumps sort in the order they happened
+    //  Days are turned into a date with the civil from days algorithm to avoid a date library
+
+    let seconds: u64 = time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let (days, time_of_day): (i64, u64) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    let shifted: i64 = days + 719_468;
+    // Counted from 0000-03-01 so leap days fall at the end of each year
+    let era: i64 = shifted.div_euclid(146_097);
+    let day_of_era: i64 = shifted - era * 146_097;
+    let year_of_era: i64 = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year: i64 = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, time_of_day / 3_600, time_of_day / 60 % 60, time_of_day % 60)
+}
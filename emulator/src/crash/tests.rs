@@ -0,0 +1,43 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::time::Duration;
+#[cfg(all(test, feature = "serde"))]
+use crate::profile::Profile;
+
+#[test]
+fn test_timestamp() {
+    assert_eq!(timestamp(UNIX_EPOCH), "19700101-000000");
+    assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)), "20231114-221320");
+    assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)), "20000229-000000");
+    // Leap day in a year divisible by 400
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_crash_dump() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,       // 0x0000 DI
+        0x3c,       // 0x0001 INR A
+        0xd3, 0x07, // 0x0002 OUT 7
+    ]).unwrap();
+    let fault: EmulatorError = machine.run_frame().unwrap_err();
+
+    let text: String = trace(&machine, &fault);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], fault.to_string());
+    assert_eq!(lines[1], machine.cpu.registers().to_string());
+    assert!(lines[4].starts_with("0002   d3 07"));
+    assert!(lines[4].ends_with("<- fault"));
+
+    let directory: PathBuf = std::env::temp_dir().join(format!("emulator-crash-test-{}", std::process::id()));
+    let palette: Palette = Palette::new(&Profile::space_invaders().overlay).unwrap();
+    let path: PathBuf = write(&machine, &palette, &fault, 0, &directory).unwrap();
+    assert!(path.starts_with(&directory));
+    for file in ["state.json", "trace.txt", "screen.png"] {
+        assert!(path.join(file).is_file(), "{} is missing", file);
+    }
+    let _ = fs::remove_dir_all(&directory);
+}
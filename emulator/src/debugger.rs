@@ -0,0 +1,546 @@
+use std::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "serde")]
+use std::path::PathBuf;
+
+use disassembler::syntax::Syntax;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cpu::{Permissions, Registers};
+use crate::error::EmulatorError;
+use crate::machine::{Event, Machine};
+use crate::rewind::Rewind;
+use breakpoints::{Breakpoints, Entry};
+use hex::HexViewer;
+use journal::Journal;
+use crate::timeline::Kind;
+
+pub mod breakpoints;
+mod commands;
+pub mod hex;
+pub mod journal;
+#[cfg(feature = "serde")]
+pub mod session;
+mod tests;
+
+const WATCH_LOG_LIMIT: usize = 10_000;
+// Oldest logged watch hits are dropped past this so a noisy watch can't use up memory
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Breakpoint {
+    Address(u16),
+    // Break before the instruction at this address executes
+    Interrupt,
+    // Break after any interrupt is serviced
+    PortRead(u8),
+    PortWrite(u8),
+    InterruptEnable,
+    // Break after EI or DI executes
+    MemoryWrite(u16),
+    // Break after an instruction writes to this address, as it is after mirroring
+}
+impl Breakpoint {
+    fn hit_by(&self, event: &Event) -> bool {
+        match (self, event) {
+            (Breakpoint::Interrupt, Event::Interrupt(_)) => true,
+            (Breakpoint::PortRead(port), Event::PortRead(read)) => port == read,
+            (Breakpoint::PortWrite(port), Event::PortWrite(written, _)) => port == written,
+            (Breakpoint::InterruptEnable, Event::InterruptsEnabled | Event::InterruptsDisabled) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WatchedRegister {
+    A,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    Flags,
+    SP,
+    PC,
+}
+impl WatchedRegister {
+    pub const ALL: [WatchedRegister; 10] = [
+        WatchedRegister::A,
+        WatchedRegister::B,
+        WatchedRegister::C,
+        WatchedRegister::D,
+        WatchedRegister::E,
+        WatchedRegister::H,
+        WatchedRegister::L,
+        WatchedRegister::Flags,
+        WatchedRegister::SP,
+        WatchedRegister::PC,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::A => "a",
+            Self::B => "b",
+            Self::C => "c",
+            Self::D => "d",
+            Self::E => "e",
+            Self::H => "h",
+            Self::L => "l",
+            Self::Flags => "f",
+            Self::SP => "sp",
+            Self::PC => "pc",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::ALL.into_iter()
+            .find(|register| register.name() == text.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("{} is not a register, try one of {}", text, Self::ALL.map(|register| register.name()).join(", ")))
+    }
+
+    fn set(&self, registers: &mut Registers, value: u16) -> Result<(), String> {
+        let byte = || u8::try_from(value).map_err(|_| format!("{} only holds a byte, 0x{:04x} is too big", self.name(), value));
+        match self {
+            Self::A => registers.a = byte()?,
+            Self::B => registers.b = byte()?,
+            Self::C => registers.c = byte()?,
+            Self::D => registers.d = byte()?,
+            Self::E => registers.e = byte()?,
+            Self::H => registers.h = byte()?,
+            Self::L => registers.l = byte()?,
+            Self::Flags => registers.flags = byte()?,
+            Self::SP => registers.sp = value,
+            Self::PC => registers.pc = value,
+        }
+        Ok(())
+    }
+
+    fn value(&self, registers: &Registers) -> u16 {
+        match self {
+            Self::A => registers.a as u16,
+            Self::B => registers.b as u16,
+            Self::C => registers.c as u16,
+            Self::D => registers.d as u16,
+            Self::E => registers.e as u16,
+            Self::H => registers.h as u16,
+            Self::L => registers.l as u16,
+            Self::Flags => registers.flags as u16,
+            Self::SP => registers.sp,
+            Self::PC => registers.pc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WatchCondition {
+    Changed,
+    ChangedBy(u16),
+    // Moved further than this in either direction, e.g. SP jumping by more than a push or pop
+    Becomes(u16),
+    // Changed to this value from something else
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WatchAction {
+    Break,
+    Log,
+    // Recorded in the watch log and the machine keeps running
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Watch {
+    pub register: WatchedRegister,
+    pub condition: WatchCondition,
+    pub action: WatchAction,
+}
+impl Watch {
+    fn triggered(&self, old: u16, new: u16) -> bool {
+        match self.condition {
+            WatchCondition::Changed => old != new,
+            WatchCondition::ChangedBy(limit) => old.wrapping_sub(new).min(new.wrapping_sub(old)) > limit,
+            // Shortest distance around the wrap so SP going from 0x0000 to 0xfffe is a change of 2
+            WatchCondition::Becomes(value) => old != value && new == value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watch: Watch,
+    pub address: u16,
+    // Instruction that made the change
+    pub old: u16,
+    pub new: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame {
+    pub call_site: u16,
+    // Address of the CALL or RST, or the address an interrupt was taken at
+    pub target: u16,
+    pub sp: u16,
+    // Where the return address was pushed, the frame has returned once SP moves above it
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfWrite {
+    pub address: u16,
+    // Instruction that did the write, an interrupt it let in included
+    pub target: u16,
+    pub rom: bool,
+    // Read only memory so the write was ignored, otherwise it overwrote code that has run and went through
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Break {
+    // Why the debugger stopped the machine
+    Address(u16),
+    Event(Event),
+    Watch(WatchHit),
+    Fault(EmulatorError),
+    // The machine can't carry on, pc is left on the instruction that failed
+    SelfWrite(SelfWrite),
+    // Something wrote over the rom or code that has run, usually a runaway stack or a bad pointer
+    MemoryWrite { address: u16, target: u16 },
+    // The instruction at address wrote to a memory breakpoint
+}
+
+#[derive(Clone)]
+pub struct Debugger {
+    breakpoints: Breakpoints,
+    watches: Vec<Watch>,
+    watch_log: VecDeque<WatchHit>,
+    calls: Vec<CallFrame>,
+    // Calls that haven't returned yet, innermost last
+    history: Rewind,
+    instructions: u64,
+    // Instructions executed through the debugger, used to find positions in the history
+    assembling: Option<u16>,
+    // Next address asm mode writes to while it is on
+    comments: BTreeMap<u16, String>,
+    // Notes on addresses shown alongside the disassembly
+    #[cfg(feature = "serde")]
+    session: Option<PathBuf>,
+    // Folder the session is saved to after every command once one has been loaded
+    trap_self_writes: bool,
+    journal: Journal,
+    // Memory and registers changed by hand since the machine last ran
+    revert_on_resume: bool,
+    syntax: Syntax,
+    // How dis writes instructions
+    hex: HexViewer,
+}
+impl Debugger {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Breakpoints::new(),
+            watches: vec![],
+            watch_log: VecDeque::new(),
+            calls: vec![],
+            history: Rewind::default(),
+            instructions: 0,
+            assembling: None,
+            comments: BTreeMap::new(),
+            #[cfg(feature = "serde")]
+            session: None,
+            trap_self_writes: true,
+            journal: Journal::new(),
+            revert_on_resume: false,
+            syntax: Syntax::Intel,
+            hex: HexViewer::default(),
+        }
+    }
+
+    pub fn with_history(history: Rewind) -> Self {
+        Self {
+            history,
+            ..Self::new()
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if self.breakpoints.get(breakpoint).is_none() {
+            self.breakpoints.set(Entry::new(breakpoint));
+        }
+    }
+
+    pub fn set_breakpoint(&mut self, entry: Entry) {
+        // Adds a breakpoint with an ignore count or as a temporary one, or changes one already set
+        self.breakpoints.set(entry);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.remove(breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        self.breakpoints.entries().iter().map(|entry| entry.breakpoint).collect()
+    }
+
+    pub fn breakpoint(&self, breakpoint: Breakpoint) -> Option<&Entry> {
+        // How many times it has been hit and how it behaves
+        self.breakpoints.get(breakpoint)
+    }
+
+    pub fn add_watch(&mut self, watch: Watch) {
+        if !self.watches.contains(&watch) {
+            self.watches.push(watch);
+        }
+    }
+
+    pub fn remove_watch(&mut self, watch: Watch) {
+        self.watches.retain(|existing| *existing != watch);
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        self.watches.as_slice()
+    }
+
+    pub fn set_comment(&mut self, address: u16, text: &str) {
+        // An empty comment removes the one already there
+        match text.trim() {
+            "" => self.comments.remove(&address),
+            text => self.comments.insert(address, text.to_string()),
+        };
+    }
+
+    pub fn comment(&self, address: u16) -> Option<&str> {
+        self.comments.get(&address).map(String::as_str)
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = (u16, &str)> {
+        // In address order
+        self.comments.iter().map(|(address, text)| (*address, text.as_str()))
+    }
+
+    pub fn hex_viewer(&self) -> &HexViewer {
+        &self.hex
+    }
+
+    pub fn move_hex_cursor(&mut self, direction: hex::Move) {
+        self.hex.move_cursor(direction);
+    }
+
+    pub fn type_hex(&mut self, digit: char, machine: &mut Machine) -> Result<(), String> {
+        // A hex digit typed at the hex viewer's cursor, frontends pass on keys while the viewer has focus
+        let mut viewer: HexViewer = self.hex;
+        let typed: Result<(), String> = viewer.type_digit(digit, self, machine);
+        self.hex = viewer;
+        typed
+    }
+
+    pub fn set_self_write_trap(&mut self, enabled: bool) {
+        // On by default, off for code that really does modify itself
+        self.trap_self_writes = enabled;
+    }
+
+    pub fn self_write_trap(&self) -> bool {
+        self.trap_self_writes
+    }
+
+    fn check_self_writes(&self, address: u16, machine: &Machine) -> Option<SelfWrite> {
+        // Writes to the rom are caught by its permissions, writes to ram are caught when the
+        //  coverage map says an instruction there has already run
+
+        machine.cpu.memory.writes().iter().find_map(|target| {
+            let rom: bool = machine.cpu.memory.permissions_at(*target) == Permissions::ReadOnly;
+            (rom || machine.coverage.is_executed(*target)).then_some(SelfWrite { address, target: *target, rom })
+        })
+    }
+
+    pub fn watch_log(&self) -> impl Iterator<Item = &WatchHit> {
+        // Hits of logging watches, oldest first
+        self.watch_log.iter()
+    }
+
+    pub fn clear_watch_log(&mut self) {
+        self.watch_log.clear();
+    }
+
+    fn check_watches(&mut self, before: &Registers, after: &Registers) -> Option<WatchHit> {
+        // Compares the registers from either side of an instruction against every watch
+        //  Logging watches are all recorded, the first breaking watch is returned
+
+        let mut hit: Option<WatchHit> = None;
+        for watch in &self.watches {
+            let (old, new): (u16, u16) = (watch.register.value(before), watch.register.value(after));
+            if !watch.triggered(old, new) {
+                continue;
+            }
+
+            let watch_hit: WatchHit = WatchHit { watch: *watch, address: before.pc, old, new };
+            match watch.action {
+                WatchAction::Break => hit = hit.or(Some(watch_hit)),
+                WatchAction::Log => {
+                    if self.watch_log.len() == WATCH_LOG_LIMIT {
+                        self.watch_log.pop_front();
+                    }
+                    self.watch_log.push_back(watch_hit);
+                },
+            }
+        }
+        hit
+    }
+
+    pub fn call_stack(&self) -> &[CallFrame] {
+        // Calls the machine is inside of, outermost first
+        //  Worked out from the instructions run through the debugger so it starts empty
+        self.calls.as_slice()
+    }
+
+    fn track_calls(&mut self, before: &Registers, op_code: u8, machine: &Machine) {
+        // Follows SP across an instruction to keep the call stack up to date
+        //  Returns are spotted by SP rising above a frame rather than by RET so
+        //  code that drops its return address with POP or LXI SP unwinds too
+
+        let after: Registers = machine.cpu.registers();
+        let interrupted: bool = machine.last_events().iter().any(|event| matches!(event, Event::Interrupt(_)));
+        let sp: u16 = if interrupted { after.sp.wrapping_add(2) } else { after.sp };
+        // SP straight after the instruction, before an interrupt pushed onto the stack
+
+        while self.calls.last().is_some_and(|frame| frame.sp < sp) {
+            self.calls.pop();
+        }
+
+        let is_call: bool = matches!(op_code, 0xcd | 0xc4 | 0xcc | 0xd4 | 0xdc | 0xe4 | 0xec | 0xf4 | 0xfc) || op_code & 0xc7 == 0xc7;
+        // CALL, conditional calls and RST
+        if is_call && sp == before.sp.wrapping_sub(2) {
+            let target: u16 = match interrupted {
+                true => pair(machine.cpu.memory.read_at(after.sp.wrapping_add(1)), machine.cpu.memory.read_at(after.sp)),
+                false => after.pc,
+            };
+            // An interrupt straight after the call pushed where the call went
+            self.calls.push(CallFrame { call_site: before.pc, target, sp });
+        }
+
+        if interrupted {
+            let return_address: u16 = pair(machine.cpu.memory.read_at(after.sp.wrapping_add(1)), machine.cpu.memory.read_at(after.sp));
+            self.calls.push(CallFrame { call_site: return_address, target: after.pc, sp: after.sp });
+        }
+    }
+
+    pub fn step(&mut self, machine: &mut Machine) -> (bool, Option<Break>) {
+        // Executes a single instruction
+        //  Returns whether the frame finished and the breakpoint that was hit if any
+        //  Breaks are added to the machine's timeline when it has one
+
+        self.resume(machine);
+        let (frame_done, hit): (bool, Option<Break>) = self.execute(machine);
+        let cycle: u64 = machine.cycles();
+        if let (Some(hit), Some(timeline)) = (hit.as_ref(), machine.timeline_mut()) {
+            let (kind, detail): (Kind, String) = match hit {
+                Break::Address(address) => (Kind::Breakpoint, format!("address 0x{:04x}", address)),
+                Break::Event(event) => (Kind::Breakpoint, format!("{:?}", event)),
+                Break::Watch(watch_hit) => (Kind::Breakpoint, format!("watch at 0x{:04x}, 0x{:04x} -> 0x{:04x}", watch_hit.address, watch_hit.old, watch_hit.new)),
+                Break::Fault(e) => (Kind::Fault, e.to_string()),
+                Break::SelfWrite(write) => (Kind::Fault, format!("0x{:04x} wrote to 0x{:04x}", write.address, write.target)),
+                Break::MemoryWrite { address, target } => (Kind::Breakpoint, format!("0x{:04x} wrote to 0x{:04x}", address, target)),
+            };
+            timeline.record(cycle, kind, detail);
+        }
+        (frame_done, hit)
+    }
+
+    fn execute(&mut self, machine: &mut Machine) -> (bool, Option<Break>) {
+        self.history.record(self.instructions, machine);
+        let before: Registers = machine.cpu.registers();
+        let op_code: u8 = machine.cpu.memory.read_at(before.pc);
+        machine.cpu.memory.clear_writes();
+        let frame_done: bool = match machine.step_frame() {
+            Ok(frame_done) => frame_done,
+            Err(e) => return (false, Some(Break::Fault(e))),
+        };
+        self.instructions += 1;
+        self.track_calls(&before, op_code, machine);
+
+        if let Some(write) = self.check_self_writes(before.pc, machine).filter(|_| self.trap_self_writes) {
+            return (frame_done, Some(Break::SelfWrite(write)));
+        }
+        // Checked first since nothing after it can be trusted
+
+        for event in machine.last_events() {
+            if self.breakpoints.hit(|breakpoint| breakpoint.hit_by(event)).is_some() {
+                return (frame_done, Some(Break::Event(*event)));
+            }
+        }
+        for target in machine.cpu.memory.writes() {
+            if self.breakpoints.hit(|breakpoint| *breakpoint == Breakpoint::MemoryWrite(*target)).is_some() {
+                return (frame_done, Some(Break::MemoryWrite { address: before.pc, target: *target }));
+            }
+        }
+
+        if !self.watches.is_empty() {
+            if let Some(hit) = self.check_watches(&before, &machine.cpu.registers()) {
+                return (frame_done, Some(Break::Watch(hit)));
+            }
+        }
+
+        let pc: u16 = machine.cpu.pc.address;
+        if self.breakpoints.hit(|breakpoint| *breakpoint == Breakpoint::Address(pc)).is_some() {
+            return (frame_done, Some(Break::Address(pc)));
+        }
+
+        (frame_done, None)
+    }
+
+    pub fn run_frame(&mut self, machine: &mut Machine) -> Option<Break> {
+        // Runs the machine to the end of the frame unless a breakpoint is hit first
+        //  Running again after a break resumes the frame where it stopped
+
+        loop {
+            match self.step(machine) {
+                (_, Some(hit)) => return Some(hit),
+                (true, None) => return None,
+                (false, None) => {},
+            }
+        }
+    }
+
+    pub fn step_back(&mut self, machine: &mut Machine) -> Result<(), &'static str> {
+        // Puts the machine back to how it was before the last instruction
+        //  Restores the nearest snapshot then runs forward again up to the previous instruction
+
+        if self.instructions == 0 {
+            return Err("No instructions to step back over");
+        }
+        let target: u64 = self.instructions - 1;
+
+        let Some((taken, snapshot)) = self.history.nearest(target) else {
+            return Err("Instruction is older than the rewind history");
+        };
+        machine.restore(snapshot);
+        for _ in taken..target {
+            let _ = machine.step_frame();
+        }
+        // Breakpoints are ignored while replaying since those instructions have already run
+        self.instructions = target;
+        let sp: u16 = machine.cpu.registers().sp;
+        self.calls.retain(|frame| frame.sp >= sp);
+        // Calls made by the undone instruction are dropped, ones it returned from can't be brought back
+
+        Ok(())
+    }
+
+    pub fn clear_history(&mut self) {
+        // Must be called when the machine is changed outside the debugger, like new input
+        //  being latched, loading a state or resetting, otherwise replays won't match
+        self.history.clear();
+        self.instructions = 0;
+        self.calls.clear();
+    }
+}
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pair(high: u8, low: u8) -> u16 {
+    (high as u16) << 8 | low as u16
+}
@@ -0,0 +1,97 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::Breakpoint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Entry {
+    // A breakpoint and what happens when it is hit
+    pub breakpoint: Breakpoint,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ignore: u64,
+    // Hits still to be let through before it breaks, breaking on the 37th write means ignoring 36
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub temporary: bool,
+    // Removed the first time it breaks
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub hits: u64,
+    // Every hit since it was set, ignored ones included, not saved with the session
+}
+impl Entry {
+    pub fn new(breakpoint: Breakpoint) -> Self {
+        Self {
+            breakpoint,
+            ignore: 0,
+            temporary: false,
+            hits: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakpoints {
+    // Breakpoints in the order they were set, each only once
+    entries: Vec<Entry>,
+}
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, breakpoint: Breakpoint) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.breakpoint == breakpoint)
+    }
+
+    pub fn get_mut(&mut self, breakpoint: Breakpoint) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|entry| entry.breakpoint == breakpoint)
+    }
+
+    pub fn set(&mut self, entry: Entry) {
+        // Setting a breakpoint again changes how it behaves but keeps its hits
+        match self.get_mut(entry.breakpoint) {
+            Some(existing) => *existing = Entry { hits: existing.hits, ..entry },
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn remove(&mut self, breakpoint: Breakpoint) -> bool {
+        let count: usize = self.entries.len();
+        self.entries.retain(|entry| entry.breakpoint != breakpoint);
+        self.entries.len() != count
+    }
+
+    pub fn hit(&mut self, hit_by: impl Fn(&Breakpoint) -> bool) -> Option<Breakpoint> {
+        // Counts a hit on every breakpoint that matches and returns the first one that breaks
+        //  Ignored hits use up the ignore count, temporary breakpoints go once they break
+
+        let mut broke: Option<Breakpoint> = None;
+        for entry in self.entries.iter_mut().filter(|entry| hit_by(&entry.breakpoint)) {
+            entry.hits += 1;
+            if entry.ignore > 0 {
+                entry.ignore -= 1;
+                continue;
+            }
+            broke = broke.or(Some(entry.breakpoint));
+        }
+        if let Some(breakpoint) = broke.filter(|breakpoint| self.get(*breakpoint).is_some_and(|entry| entry.temporary)) {
+            self.remove(breakpoint);
+        }
+        broke
+    }
+}
+impl From<Vec<Entry>> for Breakpoints {
+    fn from(entries: Vec<Entry>) -> Self {
+        let mut breakpoints: Breakpoints = Breakpoints::new();
+        entries.into_iter().for_each(|entry| breakpoints.set(entry));
+        breakpoints
+    }
+}
@@ -0,0 +1,502 @@
+use std::fs;
+use std::path::Path;
+
+use disassembler::Operation;
+use disassembler::comments;
+use disassembler::search::{self, Pattern};
+use disassembler::symbols::SymbolTable;
+
+use super::*;
+use crate::assembler;
+#[cfg(feature = "serde")]
+use crate::savestate::{self, SaveState};
+
+const DEFAULT_LISTING: usize = 10;
+// Instructions shown by dis when no count is given
+const FIND_LIMIT: usize = 32;
+// Matches listed by find, the rest are only counted
+
+impl Debugger {
+    pub fn command(&mut self, line: &str, machine: &mut Machine, symbols: &SymbolTable) -> Result<String, String> {
+        // Runs a typed debugger command and returns what to show
+        //  Anywhere a location is taken it can be an address like 0x18d4 or a symbol like DrawAlien+3
+        //      bp <location> [options]     Break before the instruction at location
+        //      bw <location> [options]     Break after an instruction writes to location
+        //                                  options are once to clear it when it breaks and after <n>
+        //                                  to let n hits through first, bw 0x20f8 after 36 breaks on the 37th
+        //      be <event>                  Break after an event, one of interrupt for any interrupt being
+        //                                  serviced, ei for EI or DI, in <port> or out <port>
+        //      bc <location|event>         Clear the breakpoints at location or on the event
+        //      bl                          List breakpoints with a number each and how often they were hit
+        //      ignore <n> <count>          Let the next count hits on breakpoint n through
+        //      watch <register> [condition] [log]
+        //                                  Break after an instruction changes a register, condition is by <n> for
+        //                                  moving more than n either way or is <value> for becoming value, log
+        //                                  records the change in the watch log and carries on instead of breaking
+        //      unwatch <register>          Clear the watches on a register
+        //      watches                     List watches
+        //      watchlog [clear]            Show the changes logging watches have seen, or forget them
+        //      bt                          Show the call stack
+        //      regs                        Show the registers and flags
+        //      step [count]                Run count instructions, 1 by default, stopping early on a breakpoint
+        //      step-back                   Put the machine back to before the last instruction the debugger ran
+        //      dis [location] [count]      Disassemble from location, the pc by default
+        //      syntax <intel|z80>          Write dis listings with Intel or Z80 mnemonics
+        //      hex [location] [rows]       Show memory as hex bytes from location, where the hex cursor is by default
+        //                                  frontends move the cursor with the arrows and type over bytes with hex digits
+        //      find <pattern>              List where hex bytes appear in memory, ?? matches any byte
+        //                                  and ? any digit, find CD ?? 01 finds calls into 0x01xx
+        //      statediff <file>            Show memory that has changed since a save state was made
+        //      asm <location>              Assemble each following line into memory from location,
+        //                                  rom included, until a blank line
+        //      int status                  Show whether interrupts are enabled and when the next one is due
+        //      int raise <n>               Request RST n now, the cpu only takes it with interrupts enabled
+        //      int block                   Stop or restart the board's own interrupts
+        //      trap                        Turn breaking on writes over the rom or code that has run on or off
+        //      comment <location> [text]   Note shown beside location in dis, no text removes it
+        //      comments                    List comments
+        //      comments export <file>      Write a listing of the rom with comments in the disassembler's format
+        //      comments import <file>      Add the comments from a listing, replacing any on the same addresses
+        //      poke <location> <byte>...   Write bytes into memory from location, rom included
+        //      set <register> <value>      Change a register, one of a b c d e h l f sp pc
+        //      undo                        Put back the last poke, set or assembled line
+        //      redo                        Make an undone edit again
+        //      edits                       List the edits undo can put back
+        //      revert                      Undo every edit
+        //      autorevert                  Turn undoing every edit before the machine runs on or off
+        //  Edits can only be undone until the machine runs again, after that they are part of the run
+        //  Once a session has been loaded it is saved after every command so nothing is lost if the
+        //  emulator is closed without warning
+
+        let output: String = self.run_command(line, machine, symbols)?;
+        #[cfg(feature = "serde")]
+        if let Some(directory) = self.session.clone() {
+            self.save_session(&directory, symbols, machine.rom())?;
+        }
+        Ok(output)
+    }
+
+    fn run_command(&mut self, line: &str, machine: &mut Machine, symbols: &SymbolTable) -> Result<String, String> {
+        if let Some(address) = self.assembling {
+            return self.assemble_line(line, address, machine, symbols);
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let resolve = |text: &str| symbols.resolve(text).ok_or_else(|| format!("{} is not an address or a known symbol", text));
+
+        match words.as_slice() {
+            ["bp" | "bw", location, options @ ..] => {
+                let address: u16 = resolve(location)?;
+                let breakpoint: Breakpoint = match words[0] {
+                    "bp" => Breakpoint::Address(address),
+                    _ => Breakpoint::MemoryWrite(address),
+                };
+                let entry: Entry = breakpoint_options(Entry::new(breakpoint), options)?;
+                self.set_breakpoint(entry);
+                let mut output: String = match breakpoint {
+                    Breakpoint::MemoryWrite(_) => format!("Breakpoint on {}", describe_breakpoint(&breakpoint, symbols)),
+                    _ => format!("Breakpoint at {}", describe_breakpoint(&breakpoint, symbols)),
+                };
+                output.push_str(&describe_options(&entry));
+                Ok(output)
+            },
+            ["be", event @ ..] => {
+                let breakpoint: Breakpoint = event_breakpoint(event, symbols)?;
+                self.add_breakpoint(breakpoint);
+                Ok(format!("Breakpoint on {}", describe_breakpoint(&breakpoint, symbols)))
+            },
+            ["bc", event @ ("interrupt" | "ei" | "in" | "out"), ..] => {
+                let breakpoint: Breakpoint = event_breakpoint(&words[1..], symbols)?;
+                match self.breakpoints.remove(breakpoint) {
+                    true => Ok(format!("Cleared breakpoint on {}", describe_breakpoint(&breakpoint, symbols))),
+                    false => Err(format!("No breakpoint on {}", event)),
+                }
+            },
+            ["bc", location] => {
+                let address: u16 = resolve(location)?;
+                let cleared: usize = [Breakpoint::Address(address), Breakpoint::MemoryWrite(address)].into_iter()
+                    .filter(|breakpoint| self.breakpoints.remove(*breakpoint))
+                    .count();
+                match cleared {
+                    0 => Err(format!("No breakpoint at {}", describe(address, symbols))),
+                    _ => Ok(format!("Cleared breakpoint at {}", describe(address, symbols))),
+                }
+            },
+            ["bl"] if self.breakpoints.is_empty() => Ok(String::from("No breakpoints")),
+            ["bl"] => Ok(self.breakpoints.entries().iter()
+                .enumerate()
+                .map(|(index, entry)| format!("#{} {}  hits {}{}", index + 1, describe_breakpoint(&entry.breakpoint, symbols), entry.hits, describe_options(entry)))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["ignore", number, count] => {
+                let entry: Entry = number.parse::<usize>().ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| self.breakpoints.entries().get(index).copied())
+                    .ok_or_else(|| format!("{} is not a breakpoint number from bl", number))?;
+                let ignore: u64 = count.parse().map_err(|_| format!("{} is not a number of hits", count))?;
+                self.set_breakpoint(Entry { ignore, ..entry });
+                Ok(format!("Letting the next {} hits on {} through", ignore, describe_breakpoint(&entry.breakpoint, symbols)))
+            },
+            ["watch", register, options @ ..] => {
+                let watch: Watch = watch_options(WatchedRegister::parse(register)?, options, symbols)?;
+                self.add_watch(watch);
+                Ok(format!("Watching {}", describe_watch(&watch)))
+            },
+            ["unwatch", register] => {
+                let register: WatchedRegister = WatchedRegister::parse(register)?;
+                let watches: usize = self.watches.len();
+                self.watches.retain(|watch| watch.register != register);
+                match watches - self.watches.len() {
+                    0 => Err(format!("No watches on {}", register.name())),
+                    _ => Ok(format!("Cleared the watches on {}", register.name())),
+                }
+            },
+            ["watches"] if self.watches.is_empty() => Ok(String::from("No watches")),
+            ["watches"] => Ok(self.watches.iter()
+                .map(describe_watch)
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["watchlog"] if self.watch_log.is_empty() => Ok(String::from("Nothing logged")),
+            ["watchlog"] => Ok(self.watch_log.iter()
+                .map(|hit| format!("{} {} 0x{:04x} -> 0x{:04x}", describe(hit.address, symbols), hit.watch.register.name(), hit.old, hit.new))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["watchlog", "clear"] => {
+                self.clear_watch_log();
+                Ok(String::from("Cleared the watch log"))
+            },
+            ["bt"] => Ok(self.format_call_stack(machine, symbols)),
+            ["step", rest @ ..] if rest.len() <= 1 => {
+                let count: u64 = match rest.first() {
+                    Some(count) => count.parse().map_err(|_| format!("{} is not a number of instructions", count))?,
+                    None => 1,
+                };
+                for _ in 0..count {
+                    if let (_, Some(hit)) = self.step(machine) {
+                        return Ok(hit.describe(symbols) + "\n" + &listing(machine, machine.cpu.pc.address, 1, symbols, &self.comments, self.syntax));
+                    }
+                }
+                Ok(listing(machine, machine.cpu.pc.address, 1, symbols, &self.comments, self.syntax))
+            },
+            ["step-back"] => {
+                self.step_back(machine)?;
+                Ok(listing(machine, machine.cpu.pc.address, 1, symbols, &self.comments, self.syntax))
+            },
+            ["regs"] => Ok(machine.cpu.registers().to_string() + if machine.cpu.halted() { " HALT" } else { "" }),
+            ["asm", location] => {
+                let address: u16 = resolve(location)?;
+                self.assembling = Some(address);
+                Ok(format!("Assembling at {}, a blank line finishes", describe(address, symbols)))
+            },
+            ["find", pattern @ ..] => {
+                let pattern: Pattern = Pattern::parse(&pattern.join(" "))?;
+                let memory: &[u8] = machine.cpu.memory.bytes();
+                let found: Vec<u16> = pattern.find(memory, 0);
+                let mut lines: Vec<String> = vec![];
+                for address in found.iter().take(FIND_LIMIT) {
+                    let mut op: Operation = search::instruction_at(memory, *address as usize, 0);
+                    op.set_syntax(self.syntax);
+                    if let Some(name) = symbols.name(*address) {
+                        lines.push(format!("{}:", name));
+                    }
+                    lines.push(op.annotated(symbols));
+                }
+                lines.push(match found.len() {
+                    0 => format!("No matches of {}", pattern),
+                    count if count > FIND_LIMIT => format!("Matches of {}: {}, the first {} shown", pattern, count, FIND_LIMIT),
+                    count => format!("Matches of {}: {}", pattern, count),
+                });
+                Ok(lines.join("\n"))
+            },
+            #[cfg(feature = "serde")]
+            ["statediff", path] => {
+                let before: Vec<u8> = SaveState::load(Path::new(path))?.memory()?;
+                let differences: Vec<savestate::Difference> = savestate::diff(&before, machine.cpu.memory.bytes());
+                Ok(savestate::format_diff(&differences, machine.profile(), symbols))
+            },
+            ["int", "status"] => {
+                let enabled: &str = if machine.cpu.registers().interrupt_enabled { "enabled" } else { "disabled" };
+                let mut status: String = format!("Interrupts {}, RST {} due in {} cycles", enabled, machine.next_interrupt(), machine.cycles_to_interrupt());
+                if machine.interrupts_blocked() {
+                    status.push_str(", the board's interrupts are blocked");
+                }
+                if machine.cpu.halted() {
+                    status.push_str(", halted");
+                }
+                Ok(status)
+            },
+            ["int", "raise", rst] => {
+                let rst: u8 = rst.parse().ok().filter(|rst| *rst <= 7).ok_or_else(|| format!("{} is not an RST number from 0 to 7", rst))?;
+                match machine.raise_interrupt(rst) {
+                    true => {
+                        self.history.clear();
+                        self.instructions = 0;
+                        // Replaying from an older snapshot wouldn't raise it again
+                        Ok(format!("RST {} taken, now at {}", rst, describe(machine.cpu.pc.address, symbols)))
+                    },
+                    false => Err(format!("Interrupts are disabled, RST {} wasn't taken", rst)),
+                }
+            },
+            ["int", "block"] => {
+                machine.block_interrupts(!machine.interrupts_blocked());
+                self.history.clear();
+                self.instructions = 0;
+                match machine.interrupts_blocked() {
+                    true => Ok(String::from("The board's interrupts are blocked")),
+                    false => Ok(String::from("The board's interrupts are back on")),
+                }
+            },
+            ["trap"] => {
+                self.trap_self_writes = !self.trap_self_writes;
+                match self.trap_self_writes {
+                    true => Ok(String::from("Breaking on writes over the rom or code that has run")),
+                    false => Ok(String::from("Writes over the rom or code are let through")),
+                }
+            },
+            ["comment", location, ..] => {
+                let address: u16 = resolve(location)?;
+                let text: &str = line.trim()["comment".len()..].trim_start()[location.len()..].trim();
+                self.set_comment(address, text);
+                match text.is_empty() {
+                    true => Ok(format!("Removed comment at {}", describe(address, symbols))),
+                    false => Ok(format!("Comment at {}", describe(address, symbols))),
+                }
+            },
+            ["comments"] if self.comments.is_empty() => Ok(String::from("No comments")),
+            ["comments"] => Ok(self.comments.iter()
+                .map(|(address, comment)| format!("{} ; {}", describe(*address, symbols), comment))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["comments", "export", path] => {
+                let ops: Vec<Operation> = disassembler::decode(machine.cpu.memory.view(0..machine.rom().len().min(0xffff) as u16), &[]);
+                // What's in memory so code patched with asm is listed as it is now
+                fs::write(path, comments::listing(&ops, symbols, &self.comments) + "\n")
+                    .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+                Ok(format!("Exported {} comments to {}", self.comments.len(), path))
+            },
+            ["comments", "import", path] => {
+                let imported: BTreeMap<u16, String> = comments::load(Path::new(path))?;
+                let count: usize = imported.len();
+                self.comments.extend(imported);
+                Ok(format!("Imported {} comments from {}", count, path))
+            },
+            ["poke", location, bytes @ ..] if !bytes.is_empty() => {
+                let address: u16 = resolve(location)?;
+                let bytes: Vec<u8> = bytes.iter()
+                    .map(|byte| resolve(byte).and_then(|value| u8::try_from(value).map_err(|_| format!("{} is not a byte", byte))))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                self.poke(machine, address, &bytes);
+                Ok(format!("Wrote {} bytes at {}", bytes.len(), describe(address, symbols)))
+            },
+            ["set", register, value] => {
+                let register: WatchedRegister = WatchedRegister::parse(register)?;
+                self.set_register(machine, register, resolve(value)?)?;
+                Ok(machine.cpu.registers().to_string())
+            },
+            ["undo"] => Ok(format!("Undid {}", self.undo(machine)?)),
+            ["redo"] => Ok(format!("Redid {}", self.redo(machine)?)),
+            ["edits"] if self.journal.edits().is_empty() => Ok(String::from("No edits since the machine last ran")),
+            ["edits"] => Ok(self.journal.edits().iter()
+                .map(|edit| edit.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["revert"] => Ok(format!("Reverted {} edits", self.revert_edits(machine)?)),
+            ["autorevert"] => {
+                self.revert_on_resume = !self.revert_on_resume;
+                match self.revert_on_resume {
+                    true => Ok(String::from("Edits are undone before the machine runs on")),
+                    false => Ok(String::from("Edits are kept when the machine runs on")),
+                }
+            },
+            ["syntax", syntax] => {
+                self.syntax = Syntax::parse(syntax)?;
+                Ok(format!("Listing with {} mnemonics", self.syntax.name()))
+            },
+            ["hex", rest @ ..] if rest.len() <= 2 => {
+                if let Some(location) = rest.first() {
+                    self.hex.go_to(resolve(location)?);
+                }
+                if let Some(rows) = rest.get(1) {
+                    self.hex.set_rows(rows.parse().map_err(|_| format!("{} is not a number of rows", rows))?);
+                }
+                Ok(self.hex.lines(machine).join("\n"))
+            },
+            ["dis", rest @ ..] if rest.len() <= 2 => {
+                let start: u16 = match rest.first() {
+                    Some(location) => resolve(location)?,
+                    None => machine.cpu.pc.address,
+                };
+                let count: usize = match rest.get(1) {
+                    Some(count) => count.parse().map_err(|_| format!("{} is not a number of instructions", count))?,
+                    None => DEFAULT_LISTING,
+                };
+                Ok(listing(machine, start, count, symbols, &self.comments, self.syntax))
+            },
+            _ => Err(format!("Unknown command: {}", line.trim())),
+        }
+    }
+
+    pub fn assembling(&self) -> Option<u16> {
+        // Where the next line typed in asm mode will go, for showing as a prompt
+        self.assembling
+    }
+
+    fn assemble_line(&mut self, line: &str, address: u16, machine: &mut Machine, symbols: &SymbolTable) -> Result<String, String> {
+        // A bad line is reported and can be typed again, asm mode only ends on a blank line
+
+        if line.trim().is_empty() {
+            self.assembling = None;
+            return Ok(format!("Finished at {}", describe(address, symbols)));
+        }
+
+        let bytes: Vec<u8> = assembler::assemble(line, symbols)?;
+        self.poke(machine, address, &bytes);
+        self.assembling = Some(address.wrapping_add(bytes.len() as u16));
+        // Each line is its own edit so undo takes back one line at a time
+
+        Ok(listing(machine, address, 1, symbols, &self.comments, self.syntax))
+    }
+
+    fn format_call_stack(&self, machine: &Machine, symbols: &SymbolTable) -> String {
+        // Innermost first like a backtrace, the current pc is shown as frame 0
+
+        let mut lines: Vec<String> = vec![format!("#0 {}", describe(machine.cpu.pc.address, symbols))];
+        for (depth, frame) in self.calls.iter().rev().enumerate() {
+            lines.push(format!("#{} {} called {}", depth + 1, describe(frame.call_site, symbols), symbols.format(frame.target)));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Break {
+    pub fn describe(&self, symbols: &SymbolTable) -> String {
+        // What stopped the machine, for showing when it breaks
+        match self {
+            Break::Address(address) => format!("Stopped at {}", describe(*address, symbols)),
+            Break::Event(Event::Interrupt(rst)) => format!("Stopped after RST {} was serviced", rst),
+            Break::Event(Event::PortRead(port)) => format!("Stopped after reading port {}", port),
+            Break::Event(Event::PortWrite(port, value)) => format!("Stopped after writing 0x{:02x} to port {}", value, port),
+            Break::Event(Event::InterruptsEnabled) => String::from("Stopped after EI"),
+            Break::Event(Event::InterruptsDisabled) => String::from("Stopped after DI"),
+            Break::Event(Event::StackLeft(sp)) => format!("Stopped after the stack pointer left the stack for 0x{:04x}", sp),
+            Break::Watch(hit) => format!("Watch on {} hit at {}, 0x{:04x} -> 0x{:04x}", hit.watch.register.name(), describe(hit.address, symbols), hit.old, hit.new),
+            Break::Fault(e) => format!("Machine stopped: {}", e),
+            Break::SelfWrite(write) => format!("{} wrote over code at {}", describe(write.address, symbols), describe(write.target, symbols)),
+            Break::MemoryWrite { address, target } => format!("{} wrote to {}", describe(*address, symbols), describe(*target, symbols)),
+        }
+    }
+}
+
+fn describe(address: u16, symbols: &SymbolTable) -> String {
+    match symbols.locate(address) {
+        Some(_) => format!("0x{:04x} {}", address, symbols.format(address)),
+        None => format!("0x{:04x}", address),
+    }
+}
+
+fn describe_breakpoint(breakpoint: &Breakpoint, symbols: &SymbolTable) -> String {
+    match breakpoint {
+        Breakpoint::Address(address) => describe(*address, symbols),
+        Breakpoint::MemoryWrite(address) => format!("write {}", describe(*address, symbols)),
+        Breakpoint::Interrupt => String::from("interrupt"),
+        Breakpoint::InterruptEnable => String::from("ei and di"),
+        Breakpoint::PortRead(port) => format!("in {}", port),
+        Breakpoint::PortWrite(port) => format!("out {}", port),
+    }
+}
+
+fn describe_watch(watch: &Watch) -> String {
+    // e.g. "sp changing by more than 2, logged"
+    let mut text: String = match watch.condition {
+        WatchCondition::Changed => format!("{} changing", watch.register.name()),
+        WatchCondition::ChangedBy(limit) => format!("{} changing by more than {}", watch.register.name(), limit),
+        WatchCondition::Becomes(value) => format!("{} becoming 0x{:04x}", watch.register.name(), value),
+    };
+    if watch.action == WatchAction::Log {
+        text.push_str(", logged");
+    }
+    text
+}
+
+fn watch_options(register: WatchedRegister, options: &[&str], symbols: &SymbolTable) -> Result<Watch, String> {
+    // The words after a watch register, by <n> or is <value> and log in any order
+    //  n is a count so it is decimal, value is read like an address
+    let mut watch: Watch = Watch { register, condition: WatchCondition::Changed, action: WatchAction::Break };
+    let mut words = options.iter();
+    while let Some(word) = words.next() {
+        match *word {
+            "log" => watch.action = WatchAction::Log,
+            "by" => {
+                let limit: &str = words.next().ok_or("by needs a number")?;
+                watch.condition = WatchCondition::ChangedBy(limit.parse().map_err(|_| format!("{} is not a number", limit))?);
+            },
+            "is" => {
+                let value: &str = words.next().ok_or("is needs a value")?;
+                watch.condition = WatchCondition::Becomes(symbols.resolve(value).ok_or_else(|| format!("{} is not a value", value))?);
+            },
+            other => return Err(format!("{} is not a watch option, try by <n>, is <value> or log", other)),
+        }
+    }
+    Ok(watch)
+}
+
+fn event_breakpoint(words: &[&str], symbols: &SymbolTable) -> Result<Breakpoint, String> {
+    // The words after be or bc naming an event, ports can be given like any other number
+    let port = |text: &str| symbols.resolve(text)
+        .and_then(|port| u8::try_from(port).ok())
+        .ok_or_else(|| format!("{} is not a port number", text));
+    match words {
+        ["interrupt"] => Ok(Breakpoint::Interrupt),
+        ["ei"] => Ok(Breakpoint::InterruptEnable),
+        ["in", number] => Ok(Breakpoint::PortRead(port(number)?)),
+        ["out", number] => Ok(Breakpoint::PortWrite(port(number)?)),
+        _ => Err(format!("{} is not an event, try interrupt, ei, in <port> or out <port>", words.join(" "))),
+    }
+}
+
+fn describe_options(entry: &Entry) -> String {
+    // e.g. ", ignoring 36 more, once", nothing for a breakpoint that breaks every time
+    let mut options: String = String::new();
+    if entry.ignore > 0 {
+        options.push_str(&format!(", ignoring {} more", entry.ignore));
+    }
+    if entry.temporary {
+        options.push_str(", once");
+    }
+    options
+}
+
+fn breakpoint_options(mut entry: Entry, options: &[&str]) -> Result<Entry, String> {
+    // The words after a bp or bw location, once and after <n> in any order
+    let mut words = options.iter();
+    while let Some(word) = words.next() {
+        match *word {
+            "once" => entry.temporary = true,
+            "after" => {
+                let count: &str = words.next().ok_or("after needs a number of hits")?;
+                entry.ignore = count.parse().map_err(|_| format!("{} is not a number of hits", count))?;
+            },
+            other => return Err(format!("{} is not a breakpoint option, try once or after <n>", other)),
+        }
+    }
+    Ok(entry)
+}
+
+fn listing(machine: &Machine, start: u16, count: usize, symbols: &SymbolTable, comments: &BTreeMap<u16, String>, syntax: Syntax) -> String {
+    // Live disassembly of memory with symbol labels, comments and the pc marked
+
+    let end: u16 = start.saturating_add(count.saturating_mul(3).min(0xffff) as u16);
+    // Instructions are at most 3 bytes
+    let mut ops: Vec<Operation> = disassembler::decode_from(machine.cpu.memory.view(start..end), start);
+    ops.iter_mut().for_each(|op| op.set_syntax(syntax));
+
+    let mut lines: Vec<String> = vec![];
+    for op in ops.iter().take(count) {
+        if let Some(name) = symbols.name(op.address()) {
+            lines.push(format!("{}:", name));
+        }
+        let marker: char = if op.address() == machine.cpu.pc.address { '>' } else { ' ' };
+        lines.push(format!("{} {}", marker, comments::commented(op, symbols, comments.get(&op.address()).map(String::as_str))));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,169 @@
+use super::*;
+
+pub const ROW_BYTES: u16 = 16;
+const DEFAULT_ROWS: u16 = 16;
+// Rows shown by hex when no count is given, a page of 256 bytes
+const MAX_ROWS: u16 = 0x100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexViewer {
+    // Rows of memory as hex bytes with a cursor that the arrows move and hex digits type over
+    //  Two digits make a byte, written through the debugger so undo takes it back like a poke
+    top: u16,
+    // Address of the first row shown, always the start of a row
+    rows: u16,
+    cursor: u16,
+    typed: Option<u8>,
+    // First digit typed at the cursor, the byte is written once the second follows
+}
+impl HexViewer {
+    pub fn new(address: u16, rows: u16) -> Self {
+        let mut viewer: HexViewer = Self {
+            top: 0,
+            rows: rows.clamp(1, MAX_ROWS),
+            cursor: 0,
+            typed: None,
+        };
+        viewer.go_to(address);
+        viewer
+    }
+
+    pub fn cursor(&self) -> u16 {
+        self.cursor
+    }
+
+    pub fn top(&self) -> u16 {
+        self.top
+    }
+
+    pub fn typed(&self) -> Option<u8> {
+        self.typed
+    }
+
+    pub fn go_to(&mut self, address: u16) {
+        // Puts the cursor on address with its row at the top
+        self.cursor = address;
+        self.top = address - address % ROW_BYTES;
+        self.typed = None;
+    }
+
+    pub fn set_rows(&mut self, rows: u16) {
+        self.rows = rows.clamp(1, MAX_ROWS);
+        self.scroll();
+    }
+
+    pub fn move_cursor(&mut self, direction: Move) {
+        // Wraps round the ends of memory, a half typed byte is dropped
+
+        let page: u16 = self.rows.wrapping_mul(ROW_BYTES);
+        self.cursor = match direction {
+            Move::Left => self.cursor.wrapping_sub(1),
+            Move::Right => self.cursor.wrapping_add(1),
+            Move::Up => self.cursor.wrapping_sub(ROW_BYTES),
+            Move::Down => self.cursor.wrapping_add(ROW_BYTES),
+            Move::PageUp => self.cursor.wrapping_sub(page),
+            Move::PageDown => self.cursor.wrapping_add(page),
+        };
+        self.top = match direction {
+            Move::PageUp => self.top.wrapping_sub(page),
+            Move::PageDown => self.top.wrapping_add(page),
+            _ => self.top,
+        };
+        // Paging moves the rows along with the cursor
+        self.typed = None;
+        self.scroll();
+    }
+
+    fn scroll(&mut self) {
+        // Moves the rows shown just far enough to keep the cursor on screen
+
+        let row: u16 = self.cursor - self.cursor % ROW_BYTES;
+        let shown: u16 = row.wrapping_sub(self.top) / ROW_BYTES;
+        if shown < self.rows {
+            return;
+        }
+        let above: bool = self.top.wrapping_sub(row) / ROW_BYTES <= shown - self.rows + 1;
+        // Whichever way round memory is the shorter scroll
+        self.top = match above {
+            true => row,
+            false => row.wrapping_sub((self.rows - 1).wrapping_mul(ROW_BYTES)),
+        };
+    }
+
+    pub fn cancel(&mut self) {
+        // Drops a half typed byte
+        self.typed = None;
+    }
+
+    pub fn type_digit(&mut self, digit: char, debugger: &mut Debugger, machine: &mut Machine) -> Result<(), String> {
+        // The second digit writes the byte and moves on to the next one
+        //  Read only memory is refused up front, poke and asm are the way to patch the rom
+
+        let value: u8 = digit.to_digit(16).ok_or_else(|| format!("{} is not a hex digit", digit))? as u8;
+        if machine.cpu.memory.permissions_at(self.cursor) == Permissions::ReadOnly {
+            self.typed = None;
+            return Err(format!("0x{:04x} is read only, poke writes over it", self.cursor));
+        }
+
+        match self.typed.take() {
+            None => self.typed = Some(value),
+            Some(high) => {
+                debugger.poke(machine, self.cursor, &[high << 4 | value]);
+                self.move_cursor(Move::Right);
+            },
+        }
+        Ok(())
+    }
+
+    pub fn lines(&self, machine: &Machine) -> Vec<String> {
+        // e.g. "0x2000  00 01 02[03]04 ... 0f  ................"
+        //  The cursor is bracketed in place of the spaces either side so rows stay the same width
+        //  and a half typed byte shows its first digit followed by an underscore
+
+        (0..self.rows)
+            .map(|row| {
+                let start: u16 = self.top.wrapping_add(row.wrapping_mul(ROW_BYTES));
+                let bytes: Vec<(u16, u8)> = (0..ROW_BYTES)
+                    .map(|offset| start.wrapping_add(offset))
+                    .map(|address| (address, machine.cpu.memory.read_at(address)))
+                    .collect();
+
+                let mut hex: String = String::new();
+                for (address, byte) in &bytes {
+                    let opening: char = match *address == self.cursor {
+                        true => '[',
+                        false if address.wrapping_sub(1) == self.cursor && *address != start => ']',
+                        false => ' ',
+                    };
+                    hex.push(opening);
+                    match (*address == self.cursor, self.typed) {
+                        (true, Some(typed)) => hex.push_str(&format!("{:x}_", typed)),
+                        _ => hex.push_str(&format!("{:02x}", byte)),
+                    }
+                }
+                let last: u16 = start.wrapping_add(ROW_BYTES - 1);
+                hex.push(if last == self.cursor { ']' } else { ' ' });
+
+                let text: String = bytes.iter()
+                    .map(|(_, byte)| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' })
+                    .collect();
+                format!("0x{:04x} {} {}", start, hex, text)
+            })
+            .collect()
+    }
+}
+impl Default for HexViewer {
+    fn default() -> Self {
+        Self::new(0, DEFAULT_ROWS)
+    }
+}
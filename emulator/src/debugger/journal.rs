@@ -0,0 +1,185 @@
+use std::fmt;
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    // Something changed by hand from the debugger, with what it was before so it can be put back
+    Memory {
+        address: u16,
+        before: Vec<u8>,
+        after: Vec<u8>,
+    },
+    Register {
+        register: WatchedRegister,
+        before: u16,
+        after: u16,
+    },
+}
+impl Edit {
+    fn apply(&self, machine: &mut Machine, undo: bool) -> Result<(), String> {
+        // Writes one side of the edit, only if the other side is still there
+        //  Anything else means the value was changed since and putting ours back would lose that change
+
+        match self {
+            Edit::Memory { address, before, after } => {
+                let (from, to): (&[u8], &[u8]) = if undo { (after, before) } else { (before, after) };
+                let current: Vec<u8> = (0..from.len()).map(|offset| machine.cpu.memory.read_at(address.wrapping_add(offset as u16))).collect();
+                if current != from {
+                    return Err(format!("0x{:04x} has changed since, leaving it as it is", address));
+                }
+                for (offset, byte) in to.iter().enumerate() {
+                    machine.cpu.memory.poke(address.wrapping_add(offset as u16), *byte);
+                }
+            },
+            Edit::Register { register, before, after } => {
+                let (from, to): (u16, u16) = if undo { (*after, *before) } else { (*before, *after) };
+                let mut registers: Registers = machine.cpu.registers();
+                if register.value(&registers) != from {
+                    return Err(format!("{} has changed since, leaving it as it is", register.name()));
+                }
+                register.set(&mut registers, to)?;
+                machine.cpu.set_registers(registers);
+            },
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = |bytes: &[u8]| bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<String>>().join(" ");
+        match self {
+            Edit::Memory { address, before, after } => write!(f, "0x{:04x}: {} -> {}", address, bytes(before), bytes(after)),
+            Edit::Register { register, before, after } => write!(f, "{}: {:04x} -> {:04x}", register.name(), before, after),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Journal {
+    // Edits made since the machine last ran, oldest first
+    done: Vec<Edit>,
+    undone: Vec<Edit>,
+    // Undone edits that redo can put back, cleared by a new edit
+}
+impl Journal {
+    pub fn new() -> Self {
+        Self {
+            done: vec![],
+            undone: vec![],
+        }
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.done
+    }
+
+    pub fn record(&mut self, edit: Edit) {
+        self.done.push(edit);
+        self.undone.clear();
+    }
+
+    pub fn undo(&mut self, machine: &mut Machine) -> Result<Edit, String> {
+        let edit: &Edit = self.done.last().ok_or("Nothing to undo")?;
+        edit.apply(machine, true)?;
+        let edit: Edit = self.done.pop().unwrap();
+        self.undone.push(edit.clone());
+        Ok(edit)
+    }
+
+    pub fn redo(&mut self, machine: &mut Machine) -> Result<Edit, String> {
+        let edit: &Edit = self.undone.last().ok_or("Nothing to redo")?;
+        edit.apply(machine, false)?;
+        let edit: Edit = self.undone.pop().unwrap();
+        self.done.push(edit.clone());
+        Ok(edit)
+    }
+
+    pub fn revert(&mut self, machine: &mut Machine) -> Result<usize, String> {
+        // Undoes every edit, newest first, stopping at one that can't be undone
+        let mut reverted: usize = 0;
+        while !self.done.is_empty() {
+            self.undo(machine)?;
+            reverted += 1;
+        }
+        Ok(reverted)
+    }
+
+    pub fn commit(&mut self) {
+        // Forgets the edits, they are part of the machine from here on
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+impl Debugger {
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    fn edited(&mut self) {
+        self.history.clear();
+        self.instructions = 0;
+        // Snapshots from before the edit would replay without it
+    }
+
+    pub fn poke(&mut self, machine: &mut Machine, address: u16, bytes: &[u8]) {
+        // Writes memory, rom included, and keeps what was there for undo
+        let before: Vec<u8> = (0..bytes.len()).map(|offset| machine.cpu.memory.read_at(address.wrapping_add(offset as u16))).collect();
+        for (offset, byte) in bytes.iter().enumerate() {
+            machine.cpu.memory.poke(address.wrapping_add(offset as u16), *byte);
+        }
+        self.journal.record(Edit::Memory { address, before, after: bytes.to_vec() });
+        self.edited();
+    }
+
+    pub fn set_register(&mut self, machine: &mut Machine, register: WatchedRegister, value: u16) -> Result<(), String> {
+        let mut registers: Registers = machine.cpu.registers();
+        let before: u16 = register.value(&registers);
+        register.set(&mut registers, value)?;
+        machine.cpu.set_registers(registers);
+        self.journal.record(Edit::Register { register, before, after: value });
+        self.edited();
+        Ok(())
+    }
+
+    pub fn undo(&mut self, machine: &mut Machine) -> Result<Edit, String> {
+        let edit: Edit = self.journal.undo(machine)?;
+        self.edited();
+        Ok(edit)
+    }
+
+    pub fn redo(&mut self, machine: &mut Machine) -> Result<Edit, String> {
+        let edit: Edit = self.journal.redo(machine)?;
+        self.edited();
+        Ok(edit)
+    }
+
+    pub fn revert_edits(&mut self, machine: &mut Machine) -> Result<usize, String> {
+        if self.journal.edits().is_empty() {
+            return Ok(0);
+        }
+        let reverted: Result<usize, String> = self.journal.revert(machine);
+        self.edited();
+        reverted
+    }
+
+    pub fn set_revert_on_resume(&mut self, enabled: bool) {
+        // Undoes every edit before the machine runs on, so poking around can't change the run being debugged
+        self.revert_on_resume = enabled;
+    }
+
+    pub fn revert_on_resume(&self) -> bool {
+        self.revert_on_resume
+    }
+
+    pub(super) fn resume(&mut self, machine: &mut Machine) {
+        // Called before anything runs, edits are only kept for undo while the machine is stopped
+
+        if self.revert_on_resume {
+            let _ = self.revert_edits(machine);
+        }
+        // Edits to values the machine changed itself are left as they are
+        self.journal.commit();
+    }
+}
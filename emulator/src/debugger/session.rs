@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use disassembler::symbols::SymbolTable;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::*;
+use crate::headless;
+use crate::versioned::{self, Migration};
+
+pub const VERSION: u32 = 2;
+const MIGRATIONS: &[(u32, Migration)] = &[(1, breakpoint_entries)];
+pub const DIRECTORY: &str = "sessions";
+// Where sessions are kept when no other folder is given, relative to the working directory
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Session {
+    // Everything set up while debugging a rom, saved per rom so it is all back the next time the rom is opened
+    //  The machine itself isn't included, save states do that
+    pub version: u32,
+    pub rom_hash: String,
+    pub breakpoints: Vec<Entry>,
+    pub watches: Vec<Watch>,
+    pub symbols: BTreeMap<u16, String>,
+    pub comments: BTreeMap<u16, String>,
+}
+impl Session {
+    pub fn capture(debugger: &Debugger, symbols: &SymbolTable, rom: &[u8]) -> Self {
+        Self {
+            version: VERSION,
+            rom_hash: headless::rom_hash(rom),
+            breakpoints: debugger.breakpoints.entries().to_vec(),
+            watches: debugger.watches.clone(),
+            symbols: symbols.iter().map(|(address, name)| (address, name.to_string())).collect(),
+            comments: debugger.comments.clone(),
+        }
+    }
+
+    pub fn restore(&self, debugger: &mut Debugger, symbols: &mut SymbolTable) -> Result<(), String> {
+        // Replaces the debugger's breakpoints, watches and comments, symbols are added to the ones already loaded
+        //  A symbol file loaded since may have taken a name, the rest are still restored when one clashes
+
+        debugger.breakpoints = Breakpoints::from(self.breakpoints.clone());
+        debugger.watches = self.watches.clone();
+        debugger.comments = self.comments.clone();
+
+        let clashes: Vec<String> = self.symbols.iter()
+            .filter_map(|(address, name)| symbols.insert(*address, name).err())
+            .collect();
+        match clashes.is_empty() {
+            true => Ok(()),
+            false => Err(clashes.join("\n")),
+        }
+    }
+
+    pub fn path(directory: &Path, rom: &[u8]) -> PathBuf {
+        // One file per rom named after its hash so renaming or moving the rom keeps its session
+        directory.join(format!("{}.json", headless::rom_hash(rom)))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        versioned::from_json(&text, "session", VERSION, MIGRATIONS).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let json: String = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+fn breakpoint_entries(session: &mut Map<String, Value>) -> Result<(), String> {
+    // Version 1 kept bare breakpoints, they become entries that break every time
+    let breakpoints: &mut Value = session.get_mut("breakpoints").ok_or("no breakpoints")?;
+    let list: &mut Vec<Value> = breakpoints.as_array_mut().ok_or("breakpoints isn't a list")?;
+    for breakpoint in list.iter_mut() {
+        let mut entry: Map<String, Value> = Map::new();
+        entry.insert(String::from("breakpoint"), breakpoint.take());
+        *breakpoint = Value::Object(entry);
+    }
+    Ok(())
+}
+
+impl Debugger {
+    pub fn load_session(&mut self, directory: &Path, symbols: &mut SymbolTable, rom: &[u8]) -> Result<bool, String> {
+        // Restores the rom's session if it has one, returns whether there was one
+        //  Either way the session is saved to the same folder after every command from then on
+
+        self.session = Some(directory.to_path_buf());
+        let path: PathBuf = Session::path(directory, rom);
+        if !path.exists() {
+            return Ok(false);
+        }
+        Session::load(&path)?.restore(self, symbols)?;
+        Ok(true)
+    }
+
+    pub fn save_session(&self, directory: &Path, symbols: &SymbolTable, rom: &[u8]) -> Result<PathBuf, String> {
+        let path: PathBuf = Session::path(directory, rom);
+        Session::capture(self, symbols, rom).save(&path)?;
+        Ok(path)
+    }
+}
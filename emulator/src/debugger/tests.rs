@@ -0,0 +1,560 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use crate::error::ErrorKind;
+#[cfg(test)]
+use disassembler::symbols::SymbolTable;
+#[cfg(test)]
+use hex::Move;
+#[cfg(test)]
+use crate::observer::MachineObserver;
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_no_breakpoints() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert!(machine.events().contains(&Event::Interrupt(1)));
+    assert_eq!(machine.events().last(), Some(&Event::Interrupt(2)));
+}
+
+#[test]
+fn test_event_breakpoints() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut debugger: Debugger = Debugger::new();
+
+    // EI
+    debugger.add_breakpoint(Breakpoint::InterruptEnable);
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Event(Event::InterruptsEnabled)));
+    assert_eq!(machine.cpu.pc.address, 0x0001);
+    debugger.remove_breakpoint(Breakpoint::InterruptEnable);
+
+    // Ports
+    machine.cpu.a.value = 0x42;
+    debugger.add_breakpoint(Breakpoint::PortWrite(3));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Event(Event::PortWrite(3, 0x42))));
+    assert_eq!(machine.cpu.pc.address, 0x0003);
+    debugger.remove_breakpoint(Breakpoint::PortWrite(3));
+
+    debugger.add_breakpoint(Breakpoint::PortRead(2));
+    debugger.add_breakpoint(Breakpoint::PortRead(1));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Event(Event::PortRead(1))));
+    assert_eq!(machine.cpu.pc.address, 0x0005);
+    debugger.remove_breakpoint(Breakpoint::PortRead(1));
+    debugger.remove_breakpoint(Breakpoint::PortRead(2));
+
+    // Interrupts
+    debugger.add_breakpoint(Breakpoint::Interrupt);
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Event(Event::Interrupt(1))));
+    assert_eq!(machine.cpu.pc.address, 0x0008);
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Event(Event::Interrupt(2))));
+    assert_eq!(machine.cpu.pc.address, 0x0010);
+}
+
+#[test]
+fn test_address_breakpoints() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut debugger: Debugger = Debugger::new();
+
+    debugger.add_breakpoint(Breakpoint::Address(0x0005));
+    debugger.add_breakpoint(Breakpoint::Address(0x0005));
+    assert_eq!(debugger.breakpoints(), &[Breakpoint::Address(0x0005)]);
+    // Duplicates are ignored
+
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0005)));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0005)));
+    // Resuming runs the instruction at the breakpoint before checking again
+    assert_eq!(machine.cpu.pc.address, 0x0005);
+}
+
+#[test]
+fn test_step_back() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut debugger: Debugger = Debugger::with_history(Rewind::new(4, 16));
+    let mut history: Vec<(u16, usize)> = vec![];
+
+    for _ in 0..40 {
+        history.push((machine.cpu.pc.address, machine.events().len()));
+        debugger.step(&mut machine);
+    }
+
+    for (pc, events) in history.iter().rev().take(20) {
+        assert_eq!(debugger.step_back(&mut machine), Ok(()));
+        assert_eq!((machine.cpu.pc.address, machine.events().len()), (*pc, *events));
+    }
+    // Steps back across snapshots by replaying from the one before
+
+    debugger.step(&mut machine);
+    assert_eq!(machine.cpu.pc.address, history[21].0);
+    // Stepping forward again after rewinding follows the same path
+
+    let mut debugger: Debugger = Debugger::with_history(Rewind::new(1, 16));
+    for _ in 0..40 {
+        debugger.step(&mut machine);
+    }
+    for _ in 0..8 {
+        assert_eq!(debugger.step_back(&mut machine), Ok(()));
+    }
+    assert!(debugger.step_back(&mut machine).is_err());
+    // Only the last 16 instructions are still reachable with a single snapshot
+}
+
+#[test]
+fn test_step_back_keeps_observers() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut debugger: Debugger = Debugger::with_history(Rewind::new(4, 16));
+    let observer: MachineObserver = machine.observer();
+    let frames: Arc<Mutex<usize>> = Arc::default();
+    let counter: Arc<Mutex<usize>> = Arc::clone(&frames);
+    machine.on_vblank(move |_| *counter.lock().unwrap() += 1);
+
+    for _ in 0..40 {
+        debugger.step(&mut machine);
+    }
+    assert_eq!(debugger.step_back(&mut machine), Ok(()));
+    assert!(!observer.updated());
+
+    debugger.run_frame(&mut machine);
+    assert!(observer.updated());
+    assert_eq!(*frames.lock().unwrap(), 1);
+    // The rewind snapshots were copies without the observers or callbacks, restoring one keeps the machine's own
+}
+
+#[test]
+fn test_fault_break() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x00,       // 0x0000 NOP
+        0xdb, 0x07, // 0x0001 IN 7
+    ]).unwrap();
+    let mut debugger: Debugger = Debugger::new();
+
+    let fault: EmulatorError = EmulatorError { address: 0x0001, op_code: 0xdb, kind: ErrorKind::UnconnectedReadPort(7) };
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Fault(fault)));
+    assert_eq!(machine.cpu.pc.address, 0x0001);
+    // Stays on the instruction that failed
+    assert_eq!(machine.run_frame(), Err(fault));
+}
+
+#[test]
+fn test_register_watches() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x31, 0x00, 0x24, // 0x0000 LXI SP,0x2400
+        0x3e, 0xfe,       // 0x0003 MVI A,0xfe
+        0x3c,             // 0x0005 INR A
+        0xc5,             // 0x0006 PUSH B
+        0x31, 0x00, 0x23, // 0x0007 LXI SP,0x2300
+        0xc3, 0x03, 0x00, // 0x000a JMP 0x0003
+    ]).unwrap();
+    let mut debugger: Debugger = Debugger::new();
+
+    let sp_jump: Watch = Watch { register: WatchedRegister::SP, condition: WatchCondition::ChangedBy(2), action: WatchAction::Break };
+    let a_full: Watch = Watch { register: WatchedRegister::A, condition: WatchCondition::Becomes(0xff), action: WatchAction::Log };
+    debugger.add_watch(sp_jump);
+    debugger.add_watch(a_full);
+    debugger.add_watch(a_full);
+    assert_eq!(debugger.watches(), &[sp_jump, a_full]);
+
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Watch(WatchHit { watch: sp_jump, address: 0x0007, old: 0x23fe, new: 0x2300 })));
+    // The PUSH only moved SP by 2
+    assert_eq!(debugger.watch_log().collect::<Vec<&WatchHit>>(), vec![&WatchHit { watch: a_full, address: 0x0005, old: 0xfe, new: 0xff }]);
+
+    debugger.remove_watch(sp_jump);
+    debugger.clear_watch_log();
+    for _ in 0..5 {
+        debugger.step(&mut machine);
+    }
+    assert_eq!(debugger.watch_log().count(), 1);
+    // Logging watches don't stop the machine
+
+    assert!(!Watch { register: WatchedRegister::SP, condition: WatchCondition::ChangedBy(2), action: WatchAction::Log }.triggered(0x0000, 0xfffe));
+    assert!(Watch { register: WatchedRegister::PC, condition: WatchCondition::Changed, action: WatchAction::Log }.triggered(0x0001, 0x0002));
+}
+
+#[cfg(test)]
+fn calling_machine() -> (Machine, SymbolTable) {
+    // Main calls Outer which calls Inner, then they return and it starts again
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x31, 0x00, 0x24, // 0x0001 LXI SP,0x2400
+        0xcd, 0x10, 0x00, // 0x0004 CALL Outer
+        0xc3, 0x04, 0x00, // 0x0007 JMP 0x0004
+    ]).unwrap();
+    machine.cpu.memory.load_rom(&[
+        0xcd, 0x20, 0x00, // 0x0010 CALL Inner
+        0xc9,             // 0x0013 RET
+    ], 0x10).unwrap();
+    machine.cpu.memory.load_rom(&[
+        0x00,             // 0x0020 NOP
+        0xc9,             // 0x0021 RET
+    ], 0x20).unwrap();
+
+    let symbols: SymbolTable = SymbolTable::parse("
+        0x0000 Main
+        0x0010 Outer   ; calls Inner
+        0x0020 Inner
+    ").unwrap();
+
+    (machine, symbols)
+}
+
+#[test]
+fn test_call_stack() {
+    let (mut machine, _) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    debugger.add_breakpoint(Breakpoint::Address(0x0021));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0021)));
+    assert_eq!(debugger.call_stack(), &[
+        CallFrame { call_site: 0x0004, target: 0x0010, sp: 0x23fe },
+        CallFrame { call_site: 0x0010, target: 0x0020, sp: 0x23fc },
+    ]);
+
+    debugger.step(&mut machine);
+    assert_eq!(debugger.call_stack().len(), 1);
+    debugger.step(&mut machine);
+    assert!(debugger.call_stack().is_empty());
+    // Each RET unwinds a frame
+}
+
+#[test]
+fn test_symbol_commands() {
+    let (mut machine, symbols) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("bp Inner+1", &mut machine, &symbols), Ok(String::from("Breakpoint at 0x0021 Inner+1")));
+    assert_eq!(debugger.breakpoints(), &[Breakpoint::Address(0x0021)]);
+    assert!(debugger.command("bp DrawAlien", &mut machine, &symbols).is_err());
+    assert!(debugger.command("bc Outer", &mut machine, &symbols).is_err());
+
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0021)));
+    assert_eq!(debugger.command("bt", &mut machine, &symbols), Ok(String::from(
+        "#0 0x0021 Inner+1\n#1 0x0010 Outer called Inner\n#2 0x0004 Main+4 called Outer"
+    )));
+
+    let listing: String = debugger.command("dis Outer 2", &mut machine, &symbols).unwrap();
+    assert_eq!(listing.lines().collect::<Vec<&str>>(), vec![
+        "Outer:",
+        "  0010   cd 00 20    CALL adr    ; Inner",
+        "  0013   c9          RET",
+    ]);
+    assert!(debugger.command("dis", &mut machine, &symbols).unwrap().starts_with("> 0021"));
+
+    assert_eq!(debugger.command("bc 0x21", &mut machine, &symbols), Ok(String::from("Cleared breakpoint at 0x0021 Inner+1")));
+    assert_eq!(debugger.command("bl", &mut machine, &symbols), Ok(String::from("No breakpoints")));
+    assert!(debugger.command("regs", &mut machine, &symbols).unwrap().contains("PC=0021"));
+    assert!(debugger.command("frobnicate", &mut machine, &symbols).is_err());
+
+    assert_eq!(debugger.command("syntax Z80", &mut machine, &symbols), Ok(String::from("Listing with z80 mnemonics")));
+    assert!(debugger.command("dis Outer 1", &mut machine, &symbols).unwrap().ends_with("CALL adr    ; Inner"));
+    assert!(debugger.command("dis 1 1", &mut machine, &symbols).unwrap().ends_with("LD SP,D16"));
+    assert!(debugger.command("syntax motorola", &mut machine, &symbols).is_err());
+}
+
+#[test]
+fn test_find_command() {
+    let (mut machine, symbols) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("find CD ?? 00", &mut machine, &symbols).unwrap().lines().collect::<Vec<&str>>(), vec![
+        "0004   cd 00 10    CALL adr    ; Outer",
+        "Outer:",
+        "0010   cd 00 20    CALL adr    ; Inner",
+        "Matches of CD ?? 00: 2",
+    ]);
+    assert_eq!(debugger.command("find c? 04 0?", &mut machine, &symbols), Ok(String::from("0007   c3 00 04    JMP adr\nMatches of C? 04 0?: 1")));
+    assert_eq!(debugger.command("find cd 30", &mut machine, &symbols), Ok(String::from("No matches of CD 30")));
+    assert!(debugger.command("find cd 3", &mut machine, &symbols).is_err());
+    assert!(debugger.command("find cd zz", &mut machine, &symbols).is_err());
+    assert!(debugger.command("find", &mut machine, &symbols).is_err());
+}
+
+#[test]
+fn test_asm_command() {
+    let (mut machine, symbols) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("asm Inner", &mut machine, &symbols), Ok(String::from("Assembling at 0x0020 Inner, a blank line finishes")));
+    assert_eq!(debugger.assembling(), Some(0x0020));
+    assert_eq!(debugger.command("MVI A, 42", &mut machine, &symbols), Ok(String::from("Inner:\n  0020   3e 42       MVI A,D8")));
+    assert!(debugger.command("MVI A, Q", &mut machine, &symbols).is_err());
+    assert_eq!(debugger.assembling(), Some(0x0022));
+    // A bad line doesn't move on or leave asm mode
+    debugger.command("RET", &mut machine, &symbols).unwrap();
+    assert_eq!(debugger.command("", &mut machine, &symbols), Ok(String::from("Finished at 0x0023 Inner+3")));
+    assert_eq!(debugger.assembling(), None);
+    assert_eq!(machine.cpu.memory.view(0x0020..0x0023), &[0x3e, 0x42, 0xc9]);
+    // Written over the rom
+
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert_eq!(machine.cpu.registers().a, 0x42);
+    // The patched code runs straight away
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_session() {
+    let (mut machine, mut symbols) = calling_machine();
+    let directory: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-session-test-{}", std::process::id()));
+    let mut debugger: Debugger = Debugger::new();
+    assert_eq!(debugger.load_session(&directory, &mut symbols, machine.rom()), Ok(false));
+
+    debugger.command("bp Inner", &mut machine, &symbols).unwrap();
+    debugger.add_watch(Watch { register: WatchedRegister::SP, condition: WatchCondition::Changed, action: WatchAction::Log });
+    debugger.set_comment(0x0013, "back to Main");
+    symbols.insert(0x0007, "Loop").unwrap();
+    debugger.command("bl", &mut machine, &symbols).unwrap();
+    // Commands save the session once one has been loaded
+
+    let listing: String = debugger.command("dis Outer 2", &mut machine, &symbols).unwrap();
+    assert_eq!(listing.lines().last(), Some("  0013   c9          RET    ; back to Main"));
+
+    let (machine, mut reopened_symbols) = calling_machine();
+    let mut reopened: Debugger = Debugger::new();
+    assert_eq!(reopened.load_session(&directory, &mut reopened_symbols, machine.rom()), Ok(true));
+    assert_eq!(reopened.breakpoints(), &[Breakpoint::Address(0x0020)]);
+    assert_eq!(reopened.watches(), debugger.watches());
+    assert_eq!(reopened.comment(0x0013), Some("back to Main"));
+    assert_eq!(reopened_symbols.address("Loop"), Some(0x0007));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_comment_commands() {
+    let (mut machine, symbols) = calling_machine();
+    let program: Vec<u8> = machine.cpu.memory.view(0x0000..0x0022).to_vec();
+    machine.load_rom(&program).unwrap();
+    // Export lists the rom so Outer and Inner have to be part of it
+    let mut debugger: Debugger = Debugger::new();
+    let path: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-comments-test-{}.lst", std::process::id()));
+    let path_text: String = path.display().to_string();
+
+    assert_eq!(debugger.command("comment Outer+0  calls  Inner  ", &mut machine, &symbols), Ok(String::from("Comment at 0x0010 Outer")));
+    assert_eq!(debugger.comment(0x0010), Some("calls  Inner"));
+    // Spacing inside the text is kept
+    debugger.command("comment 0x0013 back to Main", &mut machine, &symbols).unwrap();
+    debugger.command("comment 0x2000 score", &mut machine, &symbols).unwrap();
+    assert_eq!(debugger.command("comments", &mut machine, &symbols), Ok(String::from(
+        "0x0010 Outer ; calls  Inner\n0x0013 Outer+3 ; back to Main\n0x2000 Inner+8160 ; score"
+    )));
+    assert_eq!(debugger.command("dis Outer 1", &mut machine, &symbols), Ok(String::from(
+        "Outer:\n  0010   cd 00 20    CALL adr    ; Inner - calls  Inner"
+    )));
+
+    assert_eq!(debugger.command(&format!("comments export {}", path_text), &mut machine, &symbols), Ok(format!("Exported 3 comments to {}", path_text)));
+    let exported: String = std::fs::read_to_string(&path).unwrap();
+    assert!(exported.contains("Outer:\n0010   cd 00 20    CALL adr    ; Inner - calls  Inner\n0013   c9          RET    ; back to Main\n"));
+    assert!(exported.ends_with("2000    ; score\n"));
+
+    let mut reopened: Debugger = Debugger::new();
+    assert_eq!(reopened.command(&format!("comments import {}", path_text), &mut machine, &symbols), Ok(format!("Imported 3 comments from {}", path_text)));
+    assert_eq!(reopened.command("comments", &mut machine, &symbols), debugger.command("comments", &mut machine, &symbols));
+
+    assert_eq!(debugger.command("comment 0x2000", &mut machine, &symbols), Ok(String::from("Removed comment at 0x2000 Inner+8160")));
+    assert_eq!(debugger.comment(0x2000), None);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_interrupt_commands() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let symbols: SymbolTable = SymbolTable::default();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("int status", &mut machine, &symbols), Ok(String::from("Interrupts enabled, RST 1 due in 16500 cycles")));
+    assert_eq!(debugger.command("int raise 2", &mut machine, &symbols), Ok(String::from("RST 2 taken, now at 0x0010")));
+    assert_eq!(machine.last_events(), &[Event::Interrupt(2)]);
+    let (mut disabled, _) = calling_machine();
+    debugger.step(&mut disabled);
+    // Past its DI
+    assert_eq!(debugger.command("int raise 2", &mut disabled, &symbols), Err(String::from("Interrupts are disabled, RST 2 wasn't taken")));
+    assert!(debugger.command("int status", &mut disabled, &symbols).unwrap().starts_with("Interrupts disabled"));
+    assert!(debugger.command("int raise 8", &mut machine, &symbols).is_err());
+
+    assert_eq!(debugger.command("int block", &mut machine, &symbols), Ok(String::from("The board's interrupts are blocked")));
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert!(!machine.events().iter().any(|event| matches!(event, Event::Interrupt(_))));
+    assert!(debugger.command("int status", &mut machine, &symbols).unwrap().ends_with("the board's interrupts are blocked"));
+
+    assert_eq!(debugger.command("int block", &mut machine, &symbols), Ok(String::from("The board's interrupts are back on")));
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert_eq!(machine.events().iter().filter(|event| matches!(event, Event::Interrupt(_))).count(), 2);
+}
+
+#[test]
+fn test_self_write_trap() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x31, 0x10, 0x00, // 0x0000 LXI SP,0x0010
+        0xc5,             // 0x0003 PUSH B
+    ]).unwrap();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.step(&mut machine), (false, None));
+    let expected: SelfWrite = SelfWrite { address: 0x0003, target: 0x000f, rom: true };
+    assert_eq!(debugger.step(&mut machine), (false, Some(Break::SelfWrite(expected))));
+    assert_eq!(machine.cpu.memory.read_at(0x000f), 0x00);
+    // The rom itself is protected, the break says where the stack ran into it
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xc3, 0x00, 0x21]).unwrap(); // 0x0000 JMP 0x2100
+    machine.cpu.memory.write_at(0x2100, 0x00); // 0x2100 NOP
+    for (address, byte) in [0x3e, 0x55, 0x32, 0x00, 0x21].into_iter().enumerate() {
+        machine.cpu.memory.write_at(0x2101 + address as u16, byte);
+    }
+    // 0x2101 MVI A,0x55 then STA 0x2100 over the NOP that already ran
+    for _ in 0..3 {
+        assert_eq!(debugger.step(&mut machine), (false, None));
+    }
+    let expected: SelfWrite = SelfWrite { address: 0x2103, target: 0x2100, rom: false };
+    assert_eq!(debugger.step(&mut machine), (false, Some(Break::SelfWrite(expected))));
+
+    let symbols: SymbolTable = SymbolTable::default();
+    assert!(debugger.command("trap", &mut machine, &symbols).unwrap().contains("let through"));
+    assert!(!debugger.self_write_trap());
+    machine.cpu.pc.address = 0x2103;
+    assert_eq!(debugger.step(&mut machine), (false, None));
+}
+
+#[test]
+fn test_edit_journal() {
+    let (mut machine, symbols) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("poke 0x2000 0x12 0x34", &mut machine, &symbols), Ok(String::from("Wrote 2 bytes at 0x2000 Inner+8160")));
+    assert!(debugger.command("set a 0x42", &mut machine, &symbols).unwrap().starts_with("A=42"));
+    assert!(debugger.command("set a 0x142", &mut machine, &symbols).is_err());
+    assert!(debugger.command("set q 1", &mut machine, &symbols).is_err());
+    assert!(debugger.command("poke 0x2000 ff", &mut machine, &symbols).is_err());
+    assert_eq!(debugger.command("edits", &mut machine, &symbols), Ok(String::from("0x2000: 00 00 -> 12 34\na: 0000 -> 0042")));
+
+    assert_eq!(debugger.command("undo", &mut machine, &symbols), Ok(String::from("Undid a: 0000 -> 0042")));
+    assert_eq!(machine.cpu.registers().a, 0);
+    debugger.command("redo", &mut machine, &symbols).unwrap();
+    assert_eq!(machine.cpu.registers().a, 0x42);
+    assert!(debugger.command("redo", &mut machine, &symbols).is_err());
+
+    machine.cpu.memory.poke(0x2001, 0x99);
+    debugger.command("undo", &mut machine, &symbols).unwrap();
+    assert!(debugger.command("undo", &mut machine, &symbols).is_err_and(|e| e.contains("changed since")));
+    assert_eq!(machine.cpu.memory.view(0x2000..0x2002), &[0x12, 0x99]);
+    // Something else wrote there after the poke so undo leaves it alone
+
+    machine.cpu.memory.poke(0x2001, 0x34);
+    assert_eq!(debugger.command("revert", &mut machine, &symbols), Ok(String::from("Reverted 1 edits")));
+    assert_eq!(machine.cpu.memory.view(0x2000..0x2002), &[0x00, 0x00]);
+
+    debugger.command("poke 0x2000 0x12", &mut machine, &symbols).unwrap();
+    debugger.step(&mut machine);
+    assert_eq!(debugger.command("edits", &mut machine, &symbols), Ok(String::from("No edits since the machine last ran")));
+    assert_eq!(machine.cpu.memory.read_at(0x2000), 0x12);
+    // Running on keeps the edits
+
+    assert_eq!(debugger.command("autorevert", &mut machine, &symbols), Ok(String::from("Edits are undone before the machine runs on")));
+    let mut expected: Machine = machine.clone();
+    Debugger::new().step(&mut expected);
+    debugger.command("poke 0x2000 0x56", &mut machine, &symbols).unwrap();
+    debugger.command("set pc Inner", &mut machine, &symbols).unwrap();
+    debugger.step(&mut machine);
+    assert_eq!(machine.cpu.memory.read_at(0x2000), 0x12);
+    assert_eq!(machine.cpu.registers(), expected.cpu.registers());
+    // Both edits were undone before the step so it ran as if they were never made
+}
+
+#[test]
+fn test_breakpoint_counts() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_IN_RAM);
+    let symbols: SymbolTable = SymbolTable::new();
+    let mut debugger: Debugger = Debugger::new();
+
+    assert_eq!(debugger.command("bw 0x2010 after 36", &mut machine, &symbols), Ok(String::from("Breakpoint on write 0x2010, ignoring 36 more")));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::MemoryWrite { address: 0x0003, target: 0x2010 }));
+    assert_eq!(machine.cpu.memory.read_at(0x2010), 37);
+    // Broke on the 37th write
+    assert_eq!(debugger.breakpoint(Breakpoint::MemoryWrite(0x2010)).map(|entry| (entry.hits, entry.ignore)), Some((37, 0)));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::MemoryWrite { address: 0x0003, target: 0x2010 }));
+    assert_eq!(machine.cpu.memory.read_at(0x2010), 38);
+
+    assert_eq!(debugger.command("bp 0x0004 after 1", &mut machine, &symbols), Ok(String::from("Breakpoint at 0x0004, ignoring 1 more")));
+    assert_eq!(debugger.command("bl", &mut machine, &symbols), Ok(String::from("#1 write 0x2010  hits 38\n#2 0x0004  hits 0, ignoring 1 more")));
+    assert_eq!(debugger.command("ignore 1 2", &mut machine, &symbols), Ok(String::from("Letting the next 2 hits on write 0x2010 through")));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0004)));
+    assert_eq!(machine.cpu.memory.read_at(0x2010), 40);
+    // Both let their first hits through, the address breakpoint ran out first
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::MemoryWrite { address: 0x0003, target: 0x2010 }));
+    assert_eq!(machine.cpu.memory.read_at(0x2010), 41);
+    assert!(debugger.command("ignore 3 1", &mut machine, &symbols).is_err());
+    assert!(debugger.command("bw 0x2010 twice", &mut machine, &symbols).is_err());
+    assert!(debugger.command("bw 0x2010 after", &mut machine, &symbols).is_err());
+
+    assert_eq!(debugger.command("bc 0x2010", &mut machine, &symbols), Ok(String::from("Cleared breakpoint at 0x2010")));
+    assert_eq!(debugger.command("bp 0x0003 once", &mut machine, &symbols), Ok(String::from("Breakpoint at 0x0003, once")));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0003)));
+    assert_eq!(debugger.breakpoints(), &[Breakpoint::Address(0x0004)]);
+    // The temporary breakpoint went once it broke
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0004)));
+    assert_eq!(debugger.breakpoint(Breakpoint::Address(0x0004)).map(|entry| entry.hits), Some(3));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_session_migration() {
+    let path: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-session-v1-test-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"version": 1, "rom_hash": "00", "breakpoints": [{"Address": 32}, "Interrupt"], "watches": [], "symbols": {}, "comments": {}}"#).unwrap();
+
+    let session: session::Session = session::Session::load(&path).unwrap();
+    assert_eq!(session.version, session::VERSION);
+    assert_eq!(session.breakpoints, vec![breakpoints::Entry::new(Breakpoint::Address(0x0020)), breakpoints::Entry::new(Breakpoint::Interrupt)]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_hex_viewer() {
+    let (mut machine, symbols) = calling_machine();
+    let mut debugger: Debugger = Debugger::new();
+
+    let shown: String = debugger.command("hex 0x2003 2", &mut machine, &symbols).unwrap();
+    assert_eq!(shown, "0x2000  00 00 00[00]00 00 00 00 00 00 00 00 00 00 00 00  ................\n\
+                       0x2010  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  ................");
+
+    debugger.type_hex('4', &mut machine).unwrap();
+    assert!(debugger.command("hex", &mut machine, &symbols).unwrap().contains(" 00[4_]00 "));
+    debugger.type_hex('A', &mut machine).unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x2003), 0x4a);
+    assert_eq!(debugger.hex_viewer().cursor(), 0x2004);
+    assert!(debugger.type_hex('g', &mut machine).is_err());
+    // Two digits write a byte and move on
+
+    debugger.move_hex_cursor(Move::Down);
+    debugger.move_hex_cursor(Move::Down);
+    assert_eq!(debugger.hex_viewer().cursor(), 0x2024);
+    assert_eq!(debugger.hex_viewer().top(), 0x2010);
+    // Scrolls to keep the cursor shown
+    debugger.move_hex_cursor(Move::PageUp);
+    assert_eq!((debugger.hex_viewer().cursor(), debugger.hex_viewer().top()), (0x2004, 0x1ff0));
+
+    debugger.type_hex('1', &mut machine).unwrap();
+    debugger.move_hex_cursor(Move::Right);
+    assert_eq!(debugger.hex_viewer().typed(), None);
+    assert_eq!(machine.cpu.memory.read_at(0x2004), 0x00);
+    // Moving away drops a half typed byte
+
+    debugger.command("hex 0x0010", &mut machine, &symbols).unwrap();
+    assert!(debugger.type_hex('f', &mut machine).is_err_and(|e| e.contains("read only")));
+    assert_eq!(machine.cpu.memory.read_at(0x0010), 0xcd);
+    // The rom's region can't be typed over
+
+    assert_eq!(debugger.command("edits", &mut machine, &symbols), Ok(String::from("0x2003: 00 -> 4a")));
+    debugger.command("undo", &mut machine, &symbols).unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x2003), 0x00);
+}
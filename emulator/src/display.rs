@@ -0,0 +1,196 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use crate::cpu::Memory;
+
+mod tests;
+
+pub const BITMAP_VRAM: Range<u16> = 0x2400..0x4000;
+// Where the Midway board's bitmap is, one bit per pixel a column at a time from the bottom of the rotated screen
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Rotation {
+    // How the bitmap in memory is turned to make the screen, a line of memory is a row before turning
+    None,
+    #[default]
+    Anticlockwise,
+    // Lines become columns drawn from the bottom up, how the Midway board's monitor is mounted
+    Clockwise,
+    // Lines become columns drawn from the top down
+    Half,
+    // Upside down, lines are rows drawn from the bottom right
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Geometry {
+    // Where a one bit per pixel bitmap is in memory and how it makes up the screen
+    //  Each byte holds 8 pixels along a line, the lowest bit first
+    pub width: u16,
+    pub height: u16,
+    // The screen as it is shown, after rotation
+    pub rotation: Rotation,
+    pub base: u16,
+    // Address of the first byte of the first line
+    pub stride: Option<u16>,
+    // Bytes from the start of one line to the next, just enough for a line's pixels without it
+}
+impl Geometry {
+    pub fn space_invaders() -> Self {
+        Self {
+            width: 224,
+            height: 256,
+            rotation: Rotation::Anticlockwise,
+            base: BITMAP_VRAM.start,
+            stride: None,
+        }
+    }
+
+    fn raster(&self) -> (u16, u16) {
+        // Pixels in a line and how many lines, as the bitmap is in memory
+        match self.rotation {
+            Rotation::None | Rotation::Half => (self.width, self.height),
+            Rotation::Anticlockwise | Rotation::Clockwise => (self.height, self.width),
+        }
+    }
+
+    pub fn stride(&self) -> u16 {
+        self.stride.unwrap_or(self.raster().0.div_ceil(8))
+    }
+
+    pub fn memory(&self) -> Range<u16> {
+        // The memory that makes up the bitmap
+        let length: u32 = self.stride() as u32 * self.raster().1 as u32;
+        self.base..(self.base as u32 + length).min(u16::MAX as u32) as u16
+    }
+
+    pub fn locate(&self, x: u16, y: u16) -> Option<(usize, u8)> {
+        // Byte from the start of the bitmap and bit in it of the pixel x across from the left and y down from the top
+        //  None for a pixel off the screen
+
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let (pixel, line): (u16, u16) = match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Anticlockwise => (self.height - 1 - y, x),
+            Rotation::Clockwise => (y, self.width - 1 - x),
+            Rotation::Half => (self.width - 1 - x, self.height - 1 - y),
+        };
+        Some((line as usize * self.stride() as usize + pixel as usize / 8, (pixel % 8) as u8))
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        if self.width == 0 || self.height == 0 {
+            problems.push(format!("A {}x{} screen has no pixels", self.width, self.height));
+        }
+        let line: u16 = self.raster().0.div_ceil(8);
+        if self.stride() < line {
+            problems.push(format!("A line needs {} bytes but the stride is {}", line, self.stride()));
+        }
+        if self.base as u32 + self.stride() as u32 * self.raster().1 as u32 > u16::MAX as u32 {
+            problems.push(format!("The bitmap at 0x{:04x} runs past the end of memory", self.base));
+        }
+        problems
+    }
+}
+impl Default for Geometry {
+    fn default() -> Self {
+        Self::space_invaders()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "lowercase"))]
+pub enum Display {
+    // How the screen is drawn from memory
+    Bitmap(Geometry),
+    // The Midway board's rotated bitmap unless the profile says otherwise
+    Text { start: u16, columns: u16, rows: u16 },
+    // A byte per character cell row by row from the top left, ascii with anything unprintable left blank
+}
+impl Display {
+    pub fn memory(&self) -> Range<u16> {
+        // The memory that makes up the screen
+        match *self {
+            Display::Bitmap(geometry) => geometry.memory(),
+            Display::Text { start, columns, rows } => start..start.saturating_add(columns.saturating_mul(rows)),
+        }
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        // Text screens are laid out in the Midway board's screen, the overlay covers the same rows and columns
+        match *self {
+            Display::Bitmap(geometry) => geometry,
+            Display::Text { .. } => Geometry::space_invaders(),
+        }
+    }
+
+    pub fn framebuffer<'a>(&self, memory: &'a Memory) -> Framebuffer<'a> {
+        Framebuffer::new(self.geometry(), memory.view(self.geometry().memory()))
+    }
+
+    pub fn text_lines(&self, memory: &Memory) -> Vec<String> {
+        // Each row of a text screen as it would be drawn, nothing for a bitmap
+
+        let Display::Text { columns, .. } = *self else {
+            return vec![];
+        };
+        memory.view(self.memory())
+            .chunks(columns.max(1) as usize)
+            .map(|row| row.iter().map(|byte| character(*byte)).collect())
+            .collect()
+    }
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Display::Bitmap(Geometry::space_invaders())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framebuffer<'a> {
+    // A bitmap read through its geometry, so drawing doesn't need to know how the board lays out memory
+    geometry: Geometry,
+    vram: &'a [u8],
+}
+impl<'a> Framebuffer<'a> {
+    pub fn new(geometry: Geometry, vram: &'a [u8]) -> Self {
+        Self { geometry, vram }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.geometry.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.geometry.height
+    }
+
+    pub fn lit(&self, x: u16, y: u16) -> bool {
+        // Whether the pixel x across from the left and y down from the top is on, anything off the screen is dark
+        self.geometry.locate(x, y)
+            .and_then(|(offset, bit)| self.vram.get(offset).map(|byte| byte >> bit & 1 == 1))
+            .unwrap_or(false)
+    }
+
+    pub fn lit_pixels(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        // Every lit pixel row by row from the top left
+        (0..self.height()).flat_map(move |y| (0..self.width()).map(move |x| (x, y))).filter(|(x, y)| self.lit(*x, *y))
+    }
+}
+
+pub fn character(byte: u8) -> char {
+    match byte {
+        0x20..=0x7e => byte as char,
+        _ => ' ',
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+use super::*;
+#[cfg(all(test, feature = "serde"))]
+use crate::assembler;
+#[cfg(all(test, feature = "serde"))]
+use crate::machine::Machine;
+#[cfg(all(test, feature = "serde"))]
+use crate::profile::Profile;
+
+#[test]
+fn test_text_lines() {
+    let display: Display = Display::Text { start: 0x2000, columns: 4, rows: 2 };
+    let mut memory: Memory = Memory::init();
+    for (offset, byte) in b"AB\0\x7fz~ 1".iter().enumerate() {
+        memory.poke(0x2000 + offset as u16, *byte);
+    }
+
+    assert_eq!(display.memory(), 0x2000..0x2008);
+    assert_eq!(display.text_lines(&memory), vec!["AB  ", "z~ 1"]);
+    assert!(Display::default().text_lines(&memory).is_empty());
+    assert_eq!(Display::default().memory(), BITMAP_VRAM);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_geometry() {
+    let invaders: Geometry = Geometry::space_invaders();
+    assert_eq!((invaders.stride(), invaders.memory()), (32, BITMAP_VRAM));
+    assert_eq!(invaders.locate(0, 255), Some((0, 0)));
+    assert_eq!(invaders.locate(0, 0), Some((31, 7)));
+    assert_eq!(invaders.locate(223, 255), Some((223 * 32, 0)));
+    assert_eq!(invaders.locate(224, 0), None);
+    // A column at a time from the bottom of the screen
+
+    let mut vram: Vec<u8> = vec![0; 4 * 3];
+    vram[0] = 0b0000_0011;
+    vram[4 * 2 + 1] = 0b1000_0000;
+    let mut geometry: Geometry = Geometry { width: 10, height: 3, rotation: Rotation::None, base: 0x2000, stride: Some(4) };
+    assert_eq!(geometry.memory(), 0x2000..0x200c);
+    let lit = |geometry: Geometry| Framebuffer::new(geometry, &vram).lit_pixels().collect::<Vec<(u16, u16)>>();
+    assert_eq!(lit(geometry), [(0, 0), (1, 0)]);
+    // The last line's byte is past the screen's 10 pixels
+    geometry.rotation = Rotation::Half;
+    assert_eq!(lit(geometry), [(8, 2), (9, 2)]);
+    geometry.rotation = Rotation::Clockwise;
+    geometry.width = 3;
+    geometry.height = 16;
+    assert_eq!(lit(geometry), [(2, 0), (2, 1), (0, 15)]);
+
+    let profile: Profile = Profile::from_toml(&include_str!("../../profiles/text_console.toml").replace(
+        "kind = \"text\"\nstart = 0x1000\ncolumns = 40\nrows = 25",
+        "kind = \"bitmap\"\nwidth = 256\nheight = 32\nrotation = \"none\"\nbase = 0x1000",
+    )).unwrap();
+    assert_eq!(profile.display.memory(), 0x1000..0x1400);
+    assert_eq!(profile.display.geometry().stride(), 32);
+    assert!(Geometry { stride: Some(1), ..geometry }.problems()[0].contains("stride is 1"));
+    assert!(Geometry { base: 0xffff, ..geometry }.problems()[0].contains("past the end"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_text_console_demo() {
+    // The checked in rom has to be what the checked in source assembles to
+    let rom: &[u8] = include_bytes!("../../profiles/text_console.bin");
+    assert_eq!(assembler::assemble_program(include_str!("../../profiles/text_console.asm")).as_deref(), Ok(rom));
+
+    let profile: Profile = Profile::from_toml(include_str!("../../profiles/text_console.toml")).unwrap();
+    assert!(profile.validate().iter().all(|check| check.problems.is_empty()), "{}", profile.report());
+
+    let mut machine: Machine = Machine::with_profile(profile).unwrap();
+    machine.load_rom(rom).unwrap();
+    for _ in 0..300 {
+        machine.run_frame().unwrap();
+    }
+    // Long enough for the watchdog to have reset a rom that wasn't feeding it
+    let lines: Vec<String> = machine.profile().display.text_lines(&machine.cpu.memory);
+    assert_eq!(lines.len(), 25);
+    assert_eq!(lines[0].trim(), "HELLO FROM THE 8080 TEXT CONSOLE");
+    assert_eq!(lines[3].trim(), "FRAMES: 012B");
+    // The last frame's interrupt has only just been taken, its handler runs at the start of the next frame
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use crate::hardware::Port;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnconnectedReadPort(u8),
+    UnconnectedWritePort(u8),
+    // IN or OUT to a port number with no device behind it
+    NotReadable(Port),
+    NotWritable(Port),
+    // Device is connected the wrong way round, usually a broken machine profile
+    Cpu(&'static str),
+    // Anything the dispatcher couldn't handle
+    StackOutOfBounds(u16),
+    // Stack pointer left the profile's stack with its guard set to trap, pc is left after the instruction that moved it
+    Reload(LoadError),
+    // The watchdog reset the board and the rom no longer fitted, the address is where the game was when it fired
+}
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnconnectedReadPort(port) => write!(f, "IN from port {} which has nothing connected", port),
+            ErrorKind::UnconnectedWritePort(port) => write!(f, "OUT to port {} which has nothing connected", port),
+            ErrorKind::NotReadable(port) => write!(f, "{:?} can't be read from", port),
+            ErrorKind::NotWritable(port) => write!(f, "{:?} can't be written to", port),
+            ErrorKind::Cpu(message) => write!(f, "{}", message),
+            ErrorKind::StackOutOfBounds(sp) => write!(f, "moved the stack pointer to 0x{:04x} outside the stack", sp),
+            ErrorKind::Reload(e) => write!(f, "was reset by the watchdog and the rom couldn't be put back: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmulatorError {
+    // Something the rom did that the machine can't carry on from
+    //  Returned instead of panicking so frontends can show it and keep running
+    pub address: u16,
+    pub op_code: u8,
+    // Instruction that caused the error, pc is left pointing at it
+    pub kind: ErrorKind,
+}
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:04x}: 0x{:02x} {}", self.address, self.op_code, self.kind)
+    }
+}
+impl std::error::Error for EmulatorError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadError {
+    // A rom that doesn't fit in the space it was loaded into
+    pub size: usize,
+    pub offset: u16,
+    // Where the first byte was going to go
+    pub region: (u16, u32),
+    // Start and exclusive end of the space the rom has to fit in
+}
+impl LoadError {
+    pub fn overflow(&self) -> usize {
+        // Bytes that didn't fit, counting any space skipped when the offset is outside the region
+        (self.offset as usize + self.size).saturating_sub(self.region.1 as usize)
+    }
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.offset < self.region.0 || self.offset as u32 >= self.region.1 {
+            return write!(f, "Rom loaded at 0x{:04x} which is outside the rom space 0x{:04x}-0x{:04x}", self.offset, self.region.0, self.region.1);
+        }
+        write!(f, "Rom is {} bytes (0x{:04x}) and loaded at 0x{:04x} it runs {} bytes past the end of the rom space 0x{:04x}-0x{:04x}",
+            self.size, self.size, self.offset, self.overflow(), self.region.0, self.region.1)
+    }
+}
+impl std::error::Error for LoadError {}
@@ -0,0 +1,112 @@
+use crate::assembler;
+use crate::machine::Machine;
+
+// Small programs the unit tests run, written out as assembly so a test reads as the code it runs
+//  Numbers are hex like everywhere else in the assembler
+
+pub const COUNT_A: &str = "
+Loop: INR A
+    JMP Loop
+";
+// Counts up in A forever, 5 + 10 cycles each time round
+
+pub const COUNT_TO_PORT: &str = "
+    DI
+Loop: INR A
+    OUT 3
+    STA 0x2000
+    JMP Loop
+";
+// Counts up in A and writes it to port 3 and the start of ram every time round
+
+pub const COUNT_IN_RAM: &str = "
+    LXI H,0x2010
+Loop: INR M
+    JMP Loop
+";
+// Adds one to 0x2010 forever, a write every other instruction
+
+pub const NOP_LOOP: &str = "
+    DI
+Loop: NOP
+    NOP
+    JMP Loop
+";
+// Every instruction is 4 cycles except the 10 cycle JMP
+
+pub const PORTS_WITH_INTERRUPTS: &str = "
+    EI
+Loop: OUT 3
+    IN 1
+    JMP Loop
+    ORG 0x08
+    EI
+    RET
+    ORG 0x10
+    EI
+    RET
+";
+// Enables interrupts then loops writing and reading ports, the handlers re-enable interrupts and return
+
+pub const WAIT_FOR_INTERRUPT: &str = "
+    JMP Start
+    ORG 0x08
+    JMP Handler
+    ORG 0x10
+    JMP Handler
+    ORG 0x20
+Start: LXI SP,0x2400
+    EI
+Wait: LDA 0x2000
+    ANA A
+    JZ Wait
+    XRA A
+    STA 0x2000
+    INR B
+    JMP Wait
+    ORG 0x40
+Handler: PUSH PSW
+    MVI A,1
+    STA 0x2000
+    POP PSW
+    EI
+    RET
+";
+// Spins until an interrupt sets a flag then counts the wake up in B
+
+pub const ECHO_INPUT: &str = "
+    DI
+    LXI SP,0x2400
+Loop: IN 1
+    STA 0x2400
+    JMP Loop
+";
+// Copies input port 1 to the top of video memory over and over
+
+pub const DRAW_BYTE: &str = "
+    MVI A,0xff
+    STA 0x2400
+Spin: JMP Spin
+";
+// Lights the first byte of vram and spins
+
+pub const INPUT_AND_SHOT: &str = "
+    DI
+Loop: IN 1
+    STA 0x2400
+    MVI A,0x02
+    OUT 3
+    JMP Loop
+";
+// Draws input port 1 to the top of the screen and plays the shot sound every time round
+
+pub fn rom(source: &str) -> Vec<u8> {
+    assembler::assemble_program(source).expect("fixture programs should always assemble")
+}
+
+pub fn machine(source: &str) -> Machine {
+    // A Space Invaders machine with the program loaded as its rom
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom(source)).unwrap();
+    machine
+}
@@ -0,0 +1,57 @@
+use crate::error::EmulatorError;
+use crate::machine::Machine;
+
+mod tests;
+
+pub trait Frontend {
+    // Anything that can feed a machine input and show what it drew
+    //  run takes a &mut dyn Frontend so the frontend can be picked at runtime,
+    //  see emulator/examples for a window, a terminal and a benchmark
+
+    fn poll_input(&mut self, machine: &mut Machine);
+    // Called before each frame to set the input ports
+
+    fn present(&mut self, machine: &Machine);
+    // Called after each frame that ran without a fault
+
+    fn should_close(&mut self) -> bool;
+    // Checked before each frame, true stops the run
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Headless {
+    // Runs a set number of frames with no input and shows nothing
+    remaining: u64,
+}
+impl Headless {
+    pub fn new(frames: u64) -> Self {
+        Self {
+            remaining: frames,
+        }
+    }
+}
+impl Frontend for Headless {
+    fn poll_input(&mut self, _machine: &mut Machine) {}
+
+    fn present(&mut self, _machine: &Machine) {
+        self.remaining = self.remaining.saturating_sub(1);
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.remaining == 0
+    }
+}
+
+pub fn run(machine: &mut Machine, frontend: &mut dyn Frontend) -> Result<u64, EmulatorError> {
+    // Runs frames until the frontend closes and returns how many ran
+    //  A fault stops the run straight away, frames before it have already been presented
+
+    let mut frames: u64 = 0;
+    while !frontend.should_close() {
+        frontend.poll_input(machine);
+        machine.run_frame()?;
+        frontend.present(machine);
+        frames += 1;
+    }
+    Ok(frames)
+}
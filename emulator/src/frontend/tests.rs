@@ -0,0 +1,49 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+struct Recorder {
+    // Presses coin on the second frame and keeps the ram byte the rom copies input to
+    polled: u64,
+    seen: Vec<u8>,
+}
+#[cfg(test)]
+impl Frontend for Recorder {
+    fn poll_input(&mut self, machine: &mut Machine) {
+        self.polled += 1;
+        machine.hardware.set_inputs([if self.polled == 2 { 0x01 } else { 0x00 }, 0x00]);
+    }
+
+    fn present(&mut self, machine: &Machine) {
+        self.seen.push(machine.cpu.memory.view(0x2000..0x2001)[0]);
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.seen.len() == 3
+    }
+}
+
+#[test]
+fn test_frontend_run() {
+    let rom: [u8; 9] = [
+        0xf3,             // 0x0000 DI
+        0xdb, 0x01,       // 0x0001 IN 1
+        0x32, 0x00, 0x20, // 0x0003 STA 0x2000
+        0xc3, 0x01, 0x00, // 0x0006 JMP 0x0001
+    ];
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+
+    let mut recorder: Recorder = Recorder { polled: 0, seen: vec![] };
+    let frontends: [&mut dyn Frontend; 2] = [&mut recorder, &mut Headless::new(2)];
+    let frames: Vec<u64> = frontends.into_iter()
+        .map(|frontend| run(&mut machine, frontend).unwrap())
+        .collect();
+    assert_eq!(frames, vec![3, 2]);
+    assert_eq!(recorder.seen, vec![0x00, 0x01, 0x00]);
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xdb, 0x07]).unwrap();
+    // IN from a port with nothing connected faults before anything is presented
+    assert!(run(&mut machine, &mut Headless::new(5)).is_err());
+}
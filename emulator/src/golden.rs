@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::png;
+
+mod tests;
+
+const DIFFERENT: [u8; 3] = [0xff, 0x00, 0x00];
+// Pixels that don't match are bright red in a diff image
+const IGNORED: [u8; 3] = [0x00, 0x00, 0x60];
+// Masked out regions are dark blue so it's clear nothing there was compared
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    // 8 bit rgb pixels row by row from the top left, the same as headless::framebuffer
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        if pixels.len() != width as usize * height as usize * 3 {
+            return Err(format!("{} bytes of pixels don't make a {}x{} image", pixels.len(), width, height));
+        }
+        Ok(Self { width, height, pixels })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes: Vec<u8> = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let (width, height, pixels): (u32, u32, Vec<u8>) = png::decode_rgb(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::new(width, height, pixels)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes: Vec<u8> = png::encode_rgb(self.width, self.height, &self.pixels)?;
+        fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index: usize = (y as usize * self.width as usize + x as usize) * 3;
+        [self.pixels[index], self.pixels[index + 1], self.pixels[index + 2]]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+impl Region {
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&x) && (self.y..self.y.saturating_add(self.height)).contains(&y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    // What a comparison lets through, the default is pixel exact over the whole image
+    pub ignore: Vec<Region>,
+    // Parts of the screen that change from run to run, e.g. the score
+    pub tolerance: u8,
+    // How far each colour channel can be off and still match
+}
+impl Mask {
+    pub fn new() -> Self {
+        Self {
+            ignore: vec![],
+            tolerance: 0,
+        }
+    }
+
+    pub fn ignoring(mut self, region: Region) -> Self {
+        self.ignore.push(region);
+        self
+    }
+
+    pub fn ignored(&self, x: u32, y: u32) -> bool {
+        self.ignore.iter().any(|region| region.contains(x, y))
+    }
+}
+impl Default for Mask {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub pixels: usize,
+    // How many compared pixels didn't match
+    pub first: Option<(u32, u32)>,
+    // The top left most pixel that didn't match
+    pub image: Image,
+    // The actual image dimmed with mismatches in red and ignored regions in blue
+}
+impl Difference {
+    pub fn matched(&self) -> bool {
+        self.pixels == 0
+    }
+}
+
+pub fn compare(expected: &Image, actual: &Image, mask: &Mask) -> Result<Difference, String> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!("Expected a {}x{} image but got {}x{}", expected.width, expected.height, actual.width, actual.height));
+    }
+
+    let mut difference: Difference = Difference {
+        pixels: 0,
+        first: None,
+        image: Image { width: actual.width, height: actual.height, pixels: Vec::with_capacity(actual.pixels.len()) },
+    };
+    for y in 0..actual.height {
+        for x in 0..actual.width {
+            let wanted: [u8; 3] = expected.pixel(x, y);
+            let got: [u8; 3] = actual.pixel(x, y);
+            let colour: [u8; 3] = if mask.ignored(x, y) {
+                IGNORED
+            } else if wanted.iter().zip(got).all(|(wanted, got)| wanted.abs_diff(got) <= mask.tolerance) {
+                got.map(|channel| channel / 4)
+            } else {
+                difference.pixels += 1;
+                difference.first.get_or_insert((x, y));
+                DIFFERENT
+            };
+            difference.image.pixels.extend_from_slice(&colour);
+        }
+    }
+    Ok(difference)
+}
+
+pub fn check(name: &str, golden: &Path, actual: &Image, mask: &Mask, artifacts: &Path) -> Result<(), String> {
+    // Compares a frame against its golden png, on a mismatch the frame and a diff image are saved to artifacts
+    //  as <name>.actual.png and <name>.diff.png so the failure can be looked at
+    //  A missing golden fails too but still leaves the actual frame behind to be checked in
+
+    let difference: Result<Difference, String> = Image::load(golden).and_then(|expected| compare(&expected, actual, mask));
+    if difference.as_ref().is_ok_and(Difference::matched) {
+        return Ok(());
+    }
+
+    fs::create_dir_all(artifacts).map_err(|e| format!("Failed to create {}: {}", artifacts.display(), e))?;
+    let actual_path: PathBuf = artifacts.join(format!("{}.actual.png", name));
+    actual.save(&actual_path)?;
+    match difference {
+        Ok(difference) => {
+            let diff_path: PathBuf = artifacts.join(format!("{}.diff.png", name));
+            difference.image.save(&diff_path)?;
+            let (x, y): (u32, u32) = difference.first.unwrap_or_default();
+            Err(format!("{}: {} pixels differ from {}, first at {},{}, see {}", name, difference.pixels, golden.display(), x, y, diff_path.display()))
+        },
+        Err(e) => Err(format!("{}: {}, the frame was saved to {}", name, e, actual_path.display())),
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::env;
+
+#[cfg(test)]
+fn checkerboard() -> Image {
+    let pixels: Vec<u8> = (0..8 * 4).flat_map(|index| match (index % 8 + index / 8) % 2 {
+        0 => [0x00; 3],
+        _ => [0xff; 3],
+    }).collect();
+    Image::new(8, 4, pixels).unwrap()
+}
+
+#[test]
+fn test_compare() {
+    let expected: Image = checkerboard();
+    let mut actual: Image = checkerboard();
+    assert!(compare(&expected, &actual, &Mask::new()).unwrap().matched());
+
+    actual.pixels[(2 * 8 + 5) * 3] ^= 0x10;
+    actual.pixels[(8 + 6) * 3 + 2] ^= 0x80;
+    let difference: Difference = compare(&expected, &actual, &Mask::new()).unwrap();
+    assert_eq!(difference.pixels, 2);
+    assert_eq!(difference.first, Some((6, 1)));
+    assert_eq!(difference.image.pixel(5, 2), DIFFERENT);
+    assert_eq!(difference.image.pixel(0, 1), [0x3f; 3]);
+    // Matching pixels are dimmed
+
+    let mask: Mask = Mask { tolerance: 0x10, ..Mask::new() }.ignoring(Region { x: 6, y: 0, width: 2, height: 2 });
+    let difference: Difference = compare(&expected, &actual, &mask).unwrap();
+    assert!(difference.matched());
+    assert_eq!(difference.image.pixel(7, 0), IGNORED);
+
+    let small: Image = Image::new(4, 4, vec![0; 4 * 4 * 3]).unwrap();
+    assert!(compare(&expected, &small, &Mask::new()).is_err());
+    assert!(Image::new(4, 4, vec![0; 5]).is_err());
+}
+
+#[test]
+fn test_check() {
+    let directory: PathBuf = env::temp_dir().join(format!("emulator-golden-test-{}", std::process::id()));
+    let golden: PathBuf = directory.join("board.png");
+    let artifacts: PathBuf = directory.join("artifacts");
+    fs::create_dir_all(&directory).unwrap();
+    checkerboard().save(&golden).unwrap();
+
+    assert_eq!(check("board", &golden, &checkerboard(), &Mask::new(), &artifacts), Ok(()));
+    assert!(!artifacts.exists());
+
+    let mut actual: Image = checkerboard();
+    actual.pixels[0] = 0x80;
+    let error: String = check("board", &golden, &actual, &Mask::new(), &artifacts).unwrap_err();
+    assert!(error.contains("1 pixels differ"), "{}", error);
+    assert_eq!(Image::load(&artifacts.join("board.actual.png")).unwrap(), actual);
+    assert_eq!(Image::load(&artifacts.join("board.diff.png")).unwrap().pixel(0, 0), DIFFERENT);
+
+    assert!(check("missing", &directory.join("missing.png"), &actual, &Mask::new(), &artifacts).is_err());
+    assert!(artifacts.join("missing.actual.png").exists());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
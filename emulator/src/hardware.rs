@@ -1,8 +1,31 @@
 mod tests;
+pub mod cocktail;
+pub mod console;
+pub mod devices;
 pub mod input;
+pub mod port_map;
+pub mod shift;
+pub mod sound;
 
-#[derive(Debug, Clone, Copy)]
-enum Port {
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use crate::error::ErrorKind;
+
+use cocktail::Cocktail;
+use devices::{Claim, Direction, IoBus, IoDevice, Reset, ResetPolicy, BOARD_PRIORITY};
+use input::{DipSwitches, ServiceSwitch, COIN_BIT};
+use port_map::PortMap;
+use shift::ShiftConfig;
+use sound::SoundLatch;
+
+pub const WATCHDOG_FRAMES: u16 = 255;
+// Number of frames Space Invaders can go without writing to the watchdog port before the machine is reset
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub enum Port {
+    // Devices on the board, which port number reaches each one is set by a PortMap
     INP1,
     INP2,
     SHFTIN,
@@ -13,6 +36,43 @@ enum Port {
     WATCHDOG,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardDevice {
+    // The parts of the board a reset can clear, each cleared by the resets its policy says
+    ShiftRegister,
+    Sound,
+    Flip,
+    Watchdog,
+    Inputs,
+    DipSwitches,
+}
+impl BoardDevice {
+    pub const ALL: [BoardDevice; 6] = [BoardDevice::ShiftRegister, BoardDevice::Sound, BoardDevice::Flip, BoardDevice::Watchdog, BoardDevice::Inputs, BoardDevice::DipSwitches];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardDevice::ShiftRegister => "shift register",
+            BoardDevice::Sound => "sound",
+            BoardDevice::Flip => "flip",
+            BoardDevice::Watchdog => "watchdog",
+            BoardDevice::Inputs => "inputs",
+            BoardDevice::DipSwitches => "dip switches",
+        }
+    }
+
+    pub fn policy(self) -> ResetPolicy {
+        match self {
+            BoardDevice::ShiftRegister => ResetPolicy::PowerOnly,
+            // The shift chip isn't on the reset line, the game loads it again before reading it anyway
+            BoardDevice::Sound | BoardDevice::Flip | BoardDevice::Watchdog => ResetPolicy::Always,
+            // Latches cleared by the reset line, so a watchdog reset doesn't leave the ufo looping
+            BoardDevice::Inputs => ResetPolicy::PowerOnly,
+            // Whatever is being held stays held, only the coin counter's edge starts over with the power
+            BoardDevice::DipSwitches => ResetPolicy::Never,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Ports {
     input_1: u8,
@@ -33,11 +93,10 @@ struct Ports {
     //      6: P2 Right
     //      7: Coin info toggle (0: On, 1: Off)
     shift_amount: u8,
-    // Offset from the left that will be read when reading shift_result
-    // First 3 bits are the offset
-    // Offset of 2 will start reading from the 3rd bit
-    sound_1: u8,
-    sound_2: u8,
+    // Last byte written to SHFTAMNT, the shift config picks the offset out of it
+    //  On Space Invaders the first 3 bits are the offset from the left, 2 starts reading from the 3rd bit
+    sound_1: SoundLatch,
+    sound_2: SoundLatch,
     watchdog: u8,
     // When text is written to the screen this is the ascii value of each letter written
 }
@@ -47,8 +106,8 @@ impl Ports {
             input_1: 0x08,
             input_2: 0x00,
             shift_amount: 0x00,
-            sound_1: 0x00,
-            sound_2: 0x00,
+            sound_1: SoundLatch::default(),
+            sound_2: SoundLatch::default(),
             watchdog: 0x00,
         }
     }
@@ -59,22 +118,289 @@ impl Default for Ports {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Hardware {
     shift_register: u16,
+    shift_config: ShiftConfig,
     ports: Ports,
+    port_map: PortMap,
+    bus: IoBus,
+    // Devices attached on top of the board, they are asked before the port map
+    dip_switches: DipSwitches,
+    watchdog_frames: u16,
+    // Frames since the watchdog was last written to
+    watchdog_limit: Option<u16>,
+    // Frames without a write before the board resets, None for a board without a watchdog
+    service_switch: Option<ServiceSwitch>,
+    service_mode: bool,
+    coins: u64,
+    // The cabinet's mechanical coin counter, it only ever goes up
+    coin_held: bool,
+    coin_pulse_frames: u8,
+    // Shortest a coin press is held for, some rom revisions debounce the coin input and miss a quicker tap
+    coin_pressed: bool,
+    coin_stretch: u8,
+    // Frames left of the pulse started by the last press, the coin bit stays set until it runs out
+    cocktail: Option<Cocktail>,
+    flipped: bool,
+    // Player 2's turn on a cocktail cabinet, the screen is drawn upside down and inputs go through its remap
+    paused: bool,
+    // Held by the frontend, inputs and the watchdog and coin counter stay as they are until it is let go
+    audio_paused: bool,
+    // Whether the audio sink has been told about the pause yet, see sound::update_audio
 }
 impl Hardware {
     pub fn init() -> Self {
-        Self {
+        let mut hardware: Hardware = Self {
             shift_register: 0x0000,
+            shift_config: ShiftConfig::space_invaders(),
             ports: Ports::default(),
-        }
+            port_map: PortMap::space_invaders(),
+            bus: IoBus::new(),
+            dip_switches: DipSwitches::default(),
+            watchdog_frames: 0,
+            watchdog_limit: Some(WATCHDOG_FRAMES),
+            service_switch: None,
+            service_mode: false,
+            coins: 0,
+            coin_held: false,
+            coin_pulse_frames: 0,
+            coin_pressed: false,
+            coin_stretch: 0,
+            cocktail: None,
+            flipped: false,
+            paused: false,
+            audio_paused: false,
+        };
+        hardware.set_dip_switches(DipSwitches::default());
+
+        hardware
     }
 
     pub fn reset(&mut self) {
-        // Resets all the values of the cpu
-        *self = Hardware::default();
+        // Power on reset, everything on the board goes back to how it starts
+        //  Port numbers, attached devices and DIP switches are how the board is wired so they stay the same
+        //  The service switch, coin counter and cocktail wiring are part of the cabinet so a reset doesn't touch them either
+        //  Pausing belongs to the frontend so a reset underneath it doesn't let go
+        self.reset_board(Reset::Hard);
+    }
+
+    pub fn soft_reset(&mut self) {
+        // The reset line, only the devices wired to it are cleared, see BoardDevice::policy
+        self.reset_board(Reset::Soft);
+    }
+
+    fn reset_board(&mut self, reset: Reset) {
+        for device in BoardDevice::ALL.into_iter().filter(|device| device.policy().resets_on(reset)) {
+            self.reset_device(device);
+        }
+        self.bus.reset(reset);
+    }
+
+    fn reset_device(&mut self, device: BoardDevice) {
+        match device {
+            BoardDevice::ShiftRegister => self.set_shift_state((0x0000, 0x00)),
+            BoardDevice::Sound => {
+                self.ports.sound_1.clear();
+                self.ports.sound_2.clear();
+                // Sounds still on have to be stopped, starting over from a fresh latch would leave the ufo looping
+            },
+            BoardDevice::Flip => self.flipped = false,
+            BoardDevice::Watchdog => {
+                self.ports.watchdog = 0x00;
+                self.watchdog_frames = 0;
+            },
+            BoardDevice::Inputs => {
+                let ports: Ports = Ports::default();
+                self.set_inputs([ports.input_1, ports.input_2]);
+                self.coin_held = false;
+                (self.coin_pressed, self.coin_stretch) = (false, 0);
+                self.set_dip_switches(self.dip_switches);
+                self.set_service_mode(self.service_mode);
+            },
+            BoardDevice::DipSwitches => self.set_dip_switches(DipSwitches::default()),
+        }
+    }
+
+    pub fn set_port_map(&mut self, port_map: PortMap) {
+        // Connects devices to port numbers, any port not in the map is left unconnected
+        self.port_map = port_map;
+    }
+
+    pub fn port_map(&self) -> &PortMap {
+        &self.port_map
+    }
+
+    pub fn set_shift_config(&mut self, shift_config: ShiftConfig) {
+        self.shift_config = shift_config;
+    }
+
+    pub fn shift_config(&self) -> &ShiftConfig {
+        &self.shift_config
+    }
+
+    pub fn attach(&mut self, device: Box<dyn IoDevice>, claims: &[Claim], priority: u8) -> Result<(), String> {
+        // Plugs a device into the ports it claims
+        //  Taking over one of the board's ports needs a priority above BOARD_PRIORITY
+
+        if priority == BOARD_PRIORITY {
+            for claim in claims {
+                let board_port: Option<Port> = match claim.direction {
+                    Direction::Read => self.port_map.read(claim.number),
+                    Direction::Write => self.port_map.write(claim.number),
+                };
+                if let Some(board_port) = board_port {
+                    return Err(format!("{} claims {:?} port {} which the board uses for {:?}", device.name(), claim.direction, claim.number, board_port));
+                }
+            }
+        }
+
+        self.bus.attach(device, claims, priority)
+    }
+
+    pub fn detach(&mut self, name: &str) -> Option<Box<dyn IoDevice>> {
+        self.bus.detach(name)
+    }
+
+    pub fn bus(&self) -> &IoBus {
+        &self.bus
+    }
+
+    pub fn set_dip_switches(&mut self, dip_switches: DipSwitches) {
+        // DIP switches share input 2 with the player 2 controls
+        self.dip_switches = dip_switches;
+        self.ports.input_2 = dip_switches.merge(self.ports.input_2);
+    }
+
+    pub fn set_service_switch(&mut self, service_switch: Option<ServiceSwitch>) {
+        self.service_switch = service_switch;
+        self.set_service_mode(false);
+    }
+
+    pub fn service_switch(&self) -> Option<ServiceSwitch> {
+        self.service_switch
+    }
+
+    pub fn set_service_mode(&mut self, on: bool) -> bool {
+        // Flips the service switch, returns false when the board doesn't have one
+        let Some(service_switch) = self.service_switch else {
+            return false;
+        };
+        self.service_mode = on;
+        [self.ports.input_1, self.ports.input_2] = service_switch.merge(self.inputs(), on);
+        true
+    }
+
+    pub fn service_mode(&self) -> bool {
+        self.service_mode
+    }
+
+    pub fn coins(&self) -> u64 {
+        self.coins
+    }
+
+    pub fn set_coins(&mut self, coins: u64) {
+        // Carries the count on from an earlier session, see stats::Stats
+        self.coins = coins;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        // Freezes the cabinet around the cpu, the sounds are stopped the next time the audio is updated
+        self.paused = paused;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn tick_coin_counter(&mut self) {
+        // Should be called once per frame, counts a coin each time the coin input goes from off to on
+        if self.paused {
+            return;
+        }
+        let held: bool = self.ports.input_1 & (1 << COIN_BIT) != 0;
+        if held && !self.coin_held {
+            self.coins += 1;
+        }
+        self.coin_held = held;
+    }
+
+    pub fn set_coin_pulse_frames(&mut self, frames: u8) {
+        self.coin_pulse_frames = frames;
+        self.coin_stretch = self.coin_stretch.min(frames);
+    }
+
+    pub fn coin_pulse_frames(&self) -> u8 {
+        self.coin_pulse_frames
+    }
+
+    fn stretch_coin(&mut self, pressed: bool) -> bool {
+        // Called once a frame with whether the coin key is down, returns whether the coin bit should be set
+        //  A press holds the bit for at least coin_pulse_frames frames, holding the key longer holds it longer
+
+        if pressed && !self.coin_pressed {
+            self.coin_stretch = self.coin_pulse_frames;
+        }
+        self.coin_pressed = pressed;
+        let held: bool = pressed || self.coin_stretch > 0;
+        self.coin_stretch = self.coin_stretch.saturating_sub(1);
+        held
+    }
+
+    pub fn set_cocktail(&mut self, cocktail: Option<Cocktail>) {
+        self.cocktail = cocktail;
+        self.flipped = false;
+    }
+
+    pub fn cocktail(&self) -> Option<&Cocktail> {
+        self.cocktail.as_ref()
+    }
+
+    pub fn flipped(&self) -> bool {
+        self.flipped
+    }
+
+    pub fn set_flipped(&mut self, flipped: bool) {
+        // Only a cocktail cabinet can be turned round, for loading states and flips that come from memory
+        self.flipped = flipped && self.cocktail.is_some();
+    }
+
+    pub fn shift_state(&self) -> (u16, u8) {
+        // Shift register contents and amount, the only board state a game reads back
+        (self.shift_register, self.ports.shift_amount)
+    }
+
+    pub fn set_shift_state(&mut self, (shift_register, shift_amount): (u16, u8)) {
+        self.shift_register = shift_register;
+        self.ports.shift_amount = shift_amount;
+    }
+
+    pub fn set_watchdog(&mut self, limit: Option<u16>) {
+        self.watchdog_limit = limit;
+        self.watchdog_frames = 0;
+    }
+
+    pub fn watchdog(&self) -> Option<u16> {
+        self.watchdog_limit
+    }
+
+    pub fn tick_watchdog(&mut self) -> bool {
+        // Should be called once per frame
+        //  Returns true when the game has stopped writing to the watchdog and the machine should be reset
+        //  Doesn't count while paused so stepping through a paused machine in the debugger doesn't reset it
+
+        let Some(limit) = self.watchdog_limit else {
+            return false;
+        };
+        if self.paused {
+            return false;
+        }
+        self.watchdog_frames += 1;
+        if self.watchdog_frames > limit {
+            self.watchdog_frames = 0;
+            return true;
+        }
+        false
     }
 
     pub fn debug_input1(&self) -> u8 {
@@ -83,6 +409,23 @@ impl Hardware {
     pub fn debug_input2(&self) -> u8 {
         self.ports.input_2
     }
+
+    pub fn inputs(&self) -> [u8; 2] {
+        [self.ports.input_1, self.ports.input_2]
+    }
+
+    fn read_inputs(&self) -> [u8; 2] {
+        // What the game sees on the input ports, inputs() stays as the keys set them so recordings don't depend on the flip
+        match (&self.cocktail, self.flipped) {
+            (Some(cocktail), true) => cocktail.apply(self.inputs()),
+            _ => self.inputs(),
+        }
+    }
+
+    pub fn set_inputs(&mut self, inputs: [u8; 2]) {
+        // Sets both input ports at once, for playing back recorded input
+        [self.ports.input_1, self.ports.input_2] = inputs;
+    }
 }
 impl Default for Hardware {
     fn default() -> Self {
@@ -90,59 +433,61 @@ impl Default for Hardware {
     }
 }
 
-pub fn handle_io(op_code: u8, hardware: &mut Hardware, port_byte: u8, reg_a: u8) -> Option<u8> {
+pub fn handle_io(op_code: u8, hardware: &mut Hardware, port_byte: u8, reg_a: u8) -> Result<Option<u8>, ErrorKind> {
     match op_code {
         0xd3 => { // OUT
-            let port: Port = match port_byte {
-                2 => Port::SHFTAMNT,
-                3 => Port::SOUND1,
-                4 => Port::SHFTDATA,
-                5 => Port::SOUND2,
-                6 => Port::WATCHDOG,
-                _ => panic!("OUT should only ever have an additional byte between 2 and 6"),
+            if let Some(flipped) = hardware.cocktail.as_ref().and_then(|cocktail| cocktail.flipped_by_output(port_byte, reg_a)) {
+                hardware.flipped = flipped;
+            }
+            // The flip output shares its port with other devices so the write still goes on to them
+
+            if hardware.bus.write(port_byte, reg_a) {
+                return Ok(None);
+            }
+            let port: Port = match hardware.port_map.write(port_byte) {
+                Some(port) => port,
+                None => return Err(ErrorKind::UnconnectedWritePort(port_byte)),
             };
 
-            write_port(reg_a, port, hardware);
-            return None;
+            write_port(reg_a, port, hardware)?;
+            Ok(None)
         },
         0xdb => { // IN
-            let port: Port = match port_byte {
-                0 => panic!("INP0 port is not used by space invaders"),
-                1 => Port::INP1,
-                2 => Port::INP2,
-                3 => Port::SHFTIN,
-                _ => panic!("IN should only ever have an additional byte between 0 and 3"),
+            if let Some(value) = hardware.bus.read(port_byte) {
+                return Ok(Some(value));
+            }
+            let port: Port = match hardware.port_map.read(port_byte) {
+                Some(port) => port,
+                None => return Err(ErrorKind::UnconnectedReadPort(port_byte)),
             };
 
-            return Some(read_port(port, hardware));
+            Ok(Some(read_port(port, hardware)?))
         },
-        _ => panic!("All other op_codes should be handled by the cpu module"),
+        _ => Err(ErrorKind::Cpu("All other op_codes should be handled by the cpu module")),
     }
 }
 
-fn write_port(write_value: u8, port: Port, hardware: &mut Hardware) {
+fn write_port(write_value: u8, port: Port, hardware: &mut Hardware) -> Result<(), ErrorKind> {
     match port {
         Port::SHFTAMNT => hardware.ports.shift_amount = write_value,
-        Port::SOUND1 => hardware.ports.sound_1 = write_value,
-        Port::SHFTDATA => hardware.shift_register = ((write_value as u16) << 8) | (hardware.shift_register >> 8),
-        Port::SOUND2 => hardware.ports.sound_2 = write_value,
-        Port::WATCHDOG => hardware.ports.watchdog = write_value,
-        _ => panic!("Can only write to write ports"),
+        Port::SOUND1 => hardware.ports.sound_1.write(write_value),
+        Port::SHFTDATA => hardware.shift_register = hardware.shift_config.write(hardware.shift_register, write_value),
+        Port::SOUND2 => hardware.ports.sound_2.write(write_value),
+        Port::WATCHDOG => {
+            hardware.ports.watchdog = write_value;
+            hardware.watchdog_frames = 0;
+            // Any write kicks the watchdog
+        },
+        _ => return Err(ErrorKind::NotWritable(port)),
     }
+    Ok(())
 }
 
-fn read_port(port: Port, hardware: &mut Hardware) -> u8 {
+fn read_port(port: Port, hardware: &mut Hardware) -> Result<u8, ErrorKind> {
     match port {
-        Port::INP1 => return hardware.ports.input_1,
-        Port::INP2 => return hardware.ports.input_2,
-        Port::SHFTIN => {
-            let left_offset = hardware.ports.shift_amount & 0b0000_0111;
-            // Only get bits 0-2 for offset
-            let right_offset = 8 - left_offset;
-            // we read 8 bit which leaves over right_offset of bits not read
-
-            return (hardware.shift_register >> right_offset) as u8;
-        },
-        _ => panic!("Can only read from read ports"),
+        Port::INP1 => Ok(hardware.read_inputs()[0]),
+        Port::INP2 => Ok(hardware.read_inputs()[1]),
+        Port::SHFTIN => Ok(hardware.shift_config.read(hardware.shift_register, hardware.ports.shift_amount)),
+        _ => Err(ErrorKind::NotReadable(port)),
     }
 }
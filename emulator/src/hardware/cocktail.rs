@@ -0,0 +1,93 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "lowercase"))]
+pub enum FlipSource {
+    // What tells a cocktail cabinet it is player 2's turn
+    Output { port: u8, bit: u8 },
+    // A bit the game writes out, Space Invaders sets port 5 bit 5 while player 2 is up
+    Ram { address: u16, mask: u8, value: u8 },
+    // A byte the game keeps in memory, flipped while the masked byte equals value
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Remap {
+    // While flipped the input bit at from is read at to instead
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+    // Each is an input port, 1 or 2, and a bit
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Cocktail {
+    // A table cabinet where the players sit facing each other
+    //  The screen is turned round for player 2 and their controls are wired to their own side
+    pub flip: FlipSource,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub remap: Vec<Remap>,
+    // Lets player 1's keys play player 2's turn, a cabinet has a second set of controls instead
+}
+impl Cocktail {
+    pub fn flipped_by_output(&self, port: u8, value: u8) -> Option<bool> {
+        // Whether a write to port turns the screen round, nothing when the port isn't the flip output
+        match self.flip {
+            FlipSource::Output { port: flip_port, bit } if flip_port == port => Some(value & (1 << bit) != 0),
+            _ => None,
+        }
+    }
+
+    pub fn flipped_by_ram(&self, byte: u8) -> bool {
+        match self.flip {
+            FlipSource::Ram { mask, value, .. } => byte & mask == value,
+            FlipSource::Output { .. } => false,
+        }
+    }
+
+    pub fn address(&self) -> Option<u16> {
+        // The byte to watch when the flip comes from memory
+        match self.flip {
+            FlipSource::Ram { address, .. } => Some(address),
+            FlipSource::Output { .. } => None,
+        }
+    }
+
+    pub fn apply(&self, inputs: [u8; 2]) -> [u8; 2] {
+        // The input ports as a flipped game reads them
+        //  Every from bit is cleared before any to bit is set so two bits can swap
+
+        let mut result: [u8; 2] = inputs;
+        for remap in &self.remap {
+            if let Some(port) = result.get_mut(remap.from.0.wrapping_sub(1) as usize) {
+                *port &= !(1 << remap.from.1);
+            }
+        }
+        for remap in &self.remap {
+            let held: bool = inputs.get(remap.from.0.wrapping_sub(1) as usize).is_some_and(|port| port & (1 << remap.from.1) != 0);
+            if let (true, Some(port)) = (held, result.get_mut(remap.to.0.wrapping_sub(1) as usize)) {
+                *port |= 1 << remap.to.1;
+            }
+        }
+        result
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        if let FlipSource::Output { bit, .. } = self.flip {
+            if bit > 7 {
+                problems.push(format!("Flip output bit {} isn't a bit of a byte", bit));
+            }
+        }
+        for remap in &self.remap {
+            for (input, bit) in [remap.from, remap.to] {
+                if !(1..=2).contains(&input) || bit > 7 {
+                    problems.push(format!("Input {} bit {} isn't on an input port, inputs are 1 or 2 and bits 0 to 7", input, bit));
+                }
+            }
+        }
+        problems
+    }
+}
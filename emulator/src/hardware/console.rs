@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::devices::{Claim, IoDevice, BOARD_PRIORITY};
+use super::Hardware;
+
+pub const NAME: &str = "debug_console";
+
+#[derive(Debug, Clone)]
+pub struct DebugConsole {
+    // Lets homebrew print to the host terminal with a single OUT instead of going through a CP/M shim
+    //  OUT writes a character, IN takes the next byte typed into the terminal or 0 when there isn't one
+    input: Arc<Mutex<VecDeque<u8>>>,
+    // Shared so snapshots of the machine see the same keyboard
+    captured: Option<Arc<Mutex<Vec<u8>>>>,
+    // Output is kept here instead of printed when set
+}
+impl DebugConsole {
+    pub fn new() -> Self {
+        Self {
+            input: Arc::new(Mutex::new(VecDeque::new())),
+            captured: None,
+        }
+    }
+
+    pub fn captured() -> Self {
+        // Keeps everything written instead of printing it, for tests and frontends with their own console
+        Self {
+            captured: Some(Arc::new(Mutex::new(vec![]))),
+            ..Self::new()
+        }
+    }
+
+    pub fn read_stdin(self) -> Self {
+        // Feeds bytes typed into the terminal to IN
+        //  Stdin blocks so it is read on its own thread, bytes queue up until the program asks for them
+
+        let input: Arc<Mutex<VecDeque<u8>>> = Arc::clone(&self.input);
+        thread::spawn(move || {
+            let mut buffer: [u8; 64] = [0; 64];
+            while let Ok(read) = io::stdin().read(&mut buffer) {
+                match input.lock() {
+                    Ok(mut input) if read > 0 => input.extend(&buffer[..read]),
+                    _ => break,
+                }
+            }
+            // Stops at the end of input
+        });
+        self
+    }
+
+    pub fn push_input(&self, bytes: &[u8]) {
+        if let Ok(mut input) = self.input.lock() {
+            input.extend(bytes);
+        }
+    }
+
+    pub fn output(&self) -> Option<Vec<u8>> {
+        // Everything written so far when output is captured
+        self.captured.as_ref().and_then(|captured| captured.lock().ok().map(|output| output.clone()))
+    }
+
+    pub fn attach(self, hardware: &mut Hardware, number: u8) -> Result<(), String> {
+        // Claims both directions of a port the board doesn't use
+        hardware.attach(Box::new(self), &[Claim::read(number), Claim::write(number)], BOARD_PRIORITY)
+    }
+}
+impl Default for DebugConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl IoDevice for DebugConsole {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn read(&mut self, _number: u8) -> u8 {
+        self.input.lock().ok().and_then(|mut input| input.pop_front()).unwrap_or(0)
+    }
+
+    fn write(&mut self, _number: u8, value: u8) {
+        match &self.captured {
+            Some(captured) => if let Ok(mut output) = captured.lock() {
+                output.push(value);
+            },
+            None => {
+                let mut stdout = io::stdout();
+                let _ = stdout.write_all(&[value]);
+                let _ = stdout.flush();
+                // Flushed every byte so output isn't lost if the machine faults
+            },
+        }
+    }
+
+    fn clone_device(&self) -> Box<dyn IoDevice> {
+        Box::new(self.clone())
+    }
+}
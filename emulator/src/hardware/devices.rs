@@ -0,0 +1,196 @@
+use std::fmt;
+
+pub const BOARD_PRIORITY: u8 = 0;
+// Priority of the devices built into the board, attached devices need a higher one to take over their ports
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Read,
+    // IN
+    Write,
+    // OUT
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reset {
+    Soft,
+    // The reset line, pulled by the watchdog or the reset key while the power stays on
+    Hard,
+    // The power switched off and on again
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    // Which resets clear a device
+    Always,
+    PowerOnly,
+    // Not wired to the reset line, it keeps what it holds until the power goes
+    Never,
+    // Set by hand, nothing the board does changes it
+}
+impl ResetPolicy {
+    pub fn resets_on(self, reset: Reset) -> bool {
+        match self {
+            ResetPolicy::Always => true,
+            ResetPolicy::PowerOnly => reset == Reset::Hard,
+            ResetPolicy::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Claim {
+    // A port number a device answers in one direction
+    pub number: u8,
+    pub direction: Direction,
+}
+impl Claim {
+    pub fn read(number: u8) -> Self {
+        Self { number, direction: Direction::Read }
+    }
+
+    pub fn write(number: u8) -> Self {
+        Self { number, direction: Direction::Write }
+    }
+}
+
+pub trait IoDevice: fmt::Debug {
+    // Something plugged into the io ports on top of the board's own devices
+    //  Only called for the ports it claimed when it was attached
+
+    fn name(&self) -> &str;
+    fn read(&mut self, number: u8) -> u8;
+    fn write(&mut self, number: u8, value: u8);
+    fn reset(&mut self) {}
+    // Called when the machine is reset in a way reset_policy says clears it, devices stay attached
+    fn reset_policy(&self) -> ResetPolicy {
+        ResetPolicy::PowerOnly
+    }
+    fn clone_device(&self) -> Box<dyn IoDevice>;
+    // Lets machines with devices attached be snapshotted
+}
+impl Clone for Box<dyn IoDevice> {
+    fn clone(&self) -> Self {
+        self.clone_device()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Attached {
+    device: Box<dyn IoDevice>,
+    claims: Vec<Claim>,
+    priority: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct IoBus {
+    // Devices attached to the ports and which one answers each port number
+    devices: Vec<Attached>,
+    read: [Option<usize>; 0x100],
+    write: [Option<usize>; 0x100],
+    // Index into devices of the highest priority claim on each port
+}
+impl IoBus {
+    pub fn new() -> Self {
+        Self {
+            devices: vec![],
+            read: [None; 0x100],
+            write: [None; 0x100],
+        }
+    }
+
+    fn routes(&self, direction: Direction) -> &[Option<usize>; 0x100] {
+        match direction {
+            Direction::Read => &self.read,
+            Direction::Write => &self.write,
+        }
+    }
+
+    pub fn priority(&self, claim: Claim) -> Option<u8> {
+        // Priority of the device answering a port, None when no attached device claims it
+        self.routes(claim.direction)[claim.number as usize].map(|index| self.devices[index].priority)
+    }
+
+    pub fn attach(&mut self, device: Box<dyn IoDevice>, claims: &[Claim], priority: u8) -> Result<(), String> {
+        // Adds a device, the highest priority claim on a port gets it
+        //  Two devices claiming the same port at the same priority is a mistake so the second is refused
+
+        if self.devices.iter().any(|attached| attached.device.name() == device.name()) {
+            return Err(format!("A device called {} is already attached", device.name()));
+        }
+        for claim in claims {
+            let conflict: Option<&Attached> = self.devices.iter().find(|attached| attached.priority == priority && attached.claims.contains(claim));
+            if let Some(conflict) = conflict {
+                return Err(format!("{} and {} both claim {:?} port {} at priority {}", device.name(), conflict.device.name(), claim.direction, claim.number, priority));
+            }
+        }
+
+        self.devices.push(Attached { device, claims: claims.to_vec(), priority });
+        self.rebuild_routes();
+        Ok(())
+    }
+
+    pub fn detach(&mut self, name: &str) -> Option<Box<dyn IoDevice>> {
+        let index: usize = self.devices.iter().position(|attached| attached.device.name() == name)?;
+        let attached: Attached = self.devices.remove(index);
+        self.rebuild_routes();
+        // Any ports it was shadowing go back to the next highest claim
+        Some(attached.device)
+    }
+
+    fn rebuild_routes(&mut self) {
+        self.read = [None; 0x100];
+        self.write = [None; 0x100];
+        for (index, attached) in self.devices.iter().enumerate() {
+            for claim in &attached.claims {
+                let routes: &mut [Option<usize>; 0x100] = match claim.direction {
+                    Direction::Read => &mut self.read,
+                    Direction::Write => &mut self.write,
+                };
+                let route: &mut Option<usize> = &mut routes[claim.number as usize];
+                if route.is_none_or(|owner| self.devices[owner].priority < attached.priority) {
+                    *route = Some(index);
+                }
+            }
+        }
+    }
+
+    pub fn device(&self, name: &str) -> Option<&dyn IoDevice> {
+        self.devices.iter()
+            .find(|attached| attached.device.name() == name)
+            .map(|attached| attached.device.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        // Attached devices in the order they were attached
+        self.devices.iter().map(|attached| attached.device.name())
+    }
+
+    pub fn read(&mut self, number: u8) -> Option<u8> {
+        // None when no attached device claims the port
+        let index: usize = self.read[number as usize]?;
+        Some(self.devices[index].device.read(number))
+    }
+
+    pub fn write(&mut self, number: u8, value: u8) -> bool {
+        // False when no attached device claims the port
+        match self.write[number as usize] {
+            Some(index) => {
+                self.devices[index].device.write(number, value);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn reset(&mut self, reset: Reset) {
+        for attached in self.devices.iter_mut().filter(|attached| attached.device.reset_policy().resets_on(reset)) {
+            attached.device.reset();
+        }
+    }
+}
+impl Default for IoBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
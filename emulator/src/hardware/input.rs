@@ -1,7 +1,10 @@
-use raylib::prelude::KeyboardKey;
+#[cfg(feature = "frontend")]
+use raylib::prelude::RaylibHandle;
 use super::*;
 
-const COIN_BIT: u8 = 0;
+pub use crate::primitives::KeyboardKey;
+
+pub const COIN_BIT: u8 = 0;
 const P2_START_BIT: u8 = 1;
 const P1_START_BIT: u8 = 2;
 const P1_SHOOT_BIT: u8 = 4;
@@ -14,6 +17,125 @@ const P2_SHOOT_BIT: u8 = 4;
 const P2_LEFT_BIT: u8 = 5;
 const P2_RIGHT_BIT: u8 = 6;
 
+const LIVES_BITS: u8 = 0b0000_0011;
+const BONUS_LIFE_BIT: u8 = 3;
+const COIN_INFO_BIT: u8 = 7;
+// DIP switch bits in input 2
+
+pub trait InputSource {
+    // Anything that can report which keys are held down
+
+    fn is_key_down(&self, key: KeyboardKey) -> bool;
+}
+#[cfg(feature = "frontend")]
+impl InputSource for RaylibHandle {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        RaylibHandle::is_key_down(self, key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DipSwitches {
+    pub extra_lives: u8,
+    // 0: 3 Lives, 1: 4, 2: 5, 3: 6
+    pub bonus_life_at_1000: bool,
+    // Bonus life is at 1500 points when off
+    pub coin_info_off: bool,
+}
+impl DipSwitches {
+    pub const BITS: u8 = LIVES_BITS | 1 << BONUS_LIFE_BIT | 1 << COIN_INFO_BIT;
+    // Every bit of input 2 the switches set
+
+    fn new() -> Self {
+        Self {
+            extra_lives: 0,
+            bonus_life_at_1000: false,
+            coin_info_off: false,
+        }
+    }
+
+    pub fn merge(&self, input_2: u8) -> u8 {
+        // Sets the DIP switch bits of input 2 while leaving the player 2 controls untouched
+
+        let mut result: u8 = input_2 & !Self::BITS;
+        result |= self.extra_lives & LIVES_BITS;
+        result |= (self.bonus_life_at_1000 as u8) << BONUS_LIFE_BIT;
+        result |= (self.coin_info_off as u8) << COIN_INFO_BIT;
+
+        result
+    }
+}
+impl Default for DipSwitches {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct ServiceSwitch {
+    // The switch inside the cabinet that puts a game into its test screens, where a profile says it is wired
+    //  Flipped with F2 rather than held like a button
+    pub input: u8,
+    // 1 or 2
+    pub bit: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub active_low: bool,
+    // The bit reads 0 while the switch is on
+}
+impl ServiceSwitch {
+    pub fn merge(&self, inputs: [u8; 2], on: bool) -> [u8; 2] {
+        // Sets or clears the switch's bit in whichever input port it is on
+        let mut inputs: [u8; 2] = inputs;
+        if let Some(port) = inputs.get_mut(self.input.wrapping_sub(1) as usize) {
+            match on != self.active_low {
+                true => *port |= 1 << self.bit,
+                false => *port &= !(1 << self.bit),
+            }
+        }
+        inputs
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    // Holding the key sets a bit of one of the input ports
+    pub key: KeyboardKey,
+    pub input: u8,
+    // 1 or 2
+    pub bit: u8,
+}
+
+const KEY_NAMES: [(&str, KeyboardKey); 63] = [
+    ("A", KeyboardKey::KEY_A), ("B", KeyboardKey::KEY_B), ("C", KeyboardKey::KEY_C), ("D", KeyboardKey::KEY_D),
+    ("E", KeyboardKey::KEY_E), ("F", KeyboardKey::KEY_F), ("G", KeyboardKey::KEY_G), ("H", KeyboardKey::KEY_H),
+    ("I", KeyboardKey::KEY_I), ("J", KeyboardKey::KEY_J), ("K", KeyboardKey::KEY_K), ("L", KeyboardKey::KEY_L),
+    ("M", KeyboardKey::KEY_M), ("N", KeyboardKey::KEY_N), ("O", KeyboardKey::KEY_O), ("P", KeyboardKey::KEY_P),
+    ("Q", KeyboardKey::KEY_Q), ("R", KeyboardKey::KEY_R), ("S", KeyboardKey::KEY_S), ("T", KeyboardKey::KEY_T),
+    ("U", KeyboardKey::KEY_U), ("V", KeyboardKey::KEY_V), ("W", KeyboardKey::KEY_W), ("X", KeyboardKey::KEY_X),
+    ("Y", KeyboardKey::KEY_Y), ("Z", KeyboardKey::KEY_Z),
+    ("0", KeyboardKey::KEY_ZERO), ("1", KeyboardKey::KEY_ONE), ("2", KeyboardKey::KEY_TWO), ("3", KeyboardKey::KEY_THREE),
+    ("4", KeyboardKey::KEY_FOUR), ("5", KeyboardKey::KEY_FIVE), ("6", KeyboardKey::KEY_SIX), ("7", KeyboardKey::KEY_SEVEN),
+    ("8", KeyboardKey::KEY_EIGHT), ("9", KeyboardKey::KEY_NINE),
+    ("ENTER", KeyboardKey::KEY_ENTER), ("TAB", KeyboardKey::KEY_TAB), ("SPACE", KeyboardKey::KEY_SPACE),
+    ("BACKSPACE", KeyboardKey::KEY_BACKSPACE), ("LEFT_SHIFT", KeyboardKey::KEY_LEFT_SHIFT),
+    ("LEFT", KeyboardKey::KEY_LEFT), ("RIGHT", KeyboardKey::KEY_RIGHT), ("UP", KeyboardKey::KEY_UP), ("DOWN", KeyboardKey::KEY_DOWN),
+    ("HOME", KeyboardKey::KEY_HOME), ("END", KeyboardKey::KEY_END), ("MINUS", KeyboardKey::KEY_MINUS),
+    ("PAGE_UP", KeyboardKey::KEY_PAGE_UP), ("PAGE_DOWN", KeyboardKey::KEY_PAGE_DOWN), ("ESCAPE", KeyboardKey::KEY_ESCAPE),
+    ("F1", KeyboardKey::KEY_F1), ("F2", KeyboardKey::KEY_F2), ("F3", KeyboardKey::KEY_F3), ("F4", KeyboardKey::KEY_F4),
+    ("F5", KeyboardKey::KEY_F5), ("F6", KeyboardKey::KEY_F6), ("F7", KeyboardKey::KEY_F7), ("F8", KeyboardKey::KEY_F8),
+    ("F9", KeyboardKey::KEY_F9), ("F10", KeyboardKey::KEY_F10), ("F11", KeyboardKey::KEY_F11), ("F12", KeyboardKey::KEY_F12),
+];
+// Names keys can be given by in machine profiles
+
+pub fn key_from_name(name: &str) -> Option<KeyboardKey> {
+    KEY_NAMES.iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct InputConfig {
     coin: KeyboardKey,
@@ -43,55 +165,60 @@ impl InputConfig {
         }
     }
 }
+impl InputConfig {
+    pub fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding { key: self.coin, input: 1, bit: COIN_BIT },
+            Binding { key: self.p2_start, input: 1, bit: P2_START_BIT },
+            Binding { key: self.p1_start, input: 1, bit: P1_START_BIT },
+            Binding { key: self.p1_shoot, input: 1, bit: P1_SHOOT_BIT },
+            Binding { key: self.p1_left, input: 1, bit: P1_LEFT_BIT },
+            Binding { key: self.p1_right, input: 1, bit: P1_RIGHT_BIT },
+            Binding { key: self.tilt_button, input: 2, bit: TILT_BIT },
+            Binding { key: self.p2_shoot, input: 2, bit: P2_SHOOT_BIT },
+            Binding { key: self.p2_left, input: 2, bit: P2_LEFT_BIT },
+            Binding { key: self.p2_right, input: 2, bit: P2_RIGHT_BIT },
+        ]
+    }
+}
 impl Default for InputConfig {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub fn read_input(raylib_handle: &raylib::prelude::RaylibHandle, hardware: &mut Hardware, input_config: InputConfig) {
+pub fn read_input(input_source: &impl InputSource, hardware: &mut Hardware, input_config: InputConfig) {
     // Reads keys based on what has been assigned in the config, then sets the bits in the input
     //  ports based on which keys are pressed
+    read_bindings(input_source, hardware, &input_config.bindings());
+}
 
-    // INPUT 1
-    if raylib_handle.is_key_down(input_config.coin) {
-        hardware.ports.input_1 |= 1 << COIN_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left(COIN_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p2_start) {
-        hardware.ports.input_1 |= 1 << P2_START_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left( P2_START_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p1_start) {
-        hardware.ports.input_1 |= 1 << P1_START_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left(P1_START_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p1_shoot) {
-        hardware.ports.input_1 |= 1 << P1_SHOOT_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left(P1_SHOOT_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p1_left) {
-        hardware.ports.input_1 |= 1 << P1_LEFT_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left(P1_LEFT_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p1_right) {
-        hardware.ports.input_1 |= 1 << P1_RIGHT_BIT;
-    } else { hardware.ports.input_1 &= 0b11111110_u8.rotate_left(P1_RIGHT_BIT as u32) }
-
-    // INPUT 2
-    if raylib_handle.is_key_down(input_config.tilt_button) {
-        hardware.ports.input_2 |= 1 << TILT_BIT;
-    } else { hardware.ports.input_2 &= 0b11111110_u8.rotate_left(TILT_BIT as u32) }
-
-    if raylib_handle.is_key_down(input_config.p2_shoot) {
-        hardware.ports.input_2 |= 1 << P2_SHOOT_BIT;
-    } else { hardware.ports.input_2 &= 0b11111110_u8.rotate_left(P2_SHOOT_BIT as u32) }
+pub fn read_bindings(input_source: &impl InputSource, hardware: &mut Hardware, bindings: &[Binding]) {
+    // Sets each bound input bit while its key is held and clears it otherwise
+    //  Several keys can share a bit, it is set if any of them are held
+    //  Nothing changes while paused, the keys are read again once the machine carries on
 
-    if raylib_handle.is_key_down(input_config.p2_left) {
-        hardware.ports.input_2 |= 1 << P2_LEFT_BIT;
-    } else { hardware.ports.input_2 &= 0b11111110_u8.rotate_left(P2_LEFT_BIT as u32) }
+    if hardware.paused() {
+        return;
+    }
+    let mut bound: [u8; 2] = [0; 2];
+    let mut held: [u8; 2] = [0; 2];
+    for binding in bindings {
+        let index: usize = match binding.input {
+            1 => 0,
+            2 => 1,
+            _ => continue,
+        };
+        bound[index] |= 1 << binding.bit;
+        if input_source.is_key_down(binding.key) {
+            held[index] |= 1 << binding.bit;
+        }
+    }
 
-    if raylib_handle.is_key_down(input_config.p2_right) {
-        hardware.ports.input_2 |= 1 << P2_RIGHT_BIT;
-    } else { hardware.ports.input_2 &= 0b11111110_u8.rotate_left(P2_RIGHT_BIT as u32) }
+    if bound[0] & 1 << COIN_BIT != 0 && hardware.stretch_coin(held[0] & 1 << COIN_BIT != 0) {
+        held[0] |= 1 << COIN_BIT;
+    }
+    // Latched here rather than when the game reads the port so a recording already has the stretched press in it
+    hardware.ports.input_1 = (hardware.ports.input_1 & !bound[0]) | held[0];
+    hardware.ports.input_2 = (hardware.ports.input_2 & !bound[1]) | held[1];
 }
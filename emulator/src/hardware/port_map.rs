@@ -0,0 +1,113 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use super::Port;
+
+const SPACE_INVADERS_READ: [(u8, Port); 3] = [
+    (1, Port::INP1),
+    (2, Port::INP2),
+    (3, Port::SHFTIN),
+];
+const SPACE_INVADERS_WRITE: [(u8, Port); 5] = [
+    (2, Port::SHFTAMNT),
+    (3, Port::SOUND1),
+    (4, Port::SHFTDATA),
+    (5, Port::SOUND2),
+    (6, Port::WATCHDOG),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "PortAssignments"))]
+pub struct PortMap {
+    // Which device IN and OUT reach for each port number
+    //  Other games on the board wire the same devices to different port numbers
+    read: [Option<Port>; 0x100],
+    write: [Option<Port>; 0x100],
+    reassigned: (Option<u8>, Option<u8>),
+    // First read and write port numbers given more than once, only the last device given is connected
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct PortAssignments {
+    // How a port map is written in a profile, a list of [port number, device] pairs for each direction
+    #[serde(default)]
+    read: Vec<(u8, Port)>,
+    #[serde(default)]
+    write: Vec<(u8, Port)>,
+}
+#[cfg(feature = "serde")]
+impl From<PortAssignments> for PortMap {
+    fn from(assignments: PortAssignments) -> Self {
+        Self::from_pairs(&assignments.read, &assignments.write)
+    }
+}
+
+impl PortMap {
+    pub fn new() -> Self {
+        // Nothing connected
+        Self {
+            read: [None; 0x100],
+            write: [None; 0x100],
+            reassigned: (None, None),
+        }
+    }
+
+    pub fn space_invaders() -> Self {
+        Self::from_pairs(&SPACE_INVADERS_READ, &SPACE_INVADERS_WRITE)
+    }
+
+    pub fn from_pairs(read: &[(u8, Port)], write: &[(u8, Port)]) -> Self {
+        let mut port_map: PortMap = Self::new();
+        for (number, port) in read {
+            if port_map.read(*number).is_some() {
+                port_map.reassigned.0 = port_map.reassigned.0.or(Some(*number));
+            }
+            port_map.connect_read(*number, *port);
+        }
+        for (number, port) in write {
+            if port_map.write(*number).is_some() {
+                port_map.reassigned.1 = port_map.reassigned.1.or(Some(*number));
+            }
+            port_map.connect_write(*number, *port);
+        }
+
+        port_map
+    }
+
+    pub fn connect_read(&mut self, number: u8, port: Port) {
+        self.read[number as usize] = Some(port);
+    }
+
+    pub fn connect_write(&mut self, number: u8, port: Port) {
+        self.write[number as usize] = Some(port);
+    }
+
+    pub fn read(&self, number: u8) -> Option<Port> {
+        self.read[number as usize]
+    }
+
+    pub fn write(&self, number: u8) -> Option<Port> {
+        self.write[number as usize]
+    }
+
+    pub fn reads(&self) -> impl Iterator<Item = (u8, Port)> + '_ {
+        // Every connected read port in port number order
+        (0..=0xff).filter_map(|number| self.read(number).map(|port| (number, port)))
+    }
+
+    pub fn writes(&self) -> impl Iterator<Item = (u8, Port)> + '_ {
+        (0..=0xff).filter_map(|number| self.write(number).map(|port| (number, port)))
+    }
+
+    pub fn reassigned(&self) -> (Option<u8>, Option<u8>) {
+        // Read and write port numbers listed twice when the map was built
+        self.reassigned
+    }
+}
+impl Default for PortMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ReadFrom {
+    Left,
+    // The byte read starts offset bits down from the top of the register, how Space Invaders' board works
+    Right,
+    // The byte read starts offset bits up from the bottom
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ShiftConfig {
+    // How the board's shift register is wired, other Midway boards differ from Space Invaders
+    //  Each byte written to SHFTDATA goes in at the top and pushes the register down 8 bits
+    pub width: u8,
+    // Bits the register holds, from 9 to 16
+    pub offset_bit: u8,
+    pub offset_bits: u8,
+    // Where the offset is in bytes written to SHFTAMNT, the lowest bit and how many bits
+    pub read_from: ReadFrom,
+}
+impl ShiftConfig {
+    pub fn space_invaders() -> Self {
+        Self {
+            width: 16,
+            offset_bit: 0,
+            offset_bits: 3,
+            read_from: ReadFrom::Left,
+        }
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        // Settings that would read past the register, the register still works but reads are cut short
+
+        let mut problems: Vec<String> = vec![];
+        if !(9..=16).contains(&self.width) {
+            problems.push(format!("Width {} isn't from 9 to 16 bits", self.width));
+        }
+        if self.offset_bit as u16 + self.offset_bits as u16 > 8 {
+            problems.push(format!("Offset bits {} to {} don't fit in a byte", self.offset_bit, self.offset_bit as u16 + self.offset_bits as u16 - 1));
+        }
+        let largest: u16 = (1u16 << self.offset_bits.min(8)) - 1;
+        if largest + 8 > self.width as u16 {
+            problems.push(format!("An offset of {} reads past the end of a {} bit register", largest, self.width));
+        }
+        problems
+    }
+
+    fn width(&self) -> u32 {
+        self.width.clamp(9, 16) as u32
+    }
+
+    pub fn write(&self, register: u16, value: u8) -> u16 {
+        // The register after value is shifted in
+        let width: u32 = self.width();
+        let register: u32 = (value as u32) << (width - 8) | (register as u32) >> 8;
+        (register & ((1 << width) - 1)) as u16
+    }
+
+    pub fn offset(&self, amount: u8) -> u32 {
+        // The offset picked out of the last byte written to SHFTAMNT
+        let mask: u32 = (1 << self.offset_bits.min(8)) - 1;
+        (amount as u32).checked_shr(self.offset_bit as u32).unwrap_or(0) & mask
+    }
+
+    pub fn read(&self, register: u16, amount: u8) -> u8 {
+        let width: u32 = self.width();
+        let offset: u32 = self.offset(amount).min(width - 8);
+        let shift: u32 = match self.read_from {
+            ReadFrom::Left => width - 8 - offset,
+            ReadFrom::Right => offset,
+        };
+        (register >> shift) as u8
+    }
+}
+impl Default for ShiftConfig {
+    fn default() -> Self {
+        Self::space_invaders()
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Sound {
+    // Named the same as Sound::name in profiles
+    // SOUND1
+    Ufo,
+    // Loops for as long as the bit is held
+    Shot,
+    PlayerDie,
+    InvaderDie,
+    ExtendedPlay,
+    // SOUND2
+    #[cfg_attr(feature = "serde", serde(rename = "fleet_move_1"))]
+    FleetMove1,
+    #[cfg_attr(feature = "serde", serde(rename = "fleet_move_2"))]
+    FleetMove2,
+    #[cfg_attr(feature = "serde", serde(rename = "fleet_move_3"))]
+    FleetMove3,
+    #[cfg_attr(feature = "serde", serde(rename = "fleet_move_4"))]
+    FleetMove4,
+    UfoHit,
+}
+impl Sound {
+    pub const ALL: [Sound; 10] = [
+        Sound::Ufo,
+        Sound::Shot,
+        Sound::PlayerDie,
+        Sound::InvaderDie,
+        Sound::ExtendedPlay,
+        Sound::FleetMove1,
+        Sound::FleetMove2,
+        Sound::FleetMove3,
+        Sound::FleetMove4,
+        Sound::UfoHit,
+    ];
+    // In the same order as the enum so a sound can be used as an index with `as usize`
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sound::Ufo => "ufo",
+            Sound::Shot => "shot",
+            Sound::PlayerDie => "player_die",
+            Sound::InvaderDie => "invader_die",
+            Sound::ExtendedPlay => "extended_play",
+            Sound::FleetMove1 => "fleet_move_1",
+            Sound::FleetMove2 => "fleet_move_2",
+            Sound::FleetMove3 => "fleet_move_3",
+            Sound::FleetMove4 => "fleet_move_4",
+            Sound::UfoHit => "ufo_hit",
+        }
+    }
+
+    pub fn is_looping(&self) -> bool {
+        // Everything else is a one shot that plays to the end once triggered
+        matches!(self, Sound::Ufo)
+    }
+}
+
+const SOUND_1_BITS: [(u8, Sound); 5] = [
+    (0, Sound::Ufo),
+    (1, Sound::Shot),
+    (2, Sound::PlayerDie),
+    (3, Sound::InvaderDie),
+    (4, Sound::ExtendedPlay),
+];
+// Bit 5 enables the amplifier and is not a sound
+const SOUND_2_BITS: [(u8, Sound); 5] = [
+    (0, Sound::FleetMove1),
+    (1, Sound::FleetMove2),
+    (2, Sound::FleetMove3),
+    (3, Sound::FleetMove4),
+    (4, Sound::UfoHit),
+];
+// Bit 5 flips the screen for cocktail cabinets and is not a sound
+
+pub fn port_sounds(port: Port) -> &'static [(u8, Sound)] {
+    // Which bit of a port starts which sound, nothing for ports that aren't sound ports
+    match port {
+        Port::SOUND1 => &SOUND_1_BITS,
+        Port::SOUND2 => &SOUND_2_BITS,
+        _ => &[],
+    }
+}
+
+pub trait AudioSink {
+    // Anything that can play the sounds triggered by the sound ports
+
+    fn play(&mut self, sound: Sound);
+    fn stop(&mut self, sound: Sound);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SoundEvent {
+    Play(Sound),
+    Stop(Sound),
+}
+impl SoundEvent {
+    pub fn send(&self, audio_sink: &mut (impl AudioSink + ?Sized)) {
+        match *self {
+            SoundEvent::Play(sound) => audio_sink.play(sound),
+            SoundEvent::Stop(sound) => audio_sink.stop(sound),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoundLog {
+    // Keeps what an audio update passed on so it can be saved and played later
+    pub events: Vec<SoundEvent>,
+}
+impl SoundLog {
+    pub fn new() -> Self {
+        Self { events: vec![] }
+    }
+
+    pub fn send(&self, audio_sink: &mut (impl AudioSink + ?Sized)) {
+        for event in &self.events {
+            event.send(audio_sink);
+        }
+    }
+}
+impl AudioSink for SoundLog {
+    fn play(&mut self, sound: Sound) {
+        self.events.push(SoundEvent::Play(sound));
+    }
+
+    fn stop(&mut self, sound: Sound) {
+        self.events.push(SoundEvent::Stop(sound));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoundLatch {
+    // Tracks changes to a sound port between audio updates
+    value: u8,
+    // Last value written to the port
+    played: u8,
+    // Bits that went from 0 to 1 since the last audio update
+    stopped: u8,
+    // Bits that went from 1 to 0 since the last audio update
+}
+impl SoundLatch {
+    pub fn write(&mut self, value: u8) {
+        // Records which bits changed
+        //  Games write the sound ports every frame so only changes trigger anything
+
+        self.played |= value & !self.value;
+        self.stopped |= !value & self.value;
+        self.value = value;
+    }
+
+    pub fn clear(&mut self) {
+        // The board's reset line clears the latch, anything left on is stopped at the next audio update
+        self.write(0);
+    }
+
+    fn drain(&mut self, bits: &[(u8, Sound)], audio_sink: &mut (impl AudioSink + ?Sized)) {
+        for (bit, sound) in bits {
+            if self.played & (1 << bit) != 0 {
+                audio_sink.play(*sound);
+            }
+            if self.stopped & (1 << bit) != 0 && self.value & (1 << bit) == 0 {
+                audio_sink.stop(*sound);
+            }
+            // A bit that was pulsed 0 -> 1 -> 0 between updates still gets played then stopped
+        }
+
+        self.played = 0;
+        self.stopped = 0;
+    }
+
+    fn pause(&mut self, bits: &[(u8, Sound)], paused: bool, audio_sink: &mut (impl AudioSink + ?Sized)) {
+        // Stops everything the port has on when pausing and starts the loops back up after
+        //  One shots cut off by the pause aren't started again, they would replay from the beginning
+        //  Changes not passed on yet are dropped for the same reason
+
+        self.played = 0;
+        self.stopped = 0;
+
+        for (_, sound) in bits.iter().filter(|(bit, _)| self.value & (1 << bit) != 0) {
+            match paused {
+                true => audio_sink.stop(*sound),
+                false if sound.is_looping() => audio_sink.play(*sound),
+                false => {},
+            }
+        }
+    }
+}
+
+pub fn update_audio(hardware: &mut Hardware, audio_sink: &mut (impl AudioSink + ?Sized)) {
+    // Sends any sounds started or stopped since the last update to the audio sink
+    //  Pausing or carrying on is passed on the first update after it happens
+
+    if hardware.paused != hardware.audio_paused {
+        hardware.ports.sound_1.pause(&SOUND_1_BITS, hardware.paused, audio_sink);
+        hardware.ports.sound_2.pause(&SOUND_2_BITS, hardware.paused, audio_sink);
+        hardware.audio_paused = hardware.paused;
+    }
+    if hardware.paused {
+        return;
+    }
+    hardware.ports.sound_1.drain(&SOUND_1_BITS, audio_sink);
+    hardware.ports.sound_2.drain(&SOUND_2_BITS, audio_sink);
+}
@@ -1,21 +1,50 @@
 #[cfg(test)]
 use super::*;
+#[cfg(test)]
+use crate::primitives::KeyboardKey;
+#[cfg(test)]
+use super::cocktail::{FlipSource, Remap};
 
 #[test]
 fn test_shift() {
     let mut hardware: Hardware = Hardware::init();
 
-    write_port(0xff, Port::SHFTDATA, &mut hardware);
+    write_port(0xff, Port::SHFTDATA, &mut hardware).unwrap();
     assert_eq!(hardware.shift_register, 0xff00);
-    write_port(0xee, Port::SHFTDATA, &mut hardware);
+    write_port(0xee, Port::SHFTDATA, &mut hardware).unwrap();
     assert_eq!(hardware.shift_register, 0xeeff);
-    write_port(0xaa, Port::SHFTDATA, &mut hardware);
+    write_port(0xaa, Port::SHFTDATA, &mut hardware).unwrap();
     assert_eq!(hardware.shift_register, 0xaaee);
 
     hardware.shift_register = 0b0001111111100000;
     hardware.ports.shift_amount = 0b0000_0011;
     // Offset 3
-    assert_eq!(read_port(Port::SHFTIN, &mut hardware), 0b11111111);
+    assert_eq!(read_port(Port::SHFTIN, &mut hardware), Ok(0b11111111));
+}
+
+#[test]
+fn test_shift_config() {
+    let mut hardware: Hardware = Hardware::init();
+    hardware.set_shift_config(ShiftConfig { width: 12, offset_bit: 4, offset_bits: 2, read_from: shift::ReadFrom::Right });
+
+    write_port(0xab, Port::SHFTDATA, &mut hardware).unwrap();
+    write_port(0xcd, Port::SHFTDATA, &mut hardware).unwrap();
+    assert_eq!(hardware.shift_register, 0x0cda);
+    // Each byte goes in at the top of the 12 bits and pushes the last one down 8
+
+    write_port(0b0010_0111, Port::SHFTAMNT, &mut hardware).unwrap();
+    assert_eq!(read_port(Port::SHFTIN, &mut hardware), Ok(0x36));
+    // Offset 2 from bits 4 and 5, read from 2 bits up
+
+    hardware.reset();
+    assert_eq!(hardware.shift_config().width, 12);
+    // Wiring survives a reset
+
+    assert!(ShiftConfig::space_invaders().problems().is_empty());
+    assert_eq!(ShiftConfig { width: 9, offset_bit: 6, offset_bits: 3, read_from: shift::ReadFrom::Left }.problems(), vec![
+        String::from("Offset bits 6 to 8 don't fit in a byte"),
+        String::from("An offset of 7 reads past the end of a 9 bit register"),
+    ]);
 }
 
 #[test]
@@ -23,8 +52,8 @@ fn test_handle_io() {
     let mut hardware: Hardware = Hardware::init();
 
     // SHFTDATA
-    handle_io(0xd3, &mut hardware, 4, 0b11100000);
-    handle_io(0xd3, &mut hardware, 4, 0b00011111);
+    handle_io(0xd3, &mut hardware, 4, 0b11100000).unwrap();
+    handle_io(0xd3, &mut hardware, 4, 0b00011111).unwrap();
     assert_eq!(hardware.shift_register, 0b0001111111100000);
 
     // SHFTIN
@@ -32,5 +61,455 @@ fn test_handle_io() {
     hardware.shift_register = 0b0001111111100000;
     hardware.ports.shift_amount = 0b0000_0011;
 
-    assert_eq!(handle_io(0xdb, &mut hardware, 3, 0x00), Some(0xff));
+    assert_eq!(handle_io(0xdb, &mut hardware, 3, 0x00), Ok(Some(0xff)));
+}
+
+#[cfg(test)]
+struct MockInput {
+    keys_down: Vec<KeyboardKey>,
+}
+#[cfg(test)]
+impl input::InputSource for MockInput {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.keys_down.contains(&key)
+    }
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct MockAudio {
+    events: Vec<(&'static str, sound::Sound)>,
+}
+#[cfg(test)]
+impl sound::AudioSink for MockAudio {
+    fn play(&mut self, sound: sound::Sound) {
+        self.events.push(("play", sound));
+    }
+    fn stop(&mut self, sound: sound::Sound) {
+        self.events.push(("stop", sound));
+    }
+}
+#[cfg(test)]
+type Script<'a> = &'a [&'a [(u8, u8)]];
+// OUTs to sound ports a frame at a time
+#[cfg(test)]
+fn play_script(hardware: &mut Hardware, script: Script) -> Vec<Vec<(&'static str, sound::Sound)>> {
+    // Makes each frame's writes then updates the audio once, the way the frontend does every frame
+    script.iter().map(|writes| {
+        let mut audio: MockAudio = MockAudio::default();
+        for (port, value) in writes.iter() {
+            handle_io(0xd3, hardware, *port, *value).unwrap();
+        }
+        sound::update_audio(hardware, &mut audio);
+        audio.events
+    }).collect()
+}
+
+#[test]
+fn test_input_latching() {
+    let mut hardware: Hardware = Hardware::init();
+    let config: input::InputConfig = input::InputConfig::default();
+
+    // INPUT 1
+    let mock: MockInput = MockInput { keys_down: vec![KeyboardKey::KEY_ENTER, KeyboardKey::KEY_A] };
+    input::read_input(&mock, &mut hardware, config);
+    assert_eq!(hardware.debug_input1(), 0b0010_1001);
+    // Coin and P1 left, bit 3 is always set
+
+    let mock: MockInput = MockInput { keys_down: vec![] };
+    input::read_input(&mock, &mut hardware, config);
+    assert_eq!(hardware.debug_input1(), 0b0000_1000);
+    // Releasing keys clears their bits
+
+    // INPUT 2
+    let mock: MockInput = MockInput { keys_down: vec![KeyboardKey::KEY_K, KeyboardKey::KEY_TAB] };
+    input::read_input(&mock, &mut hardware, config);
+    assert_eq!(handle_io(0xdb, &mut hardware, 2, 0x00), Ok(Some(0b0001_0100)));
+    // P2 shoot and tilt
+}
+
+#[test]
+fn test_key_names() {
+    assert_eq!(input::key_from_name("space"), Some(KeyboardKey::KEY_SPACE));
+    assert_eq!(input::key_from_name("F5"), Some(KeyboardKey::KEY_F5));
+    assert_eq!(input::key_from_name("F12"), Some(KeyboardKey::KEY_F12));
+    assert_eq!(input::key_from_name("ESCAPE"), Some(KeyboardKey::KEY_ESCAPE));
+    assert_eq!(input::key_from_name("PAGE_DOWN"), Some(KeyboardKey::KEY_PAGE_DOWN));
+    assert_eq!(input::key_from_name("F13"), None);
+    // The same names with and without the frontend, the keys defined without it match raylib's
+}
+
+#[test]
+fn test_coin_pulse() {
+    let mut hardware: Hardware = Hardware::init();
+    let config: input::InputConfig = input::InputConfig::default();
+    let coin: MockInput = MockInput { keys_down: vec![KeyboardKey::KEY_ENTER] };
+    let nothing: MockInput = MockInput { keys_down: vec![] };
+    let read = |hardware: &mut Hardware, source: &MockInput| {
+        input::read_input(source, hardware, config);
+        hardware.debug_input1() & 1
+    };
+
+    assert_eq!([read(&mut hardware, &coin), read(&mut hardware, &nothing)], [1, 0]);
+    // Off by default, the key goes straight through
+
+    hardware.set_coin_pulse_frames(3);
+    let tap: Vec<u8> = [&coin, &nothing, &nothing, &nothing, &nothing].into_iter().map(|source| read(&mut hardware, source)).collect();
+    assert_eq!(tap, vec![1, 1, 1, 0, 0]);
+    let held: Vec<u8> = [&coin, &coin, &coin, &coin, &nothing].into_iter().map(|source| read(&mut hardware, source)).collect();
+    assert_eq!(held, vec![1, 1, 1, 1, 0]);
+    // Holding the key longer than the pulse isn't stretched any further
+
+    read(&mut hardware, &coin);
+    hardware.reset();
+    assert_eq!(read(&mut hardware, &nothing), 0);
+    // A reset ends a pulse part way through
+    assert_eq!(hardware.coin_pulse_frames(), 3);
+}
+
+#[test]
+fn test_dip_switches() {
+    let mut hardware: Hardware = Hardware::init();
+    let config: input::InputConfig = input::InputConfig::default();
+
+    hardware.set_dip_switches(input::DipSwitches {
+        extra_lives: 2,
+        bonus_life_at_1000: true,
+        coin_info_off: true,
+    });
+    assert_eq!(hardware.debug_input2(), 0b1000_1010);
+
+    let mock: MockInput = MockInput { keys_down: vec![KeyboardKey::KEY_J] };
+    input::read_input(&mock, &mut hardware, config);
+    assert_eq!(hardware.debug_input2(), 0b1010_1010);
+    // P2 left is merged with the DIP switches
+
+    let mock: MockInput = MockInput { keys_down: vec![] };
+    input::read_input(&mock, &mut hardware, config);
+    assert_eq!(hardware.debug_input2(), 0b1000_1010);
+    // Releasing controls leaves the DIP switches alone
+
+    hardware.set_dip_switches(input::DipSwitches::default());
+    assert_eq!(hardware.debug_input2(), 0x00);
+}
+
+#[test]
+fn test_sound_latch() {
+    for (port, name) in [(3, Port::SOUND1), (5, Port::SOUND2)] {
+        for (bit, sound) in sound::port_sounds(name) {
+            let on: u8 = 1 << bit;
+            let script: Script = &[&[(port, 0)], &[(port, on)], &[(port, on)], &[(port, on)], &[(port, 0)], &[(port, 0)], &[(port, on), (port, 0), (port, on)]];
+            let events: Vec<Vec<(&str, sound::Sound)>> = play_script(&mut Hardware::init(), script);
+            assert_eq!(events, [vec![], vec![("play", *sound)], vec![], vec![], vec![("stop", *sound)], vec![], vec![("play", *sound)]], "{:?}", sound);
+            // Held bits are written every frame without retriggering, a bit dropped and raised again within a frame retriggers
+        }
+    }
+
+    let mut hardware: Hardware = Hardware::init();
+    let events: Vec<Vec<(&str, sound::Sound)>> = play_script(&mut hardware, &[&[(3, 0b0011_1111)], &[(3, 0b0011_1111)], &[(3, 0b0010_1101)], &[(5, 0b0011_1111), (3, 0b0010_1111)]]);
+    assert_eq!(events[0].len(), 5);
+    assert_eq!(events[1], vec![]);
+    assert_eq!(events[2], vec![("stop", sound::Sound::Shot), ("stop", sound::Sound::ExtendedPlay)]);
+    assert_eq!(events[3].len(), 6);
+    assert_eq!(events[3][0], ("play", sound::Sound::Shot));
+    // Each bit is latched on its own, the amplifier and screen flip bits never make a sound
+
+    let script: Script = &[&[(3, 0b0011_0000)], &[(3, 0b0011_0000)], &[(3, 0b0011_0000)], &[(3, 0b0010_0000)], &[(3, 0b0011_0000)]];
+    let events: Vec<Vec<(&str, sound::Sound)>> = play_script(&mut Hardware::init(), script);
+    assert_eq!(events, [vec![("play", sound::Sound::ExtendedPlay)], vec![], vec![], vec![("stop", sound::Sound::ExtendedPlay)], vec![("play", sound::Sound::ExtendedPlay)]]);
+    // The game holds extended play on for as long as the jingle lasts, it only plays again once a new life is earned
+
+    let mut hardware: Hardware = Hardware::init();
+    play_script(&mut hardware, &[&[(3, 0b0000_0001)]]);
+    hardware.reset();
+    assert_eq!(play_script(&mut hardware, &[&[], &[]]), [vec![("stop", sound::Sound::Ufo)], vec![]]);
+    // A reset clears the latches, so the ufo stops instead of looping on
+}
+
+#[test]
+fn test_sound_edges() {
+    let mut hardware: Hardware = Hardware::init();
+    let mut audio: MockAudio = MockAudio::default();
+
+    // SOUND1
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0010).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![("play", sound::Sound::Shot)]);
+
+    audio.events.clear();
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0010).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![]);
+    // Writing the same value again doesn't retrigger
+
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0001).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![("play", sound::Sound::Ufo), ("stop", sound::Sound::Shot)]);
+
+    // SOUND2
+    audio.events.clear();
+    handle_io(0xd3, &mut hardware, 5, 0b0000_0100).unwrap();
+    handle_io(0xd3, &mut hardware, 5, 0b0000_0000).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![("play", sound::Sound::FleetMove3), ("stop", sound::Sound::FleetMove3)]);
+    // A pulse shorter than an audio update still plays
+
+    audio.events.clear();
+    handle_io(0xd3, &mut hardware, 5, 0b0010_0000).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![]);
+    // Screen flip bit isn't a sound
+
+    let mut log: sound::SoundLog = sound::SoundLog::new();
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0000).unwrap();
+    sound::update_audio(&mut hardware, &mut log);
+    assert_eq!(log.events, vec![sound::SoundEvent::Stop(sound::Sound::Ufo)]);
+    log.send(&mut audio);
+    assert_eq!(audio.events, vec![("stop", sound::Sound::Ufo)]);
+    // A log plays back the same as the update it was taken from
+}
+
+#[test]
+fn test_watchdog() {
+    let mut hardware: Hardware = Hardware::init();
+
+    for _ in 0..WATCHDOG_FRAMES {
+        assert!(!hardware.tick_watchdog());
+    }
+    handle_io(0xd3, &mut hardware, 6, 0x00).unwrap();
+    // Kick
+
+    for _ in 0..WATCHDOG_FRAMES {
+        assert!(!hardware.tick_watchdog());
+    }
+    assert!(hardware.tick_watchdog());
+    // Not kicked for too long
+    assert!(!hardware.tick_watchdog());
+    // Starts counting again after a reset
+
+    hardware.set_watchdog(Some(2));
+    assert!(!hardware.tick_watchdog());
+    assert!(!hardware.tick_watchdog());
+    assert!(hardware.tick_watchdog());
+
+    hardware.set_watchdog(None);
+    for _ in 0..=WATCHDOG_FRAMES * 4 {
+        assert!(!hardware.tick_watchdog());
+    }
+    // A board without a watchdog never resets
+}
+
+#[test]
+fn test_port_errors() {
+    let mut hardware: Hardware = Hardware::init();
+
+    assert_eq!(handle_io(0xdb, &mut hardware, 0, 0x00), Err(ErrorKind::UnconnectedReadPort(0)));
+    assert_eq!(handle_io(0xd3, &mut hardware, 7, 0x00), Err(ErrorKind::UnconnectedWritePort(7)));
+
+    hardware.set_port_map(PortMap::from_pairs(&[(1, Port::WATCHDOG)], &[(1, Port::INP1)]));
+    assert_eq!(handle_io(0xdb, &mut hardware, 1, 0x00), Err(ErrorKind::NotReadable(Port::WATCHDOG)));
+    assert_eq!(handle_io(0xd3, &mut hardware, 1, 0x00), Err(ErrorKind::NotWritable(Port::INP1)));
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct Latch {
+    // Reads back the last value written to it
+    name: &'static str,
+    value: u8,
+    policy: devices::ResetPolicy,
+}
+#[cfg(test)]
+impl devices::IoDevice for Latch {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn read(&mut self, _number: u8) -> u8 {
+        self.value
+    }
+    fn write(&mut self, _number: u8, value: u8) {
+        self.value = value;
+    }
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+    fn reset_policy(&self) -> devices::ResetPolicy {
+        self.policy
+    }
+    fn clone_device(&self) -> Box<dyn devices::IoDevice> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn test_attached_devices() {
+    use devices::Claim;
+
+    let mut hardware: Hardware = Hardware::init();
+    let latch = |name: &'static str| Box::new(Latch { name, value: 0, policy: devices::ResetPolicy::PowerOnly });
+
+    assert!(hardware.attach(latch("debug"), &[Claim::read(0x10), Claim::write(0x10)], 0).is_ok());
+    assert_eq!(handle_io(0xd3, &mut hardware, 0x10, 0x5a), Ok(None));
+    assert_eq!(handle_io(0xdb, &mut hardware, 0x10, 0x00), Ok(Some(0x5a)));
+    assert_eq!(handle_io(0xdb, &mut hardware, 0x11, 0x00), Err(ErrorKind::UnconnectedReadPort(0x11)));
+
+    assert!(hardware.attach(latch("other"), &[Claim::write(0x10)], 0).is_err());
+    assert!(hardware.attach(latch("debug"), &[Claim::write(0x20)], 0).is_err());
+    assert!(hardware.attach(latch("shift"), &[Claim::write(4)], 0).is_err());
+    // Same port at the same priority, a duplicate name and one of the board's ports
+    assert!(hardware.attach(latch("other"), &[Claim::read(4)], 0).is_ok());
+    // Only writes to port 4 reach the board
+
+    assert!(hardware.attach(latch("override"), &[Claim::read(0x10), Claim::write(4)], 1).is_ok());
+    handle_io(0xd3, &mut hardware, 4, 0xff).unwrap();
+    assert_eq!(hardware.shift_register, 0x0000);
+    assert_eq!(handle_io(0xdb, &mut hardware, 0x10, 0x00), Ok(Some(0xff)));
+    // Higher priority claims take over ports from the board and other devices
+
+    assert!(hardware.detach("override").is_some());
+    assert_eq!(handle_io(0xdb, &mut hardware, 0x10, 0x00), Ok(Some(0x5a)));
+    handle_io(0xd3, &mut hardware, 4, 0xff).unwrap();
+    assert_eq!(hardware.shift_register, 0xff00);
+    // Detaching gives the ports back
+
+    hardware.reset();
+    assert_eq!(hardware.bus().names().collect::<Vec<&str>>(), vec!["debug", "other"]);
+    assert_eq!(handle_io(0xdb, &mut hardware, 0x10, 0x00), Ok(Some(0x00)));
+    // Devices stay attached through a reset but are reset themselves
+}
+
+#[test]
+fn test_reset_policies() {
+    use devices::{Claim, ResetPolicy};
+
+    let mut hardware: Hardware = Hardware::init();
+    hardware.set_cocktail(Some(Cocktail { flip: FlipSource::Output { port: 5, bit: 5 }, remap: vec![] }));
+    hardware.set_dip_switches(input::DipSwitches { extra_lives: 2, bonus_life_at_1000: true, coin_info_off: false });
+    let attach = |hardware: &mut Hardware, name: &'static str, port: u8, policy: ResetPolicy| {
+        hardware.attach(Box::new(Latch { name, value: 0x5a, policy }), &[Claim::read(port)], 1).unwrap();
+    };
+    attach(&mut hardware, "always", 0x10, ResetPolicy::Always);
+    attach(&mut hardware, "power", 0x11, ResetPolicy::PowerOnly);
+    attach(&mut hardware, "never", 0x12, ResetPolicy::Never);
+
+    handle_io(0xd3, &mut hardware, 4, 0xab).unwrap();
+    handle_io(0xd3, &mut hardware, 2, 0x03).unwrap();
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0001).unwrap();
+    handle_io(0xd3, &mut hardware, 5, 0b0010_0000).unwrap();
+    handle_io(0xd3, &mut hardware, 6, 0x42).unwrap();
+    hardware.tick_watchdog();
+    hardware.set_inputs([0x08 | 1 << 4, hardware.inputs()[1] | 1 << 4]);
+    let inputs: [u8; 2] = hardware.inputs();
+    let mut audio: MockAudio = MockAudio::default();
+    sound::update_audio(&mut hardware, &mut audio);
+
+    hardware.soft_reset();
+    assert_eq!(hardware.shift_state(), (0xab00, 0x03));
+    assert_eq!(hardware.inputs(), inputs);
+    assert_eq!(hardware.inputs()[1] & input::DipSwitches::BITS, hardware.dip_switches.merge(0x00) & input::DipSwitches::BITS);
+    assert!(!hardware.flipped());
+    assert_eq!((hardware.ports.watchdog, hardware.watchdog_frames), (0x00, 0));
+    assert_eq!(play_script(&mut hardware, &[&[]]), [vec![("stop", sound::Sound::Ufo)]]);
+    assert_eq!([0x10, 0x11, 0x12].map(|port| handle_io(0xdb, &mut hardware, port, 0x00)), [Ok(Some(0x00)), Ok(Some(0x5a)), Ok(Some(0x5a))]);
+    // The reset line stops the sounds and the flip and leaves the shift register and whatever is held alone
+
+    hardware.reset();
+    assert_eq!(hardware.shift_state(), (0x0000, 0x00));
+    assert_eq!(hardware.inputs(), [0x08, hardware.dip_switches.merge(0x00)]);
+    assert_eq!(hardware.dip_switches.extra_lives, 2);
+    assert_eq!([0x10, 0x11, 0x12].map(|port| handle_io(0xdb, &mut hardware, port, 0x00)), [Ok(Some(0x00)), Ok(Some(0x00)), Ok(Some(0x5a))]);
+    // Switching it off clears everything but the DIP switches
+
+    assert_eq!(BoardDevice::ALL.iter().filter(|device| device.policy() == ResetPolicy::Never).map(|device| device.name()).collect::<Vec<&str>>(), vec!["dip switches"]);
+}
+
+#[test]
+fn test_debug_console() {
+    let mut hardware: Hardware = Hardware::init();
+    let console: console::DebugConsole = console::DebugConsole::captured();
+    assert!(console.clone().attach(&mut hardware, 0x2).is_err());
+    // Port 2 is the shift amount
+    assert!(console.clone().attach(&mut hardware, 0xfe).is_ok());
+
+    for byte in b"ok\n" {
+        handle_io(0xd3, &mut hardware, 0xfe, *byte).unwrap();
+    }
+    assert_eq!(console.output(), Some(b"ok\n".to_vec()));
+
+    assert_eq!(handle_io(0xdb, &mut hardware, 0xfe, 0x00), Ok(Some(0x00)));
+    console.push_input(b"y");
+    assert_eq!(handle_io(0xdb, &mut hardware, 0xfe, 0x00), Ok(Some(b'y')));
+    assert_eq!(handle_io(0xdb, &mut hardware, 0xfe, 0x00), Ok(Some(0x00)));
+    // 0 when nothing has been typed
+}
+
+#[test]
+fn test_cocktail() {
+    let mut hardware: Hardware = Hardware::init();
+    handle_io(0xd3, &mut hardware, 5, 0b0010_0000).unwrap();
+    assert!(!hardware.flipped());
+    // An upright cabinet ignores the flip output
+
+    hardware.set_cocktail(Some(Cocktail {
+        flip: FlipSource::Output { port: 5, bit: 5 },
+        remap: vec![Remap { from: (1, 5), to: (2, 6) }, Remap { from: (2, 6), to: (1, 5) }],
+    }));
+    hardware.set_inputs([0x08 | 1 << 5, 0x00]);
+    assert_eq!(handle_io(0xdb, &mut hardware, 1, 0x00), Ok(Some(0x28)));
+
+    assert_eq!(handle_io(0xd3, &mut hardware, 5, 0b0010_0001), Ok(None));
+    assert!(hardware.flipped());
+    assert_eq!(handle_io(0xdb, &mut hardware, 1, 0x00), Ok(Some(0x08)));
+    assert_eq!(handle_io(0xdb, &mut hardware, 2, 0x00), Ok(Some(1 << 6)));
+    // The two bits swap places while flipped
+    assert_eq!(hardware.inputs(), [0x28, 0x00]);
+    // What the keys set is kept as it is for recordings
+
+    hardware.reset();
+    assert!(!hardware.flipped());
+    assert!(hardware.cocktail().is_some());
+    hardware.set_flipped(true);
+    handle_io(0xd3, &mut hardware, 5, 0x00).unwrap();
+    assert!(!hardware.flipped());
+}
+
+#[test]
+fn test_paused() {
+    let mut hardware: Hardware = Hardware::init();
+    let mut audio: MockAudio = MockAudio::default();
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0011).unwrap();
+    sound::update_audio(&mut hardware, &mut audio);
+    audio.events.clear();
+
+    hardware.set_paused(true);
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![("stop", sound::Sound::Ufo), ("stop", sound::Sound::Shot)]);
+    audio.events.clear();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![]);
+
+    let input: MockInput = MockInput { keys_down: vec![KeyboardKey::KEY_ENTER] };
+    input::read_input(&input, &mut hardware, input::InputConfig::default());
+    assert_eq!(hardware.inputs()[0], 0x08);
+    // Keys held while paused aren't seen
+    for _ in 0..=WATCHDOG_FRAMES {
+        assert!(!hardware.tick_watchdog());
+    }
+    hardware.reset();
+    assert!(hardware.paused());
+
+    hardware.set_paused(false);
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![]);
+    // The reset turned the sounds off while it was paused
+    handle_io(0xd3, &mut hardware, 3, 0b0000_0011).unwrap();
+    hardware.set_paused(true);
+    sound::update_audio(&mut hardware, &mut audio);
+    hardware.set_paused(false);
+    audio.events.clear();
+    sound::update_audio(&mut hardware, &mut audio);
+    assert_eq!(audio.events, vec![("play", sound::Sound::Ufo)]);
+    // Only the loop carries on, the shot was cut off
+    input::read_input(&input, &mut hardware, input::InputConfig::default());
+    assert_eq!(hardware.inputs()[0], 0x09);
 }
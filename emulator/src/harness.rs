@@ -0,0 +1,184 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::error::EmulatorError;
+use crate::hardware::Hardware;
+use crate::hardware::devices::{Claim, IoDevice, BOARD_PRIORITY};
+use crate::machine::{Machine, Stop};
+
+mod tests;
+
+pub const NAME: &str = "test_port";
+pub const EXPECT_PORT: u8 = 0xf0;
+// OUT here sets the value the next check expects
+pub const CHECK_PORT: u8 = 0xf1;
+// OUT here checks the value against the expected one
+pub const FINISH_PORT: u8 = 0xf2;
+// OUT here ends the test with the value as its status, 0 is a pass
+pub const PASSED: u8 = 0;
+
+pub const CYCLE_LIMIT: u64 = 100_000_000;
+// About 50 seconds of machine time, far longer than any test rom should need
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssertFailure {
+    pub check: u32,
+    // Counted from 1 in the order the rom makes its checks
+    pub expected: u8,
+    pub actual: u8,
+}
+impl fmt::Display for AssertFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Check {} expected 0x{:02x} but got 0x{:02x}", self.check, self.expected, self.actual)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Results {
+    pub checks: u32,
+    pub failures: Vec<AssertFailure>,
+    pub status: Option<u8>,
+    // Set once the rom finishes
+}
+
+#[derive(Debug, Clone)]
+pub struct TestPort {
+    // Lets test roms written in 8080 assembly report to the harness through a few OUT ports
+    //  The macros in emulator/tests/roms/harness.inc wrap the protocol
+    expected: u8,
+    results: Arc<Mutex<Results>>,
+    // Shared so the harness can read results while the machine owns the device
+}
+impl TestPort {
+    pub fn new() -> Self {
+        Self {
+            expected: 0,
+            results: Arc::new(Mutex::new(Results::default())),
+        }
+    }
+
+    pub fn results(&self) -> Results {
+        self.results.lock().map(|results| results.clone()).unwrap_or_default()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.results.lock().is_ok_and(|results| results.status.is_some())
+    }
+
+    pub fn attach(self, hardware: &mut Hardware) -> Result<(), String> {
+        let claims: [Claim; 3] = [Claim::write(EXPECT_PORT), Claim::write(CHECK_PORT), Claim::write(FINISH_PORT)];
+        hardware.attach(Box::new(self), &claims, BOARD_PRIORITY)
+    }
+}
+impl Default for TestPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl IoDevice for TestPort {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn read(&mut self, _number: u8) -> u8 {
+        0
+    }
+
+    fn write(&mut self, number: u8, value: u8) {
+        let Ok(mut results) = self.results.lock() else {
+            return;
+        };
+        match number {
+            EXPECT_PORT => self.expected = value,
+            CHECK_PORT => {
+                results.checks += 1;
+                if value != self.expected {
+                    let check: u32 = results.checks;
+                    results.failures.push(AssertFailure { check, expected: self.expected, actual: value });
+                }
+            },
+            FINISH_PORT => results.status = Some(value),
+            _ => {},
+        }
+    }
+
+    fn reset(&mut self) {
+        self.expected = 0;
+    }
+
+    fn clone_device(&self) -> Box<dyn IoDevice> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed(Results),
+    Failed(Results),
+    // Finished with a failing status or had checks fail
+    Fault(EmulatorError, Results),
+    TimedOut(Results),
+    // Never wrote to the finish port
+}
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        matches!(self, Outcome::Passed(_))
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        // The rom's own status when it gave a failing one so scripts can tell failures apart
+        match self {
+            Outcome::Passed(_) => 0,
+            Outcome::Failed(results) => results.status.filter(|status| *status != PASSED).unwrap_or(1),
+            Outcome::Fault(..) | Outcome::TimedOut(_) => 1,
+        }
+    }
+}
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Passed(results) => write!(f, "Passed {} checks", results.checks)?,
+            Outcome::Failed(results) => match results.status {
+                Some(PASSED) | None => write!(f, "Failed {} of {} checks", results.failures.len(), results.checks)?,
+                Some(status) => write!(f, "Failed with status {}", status)?,
+            },
+            Outcome::Fault(e, _) => write!(f, "Faulted: {}", e)?,
+            Outcome::TimedOut(results) => write!(f, "Didn't finish after {} checks", results.checks)?,
+        }
+        let failures: &[AssertFailure] = match self {
+            Outcome::Passed(results) | Outcome::Failed(results) | Outcome::Fault(_, results) | Outcome::TimedOut(results) => &results.failures,
+        };
+        for failure in failures {
+            write!(f, "\n    {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn run(machine: &mut Machine, cycle_limit: u64) -> Result<Outcome, String> {
+    // Runs a test rom that is already loaded until it writes to the finish port
+    //  A fresh test port replaces any that is already attached
+    //  The watchdog is turned off, test roms don't feed it and a long one would be reset part way through
+
+    machine.set_watchdog(None);
+    let port: TestPort = TestPort::new();
+    machine.hardware.detach(NAME);
+    port.clone().attach(&mut machine.hardware)?;
+    // Only fails for profiles that use ports 0xf0 to 0xf2 themselves
+
+    let stopped: Result<Stop, EmulatorError> = machine.run_until(cycle_limit, |_| port.finished());
+    let results: Results = port.results();
+    Ok(match stopped {
+        Err(e) => Outcome::Fault(e, results),
+        Ok(_) if results.status.is_none() => Outcome::TimedOut(results),
+        Ok(_) if results.status == Some(PASSED) && results.failures.is_empty() => Outcome::Passed(results),
+        Ok(_) => Outcome::Failed(results),
+    })
+}
+
+pub fn run_rom(rom: &[u8]) -> Result<Outcome, String> {
+    // Runs a test rom on a fresh machine
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(rom).map_err(|e| e.to_string())?;
+    run(&mut machine, CYCLE_LIMIT)
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+fn checking_rom(status: u8) -> Vec<u8> {
+    // Makes one passing check and one failing check then finishes with status
+    vec![
+        0xf3,             // 0x0000 DI
+        0x3e, 0x08,       // 0x0001 MVI A,8
+        0xd3, 0xf0,       // 0x0003 OUT EXPECT
+        0xd3, 0xf1,       // 0x0005 OUT CHECK
+        0x3e, 0x09,       // 0x0007 MVI A,9
+        0xd3, 0xf1,       // 0x0009 OUT CHECK
+        0x3e, status,     // 0x000b MVI A,status
+        0xd3, 0xf2,       // 0x000d OUT FINISH
+        0x76,             // 0x000f HLT
+    ]
+}
+
+#[test]
+fn test_assertion_ports() {
+    let outcome: Outcome = run_rom(&checking_rom(PASSED)).unwrap();
+    let failure: AssertFailure = AssertFailure { check: 2, expected: 0x08, actual: 0x09 };
+    assert_eq!(outcome, Outcome::Failed(Results { checks: 2, failures: vec![failure], status: Some(PASSED) }));
+    assert_eq!(outcome.exit_code(), 1);
+    // Finishing with a pass doesn't hide a failed check
+    assert_eq!(outcome.to_string(), "Failed 1 of 2 checks\n    Check 2 expected 0x08 but got 0x09");
+
+    assert_eq!(run_rom(&checking_rom(5)).unwrap().exit_code(), 5);
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0xd3, 0xf1,       // 0x0001 OUT CHECK
+        0xc3, 0x01, 0x00, // 0x0003 JMP 0x0001
+    ]).unwrap();
+    let outcome: Outcome = run(&mut machine, 1_000).unwrap();
+    assert!(matches!(&outcome, Outcome::TimedOut(results) if results.checks > 1 && results.failures.is_empty()));
+    assert_eq!(outcome.exit_code(), 1);
+}
+
+#[test]
+fn test_long_rom() {
+    let rom: Vec<u8> = vec![
+        0xf3,             // 0x0000 DI
+        0x16, 0x08,       // 0x0001 MVI D,8
+        0x01, 0x00, 0x00, // 0x0003 LXI B,0
+        0x0b,             // 0x0006 DCX B
+        0x78,             // 0x0007 MOV A,B
+        0xb1,             // 0x0008 ORA C
+        0xc2, 0x06, 0x00, // 0x0009 JNZ 0x0006
+        0x15,             // 0x000c DCR D
+        0xc2, 0x03, 0x00, // 0x000d JNZ 0x0003
+        0xd3, 0xf2,       // 0x0010 OUT FINISH, A is 0 after the loop
+        0x76,             // 0x0012 HLT
+    ];
+    // 8 times round 65536 * 24 cycles, about 380 frames without touching the watchdog
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    let outcome: Outcome = run(&mut machine, CYCLE_LIMIT).unwrap();
+    assert_eq!(outcome, Outcome::Passed(Results { checks: 0, failures: vec![], status: Some(PASSED) }));
+    assert!(machine.cycles() > 300 * machine.profile().interrupts.cycles_per_frame);
+    assert_eq!(run_rom(&rom).unwrap().exit_code(), 0);
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::cpu::{Cpu, Registers};
+use crate::display::{Framebuffer, Geometry};
+use crate::machine::Machine;
+use crate::palette::Palette;
+use crate::profile::RegionKind;
+use crate::png;
+
+mod tests;
+
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct StateDump {
+    // State of the machine at the end of a headless run, saved as json so scripts can compare runs
+    pub frames: u32,
+    // Frames that finished, fewer than asked for if the machine faulted
+    pub cycles: u64,
+    pub registers: Registers,
+    pub ram_hash: String,
+    // FNV-1a of every ram and vram region in the profile
+    pub fault: Option<String>,
+}
+impl StateDump {
+    pub fn capture(machine: &Machine, frames: u32, fault: Option<String>) -> Self {
+        Self {
+            frames,
+            cycles: machine.cycles(),
+            registers: machine.cpu.registers(),
+            ram_hash: format!("{:016x}", ram_hash(machine)),
+            fault,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json: String = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+pub fn ram_hash(machine: &Machine) -> u64 {
+    // Hash of the writable memory, the same rom and inputs should always give the same hash
+
+    machine.profile().memory.iter()
+        .filter(|region| region.kind != RegionKind::Rom)
+        .fold(FNV_OFFSET, |hash, region| fnv1a(hash, &machine.cpu.memory.bytes()[region.addresses()]))
+}
+
+pub fn rom_hash(rom: &[u8]) -> String {
+    // Identifies a rom in replays and save states
+    format!("{:016x}", fnv1a(FNV_OFFSET, rom))
+}
+
+pub fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    // Carries on an FNV-1a hash, start from FNV_OFFSET
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+pub fn run(machine: &mut Machine, frames: u32) -> StateDump {
+    // Runs frames without a window or any input then records where the machine ended up
+    //  A fault stops the run early and is kept in the dump
+
+    let mut completed: u32 = 0;
+    let mut fault: Option<String> = None;
+    while completed < frames {
+        if let Err(e) = machine.run_frame() {
+            fault = Some(e.to_string());
+            break;
+        }
+        completed += 1;
+    }
+
+    StateDump::capture(machine, completed, fault)
+}
+
+pub fn framebuffer(cpu: &Cpu, geometry: Geometry, palette: &Palette) -> Vec<u8> {
+    // Rgb pixels of the screen as it would be drawn, row by row from the top left
+
+    let screen: Framebuffer = Framebuffer::new(geometry, cpu.memory.view(geometry.memory()));
+    let (width, height): (u16, u16) = (screen.width(), screen.height());
+    let mut pixels: Vec<u8> = Vec::with_capacity(width as usize * height as usize * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let colour = match screen.lit(x, y) {
+                true => palette.lit(height - 1 - y, x),
+                false => palette.background,
+            };
+            pixels.extend_from_slice(&[colour.r, colour.g, colour.b]);
+        }
+    }
+
+    pixels
+}
+
+pub fn save_png(cpu: &Cpu, geometry: Geometry, palette: &Palette, path: &Path) -> Result<(), String> {
+    let image: Vec<u8> = png::encode_rgb(geometry.width as u32, geometry.height as u32, &framebuffer(cpu, geometry, palette))?;
+    fs::write(path, image).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::profile::Profile;
+#[cfg(test)]
+use crate::{INVADERS_HEIGHT, INVADERS_WIDTH};
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_run() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_TO_PORT);
+    let before: u64 = ram_hash(&machine);
+    let dump: StateDump = run(&mut machine, 3);
+
+    assert_eq!(dump.frames, 3);
+    assert_eq!(dump.cycles, machine.cycles());
+    assert_eq!(dump.registers, machine.cpu.registers());
+    assert_eq!(dump.fault, None);
+    assert_ne!(dump.ram_hash, format!("{:016x}", before));
+
+    let mut again: Machine = fixtures::machine(fixtures::COUNT_TO_PORT);
+    assert_eq!(run(&mut again, 3), dump);
+    // Nothing outside the rom affects a headless run
+}
+
+#[test]
+fn test_run_fault() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xd3, 0xff]).unwrap();
+    // OUT to a port with nothing connected
+
+    let dump: StateDump = run(&mut machine, 5);
+    assert_eq!(dump.frames, 0);
+    assert!(dump.fault.is_some());
+}
+
+#[test]
+fn test_framebuffer() {
+    let palette: Palette = Palette::new(&Profile::space_invaders().overlay).unwrap();
+    let mut cpu: Cpu = Cpu::init();
+    cpu.memory.poke(0x2400, 0b0000_0001);
+    // Bottom left pixel
+    cpu.memory.poke(0x3fff, 0b1000_0000);
+    // Top right pixel
+
+    let pixels: Vec<u8> = framebuffer(&cpu, Geometry::space_invaders(), &palette);
+    let width: usize = INVADERS_WIDTH as usize;
+    let pixel = |x: usize, y: usize| pixels[(y * width + x) * 3..(y * width + x) * 3 + 3].to_vec();
+    let colour = |row: u16, column: u16| {
+        let colour = palette.lit(row, column);
+        vec![colour.r, colour.g, colour.b]
+    };
+    let background: Vec<u8> = vec![palette.background.r, palette.background.g, palette.background.b];
+
+    assert_eq!(pixels.len(), width * INVADERS_HEIGHT as usize * 3);
+    assert_eq!(pixel(0, INVADERS_HEIGHT as usize - 1), colour(0, 0));
+    assert_eq!(pixel(width - 1, 0), colour(INVADERS_HEIGHT as u16 - 1, width as u16 - 1));
+    assert_eq!(pixel(1, INVADERS_HEIGHT as usize - 1), background);
+}
@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use crate::cpu::{dispatcher, Cpu, Memory};
+
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fetched {
+    // The instruction about to run, its op code and the operands it takes
+    pub address: u16,
+    pub op_code: u8,
+    pub operands: [u8; 2],
+    // Operands past the instruction's length are never read from memory and are left 0
+}
+impl Fetched {
+    pub fn read(memory: &Memory, address: u16) -> Self {
+        // Reads only the bytes the op code says the instruction is made of
+        //  so nothing past the end of a one byte instruction is touched
+
+        let mut fetched: Fetched = Self {
+            address,
+            op_code: memory.read_at(address),
+            operands: [0; 2],
+        };
+        fetched.read_operands(memory, fetched.length());
+        fetched
+    }
+
+    pub fn length(&self) -> usize {
+        // Bytes in the instruction, op code included
+        dispatcher::length(self.op_code) as usize
+    }
+
+    fn read_operands(&mut self, memory: &Memory, length: usize) {
+        for offset in 1..length {
+            self.operands[offset - 1] = memory.read_at(self.address.wrapping_add(offset as u16));
+        }
+    }
+
+    fn bytes(&self) -> [u8; 3] {
+        [self.op_code, self.operands[0], self.operands[1]]
+    }
+
+    fn poke(&self, memory: &mut Memory) {
+        for (offset, byte) in self.bytes().into_iter().take(self.length()).enumerate() {
+            memory.poke(self.address.wrapping_add(offset as u16), byte);
+        }
+    }
+}
+
+pub trait InstructionHook {
+    // Sees every instruction before it runs, changing fetched changes what runs
+    //  The address can't be moved, change the cpu's pc for that
+    fn fetched(&mut self, fetched: &mut Fetched, cpu: &Cpu);
+}
+impl<F: FnMut(&mut Fetched, &Cpu)> InstructionHook for F {
+    fn fetched(&mut self, fetched: &mut Fetched, cpu: &Cpu) {
+        self(fetched, cpu)
+    }
+}
+
+#[derive(Clone)]
+pub struct Hooks {
+    // Hooks registered on a machine, for fault injection and trying out instruction substitutions
+    //  Copies of the machine share them so a rewind doesn't lose them
+    hooks: Vec<(usize, Arc<Mutex<dyn InstructionHook + Send>>)>,
+    next_id: usize,
+}
+impl Hooks {
+    pub fn new() -> Self {
+        Self {
+            hooks: vec![],
+            next_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, hook: impl InstructionHook + Send + 'static) -> usize {
+        // Returns an id for removing the hook again
+        self.next_id += 1;
+        self.hooks.push((self.next_id, Arc::new(Mutex::new(hook))));
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before: usize = self.hooks.len();
+        self.hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.hooks.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    pub fn substitute(&self, cpu: &mut Cpu) -> Option<(Fetched, Fetched)> {
+        // Runs every hook in the order they were added over the instruction at pc
+        //  When one changes it, the new bytes are written over the old ones and the original and replacement
+        //  are returned so restore can put them back once it has run
+
+        let mut original: Fetched = Fetched::read(&cpu.memory, cpu.pc.address);
+        let mut fetched: Fetched = original;
+        for (_, hook) in &self.hooks {
+            hook.lock().unwrap_or_else(|e| e.into_inner()).fetched(&mut fetched, cpu);
+        }
+        fetched.address = original.address;
+
+        if fetched == original {
+            return None;
+        }
+        original.read_operands(&cpu.memory, fetched.length().max(original.length()));
+        // A longer replacement covers bytes the original didn't use, they're read now so restore can put them back
+        fetched.poke(&mut cpu.memory);
+        Some((original, fetched))
+    }
+
+    pub fn restore(memory: &mut Memory, (original, replaced): (Fetched, Fetched)) {
+        // Puts back the bytes a substitution replaced, apart from any the instruction wrote over itself
+
+        let length: usize = original.length().max(replaced.length());
+        for (offset, (old, new)) in original.bytes().into_iter().zip(replaced.bytes()).take(length).enumerate() {
+            let address: u16 = original.address.wrapping_add(offset as u16);
+            if memory.read_at(address) == new {
+                memory.poke(address, old);
+            }
+        }
+    }
+}
+impl Default for Hooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
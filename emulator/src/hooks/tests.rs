@@ -0,0 +1,88 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[test]
+fn test_substitution() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x3e, 0x01, // 0x0000 MVI A,1
+        0x00,       // 0x0002 NOP
+        0x00,       // 0x0003 NOP
+    ]).unwrap();
+    let id: usize = machine.add_hook(|fetched: &mut Fetched, _: &Cpu| match fetched.address {
+        0x0000 => fetched.operands[0] = 0x02,
+        0x0002 => fetched.op_code = 0x3c,
+        // INR A
+        _ => {},
+    });
+
+    machine.step().unwrap();
+    assert_eq!(machine.cpu.a.value, 0x02);
+    assert_eq!(machine.cpu.memory.read_at(0x0001), 0x01);
+    // The rom is put back once the replacement has run
+    machine.step().unwrap();
+    assert_eq!((machine.cpu.a.value, machine.cpu.memory.read_at(0x0002)), (0x03, 0x00));
+
+    assert!(machine.remove_hook(id));
+    assert!(!machine.remove_hook(id));
+    machine.step().unwrap();
+    assert_eq!(machine.cpu.a.value, 0x03);
+}
+
+#[test]
+fn test_hook_sees_everything() {
+    let seen: Arc<Mutex<Vec<(u16, u8)>>> = Arc::new(Mutex::new(vec![]));
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0x00, 0x3c, 0xc3, 0x00, 0x00]).unwrap(); // 0x0000 NOP, INR A, JMP 0x0000
+    let log: Arc<Mutex<Vec<(u16, u8)>>> = Arc::clone(&seen);
+    machine.add_hook(move |fetched: &mut Fetched, cpu: &Cpu| log.lock().unwrap().push((fetched.address, cpu.a.value)));
+
+    let copy: Machine = machine.clone();
+    for _ in 0..4 {
+        machine.step().unwrap();
+    }
+    drop(copy);
+    assert_eq!(*seen.lock().unwrap(), vec![(0x0000, 0), (0x0001, 0), (0x0002, 1), (0x0000, 1)]);
+}
+
+#[test]
+fn test_self_modifying_substitution() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xc3, 0x00, 0x30]).unwrap(); // 0x0000 JMP 0x3000
+    machine.cpu.memory.poke(0x3000, 0x00);
+    machine.cpu.pc.address = 0x3000;
+    machine.add_hook(|fetched: &mut Fetched, _: &Cpu| if fetched.address == 0x3000 {
+        *fetched = Fetched { address: 0x3000, op_code: 0x32, operands: [0x01, 0x30] };
+        // STA 0x3001, writing over its own operand
+    });
+    machine.cpu.a.value = 0x77;
+
+    machine.step().unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x3000), 0x00);
+    assert_eq!(machine.cpu.memory.read_at(0x3001), 0x77);
+    // What the instruction wrote stays
+}
+
+#[test]
+fn test_operands_read_lazily() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x00,       // 0x0000 NOP
+        0x3e, 0x05, // 0x0001 MVI A,5
+    ]).unwrap();
+    assert_eq!(Fetched::read(&machine.cpu.memory, 0x0000).operands, [0x00, 0x00]);
+    // A one byte instruction doesn't read the bytes after it
+    assert_eq!(Fetched::read(&machine.cpu.memory, 0x0001).operands, [0x05, 0x00]);
+
+    machine.add_hook(|fetched: &mut Fetched, _: &Cpu| if fetched.address == 0x0000 {
+        fetched.op_code = 0x3e;
+        fetched.operands[0] = 0x09;
+        // MVI A,9 in place of the NOP, one byte longer
+    });
+    machine.step().unwrap();
+    assert_eq!((machine.cpu.a.value, machine.cpu.pc.address), (0x09, 0x0002));
+    assert_eq!((machine.cpu.memory.read_at(0x0000), machine.cpu.memory.read_at(0x0001)), (0x00, 0x3e));
+    // Both bytes the replacement covered are put back
+}
@@ -0,0 +1,188 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+mod tests;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Language {
+    // Language of everything the emulator draws on screen, set with language in the profile
+    //  Console output stays in English so bug reports read the same for everyone
+    #[default]
+    English,
+    Spanish,
+}
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // Accepts the name or the two letter code, e.g. spanish or es
+        match text.trim().to_ascii_lowercase().as_str() {
+            "english" | "en" => Ok(Language::English),
+            "spanish" | "español" | "es" => Ok(Language::Spanish),
+            _ => Err(format!("{} is not a supported language, try english or spanish", text)),
+        }
+    }
+
+    pub fn text(self, text: Text) -> &'static str {
+        match self {
+            Language::English => english(text),
+            Language::Spanish => spanish(text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    // Every piece of on screen text that isn't a number or a key name
+    InsertCoin,
+    P1Start,
+    P1Left,
+    P1Right,
+    P1Shoot,
+    ButtonLeft,
+    ButtonRight,
+    ButtonFire,
+    ButtonCoin,
+    ButtonStart,
+    InterruptLatency,
+    InterruptSpacing,
+    Mean,
+    Max,
+    NoneYet,
+    Dropped,
+    Frame,
+    HaltWaiting,
+    CyclesToInterrupt,
+    InterruptsOff,
+    AudioUnderruns,
+    AudioBuffer,
+    Samples,
+    FramesLate,
+    ServiceMode,
+    Coins,
+    InputLag,
+    Frames,
+    NoScreenChange,
+    FramePacing,
+    MemoryBandwidth,
+    Unmapped,
+    Fetched,
+    Read,
+    Written,
+    Paused,
+    Rewinding,
+    FramesAgo,
+    Instruction,
+    Running,
+    Halted,
+    Cycles,
+}
+impl Text {
+    pub const ALL: [Text; 42] = [
+        Text::InsertCoin, Text::P1Start, Text::P1Left, Text::P1Right, Text::P1Shoot,
+        Text::ButtonLeft, Text::ButtonRight, Text::ButtonFire, Text::ButtonCoin, Text::ButtonStart,
+        Text::InterruptLatency, Text::InterruptSpacing, Text::Mean, Text::Max, Text::NoneYet, Text::Dropped,
+        Text::Frame, Text::HaltWaiting, Text::CyclesToInterrupt, Text::InterruptsOff,
+        Text::AudioUnderruns, Text::AudioBuffer, Text::Samples, Text::FramesLate, Text::ServiceMode, Text::Coins,
+        Text::InputLag, Text::Frames, Text::NoScreenChange, Text::FramePacing,
+        Text::MemoryBandwidth, Text::Unmapped, Text::Fetched, Text::Read, Text::Written,
+        Text::Paused, Text::Rewinding, Text::FramesAgo, Text::Instruction,
+        Text::Running, Text::Halted, Text::Cycles,
+    ];
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::InsertCoin => "Insert Coin",
+        Text::P1Start => "P1 Start",
+        Text::P1Left => "P1 Left",
+        Text::P1Right => "P1 Right",
+        Text::P1Shoot => "P1 Shoot",
+        Text::ButtonLeft => "LEFT",
+        Text::ButtonRight => "RIGHT",
+        Text::ButtonFire => "FIRE",
+        Text::ButtonCoin => "COIN",
+        Text::ButtonStart => "START",
+        Text::InterruptLatency => "Interrupt latency",
+        Text::InterruptSpacing => "RST 1/2 spacing off by",
+        Text::Mean => "mean",
+        Text::Max => "max",
+        Text::NoneYet => "none yet",
+        Text::Dropped => "dropped",
+        Text::Frame => "Frame",
+        Text::HaltWaiting => "HALT - waiting for interrupt",
+        Text::CyclesToInterrupt => "cycles to the next one",
+        Text::InterruptsOff => "interrupts are disabled so it never will",
+        Text::AudioUnderruns => "Audio underruns",
+        Text::AudioBuffer => "buffer",
+        Text::Samples => "samples",
+        Text::FramesLate => "frames are running late",
+        Text::ServiceMode => "SERVICE MODE - F2 to leave",
+        Text::Coins => "coins counted",
+        Text::InputLag => "Input lag",
+        Text::Frames => "frames",
+        Text::NoScreenChange => "the screen didn't change",
+        Text::FramePacing => "Frame pacing",
+        Text::MemoryBandwidth => "Memory accesses last frame, plotted by half frame",
+        Text::Unmapped => "Unmapped",
+        Text::Fetched => "fetched",
+        Text::Read => "read",
+        Text::Written => "written",
+        Text::Paused => "PAUSED - P to carry on",
+        Text::Rewinding => "REWIND - arrows to step, F8 to carry on from here, F7 to close",
+        Text::FramesAgo => "frames ago",
+        Text::Instruction => "instruction",
+        Text::Running => "Running",
+        Text::Halted => "Halted",
+        Text::Cycles => "Cycles",
+    }
+}
+
+fn spanish(text: Text) -> &'static str {
+    match text {
+        Text::InsertCoin => "Insertar Moneda",
+        Text::P1Start => "J1 Empezar",
+        Text::P1Left => "J1 Izquierda",
+        Text::P1Right => "J1 Derecha",
+        Text::P1Shoot => "J1 Disparar",
+        Text::ButtonLeft => "IZQ",
+        Text::ButtonRight => "DER",
+        Text::ButtonFire => "FUEGO",
+        Text::ButtonCoin => "MONEDA",
+        Text::ButtonStart => "INICIO",
+        Text::InterruptLatency => "Latencia de interrupciones",
+        Text::InterruptSpacing => "Desfase entre RST 1/2",
+        Text::Mean => "media",
+        Text::Max => "máx",
+        Text::NoneYet => "ninguna aún",
+        Text::Dropped => "perdidas",
+        Text::Frame => "Fotograma",
+        Text::HaltWaiting => "HALT - esperando una interrupción",
+        Text::CyclesToInterrupt => "ciclos hasta la siguiente",
+        Text::InterruptsOff => "las interrupciones están desactivadas así que nunca llegará",
+        Text::AudioUnderruns => "Cortes de audio",
+        Text::AudioBuffer => "búfer",
+        Text::Samples => "muestras",
+        Text::FramesLate => "los fotogramas van con retraso",
+        Text::ServiceMode => "MODO DE SERVICIO - F2 para salir",
+        Text::Coins => "monedas contadas",
+        Text::InputLag => "Retardo de entrada",
+        Text::Frames => "fotogramas",
+        Text::NoScreenChange => "la pantalla no cambió",
+        Text::FramePacing => "Ritmo de fotogramas",
+        Text::MemoryBandwidth => "Accesos a memoria del último fotograma, por medio fotograma",
+        Text::Unmapped => "Sin mapear",
+        Text::Fetched => "leídos como código",
+        Text::Read => "leídos",
+        Text::Written => "escritos",
+        Text::Paused => "PAUSA - P para seguir",
+        Text::Rewinding => "REBOBINADO - flechas para avanzar, F8 para seguir desde aquí, F7 para cerrar",
+        Text::FramesAgo => "fotogramas atrás",
+        Text::Instruction => "instrucción",
+        Text::Running => "En marcha",
+        Text::Halted => "Detenida",
+        Text::Cycles => "Ciclos",
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_translations() {
+    for language in Language::ALL {
+        for text in Text::ALL {
+            assert!(!language.text(text).is_empty(), "{:?} has no {:?}", language, text);
+        }
+    }
+    assert_eq!(Language::default().text(Text::InsertCoin), "Insert Coin");
+    assert_eq!(Language::Spanish.text(Text::InsertCoin), "Insertar Moneda");
+
+    assert_eq!(Language::parse("ES"), Ok(Language::Spanish));
+    assert_eq!(Language::parse("english"), Ok(Language::English));
+    assert!(Language::parse("klingon").is_err());
+}
@@ -0,0 +1,102 @@
+use crate::cpu::Registers;
+
+mod tests;
+
+pub const LOOP_BYTES: u16 = 32;
+// Longest backwards jump treated as a possible polling loop
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Iteration {
+    target: u16,
+    // Address jumped back to at the end of the iteration
+    registers: Registers,
+    cycles: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct IdleDetector {
+    // Spots a rom spinning in a tight loop waiting for an interrupt
+    //  A loop that jumps back to the same place with the same registers and has written nothing
+    //  will do exactly the same thing every time round until an interrupt changes something,
+    //  so whole iterations can be counted instead of run
+    last: Option<Iteration>,
+    clean: bool,
+    // Nothing has been written to memory or a port and no io has been read since the last backwards jump
+    skipped: u64,
+}
+impl IdleDetector {
+    pub fn new() -> Self {
+        Self {
+            last: None,
+            clean: false,
+            skipped: 0,
+        }
+    }
+
+    pub fn skipped(&self) -> u64 {
+        // Cycles accounted for without being run
+        self.skipped
+    }
+
+    pub fn interrupted(&mut self) {
+        // Interrupts push the pc so the loop has to be seen again from scratch
+        self.last = None;
+        self.clean = false;
+    }
+
+    pub fn record(&mut self, op_code: u8, from: u16, registers: Registers, total_cycles: u64) -> Option<u64> {
+        // Called after each instruction with the pc it ran from and the state after it
+        //  Returns how many cycles one iteration of an idle loop takes once one is confirmed
+
+        if has_side_effects(op_code) {
+            self.clean = false;
+        }
+
+        let to: u16 = registers.pc;
+        if to >= from || from - to > LOOP_BYTES {
+            return None;
+        }
+
+        let iteration: Iteration = Iteration { target: to, registers, cycles: total_cycles };
+        let length: Option<u64> = match self.last {
+            Some(last) if self.clean && last.target == to && last.registers == registers => Some(total_cycles - last.cycles),
+            _ => None,
+        };
+        self.last = Some(iteration);
+        self.clean = true;
+        length
+    }
+
+    pub fn skip(&mut self, cycles: u64) {
+        self.skipped += cycles;
+        if let Some(last) = self.last.as_mut() {
+            last.cycles += cycles;
+        }
+        // Keeps the next iteration's length right now the clock has moved on
+    }
+}
+
+impl Default for IdleDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn has_side_effects(op_code: u8) -> bool {
+    // Instructions that write memory, push to the stack or use a port
+
+    matches!(op_code,
+        0x02 | 0x12 | 0x22 | 0x32 | 0x34 | 0x35 | 0x36
+        // STAX, SHLD, STA, INR M, DCR M, MVI M
+        | 0x70..=0x77
+        // MOV M,r and HLT
+        | 0xc4 | 0xcc | 0xcd | 0xd4 | 0xdc | 0xdd | 0xe4 | 0xec | 0xed | 0xf4 | 0xfc | 0xfd
+        // CALL and conditional calls including the undocumented copies
+        | 0xc5 | 0xd5 | 0xe5 | 0xf5 | 0xe3
+        // PUSH and XTHL
+        | 0xc7 | 0xcf | 0xd7 | 0xdf | 0xe7 | 0xef | 0xf7 | 0xff
+        // RST
+        | 0xd3 | 0xdb
+        // OUT and IN, input can change at any time
+    )
+}
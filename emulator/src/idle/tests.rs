@@ -0,0 +1,42 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::headless::{self, StateDump};
+#[cfg(test)]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_idle_skip() {
+    let mut normal: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut skipping: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    skipping.set_idle_skip(true);
+
+    let expected: StateDump = headless::run(&mut normal, 10);
+    let dump: StateDump = headless::run(&mut skipping, 10);
+    assert_eq!(dump, expected);
+    assert_eq!(skipping.cpu.registers().b, 19);
+    // Woken by both interrupts every frame, the last one hasn't been handled yet
+    assert_eq!(skipping.trace().collect::<Vec<u16>>().len(), normal.trace().collect::<Vec<u16>>().len());
+
+    assert!(skipping.idle_skipped() > skipping.cycles() / 2);
+    assert_eq!(normal.idle_skipped(), 0);
+}
+
+#[test]
+fn test_busy_loops_run() {
+    let mut detector: IdleDetector = IdleDetector::new();
+    let mut registers: Registers = Machine::init().cpu.registers();
+    registers.pc = 0x0010;
+
+    assert_eq!(detector.record(0xc3, 0x0014, registers, 100), None);
+    assert_eq!(detector.record(0xc3, 0x0014, registers, 110), Some(10));
+    assert_eq!(detector.record(0x77, 0x0012, Registers { pc: 0x0013, ..registers }, 118), None);
+    assert_eq!(detector.record(0xc3, 0x0014, registers, 120), None);
+    // MOV M,A wrote to memory during that iteration
+    registers.a = 1;
+    assert_eq!(detector.record(0xc3, 0x0014, registers, 130), None);
+    // Something changed so the loop isn't just waiting
+    assert_eq!(detector.record(0xc3, 0x0014, registers, 140), Some(10));
+}
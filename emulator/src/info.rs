@@ -0,0 +1,141 @@
+use disassembler::Operation;
+
+use crate::headless;
+use crate::png::crc32;
+use crate::profile::Profile;
+
+mod tests;
+
+pub const CHUNK_SIZE: usize = 0x800;
+// Boards take their rom as 2K chips, each one is checked on its own so a single bad dump stands out
+//  Profiles that list their chips are split by those instead
+const RESET_INSTRUCTIONS: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub start: usize,
+    pub crc: u32,
+    pub known: Option<String>,
+    pub expected: bool,
+    // Known and where that chip belongs, a known chip in the wrong place means the files were joined out of order
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomInfo {
+    // What can be told about a rom without running it
+    pub size: usize,
+    pub hash: String,
+    pub profile: String,
+    pub rom_override: Option<String>,
+    pub rom_space: usize,
+    // Bytes the profile's rom space holds
+    pub chunks: Vec<Chunk>,
+    pub vectors: Vec<(u8, String)>,
+    // First instruction of each RST vector
+    pub reset: Vec<String>,
+    // Listing from the reset vector, following the first JMP
+}
+impl RomInfo {
+    pub fn new(rom: &[u8], profile: &Profile) -> Self {
+        let mut chunks: Vec<Chunk> = vec![];
+        let mut start: usize = 0;
+        while start < rom.len() {
+            let size: usize = profile.chips.get(chunks.len()).map_or(CHUNK_SIZE, |chip| chip.size.max(1));
+            let crc: u32 = crc32(&rom[start..(start + size).min(rom.len())]);
+            let known: Option<usize> = profile.chips.iter().position(|chip| chip.crc == crc);
+            chunks.push(Chunk {
+                start,
+                crc,
+                known: known.map(|index| format!("{} {}", profile.name, profile.chips[index].file)),
+                expected: known == Some(chunks.len()),
+            });
+            start += size;
+        }
+        // Split where the profile's chips start and end so each dump can be recognised
+
+        let vectors: Vec<(u8, String)> = (0..8u8)
+            .map(|rst| (rst, instruction_at(rom, rst as u16 * 8).map_or(format!("{:04x}   outside the rom", rst as u16 * 8), |op| op.to_string())))
+            .collect();
+
+        Self {
+            size: rom.len(),
+            hash: headless::rom_hash(rom),
+            profile: profile.name.clone(),
+            rom_override: profile.rom_override(rom).map(|rom_override| rom_override.name.clone().unwrap_or_else(|| rom_override.hash.clone())),
+            rom_space: profile.rom_space().len(),
+            chunks,
+            vectors,
+            reset: reset_listing(rom),
+        }
+    }
+
+    pub fn lines(&self, profile: &Profile) -> Vec<String> {
+        // Report printed by emulator info, one fact per line
+
+        let mut lines: Vec<String> = vec![
+            format!("Size: {} bytes (0x{:04x})", self.size, self.size),
+            format!("Hash: {}", self.hash),
+            format!("Profile: {}", self.profile),
+        ];
+        if let Some(name) = &self.rom_override {
+            lines.push(format!("Rom settings: {}", name));
+        }
+        if self.size > self.rom_space {
+            lines.push(format!("The profile's rom regions only hold {} bytes, the machine won't load it", self.rom_space));
+        }
+
+        lines.push(String::from("Chunks:"));
+        for chunk in &self.chunks {
+            let known: String = match (&chunk.known, chunk.expected) {
+                (Some(name), true) => name.clone(),
+                (Some(name), false) => format!("{} in the wrong place", name),
+                (None, _) => String::from("unknown"),
+            };
+            lines.push(format!("    {:04x}  crc {:08x}  {}", chunk.start, chunk.crc, known));
+        }
+
+        lines.push(String::from("Vectors:"));
+        for (rst, instruction) in &self.vectors {
+            let purpose: &str = match *rst {
+                0 => "  reset",
+                rst if rst == profile.interrupts.mid_screen => "  mid screen interrupt",
+                rst if rst == profile.interrupts.full_screen => "  full screen interrupt",
+                _ => "",
+            };
+            lines.push(format!("    RST {}  {}{}", rst, instruction, purpose));
+        }
+
+        lines.push(String::from("Reset:"));
+        lines.extend(self.reset.iter().map(|line| format!("    {}", line)));
+        lines
+    }
+}
+
+fn instruction_at(rom: &[u8], address: u16) -> Option<Operation> {
+    // Only decodes the bytes the rom actually has, an instruction cut off by the end comes back as data
+    let bytes: &[u8] = rom.get(address as usize..)?;
+    let length: usize = bytes.len().min(3);
+    disassembler::decode_from(&bytes[..length], address).into_iter().next()
+}
+
+fn reset_listing(rom: &[u8]) -> Vec<String> {
+    // What the cpu runs first, most roms jump straight out of the vector area so the first JMP is followed once
+
+    let mut lines: Vec<String> = vec![];
+    let mut address: u16 = 0;
+    let mut followed: bool = false;
+    while lines.len() < RESET_INSTRUCTIONS {
+        let Some(op) = instruction_at(rom, address) else {
+            break;
+        };
+        lines.push(op.to_string());
+        match op.target() {
+            Some(target) if op.op_code() == 0xc3 && !followed => {
+                address = target;
+                followed = true;
+            },
+            _ => address = address.wrapping_add(op.op_bytes() as u16),
+        }
+    }
+    lines
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_rom_info() {
+    let mut rom: Vec<u8> = vec![0; 0x1000];
+    rom[..4].copy_from_slice(&[0x00, 0xc3, 0x00, 0x01]);
+    rom[0x100..0x103].copy_from_slice(&[0x31, 0x00, 0x24]);
+    rom[0x10] = 0xc9;
+    let profile: Profile = Profile::space_invaders();
+    let info: RomInfo = RomInfo::new(&rom, &profile);
+
+    assert_eq!(info.size, 0x1000);
+    assert_eq!(info.chunks.len(), 2);
+    assert_eq!(info.chunks[1], Chunk { start: 0x800, crc: crc32(&[0; CHUNK_SIZE]), known: None, expected: false });
+    assert_eq!(info.reset[..3], [
+        String::from("0000   00          NOP"),
+        String::from("0001   c3 01 00    JMP adr"),
+        String::from("0100   31 24 00    LXI SP, D16"),
+    ]);
+    // The jump out of the vectors is followed
+    assert_eq!(info.reset.len(), RESET_INSTRUCTIONS);
+
+    let lines: Vec<String> = info.lines(&profile);
+    assert!(lines.contains(&String::from("Profile: Space Invaders")));
+    assert!(lines.contains(&String::from("    RST 2  0010   c9          RET  full screen interrupt")));
+    assert!(lines.contains(&format!("    0800  crc {:08x}  unknown", info.chunks[1].crc)));
+}
+
+#[test]
+fn test_short_rom_info() {
+    let info: RomInfo = RomInfo::new(&[0xc3, 0x00], &Profile::space_invaders());
+
+    assert_eq!(info.reset, vec![String::from("0000   c3          DB"), String::from("0001   00          NOP")]);
+    // Cut off by the end of the rom
+    assert_eq!(info.vectors[1], (1, String::from("0008   outside the rom")));
+
+    let info: RomInfo = RomInfo::new(&[0; 0x2800], &Profile::space_invaders());
+    assert!(info.lines(&Profile::space_invaders()).iter().any(|line| line.contains("only hold 8192 bytes")));
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::error::EmulatorError;
+use crate::i18n::{Language, Text};
+use crate::machine::Machine;
+
+mod tests;
+
+const GIVE_UP_FRAMES: u32 = 30;
+// Presses that haven't changed the screen after half a second are counted as having no effect
+const PACING_FRAMES: usize = 120;
+// Frame intervals kept for the pacing line
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub frames: Option<u32>,
+    // Frames run from the one the press was sampled for to the one the screen first differed in, None when it never did
+    //  1 means the game reacted within the same frame
+    pub time: Option<Duration>,
+    // From sampling the input to presenting the frame that showed it
+}
+
+struct Pending {
+    shadow: Machine,
+    // The same machine run on as if the key had never been pressed
+    sampled: Instant,
+    frames: u32,
+    reacted: bool,
+}
+
+pub struct InputLag {
+    // Measures how long a key takes to show up on screen inside the emulator
+    //  When the key goes down a copy of the machine carries on without it, the first frame where the two
+    //  screens differ is the one the press showed up in. The screen also flashes while the key is held
+    //  so a camera or light sensor can measure the display's own delay on top
+    input: u8,
+    bit: u8,
+    // Input port bit of the key being measured
+    held: bool,
+    pending: Option<Pending>,
+    measurements: Vec<Measurement>,
+    last_present: Option<Instant>,
+    intervals: VecDeque<Duration>,
+    // Time between presented frames
+}
+impl InputLag {
+    pub fn new(input: u8, bit: u8) -> Self {
+        Self {
+            input,
+            bit,
+            held: false,
+            pending: None,
+            measurements: vec![],
+            last_present: None,
+            intervals: VecDeque::with_capacity(PACING_FRAMES),
+        }
+    }
+
+    fn mask(&self) -> [u8; 2] {
+        match self.input {
+            1 => [1 << self.bit, 0],
+            2 => [0, 1 << self.bit],
+            _ => [0, 0],
+        }
+    }
+
+    pub fn flashing(&self) -> bool {
+        self.held
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    pub fn sampled(&mut self, machine: &Machine, now: Instant) {
+        // Called once the frame's inputs are set, before it runs
+        //  A new press is only measured once the last one has finished
+
+        let mask: [u8; 2] = self.mask();
+        let held: bool = machine.hardware.inputs().iter().zip(mask).any(|(input, mask)| input & mask != 0);
+        if held && !self.held && self.pending.is_none() {
+            self.pending = Some(Pending { shadow: machine.clone(), sampled: now, frames: 0, reacted: false });
+        }
+        self.held = held;
+    }
+
+    pub fn ran(&mut self, machine: &Machine) -> Result<(), EmulatorError> {
+        // Called after the frame has run, runs the copy through the same frame without the key
+
+        let mask: [u8; 2] = self.mask();
+        let Some(pending) = self.pending.as_mut() else {
+            return Ok(());
+        };
+        let inputs: [u8; 2] = machine.hardware.inputs();
+        pending.shadow.hardware.set_inputs([inputs[0] & !mask[0], inputs[1] & !mask[1]]);
+        pending.shadow.run_frame()?;
+        pending.frames += 1;
+
+        let screen: Range<u16> = machine.profile().display.memory();
+        pending.reacted = screen.into_iter().any(|address| machine.cpu.memory.read_at(address) != pending.shadow.cpu.memory.read_at(address));
+        if !pending.reacted && pending.frames >= GIVE_UP_FRAMES {
+            self.measurements.push(Measurement { frames: None, time: None });
+            self.pending = None;
+        }
+        Ok(())
+    }
+
+    pub fn presented(&mut self, now: Instant) {
+        // Called once the frame is on screen
+
+        if let Some(last) = self.last_present.replace(now) {
+            if self.intervals.len() == PACING_FRAMES {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(now - last);
+        }
+        if let Some(pending) = self.pending.take_if(|pending| pending.reacted) {
+            self.measurements.push(Measurement { frames: Some(pending.frames), time: Some(now - pending.sampled) });
+        }
+    }
+
+    pub fn lines(&self, language: Language) -> Vec<String> {
+        // e.g. "Input lag 2 frames 41.3ms, mean 2.0 frames 38.9ms over 4 presses"
+        //      "Frame pacing mean 16.67ms max 17.02ms"
+
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let lag: String = match self.measurements.last() {
+            None => format!("{} {}", language.text(Text::InputLag), language.text(Text::NoneYet)),
+            Some(Measurement { frames: None, .. }) => format!("{} {}", language.text(Text::InputLag), language.text(Text::NoScreenChange)),
+            Some(Measurement { frames: Some(frames), time }) => {
+                let seen: Vec<(u32, Duration)> = self.measurements.iter().filter_map(|measurement| measurement.frames.zip(measurement.time)).collect();
+                let mean_frames: f64 = seen.iter().map(|(frames, _)| *frames as f64).sum::<f64>() / seen.len() as f64;
+                let mean_time: f64 = seen.iter().map(|(_, time)| ms(*time)).sum::<f64>() / seen.len() as f64;
+                format!("{} {} {} {:.1}ms, {} {:.1} {} {:.1}ms / {}", language.text(Text::InputLag), frames, language.text(Text::Frames),
+                    time.map_or(0.0, ms), language.text(Text::Mean), mean_frames, language.text(Text::Frames), mean_time, seen.len())
+            },
+        };
+
+        let pacing: String = match self.intervals.iter().max() {
+            Some(max) => {
+                let mean: f64 = self.intervals.iter().map(|interval| ms(*interval)).sum::<f64>() / self.intervals.len() as f64;
+                format!("{} {} {:.2}ms {} {:.2}ms", language.text(Text::FramePacing), language.text(Text::Mean), mean, language.text(Text::Max), ms(*max))
+            },
+            None => format!("{} {}", language.text(Text::FramePacing), language.text(Text::NoneYet)),
+        };
+        vec![lag, pacing]
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_input_lag() {
+    let mut machine: Machine = fixtures::machine(fixtures::ECHO_INPUT);
+    let mut lag: InputLag = InputLag::new(1, 4);
+    let start: Instant = Instant::now();
+    let frame: Duration = Duration::from_micros(16_667);
+
+    for i in 0..4u32 {
+        let now: Instant = start + frame * i;
+        machine.hardware.set_inputs([if i >= 2 { 1 << 4 } else { 0 }, 0]);
+        lag.sampled(&machine, now);
+        machine.run_frame().unwrap();
+        lag.ran(&machine).unwrap();
+        lag.presented(now + Duration::from_millis(5));
+    }
+    assert!(lag.flashing());
+    assert_eq!(lag.measurements(), &[Measurement { frames: Some(1), time: Some(Duration::from_millis(5)) }]);
+    // Held down from the third frame on, only the press is measured
+
+    let lines: Vec<String> = lag.lines(Language::English);
+    assert_eq!(lines[0], "Input lag 1 frames 5.0ms, mean 1.0 frames 5.0ms / 1");
+    assert_eq!(lines[1], "Frame pacing mean 16.67ms max 16.67ms");
+}
+
+#[test]
+fn test_input_lag_no_change() {
+    let mut machine: Machine = fixtures::machine(fixtures::ECHO_INPUT);
+    let mut lag: InputLag = InputLag::new(2, 0);
+    // Nothing reads input 2
+    let now: Instant = Instant::now();
+
+    machine.hardware.set_inputs([0, 1]);
+    for _ in 0..GIVE_UP_FRAMES {
+        lag.sampled(&machine, now);
+        machine.run_frame().unwrap();
+        lag.ran(&machine).unwrap();
+        lag.presented(now);
+    }
+    assert_eq!(lag.measurements(), &[Measurement { frames: None, time: None }]);
+    assert_eq!(lag.lines(Language::English)[0], "Input lag the screen didn't change");
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::cpu::Memory;
+use crate::hardware::input::{Binding, InputSource};
+use crate::primitives::KeyboardKey;
+use crate::profile::KioskConfig;
+
+mod tests;
+
+pub const PRESS: Duration = Duration::from_millis(250);
+// How long the coin and start keys are held, long enough for a game that reads its inputs once a frame
+pub const START_AFTER: Duration = Duration::from_millis(1000);
+// Start is pressed this long after the coin so the game has counted the credit first
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Attract { since: Instant },
+    // No game running, since the last time anything was pressed
+    Starting { since: Instant },
+    // Pressing coin then start
+    Playing,
+    GameOver { since: Instant },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Kiosk {
+    // Keeps a cabinet at an exhibition going with nobody looking after it
+    //  A coin goes in and a game starts after a while with nothing pressed, and the machine is reset
+    //  a little after the game ends so the next visitor walks up to attract mode
+    //  Times are wall clock so a slow host still waits as long as the config says
+    config: KioskConfig,
+    coin_key: KeyboardKey,
+    start_key: KeyboardKey,
+    state: State,
+    held: Option<KeyboardKey>,
+}
+impl Kiosk {
+    pub fn new(config: KioskConfig, bindings: &[Binding], now: Instant) -> Option<Self> {
+        // Needs keys bound to the coin and start bits to press
+
+        let key_for = |bit: u8| bindings.iter()
+            .find(|binding| binding.input == config.input && binding.bit == bit)
+            .map(|binding| binding.key);
+
+        Some(Self {
+            config,
+            coin_key: key_for(config.coin_bit)?,
+            start_key: key_for(config.start_bit)?,
+            state: State::Attract { since: now },
+            held: None,
+        })
+    }
+
+    pub fn update(&mut self, active: bool, memory: &Memory, now: Instant) -> bool {
+        // Called every frame with whether a player is pressing anything, returns true when the machine should be reset
+
+        let playing: bool = memory.read_at(self.config.playing) != 0;
+        let waited = |since: Instant, seconds: u32| now.saturating_duration_since(since) >= Duration::from_secs(seconds as u64);
+        let mut reset: bool = false;
+
+        self.state = match self.state {
+            State::Attract { .. } | State::Starting { .. } if playing => State::Playing,
+            State::Attract { .. } if active => State::Attract { since: now },
+            State::Attract { since } if waited(since, self.config.idle_seconds) => State::Starting { since: now },
+            State::Starting { since } if now.saturating_duration_since(since) >= START_AFTER + PRESS => State::Attract { since: now },
+            // The game didn't start, it gets another go after the idle time
+            State::Playing if !playing => State::GameOver { since: now },
+            State::GameOver { .. } if playing => State::Playing,
+            State::GameOver { .. } if active => State::Attract { since: now },
+            State::GameOver { since } if waited(since, self.config.reset_seconds) => {
+                reset = true;
+                State::Attract { since: now }
+            },
+            state => state,
+        };
+
+        self.held = match self.state {
+            State::Starting { since } => {
+                let pressing: Duration = now.saturating_duration_since(since);
+                if pressing < PRESS {
+                    Some(self.coin_key)
+                } else if pressing >= START_AFTER && pressing < START_AFTER + PRESS {
+                    Some(self.start_key)
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        };
+        reset
+    }
+
+    pub fn held(&self) -> Option<KeyboardKey> {
+        self.held
+    }
+
+    pub fn playing(&self) -> bool {
+        self.state == State::Playing
+    }
+}
+
+pub fn active(source: &impl InputSource, bindings: &[Binding]) -> bool {
+    // Whether anyone is pressing a bound key, before the kiosk adds its own
+    bindings.iter().any(|binding| source.is_key_down(binding.key))
+}
+
+pub struct WithKiosk<'a, S: InputSource> {
+    // Combines another input source with the keys the kiosk is pressing
+    pub source: &'a S,
+    pub kiosk: Option<&'a Kiosk>,
+}
+impl<S: InputSource> InputSource for WithKiosk<'_, S> {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.source.is_key_down(key) || self.kiosk.is_some_and(|kiosk| kiosk.held == Some(key))
+    }
+}
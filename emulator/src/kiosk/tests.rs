@@ -0,0 +1,75 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::hardware::input::InputConfig;
+#[cfg(test)]
+use crate::profile::Profile;
+
+#[test]
+fn test_kiosk() {
+    let config: KioskConfig = Profile::space_invaders().kiosk.expect("space invaders has kiosk settings");
+    let start: Instant = Instant::now();
+    let at = |seconds: f64| start + Duration::from_secs_f64(seconds);
+    let mut kiosk: Kiosk = Kiosk::new(config, &InputConfig::default().bindings(), start).expect("coin and start are bound");
+    let mut memory: Memory = Memory::init();
+
+    assert!(!kiosk.update(false, &memory, at(30.0)));
+    assert!(!kiosk.update(true, &memory, at(59.0)));
+    assert!(!kiosk.update(false, &memory, at(61.0)));
+    assert_eq!(kiosk.held(), None);
+    // Pressing something started the wait over
+
+    kiosk.update(false, &memory, at(119.0));
+    assert_eq!(kiosk.held(), Some(KeyboardKey::KEY_ENTER));
+    kiosk.update(false, &memory, at(119.5));
+    assert_eq!(kiosk.held(), None);
+    kiosk.update(false, &memory, at(120.1));
+    assert_eq!(kiosk.held(), Some(KeyboardKey::KEY_Q));
+    // Coin then 1 player start
+
+    memory.poke(config.playing, 1);
+    kiosk.update(false, &memory, at(120.2));
+    assert!(kiosk.playing());
+    assert_eq!(kiosk.held(), None);
+
+    memory.poke(config.playing, 0);
+    assert!(!kiosk.update(false, &memory, at(200.0)));
+    assert!(!kiosk.update(false, &memory, at(209.0)));
+    assert!(kiosk.update(false, &memory, at(210.0)));
+    assert!(!kiosk.playing());
+    // Reset once the game has been over for reset_seconds
+
+    kiosk.update(false, &memory, at(270.0));
+    kiosk.update(false, &memory, at(272.0));
+    memory.poke(config.playing, 1);
+    kiosk.update(true, &memory, at(300.0));
+    memory.poke(config.playing, 0);
+    kiosk.update(false, &memory, at(400.0));
+    assert!(!kiosk.update(true, &memory, at(405.0)));
+    assert!(!kiosk.update(false, &memory, at(420.0)));
+    // A player pressing something after game over keeps the machine as it is
+
+    assert!(Kiosk::new(config, &[], start).is_none());
+}
+
+#[test]
+fn test_with_kiosk() {
+    struct Nothing;
+    impl InputSource for Nothing {
+        fn is_key_down(&self, _key: KeyboardKey) -> bool {
+            false
+        }
+    }
+
+    let config: KioskConfig = Profile::space_invaders().kiosk.unwrap();
+    let bindings: Vec<Binding> = InputConfig::default().bindings();
+    let start: Instant = Instant::now();
+    let mut kiosk: Kiosk = Kiosk::new(KioskConfig { idle_seconds: 0, ..config }, &bindings, start).unwrap();
+    kiosk.update(false, &Memory::init(), start);
+    kiosk.update(false, &Memory::init(), start);
+
+    let source: WithKiosk<Nothing> = WithKiosk { source: &Nothing, kiosk: Some(&kiosk) };
+    assert!(source.is_key_down(KeyboardKey::KEY_ENTER));
+    assert!(!active(&Nothing, &bindings));
+    assert!(active(&source, &bindings));
+}
@@ -0,0 +1,157 @@
+use crate::i18n::{Language, Text};
+
+mod tests;
+
+const BAR_WIDTH: u64 = 20;
+// Characters in the longest bar of a drawn histogram
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    // Counts of values in equal width buckets, values outside the range go in the end buckets
+    start: i64,
+    width: i64,
+    counts: Vec<u64>,
+    samples: u64,
+    total: i64,
+    min: Option<i64>,
+    max: Option<i64>,
+}
+impl Histogram {
+    pub fn new(start: i64, width: i64, buckets: usize) -> Self {
+        Self {
+            start,
+            width: width.max(1),
+            counts: vec![0; buckets.max(1)],
+            samples: 0,
+            total: 0,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn record(&mut self, value: i64) {
+        let bucket: i64 = (value - self.start).div_euclid(self.width).clamp(0, self.counts.len() as i64 - 1);
+        self.counts[bucket as usize] += 1;
+        self.samples += 1;
+        self.total += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<i64> {
+        self.max
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.total as f64 / self.samples as f64)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        // A row per bucket with a bar scaled to the fullest one
+        //  e.g. "   0..3    ############ 812"
+
+        let most: u64 = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        self.counts.iter().enumerate()
+            .map(|(bucket, count)| {
+                let low: i64 = self.start + bucket as i64 * self.width;
+                let range: String = match bucket {
+                    0 => format!("..{}", low + self.width - 1),
+                    _ if bucket == self.counts.len() - 1 => format!("{}..", low),
+                    _ => format!("{}..{}", low, low + self.width - 1),
+                };
+                let bar: String = "#".repeat(((count * BAR_WIDTH).div_ceil(most)) as usize);
+                format!("{:>10} {:<20} {}", range, bar, count)
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.start, self.width, self.counts.len());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyStats {
+    // How long interrupts wait to be serviced and how evenly the two screen interrupts are spaced
+    //  An interrupt is requested when the frame reaches its cycle and serviced after the instruction
+    //  running at the time finishes, or dropped when interrupts are disabled
+    latency: Histogram,
+    // Cycles from request to service
+    spacing: Histogram,
+    // Cycles between one serviced interrupt and the next, less the half frame they should be apart
+    dropped: u64,
+    last_service: Option<u64>,
+}
+impl LatencyStats {
+    pub fn new() -> Self {
+        Self {
+            latency: Histogram::new(0, 4, 6),
+            spacing: Histogram::new(-10, 4, 6),
+            dropped: 0,
+            last_service: None,
+        }
+    }
+
+    pub fn latency(&self) -> &Histogram {
+        &self.latency
+    }
+
+    pub fn spacing(&self) -> &Histogram {
+        &self.spacing
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn serviced(&mut self, requested: u64, serviced: u64, half_frame: u64) {
+        // Times are the machine's total cycles
+        self.latency.record(serviced.saturating_sub(requested) as i64);
+        if let Some(last) = self.last_service {
+            self.spacing.record(serviced as i64 - last as i64 - half_frame as i64);
+        }
+        self.last_service = Some(serviced);
+    }
+
+    pub fn dropped_request(&mut self) {
+        self.dropped += 1;
+        self.last_service = None;
+        // The next gap would be a whole frame which says nothing about spacing
+    }
+
+    pub fn lines(&self, language: Language) -> Vec<String> {
+        // Text for the debug overlay
+
+        let summary = |name: Text, histogram: &Histogram| match (histogram.mean(), histogram.max()) {
+            (Some(mean), Some(max)) => format!("{} {} {:.1} {} {}", language.text(name), language.text(Text::Mean), mean, language.text(Text::Max), max),
+            _ => format!("{} {}", language.text(name), language.text(Text::NoneYet)),
+        };
+
+        let mut lines: Vec<String> = vec![format!("{}, {} {}", summary(Text::InterruptLatency, &self.latency), self.dropped, language.text(Text::Dropped))];
+        lines.extend(self.latency.lines());
+        lines.push(summary(Text::InterruptSpacing, &self.spacing));
+        lines.extend(self.spacing.lines());
+        lines
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+impl Default for LatencyStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
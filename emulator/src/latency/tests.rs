@@ -0,0 +1,56 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[test]
+fn test_histogram() {
+    let mut histogram: Histogram = Histogram::new(0, 4, 3);
+    for value in [0, 3, 4, 100, -5] {
+        histogram.record(value);
+    }
+    assert_eq!(histogram.counts(), &[3, 1, 1]);
+    // Values past either end land in the end buckets
+    assert_eq!((histogram.min(), histogram.max(), histogram.samples()), (Some(-5), Some(100), 5));
+    assert_eq!(histogram.mean(), Some(20.4));
+
+    let lines: Vec<String> = histogram.lines();
+    assert_eq!(lines[0], format!("{:>10} {:<20} 3", "..3", "#".repeat(20)));
+    assert_eq!(lines[2], format!("{:>10} {:<20} 1", "8..", "#".repeat(7)));
+
+    histogram.clear();
+    assert_eq!((histogram.samples(), histogram.mean()), (0, None));
+}
+
+#[test]
+fn test_interrupt_latency() {
+    let mut rom: Vec<u8> = vec![0; 0x12];
+    rom[0x00..0x07].copy_from_slice(&[
+        0x31, 0x00, 0x24, // 0x0000 LXI SP,0x2400
+        0xfb,             // 0x0003 EI
+        0xc3, 0x03, 0x00, // 0x0004 JMP 0x0003
+    ]);
+    rom[0x08..0x0a].copy_from_slice(&[0xfb, 0xc9]); // 0x0008 EI, RET
+    rom[0x10..0x12].copy_from_slice(&[0xfb, 0xc9]); // 0x0010 EI, RET
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+
+    for _ in 0..4 {
+        machine.run_frame().unwrap();
+    }
+    let stats: &LatencyStats = machine.latency();
+    assert_eq!(stats.latency().samples(), 8);
+    assert!(stats.latency().max().is_some_and(|max| max < 4 + 10));
+    // Never longer than EI and the JMP after it, an interrupt due during EI waits for the next instruction
+    assert_eq!(stats.spacing().samples(), 7);
+    assert_eq!(stats.dropped(), 0);
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0xc3, 0x00, 0x00, // 0x0001 JMP 0x0000
+    ]).unwrap();
+    machine.run_frame().unwrap();
+    assert_eq!((machine.latency().dropped(), machine.latency().latency().samples()), (2, 0));
+    assert!(machine.latency().lines(Language::English)[0].contains("none yet, 2 dropped"));
+}
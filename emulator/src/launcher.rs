@@ -0,0 +1,312 @@
+use std::env;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use raylib::prelude::*;
+
+use crate::info::RomInfo;
+use crate::profile::Profile;
+use crate::storage::{DirectoryStorage, Kind, Storage};
+use crate::watcher::FileWatcher;
+use crate::window::Layout;
+
+mod tests;
+
+pub const FOLDER_VARIABLE: &str = "EMULATOR_ROMS";
+// Where to look for roms when the emulator is started without arguments
+const RESCAN_FRAMES: u32 = 30;
+// How often the folder is checked for roms being added or removed
+
+const TEXT_COLOUR: Color = Color::WHITE;
+const DIM_COLOUR: Color = Color::GRAY;
+const SELECTED_COLOUR: Color = Color::DARKGRAY;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    // What the checksums say about a rom, worked out without running it
+    Verified,
+    // Every chip is a known dump in the place it belongs
+    OutOfOrder,
+    // Known chips but joined in the wrong order
+    Partly,
+    // Some chips known, others modified or from another set
+    Unknown,
+    TooBig,
+    // Won't fit in the profile's rom space
+    Error(String),
+    // The rom or its profile couldn't be read
+}
+impl Status {
+    pub fn of(info: &RomInfo) -> Self {
+        if info.size > info.rom_space {
+            return Status::TooBig;
+        }
+        let known: usize = info.chunks.iter().filter(|chunk| chunk.known.is_some()).count();
+        match known {
+            0 => Status::Unknown,
+            _ if info.chunks.iter().any(|chunk| chunk.known.is_some() && !chunk.expected) => Status::OutOfOrder,
+            known if known == info.chunks.len() => Status::Verified,
+            _ => Status::Partly,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Status::Verified => "checksums match",
+            Status::OutOfOrder => "chips out of order",
+            Status::Partly => "some chips unknown",
+            Status::Unknown => "unknown rom",
+            Status::TooBig => "too big for the profile",
+            Status::Error(e) => e,
+        }
+    }
+
+    pub fn colour(&self) -> Color {
+        match self {
+            Status::Verified => Color::GREEN,
+            Status::OutOfOrder | Status::Partly => Color::YELLOW,
+            Status::Unknown => DIM_COLOUR,
+            Status::TooBig | Status::Error(_) => Color::RED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    // A rom in the folder and what it will be run with
+    pub path: PathBuf,
+    pub name: String,
+    pub profile: String,
+    // The profile's name and the rom's own settings from it, if it has any
+    pub status: Status,
+}
+impl Entry {
+    pub fn new(path: &Path, profiles: &dyn Storage) -> Self {
+        // Picks the profile the same way running the rom does, <rom>.toml from the config folder or the built in one
+
+        let name: String = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        let error = |e: String| Self { path: path.to_path_buf(), name: name.clone(), profile: String::new(), status: Status::Error(e) };
+
+        let profile: Profile = match Profile::read(profiles, &format!("{}.toml", name)) {
+            Ok(profile) => profile.unwrap_or_else(Profile::space_invaders),
+            Err(e) => return error(e),
+        };
+        let rom: Vec<u8> = match fs::read(path) {
+            Ok(rom) => rom,
+            Err(e) => return error(e.to_string()),
+        };
+
+        let info: RomInfo = RomInfo::new(&rom, &profile);
+        Self {
+            path: path.to_path_buf(),
+            profile: match &info.rom_override {
+                Some(rom_override) => format!("{}, {}", info.profile, rom_override),
+                None => info.profile.clone(),
+            },
+            status: Status::of(&info),
+            name,
+        }
+    }
+}
+
+pub fn default_folder() -> PathBuf {
+    // EMULATOR_ROMS, otherwise a roms folder beside the save states
+    match env::var_os(FOLDER_VARIABLE) {
+        Some(folder) => PathBuf::from(folder),
+        None => DirectoryStorage::platform().map_or_else(|| PathBuf::from(Kind::Rom.folder()), |storage| storage.folder(Kind::Rom)),
+    }
+}
+
+pub fn scan(folder: &Path, profiles: &dyn Storage) -> Result<Vec<Entry>, String> {
+    // Every file in the folder sorted by name, hidden files are left out
+    //  A missing folder is just empty so there is something to show before any roms are added
+
+    let entries: fs::ReadDir = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(format!("{}: {}", folder.display(), e)),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file() && !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    Ok(paths.iter().map(|path| Entry::new(path, profiles)).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launcher {
+    // A list of roms to pick one from
+    folder: PathBuf,
+    entries: Vec<Entry>,
+    selected: usize,
+    top: usize,
+    // First entry shown, moves to keep the selection on screen
+    error: Option<String>,
+}
+impl Launcher {
+    pub fn new(folder: &Path, entries: Vec<Entry>) -> Self {
+        Self {
+            folder: folder.to_path_buf(),
+            entries,
+            selected: 0,
+            top: 0,
+            error: None,
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn move_by(&mut self, rows: isize) {
+        self.select(self.selected.saturating_add_signed(rows));
+    }
+
+    pub fn rescan(&mut self, profiles: &dyn Storage) {
+        // Keeps the same rom selected if it is still there
+
+        let selected: Option<PathBuf> = self.selected().map(|entry| entry.path.clone());
+        match scan(&self.folder, profiles) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            },
+            Err(e) => self.error = Some(e),
+        }
+        let index: Option<usize> = self.entries.iter().position(|entry| Some(&entry.path) == selected.as_ref());
+        self.select(index.unwrap_or(self.selected));
+    }
+
+    pub fn visible(&mut self, rows: usize) -> Range<usize> {
+        // Entries that fit in the given number of rows, scrolled as little as possible to show the selection
+
+        let rows: usize = rows.max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + rows {
+            self.top = self.selected + 1 - rows;
+        }
+        self.top = self.top.min(self.entries.len().saturating_sub(rows));
+        self.top..(self.top + rows).min(self.entries.len())
+    }
+
+    pub fn choose(&mut self, raylib_handle: &mut RaylibHandle, thread: &RaylibThread, profiles: &dyn Storage) -> Option<PathBuf> {
+        // Shows the list until a rom is picked, None when the window is closed
+        //  Up and down or the mouse to select, enter or a second click to launch
+
+        let mut watcher: FileWatcher = FileWatcher::new(&self.folder);
+        let mut frames: u32 = 0;
+        while !raylib_handle.window_should_close() {
+            frames += 1;
+            if (frames.is_multiple_of(RESCAN_FRAMES) && watcher.changed()) || raylib_handle.is_key_pressed(KeyboardKey::KEY_F5) {
+                self.rescan(profiles);
+            }
+            // The folder changes when roms are copied in or deleted, F5 catches changes to a rom's profile
+
+            let layout: Layout = Layout::of(raylib_handle);
+            let row_height: i32 = layout.text_size * 3 / 2;
+            let list_y: i32 = layout.text_size * 4;
+            let rows: usize = ((layout.height - list_y - layout.text_size * 2) / row_height).max(1) as usize;
+
+            let page: isize = rows as isize;
+            for (key, step) in [
+                (KeyboardKey::KEY_UP, -1),
+                (KeyboardKey::KEY_DOWN, 1),
+                (KeyboardKey::KEY_PAGE_UP, -page),
+                (KeyboardKey::KEY_PAGE_DOWN, page),
+                (KeyboardKey::KEY_HOME, isize::MIN / 2),
+                (KeyboardKey::KEY_END, isize::MAX / 2),
+            ] {
+                if raylib_handle.is_key_pressed(key) {
+                    self.move_by(step);
+                }
+            }
+            if raylib_handle.is_key_pressed(KeyboardKey::KEY_ENTER) && self.selected().is_some() {
+                return self.selected().map(|entry| entry.path.clone());
+            }
+
+            let visible: Range<usize> = self.visible(rows);
+            if raylib_handle.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                let row: i32 = (raylib_handle.get_mouse_y() - list_y).div_euclid(row_height);
+                let index: usize = visible.start + row.max(0) as usize;
+                if row >= 0 && visible.contains(&index) {
+                    if index == self.selected {
+                        return self.selected().map(|entry| entry.path.clone());
+                    }
+                    self.select(index);
+                }
+            }
+            // Clicking a rom selects it, clicking it again launches it
+
+            let mut draw_handle: RaylibDrawHandle = raylib_handle.begin_drawing(thread);
+            draw_handle.clear_background(Color::BLACK);
+            self.draw(&mut draw_handle, &layout, visible, list_y, row_height);
+        }
+        None
+    }
+
+    fn draw(&self, draw_handle: &mut impl RaylibDraw, layout: &Layout, visible: Range<usize>, list_y: i32, row_height: i32) {
+        let margin: i32 = layout.text_size;
+        let size: i32 = layout.text_size;
+        let profile_x: i32 = layout.width * 2 / 5;
+        let status_x: i32 = layout.width * 7 / 10;
+
+        draw_handle.draw_text("Choose a rom", margin, margin, size, TEXT_COLOUR);
+        draw_handle.draw_text(&self.folder.display().to_string(), margin, margin + size * 3 / 2, size * 3 / 4, DIM_COLOUR);
+
+        if self.entries.is_empty() {
+            let message: String = match &self.error {
+                Some(e) => e.clone(),
+                None => String::from("No roms here yet, copy some into this folder"),
+            };
+            draw_handle.draw_text(&message, margin, list_y, size, DIM_COLOUR);
+            return;
+        }
+
+        for (row, index) in visible.enumerate() {
+            let entry: &Entry = &self.entries[index];
+            let y: i32 = list_y + row as i32 * row_height;
+            if index == self.selected {
+                draw_handle.draw_rectangle(0, y - size / 4, layout.width, row_height, SELECTED_COLOUR);
+            }
+            draw_handle.draw_text(&entry.name, margin, y, size, TEXT_COLOUR);
+            draw_handle.draw_text(&entry.profile, profile_x, y, size, DIM_COLOUR);
+            draw_handle.draw_text(entry.status.text(), status_x, y, size, entry.status.colour());
+        }
+
+        draw_handle.draw_text(
+            "Up/Down and Enter or click to play, F5 to look again, Escape to quit",
+            margin,
+            layout.height - size * 3 / 2,
+            size * 3 / 4,
+            DIM_COLOUR,
+        );
+    }
+}
+
+pub fn run(folder: &Path, profiles: &dyn Storage) -> Result<Option<PathBuf>, String> {
+    // Opens a window with the folder's roms and returns the one picked
+    //  The window is closed again before returning, the game opens its own
+
+    let entries: Vec<Entry> = scan(folder, profiles)?;
+    let mut launcher: Launcher = Launcher::new(folder, entries);
+
+    let (mut raylib_handle, thread) = raylib::init()
+        .size(crate::WIDTH, crate::HEIGHT)
+        .title("Space Invaders")
+        .resizable()
+        .build();
+    raylib_handle.set_target_fps(60);
+    Ok(launcher.choose(&mut raylib_handle, &thread, profiles))
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::info::Chunk;
+#[cfg(test)]
+use crate::storage::MemoryStorage;
+
+#[test]
+fn test_status() {
+    let mut info: RomInfo = RomInfo::new(&[0; 0x1000], &Profile::space_invaders());
+    assert_eq!(Status::of(&info), Status::Unknown);
+
+    let known = |start: usize, expected: bool| Chunk { start, crc: 0, known: Some(String::from("invaders")), expected };
+    info.chunks = vec![known(0, true), known(0x800, true)];
+    assert_eq!(Status::of(&info), Status::Verified);
+    info.chunks[1].known = None;
+    assert_eq!(Status::of(&info), Status::Partly);
+    info.chunks = vec![known(0, false), known(0x800, true)];
+    assert_eq!(Status::of(&info), Status::OutOfOrder);
+
+    info.size = info.rom_space + 1;
+    assert_eq!(Status::of(&info), Status::TooBig);
+}
+
+#[test]
+fn test_scan() {
+    let folder: PathBuf = std::env::temp_dir().join(format!("emulator-launcher-test-{}", std::process::id()));
+    let mut profiles: MemoryStorage = MemoryStorage::new();
+    assert_eq!(scan(&folder, &profiles), Ok(vec![]));
+    // A folder that isn't there yet has no roms
+
+    fs::create_dir_all(folder.join("nested")).unwrap();
+    fs::write(folder.join("b.rom"), [0; 0x800]).unwrap();
+    fs::write(folder.join("a.rom"), [0; 0x2800]).unwrap();
+    fs::write(folder.join(".hidden"), [0]).unwrap();
+    let custom: String = include_str!("../../profiles/space_invaders.toml").replace("name = \"Space Invaders\"", "name = \"Custom\"");
+    profiles.write(Kind::Config, "b.rom.toml", custom.as_bytes()).unwrap();
+
+    let entries: Vec<Entry> = scan(&folder, &profiles).unwrap();
+    assert_eq!(entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<&str>>(), ["a.rom", "b.rom"]);
+    assert_eq!((entries[0].profile.as_str(), &entries[0].status), ("Space Invaders", &Status::TooBig));
+    assert_eq!((entries[1].profile.as_str(), &entries[1].status), ("Custom", &Status::Unknown));
+    assert_eq!(entries[1].path, folder.join("b.rom"));
+
+    profiles.write(Kind::Config, "a.rom.toml", b"not a profile").unwrap();
+    let mut launcher: Launcher = Launcher::new(&folder, entries);
+    launcher.select(1);
+    fs::write(folder.join("0.rom"), [0; 0x800]).unwrap();
+    launcher.rescan(&profiles);
+    assert_eq!(launcher.entries().len(), 3);
+    assert!(matches!(launcher.entries()[1].status, Status::Error(_)));
+    assert_eq!(launcher.selected().map(|entry| entry.name.as_str()), Some("b.rom"));
+    // The selection follows the rom when one is added above it
+
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn test_selection() {
+    let entries: Vec<Entry> = (0..10).map(|i| Entry {
+        path: PathBuf::from(format!("{}.rom", i)),
+        name: format!("{}.rom", i),
+        profile: String::new(),
+        status: Status::Unknown,
+    }).collect();
+    let mut launcher: Launcher = Launcher::new(Path::new("roms"), entries);
+
+    assert_eq!(launcher.visible(4), 0..4);
+    launcher.move_by(-1);
+    assert_eq!(launcher.selected().unwrap().name, "0.rom");
+    launcher.move_by(5);
+    assert_eq!(launcher.visible(4), 2..6);
+    // Scrolls just far enough to show the selection
+    launcher.move_by(-3);
+    assert_eq!(launcher.visible(4), 2..6);
+    launcher.move_by(isize::MAX / 2);
+    assert_eq!(launcher.selected().unwrap().name, "9.rom");
+    assert_eq!(launcher.visible(4), 6..10);
+    assert_eq!(launcher.visible(20), 0..10);
+
+    assert_eq!(Launcher::new(Path::new("roms"), vec![]).visible(4), 0..0);
+}
@@ -1,244 +1,140 @@
-use raylib::prelude::*;
-
+#[cfg(feature = "agent")]
+pub mod agent;
+pub mod assembler;
+pub mod audit;
+pub mod audio;
+pub mod bandwidth;
+pub mod banking;
+pub(crate) mod coverage;
 pub mod cpu;
+pub mod crash;
+pub mod debugger;
+pub mod display;
+pub(crate) mod error;
+#[cfg(test)]
+mod fixtures;
+pub mod frontend;
+pub mod golden;
 pub mod hardware;
-
-use cpu::Cpu;
-use hardware::Hardware;
+pub mod harness;
+pub mod headless;
+#[cfg(feature = "debug-hooks")]
+pub mod hooks;
+pub(crate) mod i18n;
+pub(crate) mod idle;
+pub mod info;
+pub mod input_lag;
+pub mod kiosk;
+pub mod latency;
+#[cfg(feature = "frontend")]
+pub mod launcher;
+pub(crate) mod machine;
+pub mod monitor;
+pub(crate) mod observer;
+pub(crate) mod pacing;
+pub mod paddle;
+pub mod palette;
+pub(crate) mod png;
+pub mod postprocess;
+#[cfg(feature = "serde")]
+pub(crate) mod port_log;
+pub(crate) mod primitives;
+pub mod profile;
+#[cfg(feature = "serde")]
+pub mod profiles;
+#[cfg(feature = "serde")]
+pub mod regress;
+#[cfg(feature = "frontend")]
+pub(crate) mod render;
+pub mod replay;
+#[cfg(feature = "serde")]
+pub mod report;
+pub(crate) mod rewind;
+pub mod rng;
+pub mod savestate;
+pub(crate) mod schedule;
+#[cfg(feature = "frontend")]
+pub mod scrubber;
+pub mod selftest;
+pub mod soak;
+pub mod state_panel;
+pub mod stats;
+pub mod storage;
+pub mod testing;
+pub mod timeline;
+#[cfg(feature = "frontend")]
+pub mod touch;
+pub mod tracer;
+pub(crate) mod vblank;
+#[cfg(feature = "serde")]
+pub(crate) mod versioned;
+pub mod video;
+pub mod watcher;
+#[cfg(feature = "frontend")]
+pub mod window;
+#[cfg(feature = "serde")]
+pub(crate) mod zip;
+
+use i18n::{Language, Text};
+use machine::Machine;
+#[cfg(feature = "frontend")]
+pub use render::{render, Hud};
+
+pub const API_VERSION: &str = env!("CARGO_PKG_VERSION");
+// The prelude only changes in ways that break an embedder with a semver breaking version,
+//  tests/api_stability.rs uses all of it from outside the crate to catch that
+
+pub mod prelude {
+    // Everything needed to embed the emulator in a frontend
+    pub use crate::API_VERSION;
+    pub use crate::coverage::Coverage;
+    pub use crate::cpu::{Cpu, Flag, FlagSet, Flags, Memory, Permissions, StateDelta};
+    pub use crate::hardware::Hardware;
+    pub use crate::hardware::devices::{Claim, IoDevice};
+    pub use crate::hardware::input::{DipSwitches, InputConfig, InputSource, KeyboardKey};
+    pub use crate::hardware::port_map::PortMap;
+    pub use crate::hardware::sound::{AudioSink, Sound};
+    pub use crate::debugger::{Break, Breakpoint, Debugger, Watch, WatchAction, WatchCondition, WatchedRegister};
+    pub use crate::display::Framebuffer;
+    pub use crate::error::{EmulatorError, ErrorKind, LoadError};
+    pub use crate::frontend::{Frontend, Headless};
+    pub use crate::i18n::{Language, Text};
+    pub use crate::machine::{step, Event, HalfFrame, Machine, Stop, CYCLES_PER_FRAME};
+    pub use crate::observer::{MachineObserver, Snapshot};
+    pub use crate::pacing::FramePacer;
+    pub use crate::primitives::Color;
+    pub use crate::profile::Profile;
+    pub use crate::rewind::Rewind;
+    pub use crate::schedule::{Scheduler, When};
+}
 
 pub const WIDTH: i32 = 1920;
 pub const HEIGHT: i32 = 1080;
-const INVADERS_WIDTH: i32 = 224;
-const INVADERS_HEIGHT: i32 = 256;
-
-const TOP_COLOUR: &str = "F41EFA";
-const MID_COLOUR: Color = Color::WHITE;
-const BOTTOM_COLOUR: &str = "22CC00";
-const OFF_COLOUR: Color = Color::BLACK;
-
-const DEBUG_TEXT_SIZE: i32 = 20;
-
-pub fn update(raylib_handle: &mut raylib::RaylibHandle, hardware: &mut Hardware, cpu: &mut Cpu) -> u64 {
-    // Handles updating the state of the emulator before rendering
-
-    hardware::input::read_input(&raylib_handle, hardware, hardware::input::InputConfig::default());
-    // Reads user input and changes the state of the hardware input ports
-
-    let op_code: u8 = cpu.memory.read_at(cpu.pc.address);
-    let op_code_location: u16 = cpu.pc.address;
-    cpu.pc.address += 1;
-    let additional_bytes: (u8, u8) = (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1));
-    // Important to remember pc address is incremented before op code is handled
-    //  when handling operations that read additional bytes, the first byte to be read will be
-    //  at the pc address NOT pc address + 1
-
-    let cycles: u8 = cpu::dispatcher::CLOCK_CYCLES[op_code as usize];
-
-    let result = match op_code {
-        0xdb | 0xd3 => { // IN & OUT
-            // IO is handled by the hardware module not the cpu
-            // For IN operations handle_io returns the value read from the port
-            let port_byte: u8 = cpu.memory.read_at(cpu.pc.address);
-            match hardware::handle_io(op_code, hardware, port_byte, cpu.a.value) {
-                Some(value) => cpu.a.value = value,
-                None => {},
-            }
-            Ok(1)
-            // IN & OUT always read one additional byte
-        },
-        _ => cpu::dispatcher::handle_op_code(op_code, cpu)
-    };
-
-    match result {
-        Err(e) => {
-            println!("0x{:04x}: 0x{:02x} encountered error: {}", op_code_location, op_code, e);
-            // panic!();
-        },
-        Ok(additional_bytes) => match additional_bytes {
-            255 => panic!("HALT"),
-            // Only halt should return 255
-            _ => cpu.pc.address += additional_bytes,
-        },
-    }
-
-    // println!("0x{:04x}: 0x{:02x}:   (0x{:02x}, 0x{:02x})", op_code_location, op_code, additional_bytes.0, additional_bytes.1);
-    cycles as u64
-}
-
-pub fn render(raylib_handle: &mut raylib::RaylibHandle, thread: &raylib::RaylibThread, hardware: &Hardware, cpu: &Cpu) {
-    // Renders things to the screen based on the state of the machine
-
-    let mut draw_handle = raylib_handle.begin_drawing(thread);
-
-    draw_handle.clear_background(OFF_COLOUR);
-
-    // Debug Rendering
-    let coin: &str = "Insert Coin: Enter";
-    let start: &str = "P1 Start: Q";
-    let left: &str = "P1 Left: A";
-    let right: &str = "P1 Right: D";
-    let shoot: &str = "P1 Shoot: S";
-
-    let debug_text: Vec<&str> = vec![coin, start, left, right, shoot];
-    for (i, text) in debug_text.iter().enumerate() {
-        draw_handle.draw_text(text, 0, (i as i32)*DEBUG_TEXT_SIZE, DEBUG_TEXT_SIZE, MID_COLOUR);
-        // 1 + i to start the debug strings after the fps
-    }
-    // Draws each debug string in a column
-
-    // Game Rendering
-    let scale: i32 = HEIGHT / INVADERS_HEIGHT;
-    // Scale Space Invaders so it fits vertically as close as possible
-    //  Not a float so can't fit exactly
-
-    let game_scaled_width: i32 = INVADERS_WIDTH * scale;
-    let game_scaled_height: i32 = INVADERS_HEIGHT * scale;
-    let game_x_offset: i32 = (WIDTH - game_scaled_width) / 2;
-    let game_y_offset: i32 = (HEIGHT - game_scaled_height) / 2;
-    // Move the game to the middle of the screen
-
-    let vram: &[u8] = cpu.memory.read_vram();
+// Biggest window opened without --window-size, smaller monitors get a smaller one
+pub const INVADERS_WIDTH: i32 = 224;
+pub const INVADERS_HEIGHT: i32 = 256;
 
-    let mut i: usize = 0;
-    for ix in 0..INVADERS_WIDTH {
-        for iy in 0..(INVADERS_HEIGHT / 8) {
-            let mut byte = vram[i];
-            i += 1;
+pub fn controls(language: Language) -> Vec<String> {
+    // Help text for the default keys, the first lines of the hud
 
-            for b in 0..8 {
-                let x: i32 = (ix as i32) * scale;
-                let y: i32 = (INVADERS_HEIGHT - ((iy * 8) as i32 + b)) * scale;
+    let coin: String = format!("{}: Enter", language.text(Text::InsertCoin));
+    let start: String = format!("{}: Q", language.text(Text::P1Start));
+    let left: String = format!("{}: A", language.text(Text::P1Left));
+    let right: String = format!("{}: D", language.text(Text::P1Right));
+    let shoot: String = format!("{}: S", language.text(Text::P1Shoot));
 
-                if byte & 1 == 1 {
-                    let mut colour: Color = match iy * 8 {
-                        201..=219 => Color::from_hex(TOP_COLOUR).unwrap(),
-                        0..=15 => Color::from_hex(BOTTOM_COLOUR).unwrap(),
-                        16 => MID_COLOUR,
-                        17..=71 => Color::from_hex(BOTTOM_COLOUR).unwrap(),
-                        _ => MID_COLOUR,
-                    };
-                    if colour == Color::from_hex(BOTTOM_COLOUR).unwrap() && iy * 8 < 15 {
-                        match ix {
-                            0..=25 => colour = MID_COLOUR,
-                            135..=INVADERS_WIDTH => colour = MID_COLOUR,
-                            _ => {},
-                        }
-                    }
-                    draw_handle.draw_rectangle(x + game_x_offset, y + game_y_offset, scale, scale, colour);
-                }
-
-                byte >>= 1;
-            }
-        }
-    }
+    vec![coin, start, left, right, shoot]
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn cpu_diag() {
-        let mut cpu: Cpu = Cpu::init();
-        let cpu_diag: &[u8] = include_bytes!("../cpudiag");
-
-        cpu.memory.load_rom(cpu_diag, 0x100);
-        cpu.pc.address = 0x100;
-        // Load cpudiag
-
-        // Fix stack pointer to 0x07ad instead of 0x06ad
-        cpu.memory.write_at(368, 0x07);
-
-        // Skip DAA test
-        cpu.memory.write_at(0x059c, 0xc3); // JMP
-        cpu.memory.write_at(0x059d, 0xc2);
-        cpu.memory.write_at(0x059e, 0x05);
-
-        while test_update(&mut cpu) == None {}
-    }
-
-    fn test_update(cpu: &mut Cpu) -> Option<&str> {
-        // Cut down version of the normal update function with some modifications for cpudiag
-
-        let op_code: u8 = cpu.memory.read_at(cpu.pc.address);
-        let op_code_location: u16 = cpu.pc.address;
-        cpu.pc.address += 1;
-        let additional_bytes: (u8, u8) = (cpu.memory.read_at(cpu.pc.address), cpu.memory.read_at(cpu.pc.address + 1));
-
-        if op_code == 0xcd && additional_bytes == (0x05, 0x00) {
-        // If the program jumps to 0x0005 execute os_syscall directly
-            cpu.pc.address += 2;
-            return os_syscall(cpu);
-        }
-
-        else {
-            if op_code == 0xc4 && additional_bytes == (0x89, 0x06) {
-                println!("Setting up SYSCALL");
-            }
-
-            let result = match op_code {
-                0xdb | 0xd3 => { // IN & OUT
-                    let port_byte: u8 = cpu.memory.read_at(cpu.pc.address);
-                    handle_out(&cpu, port_byte);
-
-                    Ok(1)
-                    // IN & OUT always read one additional byte
-                },
-                _ => cpu::dispatcher::handle_op_code(op_code, cpu)
-            };
-
-            match result {
-                Err(e) => {
-                    println!("0x{:04x}: 0x{:02x} encountered error: {}", op_code_location, op_code, e);
-                },
-                Ok(additional_bytes) => match additional_bytes {
-                    255 => panic!("HALT"),
-                    _ => cpu.pc.address += additional_bytes,
-                },
-            }
-
-            println!("0x{:04x}: 0x{:02x}:   (0x{:02x}, 0x{:02x})", op_code_location, op_code, additional_bytes.0, additional_bytes.1);
-            None
-        }
-    }
-
-    fn handle_out(cpu: &Cpu, port_byte: u8) {
-        // When an out instruction is called
-        //  if port 0: Test is finished
-        //  if port 1: Look at content of C register
-        //      if c is 2: Print the content of the E register
-        //      if c is 9: Print (DE)..(DE+1).. until (DE) == $
-        match port_byte {
-            0 => println!("Test Complete"),
-            1 => { os_syscall(cpu); },
-            _ => panic!("No other ports"),
-        }
-    }
-
-    fn os_syscall(cpu: &Cpu) -> Option<&str> {
-        // Writes out text from memory and panics if a test fails
-        match cpu.debug_c() {
-            2 => println!("{}", cpu.debug_e()),
-            9 => {
-                let mut memory_address: u16 = (cpu.debug_d() as u16) << 8 | cpu.debug_e() as u16;
-                memory_address += 3;
-                // Skip prefix stuff
-
-                let mut string_to_print: String = String::new();
-                while cpu.memory.read_at(memory_address) != '$' as u8 {
-                    string_to_print.push(cpu.memory.read_at(memory_address) as char);
-                    memory_address += 1;
-                }
-
-                println!("{}\n", string_to_print);
-
-                if string_to_print.contains("FAILED") {
-                    panic!("Test Failed");
-                } else { return Some("success"); }
-            },
-            _ => panic!("No syscalls other than 9 and 2"),
-        }
+pub fn halt_status(machine: &Machine, language: Language) -> Option<String> {
+    // Hud line while the cpu sits on a HLT, counting down to the interrupt that will wake it
+    //  e.g. "HALT - waiting for interrupt, 8250 cycles to the next one"
 
-        None
+    if !machine.cpu.halted() {
+        return None;
     }
+    Some(match machine.cpu.registers().interrupt_enabled {
+        true => format!("{}, {} {}", language.text(Text::HaltWaiting), machine.cycles_to_interrupt(), language.text(Text::CyclesToInterrupt)),
+        false => format!("{}, {}", language.text(Text::HaltWaiting), language.text(Text::InterruptsOff)),
+    })
 }
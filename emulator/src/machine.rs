@@ -0,0 +1,846 @@
+use std::collections::VecDeque;
+
+use crate::audit::Audit;
+use crate::bandwidth::Bandwidth;
+use crate::banking::{BankSelect, Banks};
+use crate::cpu;
+use crate::cpu::{Cpu, Permissions, Registers};
+use crate::coverage::Coverage;
+use crate::display::Framebuffer;
+use crate::error::{EmulatorError, ErrorKind, LoadError};
+use crate::hardware;
+use crate::hardware::Hardware;
+use crate::hardware::cocktail::Cocktail;
+use crate::hardware::devices::{Claim, BOARD_PRIORITY};
+#[cfg(feature = "debug-hooks")]
+use crate::hooks::{Fetched, Hooks, InstructionHook};
+use crate::i18n::Language;
+use crate::idle::IdleDetector;
+use crate::latency::LatencyStats;
+use crate::observer::{MachineObserver, Observers};
+use crate::profile::{Interrupts, Overlay, Profile, RegionKind, StackGuard};
+use crate::headless;
+use crate::rng::Rng;
+use crate::savestate::{self, HardwareState, SaveState};
+use crate::timeline::{Kind, Timeline};
+use crate::tracer::Tracer;
+use crate::vblank::Vblank;
+
+mod tests;
+
+pub const CYCLES_PER_FRAME: u64 = 33_000;
+// There are a total of 33 000 cycles in every Space Invaders frame, other machines set their own in their profile
+pub const TRACE_LENGTH: usize = 64;
+// Instructions remembered for crash dumps
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Interrupt(u8),
+    // RST number of an interrupt that was serviced
+    PortRead(u8),
+    PortWrite(u8, u8),
+    // Port and the value written to it
+    InterruptsEnabled,
+    InterruptsDisabled,
+    StackLeft(u16),
+    // Stack pointer went outside the profile's stack, only once until it comes back
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    // Why run_until returned
+    Condition,
+    CycleLimit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfFrame {
+    // What run_half_frame ran up to
+    pub rst: u8,
+    // RST number of the interrupt that ended it, RST 1 halfway down the screen and RST 2 at the end on Space Invaders
+    pub serviced: bool,
+    // False when the cpu had interrupts disabled or they were blocked from the monitor
+    pub cycles: u64,
+    // Cycles run, idle loops that were skipped included
+    pub end_of_frame: bool,
+}
+
+#[derive(Clone)]
+pub struct Machine {
+    pub cpu: Cpu,
+    pub hardware: Hardware,
+    pub coverage: Coverage,
+    // Addresses executed since the machine was created, survives resets
+    profile: Profile,
+    rom: Vec<u8>,
+    // Kept so the machine can be reset without reloading the rom from disk
+    banks: Vec<Banks>,
+    frame_cycles: u64,
+    // Cycles run so far in the current frame
+    total_cycles: u64,
+    // Cycles run since the machine was created
+    mid_screen_done: bool,
+    interrupts_blocked: bool,
+    // Set from the monitor to stop the board's interrupts reaching the cpu
+    events: Vec<Event>,
+    // Everything notable that has happened since the start of the frame
+    step_events: usize,
+    // Index of the first event produced by the last call to step_frame
+    trace: VecDeque<u16>,
+    // Addresses of the last TRACE_LENGTH instructions, kept for crash dumps
+    stack_left: bool,
+    // The stack pointer is outside the profile's stack
+    idle: Option<IdleDetector>,
+    // Set when idle loops are skipped
+    latency: LatencyStats,
+    timeline: Option<Timeline>,
+    // Set when notable events are being recorded
+    bandwidth: Option<Bandwidth>,
+    // Set when memory accesses are being counted
+    audit: Option<Audit>,
+    // Set when every instruction is checked against the op code tables
+    tracer: Option<Tracer>,
+    // Set when instructions, interrupts or frames are being written out
+    observers: Observers,
+    // Given a snapshot at the end of every frame, copies of the machine start with none
+    vblank: Vblank,
+    // Called with the screen when the end of frame interrupt comes due, copies start with none here too
+    rng: Rng,
+    // Anything random the machine needs comes from here so snapshots and replays repeat it
+    #[cfg(feature = "debug-hooks")]
+    hooks: Hooks,
+}
+impl Machine {
+    pub fn init() -> Self {
+        Self::wired(Profile::space_invaders())
+        // The built in profile has no banks, so there is no bank select to attach that could fail
+    }
+
+    pub fn with_profile(profile: Profile) -> Result<Self, String> {
+        // Builds a machine wired up the way the profile describes
+        //  Fails when a bank select can't have its port, which validate warns about when the profile is loaded
+
+        let mut machine: Machine = Self::wired(profile);
+        for bank in &machine.profile.banks {
+            machine.hardware.attach(Box::new(BankSelect::new(bank.port)), &[Claim::write(bank.port)], BOARD_PRIORITY)
+                .map_err(|e| format!("Bank select on port {}: {}", bank.port, e))?;
+        }
+        Ok(machine)
+    }
+
+    fn wired(profile: Profile) -> Self {
+        // Everything but the bank selects, which are the only part of a profile that can fail to attach
+
+        let mut hardware: Hardware = Hardware::init();
+        hardware.set_port_map(profile.ports);
+        hardware.set_shift_config(profile.shift);
+        hardware.set_dip_switches(profile.dip_switches);
+        hardware.set_service_switch(profile.service);
+        hardware.set_coin_pulse_frames(profile.coin_pulse_frames);
+        hardware.set_watchdog(profile.watchdog_frames);
+        hardware.set_cocktail(profile.cocktail.clone());
+        let banks: Vec<Banks> = profile.banks.iter().map(|bank| Banks::new(*bank)).collect();
+
+        let mut machine: Machine = Self {
+            cpu: Cpu::init(),
+            hardware,
+            coverage: Coverage::init(),
+            profile,
+            rom: vec![],
+            banks,
+            frame_cycles: 0,
+            total_cycles: 0,
+            mid_screen_done: false,
+            interrupts_blocked: false,
+            events: vec![],
+            step_events: 0,
+            trace: VecDeque::with_capacity(TRACE_LENGTH),
+            stack_left: false,
+            idle: None,
+            latency: LatencyStats::new(),
+            timeline: None,
+            bandwidth: None,
+            audit: None,
+            tracer: None,
+            observers: Observers::new(),
+            vblank: Vblank::new(),
+            rng: Rng::default(),
+            #[cfg(feature = "debug-hooks")]
+            hooks: Hooks::new(),
+        };
+        machine.map_memory();
+
+        machine
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn set_overlay(&mut self, overlay: Overlay) {
+        // The overlay only affects rendering so it can be swapped while running
+        self.profile.overlay = overlay;
+    }
+
+    pub fn set_coin_pulse_frames(&mut self, frames: u8) {
+        // Only changes how the coin key is read so it can be tuned while running
+        self.profile.coin_pulse_frames = frames;
+        self.hardware.set_coin_pulse_frames(frames);
+    }
+
+    pub fn set_watchdog(&mut self, frames: Option<u16>) {
+        // None leaves a rom that never feeds the watchdog running, test roms and bare programs don't know about it
+        self.profile.watchdog_frames = frames;
+        self.hardware.set_watchdog(frames);
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        // Only changes on screen text so it can be swapped while running
+        self.profile.language = language;
+    }
+
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), LoadError> {
+        // Loads a rom into memory at address 0, it has to fit in the profile's rom space
+        //  This is the machine being switched on, so it is when ram gets filled with noise if the profile asks for it
+        //  A rom that doesn't fit leaves the last one in place
+
+        let previous: Vec<u8> = std::mem::replace(&mut self.rom, rom.to_vec());
+        if self.profile.randomize_ram {
+            self.randomize_ram();
+        }
+        let placed: Result<(), LoadError> = self.place_rom();
+        if placed.is_err() {
+            self.rom = previous;
+            self.place_rom().expect("the last rom fitted");
+            // Put back in case a power cycle cleared it
+        }
+        placed
+    }
+
+    pub fn seed(&mut self, seed: u64) {
+        // Should be called before load_rom, a run can only be repeated with the seed it was started with
+        self.rng = Rng::new(seed);
+    }
+
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    fn randomize_ram(&mut self) {
+        // Real ram comes up holding whatever it settled on, some games read it before writing it
+        let mut noise: Vec<u8> = vec![];
+        for region in self.profile.memory.iter().filter(|region| region.kind != RegionKind::Rom) {
+            noise.resize(region.addresses().len(), 0);
+            self.rng.fill(&mut noise);
+            for (address, byte) in region.addresses().zip(&noise) {
+                self.cpu.memory.poke(address as u16, *byte);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) -> Result<(), LoadError> {
+        // Resets the cpu and reloads the rom like the reset line on the real board
+        //  Only the board devices on the reset line are cleared, power_cycle clears the rest
+        self.cpu.reset();
+        self.hardware.soft_reset();
+        self.place_rom()
+    }
+
+    pub fn power_cycle(&mut self, rom: &[u8]) -> Result<(), LoadError> {
+        // Switches the board off and on again with a new rom, ram is cleared and every device reset
+        //  Devices attached since the machine was built stay attached, and a debugger keeps its
+        //  breakpoints as long as its history is cleared
+        self.cpu.reset();
+        self.hardware.reset();
+        self.banks = self.profile.banks.iter().map(|bank| Banks::new(*bank)).collect();
+        self.frame_cycles = 0;
+        self.total_cycles = 0;
+        self.mid_screen_done = false;
+        self.events.clear();
+        self.step_events = 0;
+        self.trace.clear();
+        self.stack_left = false;
+        if self.idle.is_some() {
+            self.idle = Some(IdleDetector::new());
+        }
+        self.latency.clear();
+        if let Some(bandwidth) = self.bandwidth.as_mut() {
+            bandwidth.clear();
+        }
+        self.load_rom(rom)
+    }
+
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            version: savestate::VERSION,
+            rom_hash: headless::rom_hash(&self.rom),
+            registers: self.cpu.registers(),
+            memory: savestate::encode_memory(self.cpu.memory.bytes()),
+            hardware: HardwareState {
+                shift_register: self.hardware.shift_state().0,
+                shift_amount: self.hardware.shift_state().1,
+                service_mode: self.hardware.service_mode(),
+                flipped: self.hardware.flipped(),
+            },
+            rng: self.rng.state(),
+            banks: self.banks.iter().map(Banks::selected).collect(),
+            total_cycles: self.total_cycles,
+            frame_cycles: self.frame_cycles,
+            mid_screen_done: self.mid_screen_done,
+            halted: self.cpu.halted(),
+        }
+    }
+
+    pub fn load_state(&mut self, state: &SaveState) -> Result<(), String> {
+        // Only states saved with the same rom are accepted, memory would be nonsense otherwise
+
+        if state.rom_hash != headless::rom_hash(&self.rom) {
+            return Err(String::from("Save state was made with a different rom"));
+        }
+        let memory: Vec<u8> = state.memory()?;
+
+        for (banks, selected) in self.banks.iter_mut().zip(&state.banks) {
+            banks.select(*selected, &mut self.cpu.memory);
+        }
+        // Selected first so the saved memory lands over the window afterwards
+        self.cpu.memory.restore(&memory);
+        self.cpu.set_registers(state.registers);
+        self.cpu.set_halted(state.halted);
+        self.hardware.set_shift_state((state.hardware.shift_register, state.hardware.shift_amount));
+        self.hardware.set_service_mode(state.hardware.service_mode);
+        self.hardware.set_flipped(state.hardware.flipped);
+        self.rng = Rng::new(state.rng);
+        self.total_cycles = state.total_cycles;
+        self.frame_cycles = state.frame_cycles;
+        self.mid_screen_done = state.mid_screen_done;
+        self.events.clear();
+        self.step_events = 0;
+        if let Some(idle) = self.idle.as_mut() {
+            idle.interrupted();
+        }
+        if let Some(timeline) = self.timeline.as_mut() {
+            timeline.record(self.total_cycles, Kind::StateLoad, format!("pc 0x{:04x}", state.registers.pc));
+        }
+        Ok(())
+    }
+
+    fn place_rom(&mut self) -> Result<(), LoadError> {
+        // Anything in the rom file from the first rom bank on goes into the banks instead of memory
+
+        let fixed: usize = self.banks.iter()
+            .filter_map(|banks| banks.config().rom_offset)
+            .min()
+            .unwrap_or(self.rom.len())
+            .min(self.rom.len());
+        self.cpu.memory.load_rom_within(&self.rom[..fixed], 0, self.profile.rom_space())?;
+        for banks in self.banks.iter_mut() {
+            banks.load(&self.rom, &mut self.cpu.memory);
+        }
+        self.map_memory();
+        Ok(())
+    }
+
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    pub fn banks(&self) -> &[Banks] {
+        self.banks.as_slice()
+    }
+
+    fn map_memory(&mut self) {
+        // Sets up the memory map from the profile
+        for region in &self.profile.memory {
+            let permissions: Permissions = match region.kind {
+                RegionKind::Rom => Permissions::ReadOnly,
+                RegionKind::Ram => Permissions::ReadWrite,
+                RegionKind::Vram => Permissions::DirtyTracked,
+            };
+            self.cpu.memory.set_region_permissions(region.start as u32..region.end, permissions);
+        }
+        self.cpu.memory.set_mirror(self.profile.mirror);
+    }
+
+    pub fn events(&self) -> &[Event] {
+        self.events.as_slice()
+    }
+
+    pub fn last_events(&self) -> &[Event] {
+        // Events produced by the last call to step_frame
+        &self.events[self.step_events..]
+    }
+
+    pub fn step(&mut self) -> Result<u64, EmulatorError> {
+        // Executes a single instruction and returns the number of cycles it took
+
+        self.coverage.mark(self.cpu.pc.address);
+        if self.trace.len() == TRACE_LENGTH {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(self.cpu.pc.address);
+
+        #[cfg(feature = "debug-hooks")]
+        let substituted: Option<(Fetched, Fetched)> = match self.hooks.is_empty() {
+            true => None,
+            false => self.hooks.substitute(&mut self.cpu),
+        };
+        // Everything below sees the replacement as if it had been in memory all along
+
+        let from: u16 = self.cpu.pc.address;
+        let op_code: u8 = self.cpu.memory.read_at(self.cpu.pc.address);
+        let port_byte: Option<u8> = matches!(op_code, 0xd3 | 0xdb).then(|| self.cpu.memory.read_at(self.cpu.pc.address.wrapping_add(1)));
+        // Only IN and OUT have their operand read here, every other instruction reads its own as it runs
+        match (op_code, port_byte) {
+            (0xd3, Some(port_byte)) => self.events.push(Event::PortWrite(port_byte, self.cpu.a.value)),
+            (0xdb, Some(port_byte)) => self.events.push(Event::PortRead(port_byte)),
+            (0xf3, _) => self.events.push(Event::InterruptsDisabled),
+            (0xfb, _) => self.events.push(Event::InterruptsEnabled),
+            _ => {},
+        }
+
+        let stepped: Result<u64, EmulatorError> = step(&mut self.cpu, &mut self.hardware);
+        #[cfg(feature = "debug-hooks")]
+        if let Some(substituted) = substituted {
+            Hooks::restore(&mut self.cpu.memory, substituted);
+        }
+        let cycles: u64 = stepped?;
+        self.guard_stack(from, op_code)?;
+        if let (0xd3, Some(port_byte)) = (op_code, port_byte) {
+            let value: u8 = self.cpu.a.value;
+            for banks in self.banks.iter_mut().filter(|banks| banks.config().port == port_byte) {
+                banks.select(value, &mut self.cpu.memory);
+            }
+            if let (Some(timeline), Some(port)) = (self.timeline.as_mut(), self.hardware.port_map().write(port_byte)) {
+                timeline.sound_write(port, value, self.total_cycles);
+            }
+        }
+        // Switched after the OUT so the next instruction already sees the new bank
+
+        Ok(cycles)
+    }
+
+    #[cfg(feature = "debug-hooks")]
+    pub fn add_hook(&mut self, hook: impl InstructionHook + Send + 'static) -> usize {
+        // Hook that sees and can change every instruction before it runs, returns an id for remove_hook
+        //  e.g. machine.add_hook(|fetched: &mut Fetched, _: &Cpu| if fetched.address == 0x0100 { fetched.op_code = 0x00 })
+        self.hooks.add(hook)
+    }
+
+    #[cfg(feature = "debug-hooks")]
+    pub fn remove_hook(&mut self, id: usize) -> bool {
+        self.hooks.remove(id)
+    }
+
+    fn guard_stack(&mut self, from: u16, op_code: u8) -> Result<(), EmulatorError> {
+        // Looks at the stack pointer after each instruction when the profile gives a stack
+        //  Pushes made by interrupts are seen after the next instruction
+
+        let Some(stack) = self.profile.stack.filter(|stack| stack.guard != StackGuard::Off) else {
+            return Ok(());
+        };
+        let sp: u16 = self.cpu.registers().sp;
+        let left: bool = !stack.contains(sp);
+        let crossed: bool = left && !self.stack_left;
+        self.stack_left = left;
+        match (crossed, stack.guard) {
+            (true, StackGuard::Trap) => Err(EmulatorError { address: from, op_code, kind: ErrorKind::StackOutOfBounds(sp) }),
+            (true, _) => {
+                self.events.push(Event::StackLeft(sp));
+                Ok(())
+            },
+            (false, _) => Ok(()),
+        }
+    }
+
+    pub fn trace(&self) -> impl Iterator<Item = u16> + '_ {
+        // Addresses of the most recent instructions, oldest first
+        //  The last one is the instruction that faulted when step returned an error
+        self.trace.iter().copied()
+    }
+
+    pub fn set_idle_skip(&mut self, enabled: bool) {
+        // Skips ahead to the next interrupt when the rom is spinning in a loop waiting for one
+        //  Only worth it when nothing is pacing the machine, like headless runs
+        //  Cycle counts and everything the rom can see stay exactly the same
+        self.idle = enabled.then(IdleDetector::new);
+    }
+
+    pub fn idle_skipped(&self) -> u64 {
+        // Cycles skipped over in idle loops
+        self.idle.as_ref().map_or(0, |idle| idle.skipped())
+    }
+
+    pub fn latency(&self) -> &LatencyStats {
+        &self.latency
+    }
+
+    pub fn clear_latency(&mut self) {
+        self.latency.clear();
+    }
+
+    pub fn set_timeline(&mut self, enabled: bool) {
+        // Starts or stops recording interrupts, sounds, coins and state loads with the cycle they happened on
+        self.timeline = enabled.then(Timeline::new);
+    }
+
+    pub fn timeline(&self) -> Option<&Timeline> {
+        self.timeline.as_ref()
+    }
+
+    pub fn timeline_mut(&mut self) -> Option<&mut Timeline> {
+        // For things outside the machine like the debugger to add to
+        self.timeline.as_mut()
+    }
+
+    pub fn set_bandwidth(&mut self, enabled: bool) {
+        // Starts or stops counting memory accesses in each region, a half frame at a time
+        self.bandwidth = enabled.then(|| Bandwidth::new(&self.profile));
+    }
+
+    pub fn bandwidth(&self) -> Option<&Bandwidth> {
+        self.bandwidth.as_ref()
+    }
+
+    pub fn set_audit(&mut self, enabled: bool) {
+        // Starts or stops checking each instruction's bytes and cycles against the op code table and the disassembler
+        self.audit = enabled.then(Audit::new);
+    }
+
+    pub fn audit(&self) -> Option<&Audit> {
+        self.audit.as_ref()
+    }
+
+    pub fn audit_mut(&mut self) -> Option<&mut Audit> {
+        self.audit.as_mut()
+    }
+
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub fn restore(&mut self, earlier: &Machine) {
+        // Puts the machine back to a copy taken earlier, its trace, observers and vblank callbacks carry on from where they were
+        let tracer: Option<Tracer> = self.tracer.take();
+        let observers: Observers = std::mem::take(&mut self.observers);
+        let vblank: Vblank = std::mem::take(&mut self.vblank);
+        *self = earlier.clone();
+        self.tracer = tracer;
+        self.observers = observers;
+        self.vblank = vblank;
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        // For flushing, the trace is only complete once the tracer is flushed or dropped
+        self.tracer.as_mut()
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn step_frame(&mut self) -> Result<bool, EmulatorError> {
+        // Executes a single instruction and generates any interrupts that are due
+        //  Returns true when the instruction finished the frame
+
+        if self.frame_cycles == 0 {
+            self.events.clear();
+            if let Some(timeline) = self.timeline.as_mut() {
+                timeline.inputs(self.hardware.inputs(), self.total_cycles);
+            }
+        }
+        self.step_events = self.events.len();
+
+        let from: u16 = self.cpu.pc.address;
+        let op_code: u8 = self.cpu.memory.read_at(from);
+        let halted: bool = self.cpu.halted();
+        let before: Option<Registers> = self.bandwidth.is_some().then(|| {
+            self.cpu.memory.clear_writes();
+            self.cpu.registers()
+        });
+        let audited: Option<(Registers, [u8; 3])> = self.audit.as_ref().filter(|_| !halted).map(|_| {
+            let bytes: [u8; 3] = [0, 1, 2].map(|offset: u16| self.cpu.memory.read_at(from.wrapping_add(offset)));
+            (self.cpu.registers(), bytes)
+        });
+        if let Some(tracer) = self.tracer.as_mut().filter(|_| !halted) {
+            tracer.instruction(&self.cpu, self.total_cycles);
+        }
+        let cycles: u64 = match halted {
+            true => self.cycles_to_interrupt().max(1),
+            // Nothing runs while halted so the clock goes straight to the next interrupt
+            false => self.step()?,
+        };
+        if let (Some(bandwidth), Some(before)) = (self.bandwidth.as_mut(), before.filter(|_| !halted)) {
+            bandwidth.executed(&before, op_code, &self.cpu);
+            bandwidth.written(self.cpu.memory.writes());
+        }
+        if let (Some(audit), Some((before, bytes))) = (self.audit.as_mut(), audited) {
+            audit.executed(&before, bytes, &self.cpu, cycles, self.total_cycles);
+        }
+        self.frame_cycles += cycles;
+        self.total_cycles += cycles;
+        let interrupts: Interrupts = self.profile.interrupts;
+
+        if let Some(idle) = self.idle.as_mut().filter(|_| !halted) {
+            if let Some(length) = idle.record(op_code, from, self.cpu.registers(), self.total_cycles) {
+                let next: u64 = if self.mid_screen_done { interrupts.cycles_per_frame } else { interrupts.cycles_per_frame / 2 };
+                let skipped: u64 = next.saturating_sub(self.frame_cycles + 1) / length * length;
+                self.frame_cycles += skipped;
+                self.total_cycles += skipped;
+                idle.skip(skipped);
+            }
+        }
+        // Whole iterations are skipped up to just before the next interrupt
+        //  so it still lands on the same instruction it would have without skipping
+
+        if op_code == 0xfb && !halted {
+            return Ok(false);
+        }
+        // Interrupts that come due during EI wait until the instruction after it has run,
+        //  so the EI RET ending a handler returns before the next interrupt is taken
+
+        if !self.mid_screen_done && self.frame_cycles >= interrupts.cycles_per_frame / 2 {
+            let requested: u64 = self.total_cycles - (self.frame_cycles - interrupts.cycles_per_frame / 2);
+            if !self.interrupts_blocked {
+                self.interrupt(Interrupts::op_code(interrupts.mid_screen), requested);
+            }
+            self.mid_screen_done = true;
+            if let Some(bandwidth) = self.bandwidth.as_mut() {
+                bandwidth.end_half();
+            }
+        }
+
+        if self.frame_cycles >= interrupts.cycles_per_frame {
+            let requested: u64 = self.total_cycles - (self.frame_cycles - interrupts.cycles_per_frame);
+            if !self.vblank.is_empty() {
+                let framebuffer: Framebuffer = self.profile.display.framebuffer(&self.cpu.memory);
+                self.vblank.fire(&framebuffer);
+            }
+            // The screen has finished drawing whether or not the cpu takes the interrupt
+            if !self.interrupts_blocked {
+                self.interrupt(Interrupts::op_code(interrupts.full_screen), requested);
+            }
+            self.frame_cycles = 0;
+            self.mid_screen_done = false;
+            if let Some(bandwidth) = self.bandwidth.as_mut() {
+                bandwidth.end_half();
+            }
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.frame(&self.events, self.total_cycles);
+            }
+
+            self.hardware.tick_coin_counter();
+            if let Some(address) = self.hardware.cocktail().and_then(Cocktail::address) {
+                let flipped: bool = self.hardware.cocktail().is_some_and(|cocktail| cocktail.flipped_by_ram(self.cpu.memory.read_at(address)));
+                self.hardware.set_flipped(flipped);
+            }
+            // A flip kept in memory is looked at once a frame, the screen only needs to turn between frames
+            if self.hardware.tick_watchdog() {
+                let address: u16 = self.cpu.pc.address;
+                let op_code: u8 = self.cpu.memory.read_at(address);
+                self.reset().map_err(|e| EmulatorError { address, op_code, kind: ErrorKind::Reload(e) })?;
+            }
+            // The real board resets the cpu when the game stops writing to the watchdog
+
+            if !self.observers.is_empty() {
+                let mut observers: Observers = std::mem::take(&mut self.observers);
+                observers.publish(self);
+                self.observers = observers;
+            }
+            // Taken out while publishing since the snapshot is made from the rest of the machine
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    pub fn cycles_to_interrupt(&self) -> u64 {
+        // Cycles until the next interrupt is due, whether or not the cpu will take it
+
+        let interrupts: Interrupts = self.profile.interrupts;
+        let next: u64 = if self.mid_screen_done { interrupts.cycles_per_frame } else { interrupts.cycles_per_frame / 2 };
+        next.saturating_sub(self.frame_cycles)
+    }
+
+    pub fn next_interrupt(&self) -> u8 {
+        // RST number of the interrupt cycles_to_interrupt counts down to
+        match self.mid_screen_done {
+            true => self.profile.interrupts.full_screen,
+            false => self.profile.interrupts.mid_screen,
+        }
+    }
+
+    pub fn observer(&mut self) -> MachineObserver {
+        // Handle another thread can read the machine through without stopping it, updated at the end of every frame
+        self.observers.observer()
+    }
+
+    pub fn on_vblank(&mut self, callback: impl FnMut(&Framebuffer) + Send + 'static) -> usize {
+        // Calls back with the finished screen each time the end of frame interrupt comes due, returns an id for remove_vblank
+        //  e.g. machine.on_vblank(move |framebuffer: &Framebuffer| { let _ = sender.send(framebuffer.lit_pixels().count()); })
+        self.vblank.add(callback)
+    }
+
+    pub fn remove_vblank(&mut self, id: usize) -> bool {
+        self.vblank.remove(id)
+    }
+
+    pub fn block_interrupts(&mut self, blocked: bool) {
+        // While blocked the board's interrupts are never requested, the frame timing carries on the same
+        //  For seeing what code does when an interrupt doesn't come
+        self.interrupts_blocked = blocked;
+    }
+
+    pub fn interrupts_blocked(&self) -> bool {
+        self.interrupts_blocked
+    }
+
+    pub fn raise_interrupt(&mut self, rst: u8) -> bool {
+        // Requests RST rst straight away as if the board had, returns whether the cpu took it
+        //  The cpu still ignores it while interrupts are disabled, blocking only stops the board's own
+        let requested: u64 = self.total_cycles;
+        self.interrupt(Interrupts::op_code(rst), requested)
+    }
+
+    pub fn run_frame(&mut self) -> Result<(), EmulatorError> {
+        // Runs the machine until the end of the current frame
+        // Interrupts twice per frame; Once in the middle, and once at the end
+        //  A paused machine stays on the frame it was on, the debugger can still step it
+        if self.hardware.paused() {
+            return Ok(());
+        }
+        while !self.step_frame()? {}
+        Ok(())
+    }
+
+    pub fn run_half_frame(&mut self) -> Result<HalfFrame, EmulatorError> {
+        // Runs the machine up to and including the next interrupt, for looking at the screen while it's half drawn
+        //  Two calls from the start of a frame run the same instructions as one run_frame
+
+        let rst: u8 = self.next_interrupt();
+        let mid_screen_done: bool = self.mid_screen_done;
+        let start: u64 = self.total_cycles;
+        let end_of_frame: bool = loop {
+            if self.step_frame()? {
+                break true;
+            }
+            if self.mid_screen_done != mid_screen_done {
+                break false;
+            }
+        };
+        Ok(HalfFrame {
+            rst,
+            serviced: self.last_events().contains(&Event::Interrupt(rst)),
+            cycles: self.total_cycles - start,
+            end_of_frame,
+        })
+    }
+
+    pub fn run_until<F: FnMut(&Machine) -> bool>(&mut self, cycle_limit: u64, mut condition: F) -> Result<Stop, EmulatorError> {
+        // Runs the machine with interrupts until the condition is true or cycle_limit cycles have run
+        //  The condition is checked before every instruction so it can stop on a pc address
+        //  e.g. machine.run_until(1_000_000, |machine| machine.cpu.pc.address == 0x0005)
+
+        let start: u64 = self.total_cycles;
+        loop {
+            if condition(self) {
+                return Ok(Stop::Condition);
+            }
+            if self.total_cycles - start >= cycle_limit {
+                return Ok(Stop::CycleLimit);
+            }
+            self.step_frame()?;
+        }
+    }
+
+    fn interrupt(&mut self, op_code: u8, requested: u64) -> bool {
+        // requested is the cycle the interrupt was due, it is serviced once the instruction running then finishes
+
+        let from: u16 = self.cpu.pc.address;
+        let serviced: bool = cpu::generate_interrupt(op_code, &mut self.cpu);
+        if let Some(bandwidth) = self.bandwidth.as_mut().filter(|_| serviced) {
+            let sp: u16 = self.cpu.registers().sp;
+            bandwidth.written(&[sp, sp.wrapping_add(1)]);
+        }
+        // The return address pushed onto the stack
+        if serviced {
+            if let Some(idle) = self.idle.as_mut() {
+                idle.interrupted();
+            }
+            self.events.push(Event::Interrupt((op_code >> 3) & 0b111));
+            // RST n is encoded as 11nnn111
+            if let Some(timeline) = self.timeline.as_mut() {
+                timeline.record(self.total_cycles, Kind::Interrupt, format!("RST {}", (op_code >> 3) & 0b111));
+            }
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.interrupt((op_code >> 3) & 0b111, from, &self.cpu, self.total_cycles);
+            }
+            self.latency.serviced(requested, self.total_cycles, self.profile.interrupts.cycles_per_frame / 2);
+        } else {
+            self.latency.dropped_request();
+        }
+        serviced
+    }
+}
+impl Default for Machine {
+    fn default() -> Self {
+        Self::init()
+    }
+}
+
+pub fn step(cpu: &mut Cpu, hardware: &mut Hardware) -> Result<u64, EmulatorError> {
+    // Executes a single instruction and returns the number of cycles it took
+
+    let op_code: u8 = cpu.memory.read_at(cpu.pc.address);
+    let op_code_location: u16 = cpu.pc.address;
+    cpu.pc.address = cpu.pc.address.wrapping_add(1);
+    // Important to remember pc address is incremented before op code is handled
+    //  when handling operations that read additional bytes, the first byte to be read will be
+    //  at the pc address NOT pc address + 1
+
+    let mut cycles: u8 = cpu::dispatcher::CLOCK_CYCLES[op_code as usize];
+    let conditional_sp: Option<u16> = cpu::dispatcher::is_conditional_call_or_return(op_code).then(|| cpu.registers().sp);
+
+    let result: Result<u16, ErrorKind> = match op_code {
+        0xdb | 0xd3 => { // IN & OUT
+            // IO is handled by the hardware module not the cpu
+            // For IN operations handle_io returns the value read from the port
+            let port_byte: u8 = cpu.memory.read_at(cpu.pc.address);
+            hardware::handle_io(op_code, hardware, port_byte, cpu.a.value).map(|value| {
+                if let Some(value) = value {
+                    cpu.a.value = value;
+                }
+                1
+            })
+            // IN & OUT always read one additional byte
+        },
+        _ => cpu::dispatcher::handle_op_code(op_code, cpu).map_err(ErrorKind::Cpu),
+    };
+
+    let kind: ErrorKind = match result {
+        Ok(255) => {
+            cpu.set_halted(true);
+            return Ok(cycles as u64);
+        },
+        // Only halt should return 255, the pc is left on the next instruction for the interrupt to push
+        Ok(additional_bytes) => {
+            if conditional_sp == Some(cpu.registers().sp) {
+                cycles -= cpu::dispatcher::NOT_TAKEN;
+            }
+            // Only a taken call or return moves the stack pointer
+            if cfg!(debug_assertions) && !cpu::dispatcher::is_branch(op_code) {
+                if let Err(e) = cpu::dispatcher::check_length(op_code, additional_bytes) {
+                    panic!("{} at 0x{:04x}", e, op_code_location);
+                }
+            }
+            // Debug builds catch a wrong pc advance where it happens instead of wherever it leads
+            //  Decided by the op code, a branch to the very next byte leaves the pc where a plain instruction would
+            cpu.pc.address = cpu.pc.address.wrapping_add(additional_bytes);
+            return Ok(cycles as u64);
+        },
+        Err(kind) => kind,
+    };
+
+    cpu.pc.address = op_code_location;
+    // Leave pc on the instruction that failed so it can be looked at
+    Err(EmulatorError { address: op_code_location, op_code, kind })
+}
@@ -0,0 +1,382 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::banking::BankConfig;
+#[cfg(test)]
+use crate::cpu::Registers;
+#[cfg(test)]
+use crate::debugger::{Break, Breakpoint, Debugger};
+#[cfg(test)]
+use crate::hardware::sound::{self, Sound, SoundEvent, SoundLog};
+#[cfg(test)]
+use crate::profile::{Region, StackConfig};
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_run_until_condition() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_A);
+
+    assert_eq!(machine.run_until(1_000_000, |machine| machine.cpu.a.value == 10), Ok(Stop::Condition));
+    assert_eq!(machine.cpu.pc.address, 0x0001);
+    assert_eq!(machine.cycles(), 10 * (5 + 10) - 10);
+    // Stopped straight after the tenth INR, before its JMP
+
+    assert_eq!(machine.run_until(1_000_000, |machine| machine.cpu.pc.address == 0x0001), Ok(Stop::Condition));
+    assert_eq!(machine.cpu.a.value, 10);
+    // Already true so nothing runs
+}
+
+#[test]
+fn test_run_until_limit() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_A);
+
+    assert_eq!(machine.run_until(CYCLES_PER_FRAME * 2, |_| false), Ok(Stop::CycleLimit));
+    assert!(machine.cycles() >= CYCLES_PER_FRAME * 2);
+    assert!(machine.cycles() < CYCLES_PER_FRAME * 2 + 10);
+}
+
+#[test]
+fn test_run_until_error() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x00,       // 0x0000 NOP
+        0xd3, 0x07, // 0x0001 OUT 7
+    ]).unwrap();
+
+    let error: EmulatorError = machine.run_until(1_000, |_| false).unwrap_err();
+    assert_eq!(error.address, 0x0001);
+    assert_eq!(error.kind, ErrorKind::UnconnectedWritePort(0x07));
+}
+
+#[test]
+fn test_ram_mirror() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0x3e, 0x42,       // 0x0000 MVI A,0x42
+        0x32, 0x00, 0x60, // 0x0002 STA 0x6000
+        0x3a, 0x00, 0x20, // 0x0005 LDA 0x2000
+        0x47,             // 0x0008 MOV B,A
+        0x3e, 0x99,       // 0x0009 MVI A,0x99
+        0x32, 0x01, 0x40, // 0x000b STA 0x4001
+        0xaf,             // 0x000e XRA A
+        0x3a, 0x01, 0xa0, // 0x000f LDA 0xa001
+    ]).unwrap();
+
+    for _ in 0..8 {
+        machine.step().unwrap();
+    }
+    assert_eq!(machine.cpu.memory.read_at(0x2000), 0x42);
+    assert_eq!(machine.cpu.registers().b, 0x42);
+    assert_eq!(machine.cpu.a.value, 0x99);
+    // Ram written and read back through different mirrors
+
+    machine.reset().unwrap();
+    assert_eq!(machine.cpu.memory.mirror(), machine.profile().mirror);
+}
+
+#[test]
+fn test_banked_memory() {
+    let mut profile: Profile = Profile::space_invaders();
+    profile.mirror = None;
+    profile.banks = vec![
+        BankConfig { start: 0x0100, size: 0x04, count: 2, port: 0x10, rom_offset: Some(0x0104) },
+        BankConfig { start: 0x2100, size: 0x10, count: 2, port: 0x11, rom_offset: None },
+    ];
+    let mut machine: Machine = Machine::with_profile(profile).unwrap();
+
+    let mut rom: Vec<u8> = vec![
+        0x3e, 0x01,       // 0x0000 MVI A,1
+        0x32, 0x00, 0x21, // 0x0002 STA 0x2100
+        0xd3, 0x11,       // 0x0005 OUT 0x11
+        0xd3, 0x10,       // 0x0007 OUT 0x10
+        0x3a, 0x00, 0x21, // 0x0009 LDA 0x2100
+        0x47,             // 0x000c MOV B,A
+        0x3a, 0x00, 0x01, // 0x000d LDA 0x0100
+    ];
+    rom.resize(0x0104, 0x00);
+    rom.extend([0xaa, 0, 0, 0, 0xbb, 0, 0, 0]);
+    machine.load_rom(&rom).unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x0100), 0xaa);
+
+    for _ in 0..7 {
+        machine.step().unwrap();
+    }
+    assert_eq!(machine.cpu.registers().b, 0x00);
+    assert_eq!(machine.cpu.a.value, 0xbb);
+    assert_eq!(machine.banks().iter().map(|banks| banks.selected()).collect::<Vec<u8>>(), vec![1, 1]);
+    // The ram written before switching belongs to bank 0
+
+    machine.reset().unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x0100), 0xaa);
+    assert_eq!(machine.banks()[1].selected(), 0);
+}
+
+#[test]
+fn test_bank_port_taken() {
+    // Two bank selects can't share a port, the machine isn't built instead of panicking
+    let mut profile: Profile = Profile::space_invaders();
+    profile.banks = vec![
+        BankConfig { start: 0x2100, size: 0x10, count: 2, port: 0x11, rom_offset: None },
+        BankConfig { start: 0x2200, size: 0x10, count: 2, port: 0x11, rom_offset: None },
+    ];
+    assert!(Machine::with_profile(profile).is_err_and(|e| e.starts_with("Bank select on port 17")));
+}
+
+#[test]
+fn test_instruction_lengths() {
+    assert!(cpu::dispatcher::check_length(0x06, 1).is_ok());
+    assert!(cpu::dispatcher::check_length(0xcd, 2).is_ok());
+    assert!(cpu::dispatcher::check_length(0x06, 0).is_err_and(|e| e.contains("0x06")));
+
+    for op_code in (0x00..=0xff).filter(|op_code| ![0x76, 0xd3, 0xdb].contains(op_code)) {
+        let mut machine: Machine = Machine::init();
+        machine.load_rom(&[op_code, 0x00, 0x00]).unwrap();
+        let _ = machine.step();
+        // Every op code that doesn't jump is checked against the table as it runs
+    }
+}
+
+#[test]
+fn test_branch_to_next_byte() {
+    // JMP $+1, CALL $+1 and RST 1 at 0x0007 all land on the byte after their op code
+    //  which is where a plain instruction leaves the pc, debug builds used to panic on them
+    let rst: Vec<u8> = [0x00; 7].into_iter().chain([0xcf]).collect();
+    for (rom, start) in [(vec![0xc3, 0x01, 0x00], 0x0000), (vec![0xcd, 0x01, 0x00], 0x0000), (rst, 0x0007)] {
+        let mut machine: Machine = Machine::init();
+        machine.load_rom(&rom).unwrap();
+        machine.cpu.pc.address = start;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.pc.address, start + 1);
+    }
+}
+
+#[test]
+fn test_power_cycle() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_A);
+    machine.cpu.memory.write_at(0x2000, 0xaa);
+    machine.run_frame().unwrap();
+
+    let mut debugger: Debugger = Debugger::new();
+    debugger.add_breakpoint(Breakpoint::Address(0x0001));
+    machine.power_cycle(&[
+        0x04,             // 0x0000 INR B
+        0xc3, 0x00, 0x00, // 0x0001 JMP 0x0000
+    ]).unwrap();
+    debugger.clear_history();
+    assert_eq!((machine.cycles(), machine.cpu.memory.read_at(0x2000), machine.cpu.a.value), (0, 0x00, 0x00));
+
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0001)));
+    assert_eq!((machine.cpu.registers().a, machine.cpu.registers().b), (0x00, 0x01));
+    // The new rom runs from the start and the breakpoint survived
+}
+
+#[test]
+fn test_soft_reset() {
+    let rom: [u8; 12] = [
+        0xf3,             // 0x0000 DI
+        0x3e, 0xab,       // 0x0001 MVI A,0xab
+        0xd3, 0x04,       // 0x0003 OUT 4
+        0x3e, 0x01,       // 0x0005 MVI A,1
+        0xd3, 0x03,       // 0x0007 OUT 3
+        0xc3, 0x09, 0x00, // 0x0009 JMP 0x0009
+    ];
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    for _ in 0..5 {
+        machine.step().unwrap();
+    }
+    let mut log: SoundLog = SoundLog::new();
+    sound::update_audio(&mut machine.hardware, &mut log);
+    assert_eq!(log.events, vec![SoundEvent::Play(Sound::Ufo)]);
+
+    machine.reset().unwrap();
+    let mut log: SoundLog = SoundLog::new();
+    sound::update_audio(&mut machine.hardware, &mut log);
+    assert_eq!(log.events, vec![SoundEvent::Stop(Sound::Ufo)]);
+    assert_eq!(machine.hardware.shift_state(), (0xab00, 0x00));
+    // The reset line stops the ufo but the shift register isn't wired to it
+
+    machine.power_cycle(&rom).unwrap();
+    assert_eq!(machine.hardware.shift_state(), (0x0000, 0x00));
+}
+
+#[test]
+fn test_top_of_memory_wraps() {
+    // Operands, the pc and the stack carry on from 0x0000 after 0xffff like the real cpu
+
+    let mut cpu: Cpu = Cpu::init();
+    let mut hardware: Hardware = Hardware::init();
+    let mut registers: Registers = cpu.registers();
+
+    cpu.memory.poke(0xffff, 0x00);
+    cpu.pc.address = 0xffff;
+    step(&mut cpu, &mut hardware).unwrap();
+    assert_eq!(cpu.pc.address, 0x0000);
+    // NOP
+
+    cpu.memory.poke(0xfffe, 0x01);
+    cpu.memory.poke(0xffff, 0x34);
+    cpu.memory.poke(0x0000, 0x12);
+    cpu.pc.address = 0xfffe;
+    step(&mut cpu, &mut hardware).unwrap();
+    assert_eq!((cpu.registers().b, cpu.registers().c, cpu.pc.address), (0x12, 0x34, 0x0001));
+    // LXI B with its operand split across the top of memory
+
+    cpu.memory.poke(0xfffd, 0xcd);
+    cpu.memory.poke(0xfffe, 0x00);
+    cpu.memory.poke(0xffff, 0x10);
+    registers.pc = 0xfffd;
+    registers.sp = 0x0001;
+    cpu.set_registers(registers);
+    step(&mut cpu, &mut hardware).unwrap();
+    assert_eq!((cpu.pc.address, cpu.registers().sp), (0x1000, 0xffff));
+    assert_eq!((cpu.memory.read_at(0x0000), cpu.memory.read_at(0xffff)), (0x00, 0x00));
+    // CALL returns to 0x0000 and pushes across the bottom of memory
+
+    cpu.memory.poke(0x1000, 0xc9);
+    step(&mut cpu, &mut hardware).unwrap();
+    assert_eq!((cpu.pc.address, cpu.registers().sp), (0x0000, 0x0001));
+    // RET pops it back
+}
+
+#[test]
+fn test_halt() {
+    // HLT waits for the next interrupt then carries on after the handler returns
+
+    let mut rom: Vec<u8> = vec![0; 0x14];
+    rom[0x00..0x05].copy_from_slice(&[0xfb, 0x76, 0xc3, 0x01, 0x00]);
+    // EI, HLT, JMP 0x0001
+    rom[0x08..0x0b].copy_from_slice(&[0x04, 0xfb, 0xc9]);
+    rom[0x10..0x13].copy_from_slice(&[0x0c, 0xfb, 0xc9]);
+    // INR B, EI, RET for RST 1 and INR C, EI, RET for RST 2
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+
+    machine.step_frame().unwrap();
+    machine.step_frame().unwrap();
+    assert!(machine.cpu.halted());
+    assert_eq!(machine.cpu.pc.address, 0x0002);
+    assert_eq!(machine.cycles_to_interrupt(), CYCLES_PER_FRAME / 2 - 11);
+    // EI takes 4 cycles and HLT 7
+    assert!(crate::halt_status(&machine, Language::English).is_some_and(|status| status.contains("16489 cycles")));
+
+    machine.step_frame().unwrap();
+    assert!(!machine.cpu.halted());
+    assert_eq!((machine.cycles(), machine.cpu.pc.address), (CYCLES_PER_FRAME / 2, 0x0008));
+    // The clock jumps straight to the interrupt
+    machine.run_frame().unwrap();
+    assert_eq!((machine.cpu.registers().b, machine.cpu.registers().c), (1, 0));
+    assert_eq!(machine.cycles(), CYCLES_PER_FRAME);
+    machine.run_frame().unwrap();
+    assert_eq!((machine.cpu.registers().b, machine.cpu.registers().c), (2, 1));
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xf3, 0x76]).unwrap();
+    machine.run_frame().unwrap();
+    assert!(machine.cpu.halted());
+    // With interrupts disabled nothing ever wakes it, frames still go by
+    assert!(crate::halt_status(&machine, Language::English).is_some_and(|status| status.contains("disabled")));
+    assert!(machine.save_state().halted);
+}
+
+#[test]
+fn test_run_half_frame() {
+    let mut rom: Vec<u8> = vec![0; 0x20];
+    rom[0x00..0x03].copy_from_slice(&[0xc3, 0x18, 0x00]); // 0x0000 JMP 0x0018
+    rom[0x08..0x0a].copy_from_slice(&[0xfb, 0xc9]);       // 0x0008 EI, RET
+    rom[0x10..0x12].copy_from_slice(&[0xfb, 0xc9]);       // 0x0010 EI, RET
+    rom[0x18..0x1f].copy_from_slice(&[
+        0x31, 0x00, 0x24, // 0x0018 LXI SP,0x2400
+        0xfb,             // 0x001b EI
+        0xc3, 0x1c, 0x00, // 0x001c JMP 0x001c
+    ]);
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    let mut whole: Machine = machine.clone();
+
+    let first: HalfFrame = machine.run_half_frame().unwrap();
+    assert_eq!((first.rst, first.serviced, first.end_of_frame), (1, true, false));
+    assert!(first.cycles >= CYCLES_PER_FRAME / 2 && first.cycles < CYCLES_PER_FRAME / 2 + 10);
+    assert_eq!(machine.cpu.pc.address, 0x0008);
+    // Stopped on the interrupt's handler before it runs
+
+    let second: HalfFrame = machine.run_half_frame().unwrap();
+    assert_eq!((second.rst, second.serviced, second.end_of_frame), (2, true, true));
+    whole.run_frame().unwrap();
+    assert_eq!(machine.cycles(), first.cycles + second.cycles);
+    assert_eq!(machine.cycles(), whole.cycles());
+    assert_eq!(machine.cpu.registers(), whole.cpu.registers());
+    // The same as running the frame in one go
+
+    machine.block_interrupts(true);
+    let blocked: HalfFrame = machine.run_half_frame().unwrap();
+    assert_eq!((blocked.rst, blocked.serviced, blocked.end_of_frame), (1, false, false));
+}
+
+#[test]
+fn test_paused_frame() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_A);
+    machine.hardware.set_paused(true);
+    machine.run_frame().unwrap();
+    assert_eq!(machine.cycles(), 0);
+    machine.hardware.set_paused(false);
+    machine.run_frame().unwrap();
+    assert!(machine.cycles() > 0);
+}
+
+#[test]
+fn test_load_rom_space() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0x3c; 0x10]).unwrap();
+
+    let error: LoadError = machine.load_rom(&[0x00; 0x2800]).unwrap_err();
+    assert_eq!(error, LoadError { size: 0x2800, offset: 0, region: (0x0000, 0x2000) });
+    assert_eq!(error.overflow(), 0x800);
+    assert_eq!(machine.rom(), &[0x3c; 0x10]);
+    assert_eq!(machine.cpu.memory.read_at(0x0000), 0x3c);
+    // The rom that was already there stays
+
+    machine.power_cycle(&[0x00; 0x2800]).unwrap_err();
+    assert_eq!(machine.cpu.memory.read_at(0x0000), 0x3c);
+
+    let mut profile: Profile = Profile::space_invaders();
+    profile.memory.retain(|region| region.kind != RegionKind::Ram);
+    profile.memory.push(Region { start: 0x2000, end: 0x2400, kind: RegionKind::Rom });
+    assert_eq!(profile.rom_space(), 0x0000..0x2400);
+    let mut machine: Machine = Machine::with_profile(profile).unwrap();
+    machine.load_rom(&[0x00; 0x2400]).unwrap();
+    // A profile can make room for a bigger rom
+}
+
+#[test]
+fn test_stack_guard() {
+    let rom: [u8; 13] = [
+        0x31, 0xf0, 0x1f, // 0x0000 LXI SP,0x1ff0
+        0xc5,             // 0x0003 PUSH B
+        0x31, 0x00, 0x24, // 0x0004 LXI SP,0x2400
+        0x31, 0x00, 0x10, // 0x0007 LXI SP,0x1000
+        0xc3, 0x0a, 0x00, // 0x000a JMP 0x000a
+    ];
+    let machine_with = |guard: StackGuard| {
+        let mut profile: Profile = Profile::space_invaders();
+        profile.stack = profile.stack.map(|stack| StackConfig { guard, ..stack });
+        let mut machine: Machine = Machine::with_profile(profile).unwrap();
+        machine.load_rom(&rom).unwrap();
+        machine
+    };
+
+    let mut machine: Machine = machine_with(StackGuard::Warn);
+    assert_eq!(machine.run_until(1_000, |machine| machine.cpu.pc.address == 0x000a), Ok(Stop::Condition));
+    assert_eq!(machine.events(), &[Event::StackLeft(0x1ff0), Event::StackLeft(0x1000)]);
+    // Pushing while already outside isn't noted again, going back in and out again is
+
+    let mut machine: Machine = machine_with(StackGuard::Trap);
+    let error: EmulatorError = machine.run_until(1_000, |_| false).unwrap_err();
+    assert_eq!((error.address, error.kind), (0x0000, ErrorKind::StackOutOfBounds(0x1ff0)));
+    assert_eq!(machine.cpu.pc.address, 0x0003);
+
+    let mut machine: Machine = machine_with(StackGuard::Off);
+    machine.run_until(1_000, |machine| machine.cpu.pc.address == 0x000a).unwrap();
+    assert!(machine.events().is_empty());
+}
@@ -1,61 +1,1289 @@
 use std::env;
+use std::fmt::Display;
 use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use emulator::cpu;
-use emulator::cpu::Cpu;
-use emulator::hardware::Hardware;
+use emulator::audio::{AudioBackend, Mixer};
+use emulator::audio::samples::SamplePack;
+use emulator::audio::synth::Synth;
+use emulator::audio::device::Device;
+use emulator::assembler;
+use emulator::cpu::delta;
+use emulator::crash;
+use emulator::debugger::hex::Move;
+use emulator::debugger::session;
+use emulator::display::Geometry;
+use emulator::hardware::{input, sound};
+use emulator::hardware::sound::SoundLog;
+use emulator::hardware::console::DebugConsole;
+use emulator::info::RomInfo;
+use emulator::input_lag::InputLag;
+use emulator::launcher;
+use emulator::monitor::Monitor;
+use emulator::harness::{self, Outcome};
+use emulator::headless::{self, StateDump};
+use emulator::prelude::*;
+use emulator::Hud;
+use emulator::replay::{FrameHash, Replay, ReplayFrame};
+use emulator::report;
+use emulator::rng;
+use emulator::savestate::{self, SaveState};
+use emulator::scrubber::Scrubber;
+use emulator::selftest::{self, Check};
+use emulator::soak::{self, Soak};
+use emulator::state_panel;
+use emulator::stats::{self, Stats};
+use emulator::storage::{self, DirectoryStorage, Storage};
+use emulator::timeline::Kind;
+use emulator::profile::{Clock, DeviceConfig, Overlay, RomOverride};
+use emulator::profiles;
+use emulator::palette::{Palette, Preset};
+use emulator::postprocess::{Chain, Effect};
+use emulator::kiosk::{self, Kiosk, WithKiosk};
+use emulator::paddle::{Paddle, WithPaddle};
+use emulator::touch::{VirtualButtons, WithButtons};
+use emulator::tracer::{Level, Tracer};
+use emulator::video;
+use emulator::watcher::FileWatcher;
+use emulator::window::{Layout, WindowMode};
+use disassembler::symbols::SymbolTable;
+use disassembler::syntax::Syntax;
+use raylib::core::audio::{AudioStream, RaylibAudio};
+use raylib::core::window;
+use raylib::prelude::{Color, KeyboardKey};
+
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+const RELOAD_CHECK_FRAMES: u32 = 30;
+// How often the profile is checked for changes
+const HEX_MOVES: [(KeyboardKey, Move); 6] = [
+    (KeyboardKey::KEY_LEFT, Move::Left),
+    (KeyboardKey::KEY_RIGHT, Move::Right),
+    (KeyboardKey::KEY_UP, Move::Up),
+    (KeyboardKey::KEY_DOWN, Move::Down),
+    (KeyboardKey::KEY_PAGE_UP, Move::PageUp),
+    (KeyboardKey::KEY_PAGE_DOWN, Move::PageDown),
+];
 
 fn main() -> Result<(), u8> {
-    let (mut raylib_handle, thread) = raylib::init()
-        .size(emulator::WIDTH, emulator::HEIGHT)
-        .title("Space Invaders")
-        .build();
-    raylib_handle.set_target_fps(60);
+    let mut args: Vec<String> = env::args().collect();
+
+    let command: Command = Command::of(&args);
+    if command == Command::RenderReplay {
+        if args.len() < 4 {
+            usage();
+            return Err(1);
+        }
+        let (rom, replay): (String, String) = (args[2].clone(), args[3].clone());
+        args = [&args[..2], &[String::from("--replay"), replay], &args[4..], &[rom]].concat();
+    }
+    // render-replay <rom> <replay> is run as --replay <replay> <rom> so the profile, seed and rom check are the same
+
+    match command {
+        Command::Selftest => selftest(&args),
+        Command::Assemble => args.windows(3)
+            .find(|window| window[0] == "--assemble")
+            .map_or(Err(1), |paths| assemble(Path::new(&paths[1]), Path::new(&paths[2]))),
+        Command::ValidateProfile => Timing::parse(&args).map_err(fail).and_then(|timing| validate_profile(value(&args, "--profile").map(Path::new), timing)),
+        Command::ListMachines => {
+            list_machines(&storage(&args, &args[args.len() - 1]));
+            Ok(())
+        },
+        Command::DiffStates => diff_states(&args),
+        Command::RomHash => rom_hash(&args),
+        Command::Info => info(&args),
+        Command::Test => run_test(&args),
+        Command::RenderReplay => render_replay(&args),
+        Command::Headless => headless(&args),
+        Command::Window => window(args),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    // What the command line asks for, worked out before any rom is read so each one only touches what it needs
+    Selftest,
+    // Checks the cpu, shift register, screen decoding and audio device against known answers, no rom needed
+    Assemble,
+    // Builds a rom from 8080 assembly, like the text console demo
+    ValidateProfile,
+    // Checks a profile without running anything, the built in one when none is given
+    ListMachines,
+    // What the built in profiles and any in the config folder describe, no rom needed
+    DiffStates,
+    // Compares two save states, no rom needed
+    RomHash,
+    // For picking out the rom in a profile's [[roms]] settings
+    Info,
+    // What the rom is and what it starts by doing, without opening a window
+    Test,
+    // Runs a rom written for the test harness and exits with its status
+    RenderReplay,
+    Headless,
+    Window,
+}
+impl Command {
+    fn of(args: &[String]) -> Self {
+        match args.get(1).map(String::as_str) {
+            Some("selftest") => return Command::Selftest,
+            Some("render-replay") => return Command::RenderReplay,
+            Some("info") => return Command::Info,
+            _ => (),
+        }
+        if args.windows(3).any(|window| window[0] == "--assemble") {
+            return Command::Assemble;
+        }
+        if args.iter().any(|arg| arg == "--validate-profile") {
+            return Command::ValidateProfile;
+        }
+        if args.iter().any(|arg| arg == "--list-machines") {
+            return Command::ListMachines;
+        }
+        if args.windows(3).any(|window| window[0] == "--diff-states") {
+            return Command::DiffStates;
+        }
+        match (flag(args, "--rom-hash"), flag(args, "--test"), flag(args, "--headless")) {
+            (true, _, _) => Command::RomHash,
+            (_, true, _) => Command::Test,
+            (_, _, true) => Command::Headless,
+            _ => Command::Window,
+        }
+    }
+}
+
+fn fail(message: impl Display) -> u8 {
+    // Prints why a command can't go on and gives the exit code for main to return
+    println!("{}", message);
+    1
+}
 
-    let mut cpu: Cpu = Cpu::init();
-    let mut hardware: Hardware = Hardware::init();
-    // Initialize Cpu
+fn flag(args: &[String], name: &str) -> bool {
+    // Flags come before the rom path
+    args[..args.len() - 1].iter().any(|arg| arg == name)
+}
 
-    let args: Vec<String> = env::args().collect();
+fn value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    // The argument after a flag, e.g. the file in --profile <file>
+    args.windows(2).find(|pair| pair[0] == name).map(|pair| &pair[1])
+}
 
-    if args.len() < 2 {
-        println!("Please provide a rom to emulate");
+fn storage(args: &[String], file_path: &str) -> DirectoryStorage {
+    // Save states, stats and profiles go in the user's data and config folders, or next to the rom without a home folder
+    match value(args, "--data-dir") {
+        Some(dir) => DirectoryStorage::new(Path::new(dir)),
+        None => DirectoryStorage::platform().unwrap_or_else(|| DirectoryStorage::new(Path::new(file_path).parent().unwrap_or(Path::new(".")))),
     }
+}
+
+fn rom_name(file_path: &str) -> &str {
+    Path::new(file_path).file_name().and_then(|name| name.to_str()).unwrap_or("rom")
+}
+
+fn load_profile(args: &[String], saves: &DirectoryStorage, file_path: &str) -> Result<(Profile, String), String> {
+    // Without --profile a rom uses <rom>.toml from the config folder when there is one
+    //  Returns the profile and where it came from
+
+    let rom_name: &str = rom_name(file_path);
+    let (mut profile, profile_source): (Profile, String) = match value(args, "--profile") {
+        Some(path) => (Profile::load(Path::new(path))?, path.clone()),
+        None => match Profile::read(saves, &format!("{}.toml", rom_name))? {
+            Some(profile) => (profile, saves.describe(storage::Kind::Config, &format!("{}.toml", rom_name))),
+            None => (Profile::space_invaders(), String::from("built in")),
+        },
+    };
+    Timing::parse(args)?.apply(&mut profile);
+    Ok((profile, profile_source))
+}
+
+fn read_rom(file_path: &str) -> Result<Vec<u8>, u8> {
+    fs::read(file_path).map_err(|e| {
+        println!("{}: {}", file_path, e);
+        1
+    })
+}
+
+struct Loaded {
+    // A machine with the rom in it and everything the command line asked to be attached
+    machine: Machine,
+    rom: Vec<u8>,
+    bindings: Vec<input::Binding>,
+    playback: Option<Replay>,
+    seed: u64,
+    profile_source: String,
+}
+
+fn load(args: &[String]) -> Result<Loaded, u8> {
+    // Reads the profile and rom and builds the machine every command that runs the rom starts from
+
+    let file_path: &str = &args[args.len() - 1];
+    let (profile, profile_source): (Profile, String) = load_profile(args, &storage(args, file_path), file_path).map_err(fail)?;
+    let rom: Vec<u8> = read_rom(file_path)?;
+
+    let rom_override: Option<RomOverride> = profile.rom_override(&rom).cloned();
+    let profile: Profile = profile.for_rom(&rom);
+    let bindings: Vec<input::Binding> = profile.bindings().map_err(fail)?;
 
-    let file_path: &str = &args[1];
-    let rom: Vec<u8> = match fs::read(file_path) {
-        Ok(result) => result,
-        Err(e) => panic!("{}", e),
+    let playback: Option<Replay> = value(args, "--replay").map(|path| Replay::load(Path::new(path))).transpose().map_err(fail)?;
+    if let Some(Err(e)) = playback.as_ref().map(|replay| replay.check_rom(&rom)) {
+        println!("{}", e);
+        return Err(1);
+    }
+    let seed: u64 = match &playback {
+        Some(replay) => replay.seed.unwrap_or(rng::DEFAULT_SEED),
+        None if flag(args, "--headless") || flag(args, "--test") => rng::DEFAULT_SEED,
+        None => rng::host_seed(),
     };
-    cpu.memory.load_rom(&rom, 0);
+    // The only randomness from the host, a replay brings back the seed it was recorded with
+
+    let mut machine: Machine = Machine::with_profile(profile).map_err(fail)?;
+    // Initialize Cpu and Hardware wired up for the game
+    machine.seed(seed);
+    if let Err(e) = machine.load_rom(&rom) {
+        println!("{}", e);
+        return Err(1);
+    }
     // Loads Rom into memory
+    machine.set_timeline(value(args, "--timeline").is_some());
+    // Interrupts, sounds, coins and state loads with the cycle they happened on, saved on exit
+    machine.set_audit(flag(args, "--audit"));
+    // Every instruction checked against the op code table and the disassembler, mismatches printed as they're found
+    if let Some(path) = value(args, "--trace") {
+        let trace_level: Level = value(args, "--trace-level")
+            .map_or(Ok(Level::Frame), |level| Level::parse(level))
+            .map_err(fail)?;
+        let syntax: Syntax = value(args, "--syntax")
+            .map_or(Ok(Syntax::Intel), |syntax| Syntax::parse(syntax))
+            .map_err(fail)?;
+        let trace_every: u64 = value(args, "--trace-every")
+            .map_or(Ok(1), |every| every.parse().map_err(|_| format!("{} is not a number of frames", every)))
+            .map_err(fail)?;
+        match Tracer::create(trace_level, trace_every, Path::new(path)) {
+            Ok(mut tracer) => {
+                tracer.set_syntax(syntax);
+                machine.set_tracer(Some(tracer));
+            },
+            Err(e) => {
+                println!("Can't trace: {}", e);
+                return Err(1);
+            },
+        }
+    }
+    // Written as the machine runs, a line per frame unless --trace-level asks for more
+
+    if let Some(rom_override) = rom_override {
+        println!("Using the settings for {}", rom_override.name.as_deref().unwrap_or(&rom_override.hash));
+        let loaded: Option<Result<(), String>> = rom_override.save_state
+            .map(|path| SaveState::load(Path::new(&path)).and_then(|state| machine.load_state(&state)));
+        if let Some(Err(e)) = loaded {
+            println!("Not loading the rom's save state: {}", e);
+        }
+    }
+    // Settings the profile gives for this particular rom
 
-    // for i in 0x03be..0x03c1 {
-    //     println!("0x{:04x}: 0x{:02x}", i, cpu.memory.read_at(i));
-    // }
+    if let Some(port) = value(args, "--console") {
+        let number: Option<u8> = match port.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok(),
+            None => port.parse().ok(),
+        };
+        let attached: Result<(), String> = match number {
+            Some(number) => DebugConsole::new().read_stdin().attach(&mut machine.hardware, number),
+            None => Err(format!("{} is not a port number", port)),
+        };
+        if let Err(e) = attached {
+            println!("Can't add the debug console: {}", e);
+            return Err(1);
+        }
+    }
+    // OUT to the console port prints to this terminal
+
+    Ok(Loaded { machine, rom, bindings, playback, seed, profile_source })
+}
+
+fn selftest(args: &[String]) -> Result<(), u8> {
+    let checks: Vec<Check> = selftest::run(!args.iter().any(|arg| arg == "--no-audio"));
+    selftest::report(&checks).iter().for_each(|line| println!("{}", line));
+    match checks.iter().all(Check::passed) {
+        true => Ok(()),
+        false => Err(1),
+    }
+}
+
+fn rom_hash(args: &[String]) -> Result<(), u8> {
+    let rom: Vec<u8> = read_rom(&args[args.len() - 1])?;
+    println!("{}", headless::rom_hash(&rom));
+    Ok(())
+}
+
+fn info(args: &[String]) -> Result<(), u8> {
+    let file_path: &str = &args[args.len() - 1];
+    let (profile, profile_source): (Profile, String) = load_profile(args, &storage(args, file_path), file_path).map_err(fail)?;
+    let rom: Vec<u8> = read_rom(file_path)?;
+    println!("{}", file_path);
+    println!("Profile from: {}", profile_source);
+    RomInfo::new(&rom, &profile).lines(&profile).iter().for_each(|line| println!("{}", line));
+    Ok(())
+}
+
+fn run_test(args: &[String]) -> Result<(), u8> {
+    let mut machine: Machine = load(args)?.machine;
+    let outcome: Outcome = harness::run(&mut machine, harness::CYCLE_LIMIT).map_err(fail)?;
+    finish_audit(&mut machine);
+    println!("{}: {}", args[args.len() - 1], outcome);
+    std::process::exit(outcome.exit_code() as i32);
+}
+
+fn headless(args: &[String]) -> Result<(), u8> {
+    let Loaded { mut machine, playback, .. } = load(args)?;
+    if let Some(replay) = playback {
+        let verified: Result<(), u8> = verify_replay(&replay, &mut machine);
+        finish_audit(&mut machine);
+        return verified;
+    }
+    let ran: Result<(), u8> = run_headless(args, &mut machine);
+    save_timeline(&machine, value(args, "--timeline"));
+    finish_trace(&mut machine);
+    finish_audit(&mut machine);
+    ran
+}
+
+fn palette_preset(args: &[String]) -> Result<Preset, String> {
+    value(args, "--palette").map_or(Ok(Preset::Overlay), |preset| Preset::parse(preset))
+}
+
+fn overlay_choice(args: &[String]) -> Result<Option<usize>, String> {
+    // One of the bundled cabinet overlays in place of the profile's, F6 cycles through them
+    value(args, "--overlay").map(|name| Overlay::preset(name)
+        .map(|_| Overlay::PRESETS.iter().position(|preset| preset.eq_ignore_ascii_case(name.trim())).unwrap_or(0)))
+        .transpose()
+}
+
+fn window(mut args: Vec<String>) -> Result<(), u8> {
+    // Opens the game in a window, the usual way of running it
+
+    let roms_folder: Option<PathBuf> = match args.len() {
+        1 => Some(launcher::default_folder()),
+        _ => Some(PathBuf::from(&args[args.len() - 1])).filter(|path| path.is_dir()),
+    };
+    if let Some(folder) = roms_folder {
+        if args.len() < 2 {
+            println!("No rom given, pick one from {} or set {} to another folder", folder.display(), launcher::FOLDER_VARIABLE);
+            usage();
+        }
+        match choose_rom(&args, &folder) {
+            Ok(Some(path)) if args.len() < 2 => args.push(path.display().to_string()),
+            Ok(Some(path)) => *args.last_mut().unwrap() = path.display().to_string(),
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                println!("{}", e);
+                return Err(1);
+            },
+        }
+    }
+    // Without a rom, or given a folder in place of one, a window lists the folder's roms to pick from
+    //  The rest of the arguments are kept so flags still apply to the rom picked
+
+    let Loaded { mut machine, rom, mut bindings, playback, seed, profile_source } = load(&args)?;
+    let file_path: &str = &args[args.len() - 1];
+    let rom_name: &str = rom_name(file_path);
+    let mut saves: DirectoryStorage = storage(&args, file_path);
+    let timing: Timing = Timing::parse(&args).map_err(fail)?;
+    let coverage_path: Option<&String> = value(&args, "--coverage");
+    let profile_path: Option<&String> = value(&args, "--profile");
+    let samples_path: Option<&String> = value(&args, "--samples");
+    let audio_backend: &str = value(&args, "--audio").map_or("samples", String::as_str);
+    let touch: bool = flag(&args, "--touch");
+    let watch_rom: bool = flag(&args, "--watch");
+    let paddle_mode: bool = flag(&args, "--paddle");
+    let kiosk_mode: bool = flag(&args, "--kiosk");
+    let scrub: bool = flag(&args, "--scrub");
+    // Keeps the last few seconds so F7 can go back through them
+    let borderless: bool = flag(&args, "--borderless");
+    let monitor: Option<i32> = value(&args, "--monitor")
+        .map(|monitor| monitor.parse().map_err(|_| format!("{} is not a monitor number, the first is 0", monitor)))
+        .transpose()
+        .map_err(fail)?;
+    let window_size: Option<(i32, i32)> = value(&args, "--window-size").map(|size| emulator::window::parse_size(size)).transpose().map_err(fail)?;
+    let soak_duration: Option<Duration> = value(&args, "--soak").map(|hours| Soak::parse_hours(hours)).transpose().map_err(fail)?;
+    let record_path: Option<&String> = value(&args, "--record");
+    let timeline_path: Option<&String> = value(&args, "--timeline");
+    let palette_preset: Preset = palette_preset(&args).map_err(fail)?;
+    let mut overlay_choice: Option<usize> = overlay_choice(&args).map_err(fail)?;
+    let mut effects: Vec<Effect> = vec![Effect::Overlay];
+    if let Some(pair) = args[..args.len() - 1].windows(2).find(|pair| pair[0] == "--effects") {
+        effects.extend(Effect::parse_list(&pair[1]).map_err(fail)?);
+    }
+    if flag(&args, "--reduce-flashes") {
+        effects.push(Effect::FlashDamping);
+    }
+    // What the screen goes through on the way to the window, e.g. --effects blend,scanlines,crt:40
+    let show_frame_hash: bool = flag(&args, "--frame-hash");
+    let mut debugger: Option<Monitor> = match flag(&args, "--debug") {
+        true => {
+            let mut monitor: Monitor = value(&args, "--symbols")
+                .map_or(Ok(Monitor::new(SymbolTable::new())), |path| Monitor::with_symbol_file(Path::new(path)))
+                .map_err(fail)?;
+            load_session(&mut monitor, &machine);
+            println!("Debugging, ` or a breakpoint stops the game and commands typed here are run against it");
+            Some(monitor.read_stdin())
+        },
+        false => None,
+    };
+
+    let mut input_lag: Option<InputLag> = value(&args, "--input-lag").map(|name| {
+        let key: KeyboardKey = input::key_from_name(name).ok_or_else(|| format!("{} is not a key name", name))?;
+        match bindings.iter().find(|binding| binding.key == key) {
+            Some(binding) => Ok(InputLag::new(binding.input, binding.bit)),
+            None => Err(format!("{} isn't bound to an input", name)),
+        }
+    }).transpose().map_err(fail)?;
+    // Flashes the screen while the key is held and times how long the game takes to show it
+
+    let mut paddle: Option<Paddle> = if paddle_mode { new_paddle(machine.profile(), &bindings) } else { None };
+    // Mouse steers the player towards the cursor
+    let mut kiosk: Option<Kiosk> = if kiosk_mode { new_kiosk(machine.profile(), &bindings) } else { None };
+
+    let mut palette: Palette = Palette::for_screen(&palette_preset.apply(&chosen_overlay(machine.profile(), overlay_choice)), machine.profile().display.geometry())
+        .map_err(fail)?;
+    // Overlay colours are worked out once instead of every frame
+    let mut post: Chain = Chain::build(&effects, &palette);
+
+    let (mut raylib_handle, thread) = raylib::init()
+        .size(emulator::WIDTH, emulator::HEIGHT)
+        .title("Space Invaders")
+        .resizable()
+        .build();
+    raylib_handle.set_window_min_size(emulator::INVADERS_WIDTH, emulator::INVADERS_HEIGHT);
+    raylib_handle.set_window_title(&thread, &machine.profile().name);
+    raylib_handle.set_target_fps(0);
+    // Frames are paced by FramePacer instead of raylib which spins the cpu while waiting
+    let current: WindowMode = WindowMode { borderless: false, monitor: window::get_current_monitor(), size: window_size };
+    let mut window_mode: WindowMode = emulator::window::apply(&mut raylib_handle, current, WindowMode { borderless, monitor: monitor.unwrap_or(current.monitor), ..current });
+    // Sized to fit the monitor it opens on unless --window-size says otherwise
+    //  F11 switches borderless on and off and F10 moves to the next monitor
+    let mut layout: Layout = Layout::of(&raylib_handle).fit(machine.profile().display.geometry());
+    let mut buttons: Option<VirtualButtons> = touch.then(|| VirtualButtons::new(&bindings, layout.width, layout.height, machine.profile().language));
+    // On screen controls for touch screens
+
+    let audio: Option<RaylibAudio> = match RaylibAudio::init_audio_device() {
+        Ok(audio) => Some(audio),
+        Err(e) => {
+            println!("Running without sound: {}", e);
+            None
+        },
+    };
+    let mut device: Device = Device::new(device_config(machine.profile(), &args).map_err(fail)?);
+    let mut audio_stream: Option<AudioStream> = None;
+    // Opened on the first frame at the profile's sample rate and buffer size
+    let mut mixer: Box<dyn AudioBackend> = new_audio_backend(audio_backend, samples_path);
+    mixer.set_config(machine.profile().audio.clone());
+    let mut audio_buffer: Vec<i16> = vec![];
+
+    let mut profile_watcher: Option<FileWatcher> = profile_path.map(|path| FileWatcher::new(Path::new(path)));
+    let state_name: String = format!("{}.{}", rom_name, savestate::EXTENSION);
+    let stats_name: String = format!("{}.{}", rom_name, stats::EXTENSION);
+    let mut stats: Stats = Stats::read(&saves, &stats_name).unwrap_or_else(|e| {
+        println!("Starting the coin counter from 0: {}", e);
+        Stats::new()
+    });
+    stats.apply(&mut machine.hardware);
+    let mut rom_watcher: Option<FileWatcher> = watch_rom.then(|| FileWatcher::new(Path::new(file_path)));
+    let mut frames: u32 = 0;
+    let mut fault: Option<EmulatorError> = None;
+    let mut pacer: FramePacer = FramePacer::new(FRAMES_PER_SECOND);
+    let mut show_latency: bool = false;
+    let mut show_state: bool = false;
+    let mut frame_hash: FrameHash = FrameHash::new();
+    let mut recording: Option<Replay> = record_path.map(|_| Replay { seed: Some(seed), ..Replay::new(&rom) });
+    let mut played: usize = 0;
+    let mut soak: Option<Soak> = soak_duration.map(|duration| Soak::new(duration, FRAMES_PER_SECOND, Instant::now()));
+    // Left running in attract mode, checks every minute that memory and frame timing hold steady
+    let mut scrubber: Option<Scrubber> = scrub.then(Scrubber::default);
 
     while !raylib_handle.window_should_close() {
-        // Locked to 60 frames per second
-        // Interrupts twice per frame; Once in the middle, and once at the end
-        // There are a total of 33 000 cycles in every half frame
-        let mut frame_cycles: u64 = 0;
-        let cycle_max: u64 = 33_000;
+        let late: bool = pacer.wait() > pacer.frame_duration() / 2;
+        // Locked to 60 frames per second, waking up half a frame late means this machine can't keep up
 
-        while frame_cycles < cycle_max / 2 {
-            frame_cycles += emulator::update(&mut raylib_handle, &mut hardware, &mut cpu);
+        frames = frames.wrapping_add(1);
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F11) {
+            window_mode = emulator::window::apply(&mut raylib_handle, window_mode, WindowMode { borderless: !window_mode.borderless, ..window_mode });
         }
-        cpu::generate_interrupt(0xcf, &mut cpu);
-        // Call mid screen interrupt
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F10) {
+            let next: i32 = (window_mode.monitor + 1) % window::get_monitor_count().max(1);
+            window_mode = emulator::window::apply(&mut raylib_handle, window_mode, WindowMode { monitor: next, ..window_mode });
+        }
+        if Layout::of(&raylib_handle).fit(machine.profile().display.geometry()) != layout {
+            layout = Layout::of(&raylib_handle).fit(machine.profile().display.geometry());
+            if touch {
+                buttons = Some(VirtualButtons::new(&bindings, layout.width, layout.height, machine.profile().language));
+            }
+        }
+        // Resizing moves the buttons out to the new corners, the game and hud follow on their own when rendered
 
-        while frame_cycles < cycle_max {
-            frame_cycles += emulator::update(&mut raylib_handle, &mut hardware, &mut cpu);
+        if let Some(watcher) = profile_watcher.as_mut().filter(|_| frames.is_multiple_of(RELOAD_CHECK_FRAMES)) {
+            if watcher.changed() {
+                reload_profile(watcher.path(), timing, &mut machine, &mut bindings);
+                mixer.set_config(machine.profile().audio.clone());
+                if let Ok(config) = device_config(machine.profile(), &args) {
+                    device.set_config(config);
+                }
+                // The flags were checked at start up so only the profile's side can have changed
+                if let Ok(new_palette) = Palette::for_screen(&palette_preset.apply(&chosen_overlay(machine.profile(), overlay_choice)), machine.profile().display.geometry()) {
+                    palette = new_palette;
+                    post = Chain::build(&effects, &palette);
+                }
+                if touch {
+                    buttons = Some(VirtualButtons::new(&bindings, layout.width, layout.height, machine.profile().language));
+                }
+                if paddle_mode {
+                    paddle = new_paddle(machine.profile(), &bindings);
+                }
+                if kiosk_mode {
+                    kiosk = new_kiosk(machine.profile(), &bindings);
+                }
+                // Buttons press whatever keys are now bound
+            }
         }
-        cpu::generate_interrupt(0xd7, &mut cpu);
-        // Call full screen interrupt
+        // Lets the overlay and keys be tuned without restarting
+        if let Some(watcher) = rom_watcher.as_mut().filter(|_| frames.is_multiple_of(RELOAD_CHECK_FRAMES)) {
+            if watcher.changed() {
+                match fs::read(watcher.path()) {
+                    Ok(new_rom) => {
+                        let loaded: Result<(), LoadError> = machine.power_cycle(&new_rom);
+                        fault = None;
+                        machine_changed(&mut debugger);
+                        frame_hash = FrameHash::new();
+                        if recording.take().is_some() {
+                            println!("Stopped recording, a replay can't follow the rom changing");
+                        }
+                        if let Some(monitor) = debugger.as_mut() {
+                            load_session(monitor, &machine);
+                        }
+                        match loaded {
+                            Ok(()) => println!("Reloaded {}", watcher.path().display()),
+                            Err(e) => println!("Restarted with the old rom: {}", e),
+                        }
+                    },
+                    Err(e) => println!("Not reloading rom: {}", e),
+                }
+            }
+        }
+        // Power cycles with the new rom whenever it is assembled again, keeping the keys and profile
 
-        emulator::render(&mut raylib_handle, &thread, &hardware, &cpu);
+        if let Some(buttons) = buttons.as_mut() {
+            buttons.update(&raylib_handle);
+        }
+        if let Some(paddle) = paddle.as_mut() {
+            let target: Option<u16> = layout.column(raylib_handle.get_mouse_x() as f32);
+            paddle.update(target, &machine.cpu.memory);
+        }
+        let with_buttons: WithButtons<raylib::RaylibHandle> = WithButtons { source: &raylib_handle, buttons: buttons.as_ref() };
+        let with_paddle: WithPaddle<WithButtons<raylib::RaylibHandle>> = WithPaddle { source: &with_buttons, paddle: paddle.as_ref() };
+        if let Some(kiosk) = kiosk.as_mut() {
+            if kiosk.update(kiosk::active(&with_paddle, &bindings), &machine.cpu.memory, Instant::now()) {
+                machine_changed(&mut debugger);
+                match machine.reset() {
+                    Ok(()) => println!("Kiosk reset the machine after the game ended"),
+                    Err(e) => println!("Kiosk couldn't reset the machine: {}", e),
+                }
+            }
+        }
+        let input_source: WithKiosk<WithPaddle<WithButtons<raylib::RaylibHandle>>> = WithKiosk { source: &with_paddle, kiosk: kiosk.as_ref() };
+        let inputs: [u8; 2] = machine.hardware.inputs();
+        input::read_bindings(&input_source, &mut machine.hardware, &bindings);
+        // Reads user input and changes the state of the hardware input ports
+        let replay_frame: Option<&ReplayFrame> = playback.as_ref().and_then(|replay| replay.frames.get(played));
+        if let Some(replay_frame) = replay_frame {
+            machine.hardware.set_inputs(replay_frame.inputs);
+        }
+        // A replay's input replaces the keyboard until it runs out
+        if machine.hardware.inputs() != inputs {
+            machine_changed(&mut debugger);
+        }
+        if let Some(input_lag) = input_lag.as_mut() {
+            input_lag.sampled(&machine, Instant::now());
+        }
+
+        let mut hashed: bool = false;
+        if fault.is_none() && !machine.hardware.paused() && !debugger.as_ref().is_some_and(Monitor::is_paused) {
+            let ran: Result<(), EmulatorError> = match debugger.as_mut() {
+                Some(monitor) => monitor.run_frame(&mut machine).map(|hit| {
+                    if let Some(hit) = hit {
+                        show(monitor, &[hit]);
+                    }
+                }),
+                None => machine.run_frame(),
+            };
+            let finished: bool = ran.is_ok() && !debugger.as_ref().is_some_and(Monitor::is_paused);
+            // A breakpoint stops the machine part way through the frame, carrying on finishes it
+            if let Some(audit) = machine.audit_mut() {
+                audit.take_new().iter().for_each(|line| println!("Audit: {}", line));
+            }
+            for event in machine.events() {
+                if let Event::StackLeft(sp) = event {
+                    println!("Stack pointer moved to 0x{:04x} outside the stack at 0x{:04x}", sp, machine.cpu.pc.address);
+                }
+            }
+            if let (Some(scrubber), true) = (scrubber.as_mut(), finished) {
+                scrubber.record(&machine);
+            }
+            if finished && (show_frame_hash || recording.is_some() || replay_frame.is_some()) {
+                hashed = true;
+                let hash: u64 = frame_hash.update_from(&machine);
+                if let Some(recording) = recording.as_mut() {
+                    recording.record(machine.hardware.inputs(), hash);
+                }
+                if replay_frame.is_some_and(|replay_frame| replay_frame.hash != format!("{:016x}", hash)) {
+                    println!("Replay stopped matching at frame {}", played + 1);
+                }
+                played += 1;
+            }
+            // The hash chain has to see every frame from the start to match a replay
+            if let (Some(input_lag), true) = (input_lag.as_mut(), finished) {
+                if let Err(e) = input_lag.ran(&machine) {
+                    println!("Input lag measurement stopped: {}", e);
+                }
+            }
+            if let Err(e) = ran {
+                println!("Machine stopped: {}", e);
+                let cycle: u64 = machine.cycles();
+                if let Some(timeline) = machine.timeline_mut() {
+                    timeline.record(cycle, Kind::Fault, e.to_string());
+                }
+                match crash::write(&machine, &palette, &e, frames, Path::new(crash::DIRECTORY)) {
+                    Ok(path) => println!("Crash dump saved to {}, please attach it to any bug report", path.display()),
+                    Err(e) => println!("Failed to save crash dump: {}", e),
+                }
+                fault = Some(e);
+            }
+        }
+        // The last frame stays on screen after a fault so it can be looked at
+
+        let mut sounds: SoundLog = SoundLog::new();
+        sound::update_audio(&mut machine.hardware, &mut sounds);
+        if let (Some(recording), true) = (recording.as_mut(), hashed) {
+            recording.record_sounds(&sounds.events);
+        }
+        match replay_frame.filter(|_| hashed).and_then(|replay_frame| replay_frame.sounds.as_ref()) {
+            Some(logged) => logged.iter().for_each(|event| event.send(mixer.as_mut())),
+            None => sounds.send(mixer.as_mut()),
+        }
+        // A replay plays the sounds it was recorded with so they stay in step with the picture
+        //  even where the ports would come out differently, older replays without them use the ports
+        if let Some(audio) = audio.as_ref() {
+            let ready: bool = audio.is_audio_device_ready();
+            if !ready && audio_stream.take().is_some() {
+                println!("Lost the audio device, sound comes back when it does");
+            }
+            if let Some((sample_rate, samples)) = device.poll(ready) {
+                mixer.set_sample_rate(sample_rate);
+                audio_buffer = vec![0; samples];
+                audio_stream = Some(open_stream(audio, sample_rate, samples));
+            }
+        }
+        // The stream is opened again whenever the device comes back or its settings change
+        if let Some(stream) = audio_stream.as_mut() {
+            let mut refilled: usize = 0;
+            while stream.is_processed() {
+                mixer.mix(&mut audio_buffer);
+                stream.update(&audio_buffer);
+                refilled += 1;
+            }
+            if let Some(samples) = device.refilled(refilled, frames as u64, late) {
+                println!("Audio kept running dry, buffer is now {} samples", samples);
+            }
+        }
+        // Tops up the stream whenever raylib has finished playing a buffer
+        //  Having to refill both halves at once means it ran dry and crackled, the buffer grows when it keeps happening
+        if soak.as_mut().is_some_and(|soak| soak.frame(&machine, late, device.underruns().count(), Instant::now())) {
+            break;
+        }
+
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_P) {
+            let paused: bool = machine.hardware.paused();
+            machine.hardware.set_paused(!paused);
+        }
+        // P freezes the game, sounds stop on the next audio update and the keys aren't read until it carries on
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F2) && !machine.hardware.set_service_mode(!machine.hardware.service_mode()) {
+            println!("{} has no service switch", machine.profile().name);
+        }
+        // F2 flips the service switch when the profile says where it is wired
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F1) {
+            show_state = !show_state;
+        }
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F3) {
+            show_latency = !show_latency;
+            machine.clear_latency();
+        }
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F4) {
+            let counting: bool = machine.bandwidth().is_some();
+            machine.set_bandwidth(!counting);
+        }
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F5) {
+            match machine.save_state().write(&mut saves, &state_name) {
+                Ok(()) => println!("Saved state to {}", saves.describe(storage::Kind::SaveState, &state_name)),
+                Err(e) => println!("{}", e),
+            }
+        }
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F6) {
+            overlay_choice = match overlay_choice {
+                None => Some(0),
+                Some(index) if index + 1 < Overlay::PRESETS.len() => Some(index + 1),
+                Some(_) => None,
+            };
+            match Palette::for_screen(&palette_preset.apply(&chosen_overlay(machine.profile(), overlay_choice)), machine.profile().display.geometry()) {
+                Ok(new_palette) => {
+                    palette = new_palette;
+                    post = Chain::build(&effects, &palette);
+                },
+                Err(e) => println!("{}", e),
+            }
+            println!("Overlay: {}", overlay_choice.map_or("profile", |index| Overlay::PRESETS[index]));
+        }
+        // F6 goes through the bundled overlays and back to the profile's own
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F9) {
+            match SaveState::read(&saves, &state_name).and_then(|state| machine.load_state(&state)) {
+                Ok(()) => {
+                    fault = None;
+                    machine_changed(&mut debugger);
+                    if recording.take().is_some() {
+                        println!("Stopped recording, a replay can't follow loading a state");
+                    }
+                    println!("Loaded state from {}", saves.describe(storage::Kind::SaveState, &state_name));
+                },
+                Err(e) => println!("{}", e),
+            }
+        }
+        // F5 saves the machine and F9 puts it back
+        if raylib_handle.is_key_pressed(KeyboardKey::KEY_F12) {
+            let about: [String; 2] = [format!("Started with {}", args.join(" ")), format!("Profile from {}", profile_source)];
+            match report::write(&machine, &palette, frames, fault.as_ref(), &about, Path::new(report::DIRECTORY)) {
+                Ok(path) => println!("Bug report saved to {}, attach it to the issue", path.display()),
+                Err(e) => println!("Failed to save bug report: {}", e),
+            }
+        }
+        // F12 zips up the profile, a save state, the screen, recent instructions and the timeline for a bug report
+        if let Some(scrubber) = scrubber.as_mut() {
+            if raylib_handle.is_key_pressed(KeyboardKey::KEY_F7) {
+                match scrubber.is_open() {
+                    true => scrubber.close(),
+                    false => {
+                        machine.hardware.set_paused(true);
+                        scrubber.open(&machine);
+                    },
+                }
+            }
+            if scrubber.is_open() {
+                scrubber.drag(&raylib_handle, &layout);
+                if raylib_handle.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+                    scrubber.step_forward();
+                }
+                if raylib_handle.is_key_pressed(KeyboardKey::KEY_LEFT) {
+                    scrubber.step_back();
+                }
+            }
+            if raylib_handle.is_key_pressed(KeyboardKey::KEY_F8) && scrubber.resume(&mut machine).is_some() {
+                fault = None;
+                machine_changed(&mut debugger);
+                if recording.take().is_some() {
+                    println!("Stopped recording, a replay can't follow going back in time");
+                }
+            }
+        }
+        // F7 looks back through the last few seconds with the game paused, dragging the bar or the arrow keys
+        //  move through them and F8 carries on from there, closing it again leaves the machine where it was
+        if let Some(monitor) = debugger.as_mut() {
+            if raylib_handle.is_key_pressed(KeyboardKey::KEY_GRAVE) {
+                let line: &str = if monitor.is_paused() { "c" } else { "pause" };
+                let output: String = monitor.execute(line, &mut machine).unwrap_or_else(|e| e);
+                show(monitor, &[output]);
+            }
+            let output: Vec<String> = monitor.poll(&mut machine);
+            if !output.is_empty() {
+                show(monitor, &output);
+            }
+            for (key, direction) in HEX_MOVES {
+                if raylib_handle.is_key_pressed(key) {
+                    monitor.move_hex_cursor(direction);
+                }
+            }
+            while let Some(typed) = raylib_handle.get_char_pressed() {
+                if !typed.is_ascii_hexdigit() {
+                    continue;
+                }
+                if let Err(e) = monitor.type_hex(typed, &mut machine) {
+                    show(monitor, &[e]);
+                }
+            }
+            // While the hex viewer is open the arrows move its cursor and hex digits type over the byte under it
+        }
+        // ` stops and starts the game under the debugger, typed commands are run whether it is stopped or not
+
+        let language: Language = machine.profile().language;
+        let mut hud: Vec<String> = emulator::controls(language);
+        if show_state {
+            hud.extend(state_panel::machine_lines(&machine, frames as u64, language));
+        }
+        if show_latency {
+            hud.extend(machine.latency().lines(language));
+        }
+        if show_frame_hash {
+            hud.push(frame_hash.hud(language));
+        }
+        if let Some(bandwidth) = machine.bandwidth() {
+            hud.extend(bandwidth.lines(language));
+        }
+        hud.extend(emulator::halt_status(&machine, language));
+        hud.extend(device.underruns().hud(frames as u64, language));
+        if let Some(input_lag) = input_lag.as_ref() {
+            hud.extend(input_lag.lines(language));
+        }
+        if machine.hardware.paused() || debugger.as_ref().is_some_and(Monitor::is_paused) {
+            hud.push(String::from(language.text(Text::Paused)));
+        }
+        if let Some(scrubber) = scrubber.as_ref() {
+            hud.extend(scrubber.lines(language));
+        }
+        if let Some(monitor) = debugger.as_ref() {
+            hud.extend(monitor.hex_lines(&machine));
+        }
+        if machine.hardware.service_mode() {
+            hud.push(format!("{}, {} {}", language.text(Text::ServiceMode), machine.hardware.coins(), language.text(Text::Coins)));
+        }
+        // F1 shows the registers, F3 shows how long interrupts wait, counted from when it was turned on, and F4 counts memory accesses
+
+        let background: Color = palette.background;
+        if input_lag.as_ref().is_some_and(InputLag::flashing) {
+            palette.background = Color::WHITE;
+        }
+        emulator::render(&mut raylib_handle, &thread, &machine, &palette, &mut post, &Hud { lines: &hud, buttons: buttons.as_ref(), scrubber: scrubber.as_ref() });
         // Render frame
+        palette.background = background;
+        if let Some(input_lag) = input_lag.as_mut() {
+            input_lag.presented(Instant::now());
+        }
+    }
+
+    if let Some(monitor) = debugger.as_ref() {
+        match monitor.save_session(Path::new(session::DIRECTORY), &machine) {
+            Ok(path) => println!("Saved the debugging session to {}", path.display()),
+            Err(e) => println!("{}", e),
+        }
+    }
+    // Commands save it too, this also keeps ignore counts used up after the last command
+
+    if let (Some(path), Some(recording)) = (record_path, recording) {
+        match recording.save(Path::new(path)) {
+            Ok(()) => println!("Saved {} frames to {}", recording.frames.len(), path),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    if let Some(soak) = soak {
+        print!("{}", soak.report());
+        match soak.save(soak::REPORT) {
+            Ok(()) => println!("Saved the soak report to {}", soak::REPORT),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    save_timeline(&machine, timeline_path);
+    finish_trace(&mut machine);
+    finish_audit(&mut machine);
+    stats.update(&machine.hardware);
+    if let Err(e) = stats.write(&mut saves, &stats_name) {
+        println!("{}", e);
+    }
+
+    if let Some(coverage_path) = coverage_path {
+        if let Err(e) = machine.coverage.save(Path::new(coverage_path)) {
+            println!("Failed to save coverage map: {}", e);
+        }
+        // Merged with any map already saved there so coverage builds up over sessions
     }
 
     Ok(())
 }
+
+fn audio_samples(args: &[String], flag: &str, default: usize) -> Result<usize, String> {
+    match value(args, flag) {
+        Some(samples) => match samples.parse::<usize>() {
+            Ok(samples) if samples >= 64 => Ok(samples),
+            _ => Err(format!("{} is not a number of samples, it needs to be at least 64", samples)),
+        },
+        None => Ok(default),
+    }
+}
+
+fn device_config(profile: &Profile, args: &[String]) -> Result<DeviceConfig, String> {
+    // The profile's [audio.device] settings with --audio-buffer and --max-audio-buffer on top
+    //  The biggest buffer is kept as a latency so it still means the same delay if the sample rate changes
+
+    let mut config: DeviceConfig = profile.audio.device;
+    config.buffer_samples = audio_samples(args, "--audio-buffer", config.buffer_samples)?;
+    if args.iter().any(|arg| arg == "--max-audio-buffer") {
+        let max_samples: usize = audio_samples(args, "--max-audio-buffer", config.max_buffer_samples())?;
+        config.latency_ms = (max_samples as u64 * 1000).div_ceil(config.sample_rate.max(1) as u64) as u32;
+    }
+    Ok(config)
+}
+
+fn open_stream(audio: &RaylibAudio, sample_rate: u32, samples: usize) -> AudioStream<'_> {
+    audio.set_audio_stream_buffer_size_default(samples as i32);
+    let stream: AudioStream = audio.new_audio_stream(sample_rate, 16, 1);
+    stream.play();
+    stream
+}
+
+fn usage() {
+    println!("Usage: emulator [--profile <file>] [--coverage <map>] [--samples <dir> | --audio synth] [--touch] [--paddle] [--kiosk] [--scrub] [--console <port>] [--clock <speed>] [--cycles-per-frame <n>] [--frame-hash] [--record <replay> | --replay <replay>] [--watch] [--timeline <file>] [--trace <file> [--trace-level <instruction|interrupt|frame>] [--trace-every <n>] [--syntax <intel|z80>]] [--palette <overlay|high_contrast|colourblind>] [--overlay <tv|midway_gel|monochrome>] [--reduce-flashes] [--effects <scanlines,blend,crt:50>] [--audio-buffer <samples>] [--max-audio-buffer <samples>] [--borderless] [--monitor <n>] [--window-size <width>x<height>] [--input-lag <key>] [--debug [--symbols <file>]] [--data-dir <dir>] [--soak <hours>] [--audit] <rom>");
+    println!("       emulator [<flags>] [<rom folder>]");
+    println!("       emulator --headless --frames <n> [--dump <state.json>] [--png <file>] [--no-idle-skip] [--timeline <file>] [--trace <file> [--trace-level <level>] [--trace-every <n>] [--syntax <intel|z80>]] [--profile <file>] [--audit] <rom>");
+    println!("       emulator --headless --replay <replay> [--profile <file>] <rom>");
+    println!("       emulator render-replay <rom> <replay> [--out <dir>] [--profile <file>] [--samples <dir> | --audio synth] [--palette <preset>] [--overlay <preset>]");
+    println!("       emulator --test [--profile <file>] <rom>");
+    println!("       emulator --diff-states <before.state> <after.state> [--profile <file>]");
+    println!("       emulator --rom-hash <rom>");
+    println!("       emulator info [--profile <file>] <rom>");
+    println!("       emulator selftest [--no-audio]");
+    println!("       emulator --validate-profile [--profile <file>]");
+    println!("       emulator --list-machines [--data-dir <dir>]");
+    println!("       emulator --assemble <source.asm> <rom>");
+}
+
+fn choose_rom(args: &[String], folder: &Path) -> Result<Option<PathBuf>, String> {
+    // Profiles come from the same place running the rom looks for them
+
+    let profiles: DirectoryStorage = match args.windows(2).find(|pair| pair[0] == "--data-dir") {
+        Some(pair) => DirectoryStorage::new(Path::new(&pair[1])),
+        None => DirectoryStorage::platform().unwrap_or_else(|| DirectoryStorage::new(folder)),
+    };
+    launcher::run(folder, &profiles)
+}
+
+fn validate_profile(path: Option<&Path>, timing: Timing) -> Result<(), u8> {
+    // Prints what validate found and fails when there was anything wrong
+
+    let mut profile: Profile = match path.map(Profile::load) {
+        Some(Ok(profile)) => profile,
+        Some(Err(e)) => {
+            println!("{}", e);
+            return Err(1);
+        },
+        None => Profile::space_invaders(),
+    };
+    timing.apply(&mut profile);
+
+    println!("{}", profile.report());
+    match profile.validate().iter().all(|check| check.problems.is_empty()) {
+        true => Ok(()),
+        false => Err(1),
+    }
+}
+
+fn assemble(source: &Path, rom: &Path) -> Result<(), u8> {
+    let assembled: Result<Vec<u8>, String> = fs::read_to_string(source)
+        .map_err(|e| format!("{}: {}", source.display(), e))
+        .and_then(|text| assembler::assemble_program(&text).map_err(|e| format!("{}: {}", source.display(), e)));
+    let written: Result<usize, String> = assembled.and_then(|bytes| fs::write(rom, &bytes)
+        .map(|_| bytes.len())
+        .map_err(|e| format!("Failed to write {}: {}", rom.display(), e)));
+    match written {
+        Ok(length) => {
+            println!("Assembled {} bytes to {}", length, rom.display());
+            Ok(())
+        },
+        Err(e) => {
+            println!("{}", e);
+            Err(1)
+        },
+    }
+}
+
+fn diff_states(args: &[String]) -> Result<(), u8> {
+    // Prints the registers and memory that changed between two save states, handy for finding where a game keeps things
+
+    let Some(paths) = args.windows(3).find(|window| window[0] == "--diff-states") else {
+        usage();
+        return Err(1);
+    };
+    let (before, after): (&Path, &Path) = (Path::new(&paths[1]), Path::new(&paths[2]));
+    let file_path: &str = &args[args.len() - 1];
+    let (profile, _): (Profile, String) = load_profile(args, &storage(args, file_path), file_path).map_err(fail)?;
+    let load = |path: &Path| SaveState::load(path).and_then(|state| Ok((state.memory()?, state.registers)));
+    match (load(before), load(after)) {
+        (Ok((before, before_registers)), Ok((after, after_registers))) => {
+            for delta in delta::registers(&before_registers, &after_registers) {
+                println!("{}", delta);
+            }
+            println!("{}", savestate::format_diff(&savestate::diff(&before, &after), &profile, &SymbolTable::default()));
+            Ok(())
+        },
+        (Err(e), _) | (_, Err(e)) => {
+            println!("{}", e);
+            Err(1)
+        },
+    }
+}
+
+fn verify_replay(replay: &Replay, machine: &mut Machine) -> Result<(), u8> {
+    // Plays a replay without a window and checks every frame hash matches
+
+    match replay.verify(machine) {
+        Ok(hash) => {
+            println!("Replay verified, {} frames ending with hash {:016x}", replay.frames.len(), hash);
+            Ok(())
+        },
+        Err(e) => {
+            println!("Replay failed verification: {}", e);
+            Err(1)
+        },
+    }
+}
+
+fn list_machines(saves: &DirectoryStorage) {
+    for info in profiles::list() {
+        info.lines().iter().for_each(|line| println!("{}", line));
+    }
+    for listed in profiles::list_in(saves) {
+        match listed {
+            Ok(info) => info.lines().iter().for_each(|line| println!("{}", line)),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn new_audio_backend(name: &str, samples_path: Option<&String>) -> Box<dyn AudioBackend> {
+    // --audio synth or the sample mixer, missing samples are synthesized so there is always something to hear
+    match (name, samples_path) {
+        ("synth", _) => Box::new(Synth::new()),
+        (_, Some(path)) => Box::new(Mixer::new(SamplePack::load(Path::new(path)))),
+        (_, None) => Box::new(Mixer::new(SamplePack::synthesized())),
+    }
+}
+
+fn render_replay(args: &[String]) -> Result<(), u8> {
+    // Plays a replay without a window as fast as it will go, writing the frames and sound for ffmpeg to put together
+
+    let Loaded { mut machine, playback, .. } = load(args)?;
+    let Some(replay) = playback else {
+        usage();
+        return Err(1);
+    };
+    let overlay: Overlay = palette_preset(args).map_err(fail)?.apply(&chosen_overlay(machine.profile(), overlay_choice(args).map_err(fail)?));
+    let mut audio: Box<dyn AudioBackend> = new_audio_backend(value(args, "--audio").map_or("samples", String::as_str), value(args, "--samples"));
+    let out: &Path = Path::new(args.windows(2)
+        .find(|pair| pair[0] == "--out")
+        .map_or(video::DIRECTORY, |pair| pair[1].as_str()));
+    let palette: Palette = match Palette::for_screen(&overlay, machine.profile().display.geometry()) {
+        Ok(palette) => palette,
+        Err(e) => {
+            println!("{}", e);
+            return Err(1);
+        },
+    };
+    audio.set_config(machine.profile().audio.clone());
+    audio.set_sample_rate(machine.profile().audio.device.sample_rate);
+    machine.set_idle_skip(true);
+    // Nothing is waiting on the clock
+
+    let started: Instant = Instant::now();
+    match video::render_replay(&mut machine, &replay, &palette, audio.as_mut(), out) {
+        Ok(frames) => {
+            println!("Rendered {} frames to {} in {:.1}s, put them together with", frames, out.display(), started.elapsed().as_secs_f64());
+            println!("{}", video::ffmpeg_command(out));
+            Ok(())
+        },
+        Err(e) => {
+            println!("Stopped rendering: {}", e);
+            Err(1)
+        },
+    }
+}
+
+fn run_headless(args: &[String], machine: &mut Machine) -> Result<(), u8> {
+    // Runs a fixed number of frames without opening a window then saves the final state
+    //  The dump is printed when no file is given so it can be piped into other tools
+
+    let frames: Option<u32> = args.windows(2)
+        .find(|pair| pair[0] == "--frames")
+        .and_then(|pair| pair[1].parse().ok());
+    let dump_path: Option<&String> = args.windows(2)
+        .find(|pair| pair[0] == "--dump")
+        .map(|pair| &pair[1]);
+    let png_path: Option<&String> = args.windows(2)
+        .find(|pair| pair[0] == "--png")
+        .map(|pair| &pair[1]);
+
+    let Some(frames) = frames else {
+        println!("--headless needs --frames <n>");
+        return Err(1);
+    };
+    machine.set_idle_skip(!args[..args.len() - 1].iter().any(|arg| arg == "--no-idle-skip"));
+    // Nothing is waiting on the clock so time spent waiting for interrupts is skipped
+
+    let dump: StateDump = headless::run(machine, frames);
+    if let Some(e) = &dump.fault {
+        println!("Machine stopped after {} frames: {}", dump.frames, e);
+    }
+
+    match dump_path {
+        Some(path) => if let Err(e) = dump.save(Path::new(path)) {
+            println!("{}", e);
+            return Err(1);
+        },
+        None => println!("{}", serde_json::to_string_pretty(&dump).unwrap_or_default()),
+    }
+
+    if let Some(path) = png_path {
+        let geometry: Geometry = machine.profile().display.geometry();
+        let saved: Result<(), String> = Palette::for_screen(&machine.profile().overlay, geometry)
+            .and_then(|palette| headless::save_png(&machine.cpu, geometry, &palette, Path::new(path)));
+        if let Err(e) = saved {
+            println!("{}", e);
+            return Err(1);
+        }
+    }
+
+    match dump.fault {
+        Some(_) => Err(1),
+        None => Ok(()),
+    }
+}
+
+fn save_timeline(machine: &Machine, path: Option<&String>) {
+    // CSV when the path ends in .csv, JSON otherwise
+    let (Some(path), Some(timeline)) = (path, machine.timeline()) else {
+        return;
+    };
+    match timeline.save(Path::new(path)) {
+        Ok(()) => println!("Saved {} timeline events to {}", timeline.len(), path),
+        Err(e) => println!("{}", e),
+    }
+}
+
+fn finish_trace(machine: &mut Machine) {
+    let Some(tracer) = machine.tracer_mut() else {
+        return;
+    };
+    if let Err(e) = tracer.flush() {
+        println!("Trace is incomplete: {}", e);
+    }
+}
+
+fn finish_audit(machine: &mut Machine) {
+    // Anything found since the last frame was printed, then the totals
+    let Some(audit) = machine.audit_mut() else {
+        return;
+    };
+    audit.take_new().iter().for_each(|line| println!("Audit: {}", line));
+    println!("{}", audit.summary());
+}
+
+fn show(monitor: &Monitor, lines: &[String]) {
+    // Prints what the monitor said followed by a prompt for the next command
+    lines.iter().for_each(|line| println!("{}", line));
+    print!("{}", monitor.prompt());
+    let _ = io::stdout().flush();
+}
+
+fn load_session(monitor: &mut Monitor, machine: &Machine) {
+    // Sessions are kept per rom so a rom debugged before gets its breakpoints back
+    match monitor.load_session(Path::new(session::DIRECTORY), machine) {
+        Ok(true) => println!("Restored the debugging session for this rom"),
+        Ok(false) => {},
+        Err(e) => println!("Couldn't restore the debugging session: {}", e),
+    }
+}
+
+fn machine_changed(monitor: &mut Option<Monitor>) {
+    // Step back replays from snapshots so it can't go back past the machine being changed outside the debugger
+    if let Some(monitor) = monitor.as_mut() {
+        monitor.debugger_mut().clear_history();
+    }
+}
+
+fn chosen_overlay(profile: &Profile, choice: Option<usize>) -> Overlay {
+    // The profile's own overlay unless one of the bundled ones has been picked
+    choice.and_then(|index| Overlay::preset(Overlay::PRESETS[index]).ok()).unwrap_or_else(|| profile.overlay.clone())
+}
+
+fn new_paddle(profile: &Profile, bindings: &[input::Binding]) -> Option<Paddle> {
+    let paddle: Option<Paddle> = profile.paddle.and_then(|config| Paddle::new(config, bindings));
+    if paddle.is_none() {
+        println!("{} can't be played with the mouse", profile.name);
+    }
+    paddle
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Timing {
+    // Clock and cycle budget given on the command line, they win over the profile's
+    clock: Option<u64>,
+    cycles_per_frame: Option<u64>,
+}
+impl Timing {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        // Lets the cpu be over or underclocked without editing the profile
+        Ok(Timing {
+            clock: value(args, "--clock").map(|clock| Clock::parse(clock)).transpose()?,
+            cycles_per_frame: value(args, "--cycles-per-frame").map(|cycles| match cycles.parse::<u64>() {
+                Ok(cycles) if cycles >= 2 => Ok(cycles),
+                _ => Err(format!("{} is not a number of cycles", cycles)),
+            }).transpose()?,
+        })
+    }
+
+    fn apply(&self, profile: &mut Profile) {
+        if let Some(hz) = self.clock {
+            profile.set_clock(hz);
+        }
+        if let Some(cycles) = self.cycles_per_frame {
+            profile.interrupts.cycles_per_frame = cycles;
+        }
+        // Set after the clock so an exact budget isn't scaled
+    }
+}
+
+fn new_kiosk(profile: &Profile, bindings: &[input::Binding]) -> Option<Kiosk> {
+    let kiosk: Option<Kiosk> = profile.kiosk.and_then(|config| Kiosk::new(config, bindings, Instant::now()));
+    if kiosk.is_none() {
+        println!("{} has no kiosk settings with keys bound to its coin and start", profile.name);
+    }
+    kiosk
+}
+
+fn reload_profile(path: &Path, timing: Timing, machine: &mut Machine, bindings: &mut Vec<input::Binding>) {
+    // Applies the parts of a changed profile that are safe to change while running
+    //  A profile that fails to load is ignored so a half saved file doesn't crash the emulator
+
+    let mut profile: Profile = match Profile::load(path) {
+        Ok(profile) => profile,
+        Err(e) => {
+            println!("Not reloading profile: {}", e);
+            return;
+        },
+    };
+    timing.apply(&mut profile);
+    profile = profile.for_rom(machine.rom());
+    match profile.bindings() {
+        Ok(new_bindings) => *bindings = new_bindings,
+        Err(e) => {
+            println!("Not reloading profile: {}", e);
+            return;
+        },
+    }
+
+    if profile.memory != machine.profile().memory || profile.mirror != machine.profile().mirror || profile.banks != machine.profile().banks || profile.interrupts != machine.profile().interrupts {
+        println!("Memory map and interrupt changes need a restart");
+    }
+    if profile.dip_switches != machine.profile().dip_switches || profile.service != machine.profile().service {
+        println!("DIP and service switch changes need a restart");
+    }
+    machine.set_overlay(profile.overlay);
+    machine.set_language(profile.language);
+    machine.set_coin_pulse_frames(profile.coin_pulse_frames);
+    machine.set_watchdog(profile.watchdog_frames);
+    println!("Reloaded {}", path.display());
+}
@@ -0,0 +1,181 @@
+use std::io::{self, BufRead};
+use std::path::Path;
+#[cfg(feature = "serde")]
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use disassembler::symbols::SymbolTable;
+
+use crate::debugger::{Break, Debugger};
+use crate::debugger::hex::Move;
+use crate::error::EmulatorError;
+use crate::machine::Machine;
+
+mod tests;
+
+pub struct Monitor {
+    // Debugger console for the window, the machine stops on a hotkey or a breakpoint and typed
+    //  commands are run against it until c carries on
+    debugger: Debugger,
+    symbols: SymbolTable,
+    paused: bool,
+    hex_open: bool,
+    // The hex viewer is shown over the game and takes the arrows and hex digits until carrying on
+    lines: Option<Receiver<String>>,
+    // Lines typed into the terminal when it is read from
+}
+impl Monitor {
+    pub fn new(symbols: SymbolTable) -> Self {
+        Self {
+            debugger: Debugger::new(),
+            symbols,
+            paused: false,
+            hex_open: false,
+            lines: None,
+        }
+    }
+
+    pub fn with_symbol_file(path: &Path) -> Result<Self, String> {
+        // Symbols from a file in the disassembler's format so breakpoints can be set by name
+        SymbolTable::load(path).map(Self::new)
+    }
+
+    pub fn read_stdin(mut self) -> Self {
+        // Takes commands from the terminal
+        //  Stdin blocks so it is read on its own thread, lines queue up until the next poll
+
+        let (sender, receiver) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if line.ok().is_none_or(|line| sender.send(line).is_err()) {
+                    break;
+                }
+            }
+            // Stops at the end of input or once the monitor is gone
+        });
+        self.lines = Some(receiver);
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load_session(&mut self, directory: &Path, machine: &Machine) -> Result<bool, String> {
+        // Brings back the breakpoints, watches, symbols and comments from the last time the machine's rom was debugged
+        //  A rom without a session keeps what is already set, so reassembling a rom doesn't lose them
+        self.debugger.load_session(directory, &mut self.symbols, machine.rom())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save_session(&self, directory: &Path, machine: &Machine) -> Result<PathBuf, String> {
+        self.debugger.save_session(directory, &self.symbols, machine.rom())
+    }
+
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self, machine: &Machine) -> String {
+        self.paused = true;
+        format!("Paused at {}, c carries on", self.symbols.format(machine.cpu.pc.address))
+    }
+
+    pub fn is_hex_open(&self) -> bool {
+        self.hex_open
+    }
+
+    pub fn hex_lines(&self, machine: &Machine) -> Vec<String> {
+        // What the window shows while the hex viewer is open
+        match self.hex_open {
+            true => self.debugger.hex_viewer().lines(machine),
+            false => vec![],
+        }
+    }
+
+    pub fn move_hex_cursor(&mut self, direction: Move) {
+        if self.hex_open {
+            self.debugger.move_hex_cursor(direction);
+        }
+    }
+
+    pub fn type_hex(&mut self, digit: char, machine: &mut Machine) -> Result<(), String> {
+        // Keys typed at the window go to the hex viewer only while it is open
+        match self.hex_open {
+            true => self.debugger.type_hex(digit, machine),
+            false => Ok(()),
+        }
+    }
+
+    pub fn prompt(&self) -> String {
+        match self.debugger.assembling() {
+            Some(address) => format!("asm 0x{:04x}> ", address),
+            None => String::from("> "),
+        }
+    }
+
+    pub fn run_frame(&mut self, machine: &mut Machine) -> Result<Option<String>, EmulatorError> {
+        // Runs the rest of the frame through the debugger so breakpoints and watches are checked after every instruction
+        //  A hit pauses the monitor part way through the frame and returns what stopped it, carrying on finishes the frame
+        //  Nothing runs while paused
+
+        if self.paused {
+            return Ok(None);
+        }
+        match self.debugger.run_frame(machine) {
+            None => Ok(None),
+            Some(Break::Fault(e)) => {
+                self.paused = true;
+                Err(e)
+            },
+            Some(hit) => {
+                self.paused = true;
+                Ok(Some(hit.describe(&self.symbols)))
+            },
+        }
+    }
+
+    pub fn execute(&mut self, line: &str, machine: &mut Machine) -> Result<String, String> {
+        // Runs a command typed into the monitor, see Debugger::command for the list
+        //      pause                       Stop the machine where it is
+        //      c                           Carry on running, closing the hex viewer
+        //  hex also pauses the machine and opens the hex viewer in the window
+        //  Commands work while the machine runs too, a breakpoint set then stops it when hit
+
+        match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["pause"] if self.debugger.assembling().is_none() => Ok(self.pause(machine)),
+            ["c" | "continue"] if self.debugger.assembling().is_none() => {
+                self.paused = false;
+                self.hex_open = false;
+                Ok(String::from("Carrying on"))
+            },
+            ["hex", ..] if self.debugger.assembling().is_none() => {
+                let output: String = self.debugger.command(line, machine, &self.symbols)?;
+                self.paused = true;
+                self.hex_open = true;
+                Ok(output)
+            },
+            _ => self.debugger.command(line, machine, &self.symbols),
+        }
+    }
+
+    pub fn poll(&mut self, machine: &mut Machine) -> Vec<String> {
+        // Runs every line typed since the last poll and returns what to show for each
+        //  Errors are shown the same as output since they are only ever read
+
+        let lines: Vec<String> = self.lines.as_ref().map_or(vec![], |lines| lines.try_iter().collect());
+        lines.iter()
+            .map(|line| self.execute(line, machine).unwrap_or_else(|e| e))
+            .collect()
+    }
+}
@@ -0,0 +1,344 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::fixtures;
+#[cfg(test)]
+use crate::machine::Event;
+#[cfg(test)]
+use crate::cpu::Registers;
+
+#[test]
+fn test_breaking_into_the_monitor() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::parse("0x0001 Loop").unwrap());
+
+    assert_eq!(monitor.execute("be out 3", &mut machine), Ok(String::from("Breakpoint on out 3")));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped after writing 0x00 to port 3"))));
+    assert!(monitor.is_paused());
+    assert_eq!(machine.cpu.pc.address, 0x0003);
+    // A hit stops the machine straight after the instruction
+
+    let cycles: u64 = machine.cycles();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(machine.cycles(), cycles);
+    // Nothing runs while paused
+
+    assert_eq!(monitor.execute("bc out 3", &mut machine), Ok(String::from("Cleared breakpoint on out 3")));
+    assert_eq!(monitor.execute("step", &mut machine), Ok(String::from("> 0005   c3 00 01    JMP adr    ; Loop")));
+    assert_eq!(machine.cpu.pc.address, 0x0005);
+    assert_eq!(monitor.execute("bp Loop", &mut machine), Ok(String::from("Breakpoint at 0x0001 Loop")));
+    assert_eq!(monitor.execute("step 5", &mut machine), Ok(String::from("Stopped at 0x0001 Loop\nLoop:\n> 0001   d3 03       OUT D8")));
+    // Stepping stops early on a breakpoint
+
+    assert_eq!(monitor.execute("c", &mut machine), Ok(String::from("Carrying on")));
+    assert!(!monitor.is_paused());
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped at 0x0001 Loop"))));
+    assert_eq!(monitor.execute("bc Loop", &mut machine), Ok(String::from("Cleared breakpoint at 0x0001 Loop")));
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(machine.events().last(), Some(&Event::Interrupt(2)));
+    // Carrying on finishes the frame the breakpoint stopped in
+
+    assert_eq!(monitor.execute("pause", &mut machine), Ok(String::from("Paused at Loop+15, c carries on")));
+    assert!(monitor.is_paused());
+}
+
+#[test]
+fn test_monitor_event_breakpoints() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    monitor.execute("be ei", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped after EI"))));
+    monitor.execute("bc ei", &mut machine).unwrap();
+    monitor.execute("be in 0x01", &mut machine).unwrap();
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped after reading port 1"))));
+    monitor.execute("bc in 1", &mut machine).unwrap();
+    monitor.execute("be interrupt", &mut machine).unwrap();
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped after RST 1 was serviced"))));
+    assert_eq!(machine.cpu.pc.address, 0x0008);
+
+    assert_eq!(monitor.execute("bc out 3", &mut machine), Err(String::from("No breakpoint on out")));
+    assert!(monitor.execute("be out 0x100", &mut machine).unwrap_err().contains("not a port number"));
+    assert!(monitor.execute("be reset", &mut machine).unwrap_err().contains("not an event"));
+}
+
+#[test]
+fn test_monitor_step_back() {
+    let mut machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    monitor.pause(&machine);
+    let (registers, cycles): (Registers, u64) = (machine.cpu.registers(), machine.cycles());
+    monitor.execute("step 3", &mut machine).unwrap();
+    for _ in 0..3 {
+        monitor.execute("step-back", &mut machine).unwrap();
+    }
+    assert_eq!((machine.cpu.registers(), machine.cycles()), (registers, cycles));
+
+    assert_eq!(monitor.execute("step-back", &mut machine), Ok(String::from("> 0003   db 01       IN D8")));
+    assert_ne!(machine.cycles(), cycles);
+    // Instructions run by the frame before pausing can be stepped back over too
+
+    monitor.debugger_mut().clear_history();
+    assert_eq!(monitor.execute("step-back", &mut machine), Err(String::from("No instructions to step back over")));
+}
+
+#[test]
+fn test_monitor_watches() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    assert_eq!(monitor.execute("watch sp by 1", &mut machine), Ok(String::from("Watching sp changing by more than 1")));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Watch on sp hit at 0x0024, 0x2400 -> 0x23fe"))));
+    // The interrupt taken after LDA pushed the return address
+    assert_eq!(monitor.execute("unwatch sp", &mut machine), Ok(String::from("Cleared the watches on sp")));
+    assert_eq!(monitor.execute("unwatch sp", &mut machine), Err(String::from("No watches on sp")));
+
+    monitor.execute("watch pc is 0x0040 log", &mut machine).unwrap();
+    monitor.execute("watch a", &mut machine).unwrap();
+    assert_eq!(monitor.execute("watches", &mut machine), Ok(String::from("pc becoming 0x0040, logged\na changing")));
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Watch on a hit at 0x0041, 0x0000 -> 0x0001"))));
+    assert_eq!(monitor.execute("watchlog", &mut machine), Ok(String::from("0x0008 pc 0x0008 -> 0x0040")));
+    // The jump into the handler was logged without stopping
+
+    monitor.execute("watchlog clear", &mut machine).unwrap();
+    assert_eq!(monitor.execute("watchlog", &mut machine), Ok(String::from("Nothing logged")));
+    assert!(monitor.execute("watch sp by", &mut machine).is_err());
+    assert!(monitor.execute("watch q", &mut machine).unwrap_err().contains("not a register"));
+}
+
+#[test]
+fn test_monitor_symbols() {
+    let path: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-monitor-symbols-{}.sym", std::process::id()));
+    std::fs::write(&path, "0x0020 Start\n0x0024 Wait\n0x0040 Handler\n").unwrap();
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::with_symbol_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(monitor.execute("bp Handler+1", &mut machine), Ok(String::from("Breakpoint at 0x0041 Handler+1")));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped at 0x0041 Handler+1"))));
+    assert_eq!(monitor.execute("bt", &mut machine), Ok(String::from("#0 0x0041 Handler+1\n#1 0x0027 Wait+3 called 0x0008")));
+    assert_eq!(monitor.execute("dis Handler 2", &mut machine), Ok(String::from("Handler:\n  0040   f5          PUSH PSW\n> 0041   3e 01       MVI A,D8")));
+    assert_eq!(monitor.execute("bl", &mut machine), Ok(String::from("#1 0x0041 Handler+1  hits 1")));
+    assert_eq!(monitor.execute("bp Missing", &mut machine), Err(String::from("Missing is not an address or a known symbol")));
+
+    assert!(Monitor::with_symbol_file(&path).is_err());
+}
+
+#[test]
+fn test_monitor_assembling() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    monitor.pause(&machine);
+    assert_eq!(monitor.execute("asm 0x0041", &mut machine), Ok(String::from("Assembling at 0x0041, a blank line finishes")));
+    assert_eq!(monitor.prompt(), "asm 0x0041> ");
+    assert_eq!(monitor.execute("MVI A,2", &mut machine), Ok(String::from("  0041   3e 02       MVI A,D8")));
+    assert_eq!(monitor.prompt(), "asm 0x0043> ");
+    assert_eq!(machine.cpu.memory.read_at(0x0042), 0x02);
+    // Written over the rom
+
+    assert_eq!(monitor.execute("c", &mut machine), Err(String::from("c is not an 8080 instruction")));
+    assert!(monitor.is_paused());
+    // Lines are assembled until the blank line, even ones the monitor would otherwise take
+    assert_eq!(monitor.execute("", &mut machine), Ok(String::from("Finished at 0x0043")));
+    assert_eq!(monitor.prompt(), "> ");
+    monitor.execute("c", &mut machine).unwrap();
+    assert!(!monitor.is_paused());
+
+    assert_eq!(monitor.execute("undo", &mut machine), Ok(String::from("Undid 0x0041: 3e 01 -> 3e 02")));
+    assert_eq!(machine.cpu.memory.read_at(0x0042), 0x01);
+    // Each assembled line is an edit that can be taken back before the machine runs
+}
+
+#[test]
+fn test_monitor_comments() {
+    let path: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-monitor-comments-{}.lst", std::process::id()));
+    let export: String = format!("comments export {}", path.display());
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::parse("0x0024 Wait").unwrap());
+
+    assert_eq!(monitor.execute("comment Wait Spins until the handler sets the flag", &mut machine), Ok(String::from("Comment at 0x0024 Wait")));
+    assert_eq!(monitor.execute("dis Wait 1", &mut machine), Ok(String::from("Wait:\n  0024   3a 20 00    LDA adr    ; Spins until the handler sets the flag")));
+    assert!(monitor.execute(&export, &mut machine).unwrap().starts_with("Exported 1 comments"));
+
+    assert_eq!(monitor.execute("comment Wait", &mut machine), Ok(String::from("Removed comment at 0x0024 Wait")));
+    assert_eq!(monitor.execute("comments", &mut machine), Ok(String::from("No comments")));
+    assert!(monitor.execute(&format!("comments import {}", path.display()), &mut machine).unwrap().starts_with("Imported 1 comments"));
+    assert_eq!(monitor.execute("comments", &mut machine), Ok(String::from("0x0024 Wait ; Spins until the handler sets the flag")));
+    // Back from the listing it was exported to
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_monitor_interrupts() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::parse("0x0040 Handler").unwrap());
+
+    assert_eq!(monitor.execute("int status", &mut machine), Ok(String::from("Interrupts enabled, RST 1 due in 16500 cycles")));
+    assert_eq!(monitor.execute("int raise 1", &mut machine), Ok(String::from("RST 1 taken, now at 0x0008")));
+    assert_eq!(monitor.execute("step 3", &mut machine), Ok(String::from("> 0043   32 20 00    STA adr")));
+    assert_eq!(monitor.execute("int status", &mut machine), Ok(String::from("Interrupts disabled, RST 1 due in 16472 cycles")));
+    assert_eq!(monitor.execute("int raise 1", &mut machine), Err(String::from("Interrupts are disabled, RST 1 wasn't taken")));
+    // Taking the interrupt disabled them until the handler's EI
+
+    assert_eq!(monitor.execute("int block", &mut machine), Ok(String::from("The board's interrupts are blocked")));
+    assert!(monitor.execute("int status", &mut machine).unwrap().ends_with(", the board's interrupts are blocked"));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(machine.cpu.registers().b, 1);
+    // Only the raised interrupt woke the wait loop
+    assert_eq!(monitor.execute("int block", &mut machine), Ok(String::from("The board's interrupts are back on")));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(machine.cpu.registers().b, 2);
+
+    assert_eq!(monitor.execute("int raise 9", &mut machine), Err(String::from("9 is not an RST number from 0 to 7")));
+}
+
+#[test]
+fn test_monitor_self_write_trap() {
+    let source: &str = "
+    LXI H,0x0000
+Loop: MVI M,0xff
+    JMP Loop
+";
+    let mut machine: Machine = fixtures::machine(source);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("0x0003 wrote over code at 0x0000"))));
+    assert!(monitor.is_paused());
+    assert_eq!(machine.cpu.memory.read_at(0x0000), 0x21);
+    // The write to the rom was ignored and the machine stopped on the instruction after it
+
+    assert_eq!(monitor.execute("trap", &mut machine), Ok(String::from("Writes over the rom or code are let through")));
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(monitor.execute("trap", &mut machine), Ok(String::from("Breaking on writes over the rom or code that has run")));
+}
+
+#[test]
+fn test_monitor_edits() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    monitor.pause(&machine);
+    assert_eq!(monitor.execute("poke 0x2100 1 2", &mut machine), Ok(String::from("Wrote 2 bytes at 0x2100")));
+    assert!(monitor.execute("set b 0x42", &mut machine).unwrap().contains("B=42"));
+    assert_eq!(monitor.execute("edits", &mut machine), Ok(String::from("0x2100: 00 00 -> 01 02\nb: 0000 -> 0042")));
+
+    assert_eq!(monitor.execute("undo", &mut machine), Ok(String::from("Undid b: 0000 -> 0042")));
+    assert_eq!(machine.cpu.registers().b, 0x00);
+    assert_eq!(monitor.execute("redo", &mut machine), Ok(String::from("Redid b: 0000 -> 0042")));
+    assert_eq!(machine.cpu.registers().b, 0x42);
+    assert_eq!(monitor.execute("revert", &mut machine), Ok(String::from("Reverted 2 edits")));
+    assert_eq!((machine.cpu.registers().b, machine.cpu.memory.read_at(0x2100)), (0x00, 0x00));
+    assert_eq!(monitor.execute("edits", &mut machine), Ok(String::from("No edits since the machine last ran")));
+    assert_eq!(monitor.execute("undo", &mut machine), Err(String::from("Nothing to undo")));
+
+    monitor.execute("poke 0x2100 5", &mut machine).unwrap();
+    assert_eq!(monitor.execute("autorevert", &mut machine), Ok(String::from("Edits are undone before the machine runs on")));
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(machine.cpu.memory.read_at(0x2100), 0x00);
+    // Carrying on put the poke back before the frame ran
+}
+
+#[test]
+fn test_monitor_breakpoint_counts() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    assert_eq!(monitor.execute("bw 0x2000 after 2", &mut machine), Ok(String::from("Breakpoint on write 0x2000, ignoring 2 more")));
+    assert_eq!(monitor.execute("bp 0x0040 once", &mut machine), Ok(String::from("Breakpoint at 0x0040, once")));
+
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped at 0x0040"))));
+    assert_eq!(monitor.execute("bl", &mut machine), Ok(String::from("#1 write 0x2000  hits 0, ignoring 2 more")));
+    // The one shot breakpoint is gone once it has stopped the machine
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(monitor.execute("bl", &mut machine), Ok(String::from("#1 write 0x2000  hits 2")));
+    monitor.execute("c", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("0x0043 wrote to 0x2000"))));
+    assert_eq!(monitor.execute("bl", &mut machine), Ok(String::from("#1 write 0x2000  hits 3")));
+    // Ignored hits are still counted, the third write is the first to stop
+
+    assert_eq!(monitor.execute("ignore 1 3", &mut machine), Ok(String::from("Letting the next 3 hits on write 0x2000 through")));
+    assert_eq!(monitor.execute("ignore 5 3", &mut machine), Err(String::from("5 is not a breakpoint number from bl")));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_monitor_session() {
+    let directory: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-monitor-session-{}", std::process::id()));
+    let rom_path: std::path::PathBuf = directory.join("wait.rom");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(&rom_path, fixtures::rom(fixtures::WAIT_FOR_INTERRUPT)).unwrap();
+    let open = || {
+        let mut machine: Machine = Machine::init();
+        machine.load_rom(&std::fs::read(&rom_path).unwrap()).unwrap();
+        machine
+    };
+    // Read back from the file the way the frontends load a rom
+
+    let mut machine: Machine = open();
+    let mut monitor: Monitor = Monitor::new(SymbolTable::parse("0x0040 Handler").unwrap());
+    assert_eq!(monitor.load_session(&directory, &machine), Ok(false));
+    monitor.execute("bp Handler after 1", &mut machine).unwrap();
+    monitor.execute("watch sp by 2 log", &mut machine).unwrap();
+    monitor.execute("comment 0x0024 Spins on the flag", &mut machine).unwrap();
+    assert_eq!(monitor.run_frame(&mut machine), Ok(None));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped at 0x0040 Handler"))));
+    // Interrupts are only enabled in time for the one at the end of the first frame
+    assert!(monitor.save_session(&directory, &machine).unwrap().exists());
+
+    let mut machine: Machine = open();
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+    assert_eq!(monitor.load_session(&directory, &machine), Ok(true));
+    assert_eq!(monitor.execute("bl", &mut machine), Ok(String::from("#1 0x0040 Handler  hits 0")));
+    assert_eq!(monitor.execute("watches", &mut machine), Ok(String::from("sp changing by more than 2, logged")));
+    assert_eq!(monitor.execute("comments", &mut machine), Ok(String::from("0x0024 ; Spins on the flag")));
+    assert_eq!(monitor.symbols().address("Handler"), Some(0x0040));
+    assert_eq!(monitor.run_frame(&mut machine), Ok(Some(String::from("Stopped at 0x0040 Handler"))));
+    // The reopened rom breaks on the first hit since the ignore count was used up before it was saved
+
+    let machine: Machine = fixtures::machine(fixtures::PORTS_WITH_INTERRUPTS);
+    assert_eq!(Monitor::new(SymbolTable::new()).load_session(&directory, &machine), Ok(false));
+    // Another rom has a session of its own
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_monitor_hex_viewer() {
+    let mut machine: Machine = fixtures::machine(fixtures::WAIT_FOR_INTERRUPT);
+    let mut monitor: Monitor = Monitor::new(SymbolTable::new());
+
+    monitor.move_hex_cursor(Move::Down);
+    assert_eq!(monitor.type_hex('4', &mut machine), Ok(()));
+    assert!(monitor.hex_lines(&machine).is_empty());
+    assert_eq!(monitor.debugger().hex_viewer().cursor(), 0x0000);
+    // Keys are left alone while the viewer is closed
+
+    let rows: String = monitor.execute("hex 0x2000 2", &mut machine).unwrap();
+    assert!(monitor.is_paused() && monitor.is_hex_open());
+    assert_eq!(monitor.hex_lines(&machine).join("\n"), rows);
+    monitor.move_hex_cursor(Move::Down);
+    monitor.move_hex_cursor(Move::Right);
+    monitor.type_hex('4', &mut machine).unwrap();
+    monitor.type_hex('B', &mut machine).unwrap();
+    assert_eq!(machine.cpu.memory.read_at(0x2011), 0x4b);
+    assert_eq!(monitor.debugger().hex_viewer().cursor(), 0x2012);
+    assert!(monitor.hex_lines(&machine)[1].starts_with("0x2010  00 4b[00]"));
+
+    monitor.execute("hex 0x0000", &mut machine).unwrap();
+    assert_eq!(monitor.type_hex('1', &mut machine), Err(String::from("0x0000 is read only, poke writes over it")));
+    assert_eq!(monitor.execute("undo", &mut machine), Ok(String::from("Undid 0x2011: 00 -> 4b")));
+    // Typed bytes go through the edit journal like pokes
+
+    monitor.execute("c", &mut machine).unwrap();
+    assert!(!monitor.is_hex_open());
+}
@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::cpu::{Cpu, Registers};
+use crate::headless;
+use crate::machine::{Event, Machine};
+
+mod tests;
+
+const FRESH: usize = 0b100;
+// Set on the middle slot's index when the machine has published something the observer hasn't taken yet
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    // The machine as it was at the end of a frame
+    pub frame: u64,
+    // Frames published since the observer was made, 0 until the first one ends
+    pub cycles: u64,
+    pub registers: Registers,
+    pub inputs: [u8; 2],
+    pub writes: Vec<(u8, u8)>,
+    // Port and value of every OUT in the frame, in order
+    pub ram_hash: u64,
+    // Same hash as --frame-hash uses, changes whenever anything writable in memory does
+}
+impl Snapshot {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            cycles: 0,
+            registers: Cpu::init().registers(),
+            inputs: [0, 0],
+            writes: vec![],
+            ram_hash: headless::FNV_OFFSET,
+        }
+    }
+}
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Slots {
+    // Triple buffer, the machine fills one slot while the observer reads another
+    //  and the third holds the newest finished snapshot for them to swap with
+    snapshots: [Mutex<Snapshot>; 3],
+    middle: AtomicUsize,
+    // Index of the slot neither side owns, with FRESH set when it's newer than the observer's
+}
+
+pub struct MachineObserver {
+    // Read only view of a running machine for other threads, an http server or stats overlay
+    //  Reading never blocks the machine, each side only ever locks the slot it owns
+    slots: Arc<Slots>,
+    front: usize,
+}
+impl MachineObserver {
+    pub fn updated(&self) -> bool {
+        // Whether a frame has ended since latest was last called
+        self.slots.middle.load(Ordering::Acquire) & FRESH != 0
+    }
+
+    pub fn latest(&mut self) -> Snapshot {
+        // Newest snapshot the machine has published
+
+        if self.updated() {
+            self.front = self.slots.middle.swap(self.front, Ordering::AcqRel) & !FRESH;
+        }
+        self.slots.snapshots[self.front].lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+struct Publisher {
+    slots: Arc<Slots>,
+    back: usize,
+    frame: u64,
+}
+impl Publisher {
+    fn publish(&mut self, machine: &Machine) {
+        self.frame += 1;
+        {
+            let mut snapshot = self.slots.snapshots[self.back].lock().unwrap_or_else(|e| e.into_inner());
+            snapshot.frame = self.frame;
+            snapshot.cycles = machine.cycles();
+            snapshot.registers = machine.cpu.registers();
+            snapshot.inputs = machine.hardware.inputs();
+            snapshot.writes.clear();
+            snapshot.writes.extend(machine.events().iter().filter_map(|event| match event {
+                Event::PortWrite(port, value) => Some((*port, *value)),
+                _ => None,
+            }));
+            // Reuses the slot's vec so publishing doesn't allocate once it has grown
+            snapshot.ram_hash = headless::ram_hash(machine);
+        }
+        self.back = self.slots.middle.swap(self.back | FRESH, Ordering::AcqRel) & !FRESH;
+    }
+}
+
+pub struct Observers {
+    // Publishing side of every observer handed out by a machine
+    publishers: Vec<Publisher>,
+}
+impl Observers {
+    pub fn new() -> Self {
+        Self { publishers: vec![] }
+    }
+
+    pub fn observer(&mut self) -> MachineObserver {
+        let slots: Arc<Slots> = Arc::new(Slots {
+            snapshots: [Mutex::new(Snapshot::new()), Mutex::new(Snapshot::new()), Mutex::new(Snapshot::new())],
+            middle: AtomicUsize::new(1),
+        });
+        self.publishers.push(Publisher { slots: Arc::clone(&slots), back: 0, frame: 0 });
+        MachineObserver { slots, front: 2 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.publishers.is_empty()
+    }
+
+    pub fn publish(&mut self, machine: &Machine) {
+        // Called at the end of every frame, observers that have been dropped stop being published to
+        self.publishers.retain(|publisher| Arc::strong_count(&publisher.slots) > 1);
+        for publisher in &mut self.publishers {
+            publisher.publish(machine);
+        }
+    }
+}
+impl Default for Observers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::new()
+        // A copy of the machine, a rewind point or a shadow run ahead, shouldn't show up in the original's observers
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::thread;
+#[cfg(test)]
+use crate::fixtures;
+
+#[cfg(test)]
+fn counted(snapshot: &Snapshot) -> bool {
+    // Every value written is one more than the last and A is at most one past the final write
+    let values: Vec<u8> = snapshot.writes.iter().map(|(_, value)| *value).collect();
+    values.windows(2).all(|pair| pair[1] == pair[0].wrapping_add(1))
+        && values.last().is_none_or(|last| snapshot.registers.a == *last || snapshot.registers.a == last.wrapping_add(1))
+}
+
+#[test]
+fn test_observer() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_TO_PORT);
+    let mut observer: MachineObserver = machine.observer();
+    assert!(!observer.updated());
+    assert_eq!(observer.latest(), Snapshot::new());
+
+    for _ in 0..3 {
+        machine.run_frame().unwrap();
+    }
+    assert!(observer.updated());
+    let snapshot: Snapshot = observer.latest();
+    assert!(!observer.updated());
+    assert_eq!(snapshot.frame, 3);
+    // Frames skipped while nobody looked are dropped, only the newest is kept
+    assert_eq!((snapshot.cycles, snapshot.registers), (machine.cycles(), machine.cpu.registers()));
+    assert_eq!(snapshot.ram_hash, headless::ram_hash(&machine));
+    assert!(!snapshot.writes.is_empty() && snapshot.writes.iter().all(|(port, _)| *port == 3));
+    assert!(counted(&snapshot));
+
+    let copy: Machine = machine.clone();
+    drop(copy);
+    machine.run_frame().unwrap();
+    assert_eq!(observer.latest().frame, 4);
+    // Copies don't publish to the original's observers
+
+    drop(observer);
+    machine.run_frame().unwrap();
+    assert_eq!(machine.observer().latest().frame, 0);
+}
+
+#[test]
+fn test_observer_thread() {
+    let mut machine: Machine = fixtures::machine(fixtures::COUNT_TO_PORT);
+    let mut observer: MachineObserver = machine.observer();
+    let reader = thread::spawn(move || {
+        let mut last: u64 = 0;
+        while last < 50 {
+            let snapshot: Snapshot = observer.latest();
+            assert!(snapshot.frame >= last);
+            assert!(counted(&snapshot));
+            // Every snapshot is whole, never half from one frame and half from the next
+            last = snapshot.frame;
+            thread::yield_now();
+        }
+    });
+    while !reader.is_finished() {
+        machine.run_frame().unwrap();
+    }
+    reader.join().unwrap();
+}
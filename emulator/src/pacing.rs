@@ -0,0 +1,51 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod tests;
+
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+// Sleeping can overshoot by about a scheduler tick so the last bit of each wait is spun
+//  Small enough that the spin barely shows up as cpu usage
+
+#[derive(Debug, Clone, Copy)]
+pub struct FramePacer {
+    // Keeps frames evenly spaced without burning a whole core waiting
+    frame_duration: Duration,
+    next_frame: Instant,
+}
+impl FramePacer {
+    pub fn new(frames_per_second: f64) -> Self {
+        let frame_duration: Duration = Duration::from_secs_f64(1.0 / frames_per_second);
+        Self {
+            frame_duration,
+            next_frame: Instant::now() + frame_duration,
+        }
+    }
+
+    pub fn frame_duration(&self) -> Duration {
+        self.frame_duration
+    }
+
+    pub fn wait(&mut self) -> Duration {
+        // Blocks until the next frame is due, returns how late it woke up
+
+        let now: Instant = Instant::now();
+        if self.next_frame > now + SPIN_MARGIN {
+            thread::sleep(self.next_frame - now - SPIN_MARGIN);
+        }
+        while Instant::now() < self.next_frame {
+            std::hint::spin_loop();
+        }
+
+        let woke: Instant = Instant::now();
+        let late: Duration = woke - self.next_frame;
+
+        self.next_frame += self.frame_duration;
+        if late > self.frame_duration {
+            self.next_frame = woke + self.frame_duration;
+        }
+        // When a frame runs long don't try to catch up by rushing the frames after it
+
+        late
+    }
+}
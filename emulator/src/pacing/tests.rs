@@ -0,0 +1,21 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_frame_pacing() {
+    let mut pacer: FramePacer = FramePacer::new(200.0);
+    let start: Instant = Instant::now();
+
+    for _ in 0..5 {
+        pacer.wait();
+    }
+    assert!(start.elapsed() >= pacer.frame_duration() * 5);
+    // Never early
+
+    thread::sleep(pacer.frame_duration() * 3);
+    pacer.wait();
+    let resumed: Instant = Instant::now();
+    pacer.wait();
+    assert!(resumed.elapsed() >= pacer.frame_duration() / 2);
+    // A long frame doesn't make the next one rush to catch up
+}
@@ -0,0 +1,60 @@
+use crate::cpu::Memory;
+use crate::hardware::input::{Binding, InputSource};
+use crate::primitives::KeyboardKey;
+use crate::profile::PaddleConfig;
+
+mod tests;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Paddle {
+    // Steers the player towards a target column by holding left or right
+    //  Bang-bang control, the player moves at full speed until it reaches the target
+    config: PaddleConfig,
+    left_key: KeyboardKey,
+    right_key: KeyboardKey,
+    held: Option<KeyboardKey>,
+}
+impl Paddle {
+    pub fn new(config: PaddleConfig, bindings: &[Binding]) -> Option<Self> {
+        // Needs keys bound to the left and right bits to press
+
+        let key_for = |bit: u8| bindings.iter()
+            .find(|binding| binding.input == config.input && binding.bit == bit)
+            .map(|binding| binding.key);
+
+        Some(Self {
+            config,
+            left_key: key_for(config.left_bit)?,
+            right_key: key_for(config.right_bit)?,
+            held: None,
+        })
+    }
+
+    pub fn update(&mut self, target: Option<u16>, memory: &Memory) {
+        // Works out which way to move for this frame, the player stops when there's no target
+
+        let centre: u16 = memory.read_at(self.config.address) as u16 + self.config.centre_offset as u16;
+        let dead_zone: u16 = self.config.dead_zone as u16;
+
+        self.held = match target {
+            Some(target) if target > centre + dead_zone => Some(self.right_key),
+            Some(target) if target + dead_zone < centre => Some(self.left_key),
+            _ => None,
+        };
+    }
+
+    pub fn held(&self) -> Option<KeyboardKey> {
+        self.held
+    }
+}
+
+pub struct WithPaddle<'a, S: InputSource> {
+    // Combines another input source with the paddle
+    pub source: &'a S,
+    pub paddle: Option<&'a Paddle>,
+}
+impl<S: InputSource> InputSource for WithPaddle<'_, S> {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.source.is_key_down(key) || self.paddle.is_some_and(|paddle| paddle.held == Some(key))
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::hardware::input::InputConfig;
+#[cfg(test)]
+use crate::profile::Profile;
+
+#[test]
+fn test_paddle() {
+    let config: PaddleConfig = Profile::space_invaders().paddle.expect("space invaders has a paddle");
+    let mut paddle: Paddle = Paddle::new(config, &InputConfig::default().bindings()).expect("left and right are bound");
+    let mut memory: Memory = Memory::init();
+    memory.poke(config.address, 100);
+    // Player centre is at column 108
+
+    paddle.update(Some(150), &memory);
+    assert_eq!(paddle.held(), Some(KeyboardKey::KEY_D));
+    paddle.update(Some(20), &memory);
+    assert_eq!(paddle.held(), Some(KeyboardKey::KEY_A));
+    paddle.update(Some(109), &memory);
+    assert_eq!(paddle.held(), None);
+    // Inside the dead zone
+    paddle.update(None, &memory);
+    assert_eq!(paddle.held(), None);
+
+    assert!(Paddle::new(config, &[]).is_none());
+}
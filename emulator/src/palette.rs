@@ -0,0 +1,177 @@
+use crate::display::{Framebuffer, Geometry};
+use crate::primitives::Color;
+use crate::profile::Overlay;
+
+mod tests;
+
+const COLOURBLIND: [&str; 7] = ["E69F00", "56B4E9", "F0E442", "009E73", "0072B2", "D55E00", "CC79A7"];
+// Okabe and Ito's colours, told apart with any kind of colour blindness
+pub const FLASH_STEP: f32 = 0.04;
+// Most the lit part of the screen's average luminance can rise from one frame to the next with flashes reduced
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    #[default]
+    Overlay,
+    // The profile's overlay as it is
+    HighContrast,
+    // Every lit pixel white on black
+    Colourblind,
+    // Each overlay colour swapped for one from COLOURBLIND
+}
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Overlay, Preset::HighContrast, Preset::Colourblind];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Overlay => "overlay",
+            Preset::HighContrast => "high_contrast",
+            Preset::Colourblind => "colourblind",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::ALL.into_iter()
+            .find(|preset| preset.name() == text.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("{} is not a palette, try one of {}", text, Self::ALL.map(|preset| preset.name()).join(", ")))
+    }
+
+    pub fn apply(&self, overlay: &Overlay) -> Overlay {
+        // The overlay with its colours replaced, bands keep their shapes so the screen is still split up the same way
+
+        let mut applied: Overlay = overlay.clone();
+        match self {
+            Preset::Overlay => {},
+            Preset::HighContrast => {
+                applied.background = String::from("000000");
+                applied.default = String::from("FFFFFF");
+                for band in applied.bands.iter_mut() {
+                    band.colour = String::from("FFFFFF");
+                }
+            },
+            Preset::Colourblind => {
+                applied.background = String::from("000000");
+                applied.default = String::from("FFFFFF");
+                let mut seen: Vec<String> = vec![overlay.default.to_ascii_uppercase()];
+                for band in applied.bands.iter_mut() {
+                    let colour: String = band.colour.to_ascii_uppercase();
+                    let index: usize = seen.iter().position(|seen| *seen == colour).unwrap_or_else(|| {
+                        seen.push(colour);
+                        seen.len() - 1
+                    });
+                    band.colour = match index {
+                        0 => String::from("FFFFFF"),
+                        // Bands the same colour as the default stay the same as the default
+                        _ => String::from(COLOURBLIND[(index - 1) % COLOURBLIND.len()]),
+                    };
+                }
+            },
+        }
+        applied
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Palette {
+    // Colours of the overlay worked out for every pixel ahead of time
+    //  so rendering doesn't parse colours or search the overlay bands
+    pub background: Color,
+    lit: Vec<Color>,
+    // Colour of each pixel when it is on, row by row from the bottom of the screen
+    width: u16,
+    height: u16,
+    brightness: f32,
+    // Lit pixels are scaled by this, below 1 while a flash is being damped
+}
+impl Palette {
+    pub fn new(overlay: &Overlay) -> Result<Self, String> {
+        Self::for_screen(overlay, Geometry::space_invaders())
+    }
+
+    pub fn for_screen(overlay: &Overlay, geometry: Geometry) -> Result<Self, String> {
+        let parse = |hex: &str| Color::from_hex(hex).map_err(|_| format!("{} is not a colour", hex));
+
+        let (width, height): (u16, u16) = (geometry.width.max(1), geometry.height.max(1));
+        let mut lit: Vec<Color> = Vec::with_capacity(width as usize * height as usize);
+        for row in 0..height {
+            for column in 0..width {
+                lit.push(parse(overlay.colour_at(row, column))?);
+            }
+        }
+
+        Ok(Self {
+            background: parse(&overlay.background)?,
+            lit,
+            width,
+            height,
+            brightness: 1.0,
+        })
+    }
+
+    fn index(&self, row: u16, column: u16) -> usize {
+        // The overlay only covers the screen, anything past it uses the last row or column
+        row.min(self.height - 1) as usize * self.width as usize + column.min(self.width - 1) as usize
+    }
+
+    pub fn lit(&self, row: u16, column: u16) -> Color {
+        let colour: Color = self.lit[self.index(row, column)];
+        if self.brightness >= 1.0 {
+            return colour;
+        }
+        let scale = |channel: u8| (channel as f32 * self.brightness).round() as u8;
+        Color { r: scale(colour.r), g: scale(colour.g), b: scale(colour.b), a: colour.a }
+    }
+
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+    }
+
+    pub fn luminance(&self, framebuffer: &Framebuffer) -> f32 {
+        // Average luminance the lit pixels add to the whole screen, from 0 for nothing lit to 1 for all white
+        //  Worked out at full brightness so damping a flash doesn't feed back into measuring it
+
+        let height: u16 = framebuffer.height();
+        let total: f32 = framebuffer.lit_pixels()
+            .map(|(x, y)| luminance(self.lit[self.index(height - 1 - y, x)]))
+            .sum();
+        total / (framebuffer.width() as f32 * height as f32).max(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlashFilter {
+    // Damps sudden flashes for players sensitive to them
+    //  When the screen gets brighter faster than FLASH_STEP a frame the lit pixels are dimmed so the
+    //  brightness shown only climbs by FLASH_STEP, getting darker is never held back
+    shown: Option<f32>,
+    // Luminance drawn last frame
+}
+impl FlashFilter {
+    pub fn new() -> Self {
+        Self { shown: None }
+    }
+
+    pub fn filter(&mut self, luminance: f32) -> f32 {
+        // Takes the luminance of the frame about to be drawn and returns the brightness to draw it at
+
+        let shown: f32 = match self.shown {
+            Some(shown) if luminance > shown + FLASH_STEP => shown + FLASH_STEP,
+            _ => luminance,
+        };
+        self.shown = Some(shown);
+        match luminance > 0.0 {
+            true => (shown / luminance).min(1.0),
+            false => 1.0,
+        }
+    }
+}
+impl Default for FlashFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn luminance(colour: Color) -> f32 {
+    // Relative luminance without gamma correction, close enough for comparing frames
+    (0.2126 * colour.r as f32 + 0.7152 * colour.g as f32 + 0.0722 * colour.b as f32) / 255.0
+}
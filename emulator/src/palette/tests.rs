@@ -0,0 +1,49 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::profile::Profile;
+
+#[test]
+fn test_presets() {
+    let overlay: Overlay = Profile::space_invaders().overlay;
+    assert_eq!(Preset::Overlay.apply(&overlay), overlay);
+    assert_eq!(Preset::parse("High_Contrast"), Ok(Preset::HighContrast));
+    assert!(Preset::parse("sepia").is_err());
+
+    let high_contrast: Overlay = Preset::HighContrast.apply(&overlay);
+    assert_eq!(high_contrast.bands.len(), overlay.bands.len());
+    assert!(high_contrast.bands.iter().all(|band| band.colour == "FFFFFF"));
+
+    let colourblind: Overlay = Preset::Colourblind.apply(&overlay);
+    assert_eq!(colourblind.colour_at(215, 100), COLOURBLIND[0]);
+    assert_eq!(colourblind.colour_at(4, 100), COLOURBLIND[1]);
+    assert_eq!(colourblind.colour_at(40, 100), COLOURBLIND[1]);
+    // Bands that shared a colour still share one
+    assert_eq!(colourblind.colour_at(120, 100), "FFFFFF");
+    assert!(Palette::new(&colourblind).is_ok());
+}
+
+#[test]
+fn test_flash_filter() {
+    let mut palette: Palette = Palette::new(&Preset::HighContrast.apply(&Profile::space_invaders().overlay)).unwrap();
+    let mut vram: Vec<u8> = vec![0; 0x1c00];
+    assert_eq!(palette.luminance(&Framebuffer::new(Geometry::space_invaders(), &vram)), 0.0);
+    vram.fill(0xff);
+    assert!((palette.luminance(&Framebuffer::new(Geometry::space_invaders(), &vram)) - 1.0).abs() < 0.001);
+    vram[..0xe00].fill(0);
+    assert!((palette.luminance(&Framebuffer::new(Geometry::space_invaders(), &vram)) - 0.5).abs() < 0.001);
+    // Half the columns lit
+
+    let mut filter: FlashFilter = FlashFilter::new();
+    assert_eq!(filter.filter(0.1), 1.0);
+    let brightness: f32 = filter.filter(0.5);
+    assert!((brightness - (0.1 + FLASH_STEP) / 0.5).abs() < 0.001);
+    // A flash from 10% to half the screen is held to one step brighter
+    assert!(filter.filter(0.5) > brightness);
+    assert_eq!(filter.filter(0.0), 1.0);
+    // Going dark is never held back
+
+    palette.set_brightness(0.5);
+    assert_eq!(palette.lit(0, 0), Color { r: 128, g: 128, b: 128, a: 255 });
+    assert!((palette.luminance(&Framebuffer::new(Geometry::space_invaders(), &vram)) - 0.5).abs() < 0.001);
+}
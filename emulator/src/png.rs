@@ -0,0 +1,164 @@
+mod tests;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const MAX_STORED_BLOCK: usize = 0xffff;
+// Deflate stored blocks hold at most this many bytes
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    // CRC used by every png chunk, worked out a bit at a time since images are only saved occasionally
+
+    let mut crc: u32 = 0xffff_ffff;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    // Checksum at the end of a zlib stream
+
+    let (mut a, mut b): (u32, u32) = (1, 0);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Wraps data in a zlib stream without compressing it
+    //  Bigger files but no compression library is needed
+
+    let mut stream: Vec<u8> = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    // An empty stream still needs a final block
+    while let Some(block) = blocks.next() {
+        let last: u8 = blocks.peek().is_none() as u8;
+        let length: u16 = block.len() as u16;
+        stream.push(last);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start: usize = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc: u32 = crc32(&png[start..]);
+    // Covers the chunk type and data but not the length
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn encode_rgb(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, &'static str> {
+    // Encodes 8 bit rgb pixels, row by row from the top left, as a png
+
+    if pixels.len() != width as usize * height as usize * 3 {
+        return Err("Pixel data doesn't match the image size");
+    }
+
+    let mut header: Vec<u8> = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    // 8 bit depth, truecolour, default compression and filtering, not interlaced
+
+    let mut scanlines: Vec<u8> = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize * 3).filter(|_| width > 0) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    // Each row starts with its filter type, 0 leaves the row as it is
+
+    let mut png: Vec<u8> = SIGNATURE.to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    push_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+pub fn decode_rgb(png: &[u8]) -> Result<(u32, u32, Vec<u8>), &'static str> {
+    // Reads back a png written by encode_rgb, returns its width, height and pixels
+    //  Only uncompressed 8 bit rgb images with unfiltered rows can be read, enough for golden frames
+
+    let mut rest: &[u8] = png.strip_prefix(&SIGNATURE).ok_or("Not a png")?;
+    let mut header: Option<&[u8]> = None;
+    let mut stream: Vec<u8> = vec![];
+    while rest.len() >= 12 {
+        let length: usize = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let chunk: &[u8] = rest.get(4..8 + length).ok_or("Chunk runs past the end of the file")?;
+        let crc: &[u8] = rest.get(8 + length..12 + length).ok_or("Chunk runs past the end of the file")?;
+        if crc32(chunk).to_be_bytes() != crc {
+            return Err("Chunk checksum doesn't match");
+        }
+        match &chunk[..4] {
+            b"IHDR" => header = Some(&chunk[4..]),
+            b"IDAT" => stream.extend_from_slice(&chunk[4..]),
+            b"IEND" => break,
+            _ => {},
+        }
+        rest = &rest[12 + length..];
+    }
+
+    let header: &[u8] = header.filter(|header| header.len() == 13).ok_or("Missing image header")?;
+    if header[8..] != [8, 2, 0, 0, 0] {
+        return Err("Only 8 bit rgb pngs can be read");
+    }
+    let width: u32 = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let height: u32 = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+    let scanlines: Vec<u8> = zlib_unstored(&stream)?;
+    let row: usize = width as usize * 3 + 1;
+    if scanlines.len() != row * height as usize {
+        return Err("Pixel data doesn't match the image size");
+    }
+    let mut pixels: Vec<u8> = Vec::with_capacity(scanlines.len() - height as usize);
+    for scanline in scanlines.chunks(row) {
+        if scanline[0] != 0 {
+            return Err("Only unfiltered pngs can be read");
+        }
+        pixels.extend_from_slice(&scanline[1..]);
+    }
+    Ok((width, height, pixels))
+}
+
+fn zlib_unstored(stream: &[u8]) -> Result<Vec<u8>, &'static str> {
+    // The data from a zlib stream of stored blocks, the opposite of zlib_stored
+
+    let mut rest: &[u8] = stream.get(2..).ok_or("Image data is too short")?;
+    let mut data: Vec<u8> = vec![];
+    loop {
+        let (header, length, inverse): (u8, u16, u16) = match rest {
+            [header, a, b, c, d, ..] => (*header, u16::from_le_bytes([*a, *b]), u16::from_le_bytes([*c, *d])),
+            _ => return Err("Image data is too short"),
+        };
+        if header & 0b110 != 0 {
+            return Err("Only uncompressed pngs can be read");
+        }
+        if length != !inverse {
+            return Err("Image data is corrupt");
+        }
+        let block: &[u8] = rest.get(5..5 + length as usize).ok_or("Image data is too short")?;
+        data.extend_from_slice(block);
+        rest = &rest[5 + length as usize..];
+        if header & 1 == 1 {
+            break;
+        }
+    }
+    // The last block has its lowest bit set
+
+    match rest.get(..4) {
+        Some(checksum) if *checksum == adler32(&data).to_be_bytes() => Ok(data),
+        _ => Err("Image data checksum doesn't match"),
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_checksums() {
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn test_encode_rgb() {
+    let pixels: Vec<u8> = vec![0xff, 0x00, 0x00, 0x00, 0xff, 0x00];
+    let png: Vec<u8> = encode_rgb(2, 1, &pixels).unwrap();
+
+    assert_eq!(png[..8], SIGNATURE);
+    assert_eq!(png[12..16], *b"IHDR");
+    assert_eq!(png[16..24], [0, 0, 0, 2, 0, 0, 0, 1]);
+    assert_eq!(png[png.len() - 12..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+
+    let idat: usize = 8 + 25;
+    // Straight after the 13 byte header chunk
+    assert_eq!(png[idat + 4..idat + 8], *b"IDAT");
+    assert_eq!(png[idat + 8..idat + 15], [0x78, 0x01, 0x01, 7, 0, !7, 0xff]);
+    assert_eq!(png[idat + 15..idat + 22], [0, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00]);
+    // One final stored block holding the filter byte and the row
+
+    assert!(encode_rgb(2, 2, &pixels).is_err());
+}
+
+#[test]
+fn test_zlib_stored_blocks() {
+    let data: Vec<u8> = vec![7; MAX_STORED_BLOCK + 10];
+    let stream: Vec<u8> = zlib_stored(&data);
+
+    assert_eq!(stream[2..7], [0x00, 0xff, 0xff, 0x00, 0x00]);
+    let second: usize = 7 + MAX_STORED_BLOCK;
+    assert_eq!(stream[second..second + 5], [0x01, 10, 0, !10, 0xff]);
+    assert_eq!(stream.len(), 2 + 5 * 2 + data.len() + 4);
+    assert_eq!(zlib_stored(&[]), [0x78, 0x01, 0x01, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01]);
+}
+
+#[test]
+fn test_decode_rgb() {
+    let pixels: Vec<u8> = (0..200 * 120 * 3).map(|index| (index % 251) as u8).collect();
+    let png: Vec<u8> = encode_rgb(200, 120, &pixels).unwrap();
+    assert_eq!(decode_rgb(&png), Ok((200, 120, pixels)));
+    // Big enough to be split over two stored blocks
+
+    let mut corrupt: Vec<u8> = png.clone();
+    corrupt[60] ^= 1;
+    assert!(decode_rgb(&corrupt).is_err());
+    assert_eq!(decode_rgb(&png[1..]), Err("Not a png"));
+}
@@ -0,0 +1,120 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::EmulatorError;
+use crate::headless;
+use crate::machine::{Event, Machine};
+use crate::versioned;
+
+mod tests;
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access {
+    // One IN or OUT as the rom saw it
+    pub cycle: u64,
+    // Cycles since the machine was created when the instruction started
+    pub direction: Direction,
+    pub port: u8,
+    pub value: u8,
+    // What the hardware gave back for an IN, what the rom wrote for an OUT
+}
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.direction {
+            Direction::In => write!(f, "cycle {}  IN {} -> 0x{:02x}", self.cycle, self.port, self.value),
+            Direction::Out => write!(f, "cycle {}  OUT {} <- 0x{:02x}", self.cycle, self.port, self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortLog {
+    // Every port access of a run in order, for checking a change to the hardware against a run from before it
+    //  Recorded once with the old hardware then compared against the same inputs on the new one,
+    //  any read that comes back different or write that moves shows up as the first access that differs
+    pub version: u32,
+    pub rom_hash: String,
+    pub accesses: Vec<Access>,
+}
+impl PortLog {
+    pub fn new(rom: &[u8]) -> Self {
+        Self {
+            version: VERSION,
+            rom_hash: headless::rom_hash(rom),
+            accesses: vec![],
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        versioned::from_json(text, "port log", VERSION, &[])
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map(|json| json + "\n").map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn collect(&mut self, machine: &Machine, cycle: u64) {
+        // Adds the accesses made by the last call to step_frame, cycle is when it started
+        for event in machine.last_events() {
+            match event {
+                Event::PortRead(port) => self.accesses.push(Access { cycle, direction: Direction::In, port: *port, value: machine.cpu.a.value }),
+                // IN leaves what it read in A and servicing an interrupt after it doesn't touch A
+                Event::PortWrite(port, value) => self.accesses.push(Access { cycle, direction: Direction::Out, port: *port, value: *value }),
+                _ => {},
+            }
+        }
+    }
+
+    pub fn run_frame(&mut self, machine: &mut Machine) -> Result<(), EmulatorError> {
+        // The same as machine.run_frame, keeping every access along the way
+        if machine.hardware.paused() {
+            return Ok(());
+        }
+        loop {
+            let cycle: u64 = machine.cycles();
+            let frame_done: bool = machine.step_frame()?;
+            self.collect(machine, cycle);
+            if frame_done {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn compare(&self, actual: &PortLog) -> Result<(), String> {
+        // Ok when both runs made the same accesses at the same cycles, otherwise the first that differs
+
+        if self.rom_hash != actual.rom_hash {
+            return Err(format!("Recorded with rom {} but ran {}", self.rom_hash, actual.rom_hash));
+        }
+        let differs: Option<usize> = self.accesses.iter().zip(&actual.accesses).position(|(expected, actual)| expected != actual);
+        if let Some(index) = differs {
+            return Err(format!("Port access {} differs, expected {} but got {}", index + 1, self.accesses[index], actual.accesses[index]));
+        }
+        match self.accesses.len().cmp(&actual.accesses.len()) {
+            Ordering::Equal => Ok(()),
+            Ordering::Greater => Err(format!("Expected {} port accesses but got {}, the first missing is {}", self.accesses.len(), actual.accesses.len(), self.accesses[actual.accesses.len()])),
+            Ordering::Less => Err(format!("Expected {} port accesses but got {}, the first extra is {}", self.accesses.len(), actual.accesses.len(), actual.accesses[self.accesses.len()])),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+const ECHO: [u8; 8] = [
+    0xf3,             // 0x0000 DI
+    0xdb, 0x01,       // 0x0001 IN 1
+    0xd3, 0x03,       // 0x0003 OUT 3
+    0xc3, 0x01, 0x00, // 0x0005 JMP 0x0001
+];
+
+#[cfg(test)]
+fn echo_log(inputs: [u8; 2], frames: u32) -> PortLog {
+    // Writes whatever it reads from port 1 straight back out on port 3
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&ECHO).unwrap();
+    machine.hardware.set_inputs(inputs);
+    let mut log: PortLog = PortLog::new(&ECHO);
+    for _ in 0..frames {
+        log.run_frame(&mut machine).unwrap();
+    }
+    log
+}
+
+#[test]
+fn test_record() {
+    let log: PortLog = echo_log([0; 2], 1);
+    let read: u8 = log.accesses[0].value;
+    assert_eq!(log.accesses[0], Access { cycle: 4, direction: Direction::In, port: 1, value: read });
+    assert_eq!(log.accesses[1], Access { cycle: 14, direction: Direction::Out, port: 3, value: read });
+    // DI takes 4 cycles and IN 10
+    assert!(log.accesses.len() > 1000);
+    assert_eq!(log.accesses[1].to_string(), format!("cycle 14  OUT 3 <- 0x{:02x}", read));
+
+    assert_eq!(PortLog::from_json(&log.to_json().unwrap()), Ok(log));
+}
+
+#[test]
+fn test_compare() {
+    let reference: PortLog = echo_log([0; 2], 2);
+    assert_eq!(reference.compare(&echo_log([0; 2], 2)), Ok(()));
+
+    let pressed: PortLog = echo_log([0xff; 2], 2);
+    assert!(reference.compare(&pressed).is_err_and(|e| e.starts_with("Port access 1 differs, expected cycle 4  IN 1")), "{:?}", reference.compare(&pressed));
+
+    let shorter: PortLog = echo_log([0; 2], 1);
+    assert!(reference.compare(&shorter).is_err_and(|e| e.contains("the first missing is")));
+    assert!(shorter.compare(&reference).is_err_and(|e| e.contains("the first extra is")));
+
+    let mut other_rom: PortLog = echo_log([0; 2], 2);
+    other_rom.rom_hash = String::from("0000000000000000");
+    assert!(reference.compare(&other_rom).is_err_and(|e| e.starts_with("Recorded with rom")));
+}
@@ -0,0 +1,341 @@
+use std::fmt;
+
+use crate::display::Framebuffer;
+use crate::palette::{FlashFilter, Palette};
+use crate::primitives::Color;
+
+mod tests;
+
+const SCANLINE_BRIGHTNESS: f32 = 0.6;
+// Every other row is drawn this bright, like the gaps between a CRT's lines
+const PERSISTENCE: f32 = 0.5;
+// How much of the last frame is left glowing, a pixel that goes out fades by this much a frame
+const VIGNETTE: f32 = 0.3;
+// Brightness lost in the corners at full intensity, tubes were dimmer away from the middle
+const BLOOM: f32 = 0.35;
+// How much of a lit pixel spills onto the unlit ones beside it at full intensity
+pub const CRT_INTENSITY: u8 = 50;
+// Percent used when crt is given without one
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    // Rgba pixels row by row from the top left, as the screen is seen in the cabinet
+    //  Unlit pixels are transparent so the background shows through them
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn from_screen(framebuffer: &Framebuffer, flipped: bool) -> Self {
+        // Lit pixels in white, flipped turns the picture round for player 2 on a cocktail cabinet
+        let (width, height): (u16, u16) = (framebuffer.width(), framebuffer.height());
+        let mut frame: Frame = Frame::new(width, height);
+        for (x, y) in framebuffer.lit_pixels() {
+            let (x, y): (u16, u16) = match flipped {
+                false => (x, y),
+                true => (width - 1 - x, height - 1 - y),
+            };
+            frame.set(x, y, [0xff; 4]);
+        }
+        frame
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> [u8; 4] {
+        let index: usize = self.index(x, y);
+        [self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3]]
+    }
+
+    pub fn set(&mut self, x: u16, y: u16, rgba: [u8; 4]) {
+        let index: usize = self.index(x, y);
+        self.pixels[index..index + 4].copy_from_slice(&rgba);
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = (u16, u16, Color)> + '_ {
+        // Every pixel that isn't transparent with its position
+        let width: usize = self.width.max(1) as usize;
+        self.pixels.chunks(4)
+            .enumerate()
+            .filter(|(_, rgba)| rgba[3] > 0)
+            .map(move |(index, rgba)| ((index % width) as u16, (index / width) as u16, Color { r: rgba[0], g: rgba[1], b: rgba[2], a: rgba[3] }))
+    }
+
+    pub fn map(&self, change: impl Fn(u16, u16, [u8; 4]) -> [u8; 4]) -> Frame {
+        // A copy with every pixel changed, given its position
+        let mut frame: Frame = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                frame.set(x, y, change(x, y, self.get(x, y)));
+            }
+        }
+        frame
+    }
+
+    pub fn luminance(&self) -> f32 {
+        // Average luminance over the whole frame, from 0 for nothing lit to 1 for all white
+        let total: f32 = self.pixels.chunks(4)
+            .map(|rgba| (0.2126 * rgba[0] as f32 + 0.7152 * rgba[1] as f32 + 0.0722 * rgba[2] as f32) / 255.0 * rgba[3] as f32 / 255.0)
+            .sum();
+        total / (self.width as f32 * self.height as f32).max(1.0)
+    }
+}
+
+fn scale([r, g, b, a]: [u8; 4], by: f32) -> [u8; 4] {
+    // Dims the colour, the alpha is left alone so a dimmed pixel still covers the background
+    let channel = |value: u8| (value as f32 * by).round().clamp(0.0, 255.0) as u8;
+    [channel(r), channel(g), channel(b), a]
+}
+
+pub trait PostProcessor: fmt::Debug {
+    // One step between the screen as the game drew it and the picture in the window
+    //  Each gets the frame the step before it made and can keep whatever it needs between frames
+
+    fn name(&self) -> &str;
+    fn process(&mut self, input: &Frame) -> Frame;
+}
+
+#[derive(Debug, Clone)]
+pub struct OverlayColour {
+    // The coloured gel stuck over the monitor, it doesn't turn with a flipped picture
+    palette: Palette,
+}
+impl OverlayColour {
+    pub fn new(palette: Palette) -> Self {
+        Self { palette }
+    }
+}
+impl PostProcessor for OverlayColour {
+    fn name(&self) -> &str {
+        "overlay"
+    }
+
+    fn process(&mut self, input: &Frame) -> Frame {
+        let height: u16 = input.height();
+        input.map(|x, y, [r, g, b, a]| {
+            let colour: Color = self.palette.lit(height - 1 - y, x);
+            // Overlay rows count up from the bottom
+            let tint = |value: u8, by: u8| (value as u16 * by as u16 / 0xff) as u8;
+            [tint(r, colour.r), tint(g, colour.g), tint(b, colour.b), a]
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Scanlines;
+impl PostProcessor for Scanlines {
+    fn name(&self) -> &str {
+        "scanlines"
+    }
+
+    fn process(&mut self, input: &Frame) -> Frame {
+        input.map(|_, y, rgba| if y % 2 == 1 { scale(rgba, SCANLINE_BRIGHTNESS) } else { rgba })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Blend {
+    // Phosphor glow, pixels fade out over a few frames instead of going out at once
+    //  Softens the flicker of sprites the game only draws every other frame
+    previous: Option<Frame>,
+}
+impl Blend {
+    pub fn new() -> Self {
+        Self { previous: None }
+    }
+}
+impl PostProcessor for Blend {
+    fn name(&self) -> &str {
+        "blend"
+    }
+
+    fn process(&mut self, input: &Frame) -> Frame {
+        let output: Frame = match self.previous.as_ref().filter(|previous| (previous.width, previous.height) == (input.width, input.height)) {
+            Some(previous) => input.map(|x, y, rgba| {
+                let faded: [u8; 4] = previous.get(x, y).map(|channel| (channel as f32 * PERSISTENCE) as u8);
+                [0, 1, 2, 3].map(|channel| rgba[channel].max(faded[channel]))
+            }),
+            None => input.clone(),
+        };
+        self.previous = Some(output.clone());
+        output
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Crt {
+    // Brightness falling off towards the edges of the tube and a slight bloom around lit pixels
+    //  Goes with scanlines for the look of the cabinet's monitor, intensity scales both from 0 to 1
+    intensity: f32,
+}
+impl Crt {
+    pub fn new(percent: u8) -> Self {
+        Self { intensity: percent.min(100) as f32 / 100.0 }
+    }
+}
+impl PostProcessor for Crt {
+    fn name(&self) -> &str {
+        "crt"
+    }
+
+    fn process(&mut self, input: &Frame) -> Frame {
+        let (width, height): (u16, u16) = (input.width(), input.height());
+        let (centre_x, centre_y): (f32, f32) = (width as f32 / 2.0, height as f32 / 2.0);
+        let bloom: f32 = BLOOM * self.intensity;
+
+        input.map(|x, y, rgba| {
+            let neighbours: [[u8; 4]; 4] = [
+                if x > 0 { input.get(x - 1, y) } else { [0; 4] },
+                if x + 1 < width { input.get(x + 1, y) } else { [0; 4] },
+                if y > 0 { input.get(x, y - 1) } else { [0; 4] },
+                if y + 1 < height { input.get(x, y + 1) } else { [0; 4] },
+            ];
+            let glow: [u8; 4] = [0, 1, 2, 3].map(|channel| {
+                let brightest: u8 = neighbours.iter().map(|neighbour| neighbour[channel]).max().unwrap_or(0);
+                (brightest as f32 * bloom) as u8
+            });
+            let bloomed: [u8; 4] = [0, 1, 2, 3].map(|channel| rgba[channel].max(glow[channel]));
+            // The glow's alpha comes with it so unlit pixels it reaches only tint the background
+
+            let (dx, dy): (f32, f32) = ((x as f32 + 0.5 - centre_x) / centre_x.max(1.0), (y as f32 + 0.5 - centre_y) / centre_y.max(1.0));
+            let falloff: f32 = 1.0 - VIGNETTE * self.intensity * (dx * dx + dy * dy) / 2.0;
+            // 1 in the middle down to the full vignette in the corners
+            scale(bloomed, falloff)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlashDamping {
+    // Dims frames that get brighter too fast, see FlashFilter
+    filter: FlashFilter,
+}
+impl FlashDamping {
+    pub fn new() -> Self {
+        Self { filter: FlashFilter::new() }
+    }
+}
+impl PostProcessor for FlashDamping {
+    fn name(&self) -> &str {
+        "flash_damping"
+    }
+
+    fn process(&mut self, input: &Frame) -> Frame {
+        let brightness: f32 = self.filter.filter(input.luminance());
+        match brightness >= 1.0 {
+            true => input.clone(),
+            false => input.map(|_, _, rgba| scale(rgba, brightness)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Overlay,
+    Scanlines,
+    Blend,
+    FlashDamping,
+    Crt(u8),
+    // Intensity in percent
+}
+impl Effect {
+    pub const ALL: [Effect; 5] = [Effect::Overlay, Effect::Scanlines, Effect::Blend, Effect::FlashDamping, Effect::Crt(CRT_INTENSITY)];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Overlay => "overlay",
+            Effect::Scanlines => "scanlines",
+            Effect::Blend => "blend",
+            Effect::FlashDamping => "flash_damping",
+            Effect::Crt(_) => "crt",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        // The crt's intensity can follow a colon, e.g. "crt:30"
+
+        let (name, intensity): (&str, Option<&str>) = match text.trim().split_once(':') {
+            Some((name, intensity)) => (name.trim(), Some(intensity.trim())),
+            None => (text.trim(), None),
+        };
+        let effect: Effect = Effect::ALL.into_iter()
+            .find(|effect| effect.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("{} is not an effect, try one of overlay, scanlines, blend, flash_damping or crt", text))?;
+
+        match (effect, intensity) {
+            (_, None) => Ok(effect),
+            (Effect::Crt(_), Some(intensity)) => match intensity.parse::<u8>() {
+                Ok(percent) if percent <= 100 => Ok(Effect::Crt(percent)),
+                _ => Err(format!("{} is not an intensity, give a percent like crt:30", intensity)),
+            },
+            (_, Some(_)) => Err(format!("{} doesn't take an intensity", effect.name())),
+        }
+    }
+
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, String> {
+        // Comma separated, e.g. "blend,scanlines"
+        text.split(',').map(Effect::parse).collect()
+    }
+
+    pub fn processor(&self, palette: &Palette) -> Box<dyn PostProcessor> {
+        match self {
+            Effect::Overlay => Box::new(OverlayColour::new(palette.clone())),
+            Effect::Scanlines => Box::new(Scanlines),
+            Effect::Blend => Box::new(Blend::new()),
+            Effect::FlashDamping => Box::new(FlashDamping::new()),
+            Effect::Crt(percent) => Box::new(Crt::new(*percent)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Chain {
+    // Post processors run in order, each on what the last one made
+    processors: Vec<Box<dyn PostProcessor>>,
+}
+impl Chain {
+    pub fn new() -> Self {
+        Self { processors: vec![] }
+    }
+
+    pub fn build(effects: &[Effect], palette: &Palette) -> Self {
+        // Each effect once, in the order given
+        let mut chain: Chain = Chain::new();
+        for (index, effect) in effects.iter().enumerate() {
+            if !effects[..index].iter().any(|earlier| earlier.name() == effect.name()) {
+                chain.push(effect.processor(palette));
+            }
+        }
+        chain
+    }
+
+    pub fn push(&mut self, processor: Box<dyn PostProcessor>) {
+        self.processors.push(processor);
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.iter().map(|processor| processor.name()).collect()
+    }
+
+    pub fn process(&mut self, frame: Frame) -> Frame {
+        self.processors.iter_mut().fold(frame, |frame, processor| processor.process(&frame))
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::display::Geometry;
+#[cfg(test)]
+use crate::palette::{Preset, FLASH_STEP};
+#[cfg(test)]
+use crate::profile::Profile;
+
+#[cfg(test)]
+const WHITE: [u8; 4] = [0xff; 4];
+
+#[cfg(test)]
+fn filled(width: u16, height: u16, rgba: [u8; 4]) -> Frame {
+    Frame::new(width, height).map(|_, _, _| rgba)
+}
+
+#[test]
+fn test_frame() {
+    let mut vram: Vec<u8> = vec![0; 0x1c00];
+    vram[0] = 0x01;
+    // The bottom left pixel, the screen is stored rotated
+    let framebuffer: Framebuffer = Framebuffer::new(Geometry::space_invaders(), &vram);
+    let frame: Frame = Frame::from_screen(&framebuffer, false);
+    assert_eq!((frame.width(), frame.height()), (224, 256));
+    assert_eq!(frame.pixels().map(|(x, y, _)| (x, y)).collect::<Vec<(u16, u16)>>(), vec![(0, 255)]);
+    assert_eq!(Frame::from_screen(&framebuffer, true).pixels().map(|(x, y, _)| (x, y)).collect::<Vec<(u16, u16)>>(), vec![(223, 0)]);
+
+    assert_eq!(filled(4, 2, WHITE).luminance(), 1.0);
+    assert_eq!(Frame::new(4, 2).luminance(), 0.0);
+    assert!(Frame::new(4, 2).pixels().next().is_none());
+    // Transparent pixels aren't drawn
+}
+
+#[test]
+fn test_overlay_colour() {
+    let palette: Palette = Palette::new(&Profile::space_invaders().overlay).unwrap();
+    let mut input: Frame = Frame::new(224, 256);
+    input.set(100, 10, WHITE);
+    input.set(100, 250, WHITE);
+    input.set(5, 5, [0x80, 0x80, 0x80, 0x80]);
+
+    let output: Frame = OverlayColour::new(palette.clone()).process(&input);
+    let colour = |row: u16, column: u16| {
+        let colour: Color = palette.lit(row, column);
+        [colour.r, colour.g, colour.b, 0xff]
+    };
+    assert_eq!(output.get(100, 10), colour(245, 100));
+    assert_eq!(output.get(100, 250), colour(5, 100));
+    assert_ne!(output.get(100, 10), output.get(100, 250));
+    // The score at the top and the bases at the bottom are under different bands
+    assert_eq!(output.get(5, 5)[3], 0x80);
+    assert_eq!(output.get(0, 0), [0; 4]);
+}
+
+#[test]
+fn test_scanlines() {
+    let output: Frame = Scanlines.process(&filled(2, 4, WHITE));
+    assert_eq!((0..4).map(|y| output.get(1, y)).collect::<Vec<[u8; 4]>>(), vec![WHITE, [153, 153, 153, 0xff], WHITE, [153, 153, 153, 0xff]]);
+}
+
+#[test]
+fn test_blend() {
+    let mut blend: Blend = Blend::new();
+    let mut lit: Frame = Frame::new(2, 1);
+    lit.set(0, 0, WHITE);
+
+    assert_eq!(blend.process(&lit), lit);
+    assert_eq!(blend.process(&Frame::new(2, 1)).get(0, 0), [127; 4]);
+    assert_eq!(blend.process(&Frame::new(2, 1)).get(0, 0), [63; 4]);
+    // Fading out by half a frame
+    assert_eq!(blend.process(&lit).get(0, 0), WHITE);
+    assert_eq!(blend.process(&Frame::new(3, 1)), Frame::new(3, 1));
+    // A frame of another size starts over
+}
+
+#[test]
+fn test_flash_damping() {
+    let mut damping: FlashDamping = FlashDamping::new();
+    assert_eq!(damping.process(&Frame::new(2, 2)), Frame::new(2, 2));
+    let flash: Frame = damping.process(&filled(2, 2, WHITE));
+    assert_eq!(flash.get(0, 0), [(0xff as f32 * FLASH_STEP).round() as u8, (0xff as f32 * FLASH_STEP).round() as u8, (0xff as f32 * FLASH_STEP).round() as u8, 0xff]);
+    // Going from dark to all white is held to one step brighter
+    assert!(damping.process(&filled(2, 2, WHITE)).get(0, 0)[0] > flash.get(0, 0)[0]);
+    assert_eq!(damping.process(&Frame::new(2, 2)), Frame::new(2, 2));
+}
+
+#[test]
+fn test_crt() {
+    let mut frame: Frame = filled(9, 9, WHITE);
+    let output: Frame = Crt::new(100).process(&frame);
+    assert_eq!(output.get(4, 4), WHITE);
+    assert!(output.get(0, 0)[0] < output.get(4, 0)[0] && output.get(4, 0)[0] < 0xff);
+    assert_eq!(output.get(0, 0)[3], 0xff);
+    // Darker towards the corners, still covering the background
+
+    frame = Frame::new(9, 9);
+    frame.set(4, 4, WHITE);
+    let output: Frame = Crt::new(100).process(&frame);
+    assert_eq!(output.get(4, 3), [88, 88, 88, 89]);
+    assert_eq!(output.get(3, 3), [0; 4]);
+    // Only the pixels beside a lit one glow, a little dimmer for being off centre
+    assert_eq!(Crt::new(0).process(&frame), frame);
+
+    assert_eq!(Effect::parse("crt"), Ok(Effect::Crt(CRT_INTENSITY)));
+    assert_eq!(Effect::parse("CRT: 30"), Ok(Effect::Crt(30)));
+    assert!(Effect::parse("crt:101").is_err());
+    assert!(Effect::parse("blend:30").is_err());
+    let chain: Chain = Chain::build(&Effect::parse_list("crt:20,scanlines,crt").unwrap(), &Palette::new(&Profile::space_invaders().overlay).unwrap());
+    assert_eq!(chain.names(), vec!["crt", "scanlines"]);
+}
+
+#[test]
+fn test_chain() {
+    let palette: Palette = Palette::new(&Preset::HighContrast.apply(&Profile::space_invaders().overlay)).unwrap();
+    let effects: Vec<Effect> = Effect::parse_list("overlay, Scanlines,overlay").unwrap();
+    let mut chain: Chain = Chain::build(&effects, &palette);
+    assert_eq!(chain.names(), vec!["overlay", "scanlines"]);
+    // Each effect runs once
+
+    let output: Frame = chain.process(filled(224, 256, WHITE));
+    assert_eq!((output.get(0, 0), output.get(0, 1)), (WHITE, [153, 153, 153, 0xff]));
+    assert_eq!(Chain::new().process(filled(2, 2, WHITE)), filled(2, 2, WHITE));
+
+    assert_eq!(Effect::parse("FLASH_DAMPING"), Ok(Effect::FlashDamping));
+    assert!(Effect::parse_list("blend,sepia").is_err());
+    assert!(Effect::ALL.iter().all(|effect| Effect::parse(effect.name()) == Ok(*effect)));
+}
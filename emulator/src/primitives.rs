@@ -0,0 +1,40 @@
+// Colours and keys the core deals in
+//  With the frontend feature they are raylib's own so the window can use them as they are,
+//  without it the same names are defined here and nothing native is linked
+
+#[cfg(feature = "frontend")]
+pub use raylib::prelude::{Color, KeyboardKey};
+
+#[cfg(not(feature = "frontend"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+#[cfg(not(feature = "frontend"))]
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, std::num::ParseIntError> {
+        // RRGGBB, always opaque the same as raylib's
+        let rgb: u32 = u32::from_str_radix(hex, 16)?;
+        Ok(Self::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255))
+    }
+}
+
+#[cfg(not(feature = "frontend"))]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyboardKey {
+    // Every key the emulator names, the ones bindings can use and the window's hotkeys, spelt the way raylib spells them
+    KEY_A, KEY_B, KEY_C, KEY_D, KEY_E, KEY_F, KEY_G, KEY_H, KEY_I, KEY_J, KEY_K, KEY_L, KEY_M,
+    KEY_N, KEY_O, KEY_P, KEY_Q, KEY_R, KEY_S, KEY_T, KEY_U, KEY_V, KEY_W, KEY_X, KEY_Y, KEY_Z,
+    KEY_ZERO, KEY_ONE, KEY_TWO, KEY_THREE, KEY_FOUR, KEY_FIVE, KEY_SIX, KEY_SEVEN, KEY_EIGHT, KEY_NINE,
+    KEY_ENTER, KEY_TAB, KEY_SPACE, KEY_BACKSPACE, KEY_LEFT_SHIFT,
+    KEY_LEFT, KEY_RIGHT, KEY_UP, KEY_DOWN, KEY_HOME, KEY_END, KEY_MINUS, KEY_PAGE_UP, KEY_PAGE_DOWN, KEY_ESCAPE,
+    KEY_F1, KEY_F2, KEY_F3, KEY_F4, KEY_F5, KEY_F6, KEY_F7, KEY_F8, KEY_F9, KEY_F10, KEY_F11, KEY_F12,
+}
@@ -0,0 +1,756 @@
+#[cfg(feature = "serde")]
+use std::fs;
+use std::ops::Range;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use crate::banking::BankConfig;
+use crate::cpu::{Mirror, PAGE_SIZE};
+use crate::cpu::dispatcher::CLOCK_CYCLES;
+use crate::display::Display;
+use crate::hardware::cocktail::Cocktail;
+use crate::hardware::Port;
+use crate::hardware::port_map::PortMap;
+use crate::hardware::shift::ShiftConfig;
+use crate::hardware::sound::Sound;
+use crate::hardware::input::{self, Binding, DipSwitches, ServiceSwitch};
+use crate::headless;
+use crate::i18n::Language;
+#[cfg(feature = "serde")]
+use crate::storage::{self, Kind, Storage};
+
+#[cfg(any(test, not(feature = "serde")))]
+mod built_in;
+mod tests;
+
+pub const SPACE_INVADERS: &str = include_str!("../profiles/space_invaders.toml");
+const OVERLAYS: [(&str, &str); 3] = [
+    ("tv", include_str!("../profiles/overlays/tv.toml")),
+    ("midway_gel", include_str!("../profiles/overlays/midway_gel.toml")),
+    ("monochrome", include_str!("../profiles/overlays/monochrome.toml")),
+];
+// Overlays different cabinets shipped with, picked with --overlay or cycled through with F6
+// Built in so the emulator runs without any files next to it
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Profile {
+    // Everything about a machine that can be described without code
+    //  Loaded from a .toml or .json file so other games on the Midway 8080 board can be added
+    //  without recompiling
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub language: Language,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clock: Clock,
+    pub interrupts: Interrupts,
+    pub memory: Vec<Region>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub randomize_ram: bool,
+    // Fill ram and vram with noise at power on from the machine's seed instead of zeros
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stack: Option<StackConfig>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub display: Display,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mirror: Option<Mirror>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub banks: Vec<BankConfig>,
+    pub ports: PortMap,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shift: ShiftConfig,
+    pub overlay: Overlay,
+    pub keybinds: Vec<Keybind>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chips: Vec<Chip>,
+    // The rom chips the game came on in the order they're loaded, for checking a dump and saying what a machine needs
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dip_switches: DipSwitches,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub coin_pulse_frames: u8,
+    // Frames a coin press is held for at the least, 0 passes the key straight through
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub watchdog_frames: Option<u16>,
+    // Frames the rom can go without writing to the watchdog before the board resets it, left out for a board without one
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub service: Option<ServiceSwitch>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cocktail: Option<Cocktail>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub paddle: Option<PaddleConfig>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kiosk: Option<KioskConfig>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub audio: AudioConfig,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub roms: Vec<RomOverride>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Clock {
+    pub hz: u64,
+    // How fast the cpu runs, cycles_per_frame is what actually paces the machine
+}
+impl Clock {
+    pub fn parse(text: &str) -> Result<u64, String> {
+        // Reads a clock speed like 2000000, 2000khz or 3.125mhz
+
+        let lower: String = text.trim().to_ascii_lowercase();
+        let (number, scale): (&str, f64) = if let Some(number) = lower.strip_suffix("mhz") {
+            (number, 1_000_000.0)
+        } else if let Some(number) = lower.strip_suffix("khz") {
+            (number, 1_000.0)
+        } else {
+            (lower.strip_suffix("hz").unwrap_or(&lower), 1.0)
+        };
+
+        let hz: f64 = number.trim().parse::<f64>().map_err(|_| format!("{} is not a clock speed, try 2mhz", text))? * scale;
+        if !hz.is_finite() || hz < 1.0 {
+            return Err(format!("{} is too slow to run anything", text));
+        }
+        Ok(hz.round() as u64)
+    }
+}
+impl Default for Clock {
+    fn default() -> Self {
+        Self { hz: 2_000_000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Interrupts {
+    pub cycles_per_frame: u64,
+    pub mid_screen: u8,
+    pub full_screen: u8,
+    // RST numbers of the interrupts generated halfway through and at the end of each frame
+}
+impl Interrupts {
+    pub fn op_code(rst: u8) -> u8 {
+        // RST n is encoded as 11nnn111
+        0xc7 | (rst & 0b111) << 3
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum StackGuard {
+    Off,
+    #[default]
+    Warn,
+    // Notes the stack pointer leaving the stack and carries on
+    Trap,
+    // Stops the machine there like any other fault
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct StackConfig {
+    // Where the rom keeps its stack, the stack pointer leaving it usually means runaway pushes or a bad return
+    //  Only a profile knows this, CP/M programs put their stack wherever suits them
+    pub bottom: u16,
+    pub top: u16,
+    // Inclusive, an empty stack has the stack pointer at top and pushes go below it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub guard: StackGuard,
+}
+impl StackConfig {
+    pub fn contains(&self, sp: u16) -> bool {
+        (self.bottom..=self.top).contains(&sp)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RegionKind {
+    Rom,
+    Ram,
+    Vram,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Region {
+    pub start: u16,
+    pub end: u32,
+    // Exclusive, 0x10000 for a region that runs to the top of memory
+    pub kind: RegionKind,
+}
+impl Region {
+    pub fn contains(&self, address: u16) -> bool {
+        self.start <= address && (address as u32) < self.end
+    }
+
+    pub fn addresses(&self) -> Range<usize> {
+        // Kept inside memory so a region the profile got wrong can't index past it
+        let end: usize = (self.end as usize).min(0x10000);
+        (self.start as usize).min(end)..end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Band {
+    pub rows: (u16, u16),
+    // Inclusive, counted up from the bottom of the screen
+    pub columns: Option<(u16, u16)>,
+    // Inclusive, every column when not given
+    pub colour: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Overlay {
+    // Colours of the gel strips stuck over the monochrome monitor
+    pub background: String,
+    pub default: String,
+    pub bands: Vec<Band>,
+}
+impl Overlay {
+    pub const PRESETS: [&'static str; 3] = [OVERLAYS[0].0, OVERLAYS[1].0, OVERLAYS[2].0];
+
+    #[cfg(feature = "serde")]
+    pub fn preset(name: &str) -> Result<Self, String> {
+        let Some((_, text)) = OVERLAYS.iter().find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim())) else {
+            return Err(format!("{} is not an overlay, try one of {}", name, Self::PRESETS.join(", ")));
+        };
+        toml::from_str(text).map_err(|e| format!("{} overlay: {}", name, e))
+    }
+
+    pub fn colour_at(&self, row: u16, column: u16) -> &str {
+        // Hex colour of a lit pixel, the first band containing the pixel wins
+
+        self.bands.iter()
+            .find(|band| {
+                (band.rows.0..=band.rows.1).contains(&row)
+                    && band.columns.is_none_or(|(start, end)| (start..=end).contains(&column))
+            })
+            .map_or(self.default.as_str(), |band| band.colour.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Keybind {
+    pub key: String,
+    pub input: u8,
+    pub bit: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Chip {
+    pub file: String,
+    // Name of the chip's dump in the usual sets, e.g. invaders.h
+    pub size: usize,
+    pub crc: u32,
+    // CRC32 of the dump, the same one zip files list
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct RomOverride {
+    // Settings for one particular rom, picked by hash when that rom is loaded
+    //  Lets one profile cover several versions or hacks of a game, emulator --rom-hash prints the hash
+    pub hash: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overlay: Option<Overlay>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dip_switches: Option<DipSwitches>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub coin_pulse_frames: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keybinds: Vec<Keybind>,
+    // Replace whatever the profile binds to the same input bit
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub save_state: Option<String>,
+    // Known good state loaded as soon as the rom starts
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct PaddleConfig {
+    // Where the game keeps the player's position so the mouse can steer towards the cursor
+    pub address: u16,
+    pub centre_offset: u8,
+    // Added to the stored position to get the column at the middle of the player
+    pub input: u8,
+    pub left_bit: u8,
+    pub right_bit: u8,
+    pub dead_zone: u8,
+    // Columns either side of the cursor where the player stops moving
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct KioskConfig {
+    // How an unattended cabinet starts games by itself and goes back to attract mode afterwards
+    pub playing: u16,
+    // Ram byte the game keeps non zero while a game is being played
+    pub idle_seconds: u32,
+    // Seconds in attract mode with nothing pressed before a coin goes in and a game is started
+    pub reset_seconds: u32,
+    // Seconds after a game ends before the machine is reset, pressing anything first leaves it be
+    pub input: u8,
+    pub coin_bit: u8,
+    pub start_bit: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct AudioConfig {
+    // How sounds share the speaker when several play at once
+    #[cfg_attr(feature = "serde", serde(default = "AudioConfig::unlimited"))]
+    pub channels: usize,
+    // Most sounds heard at once, the lowest priority ones are silenced
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: Vec<Sound>,
+    // Highest priority first, sounds not listed come after in port bit order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ducking: Vec<Duck>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub device: DeviceConfig,
+}
+impl AudioConfig {
+    fn unlimited() -> usize {
+        Sound::ALL.len()
+    }
+}
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            channels: Self::unlimited(),
+            priority: vec![],
+            ducking: vec![],
+            device: DeviceConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DeviceConfig {
+    // What the stream to the audio device is opened with
+    pub sample_rate: u32,
+    // Everything is mixed at this rate, sample packs are resampled to it
+    pub buffer_samples: usize,
+    // Samples in each half of the stream, smaller lines sounds up with the picture but runs dry more easily
+    pub latency_ms: u32,
+    // Most delay the buffer may grow to when the device keeps running dry
+}
+impl DeviceConfig {
+    pub fn max_buffer_samples(&self) -> usize {
+        // The latency target as a buffer size, never less than the buffer it starts with
+        (self.sample_rate as u64 * self.latency_ms as u64 / 1000).max(self.buffer_samples as u64) as usize
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        if !(8000..=96000).contains(&self.sample_rate) {
+            problems.push(format!("Sample rate {} Hz isn't between 8000 and 96000", self.sample_rate));
+        }
+        if self.buffer_samples < 64 {
+            problems.push(format!("Buffer of {} samples is smaller than 64", self.buffer_samples));
+        }
+        if (self.sample_rate as u64 * self.latency_ms as u64 / 1000) < self.buffer_samples as u64 {
+            problems.push(format!("Latency target of {}ms is shorter than the {} sample buffer it starts with", self.latency_ms, self.buffer_samples));
+        }
+        problems
+    }
+}
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 22050,
+            buffer_samples: 1024,
+            latency_ms: 370,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct Duck {
+    pub sound: Sound,
+    pub under: Vec<Sound>,
+    pub volume: f32,
+    // Volume of sound while any of the sounds it ducks under are playing
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    // One part of a profile looked over by validate, no problems means it passed
+    pub name: &'static str,
+    pub problems: Vec<String>,
+}
+
+impl Profile {
+    pub fn rom_override(&self, rom: &[u8]) -> Option<&RomOverride> {
+        let hash: String = headless::rom_hash(rom);
+        self.roms.iter().find(|rom_override| rom_override.hash.eq_ignore_ascii_case(&hash))
+    }
+
+    pub fn for_rom(&self, rom: &[u8]) -> Profile {
+        // The profile with any settings given for this rom applied over it
+
+        let mut profile: Profile = self.clone();
+        let Some(rom_override) = self.rom_override(rom) else {
+            return profile;
+        };
+        if let Some(name) = &rom_override.name {
+            profile.name = name.clone();
+        }
+        if let Some(overlay) = &rom_override.overlay {
+            profile.overlay = overlay.clone();
+        }
+        if let Some(dip_switches) = rom_override.dip_switches {
+            profile.dip_switches = dip_switches;
+        }
+        if let Some(frames) = rom_override.coin_pulse_frames {
+            profile.coin_pulse_frames = frames;
+        }
+        for keybind in &rom_override.keybinds {
+            profile.keybinds.retain(|existing| (existing.input, existing.bit) != (keybind.input, keybind.bit));
+            profile.keybinds.push(keybind.clone());
+        }
+        profile
+    }
+
+    pub fn rom_space(&self) -> Range<u32> {
+        // Where a rom file goes, every rom region joined up from address 0
+        //  A profile can give the rom more than Space Invaders' 8K by making its rom regions bigger
+
+        let mut end: u32 = 0;
+        while let Some(region) = self.memory.iter().find(|region| region.kind == RegionKind::Rom && region.start as u32 == end && region.end > end) {
+            end = region.end;
+        }
+        0..end
+    }
+
+    pub fn set_clock(&mut self, hz: u64) {
+        // Runs the cpu at another speed without changing the frame rate
+        //  The cycles in each frame are scaled so interrupts still come at the same times on screen
+
+        let cycles: u128 = self.interrupts.cycles_per_frame as u128 * hz as u128 / self.clock.hz.max(1) as u128;
+        self.interrupts.cycles_per_frame = (cycles as u64).max(2);
+        // At least 2 so both interrupts still have somewhere to go
+        self.clock.hz = hz;
+    }
+
+    pub fn validate(&self) -> Vec<Check> {
+        // Looks for settings that load fine but describe a machine that can't work as written
+        //  None of these stop a profile loading, emulator --validate-profile prints them
+
+        vec![
+            Check { name: "memory", problems: self.memory_problems() },
+            Check { name: "ports", problems: self.port_problems() },
+            Check { name: "shift", problems: self.shift.problems() },
+            Check { name: "display", problems: self.display_problems() },
+            Check { name: "stack", problems: self.stack_problems() },
+            Check { name: "service", problems: self.service_problems() },
+            Check { name: "cocktail", problems: self.cocktail.as_ref().map_or(vec![], Cocktail::problems) },
+            Check { name: "overlay", problems: self.overlay_problems() },
+            Check { name: "interrupts", problems: self.interrupt_problems() },
+            Check { name: "watchdog", problems: self.watchdog_problems() },
+            Check { name: "audio", problems: self.audio.device.problems() },
+        ]
+    }
+
+    pub fn report(&self) -> String {
+        // validate's results written out a line per check with each problem under its check
+        //  e.g. "    ports       1 problem"
+
+        let mut lines: Vec<String> = vec![format!("Profile {}", self.name)];
+        for check in self.validate() {
+            match check.problems.len() {
+                0 => lines.push(format!("    {:<12}ok", check.name)),
+                1 => lines.push(format!("    {:<12}1 problem", check.name)),
+                count => lines.push(format!("    {:<12}{} problems", check.name, count)),
+            }
+            lines.extend(check.problems.iter().map(|problem| format!("        {}", problem)));
+        }
+        lines.join("\n")
+    }
+
+    fn memory_problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        if !self.memory.iter().any(|region| region.kind == RegionKind::Rom) {
+            problems.push(String::from("There is no rom region to load the rom into"));
+        }
+        for (i, region) in self.memory.iter().enumerate() {
+            if region.start as u32 >= region.end {
+                problems.push(format!("Region at 0x{:04x} ends at 0x{:04x} before it starts", region.start, region.end));
+            }
+            if region.end > 0x10000 {
+                problems.push(format!("Region at 0x{:04x} ends at 0x{:04x} past the top of memory", region.start, region.end));
+            } else if !(region.start as usize).is_multiple_of(PAGE_SIZE) || !(region.end as usize).is_multiple_of(PAGE_SIZE) {
+                problems.push(format!("Region at 0x{:04x} does not start and end on a 0x{:x} byte page", region.start, PAGE_SIZE));
+            }
+            for other in &self.memory[..i] {
+                if (region.start as u32) < other.end && (other.start as u32) < region.end {
+                    problems.push(format!("Regions at 0x{:04x} and 0x{:04x} overlap", other.start, region.start));
+                }
+            }
+        }
+
+        let Some(mirror) = self.mirror else {
+            return problems;
+        };
+        if mirror.size == 0 {
+            problems.push(format!("Mirror at 0x{:04x} has no size", mirror.start));
+        } else if mirror.target as usize + mirror.size as usize > mirror.start as usize {
+            problems.push(format!("Mirror at 0x{:04x} repeats a block that runs into the mirror itself", mirror.start));
+        }
+        for region in self.memory.iter().filter(|region| region.end > mirror.start as u32) {
+            problems.push(format!("Region at 0x{:04x} is hidden by the mirror at 0x{:04x}", region.start, mirror.start));
+        }
+        problems
+    }
+
+    fn port_problems(&self) -> Vec<String> {
+        // A port number given twice only keeps its last device, and a device on two ports is usually a typo
+
+        let mut problems: Vec<String> = vec![];
+        let (read, write): (Option<u8>, Option<u8>) = self.ports.reassigned();
+        if let Some(number) = read {
+            problems.push(format!("Read port {} is given more than one device", number));
+        }
+        if let Some(number) = write {
+            problems.push(format!("Write port {} is given more than one device", number));
+        }
+
+        for (direction, ports) in [("read", self.ports.reads().collect::<Vec<_>>()), ("write", self.ports.writes().collect())] {
+            for (i, (number, port)) in ports.iter().enumerate() {
+                if let Some((first, _)) = ports[..i].iter().find(|(_, other)| other == port) {
+                    problems.push(format!("{:?} is on {} ports {} and {}", port, direction, first, number));
+                }
+            }
+        }
+        problems
+    }
+
+    fn service_problems(&self) -> Vec<String> {
+        // The service switch needs a bit of its own, a key or DIP switch on the same bit would fight it
+
+        let Some(service) = self.service else {
+            return vec![];
+        };
+        if !(1..=2).contains(&service.input) || service.bit > 7 {
+            return vec![format!("Input {} bit {} isn't on an input port, inputs are 1 or 2 and bits 0 to 7", service.input, service.bit)];
+        }
+        let mut problems: Vec<String> = vec![];
+        if let Some(keybind) = self.keybinds.iter().find(|keybind| keybind.input == service.input && keybind.bit == service.bit) {
+            problems.push(format!("Input {} bit {} is also bound to {}", service.input, service.bit, keybind.key));
+        }
+        if service.input == 2 && DipSwitches::BITS & (1 << service.bit) != 0 {
+            problems.push(format!("Input 2 bit {} is a DIP switch", service.bit));
+        }
+        problems
+    }
+
+    fn display_problems(&self) -> Vec<String> {
+        // The screen has to be somewhere the rom can write to, and a bitmap's lines have to fit its pixels
+
+        let mut problems: Vec<String> = match self.display {
+            Display::Bitmap(geometry) => geometry.problems(),
+            Display::Text { .. } => vec![],
+        };
+        let screen: std::ops::Range<u16> = self.display.memory();
+        let writable: bool = self.memory.iter()
+            .any(|region| region.kind != RegionKind::Rom && region.start <= screen.start && screen.end as u32 <= region.end);
+        if !writable {
+            problems.push(format!("Screen at 0x{:04x}..0x{:04x} isn't inside a ram or vram region", screen.start, screen.end));
+        }
+        problems
+    }
+
+    fn stack_problems(&self) -> Vec<String> {
+        // Pushes write below top down to bottom so that has to be ram
+
+        let Some(stack) = self.stack else {
+            return vec![];
+        };
+        if stack.bottom > stack.top {
+            return vec![format!("Stack bottom 0x{:04x} is above its top 0x{:04x}", stack.bottom, stack.top)];
+        }
+        let writable: bool = stack.bottom == stack.top || self.memory.iter()
+            .any(|region| region.kind == RegionKind::Ram && region.start <= stack.bottom && stack.top as u32 <= region.end);
+        match writable {
+            true => vec![],
+            false => vec![format!("Stack at 0x{:04x}..0x{:04x} isn't inside a ram region", stack.bottom, stack.top)],
+        }
+    }
+
+    fn overlay_problems(&self) -> Vec<String> {
+        // Bands have to be on the screen, they are counted from 0 so the last row is the height less 1
+        //  Overlays for particular roms are checked too
+
+        let (width, height): (u16, u16) = (self.display.geometry().width, self.display.geometry().height);
+        let overlays = std::iter::once(("overlay", &self.overlay))
+            .chain(self.roms.iter().filter_map(|rom_override| rom_override.overlay.as_ref().map(|overlay| (rom_override.hash.as_str(), overlay))));
+        let mut problems: Vec<String> = vec![];
+        for (name, overlay) in overlays {
+            for band in &overlay.bands {
+                let (rows, columns): ((u16, u16), (u16, u16)) = (band.rows, band.columns.unwrap_or((0, 0)));
+                if rows.0 > rows.1 || rows.1 >= height {
+                    problems.push(format!("{}: band rows {}..{} aren't inside rows 0..{}", name, rows.0, rows.1, height.saturating_sub(1)));
+                }
+                if columns.0 > columns.1 || columns.1 >= width {
+                    problems.push(format!("{}: band columns {}..{} aren't inside columns 0..{}", name, columns.0, columns.1, width.saturating_sub(1)));
+                }
+            }
+        }
+        problems
+    }
+
+    fn interrupt_problems(&self) -> Vec<String> {
+        // Each half of the frame has to fit at least one instruction or an interrupt is requested before the last one is serviced
+
+        let interrupts: Interrupts = self.interrupts;
+        let mut problems: Vec<String> = vec![];
+        for (name, rst) in [("mid_screen", interrupts.mid_screen), ("full_screen", interrupts.full_screen)] {
+            if rst > 7 {
+                problems.push(format!("{} is RST {} but there are only RST 0 to 7", name, rst));
+            }
+        }
+        if interrupts.mid_screen == interrupts.full_screen {
+            problems.push(format!("Both interrupts are RST {} so the rom can't tell them apart", interrupts.mid_screen));
+        }
+
+        let longest: u64 = CLOCK_CYCLES.iter().copied().max().unwrap_or(0) as u64;
+        if interrupts.cycles_per_frame / 2 < longest {
+            problems.push(format!("Half a frame is {} cycles but the longest instruction takes {}", interrupts.cycles_per_frame / 2, longest));
+        }
+        problems
+    }
+
+    fn watchdog_problems(&self) -> Vec<String> {
+        // A watchdog nothing can write to resets the machine every few seconds
+
+        let Some(frames) = self.watchdog_frames else {
+            return vec![];
+        };
+        let mut problems: Vec<String> = vec![];
+        if frames == 0 {
+            problems.push(String::from("watchdog_frames is 0 so the machine resets every frame"));
+        }
+        if !self.ports.writes().any(|(_, port)| port == Port::WATCHDOG) {
+            problems.push(String::from("There's a watchdog but no write port is WATCHDOG so the rom can't feed it"));
+        }
+        problems
+    }
+
+    pub fn bindings(&self) -> Result<Vec<Binding>, String> {
+        // Turns the key names into keys the frontend can check
+
+        self.keybinds.iter()
+            .map(|keybind| match input::key_from_name(&keybind.key) {
+                Some(key) => Ok(Binding { key, input: keybind.input, bit: keybind.bit }),
+                None => Err(format!("Unknown key {}", keybind.key)),
+            })
+            .collect()
+    }
+}
+#[cfg(not(feature = "serde"))]
+impl Profile {
+    pub fn space_invaders() -> Self {
+        built_in::space_invaders()
+    }
+}
+#[cfg(feature = "serde")]
+impl Profile {
+    // Profiles are read from toml or json, without the serde feature only the built in one is there
+    pub fn space_invaders() -> Self {
+        Self::from_toml(SPACE_INVADERS).expect("built in profile should always be valid")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // Picks the format from the file extension
+
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text, path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn read(storage: &dyn Storage, name: &str) -> Result<Option<Self>, String> {
+        // A profile kept with the emulator's config, None when there isn't one by that name
+        storage::read_text(storage, Kind::Config, name)?
+            .map(|text| Self::parse(&text, Path::new(name)).map_err(|e| format!("{}: {}", storage.describe(Kind::Config, name), e)))
+            .transpose()
+    }
+
+    fn parse(text: &str, name: &Path) -> Result<Self, String> {
+        match name.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(text),
+            Some("json") => Self::from_json(text),
+            _ => Err(String::from("profiles must be .toml or .json")),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let profile: Profile = toml::from_str(text).map_err(|e| e.to_string())?;
+        profile.check_colours()?;
+        profile.check_banks()?;
+        Ok(profile)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let profile: Profile = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        profile.check_colours()?;
+        profile.check_banks()?;
+        Ok(profile)
+    }
+
+    fn check_colours(&self) -> Result<(), String> {
+        // Colours are parsed while rendering so catch bad ones when loading instead
+        //  Overlays for particular roms are checked too even though they may never be used
+
+        let overlays = std::iter::once(&self.overlay)
+            .chain(self.roms.iter().filter_map(|rom_override| rom_override.overlay.as_ref()));
+        let colours = overlays.flat_map(|overlay| [&overlay.background, &overlay.default].into_iter()
+            .chain(overlay.bands.iter().map(|band| &band.colour)));
+        for colour in colours {
+            if colour.len() != 6 || !colour.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("{} is not a colour, colours are written as RRGGBB", colour));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_banks(&self) -> Result<(), String> {
+        // Banked windows need a port of their own and can't overlap each other
+
+        for (i, bank) in self.banks.iter().enumerate() {
+            if bank.size == 0 || bank.count == 0 || bank.end() > 0x10000 {
+                return Err(format!("Bank at 0x{:04x} needs a size and count that fit in memory", bank.start));
+            }
+            if let Some(port) = self.ports.write(bank.port) {
+                return Err(format!("Bank select port {} is already used by {:?}", bank.port, port));
+            }
+            for other in &self.banks[..i] {
+                if other.port == bank.port {
+                    return Err(format!("Banks at 0x{:04x} and 0x{:04x} share port {}", other.start, bank.start, bank.port));
+                }
+                if (bank.start as usize) < other.end() && (other.start as usize) < bank.end() {
+                    return Err(format!("Banks at 0x{:04x} and 0x{:04x} overlap", other.start, bank.start));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+impl Default for Profile {
+    fn default() -> Self {
+        Self::space_invaders()
+    }
+}
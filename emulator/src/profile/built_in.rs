@@ -0,0 +1,70 @@
+use super::*;
+use crate::display::Geometry;
+
+pub fn space_invaders() -> Profile {
+    // profiles/space_invaders.toml written out in code for builds without the serde feature
+    //  The tests check it against the file so the two can't drift apart
+
+    Profile {
+        name: String::from("Space Invaders"),
+        language: Language::English,
+        clock: Clock::default(),
+        interrupts: Interrupts { cycles_per_frame: 33000, mid_screen: 1, full_screen: 2 },
+        memory: vec![
+            Region { start: 0x0000, end: 0x2000, kind: RegionKind::Rom },
+            Region { start: 0x2000, end: 0x2400, kind: RegionKind::Ram },
+            Region { start: 0x2400, end: 0x4000, kind: RegionKind::Vram },
+        ],
+        randomize_ram: false,
+        stack: Some(StackConfig { bottom: 0x2000, top: 0x2400, guard: StackGuard::Warn }),
+        display: Display::Bitmap(Geometry::space_invaders()),
+        mirror: Some(Mirror { start: 0x4000, target: 0x2000, size: 0x2000 }),
+        banks: vec![],
+        ports: PortMap::space_invaders(),
+        shift: ShiftConfig::space_invaders(),
+        overlay: Overlay {
+            background: String::from("000000"),
+            default: String::from("FFFFFF"),
+            bands: vec![
+                band((208, 223), None, "F41EFA"),
+                band((0, 15), Some((0, 25)), "FFFFFF"),
+                band((0, 15), Some((135, 223)), "FFFFFF"),
+                band((0, 15), None, "22CC00"),
+                band((16, 23), None, "FFFFFF"),
+                band((24, 71), None, "22CC00"),
+            ],
+        },
+        keybinds: [("ENTER", 1, 0), ("U", 1, 1), ("Q", 1, 2), ("S", 1, 4), ("A", 1, 5), ("D", 1, 6), ("TAB", 2, 2), ("K", 2, 4), ("J", 2, 5), ("L", 2, 6)]
+            .into_iter()
+            .map(|(key, input, bit)| Keybind { key: String::from(key), input, bit })
+            .collect(),
+        chips: [("invaders.h", 0x734f5ad8), ("invaders.g", 0x6bfaca4a), ("invaders.f", 0x0ccead96), ("invaders.e", 0x14e538b0)]
+            .into_iter()
+            .map(|(file, crc)| Chip { file: String::from(file), size: 0x800, crc })
+            .collect(),
+        dip_switches: DipSwitches::default(),
+        coin_pulse_frames: 0,
+        watchdog_frames: Some(255),
+        service: None,
+        cocktail: None,
+        paddle: Some(PaddleConfig { address: 0x201b, centre_offset: 8, input: 1, left_bit: 5, right_bit: 6, dead_zone: 2 }),
+        kiosk: Some(KioskConfig { playing: 0x20ef, idle_seconds: 60, reset_seconds: 10, input: 1, coin_bit: 0, start_bit: 2 }),
+        audio: AudioConfig {
+            channels: 4,
+            priority: vec![Sound::PlayerDie, Sound::UfoHit, Sound::ExtendedPlay, Sound::InvaderDie, Sound::Shot, Sound::Ufo],
+            ducking: vec![
+                Duck { sound: Sound::Ufo, under: vec![Sound::PlayerDie, Sound::UfoHit, Sound::InvaderDie], volume: 0.35 },
+                Duck { sound: Sound::FleetMove1, under: vec![Sound::PlayerDie], volume: 0.5 },
+                Duck { sound: Sound::FleetMove2, under: vec![Sound::PlayerDie], volume: 0.5 },
+                Duck { sound: Sound::FleetMove3, under: vec![Sound::PlayerDie], volume: 0.5 },
+                Duck { sound: Sound::FleetMove4, under: vec![Sound::PlayerDie], volume: 0.5 },
+            ],
+            device: DeviceConfig::default(),
+        },
+        roms: vec![],
+    }
+}
+
+fn band(rows: (u16, u16), columns: Option<(u16, u16)>, colour: &str) -> Band {
+    Band { rows, columns, colour: String::from(colour) }
+}
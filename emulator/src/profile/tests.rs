@@ -0,0 +1,295 @@
+#[cfg(test)]
+use super::*;
+#[cfg(all(test, feature = "serde"))]
+use crate::hardware::Port;
+#[cfg(all(test, feature = "serde"))]
+use crate::hardware::cocktail::{FlipSource, Remap};
+#[cfg(test)]
+use crate::hardware::input::{InputConfig, ServiceSwitch};
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[test]
+fn test_space_invaders_profile() {
+    let profile: Profile = Profile::space_invaders();
+
+    assert_eq!(profile.interrupts, Interrupts { cycles_per_frame: 33_000, mid_screen: 1, full_screen: 2 });
+    assert_eq!(profile.clock, Clock { hz: 2_000_000 });
+    assert_eq!(Interrupts::op_code(profile.interrupts.mid_screen), 0xcf);
+    assert_eq!(Interrupts::op_code(profile.interrupts.full_screen), 0xd7);
+    assert_eq!(profile.ports, PortMap::space_invaders());
+    assert_eq!(profile.audio.channels, 4);
+    assert_eq!(profile.watchdog_frames, Some(255));
+    assert_eq!(profile.audio.ducking[1].sound, Sound::FleetMove1);
+    assert_eq!(profile.memory[0], Region { start: 0x0000, end: 0x2000, kind: RegionKind::Rom });
+    assert_eq!(profile.mirror.map(|mirror| mirror.resolve(0x4400)), Some(0x2400));
+
+    assert_eq!(profile.bindings(), Ok(InputConfig::default().bindings()));
+    assert_eq!(profile.paddle.map(|paddle| paddle.address), Some(0x201b));
+    assert_eq!(profile.kiosk.map(|kiosk| kiosk.playing), Some(0x20ef));
+    // Same controls as the built in config
+
+    let overlay: &Overlay = &profile.overlay;
+    assert_eq!(overlay.colour_at(215, 100), "F41EFA");
+    assert_eq!(overlay.colour_at(4, 10), "FFFFFF");
+    assert_eq!(overlay.colour_at(4, 100), "22CC00");
+    assert_eq!(overlay.colour_at(20, 100), "FFFFFF");
+    assert_eq!(overlay.colour_at(40, 100), "22CC00");
+    assert_eq!(overlay.colour_at(120, 100), "FFFFFF");
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_built_in_profile() {
+    // Builds without serde use the profile written out in code, it has to match the file
+    assert_eq!(format!("{:?}", built_in::space_invaders()), format!("{:?}", Profile::space_invaders()));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_json_profile() {
+    let json: &str = r#"{
+        "name": "Test",
+        "interrupts": { "cycles_per_frame": 1000, "mid_screen": 1, "full_screen": 2 },
+        "memory": [{ "start": 0, "end": 4096, "kind": "rom" }],
+        "ports": { "read": [[0, "INP1"]], "write": [[1, "WATCHDOG"]] },
+        "overlay": { "background": "000000", "default": "FFFFFF", "bands": [] },
+        "keybinds": [{ "key": "space", "input": 1, "bit": 4 }]
+    }"#;
+    let profile: Profile = Profile::from_json(json).expect("parsing test profile");
+
+    assert_eq!(profile.ports.reads().collect::<Vec<(u8, Port)>>(), vec![(0, Port::INP1)]);
+    assert_eq!(profile.ports.write(1), Some(Port::WATCHDOG));
+    assert_eq!(profile.audio, AudioConfig::default());
+    assert_eq!(profile.clock, Clock::default());
+    // Sounds are mixed without limits when the profile doesn't say
+    assert_eq!(profile.bindings().map(|bindings| bindings[0].bit), Ok(4));
+
+    let bad_key: String = json.replace("space", "not a key");
+    assert!(Profile::from_json(&bad_key).expect("parsing test profile").bindings().is_err());
+
+    let bad_colour: String = json.replace("\"FFFFFF\"", "\"white\"");
+    assert!(Profile::from_json(&bad_colour).is_err());
+}
+
+#[test]
+fn test_region_to_top() {
+    let mut profile: Profile = Profile::space_invaders();
+    profile.mirror = None;
+    profile.memory.push(Region { start: 0xf000, end: 0x10000, kind: RegionKind::Rom });
+    let problems = |profile: &Profile| profile.validate().into_iter().find(|check| check.name == "memory").unwrap().problems;
+    assert!(problems(&profile).is_empty());
+    assert!(profile.memory[3].contains(0xffff) && !profile.memory[3].contains(0xefff));
+    assert_eq!(profile.memory[3].addresses(), 0xf000..0x10000);
+
+    profile.memory[3].start = 0xf080;
+    assert_eq!(problems(&profile), vec![String::from("Region at 0xf080 does not start and end on a 0x100 byte page")]);
+    profile.memory[3].start = 0xf000;
+
+    let mut machine: Machine = Machine::with_profile(profile.clone()).unwrap();
+    machine.cpu.memory.write_at(0xfffe, 0x12);
+    machine.cpu.memory.write_at(0xffff, 0x34);
+    assert_eq!((machine.cpu.memory.read_at(0xfffe), machine.cpu.memory.read_at(0xffff)), (0x00, 0x00));
+    // The last address is read only along with the rest of the region
+
+    profile.memory[3].kind = RegionKind::Ram;
+    let mut machine: Machine = Machine::with_profile(Profile { randomize_ram: true, ..profile.clone() }).unwrap();
+    machine.cpu.memory.write_at(0xffff, 0x34);
+    assert_eq!(machine.cpu.memory.read_at(0xffff), 0x34);
+
+    profile.memory[3].end = 0x10001;
+    assert_eq!(problems(&profile), vec![String::from("Region at 0xf000 ends at 0x10001 past the top of memory")]);
+    assert_eq!(profile.memory[3].addresses(), 0xf000..0x10000);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_profile_banks() {
+    let json: &str = r#"{
+        "name": "Test",
+        "interrupts": { "cycles_per_frame": 1000, "mid_screen": 1, "full_screen": 2 },
+        "memory": [{ "start": 0, "end": 4096, "kind": "rom" }],
+        "ports": { "read": [], "write": [[1, "WATCHDOG"]] },
+        "overlay": { "background": "000000", "default": "FFFFFF", "bands": [] },
+        "keybinds": [],
+        "banks": [
+            { "start": 4096, "size": 1024, "count": 4, "port": 5, "rom_offset": 4096 },
+            { "start": 8192, "size": 256, "count": 2, "port": 6 }
+        ]
+    }"#;
+    let profile: Profile = Profile::from_json(json).expect("parsing test profile");
+    assert_eq!(profile.banks[0].end(), 5120);
+    assert_eq!(profile.banks[1].rom_offset, None);
+
+    assert!(Profile::from_json(&json.replace("\"port\": 6", "\"port\": 5")).is_err());
+    assert!(Profile::from_json(&json.replace("\"port\": 6", "\"port\": 1")).is_err());
+    // Select ports can't be shared or taken from the board
+    assert!(Profile::from_json(&json.replace("8192", "4352")).is_err());
+    assert!(Profile::from_json(&json.replace("\"count\": 2", "\"count\": 0")).is_err());
+}
+
+#[test]
+fn test_clock() {
+    assert_eq!(Clock::parse("3.125mhz"), Ok(3_125_000));
+    assert_eq!(Clock::parse("500 kHz"), Ok(500_000));
+    assert_eq!(Clock::parse("1996800"), Ok(1_996_800));
+    assert_eq!(Clock::parse("4000000hz"), Ok(4_000_000));
+    assert!(Clock::parse("fast").is_err());
+    assert!(Clock::parse("0mhz").is_err());
+
+    let mut profile: Profile = Profile::space_invaders();
+    profile.set_clock(4_000_000);
+    assert_eq!(profile.interrupts.cycles_per_frame, 66_000);
+    profile.set_clock(1_000_000);
+    assert_eq!(profile.interrupts.cycles_per_frame, 16_500);
+    assert_eq!(profile.clock.hz, 1_000_000);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_rom_overrides() {
+    let rom: [u8; 4] = [0xc3, 0x00, 0x00, 0x00];
+    let json: String = r#"{
+        "name": "Test",
+        "interrupts": { "cycles_per_frame": 1000, "mid_screen": 1, "full_screen": 2 },
+        "memory": [{ "start": 0, "end": 8192, "kind": "rom" }],
+        "ports": { "read": [[1, "INP1"], [2, "INP2"]], "write": [] },
+        "overlay": { "background": "000000", "default": "FFFFFF", "bands": [] },
+        "keybinds": [{ "key": "S", "input": 1, "bit": 4 }, { "key": "A", "input": 1, "bit": 5 }],
+        "roms": [{
+            "hash": "HASH",
+            "name": "Hack",
+            "dip_switches": { "extra_lives": 3 },
+            "coin_pulse_frames": 4,
+            "keybinds": [{ "key": "SPACE", "input": 1, "bit": 4 }],
+            "overlay": { "background": "000000", "default": "00FF00", "bands": [] }
+        }]
+    }"#.replace("HASH", &headless::rom_hash(&rom).to_uppercase());
+    let profile: Profile = Profile::from_json(&json).expect("parsing test profile");
+    assert_eq!(profile.dip_switches, DipSwitches::default());
+
+    let applied: Profile = profile.for_rom(&rom);
+    assert_eq!((applied.name.as_str(), applied.overlay.default.as_str(), applied.dip_switches.extra_lives), ("Hack", "00FF00", 3));
+    assert_eq!(applied.keybinds, vec![
+        Keybind { key: String::from("A"), input: 1, bit: 5 },
+        Keybind { key: String::from("SPACE"), input: 1, bit: 4 },
+    ]);
+    assert_eq!(profile.for_rom(&[0x00]).name, "Test");
+    // Other roms get the profile as it is
+
+    let machine: Machine = Machine::with_profile(applied).unwrap();
+    assert_eq!(machine.hardware.debug_input2() & 0b11, 3);
+    assert_eq!((profile.coin_pulse_frames, machine.hardware.coin_pulse_frames()), (0, 4));
+    assert!(Profile::from_json(&json.replace("00FF00", "green")).is_err());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_validate_profile() {
+    assert!(Profile::space_invaders().validate().iter().all(|check| check.problems.is_empty()));
+
+    let json: &str = r#"{
+        "name": "Broken",
+        "interrupts": { "cycles_per_frame": 20, "mid_screen": 2, "full_screen": 2 },
+        "memory": [{ "start": 0, "end": 8192, "kind": "rom" }, { "start": 4096, "end": 9216, "kind": "ram" }],
+        "mirror": { "start": 8192, "target": 0, "size": 8192 },
+        "stack": { "bottom": 8960, "top": 9472, "guard": "trap" },
+        "ports": { "read": [[1, "INP1"], [1, "INP2"], [2, "INP2"]], "write": [] },
+        "overlay": { "background": "000000", "default": "FFFFFF", "bands": [{ "rows": [200, 256], "columns": [10, 5], "colour": "00FF00" }] },
+        "keybinds": [],
+        "audio": { "device": { "sample_rate": 48000, "buffer_samples": 2048, "latency_ms": 20 } }
+    }"#;
+    let profile: Profile = Profile::from_json(json).expect("parsing test profile");
+    let problems = |name: &str| profile.validate().into_iter().find(|check| check.name == name).map_or(0, |check| check.problems.len());
+
+    assert_eq!(problems("memory"), 2);
+    // The regions overlap and the ram runs into the mirror
+    assert_eq!(problems("ports"), 2);
+    // Port 1 is given twice and INP2 ends up on ports 1 and 2
+    assert_eq!(problems("overlay"), 2);
+    assert_eq!(problems("stack"), 1);
+    // The stack runs off the end of the ram
+    assert_eq!(problems("interrupts"), 2);
+    // Same RST twice and 10 cycles can't fit an 18 cycle instruction
+    assert_eq!(problems("audio"), 1);
+    // 20ms at 48khz is 960 samples, less than the buffer
+    assert!(profile.report().contains("    memory      2 problems\n        Regions at 0x0000 and 0x1000 overlap"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_watchdog_problems() {
+    let mut profile: Profile = Profile::space_invaders();
+    let problems = |profile: &Profile| profile.validate().into_iter().find(|check| check.name == "watchdog").unwrap().problems;
+
+    assert!(problems(&profile).is_empty());
+    profile.watchdog_frames = Some(0);
+    assert_eq!(problems(&profile).len(), 1);
+    profile.ports = PortMap::from_pairs(&[(1, Port::INP1)], &[(2, Port::SHFTAMNT)]);
+    assert_eq!(problems(&profile).len(), 2);
+    profile.watchdog_frames = None;
+    assert!(problems(&profile).is_empty());
+    // No watchdog to feed
+
+    let json: &str = r#"{ "name": "Bare", "interrupts": { "cycles_per_frame": 33000, "mid_screen": 1, "full_screen": 2 },
+        "memory": [{ "start": 0, "end": 16384, "kind": "ram" }], "ports": { "read": [], "write": [] },
+        "overlay": { "background": "000000", "default": "FFFFFF", "bands": [] }, "keybinds": [] }"#;
+    assert_eq!(Profile::from_json(json).expect("parsing test profile").watchdog_frames, None);
+    // Off unless the profile gives it
+}
+
+#[test]
+fn test_service_problems() {
+    let mut profile: Profile = Profile::space_invaders();
+    let problems = |profile: &Profile| profile.validate().into_iter().find(|check| check.name == "service").unwrap().problems;
+
+    profile.service = Some(ServiceSwitch { input: 1, bit: 7, active_low: false });
+    assert!(problems(&profile).is_empty());
+    profile.service = Some(ServiceSwitch { input: 2, bit: 2, active_low: true });
+    assert_eq!(problems(&profile), vec![String::from("Input 2 bit 2 is also bound to TAB")]);
+    profile.service = Some(ServiceSwitch { input: 2, bit: 7, active_low: false });
+    assert_eq!(problems(&profile), vec![String::from("Input 2 bit 7 is a DIP switch")]);
+    profile.service = Some(ServiceSwitch { input: 3, bit: 0, active_low: false });
+    assert_eq!(problems(&profile).len(), 1);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_cocktail_profile() {
+    let commented: &str = include_str!("../../profiles/space_invaders.toml");
+    let start: usize = commented.find("# [cocktail]").unwrap();
+    let end: usize = commented[start..].find("\n\n").unwrap() + start;
+    let section: String = commented[start..end].lines().map(|line| line.trim_start_matches("# ")).collect::<Vec<_>>().join("\n");
+    // The example in the shipped profile with its comments taken off
+    let profile: Profile = Profile::from_toml(&commented.replacen(&commented[start..end], &section, 1)).unwrap();
+
+    let cocktail: &Cocktail = profile.cocktail.as_ref().unwrap();
+    assert_eq!(cocktail.flipped_by_output(5, 0x20), Some(true));
+    assert_eq!(cocktail.flipped_by_output(3, 0x20), None);
+    assert_eq!(cocktail.apply([0x08 | 1 << 4, 0x00]), [0x08, 1 << 4]);
+    assert!(profile.validate().iter().all(|check| check.problems.is_empty()));
+
+    let mut profile: Profile = profile;
+    profile.cocktail = Some(Cocktail { flip: FlipSource::Output { port: 5, bit: 9 }, remap: vec![Remap { from: (3, 0), to: (2, 4) }] });
+    assert_eq!(profile.validate().into_iter().find(|check| check.name == "cocktail").unwrap().problems.len(), 2);
+    assert_eq!(Machine::with_profile(profile).unwrap().hardware.cocktail().map(|cocktail| cocktail.remap.len()), Some(1));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_overlay_presets() {
+    for name in Overlay::PRESETS {
+        let overlay: Overlay = Overlay::preset(name).unwrap();
+        assert!(crate::palette::Palette::new(&overlay).is_ok(), "{}", name);
+    }
+    assert_eq!(Overlay::preset("Midway_Gel"), Ok(Profile::space_invaders().overlay));
+    // The built in profile is drawn the way Midway's cabinet was
+
+    let tv: Overlay = Overlay::preset("tv").unwrap();
+    assert_eq!(tv.colour_at(8, 100), "00E0FF");
+    assert_eq!(tv.colour_at(60, 100), "FFE000");
+    assert_eq!(tv.colour_at(8, 10), "FFFFFF");
+    // Credits stay white over the ground line
+    assert_eq!(Overlay::preset("monochrome").unwrap().colour_at(215, 100), "FFFFFF");
+    assert!(Overlay::preset("sepia").unwrap_err().contains("tv, midway_gel, monochrome"));
+}
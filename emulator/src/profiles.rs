@@ -0,0 +1,118 @@
+use crate::display::{Display, Geometry};
+use crate::profile::{self, Chip, Keybind, Profile};
+use crate::storage::{Kind, Storage};
+
+mod tests;
+
+pub const BUILT_IN: [(&str, &str); 2] = [
+    ("space_invaders", profile::SPACE_INVADERS),
+    ("text_console", include_str!("../profiles/text_console.toml")),
+];
+// Profiles that come with the emulator, by the name of their file in profiles/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileInfo {
+    // What a profile says about the machine it describes, for showing what the emulator can run
+    pub id: String,
+    // File name without .toml, e.g. space_invaders
+    pub name: String,
+    pub rom_space: usize,
+    pub chips: Vec<Chip>,
+    // Empty when the profile doesn't say which chips make up the rom
+    pub controls: Vec<Keybind>,
+    pub geometry: Geometry,
+    pub text: Option<(u16, u16)>,
+    // Columns and rows of a text screen, the geometry is still where the overlay goes
+    pub capabilities: Vec<&'static str>,
+}
+impl ProfileInfo {
+    pub fn of(id: &str, profile: &Profile) -> Self {
+        let capabilities: Vec<&'static str> = [
+            (profile.cocktail.is_some(), "cocktail"),
+            (profile.paddle.is_some(), "paddle"),
+            (profile.kiosk.is_some(), "kiosk"),
+            (profile.service.is_some(), "service switch"),
+            (!profile.banks.is_empty(), "banked rom"),
+            (!profile.roms.is_empty(), "rom settings"),
+        ]
+        .into_iter()
+        .filter_map(|(has, name)| has.then_some(name))
+        .collect();
+
+        Self {
+            id: String::from(id),
+            name: profile.name.clone(),
+            rom_space: profile.rom_space().len(),
+            chips: profile.chips.clone(),
+            controls: profile.keybinds.clone(),
+            geometry: profile.display.geometry(),
+            text: match profile.display {
+                Display::Text { columns, rows, .. } => Some((columns, rows)),
+                Display::Bitmap(_) => None,
+            },
+            capabilities,
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        // Printed by --list-machines, e.g.
+        //  space_invaders  Space Invaders
+        //      rom 8192 bytes from invaders.h 2048 bytes crc 734f5ad8, ...
+        //      screen 224x256 bitmap
+        //      controls ENTER input 1 bit 0, ...
+
+        let rom: String = match self.chips.is_empty() {
+            true => format!("rom up to {} bytes", self.rom_space),
+            false => format!("rom {} bytes from {}", self.rom_space, self.chips.iter()
+                .map(|chip| format!("{} {} bytes crc {:08x}", chip.file, chip.size, chip.crc))
+                .collect::<Vec<String>>()
+                .join(", ")),
+        };
+        let screen: String = match self.text {
+            Some((columns, rows)) => format!("screen {}x{} text", columns, rows),
+            None => format!("screen {}x{} bitmap", self.geometry.width, self.geometry.height),
+        };
+        let controls: String = self.controls.iter()
+            .map(|keybind| format!("{} input {} bit {}", keybind.key, keybind.input, keybind.bit))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let mut lines: Vec<String> = vec![format!("{}  {}", self.id, self.name), format!("    {}", rom), format!("    {}", screen), format!("    controls {}", controls)];
+        if !self.capabilities.is_empty() {
+            lines.push(format!("    has {}", self.capabilities.join(", ")));
+        }
+        lines
+    }
+}
+
+pub fn built_in(id: &str) -> Result<Profile, String> {
+    let Some((_, text)) = BUILT_IN.iter().find(|(name, _)| name.eq_ignore_ascii_case(id.trim())) else {
+        return Err(format!("{} is not a built in profile, try one of {}", id, BUILT_IN.map(|(name, _)| name).join(", ")));
+    };
+    Profile::from_toml(text).map_err(|e| format!("{} profile: {}", id, e))
+}
+
+pub fn list() -> Vec<ProfileInfo> {
+    // Every built in profile, they are checked by the tests so none of them fail to load
+    BUILT_IN.iter()
+        .filter_map(|(id, _)| built_in(id).ok().map(|profile| ProfileInfo::of(id, &profile)))
+        .collect()
+}
+
+pub fn list_in(storage: &dyn Storage) -> Vec<Result<ProfileInfo, String>> {
+    // Profiles kept in the config folder, the ones roms pick up as <rom>.toml
+    //  One that doesn't load is listed as why so it can be fixed
+
+    let names: Vec<String> = match storage.list(Kind::Config) {
+        Ok(names) => names,
+        Err(e) => return vec![Err(e)],
+    };
+    names.iter()
+        .filter_map(|name| name.strip_suffix(".toml").or_else(|| name.strip_suffix(".json")).map(|id| (name, id)))
+        .map(|(name, id)| match Profile::read(storage, name) {
+            Ok(Some(profile)) => Ok(ProfileInfo::of(id, &profile)),
+            Ok(None) => Err(format!("{} has gone", storage.describe(Kind::Config, name))),
+            Err(e) => Err(e),
+        })
+        .collect()
+}
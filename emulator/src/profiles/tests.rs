@@ -0,0 +1,56 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::info::RomInfo;
+#[cfg(test)]
+use crate::storage::MemoryStorage;
+
+#[test]
+fn test_list() {
+    let profiles: Vec<ProfileInfo> = list();
+    assert_eq!(profiles.len(), BUILT_IN.len());
+    // Every built in profile loads
+
+    let invaders: &ProfileInfo = &profiles[0];
+    assert_eq!((invaders.id.as_str(), invaders.name.as_str(), invaders.rom_space), ("space_invaders", "Space Invaders", 0x2000));
+    assert_eq!(invaders.chips.iter().map(|chip| chip.size).sum::<usize>(), invaders.rom_space);
+    assert_eq!((invaders.geometry.width, invaders.geometry.height, invaders.text), (224, 256, None));
+    assert_eq!(invaders.capabilities, ["paddle", "kiosk"]);
+    assert!(invaders.controls.contains(&Keybind { key: String::from("ENTER"), input: 1, bit: 0 }));
+    assert!(invaders.lines()[1].starts_with("    rom 8192 bytes from invaders.h 2048 bytes crc 734f5ad8"));
+
+    let console: &ProfileInfo = &profiles[1];
+    assert_eq!((console.text, console.chips.len()), (Some((40, 25)), 0));
+    assert_eq!(console.lines()[1..3], [String::from("    rom up to 2048 bytes"), String::from("    screen 40x25 text")]);
+    assert!(built_in("pacman").is_err());
+}
+
+#[test]
+fn test_list_in() {
+    let mut storage: MemoryStorage = MemoryStorage::new();
+    let custom: String = profile::SPACE_INVADERS.replace("name = \"Space Invaders\"", "name = \"Custom\"") + "\n[cocktail]\nflip = { kind = \"output\", port = 5, bit = 5 }\n";
+    storage.write(Kind::Config, "custom.rom.toml", custom.as_bytes()).unwrap();
+    storage.write(Kind::Config, "broken.rom.toml", b"name = 1").unwrap();
+    storage.write(Kind::Config, "notes.txt", b"").unwrap();
+
+    let listed: Vec<Result<ProfileInfo, String>> = list_in(&storage);
+    assert_eq!(listed.len(), 2);
+    assert!(listed.iter().any(|info| info.as_ref().is_ok_and(|info| info.id == "custom.rom" && info.capabilities.contains(&"cocktail"))));
+    assert!(listed.iter().any(Result::is_err));
+}
+
+#[test]
+fn test_chips_recognised() {
+    let mut profile: Profile = built_in("text_console").unwrap();
+    profile.chips = vec![
+        Chip { file: String::from("a.bin"), size: 0x400, crc: crate::png::crc32(&[0x11; 0x400]) },
+        Chip { file: String::from("b.bin"), size: 0x400, crc: crate::png::crc32(&[0x22; 0x400]) },
+    ];
+    let rom: Vec<u8> = [[0x22; 0x400], [0x11; 0x400]].concat();
+    let info: RomInfo = RomInfo::new(&rom, &profile);
+    assert_eq!(info.chunks.iter().map(|chunk| (chunk.start, chunk.known.clone(), chunk.expected)).collect::<Vec<_>>(), vec![
+        (0x000, Some(String::from("Text Console b.bin")), false),
+        (0x400, Some(String::from("Text Console a.bin")), false),
+    ]);
+    // Split at the profile's 1K chips, each recognised but swapped round
+}
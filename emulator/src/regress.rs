@@ -0,0 +1,203 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde::Deserialize;
+
+use crate::error::EmulatorError;
+use crate::headless::{self, FNV_OFFSET};
+use crate::machine::Machine;
+use crate::port_log::PortLog;
+use crate::profile::Profile;
+use crate::replay::{FrameHash, Replay, ReplayFrame};
+
+mod tests;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Manifest {
+    // Scenarios run by the regress binary, written in toml
+    //      [[scenarios]]
+    //      name = "attract mode"
+    //      rom = "invaders.rom"
+    //      replay = "attract.replay"
+    //      frames = 600
+    //      ram_hash = "3f0c51a2b8e94d17"
+    //      framebuffer_hash = "9a17c0d4e2b35f68"
+    //      ports = "attract.ports"
+    //  Paths are relative to the manifest, anything expected that is left out isn't checked
+    pub scenarios: Vec<Scenario>,
+}
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub rom: PathBuf,
+    pub profile: Option<PathBuf>,
+    // The built in profile when left out
+    pub replay: Option<PathBuf>,
+    // Inputs for each frame, its frame hashes are checked along the way too
+    pub frames: Option<u32>,
+    // Frames to run, the length of the replay when left out
+    //  Frames past the end of the replay run with nothing pressed
+    pub ram_hash: Option<String>,
+    pub framebuffer_hash: Option<String>,
+    pub ports: Option<PathBuf>,
+    // Every port read and write from a reference run, see record
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub name: String,
+    pub ram_hash: String,
+    pub framebuffer_hash: String,
+    pub problems: Vec<String>,
+    // Every difference from what the scenario expected, empty when it passed
+}
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+pub fn framebuffer_hash(machine: &Machine) -> u64 {
+    // Hash of the memory the screen is drawn from, unlike a png it doesn't change when the overlay colours do
+    headless::fnv1a(FNV_OFFSET, machine.cpu.memory.view(machine.profile().display.memory()))
+}
+
+pub fn run(scenario: &Scenario, base: &Path) -> Outcome {
+    // Runs one scenario with paths taken from base, a rom or replay that can't be loaded fails it
+    play_scenario(scenario, base, false)
+}
+
+pub fn record(scenario: &Scenario, base: &Path) -> Outcome {
+    // Runs one scenario and writes its port log to its ports file instead of comparing against it
+    //  Done before changing the hardware so runs after the change can be checked access by access
+    play_scenario(scenario, base, true)
+}
+
+fn play_scenario(scenario: &Scenario, base: &Path, record: bool) -> Outcome {
+    let mut outcome: Outcome = Outcome {
+        name: scenario.name.clone(),
+        ram_hash: String::new(),
+        framebuffer_hash: String::new(),
+        problems: vec![],
+    };
+    if let Err(e) = play(scenario, base, record, &mut outcome) {
+        outcome.problems.push(e);
+    }
+    outcome
+}
+
+fn play(scenario: &Scenario, base: &Path, record: bool, outcome: &mut Outcome) -> Result<(), String> {
+    let rom_path: PathBuf = base.join(&scenario.rom);
+    let rom: Vec<u8> = fs::read(&rom_path).map_err(|e| format!("{}: {}", rom_path.display(), e))?;
+    let profile: Profile = match &scenario.profile {
+        Some(path) => Profile::load(&base.join(path))?,
+        None => Profile::default(),
+    };
+    let replay: Option<Replay> = match &scenario.replay {
+        Some(path) => Some(Replay::load(&base.join(path))?),
+        None => None,
+    };
+    if let Some(replay) = &replay {
+        replay.check_rom(&rom)?;
+    }
+
+    let frames: u32 = scenario.frames
+        .or(replay.as_ref().map(|replay| replay.frames.len() as u32))
+        .ok_or("Needs frames or a replay to know how long to run")?;
+
+    let mut machine: Machine = Machine::with_profile(profile.for_rom(&rom))?;
+    machine.load_rom(&rom).map_err(|e| e.to_string())?;
+    machine.set_idle_skip(true);
+    // Nothing is waiting on the clock so time spent waiting for interrupts is skipped
+
+    let mut ports: Option<PortLog> = scenario.ports.as_ref().map(|_| PortLog::new(&rom));
+    let mut hash: FrameHash = FrameHash::new();
+    let mut diverged: bool = false;
+    for number in 0..frames as usize {
+        let recorded: Option<&ReplayFrame> = replay.as_ref().and_then(|replay| replay.frames.get(number));
+        machine.hardware.set_inputs(recorded.map_or([0; 2], |frame| frame.inputs));
+        let ran: Result<(), EmulatorError> = match ports.as_mut() {
+            Some(ports) => ports.run_frame(&mut machine),
+            None => machine.run_frame(),
+        };
+        if let Err(e) = ran {
+            outcome.problems.push(format!("Frame {}: {}", number + 1, e));
+            break;
+        }
+
+        let actual: String = format!("{:016x}", hash.update_from(&machine));
+        if let Some(frame) = recorded.filter(|frame| !diverged && frame.hash != actual) {
+            outcome.problems.push(format!("Frame {}: replay expected hash {} but got {}", number + 1, frame.hash, actual));
+            diverged = true;
+        }
+        // Only the first frame is reported, every one after it differs too
+    }
+
+    if let (Some(path), Some(ports)) = (&scenario.ports, &ports) {
+        let path: PathBuf = base.join(path);
+        let checked: Result<(), String> = match record {
+            true => ports.save(&path),
+            false => PortLog::load(&path).and_then(|expected| expected.compare(ports)),
+        };
+        if let Err(e) = checked {
+            outcome.problems.push(e);
+        }
+    }
+
+    outcome.ram_hash = format!("{:016x}", headless::ram_hash(&machine));
+    outcome.framebuffer_hash = format!("{:016x}", framebuffer_hash(&machine));
+    for (what, expected, actual) in [("ram", &scenario.ram_hash, &outcome.ram_hash), ("framebuffer", &scenario.framebuffer_hash, &outcome.framebuffer_hash)] {
+        if let Some(expected) = expected.as_ref().filter(|expected| !expected.eq_ignore_ascii_case(actual)) {
+            outcome.problems.push(format!("Expected {} hash {} but got {}", what, expected, actual));
+        }
+    }
+    Ok(())
+}
+
+pub fn run_all(scenarios: &[Scenario], base: &Path, threads: usize, record: bool) -> Vec<Outcome> {
+    // Spreads the scenarios over threads, outcomes come back in manifest order
+    //  Recording writes the port logs of scenarios that have one rather than checking them
+
+    let per_thread: usize = scenarios.len().div_ceil(threads.max(1)).max(1);
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; scenarios.len()];
+    thread::scope(|scope| {
+        for (chunk, slots) in scenarios.chunks(per_thread).zip(outcomes.chunks_mut(per_thread)) {
+            scope.spawn(move || {
+                for (scenario, slot) in chunk.iter().zip(slots.iter_mut()) {
+                    *slot = Some(play_scenario(scenario, base, record));
+                }
+            });
+        }
+    });
+    outcomes.into_iter().flatten().collect()
+}
+
+pub fn table(outcomes: &[Outcome]) -> String {
+    // One row per scenario with each failure's differences under it, then a count
+
+    let width: usize = outcomes.iter().map(|outcome| outcome.name.len()).max().unwrap_or(0).max("Scenario".len());
+    let mut lines: Vec<String> = vec![format!("{:<width$}  {:<6}  {:<16}  {:<16}", "Scenario", "Result", "Ram hash", "Framebuffer hash")];
+    for outcome in outcomes {
+        let result: &str = if outcome.passed() { "pass" } else { "FAIL" };
+        lines.push(format!("{:<width$}  {:<6}  {:<16}  {:<16}", outcome.name, result, outcome.ram_hash, outcome.framebuffer_hash));
+        for problem in &outcome.problems {
+            lines.push(format!("    {}", problem));
+        }
+    }
+
+    let passed: usize = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    lines.push(format!("{} of {} passed", passed, outcomes.len()));
+    lines.join("\n")
+}
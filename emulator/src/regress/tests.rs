@@ -0,0 +1,96 @@
+#[cfg(test)]
+use super::*;
+
+#[cfg(test)]
+const COUNTER: [u8; 8] = [
+    0xf3,             // 0x0000 DI
+    0x3c,             // 0x0001 INR A
+    0x32, 0x00, 0x20, // 0x0002 STA 0x2000
+    0xc3, 0x01, 0x00, // 0x0005 JMP 0x0001
+];
+
+#[cfg(test)]
+fn scenario(name: &str, frames: Option<u32>) -> Scenario {
+    Scenario {
+        name: name.to_string(),
+        rom: PathBuf::from("counter.rom"),
+        profile: None,
+        replay: None,
+        frames,
+        ram_hash: None,
+        framebuffer_hash: None,
+        ports: None,
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let manifest: Manifest = Manifest::parse(r#"
+        [[scenarios]]
+        name = "counter"
+        rom = "counter.rom"
+        frames = 3
+        ram_hash = "0123456789abcdef"
+    "#).unwrap();
+
+    let mut expected: Scenario = scenario("counter", Some(3));
+    expected.ram_hash = Some(String::from("0123456789abcdef"));
+    assert_eq!(manifest.scenarios, vec![expected]);
+    assert!(Manifest::parse("[[scenarios]]\nname = \"no rom\"").is_err());
+}
+
+#[test]
+fn test_run_all() {
+    let base: PathBuf = std::env::temp_dir().join(format!("emulator-regress-test-{}", std::process::id()));
+    fs::create_dir_all(&base).unwrap();
+    fs::write(base.join("counter.rom"), COUNTER).unwrap();
+
+    let first: Outcome = run(&scenario("counter", Some(3)), &base);
+    assert!(first.passed());
+
+    let mut matching: Scenario = scenario("matching", Some(3));
+    matching.ram_hash = Some(first.ram_hash.to_uppercase());
+    matching.framebuffer_hash = Some(first.framebuffer_hash.clone());
+    let mut changed: Scenario = scenario("changed", Some(4));
+    changed.ram_hash = Some(first.ram_hash.clone());
+    let mut missing: Scenario = scenario("missing", Some(1));
+    missing.rom = PathBuf::from("missing.rom");
+
+    let outcomes: Vec<Outcome> = run_all(&[matching, changed, missing, scenario("no length", None)], &base, 2, false);
+    assert_eq!(outcomes.iter().map(|outcome| (outcome.name.as_str(), outcome.passed())).collect::<Vec<(&str, bool)>>(), vec![
+        ("matching", true),
+        ("changed", false),
+        ("missing", false),
+        ("no length", false),
+    ]);
+    assert_eq!(outcomes[1].problems.len(), 1);
+    assert!(outcomes[1].problems[0].starts_with(&format!("Expected ram hash {} but got", first.ram_hash)));
+
+    let table: String = table(&outcomes);
+    assert!(table.contains("matching   pass"));
+    assert!(table.contains("changed    FAIL"));
+    assert!(table.ends_with("1 of 4 passed"));
+
+    fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn test_port_log() {
+    let base: PathBuf = std::env::temp_dir().join(format!("emulator-regress-ports-test-{}", std::process::id()));
+    fs::create_dir_all(&base).unwrap();
+    fs::write(base.join("counter.rom"), COUNTER).unwrap();
+
+    let mut logged: Scenario = scenario("logged", Some(2));
+    logged.ports = Some(PathBuf::from("counter.ports"));
+    assert!(run(&logged, &base).problems[0].contains("counter.ports"));
+    // Nothing recorded yet
+
+    assert!(record(&logged, &base).passed());
+    assert!(base.join("counter.ports").exists());
+    assert!(run(&logged, &base).passed());
+
+    fs::write(base.join("counter.rom"), [0xf3, 0xd3, 0x05, 0xc3, 0x01, 0x00]).unwrap();
+    assert!(run(&logged, &base).problems[0].starts_with("Recorded with rom"));
+
+    fs::remove_dir_all(&base).unwrap();
+}
@@ -0,0 +1,87 @@
+use raylib::prelude::*;
+
+use crate::display::{Display, Framebuffer};
+use crate::machine::Machine;
+use crate::palette::Palette;
+use crate::postprocess::{Chain, Frame};
+use crate::scrubber::Scrubber;
+use crate::touch::VirtualButtons;
+use crate::window::Layout;
+
+const TEXT_COLOUR: Color = Color::WHITE;
+
+pub struct Hud<'a> {
+    // Everything drawn over the game
+    pub lines: &'a [String],
+    // Text down the left of the window, starting with the controls
+    pub buttons: Option<&'a VirtualButtons>,
+    pub scrubber: Option<&'a Scrubber>,
+}
+
+pub fn render(raylib_handle: &mut raylib::RaylibHandle, thread: &raylib::RaylibThread, machine: &Machine, palette: &Palette, post: &mut Chain, hud: &Hud) {
+    // Renders things to the screen based on the state of the machine
+    //  A bitmap screen goes through the post processors, the overlay colours included, a text screen is coloured by the palette
+    //  While the scrubber is open the machine it is looking at is drawn instead, with its bar along the bottom
+    //  Everything is laid out for the window's current size
+
+    let machine: &Machine = hud.scrubber.and_then(Scrubber::preview).unwrap_or(machine);
+    let layout: Layout = Layout::of(raylib_handle).fit(machine.profile().display.geometry());
+    let mut draw_handle = raylib_handle.begin_drawing(thread);
+    draw_handle.clear_background(palette.background);
+
+    // Debug Rendering
+    for (i, text) in hud.lines.iter().enumerate() {
+        draw_handle.draw_text(text, 0, (i as i32) * layout.text_size, layout.text_size, TEXT_COLOUR);
+        // 1 + i to start the debug strings after the fps
+    }
+    // Draws each debug string in a column
+
+    // Game Rendering
+    match machine.profile().display {
+        Display::Bitmap(_) => {
+            let framebuffer: Framebuffer = machine.profile().display.framebuffer(&machine.cpu.memory);
+            let frame: Frame = post.process(Frame::from_screen(&framebuffer, machine.hardware.flipped()));
+            draw_frame(&mut draw_handle, &layout, &frame);
+        },
+        Display::Text { columns, rows, .. } => {
+            let lines: Vec<String> = machine.profile().display.text_lines(&machine.cpu.memory);
+            draw_text_screen(&mut draw_handle, &layout, &lines, columns, rows, palette);
+        },
+    }
+
+    if let Some(buttons) = hud.buttons {
+        buttons.draw(&mut draw_handle);
+    }
+    if let Some(scrubber) = hud.scrubber {
+        scrubber.draw(&mut draw_handle, &layout);
+    }
+}
+
+fn draw_frame(draw_handle: &mut impl RaylibDraw, layout: &Layout, frame: &Frame) {
+    for (x, y, colour) in frame.pixels() {
+        draw_handle.draw_rectangle(
+            x as i32 * layout.scale + layout.game_x,
+            y as i32 * layout.scale + layout.game_y,
+            layout.scale,
+            layout.scale,
+            colour,
+        );
+    }
+}
+
+fn draw_text_screen(draw_handle: &mut impl RaylibDraw, layout: &Layout, lines: &[String], columns: u16, rows: u16, palette: &Palette) {
+    // Each character is drawn in its own square cell since the default font isn't monospaced
+    //  Cells are coloured the same way as bitmap pixels with rows counted up from the bottom
+
+    let cell: i32 = (layout.width / columns.max(1) as i32).min(layout.height / rows.max(1) as i32);
+    let x_offset: i32 = (layout.width - cell * columns as i32) / 2;
+    let y_offset: i32 = (layout.height - cell * rows as i32) / 2;
+
+    let mut text: [u8; 4] = [0; 4];
+    for (row, line) in lines.iter().enumerate() {
+        for (column, character) in line.chars().enumerate().filter(|(_, character)| *character != ' ') {
+            let colour: Color = palette.lit(rows - 1 - row as u16, column as u16);
+            draw_handle.draw_text(character.encode_utf8(&mut text), x_offset + column as i32 * cell, y_offset + row as i32 * cell, cell, colour);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hardware::sound::SoundEvent;
+use crate::headless::{self, FNV_OFFSET};
+use crate::i18n::{Language, Text};
+use crate::machine::Machine;
+#[cfg(feature = "serde")]
+use crate::storage::{self, Kind, Storage};
+#[cfg(feature = "serde")]
+use crate::versioned;
+
+mod tests;
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHash {
+    // Rolling hash over every frame so far, so changing any earlier frame changes every later hash
+    //  Each frame adds its number, the input ports it ran with and the ram hash it ended with
+    frame: u64,
+    hash: u64,
+}
+impl FrameHash {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            hash: FNV_OFFSET,
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn update(&mut self, inputs: [u8; 2], ram_hash: u64) -> u64 {
+        self.frame += 1;
+        self.hash = headless::fnv1a(self.hash, &self.frame.to_le_bytes());
+        self.hash = headless::fnv1a(self.hash, &inputs);
+        self.hash = headless::fnv1a(self.hash, &ram_hash.to_le_bytes());
+        self.hash
+    }
+
+    pub fn update_from(&mut self, machine: &Machine) -> u64 {
+        // Called at the end of every frame
+        self.update(machine.hardware.inputs(), headless::ram_hash(machine))
+    }
+
+    pub fn hud(&self, language: Language) -> String {
+        format!("{} {} {:016x}", language.text(Text::Frame), self.frame, self.hash)
+    }
+}
+impl Default for FrameHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReplayFrame {
+    pub inputs: [u8; 2],
+    // Input ports 1 and 2 while the frame ran
+    pub hash: String,
+    // Rolling frame hash after the frame, in hex
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub sounds: Option<Vec<SoundEvent>>,
+    // Sounds started and stopped at the end of the frame, played back as they are instead of from the sound ports
+    //  None in replays recorded before sounds were kept, those still work them out from the ports
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Replay {
+    // Input for every frame of a run with the frame hashes it should produce
+    //  Playing it back on the same rom has to give the same hashes, so anyone can check a replay
+    //  hasn't been edited by running it
+    pub version: u32,
+    pub rom_hash: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+    // What the machine was seeded with, replays made before machines were seeded used the default
+    pub frames: Vec<ReplayFrame>,
+}
+impl Replay {
+    pub fn new(rom: &[u8]) -> Self {
+        Self {
+            version: VERSION,
+            rom_hash: headless::rom_hash(rom),
+            seed: None,
+            frames: vec![],
+        }
+    }
+
+    pub fn record(&mut self, inputs: [u8; 2], hash: u64) {
+        self.frames.push(ReplayFrame { inputs, hash: format!("{:016x}", hash), sounds: None });
+    }
+
+    pub fn record_sounds(&mut self, sounds: &[SoundEvent]) {
+        // Called after record with the audio update that followed the frame
+        if let Some(frame) = self.frames.last_mut() {
+            frame.sounds = Some(sounds.to_vec());
+        }
+    }
+
+    pub fn check_rom(&self, rom: &[u8]) -> Result<(), String> {
+        match headless::rom_hash(rom) == self.rom_hash {
+            true => Ok(()),
+            false => Err(String::from("Replay was recorded with a different rom")),
+        }
+    }
+
+    pub fn verify(&self, machine: &mut Machine) -> Result<u64, String> {
+        // Plays the whole replay and returns the final hash, or where it stopped matching
+
+        self.check_rom(machine.rom())?;
+        let mut hash: FrameHash = FrameHash::new();
+        for (number, frame) in self.frames.iter().enumerate() {
+            machine.hardware.set_inputs(frame.inputs);
+            machine.run_frame().map_err(|e| format!("Frame {}: {}", number + 1, e))?;
+            let actual: String = format!("{:016x}", hash.update_from(machine));
+            if actual != frame.hash {
+                return Err(format!("Frame {}: expected hash {} but got {}", number + 1, frame.hash, actual));
+            }
+        }
+        Ok(hash.hash())
+    }
+}
+#[cfg(feature = "serde")]
+impl Replay {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        versioned::from_json(text, "replay", VERSION, &[])
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map(|json| json + "\n").map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn read(storage: &dyn Storage, name: &str) -> Result<Self, String> {
+        let text: String = storage::read_text(storage, Kind::Replay, name)?
+            .ok_or_else(|| format!("Nothing saved at {}", storage.describe(Kind::Replay, name)))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", storage.describe(Kind::Replay, name), e))
+    }
+
+    pub fn write(&self, storage: &mut dyn Storage, name: &str) -> Result<(), String> {
+        storage.write(Kind::Replay, name, self.to_json()?.as_bytes())
+    }
+}
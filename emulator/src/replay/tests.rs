@@ -0,0 +1,135 @@
+#[cfg(test)]
+use super::*;
+#[cfg(all(test, feature = "serde"))]
+use crate::hardware::sound::Sound;
+#[cfg(test)]
+use crate::fixtures;
+
+#[cfg(test)]
+const ROM: [u8; 9] = [
+    0xf3,             // 0x0000 DI
+    0xdb, 0x01,       // 0x0001 IN 1
+    0x32, 0x00, 0x20, // 0x0003 STA 0x2000
+    0xc3, 0x01, 0x00, // 0x0006 JMP 0x0001
+];
+
+#[cfg(test)]
+const WITH_INTERRUPTS: &str = "
+    JMP Start
+    ORG 0x08
+    JMP MidFrame
+    ORG 0x10
+    JMP EndOfFrame
+    ORG 0x20
+Start: LXI SP,0x2400
+    EI
+Spin: JMP Spin
+MidFrame: PUSH PSW
+    LDA 0x2001
+    INR A
+    STA 0x2001
+    POP PSW
+    EI
+    RET
+EndOfFrame: PUSH PSW
+    IN 1
+    STA 0x2000
+    POP PSW
+    EI
+    RET
+";
+// Reads the input from the end of frame interrupt and counts the mid frame ones, the main loop only spins
+
+#[cfg(test)]
+fn record(inputs: &[[u8; 2]]) -> Replay {
+    // Records a run of the test rom with the given input on each frame
+    record_rom(&ROM, inputs)
+}
+
+#[cfg(test)]
+fn record_rom(rom: &[u8], inputs: &[[u8; 2]]) -> Replay {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(rom).unwrap();
+    let mut replay: Replay = Replay::new(rom);
+    let mut hash: FrameHash = FrameHash::new();
+    for frame_inputs in inputs {
+        machine.hardware.set_inputs(*frame_inputs);
+        machine.run_frame().unwrap();
+        replay.record(*frame_inputs, hash.update_from(&machine));
+    }
+    replay
+}
+
+#[test]
+fn test_frame_hash() {
+    let mut first: FrameHash = FrameHash::new();
+    let mut second: FrameHash = FrameHash::new();
+    assert_eq!(first.update([0, 0], 1), second.update([0, 0], 1));
+    assert_ne!(first.update([0, 0], 1), second.update([1, 0], 1));
+    assert_ne!(first.update([0, 0], 1), second.update([0, 0], 1));
+    // Frames stay different once the chains have split
+    assert_eq!(first.frame(), 3);
+    assert_eq!(first.hud(Language::English), format!("Frame 3 {:016x}", first.hash()));
+}
+
+#[test]
+fn test_replay_verify() {
+    let replay: Replay = record(&[[0x00, 0x00], [0x04, 0x00], [0x04, 0x00], [0x00, 0x00]]);
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&ROM).unwrap();
+    assert_eq!(replay.verify(&mut machine), Ok(u64::from_str_radix(&replay.frames[3].hash, 16).unwrap()));
+
+    let mut tampered: Replay = replay.clone();
+    tampered.frames[2].inputs = [0x00, 0x00];
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&ROM).unwrap();
+    assert!(tampered.verify(&mut machine).is_err_and(|e| e.starts_with("Frame 3:")));
+
+    let mut tampered: Replay = replay.clone();
+    tampered.frames[1].hash = format!("{:016x}", 0);
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&ROM).unwrap();
+    assert!(tampered.verify(&mut machine).is_err_and(|e| e.starts_with("Frame 2:")));
+
+    assert!(replay.check_rom(&ROM).is_ok());
+    assert!(replay.check_rom(&[0x00]).is_err());
+}
+
+#[test]
+fn test_replay_with_interrupts() {
+    // The vblank RSTs run during the replay just as they did while recording
+    let rom: Vec<u8> = fixtures::rom(WITH_INTERRUPTS);
+    let replay: Replay = record_rom(&rom, &[[0x00, 0x00], [0x04, 0x00], [0x04, 0x00], [0x00, 0x00]]);
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    assert_eq!(replay.verify(&mut machine), Ok(u64::from_str_radix(&replay.frames[3].hash, 16).unwrap()));
+    assert_eq!(machine.cpu.memory.read_at(0x2001), 4);
+    // One mid frame interrupt a frame
+
+    let mut tampered: Replay = replay.clone();
+    tampered.frames[1].inputs = [0x00, 0x00];
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    assert!(tampered.verify(&mut machine).is_err_and(|e| e.starts_with("Frame 2:")));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_replay_sounds() {
+    let mut replay: Replay = record(&[[0x00, 0x00], [0x04, 0x00]]);
+    replay.record_sounds(&[SoundEvent::Play(Sound::FleetMove1), SoundEvent::Stop(Sound::Ufo)]);
+    assert_eq!(replay.frames[0].sounds, None);
+
+    let json: String = replay.to_json().unwrap();
+    assert!(json.contains(r#""sounds":[{"play":"fleet_move_1"},{"stop":"ufo"}]"#), "{}", json);
+    assert_eq!(Replay::from_json(&json), Ok(replay.clone()));
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&ROM).unwrap();
+    assert!(replay.verify(&mut machine).is_ok());
+    // Sounds don't change the frame hashes
+
+    let old: String = format!(r#"{{"version":1,"rom_hash":"{}","frames":[{{"inputs":[0,0],"hash":"{}"}}]}}"#, replay.rom_hash, replay.frames[0].hash);
+    assert_eq!(Replay::from_json(&old).unwrap().frames[0].sounds, None);
+    // Replays from before sounds were kept still load
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::crash;
+use crate::error::EmulatorError;
+use crate::headless::{self, StateDump};
+use crate::machine::Machine;
+use crate::palette::Palette;
+use crate::png;
+use crate::zip;
+
+mod tests;
+
+pub const DIRECTORY: &str = "reports";
+// Where bug reports go when no other folder is given, relative to the working directory
+
+pub fn bundle(machine: &Machine, palette: &Palette, frames: u32, fault: Option<&EmulatorError>, about: &[String]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    // Everything needed to see a problem happen again, as the files that go in the zip
+    //      README.txt      rom hash, version, how the emulator was started and what the other files are
+    //      profile.txt     the profile as the machine has it, rom settings and --clock included
+    //      state.json      a save state, copy it over the rom's save state and press F9 to carry on from here
+    //      dump.json       registers, cycles and ram hash in the same format as --headless --dump
+    //      trace.txt       the last instructions run
+    //      screen.png      the screen as it is now
+    //      timeline.json   interrupts, sounds, coins and faults, only when running with --timeline
+    //  about is a line each for anything the machine doesn't know, like the command line
+
+    let geometry = machine.profile().display.geometry();
+    let screen: Vec<u8> = png::encode_rgb(geometry.width as u32, geometry.height as u32, &headless::framebuffer(&machine.cpu, geometry, palette))?;
+    let trace: String = match fault {
+        Some(fault) => crash::trace(machine, fault),
+        None => std::iter::once(machine.cpu.registers().to_string()).chain(crash::recent_instructions(machine)).collect::<Vec<String>>().join("\n") + "\n",
+    };
+    let dump: String = serde_json::to_string_pretty(&StateDump::capture(machine, frames, fault.map(EmulatorError::to_string))).map_err(|e| e.to_string())?;
+
+    let mut readme: Vec<String> = vec![
+        format!("Bug report from emulator {}", env!("CARGO_PKG_VERSION")),
+        format!("Rom hash {}", headless::rom_hash(machine.rom())),
+        format!("Profile {}", machine.profile().name),
+        format!("Frames {}, cycles {}", frames, machine.cycles()),
+    ];
+    readme.extend(about.iter().cloned());
+    if let Some(fault) = fault {
+        readme.push(format!("Stopped by {}", fault));
+    }
+    readme.extend([
+        String::new(),
+        String::from("profile.txt     the profile as the machine has it, rom settings and --clock included"),
+        String::from("state.json      a save state, copy it over the rom's save state and press F9 to carry on from here"),
+        String::from("dump.json       registers, cycles and ram hash in the same format as --headless --dump"),
+        String::from("trace.txt       the last instructions run"),
+        String::from("screen.png      the screen as it was"),
+    ]);
+
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        (String::from("profile.txt"), format!("{:#?}\n", machine.profile()).into_bytes()),
+        (String::from("state.json"), (machine.save_state().to_json()? + "\n").into_bytes()),
+        (String::from("dump.json"), (dump + "\n").into_bytes()),
+        (String::from("trace.txt"), trace.into_bytes()),
+        (String::from("screen.png"), screen),
+    ];
+    match machine.timeline() {
+        Some(timeline) => {
+            files.push((String::from("timeline.json"), (timeline.to_json()? + "\n").into_bytes()));
+            readme.push(String::from("timeline.json   interrupts, sounds, coins and faults"));
+        },
+        None => readme.push(String::from("No timeline, run with --timeline <file> to include one")),
+    }
+    files.insert(0, (String::from("README.txt"), (readme.join("\n") + "\n").into_bytes()));
+
+    Ok(files)
+}
+
+pub fn write(machine: &Machine, palette: &Palette, frames: u32, fault: Option<&EmulatorError>, about: &[String], directory: &Path) -> Result<PathBuf, String> {
+    // Saves the bundle as report-<time>.zip in directory and returns its path, ready to attach to an issue
+
+    let zip: Vec<u8> = zip::encode(&bundle(machine, palette, frames, fault, about)?)?;
+    fs::create_dir_all(directory).map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    let path: PathBuf = directory.join(format!("report-{}.zip", crash::timestamp(SystemTime::now())));
+    fs::write(&path, zip).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
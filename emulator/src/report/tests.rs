@@ -0,0 +1,40 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::profile::Profile;
+
+#[test]
+fn test_report() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x3c,             // 0x0001 INR A
+        0xc3, 0x01, 0x00, // 0x0002 JMP 0x0001
+    ]).unwrap();
+    machine.set_timeline(true);
+    machine.run_frame().unwrap();
+    let palette: Palette = Palette::new(&Profile::space_invaders().overlay).unwrap();
+
+    let files: Vec<(String, Vec<u8>)> = bundle(&machine, &palette, 1, None, &[String::from("Started with emulator invaders.rom")]).unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["README.txt", "profile.txt", "state.json", "dump.json", "trace.txt", "screen.png", "timeline.json"]);
+
+    let readme: String = String::from_utf8(files[0].1.clone()).unwrap();
+    assert!(readme.contains(&format!("Rom hash {}", headless::rom_hash(machine.rom()))));
+    assert!(readme.contains("Started with emulator invaders.rom"));
+    let trace: String = String::from_utf8(files[4].1.clone()).unwrap();
+    assert!(trace.lines().any(|line| line.starts_with("0002   c3")), "{}", trace);
+
+    let directory: PathBuf = std::env::temp_dir().join(format!("emulator-report-test-{}", std::process::id()));
+    let path: PathBuf = write(&machine, &palette, 1, None, &[], &directory).unwrap();
+    assert_eq!(path.extension().and_then(|extension| extension.to_str()), Some("zip"));
+    let unzipped: Vec<(String, Vec<u8>)> = zip::decode(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(unzipped.len(), 7);
+    assert_eq!(unzipped[2], files[2]);
+    // The state is the same one the bundle was made with
+    fs::remove_dir_all(&directory).unwrap();
+
+    machine.set_timeline(false);
+    let files: Vec<(String, Vec<u8>)> = bundle(&machine, &palette, 1, None, &[]).unwrap();
+    assert_eq!(files.len(), 6);
+}
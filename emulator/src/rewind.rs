@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+use crate::machine::Machine;
+
+const DEFAULT_CAPACITY: usize = 64;
+const DEFAULT_INTERVAL: u64 = 1000;
+// A snapshot every 1000 instructions keeps a bit over 10 frames of history
+
+#[derive(Clone)]
+pub struct Rewind {
+    // Snapshots of the machine taken every interval instructions
+    //  Any instruction in the history can be reached by restoring the snapshot before it
+    //  and running the instructions in between again
+    snapshots: VecDeque<(u64, Machine)>,
+    // Paired with the instruction count each snapshot was taken at
+    capacity: usize,
+    interval: u64,
+}
+impl Rewind {
+    pub fn new(capacity: usize, interval: u64) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            interval: interval.max(1),
+        }
+    }
+
+    pub fn record(&mut self, instruction: u64, machine: &Machine) {
+        // Should be called before every instruction, only takes a snapshot when one is due
+
+        if !instruction.is_multiple_of(self.interval) {
+            return;
+        }
+
+        while self.snapshots.back().is_some_and(|(taken, _)| *taken >= instruction) {
+            self.snapshots.pop_back();
+        }
+        // Anything newer than this instruction belongs to a future that is being rewritten
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((instruction, machine.clone()));
+    }
+
+    pub fn nearest(&self, instruction: u64) -> Option<(u64, &Machine)> {
+        // Latest snapshot taken at or before the given instruction
+        self.snapshots.iter()
+            .rev()
+            .find(|(taken, _)| *taken <= instruction)
+            .map(|(taken, machine)| (*taken, machine))
+    }
+
+    pub fn oldest(&self) -> Option<u64> {
+        self.snapshots.front().map(|(taken, _)| *taken)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_INTERVAL)
+    }
+}
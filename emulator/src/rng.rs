@@ -0,0 +1,54 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod tests;
+
+pub const DEFAULT_SEED: u64 = 0x8080_8080_8080_8080;
+// Used until the frontend seeds the machine, so headless runs and tests always see the same numbers
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    // Splitmix64, the only place the machine is allowed to get randomness from
+    //  The whole state is one number so it goes into save states and is cloned with the machine,
+    //  rewinding or replaying from a snapshot then gives the same numbers as the first time
+    state: u64,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z: u64 = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let random: [u8; 8] = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&random[..chunk.len()]);
+        }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn default_state() -> u64 {
+        DEFAULT_SEED
+    }
+}
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+pub fn host_seed() -> u64 {
+    // A different seed each run from the clock and process id
+    //  Anything that records a run has to keep the seed, it can't be got back afterwards
+    let nanos: u128 = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos());
+    Rng::new((nanos as u64) ^ ((std::process::id() as u64) << 32)).next_u64()
+}
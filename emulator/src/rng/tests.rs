@@ -0,0 +1,108 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::headless;
+#[cfg(test)]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::profile::Profile;
+#[cfg(all(test, feature = "serde"))]
+use crate::replay::{FrameHash, Replay};
+#[cfg(test)]
+use crate::rewind::Rewind;
+#[cfg(test)]
+use crate::savestate::SaveState;
+
+#[cfg(test)]
+const ROM: [u8; 9] = [
+    0xf3,             // 0x0000 DI
+    0xdb, 0x01,       // 0x0001 IN 1
+    0x32, 0x00, 0x20, // 0x0003 STA 0x2000
+    0xc3, 0x01, 0x00, // 0x0006 JMP 0x0001
+];
+
+#[cfg(test)]
+fn noisy_machine(seed: u64) -> Machine {
+    // Powers on with ram full of noise from the seed
+    let mut machine: Machine = Machine::with_profile(Profile { randomize_ram: true, ..Profile::space_invaders() }).unwrap();
+    machine.seed(seed);
+    machine.load_rom(&ROM).unwrap();
+    machine
+}
+
+#[test]
+fn test_rng() {
+    let mut first: Rng = Rng::new(1);
+    let mut second: Rng = Rng::new(1);
+    assert_eq!(first.next_u64(), second.next_u64());
+    assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+    let mut bytes: [u8; 11] = [0; 11];
+    first.fill(&mut bytes);
+    let expected: [u8; 8] = second.next_u64().to_le_bytes();
+    assert_eq!(bytes[..8], expected);
+    assert_eq!(bytes[8..], second.next_u64().to_le_bytes()[..3]);
+    // Whatever is left of the last number is dropped
+    assert_eq!(first.state(), second.state());
+}
+
+#[test]
+fn test_seeded_ram() {
+    assert_eq!(headless::ram_hash(&noisy_machine(7)), headless::ram_hash(&noisy_machine(7)));
+    assert_ne!(headless::ram_hash(&noisy_machine(7)), headless::ram_hash(&noisy_machine(8)));
+    assert_ne!(noisy_machine(7).cpu.memory.view(0x2400..0x2500), [0; 0x100]);
+
+    let mut plain: Machine = Machine::init();
+    plain.seed(7);
+    plain.load_rom(&ROM).unwrap();
+    assert!(plain.cpu.memory.view(0x2000..0x4000).iter().all(|byte| *byte == 0));
+    // Only profiles that ask for it get noise
+}
+
+#[test]
+fn test_rewind_then_replay() {
+    // Going back to a snapshot and running again has to give the same hashes and the same random numbers
+
+    let mut machine: Machine = noisy_machine(42);
+    let mut rewind: Rewind = Rewind::new(4, 1);
+    for _ in 0..3 {
+        machine.run_frame().unwrap();
+    }
+    rewind.record(1, &machine);
+    let state: SaveState = machine.save_state();
+
+    let run = |machine: &mut Machine| -> (Vec<u64>, u64) {
+        let mut hashes: Vec<u64> = vec![];
+        for frame in 0..5u8 {
+            machine.hardware.set_inputs([frame, 0]);
+            machine.run_frame().unwrap();
+            hashes.push(headless::ram_hash(machine));
+        }
+        (hashes, machine.rng().next_u64())
+    };
+    let first: (Vec<u64>, u64) = run(&mut machine);
+
+    let mut rewound: Machine = rewind.nearest(1).unwrap().1.clone();
+    assert_eq!(run(&mut rewound), first);
+    let mut loaded: Machine = noisy_machine(1);
+    loaded.load_state(&state).unwrap();
+    assert_eq!(run(&mut loaded), first);
+    // A save state carries the random numbers on from where they were
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_seeded_replay() {
+    let mut machine: Machine = noisy_machine(99);
+    let mut replay: Replay = Replay { seed: Some(99), ..Replay::new(&ROM) };
+    let mut hash: FrameHash = FrameHash::new();
+    for frame in 0..4u8 {
+        machine.hardware.set_inputs([frame, 0]);
+        machine.run_frame().unwrap();
+        replay.record([frame, 0], hash.update_from(&machine));
+    }
+
+    let replay: Replay = Replay::from_json(&replay.to_json().unwrap()).unwrap();
+    assert!(replay.verify(&mut noisy_machine(replay.seed.unwrap())).is_ok());
+    assert!(replay.verify(&mut noisy_machine(100)).is_err());
+}
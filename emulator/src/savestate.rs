@@ -0,0 +1,206 @@
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+use disassembler::symbols::SymbolTable;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_json::{Map, Value};
+
+use crate::cpu::Registers;
+use crate::profile::{Profile, RegionKind};
+#[cfg(feature = "serde")]
+use crate::rng::Rng;
+#[cfg(feature = "serde")]
+use crate::storage::{self, Kind, Storage};
+#[cfg(feature = "serde")]
+use crate::versioned::{self, Migration};
+
+mod tests;
+
+pub const VERSION: u32 = 2;
+pub const EXTENSION: &str = "state";
+
+#[cfg(feature = "serde")]
+const MIGRATIONS: &[(u32, Migration)] = &[(1, version_1)];
+// Old states are upgraded when they're loaded so saves keep working as the machine gains state
+//  Anything added needs a new version and a migration filling it in, and a fixture in tests/savestates
+
+const SHOWN_BYTES: usize = 16;
+// Bytes printed for each differing range, longer ranges are cut short
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SaveState {
+    // Everything needed to carry on from a point in a game, saved as json
+    //  Banks that aren't selected and sound latches aren't kept, they are back to how they
+    //  were when the state is loaded
+    pub version: u32,
+    pub rom_hash: String,
+    pub registers: Registers,
+    pub memory: String,
+    // Hex of every byte of memory from address 0
+    pub hardware: HardwareState,
+    pub banks: Vec<u8>,
+    // Selected bank of each banked window
+    pub total_cycles: u64,
+    pub frame_cycles: u64,
+    pub mid_screen_done: bool,
+    pub halted: bool,
+    #[cfg_attr(feature = "serde", serde(default = "Rng::default_state"))]
+    pub rng: u64,
+    // State of the machine's random numbers, states from before there were any start from the default seed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HardwareState {
+    // The board's own state, kept apart from the cpu's
+    pub shift_register: u16,
+    pub shift_amount: u8,
+    pub service_mode: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flipped: bool,
+    // Player 2's turn on a cocktail cabinet, older states were all saved upright
+}
+#[cfg(feature = "serde")]
+impl SaveState {
+    // Saved as json, without the serde feature states only live in memory for rewinding and stepping back
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        versioned::from_json(text, "save state", VERSION, MIGRATIONS)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map(|json| json + "\n").map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn read(storage: &dyn Storage, name: &str) -> Result<Self, String> {
+        let text: String = storage::read_text(storage, Kind::SaveState, name)?
+            .ok_or_else(|| format!("Nothing saved at {}", storage.describe(Kind::SaveState, name)))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", storage.describe(Kind::SaveState, name), e))
+    }
+
+    pub fn write(&self, storage: &mut dyn Storage, name: &str) -> Result<(), String> {
+        storage.write(Kind::SaveState, name, self.to_json()?.as_bytes())
+    }
+}
+impl SaveState {
+    pub fn memory(&self) -> Result<Vec<u8>, String> {
+        // Decodes the memory image
+        (0..self.memory.len())
+            .step_by(2)
+            .map(|index| {
+                self.memory.get(index..index + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| format!("Save state memory has a bad byte at 0x{:04x}", index / 2))
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn version_1(state: &mut Map<String, Value>) -> Result<(), String> {
+    // Version 1 kept the shift register at the top level and only gained halted partway through
+    //  Version 2 moves the board's state into hardware, along with the service switch
+
+    let mut take = |key: &str| state.remove(key).ok_or_else(|| format!("{} is missing", key));
+    let hardware: Value = serde_json::json!({
+        "shift_register": take("shift_register")?,
+        "shift_amount": take("shift_amount")?,
+        "service_mode": false,
+    });
+    state.insert(String::from("hardware"), hardware);
+    state.entry("halted").or_insert(Value::Bool(false));
+    Ok(())
+}
+
+pub fn encode_memory(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    // A run of neighbouring addresses that changed
+    pub start: u16,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+}
+impl Difference {
+    pub fn end(&self) -> u16 {
+        // Last address in the run
+        self.start + (self.before.len() as u16 - 1)
+    }
+}
+
+pub fn diff(before: &[u8], after: &[u8]) -> Vec<Difference> {
+    // Every address where the two images differ, grouped into runs of neighbouring addresses
+
+    let mut differences: Vec<Difference> = vec![];
+    for (address, (old, new)) in before.iter().zip(after).enumerate() {
+        if old == new {
+            continue;
+        }
+        match differences.last_mut() {
+            Some(last) if last.end() as usize + 1 == address => {
+                last.before.push(*old);
+                last.after.push(*new);
+            },
+            _ => differences.push(Difference { start: address as u16, before: vec![*old], after: vec![*new] }),
+        }
+    }
+    differences
+}
+
+pub fn format_diff(differences: &[Difference], profile: &Profile, symbols: &SymbolTable) -> String {
+    // A line per run with the region it's in and the nearest symbol when there is one
+    //  e.g. "0x20f8-0x20f9 ram  PlayerScore  10 00 -> 20 00"
+
+    if differences.is_empty() {
+        return String::from("No differences");
+    }
+
+    let hex = |bytes: &[u8]| {
+        let shown: Vec<String> = bytes.iter().take(SHOWN_BYTES).map(|byte| format!("{:02x}", byte)).collect();
+        match bytes.len() > SHOWN_BYTES {
+            true => format!("{} ...", shown.join(" ")),
+            false => shown.join(" "),
+        }
+    };
+
+    differences.iter()
+        .map(|difference| {
+            let mut line: String = format!("0x{:04x}-0x{:04x} {:<8}", difference.start, difference.end(), region(difference.start, profile));
+            if symbols.locate(difference.start).is_some() {
+                line.push_str(&format!(" {}", symbols.format(difference.start)));
+            }
+            format!("{}  {} -> {}", line, hex(&difference.before), hex(&difference.after))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn region(address: u16, profile: &Profile) -> &'static str {
+    // Which part of the profile's memory map an address is in
+
+    let kind: Option<RegionKind> = profile.memory.iter()
+        .find(|region| region.contains(address))
+        .map(|region| region.kind);
+    match kind {
+        Some(RegionKind::Rom) => "rom",
+        Some(RegionKind::Ram) => "ram",
+        Some(RegionKind::Vram) => "vram",
+        None if profile.mirror.is_some_and(|mirror| address >= mirror.start) => "mirror",
+        None => "unmapped",
+    }
+}
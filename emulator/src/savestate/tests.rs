@@ -0,0 +1,80 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::profile::Region;
+
+#[test]
+fn test_save_state() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[
+        0xf3,             // 0x0000 DI
+        0x3c,             // 0x0001 INR A
+        0x32, 0x00, 0x20, // 0x0002 STA 0x2000
+        0xc3, 0x01, 0x00, // 0x0005 JMP 0x0001
+    ]).unwrap();
+    machine.run_frame().unwrap();
+    let state: SaveState = machine.save_state();
+    let saved: Machine = machine.clone();
+
+    machine.run_frame().unwrap();
+    assert_ne!(machine.cpu.registers(), saved.cpu.registers());
+    machine.load_state(&state).unwrap();
+    assert_eq!((machine.cpu.registers(), machine.cycles()), (saved.cpu.registers(), saved.cycles()));
+    assert_eq!(machine.cpu.memory.bytes(), saved.cpu.memory.bytes());
+
+    let mut other: Machine = Machine::init();
+    other.load_rom(&[0x00]).unwrap();
+    assert!(other.load_state(&state).is_err());
+}
+
+#[test]
+fn test_memory_diff() {
+    let before: Vec<u8> = vec![0x00; 0x2400];
+    let mut after: Vec<u8> = before.clone();
+    after[0x2000..0x2003].copy_from_slice(&[0x01, 0x02, 0x03]);
+    after[0x2010] = 0xff;
+    after[0x2300..0x2400].fill(0xee);
+
+    let differences: Vec<Difference> = diff(&before, &after);
+    assert_eq!(differences.iter().map(|difference| (difference.start, difference.end())).collect::<Vec<(u16, u16)>>(),
+        vec![(0x2000, 0x2002), (0x2010, 0x2010), (0x2300, 0x23ff)]);
+
+    let symbols: SymbolTable = SymbolTable::parse("0x2010 Score").unwrap();
+    let lines: Vec<String> = format_diff(&differences, &Profile::space_invaders(), &symbols).lines().map(String::from).collect();
+    assert_eq!(lines[0], "0x2000-0x2002 ram       00 00 00 -> 01 02 03");
+    assert_eq!(lines[1], "0x2010-0x2010 ram      Score  00 -> ff");
+    assert!(lines[2].ends_with("ee ee ..."));
+    assert_eq!(format_diff(&[], &Profile::space_invaders(), &symbols), "No differences");
+
+    let state: SaveState = Machine::init().save_state();
+    assert_eq!(state.memory().unwrap().len(), 0x10000);
+
+    let mut profile: Profile = Profile::space_invaders();
+    profile.mirror = None;
+    profile.memory.push(Region { start: 0xf000, end: 0x10000, kind: RegionKind::Vram });
+    assert_eq!((region(0xefff, &profile), region(0xffff, &profile)), ("unmapped", "vram"));
+    // A region can run to the top of memory
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_old_versions() {
+    // Each fixture is the same 10 frames of tests/golden/stripes.bin with the shift register set to 0x1234 by 3
+    //  saved by the version in its name, so they should all load as the same state
+    let current: SaveState = SaveState::from_json(include_str!("../../tests/savestates/v2.state")).unwrap();
+    for old in [include_str!("../../tests/savestates/v1-before-halted.state"), include_str!("../../tests/savestates/v1.state")] {
+        assert_eq!(SaveState::from_json(old).unwrap(), current);
+    }
+    assert_eq!(current.hardware, HardwareState { shift_register: 0x1234, shift_amount: 3, service_mode: false, flipped: false });
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(include_bytes!("../../tests/golden/stripes.bin")).unwrap();
+    machine.load_state(&current).unwrap();
+    assert_eq!(machine.save_state(), current);
+    machine.run_frame().unwrap();
+
+    let newer: String = current.to_json().unwrap().replacen("\"version\":2", "\"version\":3", 1);
+    assert!(SaveState::from_json(&newer).unwrap_err().contains("newer emulator"));
+}
@@ -0,0 +1,120 @@
+use crate::error::EmulatorError;
+use crate::machine::Machine;
+
+mod tests;
+
+pub type Action = Box<dyn FnMut(&mut Machine)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Cycle(u64),
+    // Machine's total cycles, see Machine::cycles
+    Frame(u64),
+    // Frames run by the scheduler, frame 0 is the first one it runs
+}
+
+struct Scheduled {
+    id: u64,
+    when: When,
+    every: Option<u64>,
+    // Repeats this many cycles or frames later, in the same unit as when
+    action: Action,
+}
+
+pub struct Scheduler {
+    // Runs actions at set cycles or frames while it drives the machine, for scripts that press buttons
+    //  at exact times, e.g. scheduler.at(When::Cycle(1_000_000), Box::new(|machine| ...))
+    //  Actions run between instructions, before the first one that starts on or after the cycle they are due
+    //  or before the first instruction of the frame they are due, so a run with the same actions always
+    //  behaves the same
+    //  Actions due before the same instruction run in the order they were scheduled
+    scheduled: Vec<Scheduled>,
+    next_id: u64,
+    frames: u64,
+}
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            scheduled: vec![],
+            next_id: 0,
+            frames: 0,
+        }
+    }
+
+    pub fn at(&mut self, when: When, action: Action) -> u64 {
+        // Runs once, returns an id that can be given to cancel
+        self.add(when, None, action)
+    }
+
+    pub fn every(&mut self, first: When, period: u64, action: Action) -> u64 {
+        // Runs at first then every period cycles or frames after it until cancelled
+        self.add(first, Some(period.max(1)), action)
+    }
+
+    fn add(&mut self, when: When, every: Option<u64>, action: Action) -> u64 {
+        let id: u64 = self.next_id;
+        self.next_id += 1;
+        self.scheduled.push(Scheduled { id, when, every, action });
+        id
+    }
+
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let before: usize = self.scheduled.len();
+        self.scheduled.retain(|scheduled| scheduled.id != id);
+        self.scheduled.len() != before
+    }
+
+    pub fn pending(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    pub fn frame(&self) -> u64 {
+        // Frames finished since the scheduler started driving the machine
+        self.frames
+    }
+
+    pub fn step(&mut self, machine: &mut Machine) -> Result<bool, EmulatorError> {
+        // Runs whatever is due then one instruction, returns true when it finished the frame
+        //  Used in place of Machine::step_frame
+
+        self.run_due(machine);
+        let finished: bool = machine.step_frame()?;
+        if finished {
+            self.frames += 1;
+        }
+        Ok(finished)
+    }
+
+    pub fn run_frame(&mut self, machine: &mut Machine) -> Result<(), EmulatorError> {
+        while !self.step(machine)? {}
+        Ok(())
+    }
+
+    fn run_due(&mut self, machine: &mut Machine) {
+        // Kept in the order they were added, a repeating action keeps its place when it comes round again
+
+        let cycles: u64 = machine.cycles();
+        let frames: u64 = self.frames;
+        let due = |when: When| match when {
+            When::Cycle(cycle) => cycle <= cycles,
+            When::Frame(frame) => frame <= frames,
+        };
+
+        for scheduled in self.scheduled.iter_mut().filter(|scheduled| due(scheduled.when)) {
+            (scheduled.action)(machine);
+            if let Some(every) = scheduled.every {
+                scheduled.when = match scheduled.when {
+                    When::Cycle(cycle) => When::Cycle(cycle + every * ((cycles - cycle) / every + 1)),
+                    When::Frame(frame) => When::Frame(frame + every * ((frames - frame) / every + 1)),
+                };
+                // Lands on the next time after now, times missed while nothing was run are skipped
+            }
+        }
+        self.scheduled.retain(|scheduled| scheduled.every.is_some() || !due(scheduled.when));
+    }
+}
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
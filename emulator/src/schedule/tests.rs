@@ -0,0 +1,57 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::machine::CYCLES_PER_FRAME;
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::rc::Rc;
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_cycle_actions() {
+    let mut machine: Machine = fixtures::machine(fixtures::NOP_LOOP);
+    let mut scheduler: Scheduler = Scheduler::new();
+    let log: Rc<RefCell<Vec<(&str, u64)>>> = Rc::new(RefCell::new(vec![]));
+
+    for name in ["first", "second"] {
+        let log: Rc<RefCell<Vec<(&str, u64)>>> = log.clone();
+        scheduler.at(When::Cycle(10), Box::new(move |machine| log.borrow_mut().push((name, machine.cycles()))));
+    }
+    let cancelled: u64 = scheduler.at(When::Cycle(0), Box::new(|_| panic!("cancelled actions don't run")));
+    assert!(scheduler.cancel(cancelled));
+    assert!(!scheduler.cancel(cancelled));
+
+    for _ in 0..4 {
+        scheduler.step(&mut machine).unwrap();
+    }
+    assert_eq!(*log.borrow(), vec![("first", 12), ("second", 12)]);
+    // DI and two NOPs end on cycle 12, the first instruction to start after cycle 10
+    //  Both ran in the order they were scheduled
+    assert_eq!(scheduler.pending(), 0);
+}
+
+#[test]
+fn test_frame_actions() {
+    let mut machine: Machine = fixtures::machine(fixtures::NOP_LOOP);
+    let mut scheduler: Scheduler = Scheduler::new();
+    let frames: Rc<RefCell<Vec<u64>>> = Rc::new(RefCell::new(vec![]));
+
+    let seen: Rc<RefCell<Vec<u64>>> = frames.clone();
+    let id: u64 = scheduler.every(When::Frame(2), 5, Box::new(move |machine| {
+        seen.borrow_mut().push(machine.cycles() / CYCLES_PER_FRAME);
+        machine.hardware.set_inputs([0x04, 0x00]);
+    }));
+    for _ in 0..13 {
+        scheduler.run_frame(&mut machine).unwrap();
+    }
+    assert_eq!(scheduler.frame(), 13);
+    assert_eq!(*frames.borrow(), vec![2, 7, 12]);
+    // Each at the very start of its frame
+    assert_eq!(machine.hardware.inputs()[0], 0x04);
+
+    assert!(scheduler.cancel(id));
+    scheduler.run_frame(&mut machine).unwrap();
+    assert_eq!(frames.borrow().len(), 3);
+}
@@ -0,0 +1,211 @@
+use raylib::prelude::*;
+
+use crate::i18n::{Language, Text};
+use crate::machine::Machine;
+use crate::rewind::Rewind;
+use crate::window::Layout;
+
+mod tests;
+
+pub const DEFAULT_FRAMES: usize = 180;
+// Three seconds at 60 frames a second
+const BAR_COLOUR: Color = Color::GRAY;
+const KNOB_COLOUR: Color = Color::WHITE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    // A point in the recent past
+    pub frame: u64,
+    // Frames counted since recording started
+    pub instruction: u64,
+    // Instructions run into that frame
+}
+
+#[derive(Clone)]
+pub struct Scrubber {
+    // Time travel over the last few seconds for looking at how the machine got where it is
+    //  A snapshot is kept from the end of every frame and anywhere inside a frame is reached by running
+    //  its instructions again from the snapshot before it, inputs only change between frames so that
+    //  run is the same as the first one
+    //  Looking is read only, the machine only goes back when resume is asked for
+    history: Rewind,
+    // Keyed by frame number, each is how the machine was when that frame started
+    frames: u64,
+    preview: Option<(Position, Machine)>,
+    // The machine at the chosen position, None while the scrubber is closed
+}
+impl Scrubber {
+    pub fn new(frames: usize) -> Self {
+        Self {
+            history: Rewind::new(frames, 1),
+            frames: 0,
+            preview: None,
+        }
+    }
+
+    pub fn record(&mut self, machine: &Machine) {
+        // Should be called after every frame the machine runs
+        self.frames += 1;
+        self.history.record(self.frames, machine);
+    }
+
+    pub fn range(&self) -> Option<(u64, u64)> {
+        // Oldest and newest frames that can be looked at
+        self.history.oldest().map(|oldest| (oldest, self.frames))
+    }
+
+    pub fn open(&mut self, machine: &Machine) {
+        // Starts looking from the start of the frame the machine is in, which is where it is unless it stopped part way
+        if self.history.oldest().is_none() {
+            self.history.record(self.frames, machine);
+        }
+        self.seek(Position { frame: self.frames, instruction: 0 });
+    }
+
+    pub fn close(&mut self) {
+        self.preview = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.preview.is_some()
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.preview.as_ref().map(|(position, _)| *position)
+    }
+
+    pub fn preview(&self) -> Option<&Machine> {
+        // The machine to show instead of the real one while the scrubber is open
+        self.preview.as_ref().map(|(_, machine)| machine)
+    }
+
+    pub fn seek(&mut self, position: Position) {
+        // Moves the preview, positions outside the history are moved to its nearest end
+        //  Running past the end of the frame or into a fault stops there instead
+        //  The newest frame hasn't run yet unless the machine stopped inside it, running into it
+        //  uses the inputs held now the same as carrying on from there would
+
+        let Some((oldest, newest)) = self.range() else {
+            return;
+        };
+        let frame: u64 = position.frame.clamp(oldest, newest);
+        let Some((_, snapshot)) = self.history.nearest(frame) else {
+            return;
+        };
+        let mut machine: Machine = snapshot.clone();
+        let mut instruction: u64 = 0;
+        while instruction < position.instruction {
+            match machine.step_frame() {
+                Ok(false) => instruction += 1,
+                Ok(true) => {
+                    instruction += 1;
+                    break;
+                },
+                Err(_) => break,
+            }
+        }
+        // After the last instruction of a frame it is the same as the start of the next
+        self.preview = Some((Position { frame, instruction }, machine));
+    }
+
+    pub fn seek_fraction(&mut self, fraction: f32) {
+        // The start of the frame that far along the history, 0 the oldest and 1 the newest
+        if let Some((oldest, newest)) = self.range() {
+            let frame: u64 = oldest + ((newest - oldest) as f32 * fraction.clamp(0.0, 1.0)).round() as u64;
+            self.seek(Position { frame, instruction: 0 });
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        // The next instruction, on into the next frame from the end of one
+        let Some(position) = self.position() else {
+            return;
+        };
+        self.seek(Position { instruction: position.instruction + 1, ..position });
+        if self.position() == Some(position) && self.range().is_some_and(|(_, newest)| position.frame < newest) {
+            self.seek(Position { frame: position.frame + 1, instruction: 1 });
+        }
+        // The end of a frame and the start of the next are the same place so the start is stepped over
+    }
+
+    pub fn step_back(&mut self) {
+        // The instruction before, back into the last frame from the start of one
+        let Some(position) = self.position() else {
+            return;
+        };
+        if position.instruction > 0 {
+            self.seek(Position { instruction: position.instruction - 1, ..position });
+        } else if self.range().is_some_and(|(oldest, _)| position.frame > oldest) {
+            self.seek(Position { frame: position.frame - 1, instruction: u64::MAX });
+            self.step_back();
+        }
+    }
+
+    pub fn resume(&mut self, machine: &mut Machine) -> Option<Position> {
+        // Carries on from the preview instead of where the machine got to, closing the scrubber
+        //  The frames after it are a future that didn't happen, they go as the new ones are recorded
+
+        let (position, preview) = self.preview.take()?;
+        machine.restore(&preview);
+        self.frames = position.frame;
+        Some(position)
+    }
+
+    pub fn lines(&self, language: Language) -> Vec<String> {
+        // e.g. "12 frames ago, instruction 345, PC 1a32"
+        let Some((position, machine)) = self.preview.as_ref() else {
+            return vec![];
+        };
+        vec![
+            String::from(language.text(Text::Rewinding)),
+            format!(
+                "{} {}, {} {}, PC {:04x}",
+                self.frames - position.frame,
+                language.text(Text::FramesAgo),
+                language.text(Text::Instruction),
+                position.instruction,
+                machine.cpu.pc.address,
+            ),
+        ]
+    }
+
+    pub fn drag(&mut self, raylib_handle: &RaylibHandle, layout: &Layout) {
+        // Follows the mouse while the left button is held over the bar
+        if !raylib_handle.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            return;
+        }
+        let mouse: Vector2 = raylib_handle.get_mouse_position();
+        let (x, y, width, height): (i32, i32, i32, i32) = bar(layout);
+        if mouse.y >= (y - height) as f32 && mouse.y < (y + height * 2) as f32 {
+            self.seek_fraction(fraction(mouse.x, x, width));
+        }
+        // The bar is thin so it can be grabbed a little above and below it
+    }
+
+    pub fn draw(&self, draw_handle: &mut impl RaylibDraw, layout: &Layout) {
+        // A bar along the bottom of the window with a knob at the frame being looked at
+        let (Some(position), Some((oldest, newest))) = (self.position(), self.range()) else {
+            return;
+        };
+        let (x, y, width, height): (i32, i32, i32, i32) = bar(layout);
+        let along: f32 = if newest > oldest { (position.frame - oldest) as f32 / (newest - oldest) as f32 } else { 1.0 };
+        draw_handle.draw_rectangle_lines(x, y, width, height, BAR_COLOUR);
+        draw_handle.draw_rectangle(x + (width as f32 * along) as i32 - height / 2, y - height / 2, height, height * 2, KNOB_COLOUR);
+    }
+}
+impl Default for Scrubber {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAMES)
+    }
+}
+
+fn bar(layout: &Layout) -> (i32, i32, i32, i32) {
+    // x, y, width and height of the bar, a text line up from the bottom of the window
+    let margin: i32 = layout.text_size;
+    (margin, layout.height - margin * 2, layout.width - margin * 2, (margin / 2).max(2))
+}
+
+pub fn fraction(mouse_x: f32, x: i32, width: i32) -> f32 {
+    // How far along a bar starting at x the mouse is, from 0 to 1
+    ((mouse_x - x as f32) / width.max(1) as f32).clamp(0.0, 1.0)
+}
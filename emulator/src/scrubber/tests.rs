@@ -0,0 +1,97 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::cpu::Registers;
+
+#[cfg(test)]
+const COUNTER: [u8; 5] = [
+    0xf3,             // 0x0000 DI
+    0x3c,             // 0x0001 INR A
+    0xc3, 0x01, 0x00, // 0x0002 JMP 0x0001
+];
+
+#[cfg(test)]
+fn registers_at(frames: u64, instructions: u64) -> Registers {
+    // The same run made straight through, to check the scrubber against
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&COUNTER).unwrap();
+    for _ in 0..frames {
+        machine.run_frame().unwrap();
+    }
+    for _ in 0..instructions {
+        machine.step_frame().unwrap();
+    }
+    machine.cpu.registers()
+}
+
+#[test]
+fn test_scrubber() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&COUNTER).unwrap();
+    let mut scrubber: Scrubber = Scrubber::new(4);
+    for _ in 0..6 {
+        machine.run_frame().unwrap();
+        scrubber.record(&machine);
+    }
+    assert_eq!(scrubber.range(), Some((3, 6)));
+    let live: Registers = machine.cpu.registers();
+
+    scrubber.open(&machine);
+    assert_eq!(scrubber.position(), Some(Position { frame: 6, instruction: 0 }));
+    assert_eq!(scrubber.preview().map(|preview| preview.cpu.registers()), Some(live));
+    assert_eq!(scrubber.lines(Language::English)[1], format!("0 frames ago, instruction 0, PC {:04x}", live.pc));
+
+    scrubber.seek(Position { frame: 4, instruction: 10 });
+    assert_eq!(scrubber.preview().map(|preview| preview.cpu.registers()), Some(registers_at(4, 10)));
+    scrubber.seek(Position { frame: 0, instruction: 0 });
+    assert_eq!(scrubber.position(), Some(Position { frame: 3, instruction: 0 }));
+    // Older than the history goes to the oldest frame
+    scrubber.seek_fraction(0.7);
+    assert_eq!(scrubber.position(), Some(Position { frame: 5, instruction: 0 }));
+
+    scrubber.seek(Position { frame: 4, instruction: 0 });
+    scrubber.step_back();
+    let Some(Position { frame: 3, instruction: end }) = scrubber.position() else {
+        panic!("stepped back to {:?}", scrubber.position());
+    };
+    scrubber.step_forward();
+    assert_eq!(scrubber.position(), Some(Position { frame: 3, instruction: end + 1 }));
+    assert_eq!(scrubber.preview().map(|preview| preview.cpu.registers()), Some(registers_at(4, 0)));
+    scrubber.step_forward();
+    assert_eq!(scrubber.position(), Some(Position { frame: 4, instruction: 1 }));
+    // The end of a frame is the start of the next, stepping goes straight over it
+    assert_eq!(machine.cpu.registers(), live);
+    // Looking never touches the machine
+
+    scrubber.seek(Position { frame: 4, instruction: 10 });
+    assert_eq!(scrubber.resume(&mut machine), Some(Position { frame: 4, instruction: 10 }));
+    assert!(!scrubber.is_open());
+    assert_eq!(machine.cpu.registers(), registers_at(4, 10));
+    machine.run_frame().unwrap();
+    scrubber.record(&machine);
+    assert_eq!(scrubber.range(), Some((3, 5)));
+    assert_eq!(machine.cpu.registers(), registers_at(5, 0));
+    // Carrying on finishes the frame it was in, the frames after it are gone
+
+    assert_eq!(fraction(0.0, 10, 100), 0.0);
+    assert_eq!(fraction(60.0, 10, 100), 0.5);
+    assert_eq!(fraction(500.0, 10, 100), 1.0);
+}
+
+#[test]
+fn test_scrubber_empty() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&COUNTER).unwrap();
+    let mut scrubber: Scrubber = Scrubber::default();
+    assert_eq!(scrubber.range(), None);
+    scrubber.step_forward();
+    assert_eq!(scrubber.resume(&mut machine), None);
+
+    scrubber.open(&machine);
+    assert_eq!(scrubber.range(), Some((0, 0)));
+    scrubber.step_forward();
+    assert_eq!(scrubber.position(), Some(Position { frame: 0, instruction: 1 }));
+    // Nothing has run yet so the only frame is the one about to
+    scrubber.close();
+    assert!(scrubber.lines(Language::English).is_empty());
+}
@@ -0,0 +1,140 @@
+#[cfg(feature = "frontend")]
+use raylib::core::audio::RaylibAudio;
+
+use crate::display::Geometry;
+use crate::error::ErrorKind;
+use crate::hardware::{self, Hardware};
+use crate::headless::{self, FNV_OFFSET};
+use crate::machine::Machine;
+use crate::palette::Palette;
+use crate::profile::Profile;
+use crate::testing;
+
+mod tests;
+
+const STRIPES: &[u8] = include_bytes!("../tests/golden/stripes.bin");
+// The golden test's rom, it draws stripes and counts frames in the top corner
+const STRIPES_FRAMES: u32 = 10;
+const STRIPES_HASH: u64 = 0x6ca6_7433_676d_d4ca;
+// FNV-1a of the screen after STRIPES_FRAMES, the same picture as tests/golden/stripes.png
+
+const OUT: u8 = 0xd3;
+const IN: u8 = 0xdb;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    // One part of the emulator checked against known answers
+    pub name: &'static str,
+    pub result: Result<String, String>,
+    // What was checked when it passed, what went wrong when it didn't
+}
+impl Check {
+    pub fn new(name: &'static str, result: Result<String, String>) -> Self {
+        Self { name, result }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    pub fn line(&self) -> String {
+        // e.g. "shift   passed  48 reads"
+        match &self.result {
+            Ok(detail) => format!("{:<8}passed  {}", self.name, detail),
+            Err(e) => format!("{:<8}FAILED  {}", self.name, e),
+        }
+    }
+}
+
+pub fn cpu() -> Check {
+    // cpudiag, the same conformance run as the tests with the built in copy
+    let result: Result<String, String> = match testing::run_cpudiag(testing::CPUDIAG) {
+        Ok(output) => Ok(format!("cpudiag: {}", output.trim())),
+        Err(output) => Err(format!("cpudiag: {}", output.trim().replace('\n', " "))),
+    };
+    Check::new("cpu", result)
+}
+
+fn shift_read(hardware: &mut Hardware, low: u8, high: u8, offset: u8) -> Result<Option<u8>, ErrorKind> {
+    // Writes two bytes and an offset then reads the result, through the ports the same way the game does
+    hardware::handle_io(OUT, hardware, 4, low)?;
+    hardware::handle_io(OUT, hardware, 4, high)?;
+    hardware::handle_io(OUT, hardware, 2, offset)?;
+    hardware::handle_io(IN, hardware, 3, 0)
+}
+
+pub fn shift_register() -> Check {
+    // Every offset against a few pairs of bytes
+    //  The expected byte is worked out from the board's wiring rather than the shift config
+
+    let mut hardware: Hardware = Hardware::init();
+    let mut reads: u32 = 0;
+    for (low, high) in [(0x00, 0xff), (0xff, 0x00), (0xa5, 0x5a), (0x01, 0x80), (0x3c, 0xc3), (0x12, 0x34)] {
+        for offset in 0..8u8 {
+            let expected: u8 = ((((high as u16) << 8 | low as u16) << offset) >> 8) as u8;
+            match shift_read(&mut hardware, low, high, offset) {
+                Ok(Some(byte)) if byte == expected => reads += 1,
+                Ok(Some(byte)) => return Check::new("shift", Err(format!("{:02x}{:02x} at offset {} read {:02x}, expected {:02x}", high, low, offset, byte, expected))),
+                Ok(None) => return Check::new("shift", Err(String::from("Reading port 3 gave nothing back"))),
+                Err(e) => return Check::new("shift", Err(e.to_string())),
+            }
+        }
+    }
+    Check::new("shift", Ok(format!("{} reads", reads)))
+}
+
+pub fn frame_hash() -> Result<u64, String> {
+    // Runs the stripes rom and hashes the screen as it would be drawn, vram decode and palette included
+    let profile: Profile = Profile::space_invaders();
+    let geometry: Geometry = profile.display.geometry();
+    let palette: Palette = Palette::for_screen(&profile.overlay, geometry)?;
+    let mut machine: Machine = Machine::with_profile(profile)?;
+    machine.load_rom(STRIPES).map_err(|e| e.to_string())?;
+    for _ in 0..STRIPES_FRAMES {
+        machine.run_frame().map_err(|e| e.to_string())?;
+    }
+    Ok(headless::fnv1a(FNV_OFFSET, &headless::framebuffer(&machine.cpu, geometry, &palette)))
+}
+
+pub fn vram() -> Check {
+    let result: Result<String, String> = match frame_hash() {
+        Ok(STRIPES_HASH) => Ok(format!("stripes frame {}: {:016x}", STRIPES_FRAMES, STRIPES_HASH)),
+        Ok(hash) => Err(format!("stripes frame {} hashed to {:016x}, expected {:016x}", STRIPES_FRAMES, hash, STRIPES_HASH)),
+        Err(e) => Err(e),
+    };
+    Check::new("vram", result)
+}
+
+#[cfg(feature = "frontend")]
+pub fn audio() -> Check {
+    // Opens the default audio device and closes it again, the other checks run without one
+    let result: Result<String, String> = match RaylibAudio::init_audio_device() {
+        Ok(_) => Ok(String::from("opened the default device")),
+        Err(e) => Err(format!("no audio device: {}", e)),
+    };
+    Check::new("audio", result)
+}
+#[cfg(not(feature = "frontend"))]
+pub fn audio() -> Check {
+    // Without raylib there is no device to open
+    Check::new("audio", Err(String::from("built without the frontend feature, no audio device to open")))
+}
+
+pub fn run(audio: bool) -> Vec<Check> {
+    let mut checks: Vec<Check> = vec![cpu(), shift_register(), vram()];
+    if audio {
+        checks.push(self::audio());
+    }
+    checks
+}
+
+pub fn report(checks: &[Check]) -> Vec<String> {
+    // A line a check then the totals
+    let failed: usize = checks.iter().filter(|check| !check.passed()).count();
+    let mut lines: Vec<String> = checks.iter().map(Check::line).collect();
+    lines.push(match failed {
+        0 => format!("All {} checks passed", checks.len()),
+        failed => format!("{} of {} checks failed", failed, checks.len()),
+    });
+    lines
+}
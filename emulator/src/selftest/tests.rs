@@ -0,0 +1,24 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_checks() {
+    for check in [cpu(), shift_register(), vram()] {
+        assert!(check.passed(), "{}", check.line());
+    }
+    assert_eq!(shift_register().line(), "shift   passed  48 reads");
+}
+
+#[test]
+fn test_stripes_hash() {
+    let (_, _, pixels) = crate::png::decode_rgb(include_bytes!("../../tests/golden/stripes.png")).unwrap();
+    assert_eq!(headless::fnv1a(FNV_OFFSET, &pixels), STRIPES_HASH);
+    // The hash is of the golden test's picture, when that changes so does this
+}
+
+#[test]
+fn test_report() {
+    let checks: Vec<Check> = vec![Check::new("cpu", Ok(String::from("fine"))), Check::new("audio", Err(String::from("no audio device")))];
+    assert_eq!(report(&checks), ["cpu     passed  fine", "audio   FAILED  no audio device", "1 of 2 checks failed"]);
+    assert_eq!(report(&[checks[0].clone(), checks[0].clone()]).last().unwrap(), "All 2 checks passed");
+}
@@ -0,0 +1,172 @@
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::headless;
+use crate::machine::Machine;
+
+mod tests;
+
+pub const REPORT: &str = "soak.txt";
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+const WARM_UP_SAMPLES: usize = 2;
+// Memory settles for the first few minutes as buffers fill up, it isn't counted towards a leak
+const LEAK_BYTES_PER_HOUR: f64 = 8.0 * 1024.0 * 1024.0;
+// Growth past this is reported as a leak
+const DRIFT_FRAMES_PER_HOUR: f64 = 60.0;
+// A second an hour behind or ahead of the clock
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub elapsed: Duration,
+    pub frames: u64,
+    pub ram_hash: u64,
+    pub resident: Option<u64>,
+    // Bytes of host memory in use, only known on Linux
+    pub underruns: u64,
+    pub late_frames: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Soak {
+    // Watches an unattended machine for hours, samples every SAMPLE_INTERVAL
+    //  The game sits in attract mode so anything that creeps up over time is the emulator's fault
+    duration: Duration,
+    frames_per_second: f64,
+    started: Instant,
+    frames: u64,
+    late_frames: u64,
+    samples: Vec<Sample>,
+}
+impl Soak {
+    pub fn new(duration: Duration, frames_per_second: f64, started: Instant) -> Self {
+        Self {
+            duration,
+            frames_per_second,
+            started,
+            frames: 0,
+            late_frames: 0,
+            samples: vec![],
+        }
+    }
+
+    pub fn parse_hours(text: &str) -> Result<Duration, String> {
+        match text.parse::<f64>() {
+            Ok(hours) if hours > 0.0 && hours.is_finite() => Ok(Duration::from_secs_f64(hours * 3600.0)),
+            _ => Err(format!("{} is not a number of hours", text)),
+        }
+    }
+
+    pub fn frame(&mut self, machine: &Machine, late: bool, underruns: u64, now: Instant) -> bool {
+        // Called after every frame, returns true once the soak has run for long enough
+
+        self.frames += 1;
+        self.late_frames += late as u64;
+        let elapsed: Duration = now.saturating_duration_since(self.started);
+        let due: Duration = SAMPLE_INTERVAL * (self.samples.len() as u32 + 1);
+        if elapsed >= due || elapsed >= self.duration {
+            self.samples.push(Sample {
+                elapsed,
+                frames: self.frames,
+                ram_hash: headless::ram_hash(machine),
+                resident: resident_bytes(),
+                underruns,
+                late_frames: self.late_frames,
+            });
+        }
+        elapsed >= self.duration
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn drift(&self, sample: &Sample) -> f64 {
+        // Frames ahead of the wall clock, negative when the machine has fallen behind
+        sample.frames as f64 - sample.elapsed.as_secs_f64() * self.frames_per_second
+    }
+
+    pub fn leak(&self) -> Option<f64> {
+        // How fast host memory grows in bytes an hour, leaving out the warm up
+        let points: Vec<(f64, f64)> = self.samples.iter()
+            .skip(WARM_UP_SAMPLES)
+            .filter_map(|sample| sample.resident.map(|resident| (hours(sample.elapsed), resident as f64)))
+            .collect();
+        slope(&points)
+    }
+
+    pub fn drift_rate(&self) -> Option<f64> {
+        // Frames gained or lost an hour
+        let points: Vec<(f64, f64)> = self.samples.iter().map(|sample| (hours(sample.elapsed), self.drift(sample))).collect();
+        slope(&points)
+    }
+
+    pub fn report(&self) -> String {
+        // A table of every sample followed by what looks wrong, if anything
+
+        let mut lines: Vec<String> = vec![
+            format!("Soak test, {:.2} hours at {} frames per second", hours(self.duration), self.frames_per_second),
+            String::from("   minutes      frames   drift  late  underruns  resident kB  ram hash"),
+        ];
+        for sample in &self.samples {
+            lines.push(format!("{:>10.1}  {:>10}  {:>6.1}  {:>4}  {:>9}  {:>11}  {:016x}",
+                sample.elapsed.as_secs_f64() / 60.0,
+                sample.frames,
+                self.drift(sample),
+                sample.late_frames,
+                sample.underruns,
+                sample.resident.map_or(String::from("-"), |resident| (resident / 1024).to_string()),
+                sample.ram_hash,
+            ));
+        }
+
+        let mut problems: Vec<String> = vec![];
+        match self.leak() {
+            Some(growth) if growth > LEAK_BYTES_PER_HOUR => problems.push(format!("Memory grew by {:.1} MB an hour", growth / (1024.0 * 1024.0))),
+            Some(_) => {},
+            None => lines.push(String::from("Not enough samples of host memory to look for a leak")),
+        }
+        if let Some(rate) = self.drift_rate().filter(|rate| rate.abs() > DRIFT_FRAMES_PER_HOUR) {
+            problems.push(format!("Frame timing drifted by {:.0} frames an hour", rate));
+        }
+        if let Some(last) = self.samples.last().filter(|last| last.underruns > 0) {
+            problems.push(format!("{} audio underruns", last.underruns));
+        }
+        match problems.is_empty() {
+            true => lines.push(String::from("No leaks or drift found")),
+            false => lines.extend(problems),
+        }
+        lines.join("\n") + "\n"
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.report()).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+pub fn resident_bytes() -> Option<u64> {
+    // Resident set size of this process from /proc, None anywhere that doesn't have it
+    let status: String = fs::read_to_string("/proc/self/status").ok()?;
+    let line: &str = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn hours(duration: Duration) -> f64 {
+    duration.as_secs_f64() / 3600.0
+}
+
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    // Least squares slope, None without at least two points spread over time
+
+    if points.len() < 2 {
+        return None;
+    }
+    let count: f64 = points.len() as f64;
+    let mean_x: f64 = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y: f64 = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let spread: f64 = points.iter().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum();
+    if spread == 0.0 {
+        return None;
+    }
+    Some(points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>() / spread)
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_soak_samples() {
+    let machine: Machine = Machine::init();
+    let started: Instant = Instant::now();
+    let mut soak: Soak = Soak::new(Duration::from_secs(150), 60.0, started);
+
+    let frame: Duration = Duration::from_nanos(16_666_667);
+    let mut finished: Option<u32> = None;
+    for i in 1..=150 * 60 {
+        if soak.frame(&machine, i % 1000 == 0, 0, started + frame * i) {
+            finished = Some(i);
+            break;
+        }
+    }
+    assert_eq!(finished, Some(150 * 60));
+    assert_eq!(soak.samples().len(), 3);
+    // Every minute and once more at the end
+    assert_eq!(soak.samples()[0].frames, 3600);
+    assert_eq!(soak.samples()[2].late_frames, 9);
+    assert!(soak.samples().iter().all(|sample| soak.drift(sample).abs() < 0.01));
+    assert!(soak.report().contains("No leaks or drift found"));
+}
+
+#[test]
+fn test_soak_problems() {
+    let mut soak: Soak = Soak::new(Duration::from_secs(3600 * 4), 60.0, Instant::now());
+    for hour in 0..=4u64 {
+        soak.samples.push(Sample {
+            elapsed: Duration::from_secs(3600 * hour),
+            frames: 3600 * 60 * hour - 120 * hour,
+            ram_hash: 0,
+            resident: Some((40 + 20 * hour) * 1024 * 1024),
+            underruns: hour,
+            late_frames: 0,
+        });
+    }
+    // Leaks 20 MB and loses 2 seconds an hour, the first samples are warm up
+
+    assert!((soak.leak().unwrap() - 20.0 * 1024.0 * 1024.0).abs() < 1.0);
+    assert!((soak.drift_rate().unwrap() + 120.0).abs() < 0.01);
+    let report: String = soak.report();
+    assert!(report.contains("Memory grew by 20.0 MB an hour"));
+    assert!(report.contains("Frame timing drifted by -120 frames an hour"));
+    assert!(report.contains("4 audio underruns"));
+    assert!(report.contains("      60.0      215880  -120.0"));
+}
@@ -0,0 +1,45 @@
+use crate::cpu::{Flags, Registers};
+use crate::i18n::{Language, Text};
+use crate::machine::Machine;
+
+mod tests;
+
+const NAME_WIDTH: usize = 3;
+const CELL_WIDTH: usize = 9;
+// Every cell is padded to the same width so the columns line up from one frame to the next
+
+fn cell(name: &str, value: String) -> String {
+    format!("{:<name$}{:<cell$}", name, value, name = NAME_WIDTH, cell = CELL_WIDTH - NAME_WIDTH)
+}
+
+pub fn lines(registers: &Registers, halted: bool, cycles: u64, frames: u64, language: Language) -> Vec<String> {
+    // Everything in a register snapshot laid out as a grid, pairs next to the bytes they're made of
+    //  e.g. "BC 1234   B  12     C  34"
+    //  Built from Registers alone so a register added there only has to be added here to show up
+
+    let pair = |name: &str, high: (&str, u8), low: (&str, u8)| -> String {
+        let value: u16 = (high.1 as u16) << 8 | low.1 as u16;
+        [cell(name, format!("{:04x}", value)), cell(high.0, format!("{:02x}", high.1)), cell(low.0, format!("{:02x}", low.1))].concat()
+    };
+
+    vec![
+        [cell("A", format!("{:02x}", registers.a)), cell("F", format!("{:02x}", registers.flags)), Flags::from_bits(registers.flags).as_str()].concat(),
+        pair("BC", ("B", registers.b), ("C", registers.c)),
+        pair("DE", ("D", registers.d), ("E", registers.e)),
+        pair("HL", ("H", registers.h), ("L", registers.l)),
+        [cell("SP", format!("{:04x}", registers.sp)), cell("PC", format!("{:04x}", registers.pc))].concat(),
+        format!("{:<cell$}{}",
+            if registers.interrupt_enabled { "EI" } else { "DI" },
+            language.text(if halted { Text::Halted } else { Text::Running }),
+            cell = CELL_WIDTH),
+        format!("{} {}  {} {}", language.text(Text::Cycles), cycles, language.text(Text::Frame), frames),
+    ]
+    .into_iter()
+    .map(|line| line.trim_end().to_string())
+    .collect()
+}
+
+pub fn machine_lines(machine: &Machine, frames: u64, language: Language) -> Vec<String> {
+    // The panel for the machine as it is now
+    lines(&machine.cpu.registers(), machine.cpu.halted(), machine.cycles(), frames, language)
+}
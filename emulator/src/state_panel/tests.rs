@@ -0,0 +1,33 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_state_panel() {
+    let registers: Registers = Registers {
+        a: 0x3e,
+        b: 0x12,
+        c: 0x34,
+        d: 0x56,
+        e: 0x78,
+        h: 0x9a,
+        l: 0xbc,
+        flags: 0b1100_0011,
+        sp: 0x23fe,
+        pc: 0x0a1b,
+        interrupt_enabled: true,
+    };
+    let panel: Vec<String> = lines(&registers, false, 1234, 5, Language::English);
+    assert_eq!(panel, vec![
+        "A  3e    F  c3    S Z - . - . - C",
+        "BC 1234  B  12    C  34",
+        "DE 5678  D  56    E  78",
+        "HL 9abc  H  9a    L  bc",
+        "SP 23fe  PC 0a1b",
+        "EI       Running",
+        "Cycles 1234  Frame 5",
+    ]);
+
+    let halted: Vec<String> = lines(&Registers { interrupt_enabled: false, ..registers }, true, 0, 0, Language::Spanish);
+    assert_eq!(halted[5], "DI       Detenida");
+    assert!(halted.iter().all(|line| line.len() == line.trim_end().len()));
+}
@@ -0,0 +1,84 @@
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hardware::Hardware;
+#[cfg(feature = "serde")]
+use crate::storage::{self, Kind, Storage};
+#[cfg(feature = "serde")]
+use crate::versioned;
+
+mod tests;
+
+pub const VERSION: u32 = 1;
+pub const EXTENSION: &str = "stats";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stats {
+    // What the cabinet keeps between sessions, saved next to the rom as JSON
+    pub version: u32,
+    pub coins: u64,
+    // The coin counter's reading
+}
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            version: VERSION,
+            coins: 0,
+        }
+    }
+
+    pub fn apply(&self, hardware: &mut Hardware) {
+        hardware.set_coins(self.coins);
+    }
+
+    pub fn update(&mut self, hardware: &Hardware) {
+        // Takes the readings back off the machine before saving
+        self.coins = hardware.coins();
+    }
+}
+#[cfg(feature = "serde")]
+impl Stats {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        versioned::from_json(text, "stats", VERSION, &[])
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map(|json| json + "\n").map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // A rom that hasn't been played yet has no stats file and starts from nothing
+
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let text: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn read(storage: &dyn Storage, name: &str) -> Result<Self, String> {
+        match storage::read_text(storage, Kind::Stats, name)? {
+            Some(text) => Self::from_json(&text).map_err(|e| format!("{}: {}", storage.describe(Kind::Stats, name), e)),
+            None => Ok(Self::new()),
+        }
+    }
+
+    pub fn write(&self, storage: &mut dyn Storage, name: &str) -> Result<(), String> {
+        storage.write(Kind::Stats, name, self.to_json()?.as_bytes())
+    }
+}
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
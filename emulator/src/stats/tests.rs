@@ -0,0 +1,56 @@
+#[cfg(test)]
+use super::*;
+#[cfg(all(test, feature = "serde"))]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::hardware::input::ServiceSwitch;
+#[cfg(all(test, feature = "serde"))]
+use crate::hardware::input::COIN_BIT;
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_coin_counter() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0xc3, 0x00, 0x00]).unwrap(); // 0x0000 JMP 0x0000
+    let path: std::path::PathBuf = std::env::temp_dir().join(format!("emulator-stats-test-{}.{}", std::process::id(), EXTENSION));
+
+    let mut stats: Stats = Stats::load(&path).unwrap();
+    assert_eq!(stats, Stats::new());
+    stats.coins = 41;
+    stats.apply(&mut machine.hardware);
+
+    for held in [true, true, false, true, false, false] {
+        machine.hardware.set_inputs([(held as u8) << COIN_BIT, 0]);
+        machine.run_frame().unwrap();
+    }
+    assert_eq!(machine.hardware.coins(), 43);
+    // Holding the coin input down only counts once
+    machine.reset().unwrap();
+    machine.hardware.reset();
+    assert_eq!(machine.hardware.coins(), 43);
+
+    stats.update(&machine.hardware);
+    stats.save(&path).unwrap();
+    assert_eq!(Stats::load(&path).unwrap().coins, 43);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_service_switch() {
+    let mut hardware: Hardware = Hardware::init();
+    assert!(!hardware.set_service_mode(true));
+    // Nothing to flip on a board without one
+
+    hardware.set_service_switch(Some(ServiceSwitch { input: 2, bit: 2, active_low: true }));
+    assert_eq!(hardware.inputs()[1] & 0b100, 0b100);
+    assert!(hardware.set_service_mode(true));
+    assert_eq!(hardware.inputs()[1] & 0b100, 0);
+    hardware.reset();
+    assert!(hardware.service_mode());
+    assert_eq!(hardware.inputs()[1] & 0b100, 0);
+    // The switch stays where it was across a reset
+
+    hardware.set_service_switch(Some(ServiceSwitch { input: 1, bit: 7, active_low: false }));
+    hardware.set_service_mode(true);
+    assert_eq!(hardware.inputs()[0] & 0x80, 0x80);
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "serde")]
+use directories::ProjectDirs;
+
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    // What is being kept, each kind gets its own folder
+    SaveState,
+    Stats,
+    Replay,
+    Config,
+    // Profiles, named after the rom they're for
+    Rom,
+    // Roms the launcher lists, only ever read
+}
+impl Kind {
+    pub fn folder(&self) -> &'static str {
+        match self {
+            Kind::SaveState => "states",
+            Kind::Stats => "stats",
+            Kind::Replay => "replays",
+            Kind::Config => "profiles",
+            Kind::Rom => "roms",
+        }
+    }
+}
+
+pub trait Storage {
+    // Everything the emulator keeps between runs goes through here instead of straight to a path
+    //  so a browser build can keep them in localStorage and tests in a temp dir
+    //  Names are file names like "invaders.state", never paths
+
+    fn read(&self, kind: Kind, name: &str) -> Result<Option<Vec<u8>>, String>;
+    // None when nothing has been written under the name yet
+    fn write(&mut self, kind: Kind, name: &str, bytes: &[u8]) -> Result<(), String>;
+    fn list(&self, kind: Kind) -> Result<Vec<String>, String>;
+    // Names of everything of the kind, sorted
+    fn describe(&self, kind: Kind, name: &str) -> String;
+    // Where the name is kept, for messages
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    match name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        true => Err(format!("{:?} isn't a file name", name)),
+        false => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryStorage {
+    // Files in a folder for each kind
+    data: PathBuf,
+    config: PathBuf,
+    // Profiles can live somewhere else, the platform keeps config apart from data
+}
+impl DirectoryStorage {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            data: root.to_path_buf(),
+            config: root.to_path_buf(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn platform() -> Option<Self> {
+        // The user's data and config folders, e.g. ~/.local/share/8080_emulator on Linux
+        //  None when the platform doesn't have a home folder to put them in
+        //  Built with the serde feature, embedders without it give their own root to new
+
+        let dirs: ProjectDirs = ProjectDirs::from("", "GecDeck", "8080_Emulator")?;
+        Some(Self {
+            data: dirs.data_dir().to_path_buf(),
+            config: dirs.config_dir().to_path_buf(),
+        })
+    }
+
+    pub fn folder(&self, kind: Kind) -> PathBuf {
+        match kind {
+            Kind::Config => self.config.join(kind.folder()),
+            _ => self.data.join(kind.folder()),
+        }
+    }
+
+    pub fn path(&self, kind: Kind, name: &str) -> PathBuf {
+        self.folder(kind).join(name)
+    }
+}
+impl Storage for DirectoryStorage {
+    fn read(&self, kind: Kind, name: &str) -> Result<Option<Vec<u8>>, String> {
+        check_name(name)?;
+        let path: PathBuf = self.path(kind, name);
+        match fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    fn write(&mut self, kind: Kind, name: &str, bytes: &[u8]) -> Result<(), String> {
+        check_name(name)?;
+        let folder: PathBuf = self.folder(kind);
+        fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+        let path: PathBuf = folder.join(name);
+        fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn list(&self, kind: Kind) -> Result<Vec<String>, String> {
+        let folder: PathBuf = self.folder(kind);
+        let entries: fs::ReadDir = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("{}: {}", folder.display(), e)),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn describe(&self, kind: Kind, name: &str) -> String {
+        self.path(kind, name).display().to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryStorage {
+    // Kept in memory only, for tests and frontends with nowhere to write files
+    files: HashMap<(Kind, String), Vec<u8>>,
+}
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self { files: HashMap::new() }
+    }
+}
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Storage for MemoryStorage {
+    fn read(&self, kind: Kind, name: &str) -> Result<Option<Vec<u8>>, String> {
+        check_name(name)?;
+        Ok(self.files.get(&(kind, name.to_string())).cloned())
+    }
+
+    fn write(&mut self, kind: Kind, name: &str, bytes: &[u8]) -> Result<(), String> {
+        check_name(name)?;
+        self.files.insert((kind, name.to_string()), bytes.to_vec());
+        Ok(())
+    }
+
+    fn list(&self, kind: Kind) -> Result<Vec<String>, String> {
+        let mut names: Vec<String> = self.files.keys()
+            .filter(|(file_kind, _)| *file_kind == kind)
+            .map(|(_, name)| name.clone())
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn describe(&self, kind: Kind, name: &str) -> String {
+        format!("{}/{}", kind.folder(), name)
+    }
+}
+
+pub fn read_text(storage: &dyn Storage, kind: Kind, name: &str) -> Result<Option<String>, String> {
+    // Reads something saved as text, saves are all JSON or TOML
+    storage.read(kind, name)?
+        .map(|bytes| String::from_utf8(bytes).map_err(|_| format!("{} isn't text", storage.describe(kind, name))))
+        .transpose()
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+use super::*;
+#[cfg(all(test, feature = "serde"))]
+use crate::machine::Machine;
+#[cfg(all(test, feature = "serde"))]
+use crate::profile::Profile;
+#[cfg(all(test, feature = "serde"))]
+use crate::replay::Replay;
+#[cfg(all(test, feature = "serde"))]
+use crate::savestate::SaveState;
+#[cfg(all(test, feature = "serde"))]
+use crate::stats::Stats;
+
+#[test]
+fn test_directory_storage() {
+    let root: PathBuf = std::env::temp_dir().join(format!("emulator-storage-test-{}", std::process::id()));
+    let mut storage: DirectoryStorage = DirectoryStorage::new(&root);
+
+    assert_eq!(storage.read(Kind::SaveState, "invaders.state"), Ok(None));
+    assert_eq!(storage.list(Kind::SaveState), Ok(vec![]));
+    // Nothing is created until the first write
+
+    storage.write(Kind::SaveState, "invaders.state", b"{}").unwrap();
+    storage.write(Kind::SaveState, "a.state", b"[]").unwrap();
+    storage.write(Kind::Stats, "invaders.stats", b"1").unwrap();
+    assert_eq!(storage.read(Kind::SaveState, "invaders.state"), Ok(Some(b"{}".to_vec())));
+    assert_eq!(storage.list(Kind::SaveState), Ok(vec![String::from("a.state"), String::from("invaders.state")]));
+    assert_eq!(storage.path(Kind::Stats, "invaders.stats"), root.join("stats").join("invaders.stats"));
+    assert!(root.join("states").join("a.state").is_file());
+
+    for name in ["", "..", "../escape.state", "nested/a.state"] {
+        assert!(storage.write(Kind::SaveState, name, b"").is_err());
+        assert!(storage.read(Kind::SaveState, name).is_err());
+    }
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_memory_storage() {
+    let mut storage: MemoryStorage = MemoryStorage::new();
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&[0x3c, 0xc3, 0x00, 0x00]).unwrap(); // 0x0000 INR A, 0x0001 JMP 0x0000
+    machine.run_frame().unwrap();
+
+    assert!(SaveState::read(&storage, "invaders.state").is_err());
+    machine.save_state().write(&mut storage, "invaders.state").unwrap();
+    assert_eq!(SaveState::read(&storage, "invaders.state").unwrap(), machine.save_state());
+
+    assert_eq!(Stats::read(&storage, "invaders.stats").unwrap(), Stats::new());
+    // Stats start from nothing instead of failing
+    let mut stats: Stats = Stats::new();
+    stats.coins = 7;
+    stats.write(&mut storage, "invaders.stats").unwrap();
+    assert_eq!(Stats::read(&storage, "invaders.stats").unwrap().coins, 7);
+
+    let replay: Replay = Replay::new(&[0x00]);
+    replay.write(&mut storage, "run.replay").unwrap();
+    assert_eq!(Replay::read(&storage, "run.replay").unwrap(), replay);
+
+    assert!(Profile::read(&storage, "invaders.toml").unwrap().is_none());
+    storage.write(Kind::Config, "invaders.toml", include_bytes!("../../profiles/space_invaders.toml")).unwrap();
+    assert_eq!(Profile::read(&storage, "invaders.toml").unwrap().map(|profile| profile.name), Some(String::from("Space Invaders")));
+    storage.write(Kind::Config, "invaders.ini", b"").unwrap();
+    assert_eq!(Profile::read(&storage, "invaders.ini").unwrap_err(), "profiles/invaders.ini: profiles must be .toml or .json");
+
+    storage.write(Kind::Stats, "broken.stats", &[0xff]).unwrap();
+    assert_eq!(Stats::read(&storage, "broken.stats").unwrap_err(), "stats/broken.stats isn't text");
+    assert_eq!(storage.list(Kind::Stats), Ok(vec![String::from("broken.stats"), String::from("invaders.stats")]));
+}
@@ -0,0 +1,132 @@
+use crate::cpu::Cpu;
+use crate::cpu::dispatcher::handle_op_code;
+
+mod tests;
+
+pub const LOAD_ADDRESS: u16 = 0x0100;
+// CP/M programs are loaded and started at 0x0100
+pub const BDOS_ADDRESS: u16 = 0xfe00;
+// CP/M keeps the address of BDOS at 0x0006 and programs use it as the top of their stack
+const WARM_BOOT_PORT: u8 = 0;
+const BDOS_PORT: u8 = 1;
+// Both CP/M entry points are patched to OUT to these ports so the shim can catch them
+
+pub const CPUDIAG: &[u8] = include_bytes!("../cpudiag");
+pub const CPUDIAG_LIMIT: u64 = 1_000_000;
+// cpudiag finishes in well under this many instructions, anything longer is stuck in a loop
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Patch {
+    // Bytes written over a program after it is loaded
+    pub address: u16,
+    pub bytes: &'static [u8],
+}
+
+pub const CPUDIAG_PATCHES: [Patch; 1] = [
+    Patch { address: 0x0170, bytes: &[0x07] },
+    // Moves the stack to 0x07ad, at 0x06ad it grows down into cpudiag's own code
+];
+
+pub fn load(cpu: &mut Cpu, program: &[u8]) {
+    // Puts a CP/M program at LOAD_ADDRESS with just enough of CP/M around it to run
+    //  0x0000 warm boot: OUT 0
+    //  0x0005 BDOS: JMP to OUT 1, RET
+
+    for (offset, byte) in program.iter().enumerate() {
+        cpu.memory.poke(LOAD_ADDRESS.wrapping_add(offset as u16), *byte);
+    }
+
+    for (address, byte) in [(0x0000, 0xd3), (0x0001, WARM_BOOT_PORT)] {
+        cpu.memory.poke(address, byte);
+    }
+    for (address, byte) in [(0x0005, 0xc3), (0x0006, BDOS_ADDRESS as u8), (0x0007, (BDOS_ADDRESS >> 8) as u8)] {
+        cpu.memory.poke(address, byte);
+    }
+    for (offset, byte) in [0xd3, BDOS_PORT, 0xc9].iter().enumerate() {
+        cpu.memory.poke(BDOS_ADDRESS + offset as u16, *byte);
+    }
+
+    cpu.pc.address = LOAD_ADDRESS;
+}
+
+pub fn apply(cpu: &mut Cpu, patches: &[Patch]) {
+    for patch in patches {
+        for (offset, byte) in patch.bytes.iter().enumerate() {
+            cpu.memory.poke(patch.address.wrapping_add(offset as u16), *byte);
+        }
+    }
+}
+
+pub fn bdos(cpu: &Cpu, output: &mut String) -> Result<(), String> {
+    // The only CP/M calls diagnostic roms make
+    //  C = 2: Print the character in E
+    //  C = 9: Print from the address in DE until a $
+
+    match cpu.debug_c() {
+        2 => output.push(cpu.debug_e() as char),
+        9 => {
+            let mut address: u16 = (cpu.debug_d() as u16) << 8 | cpu.debug_e() as u16;
+            while cpu.memory.read_at(address) != b'$' {
+                output.push(cpu.memory.read_at(address) as char);
+                address = address.wrapping_add(1);
+            }
+        },
+        call => return Err(format!("Unsupported BDOS call {}", call)),
+    }
+    Ok(())
+}
+
+pub fn run(cpu: &mut Cpu, output: &mut String, limit: Option<u64>) -> Result<(), String> {
+    // Runs until the program warm boots by jumping to 0x0000
+    //  Without a limit a program that never finishes runs forever, the exercisers need billions of instructions
+
+    let mut instructions: u64 = 0;
+    loop {
+        if limit.is_some_and(|limit| instructions >= limit) {
+            return Err(format!("0x{:04x}: Still running after {} instructions", cpu.pc.address, instructions));
+        }
+        instructions += 1;
+
+        let op_code_location: u16 = cpu.pc.address;
+        let op_code: u8 = cpu.memory.read_at(op_code_location);
+        cpu.pc.address = op_code_location.wrapping_add(1);
+
+        if op_code == 0xd3 {
+            let port: u8 = cpu.memory.read_at(cpu.pc.address);
+            cpu.pc.address = cpu.pc.address.wrapping_add(1);
+            match port {
+                WARM_BOOT_PORT => return Ok(()),
+                BDOS_PORT => bdos(cpu, output)?,
+                _ => return Err(format!("0x{:04x}: OUT to unknown port {}", op_code_location, port)),
+            }
+            continue;
+        }
+
+        match handle_op_code(op_code, cpu) {
+            Ok(255) => return Err(format!("0x{:04x}: Halted", op_code_location)),
+            Ok(additional_bytes) => cpu.pc.address = cpu.pc.address.wrapping_add(additional_bytes),
+            Err(e) => return Err(format!("0x{:04x}: 0x{:02x} {}", op_code_location, op_code, e)),
+        }
+    }
+}
+
+pub fn run_program(program: &[u8], patches: &[Patch], limit: Option<u64>) -> Result<String, String> {
+    // Runs a CP/M program on a fresh cpu and returns everything it printed
+
+    let mut cpu: Cpu = Cpu::init();
+    load(&mut cpu, program);
+    apply(&mut cpu, patches);
+
+    let mut output: String = String::new();
+    run(&mut cpu, &mut output, limit).map_err(|e| format!("{}\n{}", output, e))?;
+    Ok(output)
+}
+
+pub fn run_cpudiag(rom: &[u8]) -> Result<String, String> {
+    // Runs cpudiag with its usual fixes, an error holds the output when it reports a failure
+    let output: String = run_program(rom, &CPUDIAG_PATCHES, Some(CPUDIAG_LIMIT))?;
+    match output.contains("FAILED") {
+        true => Err(output),
+        false => Ok(output),
+    }
+}
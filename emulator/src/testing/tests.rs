@@ -0,0 +1,37 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_cpudiag() {
+    let output: String = run_cpudiag(CPUDIAG).unwrap();
+    assert!(output.contains("CPU IS OPERATIONAL"), "{}", output);
+}
+
+#[test]
+fn test_shim() {
+    // Prints through both BDOS calls then warm boots
+    let mut program: Vec<u8> = vec![
+        0x0e, 0x02, // MVI C, 2
+        0x1e, b'>', // MVI E, '>'
+        0xcd, 0x05, 0x00, // CALL 5
+        0x0e, 0x09, // MVI C, 9
+        0x11, 0x10, 0x01, // LXI D, text
+        0xcd, 0x05, 0x00, // CALL 5
+        0xc7, // RST 0
+    ];
+    program.extend(b"ok$");
+    assert_eq!(run_program(&program, &[], Some(100)), Ok(String::from(">ok")));
+
+    let mut failing: Vec<u8> = vec![
+        0x0e, 0x09, // MVI C, 9
+        0x11, 0x0b, 0x01, // LXI D, text
+        0xcd, 0x05, 0x00, // CALL 5
+        0xc3, 0x00, 0x00, // JMP 0
+    ];
+    failing.extend(b"CPU HAS FAILED!$");
+    assert!(run_cpudiag(&failing).is_err());
+
+    let spinning: [u8; 3] = [0xc3, 0x00, 0x01];
+    // JMP 0x0100
+    assert!(run_program(&spinning, &[], Some(100)).unwrap_err().contains("Still running"));
+}
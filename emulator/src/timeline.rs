@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::hardware::Port;
+use crate::hardware::sound;
+
+mod tests;
+
+pub const LIMIT: usize = 1_000_000;
+// Entries kept before the oldest are dropped, a couple of hours of interrupts
+pub const COIN_BIT: u8 = 0;
+// Bit of input port 1 that is high while a coin is going in
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Kind {
+    Interrupt,
+    Sound,
+    Coin,
+    StateLoad,
+    Breakpoint,
+    Fault,
+}
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        // Same as the names in exported JSON
+        match self {
+            Kind::Interrupt => "interrupt",
+            Kind::Sound => "sound",
+            Kind::Coin => "coin",
+            Kind::StateLoad => "state_load",
+            Kind::Breakpoint => "breakpoint",
+            Kind::Fault => "fault",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Entry {
+    pub cycle: u64,
+    // Machine's total cycles when it happened
+    pub kind: Kind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    // Notable things that happened while the machine ran, stamped with the cycle they happened on
+    //  Exported for looking at in a spreadsheet or trace viewer after something went wrong
+    entries: VecDeque<Entry>,
+    sound_ports: [u8; 2],
+    // Last values written to the two sound ports, only bits that turn on start a sound
+    coin: bool,
+}
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            sound_ports: [0; 2],
+            coin: false,
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        // Oldest first
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn record(&mut self, cycle: u64, kind: Kind, detail: String) {
+        if self.entries.len() == LIMIT {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry { cycle, kind, detail });
+    }
+
+    pub fn sound_write(&mut self, port: Port, value: u8, cycle: u64) {
+        // Games write the sound ports every frame so only bits that turn on are recorded
+
+        let index: usize = match port {
+            Port::SOUND1 => 0,
+            Port::SOUND2 => 1,
+            _ => return,
+        };
+        let started: u8 = value & !self.sound_ports[index];
+        self.sound_ports[index] = value;
+        for (bit, sound) in sound::port_sounds(port).iter().filter(|(bit, _)| started & (1 << bit) != 0) {
+            self.record(cycle, Kind::Sound, format!("{} (bit {})", sound.name(), bit));
+        }
+    }
+
+    pub fn inputs(&mut self, inputs: [u8; 2], cycle: u64) {
+        // Called with the input ports at the start of each frame
+        let coin: bool = inputs[0] & (1 << COIN_BIT) != 0;
+        if coin && !self.coin {
+            self.record(cycle, Kind::Coin, String::from("inserted"));
+        }
+        self.coin = coin;
+    }
+
+    pub fn to_csv(&self) -> String {
+        // cycle,kind,detail with details quoted since they can have commas in them
+
+        let mut csv: String = String::from("cycle,kind,detail\n");
+        for entry in &self.entries {
+            csv.push_str(&format!("{},{},\"{}\"\n", entry.cycle, entry.kind.name(), entry.detail.replace('"', "\"\"")));
+        }
+        csv
+    }
+}
+#[cfg(feature = "serde")]
+impl Timeline {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.entries).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        // CSV when the file ends in .csv, JSON otherwise
+
+        let text: String = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => self.to_csv(),
+            _ => self.to_json()? + "\n",
+        };
+        fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::debugger::{Breakpoint, Debugger};
+#[cfg(test)]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::savestate::SaveState;
+
+#[cfg(test)]
+fn timeline_rom() -> Vec<u8> {
+    // Starts the shot sound twice then waits for interrupts, which just return
+    let mut rom: Vec<u8> = vec![0; 0x20];
+    rom[0x00..0x04].copy_from_slice(&[0xf3, 0xc3, 0x18, 0x00]);
+    // DI, JMP 0x0018
+    rom[0x08..0x0a].copy_from_slice(&[0xfb, 0xc9]);
+    rom[0x10..0x12].copy_from_slice(&[0xfb, 0xc9]);
+    // EI, RET for RST 1 and RST 2
+    rom[0x18..0x20].copy_from_slice(&[0x3e, 0x02, 0xd3, 0x03, 0xd3, 0x03, 0xfb, 0xc3]);
+    rom.extend([0x1e, 0x00]);
+    // MVI A,2  OUT 3  OUT 3  EI  JMP 0x001e back to the EI
+    rom
+}
+
+#[test]
+fn test_timeline() {
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&timeline_rom()).unwrap();
+    machine.set_timeline(true);
+    machine.hardware.set_inputs([1 << COIN_BIT, 0]);
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+    // The coin is held for both frames but only goes in once
+
+    let kinds: Vec<(Kind, &str)> = machine.timeline().unwrap().entries().map(|entry| (entry.kind, entry.detail.as_str())).collect();
+    assert_eq!(kinds, vec![
+        (Kind::Coin, "inserted"),
+        (Kind::Sound, "shot (bit 1)"),
+        (Kind::Interrupt, "RST 1"),
+        (Kind::Interrupt, "RST 2"),
+        (Kind::Interrupt, "RST 1"),
+        (Kind::Interrupt, "RST 2"),
+    ]);
+    let cycles: Vec<u64> = machine.timeline().unwrap().entries().map(|entry| entry.cycle).collect();
+    assert!(cycles.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let state: SaveState = machine.save_state();
+    machine.load_state(&state).unwrap();
+    let mut debugger: Debugger = Debugger::new();
+    debugger.add_breakpoint(Breakpoint::Address(0x001e));
+    assert!(debugger.run_frame(&mut machine).is_some());
+    let last: Vec<Kind> = machine.timeline().unwrap().entries().skip(6).map(|entry| entry.kind).collect();
+    assert_eq!(last, vec![Kind::StateLoad, Kind::Breakpoint]);
+
+    let timeline: &Timeline = machine.timeline().unwrap();
+    let csv: String = timeline.to_csv();
+    assert!(csv.starts_with("cycle,kind,detail\n0,coin,\"inserted\"\n"));
+    assert_eq!(csv.lines().count(), timeline.len() + 1);
+    #[cfg(feature = "serde")]
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&timeline.to_json().unwrap()).unwrap()[1]["kind"], "sound");
+}
@@ -0,0 +1,138 @@
+use raylib::prelude::*;
+
+use crate::hardware::input::{Binding, InputSource};
+use crate::i18n::{Language, Text};
+use crate::HEIGHT;
+
+mod tests;
+
+const BUTTON_SIZE: i32 = 200;
+const BUTTON_GAP: i32 = 40;
+const BUTTON_TEXT_SIZE: i32 = 30;
+// Sizes in a window HEIGHT tall, the buttons grow and shrink with the window
+const BUTTON_COLOUR: Color = Color::GRAY;
+const HELD_COLOUR: Color = Color::WHITE;
+
+const BUTTONS: [(Text, u8, u8); 5] = [
+    (Text::ButtonLeft, 1, 5),
+    (Text::ButtonRight, 1, 6),
+    (Text::ButtonFire, 1, 4),
+    (Text::ButtonCoin, 1, 0),
+    (Text::ButtonStart, 1, 2),
+];
+// Label, input port and bit of each button
+//  Buttons press whichever key is bound to their bit so they work with any keymap
+
+#[derive(Debug, Clone, Copy)]
+pub struct VirtualButton {
+    pub label: &'static str,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub key: KeyboardKey,
+    // Key the button pretends to be
+}
+impl VirtualButton {
+    fn contains(&self, point: Vector2) -> bool {
+        point.x >= self.x as f32 && point.x < (self.x + self.width) as f32
+            && point.y >= self.y as f32 && point.y < (self.y + self.height) as f32
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VirtualButtons {
+    // On screen controls for touch screens, clicking with the mouse works too
+    buttons: Vec<VirtualButton>,
+    held: Vec<KeyboardKey>,
+    text_size: i32,
+}
+impl VirtualButtons {
+    pub fn new(bindings: &[Binding], width: i32, height: i32, language: Language) -> Self {
+        // Lays the buttons out in the space either side of the game
+        //  Movement on the left, fire on the right and coin and start above fire
+
+        let size: i32 = BUTTON_SIZE * height / HEIGHT;
+        let gap: i32 = BUTTON_GAP * height / HEIGHT;
+        let bottom: i32 = height - size - gap;
+        let positions: [(i32, i32); 5] = [
+            (gap, bottom),
+            (gap * 2 + size, bottom),
+            (width - gap - size, bottom),
+            (width - (gap + size) * 2, bottom - gap - size),
+            (width - gap - size, bottom - gap - size),
+        ];
+
+        let buttons: Vec<VirtualButton> = BUTTONS.iter()
+            .zip(positions)
+            .filter_map(|((text, input, bit), (x, y))| {
+                let binding: &Binding = bindings.iter().find(|binding| binding.input == *input && binding.bit == *bit)?;
+                Some(VirtualButton { label: language.text(*text), x, y, width: size, height: size, key: binding.key })
+            })
+            .collect();
+        // Buttons for bits that nothing is bound to are left out
+
+        Self {
+            buttons,
+            held: vec![],
+            text_size: BUTTON_TEXT_SIZE * height / HEIGHT,
+        }
+    }
+
+    pub fn buttons(&self) -> &[VirtualButton] {
+        self.buttons.as_slice()
+    }
+
+    pub fn update(&mut self, raylib_handle: &RaylibHandle) {
+        // Reads every finger on the screen, or the mouse when nothing is touching it
+
+        let touches: u32 = raylib_handle.get_touch_point_count();
+        let pointers: Vec<Vector2> = if touches > 0 {
+            (0..touches).map(|index| raylib_handle.get_touch_position(index)).collect()
+        }
+        else if raylib_handle.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+            vec![raylib_handle.get_mouse_position()]
+        }
+        else {
+            vec![]
+        };
+
+        self.update_pointers(&pointers);
+    }
+
+    pub fn update_pointers(&mut self, pointers: &[Vector2]) {
+        self.held = self.buttons.iter()
+            .filter(|button| pointers.iter().any(|pointer| button.contains(*pointer)))
+            .map(|button| button.key)
+            .collect();
+    }
+
+    pub fn is_held(&self, key: KeyboardKey) -> bool {
+        self.held.contains(&key)
+    }
+
+    pub fn draw(&self, draw_handle: &mut impl RaylibDraw) {
+        for button in &self.buttons {
+            let colour: Color = if self.is_held(button.key) { HELD_COLOUR } else { BUTTON_COLOUR };
+            draw_handle.draw_rectangle_lines(button.x, button.y, button.width, button.height, colour);
+            draw_handle.draw_text(
+                button.label,
+                button.x + self.text_size / 2,
+                button.y + (button.height - self.text_size) / 2,
+                self.text_size,
+                colour,
+            );
+        }
+    }
+}
+
+pub struct WithButtons<'a, S: InputSource> {
+    // Combines another input source with the virtual buttons
+    pub source: &'a S,
+    pub buttons: Option<&'a VirtualButtons>,
+}
+impl<S: InputSource> InputSource for WithButtons<'_, S> {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.source.is_key_down(key) || self.buttons.is_some_and(|buttons| buttons.is_held(key))
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::hardware::input::InputConfig;
+
+#[cfg(test)]
+struct NoKeys;
+#[cfg(test)]
+impl InputSource for NoKeys {
+    fn is_key_down(&self, _key: KeyboardKey) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_virtual_buttons() {
+    let bindings: Vec<Binding> = InputConfig::default().bindings();
+    let mut buttons: VirtualButtons = VirtualButtons::new(&bindings, 1920, 1080, Language::English);
+    assert_eq!(buttons.buttons().len(), 5);
+
+    let fire: VirtualButton = buttons.buttons()[2];
+    assert_eq!(fire.key, KeyboardKey::KEY_S);
+    // Presses the key bound to P1 shoot
+
+    buttons.update_pointers(&[Vector2::new((fire.x + 10) as f32, (fire.y + 10) as f32), Vector2::new(0.0, 0.0)]);
+    let input: WithButtons<NoKeys> = WithButtons { source: &NoKeys, buttons: Some(&buttons) };
+    assert!(input.is_key_down(KeyboardKey::KEY_S));
+    assert!(!input.is_key_down(KeyboardKey::KEY_A));
+
+    buttons.update_pointers(&[]);
+    assert!(!buttons.is_held(KeyboardKey::KEY_S));
+
+    let buttons: VirtualButtons = VirtualButtons::new(&bindings[..1], 1920, 1080, Language::Spanish);
+    assert_eq!(buttons.buttons().len(), 1);
+    // Only coin is bound
+    assert_eq!(buttons.buttons()[0].label, "MONEDA");
+}
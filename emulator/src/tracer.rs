@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use disassembler::syntax::Syntax;
+
+use crate::cpu::Cpu;
+use crate::machine::Event;
+
+mod tests;
+
+static INTEL: OnceLock<Vec<String>> = OnceLock::new();
+static Z80: OnceLock<Vec<String>> = OnceLock::new();
+// Each op code's text from the disassembler's table, worked out the first time an instruction is traced in that syntax
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    // How much a trace says, a full instruction trace is gigabytes after a few minutes of play
+    Instruction,
+    // Every instruction with the registers before it ran
+    Interrupt,
+    // One line per interrupt serviced
+    Frame,
+    // One line per frame with its instructions, interrupts and port accesses
+}
+impl Level {
+    pub const ALL: [Level; 3] = [Level::Instruction, Level::Interrupt, Level::Frame];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Instruction => "instruction",
+            Level::Interrupt => "interrupt",
+            Level::Frame => "frame",
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::ALL.into_iter()
+            .find(|level| level.name() == text.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("{} is not a trace level, try one of {}", text, Self::ALL.map(|level| level.name()).join(", ")))
+    }
+}
+
+pub struct Tracer {
+    // Writes what the machine runs as it runs it, at one of the levels
+    level: Level,
+    every: u64,
+    // Only one frame in every this many is traced
+    syntax: Syntax,
+    sink: Option<Box<dyn Write + Send>>,
+    // None in a copy of the machine and after a write fails
+    frame: u64,
+    instructions: u64,
+    // Run so far this frame
+    since_interrupt: u64,
+    error: Option<String>,
+}
+impl Tracer {
+    pub fn new(level: Level, every: u64, sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            level,
+            every: every.max(1),
+            syntax: Syntax::Intel,
+            sink: Some(sink),
+            frame: 0,
+            instructions: 0,
+            since_interrupt: 0,
+            error: None,
+        }
+    }
+
+    pub fn create(level: Level, every: u64, path: &Path) -> Result<Self, String> {
+        let file: File = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self::new(level, every, Box::new(BufWriter::new(file))))
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn set_syntax(&mut self, syntax: Syntax) {
+        self.syntax = syntax;
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        // Why tracing stopped, if it did
+        self.error.as_deref()
+    }
+
+    fn traced(&self) -> bool {
+        self.frame.is_multiple_of(self.every)
+    }
+
+    fn write(&mut self, line: String) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        if let Err(e) = writeln!(sink, "{}", line) {
+            self.error = Some(e.to_string());
+            self.sink = None;
+        }
+        // A full disk stops the trace rather than the machine
+    }
+
+    pub fn instruction(&mut self, cpu: &Cpu, cycle: u64) {
+        // Called before each instruction runs
+
+        self.instructions += 1;
+        self.since_interrupt += 1;
+        if self.level != Level::Instruction || !self.traced() {
+            return;
+        }
+        let pc: u16 = cpu.pc.address;
+        let bytes: [u8; 3] = [0, 1, 2].map(|offset| cpu.memory.read_at(pc.wrapping_add(offset)));
+        self.write(format!("{:>12}  {:04x}  {:<24}  {}", cycle, pc, disassemble(bytes, self.syntax), cpu.registers()));
+    }
+
+    pub fn interrupt(&mut self, rst: u8, from: u16, cpu: &Cpu, cycle: u64) {
+        // Called once an interrupt has been serviced, from is where the cpu was when it came
+
+        let since: u64 = std::mem::take(&mut self.since_interrupt);
+        if self.level == Level::Frame || !self.traced() {
+            return;
+        }
+        self.write(format!("{:>12}  RST {} from {:04x} after {} instructions, frame {}  {}", cycle, rst, from, since, self.frame, cpu.registers()));
+    }
+
+    pub fn frame(&mut self, events: &[Event], cycle: u64) {
+        // Called at the end of every frame with everything that happened in it
+
+        let instructions: u64 = std::mem::take(&mut self.instructions);
+        if self.level == Level::Frame && self.traced() {
+            self.write(summary(self.frame, cycle, instructions, events));
+        }
+        self.frame += 1;
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        if let Some(sink) = self.sink.as_mut() {
+            sink.flush().map_err(|e| e.to_string())?;
+        }
+        self.error.clone().map_or(Ok(()), Err)
+    }
+}
+impl Clone for Tracer {
+    fn clone(&self) -> Self {
+        Self { sink: None, error: self.error.clone(), ..*self }
+        // A copy of the machine, a rewind point or a shadow run ahead, mustn't write into the original's trace
+    }
+}
+
+fn disassemble(bytes: [u8; 3], syntax: Syntax) -> String {
+    // The instruction with its operands filled in, e.g. "JMP 0x1a32"
+    //  The table's own text is used rather than decoding since this runs for every instruction
+
+    let texts: &OnceLock<Vec<String>> = match syntax {
+        Syntax::Intel => &INTEL,
+        Syntax::Z80 => &Z80,
+    };
+    let texts: &[String] = texts.get_or_init(|| disassembler::instructions::table().iter().map(|instruction| syntax.format(&instruction.text)).collect());
+    let Some(text) = texts.get(bytes[0] as usize) else {
+        return format!("0x{:02x}", bytes[0]);
+    };
+    let word: u16 = (bytes[2] as u16) << 8 | bytes[1] as u16;
+    text
+        .replace("D16", &format!("0x{:04x}", word))
+        .replace("adr", &format!("0x{:04x}", word))
+        .replace("D8", &format!("0x{:02x}", bytes[1]))
+}
+
+pub fn summary(frame: u64, cycle: u64, instructions: u64, events: &[Event]) -> String {
+    // frame 120  cycle 3960000  instructions 8421  RST 1 RST 2  in 1x3 2x1  out 3x1 5x1 6x1
+
+    let mut interrupts: Vec<String> = vec![];
+    let mut reads: BTreeMap<u8, u32> = BTreeMap::new();
+    let mut writes: BTreeMap<u8, u32> = BTreeMap::new();
+    for event in events {
+        match event {
+            Event::Interrupt(rst) => interrupts.push(format!("RST {}", rst)),
+            Event::PortRead(port) => *reads.entry(*port).or_default() += 1,
+            Event::PortWrite(port, _) => *writes.entry(*port).or_default() += 1,
+            Event::InterruptsEnabled | Event::InterruptsDisabled | Event::StackLeft(_) => {},
+        }
+    }
+    let counts = |ports: &BTreeMap<u8, u32>| ports.iter().map(|(port, count)| format!(" {}x{}", port, count)).collect::<String>();
+
+    format!("frame {}  cycle {}  instructions {}  {}  in{}  out{}",
+        frame,
+        cycle,
+        instructions,
+        match interrupts.is_empty() {
+            true => String::from("no interrupts"),
+            false => interrupts.join(" "),
+        },
+        counts(&reads),
+        counts(&writes),
+    )
+}
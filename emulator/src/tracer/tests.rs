@@ -0,0 +1,119 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use crate::machine::Machine;
+
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+#[cfg(test)]
+impl Write for Shared {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+#[cfg(test)]
+impl Shared {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(String::from).collect()
+    }
+}
+
+#[cfg(test)]
+fn traced_machine(level: Level, every: u64) -> (Machine, Shared) {
+    // Counts in A with both interrupts enabled
+
+    let mut rom: Vec<u8> = vec![
+        0x31, 0x00, 0x24, // 0x0000 LXI SP, 0x2400
+        0xfb, // 0x0003 EI
+        0x3c, // 0x0004 INR A
+        0xc3, 0x04, 0x00, // 0x0005 JMP 0x0004
+    ];
+    rom.extend([0xfb, 0xc9, 0, 0, 0, 0, 0, 0]);
+    rom.extend([0xfb, 0xc9]);
+    // RST 1 and RST 2: EI, RET
+
+    let shared: Shared = Shared::default();
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    machine.set_tracer(Some(Tracer::new(level, every, Box::new(shared.clone()))));
+    (machine, shared)
+}
+
+#[test]
+fn test_levels() {
+    assert_eq!(Level::parse("Interrupt"), Ok(Level::Interrupt));
+    assert!(Level::parse("everything").is_err());
+
+    let (mut machine, shared) = traced_machine(Level::Frame, 1);
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+    let lines: Vec<String> = shared.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("frame 0  cycle 33"), "{}", lines[0]);
+    assert!(lines[1].contains("RST 1 RST 2"), "{}", lines[1]);
+
+    let (mut machine, shared) = traced_machine(Level::Interrupt, 1);
+    machine.run_frame().unwrap();
+    let lines: Vec<String> = shared.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("RST 1 from 000"), "{}", lines[0]);
+    assert!(lines[1].contains("RST 2"), "{}", lines[1]);
+
+    let (mut machine, shared) = traced_machine(Level::Instruction, 2);
+    machine.run_frame().unwrap();
+    let first: usize = shared.lines().len();
+    assert!(shared.lines()[0].contains("0000  LXI SP, 0x2400"), "{}", shared.lines()[0]);
+    machine.run_frame().unwrap();
+    assert_eq!(shared.lines().len(), first);
+    // Only every other frame is traced
+    machine.run_frame().unwrap();
+    assert!(shared.lines().len() > first);
+
+    let mut copy: Machine = machine.clone();
+    let before: usize = shared.lines().len();
+    copy.run_frame().unwrap();
+    copy.run_frame().unwrap();
+    assert_eq!(shared.lines().len(), before);
+    // Copies of the machine write nowhere
+}
+
+#[test]
+fn test_summary() {
+    let events: [Event; 5] = [Event::PortRead(1), Event::PortWrite(3, 0x01), Event::PortRead(1), Event::Interrupt(1), Event::PortRead(2)];
+    assert_eq!(summary(7, 1000, 42, &events), "frame 7  cycle 1000  instructions 42  RST 1  in 1x2 2x1  out 3x1");
+    assert_eq!(summary(0, 0, 0, &[]), "frame 0  cycle 0  instructions 0  no interrupts  in  out");
+}
+
+#[test]
+fn test_syntax() {
+    assert_eq!(disassemble([0x7e, 0, 0], Syntax::Intel), "MOV A,M");
+    assert_eq!(disassemble([0x7e, 0, 0], Syntax::Z80), "LD A,(HL)");
+    assert_eq!(disassemble([0x1a, 0, 0], Syntax::Z80), "LD A,(DE)");
+    assert_eq!(disassemble([0x3a, 0x34, 0x12], Syntax::Z80), "LD A,(0x1234)");
+    assert_eq!(disassemble([0xc2, 0x00, 0x18], Syntax::Z80), "JP NZ,0x1800");
+    assert_eq!(disassemble([0xf4, 0x00, 0x18], Syntax::Z80), "CALL P,0x1800");
+    assert_eq!(disassemble([0xd8, 0, 0], Syntax::Z80), "RET C");
+    assert_eq!(disassemble([0xd3, 0x03, 0], Syntax::Z80), "OUT (0x03),A");
+    assert_eq!(disassemble([0xcf, 0, 0], Syntax::Z80), "RST 0x08");
+    assert_eq!(disassemble([0xf5, 0, 0], Syntax::Z80), "PUSH AF");
+    assert_eq!(disassemble([0xfe, 0x20, 0], Syntax::Z80), "CP 0x20");
+
+    let texts: Vec<String> = disassembler::instructions::table().iter().map(|instruction| Syntax::Z80.format(&instruction.text)).collect();
+    let z80: [&str; 30] = [
+        "NOP", "LD", "INC", "DEC", "ADD", "ADC", "SUB", "SBC", "AND", "XOR", "OR", "CP", "RLCA", "RRCA", "RLA", "RRA",
+        "DAA", "CPL", "SCF", "CCF", "HALT", "EX", "JP", "CALL", "RET", "RST", "PUSH", "POP", "IN", "OUT",
+    ];
+    for text in texts.iter().filter(|text| !matches!(text.as_str(), "EI" | "DI")) {
+        assert!(z80.contains(&text.split(' ').next().unwrap()), "{}", text);
+        assert!(!text.ends_with(",M") && !text.ends_with(" M,D8"), "{}", text);
+    }
+    // No Intel mnemonics or M for (HL) are left over
+}
@@ -0,0 +1,55 @@
+use crate::display::Framebuffer;
+
+mod tests;
+
+pub type Callback = Box<dyn FnMut(&Framebuffer) + Send>;
+
+pub struct Vblank {
+    // Callbacks run the moment the board's end of frame interrupt comes due, given the finished screen
+    //  Frontends can present from here instead of after a fixed number of cycles
+    callbacks: Vec<(usize, Callback)>,
+    next_id: usize,
+}
+impl Vblank {
+    pub fn new() -> Self {
+        Self {
+            callbacks: vec![],
+            next_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, callback: impl FnMut(&Framebuffer) + Send + 'static) -> usize {
+        // Returns an id for removing the callback again
+        self.next_id += 1;
+        self.callbacks.push((self.next_id, Box::new(callback)));
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before: usize = self.callbacks.len();
+        self.callbacks.retain(|(callback_id, _)| *callback_id != id);
+        self.callbacks.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    pub fn fire(&mut self, framebuffer: &Framebuffer) {
+        // Runs every callback in the order they were added
+        for (_, callback) in &mut self.callbacks {
+            callback(framebuffer);
+        }
+    }
+}
+impl Default for Vblank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Clone for Vblank {
+    fn clone(&self) -> Self {
+        Self::new()
+        // Copies of the machine, rewind points and runs ahead, would otherwise present frames the player never sees
+    }
+}
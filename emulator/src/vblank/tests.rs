@@ -0,0 +1,59 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use crate::machine::Machine;
+#[cfg(test)]
+use crate::fixtures;
+
+#[cfg(test)]
+fn counting(machine: &mut Machine) -> (usize, Arc<Mutex<Vec<usize>>>) {
+    // Registers a callback that records how many pixels were lit each time it was called
+    let lit: Arc<Mutex<Vec<usize>>> = Arc::default();
+    let sink: Arc<Mutex<Vec<usize>>> = Arc::clone(&lit);
+    let id: usize = machine.on_vblank(move |framebuffer: &Framebuffer| sink.lock().unwrap().push(framebuffer.lit_pixels().count()));
+    (id, lit)
+}
+
+#[test]
+fn test_vblank() {
+    let mut machine: Machine = fixtures::machine(fixtures::DRAW_BYTE);
+    let (id, lit) = counting(&mut machine);
+
+    machine.run_half_frame().unwrap();
+    assert!(lit.lock().unwrap().is_empty());
+    // Nothing at the mid screen interrupt
+    machine.run_half_frame().unwrap();
+    assert_eq!(*lit.lock().unwrap(), vec![8]);
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+    assert_eq!(*lit.lock().unwrap(), vec![8, 8, 8]);
+
+    machine.block_interrupts(true);
+    machine.run_frame().unwrap();
+    assert_eq!(lit.lock().unwrap().len(), 4);
+    // The screen still finishes when the cpu never sees the interrupt
+
+    assert!(machine.remove_vblank(id));
+    assert!(!machine.remove_vblank(id));
+    machine.run_frame().unwrap();
+    assert_eq!(lit.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn test_vblank_copies() {
+    let mut machine: Machine = fixtures::machine(fixtures::DRAW_BYTE);
+    let earlier: Machine = machine.clone();
+    let (_, lit) = counting(&mut machine);
+
+    let mut copy: Machine = machine.clone();
+    copy.run_frame().unwrap();
+    assert!(lit.lock().unwrap().is_empty());
+    // A copy running ahead doesn't present anything
+
+    machine.restore(&earlier);
+    machine.run_frame().unwrap();
+    assert_eq!(lit.lock().unwrap().len(), 1);
+    // Restoring keeps the callbacks of the machine being restored
+}
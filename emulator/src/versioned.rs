@@ -0,0 +1,34 @@
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+mod tests;
+
+pub type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+// Rewrites a file's json from one version into the next, before it is turned into the type
+
+pub fn from_json<T: DeserializeOwned>(text: &str, what: &str, version: u32, migrations: &[(u32, Migration)]) -> Result<T, String> {
+    // Parses a json file with a version field, bringing older versions up to date first
+    //  Each migration is listed with the version it upgrades from and runs in turn until the file is current
+    //  e.g. versioned::from_json(text, "save state", VERSION, MIGRATIONS)
+
+    let mut value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let object: &mut Map<String, Value> = value.as_object_mut().ok_or_else(|| format!("{} isn't a json object", what))?;
+    let mut found: u32 = object.get("version")
+        .and_then(Value::as_u64)
+        .and_then(|found| u32::try_from(found).ok())
+        .ok_or_else(|| format!("{} has no version", what))?;
+    if found > version {
+        return Err(format!("{} version {} was made by a newer emulator, this one reads up to version {}", what, found, version));
+    }
+
+    while found < version {
+        let migration: Migration = migrations.iter()
+            .find(|(from, _)| *from == found)
+            .map(|(_, migration)| *migration)
+            .ok_or_else(|| format!("{} version {} isn't supported", what, found))?;
+        migration(object).map_err(|e| format!("Upgrading {} version {}: {}", what, found, e))?;
+        found += 1;
+        object.insert(String::from("version"), Value::from(found));
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
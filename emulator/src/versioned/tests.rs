@@ -0,0 +1,42 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use serde::Deserialize;
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Deserialize)]
+struct Counter {
+    version: u32,
+    count: u32,
+    step: u32,
+}
+
+#[cfg(test)]
+const MIGRATIONS: &[(u32, Migration)] = &[
+    (1, |counter| {
+        let total: Value = counter.remove("total").ok_or("total is missing")?;
+        counter.insert(String::from("count"), total);
+        Ok(())
+    }),
+    (2, |counter| {
+        counter.insert(String::from("step"), Value::from(1));
+        Ok(())
+    }),
+];
+// Version 2 renamed total to count and version 3 added step
+
+#[test]
+fn test_migrations() {
+    let current: Counter = from_json(r#"{"version":3,"count":4,"step":2}"#, "counter", 3, MIGRATIONS).unwrap();
+    assert_eq!(current, Counter { version: 3, count: 4, step: 2 });
+    let upgraded: Counter = from_json(r#"{"version":1,"total":4}"#, "counter", 3, MIGRATIONS).unwrap();
+    assert_eq!(upgraded, Counter { version: 3, count: 4, step: 1 });
+    // Each migration runs in turn
+
+    let error = |text: &str| from_json::<Counter>(text, "counter", 3, MIGRATIONS).unwrap_err();
+    assert_eq!(error(r#"{"version":4,"count":4,"step":2}"#), "counter version 4 was made by a newer emulator, this one reads up to version 3");
+    assert_eq!(error(r#"{"version":0}"#), "counter version 0 isn't supported");
+    assert_eq!(error(r#"{"count":4}"#), "counter has no version");
+    assert_eq!(error(r#"[1]"#), "counter isn't a json object");
+    assert_eq!(error(r#"{"version":1}"#), "Upgrading counter version 1: total is missing");
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{wav, AudioBackend};
+use crate::display::Geometry;
+use crate::hardware::sound::{self, SoundLog};
+use crate::headless;
+use crate::machine::Machine;
+use crate::palette::Palette;
+use crate::png;
+use crate::replay::{FrameHash, Replay};
+
+mod tests;
+
+pub const DIRECTORY: &str = "video";
+// Where render-replay writes when no --out is given, relative to the working directory
+pub const FRAMES_PER_SECOND: u32 = 60;
+pub const AUDIO_FILE: &str = "audio.wav";
+pub const FRAME_PATTERN: &str = "frame-%06d.png";
+// ffmpeg's name for the frame files, frame_name writes the same thing
+
+pub fn frame_name(number: usize) -> String {
+    // Numbered from 1 like ffmpeg expects
+    format!("frame-{:06}.png", number)
+}
+
+pub fn ffmpeg_command(out: &Path) -> String {
+    // What to run to put the frames and audio together into one video
+    format!(
+        "ffmpeg -framerate {} -i {} -i {} -c:v libx264 -pix_fmt yuv420p -vf scale=iw*4:ih*4:flags=neighbor -c:a aac -shortest {}",
+        FRAMES_PER_SECOND,
+        out.join(FRAME_PATTERN).display(),
+        out.join(AUDIO_FILE).display(),
+        out.join("replay.mp4").display(),
+    )
+}
+
+pub fn samples_in_frame(sample_rate: u32, frame: usize) -> usize {
+    // Samples that fall inside the frame, worked out from the start each time so
+    //  rates that don't divide by the frame rate don't drift away from the picture
+    let end: u64 = (frame as u64 + 1) * sample_rate as u64 / FRAMES_PER_SECOND as u64;
+    let start: u64 = frame as u64 * sample_rate as u64 / FRAMES_PER_SECOND as u64;
+    (end - start) as usize
+}
+
+pub fn render_replay(machine: &mut Machine, replay: &Replay, palette: &Palette, audio: &mut dyn AudioBackend, out: &Path) -> Result<usize, String> {
+    // Plays a replay as fast as the machine runs, saving every frame as a png and the sound as one wav
+    //  The frame hashes are checked on the way so a video can't be made of a run that went differently
+    //  Returns the number of frames written, the wav is written even when a frame fails so far as it got
+
+    replay.check_rom(machine.rom())?;
+    fs::create_dir_all(out).map_err(|e| format!("Failed to create {}: {}", out.display(), e))?;
+
+    let geometry: Geometry = machine.profile().display.geometry();
+    let mut hash: FrameHash = FrameHash::new();
+    let mut samples: Vec<f32> = vec![];
+    let mut buffer: Vec<i16> = vec![];
+    let mut rendered: Result<usize, String> = Ok(replay.frames.len());
+    for (number, frame) in replay.frames.iter().enumerate() {
+        machine.hardware.set_inputs(frame.inputs);
+        if let Err(e) = machine.run_frame() {
+            rendered = Err(format!("Frame {}: {}", number + 1, e));
+            break;
+        }
+        let actual: String = format!("{:016x}", hash.update_from(machine));
+        if actual != frame.hash {
+            rendered = Err(format!("Frame {}: expected hash {} but got {}", number + 1, frame.hash, actual));
+            break;
+        }
+
+        let image: Vec<u8> = png::encode_rgb(geometry.width as u32, geometry.height as u32, &headless::framebuffer(&machine.cpu, geometry, palette))?;
+        let path: PathBuf = out.join(frame_name(number + 1));
+        fs::write(&path, image).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        let mut sounds: SoundLog = SoundLog::new();
+        sound::update_audio(&mut machine.hardware, &mut sounds);
+        match frame.sounds.as_ref() {
+            Some(logged) => logged.iter().for_each(|event| event.send(audio)),
+            None => sounds.send(audio),
+        }
+        // Same as playing it back in the window, the recorded sounds when the replay has them
+        buffer.resize(samples_in_frame(audio.sample_rate(), number), 0);
+        audio.mix(&mut buffer);
+        samples.extend(buffer.iter().map(|sample| *sample as f32 / i16::MAX as f32));
+    }
+
+    let path: PathBuf = out.join(AUDIO_FILE);
+    fs::write(&path, wav::encode(audio.sample_rate(), &samples)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    rendered
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::audio::Mixer;
+#[cfg(test)]
+use crate::audio::samples::SamplePack;
+#[cfg(test)]
+use crate::fixtures;
+
+#[test]
+fn test_samples_in_frame() {
+    assert_eq!(samples_in_frame(22050, 0), 367);
+    assert_eq!((0..FRAMES_PER_SECOND as usize).map(|frame| samples_in_frame(22050, frame)).sum::<usize>(), 22050);
+    // A second of frames is a second of sound even though 60 doesn't go into 22050
+    assert_eq!(frame_name(12), "frame-000012.png");
+}
+
+#[test]
+fn test_render_replay() {
+    let mut recorder: Machine = fixtures::machine(fixtures::INPUT_AND_SHOT);
+    let mut replay: Replay = Replay::new(&fixtures::rom(fixtures::INPUT_AND_SHOT));
+    let mut hash: FrameHash = FrameHash::new();
+    for _ in 0..6 {
+        recorder.hardware.set_inputs([0xff, 0x00]);
+        recorder.run_frame().unwrap();
+        replay.record([0xff, 0x00], hash.update_from(&recorder));
+    }
+
+    let out: PathBuf = std::env::temp_dir().join(format!("emulator-video-test-{}", std::process::id()));
+    let mut machine: Machine = fixtures::machine(fixtures::INPUT_AND_SHOT);
+    let palette: Palette = Palette::for_screen(&machine.profile().overlay, machine.profile().display.geometry()).unwrap();
+    let mut mixer: Mixer = Mixer::new(SamplePack::synthesized());
+    assert_eq!(render_replay(&mut machine, &replay, &palette, &mut mixer, &out), Ok(6));
+
+    let geometry: Geometry = machine.profile().display.geometry();
+    let (width, height, pixels): (u32, u32, Vec<u8>) = png::decode_rgb(&fs::read(out.join(frame_name(6))).unwrap()).unwrap();
+    assert_eq!((width, height), (geometry.width as u32, geometry.height as u32));
+    assert_eq!(pixels, headless::framebuffer(&machine.cpu, geometry, &palette));
+    assert!(!out.join(frame_name(7)).exists());
+    let (sample_rate, samples): (u32, Vec<f32>) = wav::decode(&fs::read(out.join(AUDIO_FILE)).unwrap()).unwrap();
+    assert_eq!((sample_rate, samples.len()), (mixer.sample_rate(), 6 * 22050 / 60));
+    assert!(samples.iter().any(|sample| *sample != 0.0));
+    // The shot is heard
+
+    replay.frames[2].hash = String::from("0000000000000000");
+    let mut machine: Machine = fixtures::machine(fixtures::INPUT_AND_SHOT);
+    let error: String = render_replay(&mut machine, &replay, &palette, &mut Mixer::new(SamplePack::synthesized()), &out).unwrap_err();
+    assert!(error.starts_with("Frame 3"), "{}", error);
+    let (_, samples): (u32, Vec<f32>) = wav::decode(&fs::read(out.join(AUDIO_FILE)).unwrap()).unwrap();
+    assert_eq!(samples.len(), 2 * 22050 / 60);
+    // The sound up to where it went wrong is still saved
+    fs::remove_dir_all(&out).unwrap();
+}
@@ -0,0 +1,42 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    // Notices when a file is saved by polling its modification time
+    //  Cheap enough to check every frame without a background thread
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+impl FileWatcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified_time(path),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn changed(&mut self) -> bool {
+        // True once each time the file is modified
+        //  A file that is deleted doesn't count as changed until it comes back
+
+        let modified: Option<SystemTime> = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
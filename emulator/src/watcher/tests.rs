@@ -0,0 +1,22 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use std::time::Duration;
+
+#[test]
+fn test_file_watcher() {
+    let path: PathBuf = std::env::temp_dir().join(format!("watcher_test_{}.toml", std::process::id()));
+    fs::write(&path, "first").expect("writing test file");
+
+    let mut watcher: FileWatcher = FileWatcher::new(&path);
+    assert!(!watcher.changed());
+
+    let file = fs::File::options().write(true).open(&path).expect("opening test file");
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).expect("touching test file");
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+    // Only reported once per change
+
+    fs::remove_file(&path).expect("removing test file");
+    assert!(!watcher.changed());
+}
@@ -0,0 +1,125 @@
+use raylib::core::window;
+use raylib::prelude::*;
+
+use crate::display::Geometry;
+use crate::{HEIGHT, INVADERS_HEIGHT, INVADERS_WIDTH, WIDTH};
+
+mod tests;
+
+const WINDOWED_FILL: f32 = 0.9;
+// A window never covers more of its monitor than this so the title bar and taskbar stay in view
+const TEXT_SIZE: i32 = 20;
+// Hud text at 100% scaling
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    // Where things are drawn in a window of a given size
+    //  Worked out again every frame from the window's size so resizing never leaves anything behind
+    pub width: i32,
+    pub height: i32,
+    pub scale: i32,
+    // Window pixels to a game pixel, kept whole so every game pixel is the same size
+    pub game_x: i32,
+    pub game_y: i32,
+    // Top left of the game, centred in the window
+    pub game_width: i32,
+    pub game_height: i32,
+    // The game's screen in game pixels, the Midway board's unless the profile says otherwise
+    pub text_size: i32,
+    // Hud text, bigger on high DPI monitors so it reads the same on any of them
+}
+impl Layout {
+    pub fn new(width: i32, height: i32, dpi_scale: f32) -> Self {
+        Self {
+            width,
+            height,
+            scale: 1,
+            game_x: 0,
+            game_y: 0,
+            game_width: INVADERS_WIDTH,
+            game_height: INVADERS_HEIGHT,
+            text_size: (TEXT_SIZE as f32 * dpi_scale.max(1.0)).round() as i32,
+        }.fit(Geometry::space_invaders())
+    }
+
+    pub fn fit(self, geometry: Geometry) -> Self {
+        // The same window laid out for a screen of another size
+        let (game_width, game_height): (i32, i32) = (geometry.width.max(1) as i32, geometry.height.max(1) as i32);
+        let scale: i32 = (self.width / game_width).min(self.height / game_height).max(1);
+        Self {
+            scale,
+            game_x: (self.width - game_width * scale) / 2,
+            game_y: (self.height - game_height * scale) / 2,
+            game_width,
+            game_height,
+            ..self
+        }
+    }
+
+    pub fn of(raylib_handle: &RaylibHandle) -> Self {
+        Self::new(raylib_handle.get_screen_width(), raylib_handle.get_screen_height(), raylib_handle.get_window_scale_dpi().x)
+    }
+
+    pub fn column(&self, x: f32) -> Option<u16> {
+        // Game screen column under a window x position, None when outside the game
+        let column: i32 = (x as i32 - self.game_x).div_euclid(self.scale);
+        (0..self.game_width).contains(&column).then_some(column as u16)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowMode {
+    pub borderless: bool,
+    // Covers the whole monitor without a title bar, the desktop's own resolution is kept
+    pub monitor: i32,
+    pub size: Option<(i32, i32)>,
+    // Chosen with --window-size, otherwise the window is fitted to the monitor
+}
+
+pub fn windowed_size(monitor_width: i32, monitor_height: i32, dpi_scale: f32) -> (i32, i32) {
+    // Largest 16:9 window that fits on the monitor, never bigger than WIDTH x HEIGHT
+    //  Monitor sizes are in pixels and windows are sized in points, there are dpi_scale pixels to a point
+
+    let (width, height): (f32, f32) = (monitor_width as f32 / dpi_scale.max(1.0), monitor_height as f32 / dpi_scale.max(1.0));
+    let scale: f32 = (width * WINDOWED_FILL / WIDTH as f32).min(height * WINDOWED_FILL / HEIGHT as f32).min(1.0);
+    ((WIDTH as f32 * scale) as i32, (HEIGHT as f32 * scale) as i32)
+}
+
+pub fn apply(raylib_handle: &mut RaylibHandle, from: WindowMode, to: WindowMode) -> WindowMode {
+    // Moves the window between monitors and in or out of borderless, returns the mode it ended up in
+    //  A monitor that isn't plugged in falls back to the last one
+
+    let to: WindowMode = WindowMode { monitor: to.monitor.clamp(0, window::get_monitor_count() - 1), ..to };
+    if from.borderless {
+        raylib_handle.toggle_borderless_windowed();
+    }
+    // Back to a normal window first so changing monitor moves it rather than stretching it
+    raylib_handle.set_window_monitor(to.monitor);
+
+    if to.borderless {
+        raylib_handle.toggle_borderless_windowed();
+    } else {
+        let dpi_scale: f32 = raylib_handle.get_window_scale_dpi().x;
+        let (monitor_width, monitor_height): (i32, i32) = (window::get_monitor_width(to.monitor), window::get_monitor_height(to.monitor));
+        let (width, height): (i32, i32) = to.size.unwrap_or_else(|| windowed_size(monitor_width, monitor_height, dpi_scale));
+        let position: Vector2 = window::get_monitor_position(to.monitor);
+        raylib_handle.set_window_size(width, height);
+        raylib_handle.set_window_position(
+            position.x as i32 + (monitor_width as f32 / dpi_scale.max(1.0)) as i32 / 2 - width / 2,
+            position.y as i32 + (monitor_height as f32 / dpi_scale.max(1.0)) as i32 / 2 - height / 2,
+        );
+        // Centred on the monitor
+    }
+    to
+}
+
+pub fn parse_size(text: &str) -> Result<(i32, i32), String> {
+    // A window size like 1280x720, in points, big enough to show the game at its own size
+
+    let size: Option<(i32, i32)> = text.split_once('x').and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    match size {
+        Some((width, height)) if width >= INVADERS_WIDTH && height >= INVADERS_HEIGHT => Ok((width, height)),
+        Some(_) => Err(format!("{} is too small, the window has to be at least {}x{}", text, INVADERS_WIDTH, INVADERS_HEIGHT)),
+        None => Err(format!("{} is not a window size like 1280x720", text)),
+    }
+}
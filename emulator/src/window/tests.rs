@@ -0,0 +1,44 @@
+#[cfg(test)]
+use super::*;
+#[cfg(test)]
+use crate::display::Rotation;
+
+#[test]
+fn test_layout() {
+    assert_eq!(Layout::new(WIDTH, HEIGHT, 1.0), Layout { width: 1920, height: 1080, scale: 4, game_x: 512, game_y: 28, game_width: 224, game_height: 256, text_size: 20 });
+    assert_eq!(Layout::new(1280, 720, 0.0), Layout { width: 1280, height: 720, scale: 2, game_x: 416, game_y: 104, game_width: 224, game_height: 256, text_size: 20 });
+    assert_eq!(Layout::new(800, 600, 2.0).text_size, 40);
+    // Text follows the monitor's scaling, not the window's size
+    assert_eq!(Layout::new(500, 1080, 1.0).scale, 2);
+    // A tall thin window is limited by its width
+    assert_eq!(Layout::new(100, 100, 1.0).scale, 1);
+
+    let layout: Layout = Layout::new(1280, 720, 1.0);
+    assert_eq!(layout.column(416.0), Some(0));
+    assert_eq!(layout.column(417.0 + 223.0 * 2.0), Some(223));
+    assert_eq!(layout.column(415.0), None);
+    assert_eq!(layout.column(416.0 + 224.0 * 2.0), None);
+
+    let wide: Layout = layout.fit(Geometry { width: 256, height: 32, rotation: Rotation::None, ..Geometry::space_invaders() });
+    assert_eq!((wide.scale, wide.game_x, wide.game_y), (5, 0, 280));
+    assert_eq!(wide.column(1279.0), Some(255));
+    assert_eq!(wide.fit(Geometry::space_invaders()), layout);
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("1280x720"), Ok((1280, 720)));
+    assert!(parse_size("200x200").unwrap_err().contains("too small"));
+    assert!(parse_size("1280").unwrap_err().contains("not a window size"));
+    assert!(parse_size("widex720").is_err());
+}
+
+#[test]
+fn test_windowed_size() {
+    assert_eq!(windowed_size(3840, 2160, 1.0), (WIDTH, HEIGHT));
+    // Never bigger than the canvas
+    assert_eq!(windowed_size(1920, 1080, 1.0), (1728, 972));
+    assert_eq!(windowed_size(3840, 2160, 2.0), (1728, 972));
+    // A 4k monitor at 200% has as much room as a 1080p one
+    assert_eq!(windowed_size(1366, 768, 0.0), (1228, 691));
+}
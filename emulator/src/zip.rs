@@ -0,0 +1,116 @@
+use crate::png::crc32;
+
+mod tests;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const VERSION: u16 = 10;
+// Stored files only need version 1.0 of the format to open
+const DOS_DATE: u16 = 0x0021;
+// 1980-01-01, the earliest a zip can say, timestamps go in the name of the zip instead
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn encode(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, &'static str> {
+    // Writes a zip with every file stored as it is, the same as the png encoder nothing is compressed
+    //  Small enough that it doesn't need zip64, anything over 4GB is refused
+
+    let mut zip: Vec<u8> = vec![];
+    let mut directory: Vec<u8> = vec![];
+    for (name, data) in files {
+        let offset: u32 = u32::try_from(zip.len()).map_err(|_| "Zip is too big")?;
+        let size: u32 = u32::try_from(data.len()).map_err(|_| "File is too big for a zip")?;
+        let name_length: u16 = u16::try_from(name.len()).map_err(|_| "File name is too long for a zip")?;
+        let crc: u32 = crc32(data);
+
+        push_u32(&mut zip, LOCAL_HEADER);
+        push_u16(&mut zip, VERSION);
+        for value in [0, 0, 0, DOS_DATE] {
+            push_u16(&mut zip, value);
+        }
+        // Flags, stored, midnight
+        for value in [crc, size, size] {
+            push_u32(&mut zip, value);
+        }
+        push_u16(&mut zip, name_length);
+        push_u16(&mut zip, 0);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(data);
+
+        push_u32(&mut directory, CENTRAL_HEADER);
+        push_u16(&mut directory, VERSION);
+        push_u16(&mut directory, VERSION);
+        for value in [0, 0, 0, DOS_DATE] {
+            push_u16(&mut directory, value);
+        }
+        for value in [crc, size, size] {
+            push_u32(&mut directory, value);
+        }
+        push_u16(&mut directory, name_length);
+        for _ in 0..4 {
+            push_u16(&mut directory, 0);
+        }
+        // No extra field, comment, disk number or internal attributes
+        push_u32(&mut directory, 0);
+        push_u32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let count: u16 = u16::try_from(files.len()).map_err(|_| "Too many files for a zip")?;
+    let directory_offset: u32 = u32::try_from(zip.len()).map_err(|_| "Zip is too big")?;
+    let directory_size: u32 = directory.len() as u32;
+    zip.extend(directory);
+    push_u32(&mut zip, END_OF_DIRECTORY);
+    for value in [0, 0, count, count] {
+        push_u16(&mut zip, value);
+    }
+    push_u32(&mut zip, directory_size);
+    push_u32(&mut zip, directory_offset);
+    push_u16(&mut zip, 0);
+
+    Ok(zip)
+}
+
+#[cfg(test)]
+pub fn decode(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>, &'static str> {
+    // Reads back a zip of stored files without a comment, like the ones encode writes
+
+    let u16_at = |at: usize| zip.get(at..at + 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize).ok_or("Zip ends early");
+    let u32_at = |at: usize| zip.get(at..at + 4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize).ok_or("Zip ends early");
+
+    let end: usize = zip.len().checked_sub(22).ok_or("Too short to be a zip")?;
+    if u32_at(end)? != END_OF_DIRECTORY as usize {
+        return Err("No end of directory record, zips with comments aren't read");
+    }
+
+    let mut files: Vec<(String, Vec<u8>)> = vec![];
+    let mut entry: usize = u32_at(end + 16)?;
+    for _ in 0..u16_at(end + 10)? {
+        if u32_at(entry)? != CENTRAL_HEADER as usize {
+            return Err("Directory entry is missing");
+        }
+        if u16_at(entry + 10)? != 0 {
+            return Err("Only stored files can be read");
+        }
+        let (crc, size, name_length): (usize, usize, usize) = (u32_at(entry + 16)?, u32_at(entry + 20)?, u16_at(entry + 28)?);
+        let local: usize = u32_at(entry + 42)?;
+
+        let name: &[u8] = zip.get(entry + 46..entry + 46 + name_length).ok_or("Zip ends early")?;
+        let data_start: usize = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let data: &[u8] = zip.get(data_start..data_start + size).ok_or("Zip ends early")?;
+        if crc32(data) as usize != crc {
+            return Err("File doesn't match its checksum");
+        }
+
+        files.push((String::from_utf8_lossy(name).into_owned(), data.to_vec()));
+        entry += 46 + name_length + u16_at(entry + 30)? + u16_at(entry + 32)?;
+    }
+    Ok(files)
+}
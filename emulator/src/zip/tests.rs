@@ -0,0 +1,26 @@
+#[cfg(test)]
+use super::*;
+
+#[test]
+fn test_zip() {
+    let files: Vec<(String, Vec<u8>)> = vec![
+        (String::from("README.txt"), b"Hello".to_vec()),
+        (String::from("empty.json"), vec![]),
+        (String::from("screen.png"), (0..=255).collect()),
+    ];
+    let zip: Vec<u8> = encode(&files).unwrap();
+
+    assert_eq!(zip[..4], [b'P', b'K', 3, 4]);
+    assert_eq!(zip[14..18], crc32(b"Hello").to_le_bytes());
+    assert_eq!(zip[30..40], *b"README.txt");
+    assert_eq!(zip[zip.len() - 22..zip.len() - 18], [b'P', b'K', 5, 6]);
+    assert_eq!(zip[zip.len() - 12..zip.len() - 10], [3, 0]);
+    assert_eq!(decode(&zip).unwrap(), files);
+
+    let mut damaged: Vec<u8> = zip.clone();
+    damaged[40] ^= 0xff;
+    assert!(decode(&damaged).is_err());
+    // The first byte of README.txt no longer matches its checksum
+    assert!(decode(&zip[..zip.len() - 1]).is_err());
+    assert_eq!(decode(&encode(&[]).unwrap()).unwrap(), vec![]);
+}
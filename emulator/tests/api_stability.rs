@@ -0,0 +1,287 @@
+// Uses everything in the prelude the way a crate embedding the emulator would, from outside the crate
+//  Signatures are pinned with typed function pointers and the traits are implemented here, so a change
+//  that would break an embedder stops this compiling or fails a test
+//  When a break is meant to happen it needs a breaking release, bump the version in Cargo.toml and
+//  WRITTEN_FOR along with fixing this file
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use disassembler::symbols::SymbolTable;
+use emulator::frontend;
+use emulator::hardware::sound::SoundEvent;
+use emulator::prelude::*;
+use emulator::testing::{self, Patch};
+
+const WRITTEN_FOR: &str = "0.2.";
+// Versions this file was written against, while the major version is 0 a new minor version is a breaking one
+
+const ECHO_PORT: u8 = 0xf0;
+
+const COUNTER: [u8; 12] = [
+    0xf3,             // 0x0000 DI
+    0x3c,             // 0x0001 INR A
+    0xd3, 0xf0,       // 0x0002 OUT 0xf0
+    0xdb, 0xf0,       // 0x0004 IN 0xf0
+    0x32, 0x00, 0x20, // 0x0006 STA 0x2000
+    0xc3, 0x01, 0x00, // 0x0009 JMP 0x0001
+];
+
+#[derive(Debug, Clone, Default)]
+struct Echo {
+    // Gives back the last byte written, shared so the test can look at it while the machine owns a copy
+    last: Arc<Mutex<u8>>,
+}
+impl IoDevice for Echo {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn read(&mut self, _number: u8) -> u8 {
+        *self.last.lock().unwrap()
+    }
+
+    fn write(&mut self, _number: u8, value: u8) {
+        *self.last.lock().unwrap() = value;
+    }
+
+    fn reset(&mut self) {
+        *self.last.lock().unwrap() = 0;
+    }
+
+    fn clone_device(&self) -> Box<dyn IoDevice> {
+        Box::new(self.clone())
+    }
+}
+
+fn counter_machine() -> (Machine, Echo) {
+    let echo: Echo = Echo::default();
+    let mut machine: Machine = Machine::with_profile(Profile::space_invaders()).unwrap();
+    machine.load_rom(&COUNTER).unwrap();
+    machine.hardware.attach(Box::new(echo.clone()), &[Claim::read(ECHO_PORT), Claim::write(ECHO_PORT)], 1).unwrap();
+    (machine, echo)
+}
+
+#[test]
+fn version() {
+    assert!(emulator::API_VERSION.starts_with(WRITTEN_FOR), "{} needs this file checked over, see the top", emulator::API_VERSION);
+}
+
+#[test]
+fn machine() {
+    let _: fn() -> Machine = Machine::init;
+    let _: fn(Profile) -> Result<Machine, String> = Machine::with_profile;
+    let _: fn(&mut Machine, &[u8]) -> Result<(), LoadError> = Machine::load_rom;
+    let _: fn(&mut Machine) -> Result<u64, EmulatorError> = Machine::step;
+    let _: fn(&mut Machine) -> Result<bool, EmulatorError> = Machine::step_frame;
+    let _: fn(&mut Machine) -> Result<(), EmulatorError> = Machine::run_frame;
+    let _: fn(&mut Machine) -> Result<HalfFrame, EmulatorError> = Machine::run_half_frame;
+    let _: fn(&mut Machine) -> Result<(), LoadError> = Machine::reset;
+    let _: fn(&mut Machine, &[u8]) -> Result<(), LoadError> = Machine::power_cycle;
+    let _: fn(&Machine) -> u64 = Machine::cycles;
+    let _: fn(&Machine) -> &[Event] = Machine::events;
+    let _: fn(&mut Cpu, &mut Hardware) -> Result<u64, EmulatorError> = step;
+
+    let (mut machine, echo) = counter_machine();
+    let HalfFrame { rst, serviced, cycles, end_of_frame } = machine.run_half_frame().unwrap();
+    assert_eq!((rst, serviced, end_of_frame), (1, false, false));
+    // Interrupts are disabled so the board's interrupt isn't taken
+    assert!(cycles >= CYCLES_PER_FRAME / 2);
+    machine.run_frame().unwrap();
+    assert!(machine.events().iter().any(|event| matches!(event, Event::PortWrite(ECHO_PORT, _))));
+
+    let stop: Stop = machine.run_until(CYCLES_PER_FRAME, |machine| machine.cpu.pc.address == 0x0009).unwrap();
+    assert_eq!(stop, Stop::Condition);
+    assert_eq!(machine.cpu.memory.read_at(0x2000), *echo.last.lock().unwrap());
+    // What was written to the device came back and was stored
+
+    machine.reset().unwrap();
+    assert_eq!(machine.cpu.pc.address, 0x0000);
+    assert_ne!(*echo.last.lock().unwrap(), 0);
+    machine.power_cycle(&COUNTER).unwrap();
+    assert_eq!(*echo.last.lock().unwrap(), 0);
+    // Attached devices are reset with the board and stay attached
+
+    assert!(Machine::init().load_rom(&vec![0; 0x10000]).is_err_and(|e: LoadError| e.overflow() > 0));
+}
+
+type Attach = fn(&mut Hardware, Box<dyn IoDevice>, &[Claim], u8) -> Result<(), String>;
+
+#[test]
+fn devices() {
+    let _: Attach = Hardware::attach;
+    let _: fn(&mut Hardware, &str) -> Option<Box<dyn IoDevice>> = Hardware::detach;
+
+    let (mut machine, _) = counter_machine();
+    assert!(machine.hardware.attach(Box::new(Echo::default()), &[Claim::read(ECHO_PORT)], 1).is_err());
+    // Only one device of a name
+
+    let detached: Box<dyn IoDevice> = machine.hardware.detach("echo").unwrap();
+    assert_eq!(detached.name(), "echo");
+    let error: EmulatorError = machine.run_frame().unwrap_err();
+    assert_eq!((error.address, error.op_code), (0x0002, 0xd3));
+    assert!(matches!(error.kind, ErrorKind::UnconnectedWritePort(ECHO_PORT)));
+    // Nothing answers the port once it's gone
+
+    let map: PortMap = PortMap::space_invaders();
+    assert!(map.read(1).is_some() && map.write(ECHO_PORT).is_none());
+}
+
+#[derive(Default)]
+struct Counting {
+    polled: u64,
+    presented: Vec<u8>,
+}
+impl Frontend for Counting {
+    fn poll_input(&mut self, machine: &mut Machine) {
+        self.polled += 1;
+        machine.hardware.set_inputs([0x01, 0x00]);
+    }
+
+    fn present(&mut self, machine: &Machine) {
+        self.presented.push(machine.cpu.memory.read_at(0x2000));
+    }
+
+    fn should_close(&mut self) -> bool {
+        self.presented.len() == 3
+    }
+}
+
+#[test]
+fn frontends() {
+    let (mut machine, _) = counter_machine();
+    let mut counting: Counting = Counting::default();
+    assert_eq!(frontend::run(&mut machine, &mut counting), Ok(3));
+    assert_eq!(counting.polled, 3);
+    assert_eq!(frontend::run(&mut machine, &mut Headless::new(2)), Ok(2));
+
+    let widths: Arc<Mutex<Vec<u16>>> = Arc::default();
+    let sink: Arc<Mutex<Vec<u16>>> = Arc::clone(&widths);
+    let id: usize = machine.on_vblank(move |framebuffer: &Framebuffer| sink.lock().unwrap().push(framebuffer.width()));
+    machine.run_frame().unwrap();
+    assert!(machine.remove_vblank(id));
+    machine.run_frame().unwrap();
+    assert_eq!(*widths.lock().unwrap(), vec![machine.profile().display.geometry().width]);
+
+    let _: fn(f64) -> FramePacer = FramePacer::new;
+    let _: fn(&mut FramePacer) -> Duration = FramePacer::wait;
+    assert_eq!(FramePacer::new(50.0).frame_duration(), Duration::from_millis(20));
+}
+
+#[derive(Default)]
+struct Speaker {
+    playing: Vec<Sound>,
+}
+impl AudioSink for Speaker {
+    fn play(&mut self, sound: Sound) {
+        self.playing.push(sound);
+    }
+
+    fn stop(&mut self, sound: Sound) {
+        self.playing.retain(|playing| *playing != sound);
+    }
+}
+
+struct Keys(Vec<KeyboardKey>);
+impl InputSource for Keys {
+    fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.0.contains(&key)
+    }
+}
+
+#[test]
+fn cabinet() {
+    let mut hardware: Hardware = Hardware::init();
+    emulator::hardware::input::read_input(&Keys(vec![KeyboardKey::KEY_ENTER]), &mut hardware, InputConfig::default());
+    assert_ne!(hardware.inputs()[0], Hardware::init().inputs()[0]);
+    // Enter is the coin
+    let _: fn(&emulator::palette::Palette, u16, u16) -> Color = emulator::palette::Palette::lit;
+
+    let switches: DipSwitches = DipSwitches { extra_lives: 3, bonus_life_at_1000: true, coin_info_off: false };
+    assert_eq!(switches.merge(0x00) & DipSwitches::BITS, switches.merge(0xff) & DipSwitches::BITS);
+
+    let mut speaker: Speaker = Speaker::default();
+    SoundEvent::Play(Sound::Shot).send(&mut speaker);
+    SoundEvent::Play(Sound::Ufo).send(&mut speaker);
+    SoundEvent::Stop(Sound::Shot).send(&mut speaker);
+    assert_eq!(speaker.playing, vec![Sound::Ufo]);
+    assert_eq!(Sound::Shot.name(), "shot");
+}
+
+#[test]
+fn cpu() {
+    let mut cpu: Cpu = Cpu::init();
+    cpu.memory.write_at(0x2000, 0x42);
+    assert_eq!(cpu.memory.read_at(0x2000), 0x42);
+    assert!(matches!(cpu.memory.permissions_at(0x0000), Permissions::ReadOnly | Permissions::ReadWrite | Permissions::DirtyTracked));
+
+    let mut flags: Flags = Flags::new();
+    flags.set_flag(Flag::CY);
+    flags.assign(FlagSet::Z, true);
+    assert!(flags.check(FlagSet::CY) && flags.check(FlagSet::Z));
+    assert_eq!(Flags::from_bits(flags.bits()).check_flag(Flag::CY), 1);
+
+    let mut after: Cpu = cpu.clone();
+    after.a.value = 0x13;
+    let deltas: Vec<StateDelta> = cpu.diff(&after);
+    assert_eq!(deltas, vec![StateDelta::Register("A", 0x00, 0x13)]);
+    assert_eq!(deltas[0].to_string(), "A: 0x00 -> 0x13");
+
+    let mut coverage: Coverage = Coverage::init();
+    coverage.mark(0x0010);
+    assert!(coverage.is_executed(0x0010) && coverage.count(0x0000..0x0100) == 1);
+}
+
+#[test]
+fn debugger() {
+    let (mut machine, _) = counter_machine();
+    let mut debugger: Debugger = Debugger::new();
+    debugger.add_breakpoint(Breakpoint::Address(0x0006));
+    assert_eq!(debugger.run_frame(&mut machine), Some(Break::Address(0x0006)));
+    debugger.remove_breakpoint(Breakpoint::Address(0x0006));
+
+    debugger.add_watch(Watch { register: WatchedRegister::A, condition: WatchCondition::Becomes(0x80), action: WatchAction::Break });
+    assert!(matches!(debugger.run_frame(&mut machine), Some(Break::Watch(_))));
+    assert_eq!(machine.cpu.a.value, 0x80);
+
+    let (stepped, hit): (bool, Option<Break>) = debugger.step(&mut machine);
+    assert!(!stepped && hit.is_none());
+    let symbols: SymbolTable = SymbolTable::new();
+    assert!(debugger.command("regs", &mut machine, &symbols).is_ok_and(|regs| regs.starts_with("A=80")));
+}
+
+#[test]
+fn test_roms() {
+    let patches: [Patch; 1] = [Patch { address: testing::LOAD_ADDRESS, bytes: &[0xc7] }];
+    assert_eq!(testing::run_program(&[0x76], &patches, Some(10)), Ok(String::new()));
+    // The HLT is patched to RST 0, which warm boots straight away
+    let _: fn(&[u8]) -> Result<String, String> = testing::run_cpudiag;
+    assert!(testing::run_cpudiag(testing::CPUDIAG).is_ok_and(|output| output.contains("CPU IS OPERATIONAL")));
+}
+
+#[test]
+fn around_the_machine() {
+    let (mut machine, _) = counter_machine();
+    let mut observer: MachineObserver = machine.observer();
+    let mut rewind: Rewind = Rewind::new(4, 1);
+    let mut scheduler: Scheduler = Scheduler::new();
+    scheduler.at(When::Frame(1), Box::new(|machine: &mut Machine| machine.hardware.set_inputs([0x04, 0x00])));
+
+    rewind.record(0, &machine);
+    scheduler.run_frame(&mut machine).unwrap();
+    scheduler.run_frame(&mut machine).unwrap();
+    assert_eq!(scheduler.pending(), 0);
+
+    assert!(observer.updated());
+    let snapshot: Snapshot = observer.latest();
+    assert_eq!((snapshot.frame, snapshot.cycles, snapshot.inputs), (2, machine.cycles(), machine.hardware.inputs()));
+    assert_eq!(snapshot.registers, machine.cpu.registers());
+    assert!(snapshot.writes.iter().all(|(port, _)| *port == ECHO_PORT));
+    assert_ne!(snapshot.ram_hash, 0);
+
+    let (instruction, earlier): (u64, &Machine) = rewind.nearest(10).unwrap();
+    assert_eq!((instruction, earlier.cycles()), (0, 0));
+
+    assert_eq!(Language::parse("spanish"), Ok(Language::Spanish));
+    assert!(!Language::English.text(Text::InsertCoin).is_empty());
+}
@@ -0,0 +1,30 @@
+use std::env;
+use std::path::PathBuf;
+
+use emulator::cpu::vectors;
+
+#[test]
+#[ignore]
+fn conformance() {
+    // Runs the per instruction json test vectors from the community 8080 test suite
+    //  cargo test --test conformance -- --ignored --nocapture
+    // The vectors aren't included, point EMULATOR_TEST_VECTORS at the folder of XX.json files
+    //  or put them in emulator/tests/vectors
+
+    let directory: PathBuf = match env::var("EMULATOR_TEST_VECTORS") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/vectors"),
+    };
+    if !directory.is_dir() {
+        println!("No test vectors found at {}", directory.display());
+        return;
+    }
+
+    let report: vectors::Report = vectors::run_directory(&directory).expect("reading test vectors");
+    report.print_matrix();
+    for failure in report.failures.iter().take(50) {
+        println!("{}", failure);
+    }
+
+    assert!(report.all_passed(), "{} test vectors failed", report.failures.len());
+}
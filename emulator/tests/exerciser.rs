@@ -0,0 +1,33 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+
+use emulator::cpu::exerciser;
+
+#[test]
+#[ignore]
+fn exerciser() {
+    // Runs an instruction exerciser rom like 8080EXM.COM with its test groups split across threads
+    //  cargo test --release --test exerciser -- --ignored --nocapture
+    // The rom isn't included, point EMULATOR_EXERCISER at it or put it at emulator/tests/8080EXM.COM
+
+    let path: PathBuf = match env::var("EMULATOR_EXERCISER") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/8080EXM.COM"),
+    };
+    let rom: Vec<u8> = match fs::read(&path) {
+        Ok(rom) => rom,
+        Err(_) => {
+            println!("No exerciser found at {}", path.display());
+            return;
+        },
+    };
+
+    let threads: usize = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let results: Vec<exerciser::TestResult> = exerciser::run_parallel(&rom, threads).expect("finding the exerciser's tests");
+    exerciser::print_results(&results);
+
+    let failed: usize = results.iter().filter(|result| !result.passed).count();
+    assert_eq!(failed, 0, "{} exerciser tests failed", failed);
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::PathBuf;
+
+use emulator::display::Geometry;
+use emulator::golden::{self, Image, Mask, Region};
+use emulator::headless;
+use emulator::palette::Palette;
+use emulator::prelude::*;
+
+#[test]
+fn stripes() {
+    // Runs the stripes rom in emulator/tests/golden and checks its screen against stripes.png
+    //  A failure leaves stripes.actual.png and stripes.diff.png in the test's target directory,
+    //  when the change is meant to happen the actual frame replaces the golden one
+
+    let directory: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let rom: Vec<u8> = fs::read(directory.join("stripes.bin")).expect("reading the stripes rom");
+
+    let profile: Profile = Profile::space_invaders();
+    let palette: Palette = Palette::new(&profile.overlay).expect("building the palette");
+    let mut machine: Machine = Machine::with_profile(profile).unwrap();
+    machine.load_rom(&rom).unwrap();
+    for _ in 0..10 {
+        machine.run_frame().expect("running a frame");
+    }
+
+    let geometry: Geometry = machine.profile().display.geometry();
+    let frame: Image = Image::new(geometry.width as u32, geometry.height as u32, headless::framebuffer(&machine.cpu, geometry, &palette)).unwrap();
+    let mask: Mask = Mask::new().ignoring(Region { x: 0, y: 0, width: 8, height: 8 });
+    // The frame count
+    let artifacts: PathBuf = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    if let Err(e) = golden::check("stripes", &directory.join("stripes.png"), &frame, &mask, &artifacts) {
+        panic!("{}", e);
+    }
+}
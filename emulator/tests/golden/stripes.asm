@@ -0,0 +1,50 @@
+; Golden frame rom, fills the screen with a pattern and counts frames in the top left corner
+;   Assembled to stripes.bin with emulator --assemble stripes.asm stripes.bin
+;   The count changes every frame so the golden frame test masks it out, the same as a game's score
+; Numbers are hex
+
+Count           EQU     0x2000
+Corner          EQU     0x241f          ; Top 8 rows of the first column on the rotated screen
+
+                DI
+                LXI     SP, 0x2400
+                JMP     Start
+
+                ORG     0x08            ; RST 1, halfway through the frame
+                EI
+                RET
+
+                ORG     0x10            ; RST 2, end of the frame
+                JMP     Frame
+
+Start:          LXI     H, 0x2400
+Fill:           MOV     M, L            ; Each byte is the low half of its address
+                INX     H
+                MOV     A, H
+                CPI     0x40
+                JNZ     Fill
+                EI
+Wait:           HLT
+                JMP     Wait
+
+Frame:          PUSH    PSW
+                PUSH    B
+                PUSH    D
+                PUSH    H
+                OUT     0x06            ; Feed the watchdog
+                LDA     Count
+                INR     A
+                STA     Count
+                LXI     H, Corner
+                LXI     D, 0x20         ; One column along
+                MVI     C, 8
+Column:         MOV     M, A
+                DAD     D
+                DCR     C
+                JNZ     Column
+                POP     H
+                POP     D
+                POP     B
+                POP     PSW
+                EI
+                RET
@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::PathBuf;
+
+use emulator::assembler;
+use emulator::harness::{self, Outcome};
+use emulator::prelude::*;
+
+fn run(name: &str) -> Machine {
+    // Assembles one of the micro test roms in emulator/tests/roms/micro and runs it through the assertion port harness
+    //  Each rom goes after ports.asm so they all share the port names, and only aims at one behaviour
+    //  that is easy to get wrong, the third party exercisers cover the rest of the instruction set
+
+    let directory: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/roms/micro");
+    let source: String = fs::read_to_string(directory.join("ports.asm")).expect("reading the port names")
+        + &fs::read_to_string(directory.join(format!("{}.asm", name))).expect("reading the test rom");
+    let rom: Vec<u8> = assembler::assemble_program(&source).unwrap_or_else(|e| panic!("{}.asm after ports.asm: {}", name, e));
+
+    let mut machine: Machine = Machine::init();
+    machine.load_rom(&rom).unwrap();
+    let outcome: Outcome = harness::run(&mut machine, harness::CYCLE_LIMIT).expect("attaching the test port");
+    assert!(outcome.passed(), "{}: {}", name, outcome);
+    machine
+}
+
+#[test]
+fn daa() {
+    run("daa");
+}
+
+#[test]
+fn xthl() {
+    run("xthl");
+}
+
+#[test]
+fn conditional_calls() {
+    let machine: Machine = run("conditional_calls");
+    assert_eq!(machine.cycles(), 79);
+    // Counted down the side of the rom
+}
+
+#[test]
+fn interrupts() {
+    run("interrupts");
+}
@@ -0,0 +1,34 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use emulator::harness::{self, Outcome};
+
+#[test]
+fn roms() {
+    // Runs every test rom in emulator/tests/roms through the assertion port harness
+    //  Roms are written in 8080 assembly with the macros in harness.inc and checked in assembled
+
+    let directory: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&directory).expect("reading test roms")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "bin"))
+        .collect();
+    paths.sort();
+
+    let mut failed: usize = 0;
+    for path in &paths {
+        let rom: Vec<u8> = fs::read(path).expect("reading test rom");
+        let outcome: Outcome = harness::run_rom(&rom).expect("attaching the test port");
+        println!("{}: {}", name(path), outcome);
+        if !outcome.passed() {
+            failed += 1;
+        }
+    }
+
+    assert!(!paths.is_empty(), "No test roms found in {}", directory.display());
+    assert_eq!(failed, 0, "{} of {} test roms failed", failed, paths.len());
+}
+
+fn name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
@@ -0,0 +1,19 @@
+; Conditional calls and returns, a call or return that isn't taken leaves the stack alone
+;   and takes 6 cycles less than one that is, the test checks the machine ran 79 cycles
+;   Cycles are on the right
+
+                DI                      ; 4
+                LXI     SP, 0x2400      ; 10
+                XRA     A               ; 4     Z set
+                CNZ     Fail            ; 11    Not taken
+                RNZ                     ; 5     Not taken, nothing has been pushed
+                CZ      Taken           ; 17
+                MVI     A, 0x00         ; 7
+                OUT     Finish          ; 10
+                HLT
+
+Taken:          RZ                      ; 11
+
+Fail:           MVI     A, 0x01
+                OUT     Finish
+                HLT
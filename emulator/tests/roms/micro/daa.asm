@@ -0,0 +1,82 @@
+; DAA after additions, each check is A then the flags masked to S Z AC P CY
+;   DAA adjusts for an addition whatever ran before it, the 8080 has no flag saying it was a subtraction
+
+                DI
+                LXI     SP, 0x2400
+
+                MVI     A, 0x15
+                ADI     0x27            ; 0x3c
+                DAA                     ; Low digit over 9, AC from adding 6
+                PUSH    PSW
+                MVI     A, 0x42
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+                PUSH    PSW
+                POP     B
+                MVI     A, 0x14         ; AC P
+                OUT     Expect
+                MOV     A, C
+                ANI     0xd5
+                OUT     Check
+
+                MVI     A, 0x99
+                ADI     0x01            ; 0x9a
+                DAA                     ; Both digits over 9, wraps to 0 with a carry
+                PUSH    PSW
+                MVI     A, 0x00
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+                PUSH    PSW
+                POP     B
+                MVI     A, 0x55         ; Z AC P CY
+                OUT     Expect
+                MOV     A, C
+                ANI     0xd5
+                OUT     Check
+
+                MVI     A, 0x09
+                ADI     0x09            ; 0x12 with AC set
+                DAA                     ; AC alone adjusts the low digit
+                PUSH    PSW
+                MVI     A, 0x18
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+                PUSH    PSW
+                POP     B
+                MVI     A, 0x04         ; P
+                OUT     Expect
+                MOV     A, C
+                ANI     0xd5
+                OUT     Check
+
+                MVI     A, 0x90
+                ADI     0x90            ; 0x20 with CY set
+                DAA                     ; CY alone adjusts the high digit and stays set
+                PUSH    PSW
+                MVI     A, 0x80
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+                PUSH    PSW
+                POP     B
+                MVI     A, 0x81         ; S CY
+                OUT     Expect
+                MOV     A, C
+                ANI     0xd5
+                OUT     Check
+
+                MVI     A, 0x10
+                SUI     0x01            ; 0x0f
+                DAA                     ; Adjusted as if it were a sum
+                PUSH    PSW
+                MVI     A, 0x15
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+
+                MVI     A, 0x00
+                OUT     Finish
+                HLT
@@ -0,0 +1,44 @@
+; Taking an interrupt turns interrupts off, and one that comes due during EI waits for the instruction after it
+;   Every interrupt counts itself in C then spins through the next few without EI before returning
+
+                DI
+                LXI     SP, 0x2400
+                JMP     Start
+
+                ORG     0x08            ; RST 1
+                JMP     Handler
+                ORG     0x10            ; RST 2
+                JMP     Handler
+
+Handler:        INR     C
+                LXI     D, 0x0800       ; About 49000 cycles, three interrupts' worth
+Spin:           DCX     D
+                MOV     A, D
+                ORA     E
+                JNZ     Spin
+                RET                     ; Interrupts stay off
+
+Start:          MVI     C, 0x00
+                EI
+                HLT                     ; Woken by the first interrupt
+                MVI     A, 0x01         ; Once, none were taken while the handler ran
+                OUT     Expect
+                MOV     A, C
+                OUT     Check
+
+                MVI     C, 0x00
+                LXI     D, 0x1400       ; 32 cycles a time round, ten interrupts' worth
+Window:         EI                      ; Sometimes an interrupt comes due here
+                DI                      ; but is dropped once this has run
+                DCX     D
+                MOV     A, D
+                ORA     E
+                JNZ     Window
+                MVI     A, 0x00         ; None taken
+                OUT     Expect
+                MOV     A, C
+                OUT     Check
+
+                MVI     A, 0x00
+                OUT     Finish
+                HLT
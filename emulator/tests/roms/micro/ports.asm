@@ -0,0 +1,9 @@
+; Test port names, put in front of every micro test rom when it is assembled
+;   The emulator's assembler has no macros so each check is written out in full
+;       PUSH PSW, MVI A expected, OUT Expect, POP PSW, OUT Check       checks A
+;       MVI A expected, OUT Expect, MOV A reg, OUT Check                checks a register
+; Numbers are hex
+
+Expect          EQU     0xf0            ; OUT sets the value the next check expects
+Check           EQU     0xf1            ; OUT compares A against the expected value
+Finish          EQU     0xf2            ; OUT ends the test, 0 is a pass and anything else a failure
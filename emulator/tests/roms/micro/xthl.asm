@@ -0,0 +1,55 @@
+; XTHL swaps HL with the two bytes on top of the stack, low byte at SP and high byte above it
+
+                DI
+                LXI     SP, 0x2400
+
+                LXI     H, 0x1234
+                LXI     B, 0x5678
+                PUSH    B
+                XTHL
+                MVI     A, 0x56
+                OUT     Expect
+                MOV     A, H
+                OUT     Check
+                MVI     A, 0x78
+                OUT     Expect
+                MOV     A, L
+                OUT     Check
+
+                LDA     0x23fe          ; Low byte of the old HL at SP
+                PUSH    PSW
+                MVI     A, 0x34
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+                LDA     0x23ff
+                PUSH    PSW
+                MVI     A, 0x12
+                OUT     Expect
+                POP     PSW
+                OUT     Check
+
+                POP     D
+                MVI     A, 0x12
+                OUT     Expect
+                MOV     A, D
+                OUT     Check
+                MVI     A, 0x34
+                OUT     Expect
+                MOV     A, E
+                OUT     Check
+
+                LXI     H, 0x0000
+                DAD     SP              ; SP is back where it started, XTHL doesn't move it
+                MVI     A, 0x24
+                OUT     Expect
+                MOV     A, H
+                OUT     Check
+                MVI     A, 0x00
+                OUT     Expect
+                MOV     A, L
+                OUT     Check
+
+                MVI     A, 0x00
+                OUT     Finish
+                HLT
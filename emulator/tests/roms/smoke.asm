@@ -0,0 +1,26 @@
+; Checks the harness itself, the macros have to work for any other test rom to mean anything
+;   Assembled to smoke.bin
+
+                INCLUDE harness.inc
+
+                ORG     0
+                START
+
+                MVI     A,5
+                ADI     3
+                ASSERT  8
+                ASSERT_FLAGS 00H
+                XRA     A
+                ASSERT_FLAGS 44H        ; Zero and even parity
+                MVI     A,8
+
+                MVI     B,0FFH
+                INR     B
+                ASSERT_REG B,0
+
+                LXI     H,2000H
+                MVI     M,42H
+                ASSERT_REG M,42H
+                ASSERT  8               ; A survived the other checks
+
+                PASS